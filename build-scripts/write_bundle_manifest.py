@@ -0,0 +1,109 @@
+#!/usr/bin/env python3
+"""Write the manifest the app checks its bundled resources against at startup.
+
+Lists every file of the bundled resources (`python/`, and `git/` and `ccache/`
+where they exist) with its size, plus a SHA-256 for the native binaries outside
+`site-packages`: the interpreter, its libraries, git and ccache. Sizes catch
+the common damage (a truncated download, files an antivirus quarantined)
+cheaply across the whole tree; hashing is kept to the handful of files that
+matter most, so the startup check stays fast.
+
+The manifest lands at `python/.bundle-manifest.json`, inside the one resource
+every platform ships (the AppImage injects only `python/`). Paths in it are
+relative to the resource directory and always use `/`.
+
+Run it last, after prepare_bundle.sh and, on macOS, after
+sign_python_bundle.sh: signing rewrites the binaries, and a manifest written
+before it would condemn every signed install.
+
+Usage:
+
+    python3 build-scripts/write_bundle_manifest.py [src-tauri dir]
+"""
+
+from __future__ import annotations
+
+import hashlib
+import json
+import os
+import sys
+from pathlib import Path
+
+RESOURCES = ("python", "git", "ccache")
+MANIFEST_NAME = ".bundle-manifest.json"
+
+# Leading bytes of ELF, PE and (thin and fat) Mach-O files.
+BINARY_MAGICS = (
+    b"\x7fELF",
+    b"MZ",
+    b"\xfe\xed\xfa\xce",
+    b"\xfe\xed\xfa\xcf",
+    b"\xce\xfa\xed\xfe",
+    b"\xcf\xfa\xed\xfe",
+    b"\xca\xfe\xba\xbe",
+)
+
+# Never hashed: pip rewrites what's under these when ESPHome updates in the
+# user's copy, and the extension modules in site-packages are too many to hash
+# on every launch.
+UNHASHED_DIRS = frozenset({"site-packages", "Scripts"})
+
+
+def is_binary(path: Path) -> bool:
+    with path.open("rb") as f:
+        head = f.read(4)
+    return head.startswith(BINARY_MAGICS)
+
+
+def sha256(path: Path) -> str:
+    digest = hashlib.sha256()
+    with path.open("rb") as f:
+        for chunk in iter(lambda: f.read(1 << 20), b""):
+            digest.update(chunk)
+    return digest.hexdigest()
+
+
+def build_manifest(resource_dir: Path) -> dict[str, object]:
+    """The manifest for the resources under `resource_dir`."""
+    files: dict[str, dict[str, object]] = {}
+    for resource in RESOURCES:
+        top = resource_dir / resource
+        if not top.is_dir():
+            continue
+        for dirpath, dirnames, filenames in os.walk(top):
+            dirnames.sort()
+            for name in sorted(filenames):
+                path = Path(dirpath) / name
+                # Symlinks are recreated, not copied, by some bundlers; the
+                # files they point at are listed in their own right.
+                if path.is_symlink():
+                    continue
+                relative = path.relative_to(resource_dir)
+                if relative.as_posix() == f"python/{MANIFEST_NAME}":
+                    continue
+                entry: dict[str, object] = {"size": path.stat().st_size}
+                if not UNHASHED_DIRS.intersection(relative.parts) and is_binary(path):
+                    entry["sha256"] = sha256(path)
+                files[relative.as_posix()] = entry
+    return {"files": files}
+
+
+def main(argv: list[str] | None = None) -> int:
+    args = sys.argv[1:] if argv is None else argv
+    default = Path(__file__).resolve().parent.parent / "src-tauri"
+    resource_dir = Path(args[0]) if args else default
+    if not (resource_dir / "python").is_dir():
+        print(f"error: no python/ bundle under {resource_dir}", file=sys.stderr)
+        return 1
+    manifest = build_manifest(resource_dir)
+    out = resource_dir / "python" / MANIFEST_NAME
+    out.write_text(json.dumps(manifest, indent=0, sort_keys=True), encoding="utf-8")
+    files = manifest["files"]
+    assert isinstance(files, dict)
+    hashed = sum(1 for entry in files.values() if "sha256" in entry)
+    print(f"Wrote {out}: {len(files)} files, {hashed} hashed")
+    return 0
+
+
+if __name__ == "__main__":
+    sys.exit(main())
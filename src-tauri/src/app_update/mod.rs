@@ -15,11 +15,13 @@ use std::time::Duration;
 
 use tauri::{AppHandle, Manager};
 use tauri_plugin_dialog::MessageDialogKind;
-use tauri_plugin_updater::UpdaterExt;
 use tracing::{debug, error, info, warn};
 
 use crate::i18n::{t, t_with};
 
+/// The desktop app's key in the update reminders.
+const REMINDER_KEY: &str = crate::update::DESKTOP_KEY;
+
 /// Whether the orchestrator should proceed to check the Python packages
 /// (`esphome` / `esphome-device-builder`) after the desktop self-update
 /// check completes.
@@ -38,7 +40,7 @@ pub enum NextStep {
 /// is true, so chained callers ("Check for Updates") can stay quiet and fall
 /// through to the ESPHome check instead. Errors are always surfaced.
 pub async fn check_for_user(app_handle: &AppHandle, show_no_update_dialog: bool) -> NextStep {
-    let updater = match app_handle.updater() {
+    let updater = match crate::network::updater(app_handle) {
         Ok(u) => u,
         Err(e) => {
             warn!("Updater not available: {}", e);
@@ -66,12 +68,12 @@ pub async fn check_for_user(app_handle: &AppHandle, show_no_update_dialog: bool)
             let notes = update.body.clone().unwrap_or_default();
 
             let msg = format_update_prompt(&current_version, &new_version, &notes);
-            let confirmed = crate::dialog::confirm(
+            let confirmed = crate::update::ask_to_update(
                 app_handle,
                 &t("app_update.available_title"),
                 msg,
-                &t("common.update_now"),
-                &t("common.later"),
+                REMINDER_KEY,
+                &new_version,
             )
             .await;
 
@@ -79,6 +81,12 @@ pub async fn check_for_user(app_handle: &AppHandle, show_no_update_dialog: bool)
                 // User saw the dialog and declined — fall through to ESPHome check.
                 return NextStep::Continue;
             }
+            if let Some(state) = app_handle.try_state::<std::sync::Arc<crate::AppState>>() {
+                let reason = t("auth.update");
+                if !crate::control::ops::authorize(&state, reason, &|_, _| {}).await {
+                    return NextStep::Skip;
+                }
+            }
 
             apply_update(app_handle, update).await;
             // The install completed (or failed and surfaced an error). Either
@@ -117,9 +125,11 @@ pub async fn check_for_user(app_handle: &AppHandle, show_no_update_dialog: bool)
 /// Background check. Only surfaces a notification when a new version is
 /// available; stays silent for "no update" and for errors. Returns
 /// [`NextStep::Skip`] when an update is available so the background loop
-/// can skip the Python-package checks until the user installs.
+/// can skip the Python-package checks until the user installs, unless the
+/// user skipped that version. Notifies once per version, like the packages'
+/// checks.
 pub async fn check_and_notify(app_handle: &AppHandle, tray_available: bool) -> NextStep {
-    let updater = match app_handle.updater() {
+    let updater = match crate::network::updater(app_handle) {
         Ok(u) => u,
         Err(e) => {
             debug!("Updater not available for background check: {}", e);
@@ -133,6 +143,14 @@ pub async fn check_and_notify(app_handle: &AppHandle, tray_available: bool) -> N
                 "Desktop update available in background: {} (current: {})",
                 update.version, update.current_version
             );
+            // A skipped version isn't coming, so the packages are worth
+            // checking on this one.
+            if crate::update::is_skipped(app_handle, REMINDER_KEY, &update.version) {
+                return NextStep::Continue;
+            }
+            if !crate::update::should_notify(app_handle, REMINDER_KEY, &update.version) {
+                return NextStep::Skip;
+            }
             if let Err(e) = crate::update::notify_update_available(
                 app_handle,
                 &t_with(
@@ -168,7 +186,13 @@ pub async fn check_and_notify(app_handle: &AppHandle, tray_available: bool) -> N
 async fn apply_update(app_handle: &AppHandle, update: tauri_plugin_updater::Update) {
     let new_version = update.version.clone();
 
-    match apply_update_noninteractive(app_handle, update, &|_, _| {}).await {
+    let result = apply_update_noninteractive(app_handle, update, &|_, _| {}).await;
+    let detail = match &result {
+        Ok(()) => format!("desktop app updated to {new_version}"),
+        Err(e) => format!("desktop app update to {new_version} failed: {e}"),
+    };
+    crate::audit::record(app_handle, crate::audit::Source::Tray, "update", detail);
+    match result {
         Ok(()) => {
             // Always relaunch after a successful install rather than offering to
             // defer: the install replaced the .app bundle, and the running
@@ -299,7 +323,11 @@ async fn install_update_bytes(
 async fn restore_backend(app_handle: &AppHandle) {
     if let Some(state) = app_handle.try_state::<std::sync::Arc<crate::AppState>>() {
         info!("Restarting ESPHome backend after desktop update");
-        if let Err(e) = state.daemon.start().await {
+        if let Err(e) = state
+            .daemon
+            .start_for(crate::store::RestartReason::Update)
+            .await
+        {
             warn!("Failed to restart backend after update: {}", e);
         }
     }
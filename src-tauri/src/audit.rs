@@ -0,0 +1,216 @@
+//! Audit log of administrative actions: updates, switches, restarts and
+//! settings changes, from the tray or the CLI (`audit.log`).
+//!
+//! The file is only ever appended to. Each line is a JSON entry carrying a
+//! hash of the line before it, so editing or deleting an entry breaks the
+//! chain at the next one and [`read`] reports where. That makes it
+//! tamper-evident, not tamper-proof: whoever can write the file can also
+//! rewrite the whole chain, and cutting entries off the end leaves no trace.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tauri::AppHandle;
+use tracing::warn;
+
+/// File name of the audit log inside the data directory.
+pub(crate) const AUDIT_FILE_NAME: &str = "audit.log";
+
+/// `prev` of the first entry.
+const GENESIS: &str = "0000000000000000";
+
+/// Serializes appends, which read the last line to chain from it.
+static APPEND_LOCK: Mutex<()> = Mutex::new(());
+
+/// Where an action came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Source {
+    Tray,
+    Cli,
+}
+
+impl Source {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Source::Tray => "tray",
+            Source::Cli => "cli",
+        }
+    }
+}
+
+/// One audited action.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Entry {
+    /// UTC, RFC 3339.
+    pub time: String,
+    pub source: Source,
+    /// What was done, e.g. `update` or `set_channel`.
+    pub action: String,
+    /// The outcome, in the words the tray log or CLI reply used.
+    pub detail: String,
+    /// Hash of the previous line ([`GENESIS`] for the first).
+    pub prev: String,
+}
+
+/// The parsed log.
+#[derive(Debug, Default)]
+pub(crate) struct AuditLog {
+    pub entries: Vec<Entry>,
+    /// 1-based line number of the first line that doesn't chain from the one
+    /// before it (or doesn't parse), if any.
+    pub broken_at: Option<usize>,
+}
+
+/// Path of the audit log in `data_dir`.
+pub(crate) fn path(data_dir: &Path) -> PathBuf {
+    data_dir.join(AUDIT_FILE_NAME)
+}
+
+/// Append an entry. Best-effort: a failure is logged and the action it
+/// describes goes ahead regardless.
+pub(crate) fn record(app: &AppHandle, source: Source, action: &str, detail: impl Into<String>) {
+    match crate::platform::get_data_dir(app) {
+        Ok(dir) => record_in(&dir, source, action, detail),
+        Err(e) => warn!("Failed to write audit log: {e:#}"),
+    }
+}
+
+/// [`record`] into the log in `data_dir`, for the offline CLI, which has no
+/// `AppHandle`.
+pub(crate) fn record_in(data_dir: &Path, source: Source, action: &str, detail: impl Into<String>) {
+    let result = append(
+        &path(data_dir),
+        source,
+        action,
+        detail.into(),
+        SystemTime::now(),
+    );
+    if let Err(e) = result {
+        warn!("Failed to write audit log: {e:#}");
+    }
+}
+
+fn append(path: &Path, source: Source, action: &str, detail: String, at: SystemTime) -> Result<()> {
+    let _lock = APPEND_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let prev = match std::fs::read_to_string(path) {
+        Ok(content) => content
+            .lines()
+            .last()
+            .map_or(GENESIS.to_string(), line_hash),
+        Err(e) if e.kind() == ErrorKind::NotFound => GENESIS.to_string(),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {path:?}")),
+    };
+    let entry = Entry {
+        time: rfc3339(at),
+        source,
+        action: action.to_string(),
+        detail,
+        prev,
+    };
+    let mut line = serde_json::to_string(&entry).context("Failed to serialize audit entry")?;
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut f| f.write_all(line.as_bytes()))
+        .with_context(|| format!("Failed to append to {path:?}"))
+}
+
+/// Read and verify the log; a missing file is an empty log.
+pub(crate) fn read(path: &Path) -> Result<AuditLog> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(AuditLog::default()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {path:?}")),
+    };
+    let mut log = AuditLog::default();
+    let mut expected = GENESIS.to_string();
+    for (i, line) in content.lines().enumerate() {
+        match serde_json::from_str::<Entry>(line) {
+            Ok(entry) => {
+                if entry.prev != expected && log.broken_at.is_none() {
+                    log.broken_at = Some(i + 1);
+                }
+                log.entries.push(entry);
+            }
+            Err(_) => {
+                log.broken_at.get_or_insert(i + 1);
+            }
+        }
+        expected = line_hash(line);
+    }
+    Ok(log)
+}
+
+/// FNV-1a, hex. The chain only has to notice a changed line, and a keyless
+/// hash of any strength could be recomputed by whoever changed it.
+fn line_hash(line: &str) -> String {
+    let hash = line.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |h, b| {
+        (h ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{hash:016x}")
+}
+
+/// `at` as `YYYY-MM-DDTHH:MM:SSZ`.
+pub(crate) fn rfc3339(at: SystemTime) -> String {
+    let secs = at.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+    // Civil date from days since the epoch (Howard Hinnant's algorithm).
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::unique_temp_dir;
+    use std::fs;
+    use std::time::Duration;
+
+    #[test]
+    fn formats_utc_timestamps() {
+        assert_eq!(rfc3339(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        let leap_day = UNIX_EPOCH + Duration::from_secs(1_709_210_096);
+        assert_eq!(rfc3339(leap_day), "2024-02-29T12:34:56Z");
+    }
+
+    #[test]
+    fn chain_verifies_and_an_edited_entry_breaks_it() {
+        let dir = unique_temp_dir("audit");
+        let path = path(&dir);
+        for action in ["update", "restart", "set_channel"] {
+            append(&path, Source::Tray, action, "ok".into(), UNIX_EPOCH).unwrap();
+        }
+        let log = read(&path).unwrap();
+        assert_eq!(log.entries.len(), 3);
+        assert_eq!(log.broken_at, None);
+
+        let edited = fs::read_to_string(&path)
+            .unwrap()
+            .replace("\"restart\"", "\"quit\"");
+        fs::write(&path, edited).unwrap();
+        assert_eq!(read(&path).unwrap().broken_at, Some(3));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,507 @@
+//! Removing what the app leaves on disk: the Python environment, settings,
+//! logs and build caches, and optionally PlatformIO's toolchains and the
+//! device configs.
+//!
+//! Uninstalling removes the app, not the gigabytes it downloads and builds at
+//! run time. The tray's "Uninstall Data..." item and the `uninstall-data`
+//! subcommand (which the Windows uninstaller runs) remove the same set,
+//! listed by [`targets`]. A reset removes less of it (see [`Purpose`]) and
+//! lets the next launch set the app up again from the bundle. Nothing is
+//! removed while the dashboard runs from it: the tray stops it first and the
+//! subcommands refuse to run next to the app.
+
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// What a removable directory holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Kind {
+    /// The managed Python tree, where it has a directory of its own (Windows).
+    PythonEnv,
+    /// The data directory: settings, logs, notes, the audit log, and on macOS
+    /// and Linux the Python tree. On Linux also the directories the settings,
+    /// the logs and the caches have under the XDG base directories.
+    AppData,
+    /// ESPHome's build directories: under the config directory, and on
+    /// Windows the device builder's short `C:\esphb`.
+    BuildCache,
+    /// PlatformIO's toolchains and packages.
+    Toolchains,
+    /// The device configs themselves.
+    Configs,
+}
+
+impl Kind {
+    /// English description, for the CLI and the log (the tray translates).
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Kind::PythonEnv => "Python environment",
+            Kind::AppData => "settings and app data",
+            Kind::BuildCache => "build cache",
+            Kind::Toolchains => "PlatformIO toolchains (shared with other PlatformIO use)",
+            Kind::Configs => "device configs",
+        }
+    }
+}
+
+/// Why the data is being removed, which decides how much of it goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Purpose {
+    /// Everything the app made.
+    Uninstall,
+    /// What a broken install can be made of: the Python environment,
+    /// settings and build caches. What the user wrote (device notes and
+    /// groups, the devices added by hand, the source allow-list, the undo
+    /// history with the configs it holds), the audit log and the logs stay,
+    /// and so do the toolchains, which are slow to download again.
+    Reset,
+}
+
+/// Entries of the data directory a reset keeps.
+const KEPT_ON_RESET: &[&str] = &[
+    crate::devices::NOTES_FILE_NAME,
+    crate::devices::registry::REGISTRY_FILE_NAME,
+    crate::devices::sources::SOURCES_FILE_NAME,
+    crate::devices::undo::UNDO_DIR,
+    crate::audit::AUDIT_FILE_NAME,
+    "logs",
+];
+
+/// A directory [`remove`] would delete, and how much it holds.
+#[derive(Debug, Clone)]
+pub(crate) struct Target {
+    pub kind: Kind,
+    pub path: PathBuf,
+    pub bytes: u64,
+    /// Entries directly inside `path` that stay.
+    pub keep: &'static [&'static str],
+}
+
+/// Where the app keeps things.
+#[derive(Debug, Clone)]
+pub(crate) struct Locations {
+    pub data_dir: PathBuf,
+    pub local_data_dir: PathBuf,
+    /// Where `settings.json` goes.
+    pub settings_dir: PathBuf,
+    /// The app's directories besides the data directory holding settings,
+    /// logs or caches (see [`crate::platform::config_dir_no_handle`]).
+    pub app_dirs: Vec<PathBuf>,
+    pub config_dir: PathBuf,
+    /// Where builds go instead of the config directory, if anywhere.
+    pub build_dir: Option<PathBuf>,
+    pub platformio_dir: Option<PathBuf>,
+    pub home_dir: Option<PathBuf>,
+}
+
+impl Locations {
+    /// Resolve the locations without an `AppHandle`, the config directory from
+    /// the settings file like the other offline subcommands.
+    pub(crate) fn resolve() -> Option<Self> {
+        let data_dir = crate::platform::data_dir_no_handle()?;
+        let local_data_dir = crate::platform::local_data_dir_no_handle()?;
+        let settings_dir = crate::platform::config_dir_no_handle()?;
+        let logs_dir = crate::platform::logs_dir_no_handle()?;
+        let app_dirs = [
+            Some(settings_dir.clone()),
+            logs_dir.parent().map(Path::to_path_buf),
+            crate::platform::cache_dir_no_handle(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        let config_dir = crate::platform::settings_path_no_handle()
+            .and_then(|path| crate::settings::peek_settings_file(&path))
+            .and_then(|s| s.workspace.config_dir)
+            .unwrap_or_else(crate::settings::default_config_dir);
+        let home_dir = dirs::home_dir();
+        let platformio_dir = std::env::var_os("PLATFORMIO_CORE_DIR")
+            .map(PathBuf::from)
+            .or_else(|| home_dir.as_ref().map(|h| h.join(".platformio")));
+        // The device builder moves Windows builds out of the profile, away
+        // from the path-length limit and spaces in user names.
+        let build_dir = cfg!(target_os = "windows").then(|| PathBuf::from(r"C:\esphb"));
+        Some(Self {
+            data_dir,
+            local_data_dir,
+            settings_dir,
+            app_dirs,
+            config_dir,
+            build_dir,
+            platformio_dir,
+            home_dir,
+        })
+    }
+}
+
+/// The directories to remove for `purpose` that exist, with their sizes. The
+/// configs only with `include_config`, and PlatformIO's toolchains, which
+/// other PlatformIO use shares, only with `include_toolchains`. A directory inside another one listed
+/// is left to that one, and the home directory or anything above it is never
+/// listed, whatever the config directory is set to.
+pub(crate) fn targets(
+    locations: &Locations,
+    purpose: Purpose,
+    include_config: bool,
+    include_toolchains: bool,
+) -> Vec<Target> {
+    let mut candidates = Vec::new();
+    if locations.local_data_dir != locations.data_dir {
+        candidates.push((Kind::PythonEnv, locations.local_data_dir.clone()));
+    }
+    candidates.push((Kind::AppData, locations.data_dir.clone()));
+    candidates.extend(
+        locations
+            .app_dirs
+            .iter()
+            .map(|dir| (Kind::AppData, dir.clone())),
+    );
+    candidates.push((Kind::BuildCache, locations.config_dir.join(".esphome")));
+    candidates.extend(
+        locations
+            .build_dir
+            .clone()
+            .map(|dir| (Kind::BuildCache, dir)),
+    );
+    if include_toolchains {
+        candidates.extend(
+            locations
+                .platformio_dir
+                .clone()
+                .map(|dir| (Kind::Toolchains, dir)),
+        );
+    }
+    if include_config {
+        candidates.push((Kind::Configs, locations.config_dir.clone()));
+    }
+
+    let protected = |path: &Path| {
+        path.parent().is_none()
+            || locations
+                .home_dir
+                .as_deref()
+                .is_some_and(|home| home.starts_with(path))
+    };
+    let mut kept: Vec<(Kind, PathBuf)> = Vec::new();
+    for (kind, path) in candidates {
+        if protected(&path) || std::fs::symlink_metadata(&path).is_err() {
+            continue;
+        }
+        if kept.iter().any(|(_, k)| path.starts_with(k)) {
+            continue;
+        }
+        kept.retain(|(_, k)| !k.starts_with(&path));
+        kept.push((kind, path));
+    }
+    kept.into_iter()
+        .map(|(kind, path)| {
+            let keep = match (purpose, kind) {
+                (Purpose::Reset, Kind::AppData | Kind::PythonEnv) => KEPT_ON_RESET,
+                _ => &[],
+            };
+            let bytes = if keep.is_empty() {
+                dir_size(&path)
+            } else {
+                children(&path, keep).iter().map(|p| dir_size(p)).sum()
+            };
+            Target {
+                kind,
+                path,
+                bytes,
+                keep,
+            }
+        })
+        .collect()
+}
+
+/// The entries of the directory `path`, but those named in `keep`.
+fn children(path: &Path, keep: &[&str]) -> Vec<PathBuf> {
+    std::fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter(|e| !keep.iter().any(|k| e.file_name() == *k))
+                .map(|e| e.path())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Total size of the files under `path`. Symlinks are counted, not followed.
+pub(crate) fn dir_size(path: &Path) -> u64 {
+    let Ok(meta) = std::fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !meta.is_dir() {
+        return meta.len();
+    }
+    std::fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| dir_size(&e.path()))
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+/// Remove `targets`, carrying on past failures. Returns what could not be
+/// removed (completely), with why.
+///
+/// Everything goes to the OS trash, so a wrong answer to the prompt can be
+/// taken back. Where there is no trash the app's own data is deleted, but the
+/// configs, which nothing can make again, stay and count as a failure.
+pub(crate) fn remove(targets: &[Target]) -> Vec<(PathBuf, std::io::Error)> {
+    let mut failures = Vec::new();
+    for target in targets {
+        let paths = if target.keep.is_empty() {
+            vec![target.path.clone()]
+        } else {
+            children(&target.path, target.keep)
+        };
+        for path in paths {
+            match dispose(&path, target.kind) {
+                Ok(()) => info!("Removed {:?} ({})", path, target.kind.name()),
+                Err(e) if e.kind() == ErrorKind::NotFound => {}
+                Err(e) => {
+                    warn!("Failed to remove {:?}: {}", path, e);
+                    failures.push((path, e));
+                }
+            }
+        }
+    }
+    failures
+}
+
+/// Move `path` to the OS trash, or delete it where that fails, but for the
+/// configs.
+fn dispose(path: &Path, kind: Kind) -> std::io::Result<()> {
+    let meta = std::fs::symlink_metadata(path)?;
+    match to_os_trash(path) {
+        Ok(()) => return Ok(()),
+        Err(e) if kind == Kind::Configs => return Err(std::io::Error::other(format!("{e:#}"))),
+        Err(e) => warn!(
+            "Failed to move {:?} to the trash, deleting it: {:#}",
+            path, e
+        ),
+    }
+    if meta.is_dir() {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    }
+}
+
+fn to_os_trash(path: &Path) -> anyhow::Result<()> {
+    // Tests' files have no business in the user's trash.
+    if cfg!(test) {
+        anyhow::bail!("no trash in tests");
+    }
+    crate::platform::trash::move_to_trash(path)
+}
+
+/// Remove what a reset removes. When the configs stay, the fresh settings
+/// keep pointing at them, so the relaunched app finds them again.
+pub(crate) fn reset(locations: &Locations, include_config: bool) -> Vec<(PathBuf, std::io::Error)> {
+    let mut failures = remove(&targets(locations, Purpose::Reset, include_config, false));
+    if !include_config && locations.config_dir != crate::settings::default_config_dir() {
+        let path = locations
+            .settings_dir
+            .join(crate::platform::SETTINGS_FILE_NAME);
+        if let Err(e) = keep_config_dir(&path, &locations.config_dir) {
+            warn!("Failed to keep the config directory setting: {:#}", e);
+            failures.push((path, std::io::Error::other(format!("{e:#}"))));
+        }
+    }
+    failures
+}
+
+/// Write default settings, but for `config_dir`, to `path`.
+fn keep_config_dir(path: &Path, config_dir: &Path) -> anyhow::Result<()> {
+    let mut settings = crate::settings::Settings::default();
+    settings.workspace.config_dir = Some(config_dir.to_path_buf());
+    settings.save_to(path)
+}
+
+/// `bytes` for people: `0 B`, `12 KB`, `340 MB`, `1.2 GB` (decimal units, like
+/// the file managers on macOS and Linux).
+pub(crate) fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1000 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64 / 1000.0;
+    let mut unit = 0;
+    while value >= 1000.0 && unit + 1 < UNITS.len() {
+        value /= 1000.0;
+        unit += 1;
+    }
+    if value < 10.0 && unit >= 2 {
+        format!("{value:.1} {}", UNITS[unit])
+    } else {
+        format!("{value:.0} {}", UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::unique_temp_dir;
+    use std::fs;
+
+    fn locations(root: &Path) -> Locations {
+        Locations {
+            data_dir: root.join("data"),
+            local_data_dir: root.join("data"),
+            settings_dir: root.join("data"),
+            app_dirs: Vec::new(),
+            config_dir: root.join("home/esphome"),
+            build_dir: None,
+            platformio_dir: Some(root.join("home/.platformio")),
+            home_dir: Some(root.join("home")),
+        }
+    }
+
+    #[test]
+    fn lists_existing_directories_and_keeps_configs_unless_asked() {
+        let root = unique_temp_dir("cleanup");
+        fs::create_dir_all(root.join("data/python")).unwrap();
+        fs::write(root.join("data/python/lib.so"), [0u8; 1500]).unwrap();
+        fs::write(root.join("data/settings.json"), "{}").unwrap();
+        fs::create_dir_all(root.join("home/esphome/.esphome/build")).unwrap();
+        fs::write(root.join("home/esphome/porch.yaml"), "esphome:\n").unwrap();
+        let locations = locations(&root);
+
+        // No PlatformIO directory: not listed.
+        let found = targets(&locations, Purpose::Uninstall, false, false);
+        let kinds: Vec<_> = found.iter().map(|t| t.kind).collect();
+        assert_eq!(kinds, [Kind::AppData, Kind::BuildCache]);
+        assert_eq!(found[0].bytes, 1502);
+
+        // The configs take the build cache inside them along.
+        let kinds: Vec<_> = targets(&locations, Purpose::Uninstall, true, false)
+            .iter()
+            .map(|t| t.kind)
+            .collect();
+        assert_eq!(kinds, [Kind::AppData, Kind::Configs]);
+
+        assert!(remove(&targets(&locations, Purpose::Uninstall, false, false)).is_empty());
+        assert!(!root.join("data").exists());
+        assert!(!root.join("home/esphome/.esphome").exists());
+        assert!(root.join("home/esphome/porch.yaml").exists());
+
+        // Without a trash the configs are not deleted outright.
+        let failures = remove(&targets(&locations, Purpose::Uninstall, true, false));
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].0, locations.config_dir);
+        assert!(root.join("home/esphome/porch.yaml").exists());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn lists_the_toolchains_only_when_asked() {
+        let root = unique_temp_dir("cleanup-toolchains");
+        fs::create_dir_all(root.join("data")).unwrap();
+        fs::create_dir_all(root.join("home/.platformio/packages")).unwrap();
+        let locations = locations(&root);
+
+        let kinds: Vec<_> = targets(&locations, Purpose::Uninstall, false, false)
+            .iter()
+            .map(|t| t.kind)
+            .collect();
+        assert_eq!(kinds, [Kind::AppData]);
+        let kinds: Vec<_> = targets(&locations, Purpose::Uninstall, false, true)
+            .iter()
+            .map(|t| t.kind)
+            .collect();
+        assert_eq!(kinds, [Kind::AppData, Kind::Toolchains]);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn reset_keeps_what_the_user_wrote_and_the_config_dir_setting() {
+        let root = unique_temp_dir("cleanup-reset");
+        fs::create_dir_all(root.join("data/python")).unwrap();
+        fs::create_dir_all(root.join("data/logs")).unwrap();
+        fs::write(root.join("data/settings.json"), "{}").unwrap();
+        for kept in KEPT_ON_RESET.iter().filter(|k| k.ends_with(".json")) {
+            fs::write(root.join("data").join(kept), "{}").unwrap();
+        }
+        fs::create_dir_all(root.join("data/undo/trash")).unwrap();
+        fs::create_dir_all(root.join("home/.platformio")).unwrap();
+        fs::create_dir_all(root.join("home/esphome")).unwrap();
+        let locations = locations(&root);
+
+        let kinds: Vec<_> = targets(&locations, Purpose::Reset, false, false)
+            .iter()
+            .map(|t| t.kind)
+            .collect();
+        assert_eq!(kinds, [Kind::AppData]);
+
+        assert!(reset(&locations, false).is_empty());
+        assert!(!root.join("data/python").exists());
+        assert!(root.join("data/logs").exists());
+        for kept in KEPT_ON_RESET.iter().filter(|k| k.ends_with(".json")) {
+            assert!(root.join("data").join(kept).exists(), "{kept} removed");
+        }
+        assert!(root.join("data/undo/trash").exists());
+        assert!(root.join("home/.platformio").exists());
+        let settings =
+            crate::settings::peek_settings_file(&root.join("data/settings.json")).unwrap();
+        assert_eq!(
+            settings.workspace.config_dir,
+            Some(locations.config_dir.clone())
+        );
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn reset_covers_the_separate_settings_logs_and_cache_directories() {
+        let root = unique_temp_dir("cleanup-xdg");
+        for dir in ["config", "state/logs", "cache/compare/config", "data"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        fs::write(root.join("config/settings.json"), "{}").unwrap();
+        fs::write(root.join("state/logs/app.log"), "").unwrap();
+        let mut locations = locations(&root);
+        locations.settings_dir = root.join("config");
+        locations.app_dirs = ["config", "state", "cache"]
+            .map(|dir| root.join(dir))
+            .to_vec();
+
+        assert!(reset(&locations, false).is_empty());
+        assert!(root.join("state/logs/app.log").exists());
+        assert!(!root.join("cache/compare").exists());
+        let settings =
+            crate::settings::peek_settings_file(&root.join("config/settings.json")).unwrap();
+        assert_eq!(
+            settings.workspace.config_dir,
+            Some(locations.config_dir.clone())
+        );
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn never_lists_the_home_directory() {
+        let root = unique_temp_dir("cleanup-home");
+        fs::create_dir_all(root.join("home")).unwrap();
+        let mut locations = locations(&root);
+        locations.config_dir = root.join("home");
+        assert!(targets(&locations, Purpose::Uninstall, true, false).is_empty());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn formats_sizes() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(12_345), "12 KB");
+        assert_eq!(format_size(340_000_000), "340 MB");
+        assert_eq!(format_size(1_234_000_000), "1.2 GB");
+    }
+}
@@ -57,6 +57,24 @@ pub enum OnOff {
     Off,
 }
 
+/// What `skip-update` skips an update of.
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+#[value(rename_all = "kebab-case")]
+pub enum UpdateComponentArg {
+    Esphome,
+    DeviceBuilder,
+    Desktop,
+}
+
+/// Output format of `device inventory`.
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+#[value(rename_all = "lowercase")]
+pub enum InventoryFormat {
+    Csv,
+    Json,
+    Markdown,
+}
+
 /// Subcommands that control an already-running app over the local control
 /// channel instead of launching a new instance. They mirror the tray menu so
 /// systems without a working tray (some Linux desktops) can still drive the
@@ -83,6 +101,22 @@ pub enum CliCommand {
         #[arg(value_enum)]
         state: Option<OnOff>,
     },
+    /// Show, start or stop the editor language server, which serves ESPHome
+    /// diagnostics over LSP on a loopback port
+    LanguageServer {
+        /// New state; omit to show the current one
+        #[arg(value_enum)]
+        state: Option<OnOff>,
+        /// Port to listen on, saved for later starts (with `on`)
+        #[arg(long, requires = "state")]
+        port: Option<u16>,
+    },
+    /// Run a second, temporary dashboard on another ESPHome release, on a
+    /// copy of the configs, to compare the two when chasing a regression
+    Compare {
+        #[command(subcommand)]
+        action: Option<CompareAction>,
+    },
     /// Update the desktop app, ESPHome, and the device builder
     Update,
     /// Show recent dashboard log output
@@ -94,10 +128,76 @@ pub enum CliCommand {
         #[arg(long)]
         open: bool,
     },
+    /// List devices with their notes and tags, edit them, manage device
+    /// groups, or validate/compile/upload several devices at once
+    Device {
+        #[command(subcommand)]
+        action: Option<DeviceAction>,
+    },
+    /// Show the audit log of updates, switches, restarts and settings
+    /// changes, and check it hasn't been edited
+    Audit,
+    /// Store the SMTP password the email digest logs in with in the OS
+    /// keychain, read from standard input
+    EmailPassword {
+        /// The SMTP user, as in the settings' `email_digest: username`
+        username: String,
+        /// Remove the stored password instead
+        #[arg(long)]
+        remove: bool,
+    },
+    /// Store the HTTP proxy's password in the OS keychain, read from
+    /// standard input
+    ProxyPassword {
+        /// The proxy user, as in the settings' `proxy: username`
+        username: String,
+        /// Remove the stored password instead
+        #[arg(long)]
+        remove: bool,
+    },
+    /// Stop the daily update check notifying about a version: the one it
+    /// last notified about, or `version`. A newer one is news again
+    SkipUpdate {
+        /// esphome, device-builder or desktop
+        component: UpdateComponentArg,
+        /// The version to skip (default: the one last notified about)
+        version: Option<String>,
+        /// Forget the skipped version instead
+        #[arg(long, conflicts_with = "version")]
+        clear: bool,
+    },
     /// Restart the dashboard backend
     Restart,
+    /// Stop the dashboard's running compile, leaving the dashboard up
+    CancelBuild,
     /// Quit the running app
     Quit,
+    /// Reset the app to a clean slate: remove the Python environment, settings
+    /// and build caches, and optionally the device configs. The next launch
+    /// sets the app up again from the bundle. The app must not be running.
+    Reset {
+        /// Also delete the config directory with the device configs
+        #[arg(long)]
+        include_config: bool,
+        /// Don't ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Remove the Python environment, settings, logs and build caches the app
+    /// leaves behind, and optionally PlatformIO's toolchains and the device
+    /// configs. Run it before removing the app; the app must not be running.
+    UninstallData {
+        /// Also delete the config directory with the device configs
+        #[arg(long)]
+        include_config: bool,
+        /// Also remove PlatformIO's toolchains, which any other PlatformIO use
+        /// on this computer shares
+        #[arg(long)]
+        include_toolchains: bool,
+        /// Don't ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
     /// Show app and backend status
     Status {
         /// Print the status as JSON
@@ -110,6 +210,283 @@ pub enum CliCommand {
     Api(ApiMethod),
 }
 
+/// `esphome-desktop compare` actions. Without one, shows what is running.
+#[derive(clap::Subcommand, Debug, Clone)]
+pub enum CompareAction {
+    /// Start the comparison dashboard, replacing one already running. The
+    /// first start of a version installs it, which takes a few minutes
+    Start {
+        /// ESPHome version to run, e.g. 2024.6.4
+        version: String,
+        /// Port to serve it on (default: the one after the main dashboard's)
+        #[arg(long)]
+        port: Option<u16>,
+    },
+    /// Stop the comparison dashboard
+    Stop,
+}
+
+/// `esphome-desktop device` actions. Notes, tags and groups are plain files in
+/// the data directory, so editing them works whether or not the app is
+/// running; the batch actions run in the app.
+#[derive(clap::Subcommand, Debug, Clone)]
+pub enum DeviceAction {
+    /// List devices with their notes and tags (the default)
+    List,
+    /// Set a device's note; omit the text to clear it
+    Note {
+        /// Device name (its config file name without `.yaml`)
+        device: String,
+        /// The note
+        text: Vec<String>,
+    },
+    /// Add tags to a device
+    Tag {
+        /// Device name (its config file name without `.yaml`)
+        device: String,
+        /// Tags to add, e.g. `attic` or `no-auto-update`
+        #[arg(required = true)]
+        tags: Vec<String>,
+    },
+    /// Remove tags from a device
+    Untag {
+        /// Device name (its config file name without `.yaml`)
+        device: String,
+        /// Tags to remove
+        #[arg(required = true)]
+        tags: Vec<String>,
+    },
+    /// Start a new device config from the `new_device` preset in the
+    /// settings; the flags override it for this device
+    New {
+        /// Device name: lowercase letters, digits and hyphens
+        device: String,
+        /// Chip family: esp32, esp8266, rp2040, bk72xx, rtl87xx or ln882x
+        #[arg(long)]
+        platform: Option<String>,
+        /// Board id, e.g. esp32-c3-devkitm-1
+        #[arg(long)]
+        board: Option<String>,
+        /// arduino or esp-idf (esp32 only)
+        #[arg(long)]
+        framework: Option<String>,
+    },
+    /// Open a device's config in an editor: `editor_command` from the
+    /// settings, else the first of VS Code, Sublime Text and Notepad++ found
+    Edit {
+        /// Device name (its config file name without `.yaml`)
+        device: String,
+        /// Use this editor instead: code, sublime, notepad++, or default for
+        /// the system's default app
+        #[arg(long)]
+        editor: Option<String>,
+    },
+    /// List the editors `edit` can use
+    Editors,
+    /// List device groups and their members
+    Groups,
+    /// Add devices to a group, creating it if needed
+    Group {
+        /// Group name, e.g. "outdoor sensors"
+        group: String,
+        /// Devices to add
+        #[arg(required = true)]
+        devices: Vec<String>,
+    },
+    /// Remove devices from a group; an emptied group is deleted
+    Ungroup {
+        /// Group name
+        group: String,
+        /// Devices to remove
+        #[arg(required = true)]
+        devices: Vec<String>,
+    },
+    /// Show each device's flash and RAM use from its last build, or one
+    /// device's history, as recorded by batch compiles and uploads
+    Sizes {
+        /// Device name; omit for every device's latest build
+        device: Option<String>,
+    },
+    /// List every device with its chip, board, framework, firmware release,
+    /// when and where it last answered, and its tags
+    Inventory {
+        /// csv, json or markdown
+        #[arg(long, value_enum, default_value_t = InventoryFormat::Markdown)]
+        format: InventoryFormat,
+        /// Write the inventory to this file instead of printing it
+        #[arg(long)]
+        output: Option<std::path::PathBuf>,
+    },
+    /// Show each device's partition scheme, or write a standard partition
+    /// table with larger app slots into the config directory
+    Partitions {
+        /// Device name; omit for every device
+        device: Option<String>,
+        /// Write this standard table (min_spiffs or huge_app) as
+        /// partitions_<name>.csv
+        #[arg(long, requires = "device")]
+        write: Option<String>,
+    },
+    /// Compile a device with two ESPHome releases and report how the
+    /// firmware size, sdkconfig and generated main.cpp differ
+    Diff {
+        /// Device name (its config file name without `.yaml`)
+        device: String,
+        /// The release it last worked with, e.g. 2024.6.4
+        from: String,
+        /// The release to compare against, e.g. 2024.10.0
+        to: String,
+    },
+    /// Scaffold a config for a device whose YAML is lost, from what it
+    /// reports over the native API
+    Recover {
+        /// The device's address, e.g. 192.168.1.40 or porch.local, or the
+        /// name of one added with `device add`
+        host: String,
+        /// Name for the new config; defaults to the one the device reports
+        #[arg(long)]
+        name: Option<String>,
+        /// The device's API encryption key, if it has one (default: the one
+        /// stored with `device key`, else the secret named with `device add
+        /// --key-secret`)
+        #[arg(long)]
+        key: Option<String>,
+        /// The device's legacy API password, if it has one
+        #[arg(long)]
+        password: Option<String>,
+        /// Native API port
+        #[arg(long, default_value_t = crate::devices::api::DEFAULT_PORT)]
+        port: u16,
+    },
+    /// Show a device's entities and their live states, its Bluetooth proxy
+    /// connections, and optionally its logs, from its native API (devices
+    /// without API encryption)
+    Watch {
+        /// The device's address, e.g. 192.168.1.40 or porch.local, or the
+        /// name of one added with `device add`
+        host: String,
+        /// The device's legacy API password, if it has one
+        #[arg(long, default_value = "")]
+        password: String,
+        /// Native API port
+        #[arg(long, default_value_t = crate::devices::api::DEFAULT_PORT)]
+        port: u16,
+        /// Also stream its logs, at this level and up (default debug)
+        #[arg(long, num_args = 0..=1, default_missing_value = "debug")]
+        logs: Option<String>,
+    },
+    /// Show how fast and reliably each device answered over the last day,
+    /// or one device's rounds, as recorded with `monitor_latency` on
+    Latency {
+        /// Device name; omit for every device, worst first
+        device: Option<String>,
+    },
+    /// List devices' fallback hotspots in range, with how to get each device
+    /// back onto the Wi-Fi
+    Hotspots,
+    /// Show how often batch uploads failed per device, subnet and tag,
+    /// worst first, to find where the Wi-Fi is too weak for OTA
+    Ota,
+    /// Add a device the network doesn't show (e.g. on another VLAN) by its
+    /// address, or change its entry; it is then listed, monitored and
+    /// uploaded to at that address
+    Add {
+        /// Device name: its config's, if it has one
+        device: String,
+        /// IP address or hostname
+        address: String,
+        /// Name in secrets.yaml of its API encryption key
+        #[arg(long)]
+        key_secret: Option<String>,
+    },
+    /// Remove a device added with `add`; its config, if any, stays
+    Forget {
+        /// Device name
+        device: String,
+    },
+    /// Move a retired device's config, build files and size history into
+    /// `archive/` in the config folder, out of the dashboard and batch
+    /// actions
+    Archive {
+        /// Device name (its config file name without `.yaml`)
+        device: String,
+    },
+    /// Bring an archived device back
+    Restore {
+        /// Device name (its config file name without `.yaml`)
+        device: String,
+    },
+    /// List archived devices
+    Archived,
+    /// Undo the app's last change to the config folder: a rename, archive or
+    /// restore, an added snippet, or a new or recovered config
+    Undo,
+    /// Store a device's API encryption key in the OS keychain, or show
+    /// whether one is stored
+    Key {
+        /// Device name
+        device: String,
+        /// The key, as in its config's `api: encryption: key:`
+        key: Option<String>,
+        /// Remove the stored key instead
+        #[arg(long, conflicts_with = "key")]
+        remove: bool,
+    },
+    /// Copy devices' API encryption keys from their configs (and
+    /// secrets.yaml) into the OS keychain
+    ImportKeys {
+        /// Devices to import; omit for every device
+        devices: Vec<String>,
+    },
+    /// Validate the configs of several devices
+    Validate(BatchTarget),
+    /// Compile several devices, one after another
+    Compile(BatchTarget),
+    /// Compile several devices and upload each over the air
+    Upload {
+        #[command(flatten)]
+        target: BatchTarget,
+        /// Flash a device that keeps failing OTA over the USB serial port
+        /// instead, when exactly one is connected
+        #[arg(long)]
+        serial_fallback: bool,
+    },
+    /// List each device's external component sources and whether they are
+    /// on the allow-list
+    Sources,
+    /// Add external component sources to the allow-list, e.g.
+    /// `github://someone/components` or `github.com/someone` for all of their
+    /// repositories
+    Allow {
+        #[arg(required = true)]
+        sources: Vec<String>,
+    },
+    /// Remove external component sources from the allow-list
+    Disallow {
+        #[arg(required = true)]
+        sources: Vec<String>,
+    },
+    /// Show or set whether batch actions refuse configs with unlisted
+    /// sources (off: they only warn)
+    BlockUnlisted {
+        /// New state; omit to show the current one
+        #[arg(value_enum)]
+        state: Option<OnOff>,
+    },
+}
+
+/// The devices a batch action runs on: those named, plus a group's members.
+#[derive(clap::Args, Debug, Clone)]
+#[command(group(clap::ArgGroup::new("target").required(true).multiple(true)))]
+pub struct BatchTarget {
+    /// Device names
+    #[arg(group = "target")]
+    pub devices: Vec<String>,
+    /// Also run on every device in this group
+    #[arg(long, group = "target")]
+    pub group: Option<String>,
+}
+
 /// Methods of the machine-readable `esphome-desktop api <method>` interface.
 /// This is the contract the device-builder dashboard codes against; unlike the
 /// human subcommands above it emits only NDJSON and is versioned via
@@ -157,6 +534,17 @@ pub struct Cli {
     /// Only takes effect together with `--use-builder`.
     #[arg(long = "builder-channel", value_enum, default_value_t = BuilderChannelArg::Beta)]
     pub builder_channel: BuilderChannelArg,
+
+    /// Check headlessly that this build works, then exit (for packagers and
+    /// CI; see `self_test`)
+    #[arg(long = "self-test", hide = true)]
+    pub self_test: bool,
+
+    /// Forward a loopback port to a dashboard elsewhere until killed, as
+    /// `<port>=<host:port>`. The `remote` runtime runs the app with this as
+    /// its backend process.
+    #[arg(long = "relay", hide = true, value_name = "LISTEN=ADDRESS")]
+    pub relay: Option<String>,
 }
 
 /// Run a control subcommand as a short-lived CLI client and return its exit
@@ -165,6 +553,17 @@ pub fn run_cli(command: CliCommand) -> std::process::ExitCode {
     crate::control::client::run(command)
 }
 
+/// Run `--self-test` and return its exit code. Like [`run_cli`], without
+/// Tauri, so it needs no display.
+pub fn run_self_test() -> std::process::ExitCode {
+    crate::self_test::run()
+}
+
+/// Run `--relay` and return its exit code, again without Tauri.
+pub fn run_relay(spec: &str) -> std::process::ExitCode {
+    crate::daemon::run_relay(spec)
+}
+
 /// Whether this is a bare `esphome-desktop` run from a terminal — no
 /// subcommand and no flags at all, just the program name — which should print
 /// the command list instead of launching another app instance. Any explicit
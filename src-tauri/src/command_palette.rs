@@ -0,0 +1,165 @@
+//! The command palette: a small window, summoned from anywhere with the
+//! `palette_shortcut` global shortcut, listing the tray menu's actions and a
+//! shortcut to every device (see [`crate::tray::actions`]), narrowed by a
+//! fuzzy match as the user types. The page is `dist/palette.html`.
+
+use serde::Serialize;
+use tauri::plugin::TauriPlugin;
+use tauri::{AppHandle, Manager, Wry};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
+use tracing::{info, warn};
+
+use crate::i18n::{t, t_with};
+use crate::tray::actions;
+
+/// The palette window's label.
+const LABEL: &str = "command_palette";
+
+/// The global shortcut plugin, opening the palette on any registered
+/// shortcut.
+pub(crate) fn plugin() -> TauriPlugin<Wry> {
+    tauri_plugin_global_shortcut::Builder::new()
+        .with_handler(|app, _shortcut, event| {
+            if matches!(event.state(), ShortcutState::Pressed) {
+                if let Err(e) = open(app) {
+                    warn!("Failed to open the command palette: {}", e);
+                }
+            }
+        })
+        .build()
+}
+
+/// Open the palette on `shortcut`. Best-effort: another application may
+/// hold it.
+pub(crate) fn register(app: &AppHandle, shortcut: &str) {
+    match app.global_shortcut().register(shortcut) {
+        Ok(()) => info!("Command palette on {}", shortcut),
+        Err(e) => warn!(
+            "Failed to register the palette shortcut {:?}: {}",
+            shortcut, e
+        ),
+    }
+}
+
+/// Show the palette, opening it if it isn't.
+pub(crate) fn open(app: &AppHandle) -> tauri::Result<()> {
+    let texts = serde_json::json!({
+        "placeholder": t("palette.placeholder"),
+        "label": t("palette.label"),
+        "none": t("palette.none"),
+    });
+    crate::window::open_sized(
+        app,
+        LABEL,
+        "palette.html",
+        t("palette.title"),
+        texts,
+        (560.0, 380.0),
+    )
+}
+
+/// One action, as the page shows it.
+#[derive(Debug, Serialize)]
+pub(crate) struct Action {
+    id: String,
+    label: String,
+}
+
+/// The actions matching `query`, best first.
+#[tauri::command]
+pub(crate) async fn palette_actions(app: AppHandle, query: String) -> Vec<Action> {
+    rank(&query, actions::list(&app))
+        .into_iter()
+        .map(|(id, label)| Action { id, label })
+        .collect()
+}
+
+/// Run the action `id` and close the palette.
+#[tauri::command]
+pub(crate) async fn run_palette_action(app: AppHandle, id: String) -> Result<(), String> {
+    if !actions::run(&app, &id) {
+        return Err(t_with("palette.gone", &[("action", &id)]));
+    }
+    close_palette(app).await;
+    Ok(())
+}
+
+/// Close the palette, as Escape does.
+#[tauri::command]
+pub(crate) async fn close_palette(app: AppHandle) {
+    if let Some(window) = app.get_webview_window(LABEL) {
+        if let Err(e) = window.close() {
+            warn!("Failed to close the command palette: {}", e);
+        }
+    }
+}
+
+/// The `actions` whose label fuzzily matches `query`, best first; ties, and
+/// everything for a blank query, keep their order.
+fn rank(query: &str, actions: Vec<(String, String)>) -> Vec<(String, String)> {
+    let mut scored: Vec<(u32, (String, String))> = actions
+        .into_iter()
+        .filter_map(|action| Some((score(query, &action.1)?, action)))
+        .collect();
+    scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    scored.into_iter().map(|(_, action)| action).collect()
+}
+
+/// How well `label` matches `query`, ignoring case and spaces in the query:
+/// `None` unless its characters all appear in order. Each counts, more so
+/// right after the previous one or at the start of a word.
+fn score(query: &str, label: &str) -> Option<u32> {
+    let label: Vec<char> = label.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut next = 0;
+    let mut previous = None;
+    for wanted in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = next + label[next..].iter().position(|&c| c == wanted)?;
+        score += 1;
+        if previous.is_some_and(|p: usize| p + 1 == found) {
+            score += 4;
+        }
+        if found == 0 || !label[found - 1].is_alphanumeric() {
+            score += 8;
+        }
+        previous = Some(found);
+        next = found + 1;
+    }
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranks_word_starts_and_runs_first() {
+        let actions = |labels: &[&str]| -> Vec<(String, String)> {
+            labels
+                .iter()
+                .map(|label| (label.to_string(), label.to_string()))
+                .collect()
+        };
+        let labels = |ranked: Vec<(String, String)>| {
+            ranked
+                .into_iter()
+                .map(|(_, label)| label)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(score("xyz", "Restart Dashboard"), None);
+        assert_eq!(
+            labels(rank(
+                "rd",
+                actions(&["Check for Updates", "Open: garden", "Restart Dashboard"])
+            )),
+            ["Restart Dashboard", "Open: garden", "Check for Updates"]
+        );
+        assert_eq!(
+            labels(rank("logs", actions(&["Blogs", "View Logs..."]))),
+            ["View Logs...", "Blogs"]
+        );
+        // A blank query keeps the menu's order.
+        assert_eq!(labels(rank(" ", actions(&["b", "a"]))), ["b", "a"]);
+    }
+}
@@ -0,0 +1,76 @@
+//! The `audit` subcommand: print the audit log and check its chain.
+//!
+//! Like `logs`, this reads the file directly and works whether or not the app
+//! is running.
+
+use std::process::ExitCode;
+
+use super::fail;
+use crate::audit::{self, AuditLog};
+
+pub(super) fn run() -> ExitCode {
+    let Some(data_dir) = crate::platform::data_dir_no_handle() else {
+        return fail("could not resolve the data directory");
+    };
+    let path = audit::path(&data_dir);
+    let log = match audit::read(&path) {
+        Ok(log) => log,
+        Err(e) => return fail(format!("{e:#}")),
+    };
+    println!("Audit log: {}", path.display());
+    println!();
+    print!("{}", render(&log));
+    match log.broken_at {
+        Some(line) => fail(format!(
+            "the audit log was modified: line {line} doesn't follow the line before it"
+        )),
+        None => ExitCode::SUCCESS,
+    }
+}
+
+/// One line per entry: `time  source  action  detail`.
+fn render(log: &AuditLog) -> String {
+    if log.entries.is_empty() {
+        return "No administrative actions recorded\n".to_string();
+    }
+    log.entries
+        .iter()
+        .map(|e| {
+            format!(
+                "{}  {:<4}  {:<12}  {}\n",
+                e.time,
+                e.source.name(),
+                e.action,
+                e.detail
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::{Entry, Source};
+
+    #[test]
+    fn renders_one_aligned_line_per_entry() {
+        let log = AuditLog {
+            entries: vec![Entry {
+                time: "2026-10-16T09:00:00Z".into(),
+                source: Source::Cli,
+                action: "restart".into(),
+                detail: "dashboard restarted and ready".into(),
+                prev: String::new(),
+            }],
+            broken_at: None,
+        };
+        assert_eq!(
+            render(&log),
+            "2026-10-16T09:00:00Z  cli   restart       dashboard restarted and ready\n"
+        );
+        assert_eq!(
+            render(&AuditLog::default()),
+            "No administrative actions recorded\n"
+        );
+    }
+}
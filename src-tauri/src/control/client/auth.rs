@@ -0,0 +1,26 @@
+//! OS authentication for the subcommands that change things without the app
+//! (`require_auth`), asked here as the app asks before the same change.
+
+/// What a change refused by [`authorize`] fails with, as the control channel
+/// words it.
+pub(super) const NOT_AUTHORIZED: &str = "OS authentication was declined or isn't available";
+
+/// Ask for OS authentication when `require_auth` is on, as the app does
+/// ([`crate::control::ops::authorize`]). Unreadable settings are the
+/// defaults, as they are to the app. Anything but a successful
+/// authentication is a refusal.
+pub(super) fn authorize(reason: &str) -> bool {
+    let required = crate::platform::settings_path_no_handle()
+        .and_then(|path| crate::settings::peek_settings_file(&path))
+        .is_some_and(|settings| settings.require_auth);
+    if !required {
+        return true;
+    }
+    match crate::platform::authenticate(reason) {
+        Ok(authenticated) => authenticated,
+        Err(e) => {
+            eprintln!("OS authentication unavailable: {e:#}");
+            false
+        }
+    }
+}
@@ -0,0 +1,142 @@
+//! The `reset` and `uninstall-data` subcommands: remove what the app keeps on
+//! disk, for a clean slate or before removing the app.
+//!
+//! Run offline, and only offline: removing the Python environment under a
+//! running dashboard would break it mid-compile. The Windows uninstaller runs
+//! `uninstall-data --yes` after asking its own questions.
+
+use std::io::{BufRead, Write};
+use std::process::ExitCode;
+
+use super::{connect, fail};
+use crate::audit::{self, Source};
+use crate::cleanup::{self, Locations, Purpose, Target};
+
+pub(super) fn run(
+    purpose: Purpose,
+    include_config: bool,
+    include_toolchains: bool,
+    yes: bool,
+) -> ExitCode {
+    if connect().is_ok() {
+        let item = match purpose {
+            Purpose::Uninstall => "Uninstall Data...",
+            Purpose::Reset => "Reset ESPHome Builder...",
+        };
+        return fail(format!(
+            "ESPHome Device Builder is running. Quit it first, or use \"{item}\" in the tray menu."
+        ));
+    }
+    let Some(locations) = Locations::resolve() else {
+        return fail("could not resolve the data directory");
+    };
+    let targets = cleanup::targets(&locations, purpose, include_config, include_toolchains);
+    if targets.is_empty() {
+        println!("Nothing to remove");
+        return ExitCode::SUCCESS;
+    }
+    println!("This removes:");
+    print!("{}", describe(&targets));
+    if !include_config {
+        println!(
+            "Your device configs in {} are kept (--include-config removes them too).",
+            locations.config_dir.display()
+        );
+    }
+    if let Some(toolchains) = locations
+        .platformio_dir
+        .as_ref()
+        .filter(|dir| purpose == Purpose::Uninstall && !include_toolchains && dir.exists())
+    {
+        println!(
+            "PlatformIO's toolchains in {} are kept (--include-toolchains removes them too).",
+            toolchains.display()
+        );
+    }
+    if purpose == Purpose::Reset {
+        println!(
+            "Logs, device notes and groups, devices added by hand, the component source \
+             allow-list, the undo history, the audit log and PlatformIO toolchains are kept."
+        );
+    }
+    if !yes && !ask("Remove them? [y/N] ") {
+        println!("Nothing removed");
+        return ExitCode::SUCCESS;
+    }
+
+    let failures = match purpose {
+        Purpose::Uninstall => cleanup::remove(&targets),
+        Purpose::Reset => {
+            let failures = cleanup::reset(&locations, include_config);
+            let configs = if include_config { "deleted" } else { "kept" };
+            audit::record_in(
+                &locations.data_dir,
+                Source::Cli,
+                "reset",
+                format!("app data reset, configs {configs}"),
+            );
+            failures
+        }
+    };
+    for (path, e) in &failures {
+        eprintln!("Could not remove {}: {e}", path.display());
+    }
+    match (purpose, failures.is_empty()) {
+        (_, false) => fail("some files could not be removed; delete them by hand"),
+        (Purpose::Uninstall, true) => {
+            println!("Removed");
+            ExitCode::SUCCESS
+        }
+        (Purpose::Reset, true) => {
+            println!("Reset. The next launch sets the app up again: esphome-desktop open");
+            ExitCode::SUCCESS
+        }
+    }
+}
+
+/// One line per target: size, what it is, where.
+fn describe(targets: &[Target]) -> String {
+    targets
+        .iter()
+        .map(|t| {
+            format!(
+                "  {:>7}  {}\n           {}\n",
+                cleanup::format_size(t.bytes),
+                t.kind.name(),
+                t.path.display()
+            )
+        })
+        .collect()
+}
+
+/// Ask a yes/no question on the terminal; anything but `y`/`yes` is a no.
+fn ask(question: &str) -> bool {
+    print!("{question}");
+    let _ = std::io::stdout().flush();
+    let mut answer = String::new();
+    if std::io::stdin().lock().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cleanup::Kind;
+    use std::path::PathBuf;
+
+    #[test]
+    fn describes_each_target_with_its_size() {
+        let targets = [Target {
+            kind: Kind::BuildCache,
+            path: PathBuf::from("/home/x/esphome/.esphome"),
+            bytes: 2_500_000_000,
+            keep: &[],
+        }];
+        assert_eq!(
+            describe(&targets),
+            "   2.5 GB  build cache\n           /home/x/esphome/.esphome\n"
+        );
+    }
+}
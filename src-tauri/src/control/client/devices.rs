@@ -0,0 +1,848 @@
+//! The `device` subcommand: list devices, add ones the network doesn't show,
+//! start new configs, archive them, edit their notes, tags and groups, open
+//! their configs in an editor, show their build sizes, latency and upload
+//! failures, write an inventory, manage the external component allow-list,
+//! and start batch actions, build diffs and config recovery.
+//!
+//! Apart from those three, this never touches the control channel, like
+//! `logs`. The device list is the config directory plus the registry, and the
+//! notes, groups, registry and allow-list are files in the data directory,
+//! all readable whether or not the app is running. Batch targets are resolved
+//! here too, so the app only ever sees a list of device names.
+
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use super::auth::{authorize, NOT_AUTHORIZED};
+use super::{fail, simple, BATCH_TIMEOUT, CHECK_TIMEOUT};
+use crate::audit::Source;
+use crate::control::protocol::Request;
+use crate::devices::batch::BatchAction;
+use crate::devices::discovery::{summarize, LatencyHistory, Summary};
+use crate::devices::editor;
+use crate::devices::hotspots;
+use crate::devices::inventory;
+use crate::devices::keys;
+use crate::devices::partitions::{self, Scheme};
+use crate::devices::registry::{self, ManualDevice, Registry};
+use crate::devices::sizes::{Sample, SizeHistory, Usage};
+use crate::devices::sources::{self, AllowList};
+use crate::devices::template;
+use crate::devices::{list_devices, NotesStore};
+use crate::util::ConfigStore;
+use crate::{BatchTarget, DeviceAction, InventoryFormat, OnOff};
+
+pub(super) fn run(action: Option<DeviceAction>) -> ExitCode {
+    let Some(data_dir) = crate::platform::data_dir_no_handle() else {
+        return fail("could not resolve the data directory");
+    };
+    let config_dir = config_dir();
+    let notes_path = NotesStore::path(&data_dir);
+    let store = match NotesStore::load(&notes_path) {
+        Ok(store) => store,
+        Err(e) => return fail(format!("{e:#}")),
+    };
+    let devices = match list_devices(&config_dir) {
+        Ok(devices) => devices,
+        Err(e) => return fail(format!("{e:#}")),
+    };
+    let registry_path = Registry::path(&data_dir);
+    let registry = match Registry::load(&registry_path) {
+        Ok(registry) => registry,
+        Err(e) => return fail(format!("{e:#}")),
+    };
+    let known = registry::with_manual(devices.clone(), &registry);
+
+    // Catch a typo here rather than filing notes under a name nothing uses.
+    let unknown = |device: &str| {
+        (!known.iter().any(|d| d == device)).then(|| {
+            fail(format!(
+                "no device named {device:?} in {} (see `esphome-desktop device list`)",
+                config_dir.display()
+            ))
+        })
+    };
+    let save = |store: &NotesStore| store.save(&notes_path).map_err(|e| fail(format!("{e:#}")));
+    let edit = |device: &str, f: &dyn Fn(&mut NotesStore)| {
+        if let Some(code) = unknown(device) {
+            return code;
+        }
+        let mut store = store.clone();
+        f(&mut store);
+        if let Err(code) = save(&store) {
+            return code;
+        }
+        println!("{}", describe(device, &store));
+        ExitCode::SUCCESS
+    };
+    let edit_group = |group: &str, members: &[String], add: bool| {
+        if let Some(code) = members.iter().find_map(|d| unknown(d)) {
+            return code;
+        }
+        let mut store = store.clone();
+        let members = members.iter().map(String::as_str);
+        if add {
+            store.add_to_group(group, members);
+        } else {
+            store.remove_from_group(group, members);
+        }
+        if let Err(code) = save(&store) {
+            return code;
+        }
+        print_groups(&store);
+        ExitCode::SUCCESS
+    };
+    let batch =
+        |action: BatchAction, target: BatchTarget, serial_fallback: bool| match batch_devices(
+            &target, &store,
+        ) {
+            Ok(targets) => match targets.iter().find_map(|d| unknown(d)) {
+                Some(code) => code,
+                None => simple(
+                    Request::DeviceBatch {
+                        action,
+                        devices: targets,
+                        serial_fallback,
+                    },
+                    BATCH_TIMEOUT,
+                ),
+            },
+            Err(message) => fail(message),
+        };
+    match action.unwrap_or(DeviceAction::List) {
+        DeviceAction::List => print_devices(&known, &store, &registry, &config_dir),
+        DeviceAction::Note { device, text } => {
+            edit(&device, &|s| s.set_note(&device, &text.join(" ")))
+        }
+        DeviceAction::Tag { device, tags } => edit(&device, &|s| {
+            s.add_tags(&device, tags.iter().map(String::as_str))
+        }),
+        DeviceAction::Untag { device, tags } => edit(&device, &|s| {
+            s.remove_tags(&device, tags.iter().map(String::as_str))
+        }),
+        DeviceAction::New {
+            device,
+            platform,
+            board,
+            framework,
+        } => {
+            let mut preset = new_device_preset();
+            if let Some(platform) = platform {
+                preset.platform = platform;
+            }
+            preset.board = board.unwrap_or(preset.board);
+            preset.framework = framework.or(preset.framework);
+            match template::create(&config_dir, &data_dir, &device, &preset) {
+                Ok(path) => {
+                    println!("Created {}", path.display());
+                    ExitCode::SUCCESS
+                }
+                Err(e) => fail(format!("{e:#}")),
+            }
+        }
+        DeviceAction::Edit { device, editor } => match unknown(&device) {
+            Some(code) => code,
+            None => edit_config(&config_dir, &device, editor.as_deref()),
+        },
+        DeviceAction::Editors => print_editors(),
+        DeviceAction::Groups => {
+            print_groups(&store);
+            ExitCode::SUCCESS
+        }
+        DeviceAction::Group { group, devices } => edit_group(&group, &devices, true),
+        DeviceAction::Ungroup { group, devices } => edit_group(&group, &devices, false),
+        DeviceAction::Sizes { device } => match device.as_deref().and_then(|d| unknown(d)) {
+            Some(code) => code,
+            None => print_sizes(&data_dir, device.as_deref()),
+        },
+        DeviceAction::Inventory { format, output } => {
+            let rows = inventory::rows(&known, &config_dir, &data_dir, &store, &registry);
+            let text = match format {
+                InventoryFormat::Csv => inventory::csv(&rows),
+                InventoryFormat::Markdown => inventory::markdown(&rows),
+                InventoryFormat::Json => match serde_json::to_string_pretty(&rows) {
+                    Ok(json) => json + "\n",
+                    Err(e) => return fail(format!("{e:#}")),
+                },
+            };
+            match output {
+                None => {
+                    print!("{text}");
+                    ExitCode::SUCCESS
+                }
+                Some(path) => match std::fs::write(&path, text) {
+                    Ok(()) => {
+                        println!("Wrote {} devices to {}", rows.len(), path.display());
+                        ExitCode::SUCCESS
+                    }
+                    Err(e) => fail(format!("Failed to write {}: {e}", path.display())),
+                },
+            }
+        }
+        DeviceAction::Partitions { device, write } => {
+            match device.as_deref().and_then(|d| unknown(d)) {
+                Some(code) => code,
+                None => show_partitions(&config_dir, &data_dir, &devices, device, write),
+            }
+        }
+        DeviceAction::Diff { device, from, to } => match unknown(&device) {
+            Some(code) => code,
+            None => simple(Request::DeviceDiff { device, from, to }, BATCH_TIMEOUT),
+        },
+        DeviceAction::Recover {
+            host,
+            name,
+            key,
+            password,
+            port,
+        } => {
+            let manual = registry.get(&host);
+            let device = name.as_deref().or(manual.map(|_| host.as_str()));
+            let key = match (key, device) {
+                (None, Some(device)) => match stored_key(&config_dir, &registry, device) {
+                    Ok(key) => key,
+                    Err(e) => return fail(format!("{e:#}")),
+                },
+                (key, _) => key,
+            };
+            simple(
+                Request::DeviceRecover {
+                    name: name.or_else(|| manual.map(|_| host.clone())),
+                    host: manual.map_or(host.clone(), |m| m.address.clone()),
+                    port,
+                    key,
+                    password,
+                },
+                CHECK_TIMEOUT,
+            )
+        }
+        DeviceAction::Watch {
+            host,
+            password,
+            port,
+            logs,
+        } => {
+            let host = registry.get(&host).map_or(host, |m| m.address.clone());
+            super::watch::run(&host, port, &password, logs.as_deref())
+        }
+        DeviceAction::Latency { device } => match device.as_deref().and_then(|d| unknown(d)) {
+            Some(code) => code,
+            None => print_latency(&data_dir, device.as_deref()),
+        },
+        DeviceAction::Hotspots => print_hotspots(&config_dir),
+        DeviceAction::Ota => super::ota::run(&data_dir, &store),
+        DeviceAction::Add {
+            device,
+            address,
+            key_secret,
+        } => {
+            let mut registry = registry.clone();
+            let manual = ManualDevice {
+                address,
+                key_secret,
+            };
+            if let Err(e) = registry
+                .add(&device, manual)
+                .and_then(|()| registry.save(&registry_path))
+            {
+                return fail(format!("{e:#}"));
+            }
+            if devices.contains(&device) {
+                println!("Added {device}");
+            } else {
+                println!(
+                    "Added {device}; it has no config in {}",
+                    config_dir.display()
+                );
+            }
+            ExitCode::SUCCESS
+        }
+        DeviceAction::Key {
+            device,
+            key,
+            remove,
+        } => {
+            // No `unknown` check: `recover` wants keys of devices whose
+            // config is gone.
+            edit_key(&device, key.as_deref(), remove)
+        }
+        DeviceAction::ImportKeys { devices } => match devices.iter().find_map(|d| unknown(d)) {
+            Some(code) => code,
+            None if devices.is_empty() => import_keys(&config_dir, &registry, &known),
+            None => import_keys(&config_dir, &registry, &devices),
+        },
+        DeviceAction::Forget { device } => {
+            let mut registry = registry.clone();
+            if !registry.remove(&device) {
+                return fail(format!("{device:?} wasn't added with `device add`"));
+            }
+            match registry.save(&registry_path) {
+                Ok(()) => {
+                    println!("Forgot {device}");
+                    ExitCode::SUCCESS
+                }
+                Err(e) => fail(format!("{e:#}")),
+            }
+        }
+        DeviceAction::Archive { device } => {
+            match crate::devices::archive::archive(&config_dir, &data_dir, &device) {
+                Ok(path) => {
+                    println!(
+                        "Archived {device} to {}; `device restore {device}` brings it back",
+                        path.display()
+                    );
+                    ExitCode::SUCCESS
+                }
+                Err(e) => fail(format!("{e:#}")),
+            }
+        }
+        DeviceAction::Restore { device } => {
+            if !authorize("Authenticate to restore an archived device.") {
+                return fail(NOT_AUTHORIZED);
+            }
+            match crate::devices::archive::restore(&config_dir, &data_dir, &device) {
+                Ok(path) => {
+                    println!("Restored {device} to {}", path.display());
+                    ExitCode::SUCCESS
+                }
+                Err(e) => fail(format!("{e:#}")),
+            }
+        }
+        DeviceAction::Archived => match crate::devices::archive::archived(&config_dir) {
+            Ok(archived) if archived.is_empty() => {
+                println!("No archived devices");
+                ExitCode::SUCCESS
+            }
+            Ok(archived) => {
+                archived.iter().for_each(|device| println!("{device}"));
+                ExitCode::SUCCESS
+            }
+            Err(e) => fail(format!("{e:#}")),
+        },
+        DeviceAction::Undo
+            if !authorize("Authenticate to undo the last change to your config folder.") =>
+        {
+            fail(NOT_AUTHORIZED)
+        }
+        DeviceAction::Undo => match crate::devices::undo::undo(&data_dir) {
+            Ok(Some(entry)) => {
+                crate::audit::record_in(&data_dir, Source::Cli, "undo", entry.label.clone());
+                println!("Undid {} (from {})", entry.label, entry.time);
+                ExitCode::SUCCESS
+            }
+            Ok(None) => {
+                println!("Nothing to undo");
+                ExitCode::SUCCESS
+            }
+            Err(e) => fail(format!("{e:#}")),
+        },
+        DeviceAction::Validate(target) => batch(BatchAction::Validate, target, false),
+        DeviceAction::Compile(target) => batch(BatchAction::Compile, target, false),
+        DeviceAction::Upload {
+            target,
+            serial_fallback,
+        } => batch(BatchAction::Upload, target, serial_fallback),
+        DeviceAction::Sources => print_sources(&devices, &config_dir, &data_dir),
+        DeviceAction::Allow { sources } => edit_allow_list(&data_dir, |list| {
+            match sources.iter().find(|s| !list.allow(s)) {
+                Some(s) => Err(format!("{s:?} is not a git source")),
+                None => Ok(()),
+            }
+        }),
+        DeviceAction::Disallow { sources } => edit_allow_list(&data_dir, |list| {
+            match sources.iter().find(|s| !list.disallow(s)) {
+                Some(s) => Err(format!("{s:?} is not on the allow-list")),
+                None => Ok(()),
+            }
+        }),
+        DeviceAction::BlockUnlisted { state } => edit_allow_list(&data_dir, |list| {
+            if let Some(state) = state {
+                list.block = matches!(state, OnOff::On);
+            }
+            Ok(())
+        }),
+    }
+}
+
+/// Load the allow-list, apply `f`, save it if that changed anything and
+/// print the result.
+fn edit_allow_list(
+    data_dir: &Path,
+    f: impl FnOnce(&mut AllowList) -> Result<(), String>,
+) -> ExitCode {
+    let path = AllowList::path(data_dir);
+    let original = match AllowList::load(&path) {
+        Ok(list) => list,
+        Err(e) => return fail(format!("{e:#}")),
+    };
+    let mut list = original.clone();
+    if let Err(message) = f(&mut list) {
+        return fail(message);
+    }
+    if list != original {
+        if let Err(e) = list.save(&path) {
+            return fail(format!("{e:#}"));
+        }
+    }
+    println!("Allowed external component sources:");
+    for source in list.allowed() {
+        println!("  {source}");
+    }
+    let mode = if list.block {
+        "refused"
+    } else {
+        "warned about"
+    };
+    println!("Configs using other sources are {mode} by batch actions");
+    ExitCode::SUCCESS
+}
+
+fn print_sources(devices: &[String], config_dir: &Path, data_dir: &Path) -> ExitCode {
+    let list = match AllowList::load(&AllowList::path(data_dir)) {
+        Ok(list) => list,
+        Err(e) => return fail(format!("{e:#}")),
+    };
+    let mut any = false;
+    for device in devices {
+        let Some(yaml) = crate::devices::config_path(config_dir, device)
+            .and_then(|path| std::fs::read_to_string(path).ok())
+        else {
+            continue;
+        };
+        let lines = describe_sources(&sources::scan(&yaml), &list);
+        if !lines.is_empty() {
+            any = true;
+            println!("{device}");
+            lines.iter().for_each(|line| println!("  {line}"));
+        }
+    }
+    if !any {
+        println!("No device uses external components from git");
+    }
+    ExitCode::SUCCESS
+}
+
+/// One line per source: `source  allowed` or `source  NOT ALLOWED`.
+fn describe_sources(found: &[String], list: &AllowList) -> Vec<String> {
+    found
+        .iter()
+        .map(|source| {
+            let status = if list.allows(source) {
+                "allowed"
+            } else {
+                "NOT ALLOWED"
+            };
+            format!("{source}  {status}")
+        })
+        .collect()
+}
+
+fn edit_config(config_dir: &Path, device: &str, editor: Option<&str>) -> ExitCode {
+    let Some(path) = crate::devices::config_path(config_dir, device) else {
+        return fail(format!("no config file for {device:?}"));
+    };
+    let command = editor_command();
+    let editor = match editor::choose(command.as_deref(), editor) {
+        Ok(editor) => editor,
+        Err(e) => return fail(format!("{e:#}")),
+    };
+    if let Err(e) = editor::open(editor.as_ref(), &path) {
+        return fail(format!("{e:#}"));
+    }
+    let with = editor.map_or("the default app".to_string(), |e| e.name);
+    println!("Opened {} with {with}", path.display());
+    ExitCode::SUCCESS
+}
+
+fn print_editors() -> ExitCode {
+    if let Some(command) = editor_command() {
+        println!("custom     {command}  (editor_command, used by default)");
+    }
+    let detected = editor::detect();
+    for e in &detected {
+        println!("{:<10} {}", e.id, e.name);
+    }
+    if detected.is_empty() {
+        println!("No supported editor found (VS Code, Sublime Text, Notepad++)");
+    }
+    println!("default    the system's default app for .yaml files");
+    ExitCode::SUCCESS
+}
+
+/// The named devices followed by the group's members, without duplicates.
+fn batch_devices(target: &BatchTarget, store: &NotesStore) -> Result<Vec<String>, String> {
+    let mut devices = target.devices.clone();
+    if let Some(group) = &target.group {
+        let members = store
+            .group(group)
+            .ok_or_else(|| format!("no device group named {group:?}"))?;
+        devices.extend(members.iter().cloned());
+    }
+    let mut seen = std::collections::HashSet::new();
+    devices.retain(|d| seen.insert(d.clone()));
+    Ok(devices)
+}
+
+fn print_groups(store: &NotesStore) {
+    if store.groups().is_empty() {
+        println!("No device groups");
+    }
+    for (group, members) in store.groups() {
+        let members: Vec<&str> = members.iter().map(String::as_str).collect();
+        println!("{group}: {}", members.join(", "));
+    }
+}
+
+/// The configured config directory, or the default when settings are
+/// missing or unreadable (the same fallback the app uses).
+fn config_dir() -> PathBuf {
+    peek_settings()
+        .and_then(|s| s.workspace.config_dir)
+        .unwrap_or_else(crate::settings::default_config_dir)
+}
+
+fn new_device_preset() -> crate::settings::NewDevicePreset {
+    peek_settings()
+        .map(|s| s.workspace.new_device)
+        .unwrap_or_default()
+}
+
+fn editor_command() -> Option<String> {
+    peek_settings().and_then(|s| s.workspace.editor_command)
+}
+
+fn peek_settings() -> Option<crate::settings::Settings> {
+    crate::platform::settings_path_no_handle()
+        .and_then(|path| crate::settings::peek_settings_file(&path))
+}
+
+fn print_devices(
+    devices: &[String],
+    store: &NotesStore,
+    registry: &Registry,
+    config_dir: &Path,
+) -> ExitCode {
+    if devices.is_empty() {
+        println!("No device configs in {}", config_dir.display());
+        return ExitCode::SUCCESS;
+    }
+    for device in devices {
+        let mut line = describe(device, store);
+        if let Some(manual) = registry.get(device) {
+            line.push_str(&format!("  (at {})", manual.address));
+        }
+        println!("{line}");
+    }
+    ExitCode::SUCCESS
+}
+
+/// `device`'s key from the keychain, else from its config or registry entry.
+fn stored_key(
+    config_dir: &Path,
+    registry: &Registry,
+    device: &str,
+) -> anyhow::Result<Option<String>> {
+    match keys::load(device) {
+        Ok(Some(key)) => return Ok(Some(key)),
+        Ok(None) => {}
+        Err(e) => eprintln!("Couldn't read the keychain: {e:#}"),
+    }
+    keys::find(config_dir, registry, device)
+}
+
+fn edit_key(device: &str, key: Option<&str>, remove: bool) -> ExitCode {
+    let result = match (key, remove) {
+        (Some(key), _) => keys::store(device, key).map(|()| format!("Stored {device}'s key")),
+        (None, true) => keys::delete(device).map(|()| format!("Removed {device}'s key")),
+        (None, false) => keys::load(device).map(|key| match key {
+            Some(_) => format!("{device} has a key in the keychain"),
+            None => format!("No key stored for {device}"),
+        }),
+    };
+    match result {
+        Ok(message) => {
+            println!("{message}");
+            ExitCode::SUCCESS
+        }
+        Err(e) => fail(format!("{e:#}")),
+    }
+}
+
+fn import_keys(config_dir: &Path, registry: &Registry, devices: &[String]) -> ExitCode {
+    let mut failed = 0;
+    for device in devices {
+        let outcome = keys::find(config_dir, registry, device).and_then(|key| match key {
+            Some(key) => keys::store(device, &key).map(|()| "imported"),
+            None => Ok("no key in its config"),
+        });
+        match outcome {
+            Ok(what) => println!("{device:<24} {what}"),
+            Err(e) => {
+                failed += 1;
+                println!("{device:<24} failed: {e:#}");
+            }
+        }
+    }
+    if failed > 0 {
+        return fail(format!("{failed} key(s) not imported"));
+    }
+    ExitCode::SUCCESS
+}
+
+fn print_sizes(data_dir: &Path, device: Option<&str>) -> ExitCode {
+    let history = match SizeHistory::load(&SizeHistory::path(data_dir)) {
+        Ok(history) => history,
+        Err(e) => return fail(format!("{e:#}")),
+    };
+    let Some(device) = device else {
+        let mut any = false;
+        for (device, sample) in history.latest() {
+            any = true;
+            let mut line = format!("{device:<24} {}", size_summary(sample));
+            if !sample.near_limit().is_empty() {
+                line.push_str("  close to its limit");
+            }
+            println!("{line}");
+        }
+        if !any {
+            println!("No builds recorded yet; batch compiles and uploads record them");
+        }
+        return ExitCode::SUCCESS;
+    };
+    let samples = history.samples(device);
+    if samples.is_empty() {
+        println!("No builds of {device} recorded yet");
+    }
+    for sample in samples {
+        let esphome = sample.esphome.as_deref().unwrap_or("?");
+        let bar = |region: &str, usage: Option<Usage>| {
+            usage.map_or(String::new(), |u| {
+                format!("  {region} {} {:>3}%", size_bar(u.percent()), u.percent())
+            })
+        };
+        println!(
+            "{}  {esphome:<10}{}{}",
+            sample.time,
+            bar("flash", sample.flash),
+            bar("RAM", sample.ram)
+        );
+    }
+    ExitCode::SUCCESS
+}
+
+fn show_partitions(
+    config_dir: &Path,
+    data_dir: &Path,
+    devices: &[String],
+    device: Option<String>,
+    write: Option<String>,
+) -> ExitCode {
+    if let (Some(device), Some(table)) = (&device, &write) {
+        return match partitions::write_table(config_dir, table) {
+            Ok(path) => {
+                let file = path.file_name().unwrap_or_default().to_string_lossy();
+                println!("Wrote {}", path.display());
+                println!("Use it in {device}.yaml:\n\nesp32:\n  partitions: {file}\n");
+                println!("Flash the device over USB once: OTA can't change a partition table.");
+                ExitCode::SUCCESS
+            }
+            Err(e) => fail(format!("{e:#}")),
+        };
+    }
+    // The app slot is the "from" of the last build's flash summary.
+    let sizes = SizeHistory::load(&SizeHistory::path(data_dir)).unwrap_or_default();
+    let selected = match &device {
+        Some(device) => std::slice::from_ref(device),
+        None => devices,
+    };
+    for name in selected {
+        let Some(yaml) = crate::devices::config_path(config_dir, name)
+            .and_then(|path| std::fs::read_to_string(path).ok())
+        else {
+            continue;
+        };
+        let mut line = format!("{name:<24} {}", Scheme::scan(&yaml).describe());
+        if let Some(flash) = sizes.samples(name).last().and_then(|s| s.flash) {
+            line += &format!(
+                "; app slot {} bytes, {}% used",
+                flash.total,
+                flash.percent()
+            );
+        }
+        println!("{line}");
+    }
+    if device.is_some() {
+        println!("\nStandard tables for a 4MB ESP32 that outgrew ESPHome's layout:");
+        for (name, trade) in partitions::standard_tables() {
+            println!("  {name:<12} {trade}");
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+/// `flash 93% (1706557 of 1835008 bytes)  RAM 11%  (2024.10.0, 2024-...)`.
+fn size_summary(sample: &Sample) -> String {
+    let mut parts = Vec::new();
+    if let Some(flash) = sample.flash {
+        parts.push(format!(
+            "flash {}% ({} of {} bytes)",
+            flash.percent(),
+            flash.used,
+            flash.total
+        ));
+    }
+    if let Some(ram) = sample.ram {
+        parts.push(format!("RAM {}%", ram.percent()));
+    }
+    let esphome = sample.esphome.as_deref().unwrap_or("unknown ESPHome");
+    parts.push(format!("({esphome}, {})", sample.time));
+    parts.join("  ")
+}
+
+/// A 20-column bar: `[##########..........]` for 50%.
+fn size_bar(percent: u64) -> String {
+    let filled = (percent.min(100) / 5) as usize;
+    format!("[{}{}]", "#".repeat(filled), ".".repeat(20 - filled))
+}
+
+fn print_latency(data_dir: &Path, device: Option<&str>) -> ExitCode {
+    let history = match LatencyHistory::load(&LatencyHistory::path(data_dir)) {
+        Ok(history) => history,
+        Err(e) => return fail(format!("{e:#}")),
+    };
+    let ms = |ms: Option<u32>| ms.map_or("-".to_string(), |ms| format!("{ms} ms"));
+    let Some(device) = device else {
+        let mut summaries: Vec<(&str, Summary)> = history
+            .devices()
+            .map(|(device, rounds)| (device, summarize(rounds)))
+            .collect();
+        summaries.sort_by_key(|(_, s)| (std::cmp::Reverse(s.loss), std::cmp::Reverse(s.avg_ms)));
+        if summaries.is_empty() {
+            println!("Nothing recorded yet; set monitor_latency to true in settings.json");
+        }
+        for (device, s) in summaries {
+            println!(
+                "{device:<24} loss {:>3}%  avg {:>7}  worst {:>7}  {} of {} rounds unanswered",
+                s.loss,
+                ms(s.avg_ms),
+                ms(s.max_ms),
+                s.outages,
+                s.rounds
+            );
+        }
+        return ExitCode::SUCCESS;
+    };
+    let rounds = history.rounds(device);
+    if rounds.is_empty() {
+        println!("Nothing recorded for {device} yet");
+    }
+    for round in rounds {
+        // 10 ms a column, so the bar fills at 200 ms.
+        let bar = round
+            .avg_ms
+            .map_or(format!("{:<22}", "[unanswered]"), |avg| {
+                size_bar(u64::from(avg) / 2)
+            });
+        let loss = match round.loss() {
+            0 => String::new(),
+            loss => format!("  loss {loss}%"),
+        };
+        println!("{}  {bar} {:>7}{loss}", round.time, ms(round.avg_ms));
+    }
+    ExitCode::SUCCESS
+}
+
+/// Scan the Wi-Fi networks in range for fallback hotspots and say how to
+/// bring each device back.
+fn print_hotspots(config_dir: &Path) -> ExitCode {
+    let visible = match crate::platform::visible_ssids() {
+        Ok(visible) => visible,
+        Err(e) => return fail(format!("couldn't list Wi-Fi networks: {e:#}")),
+    };
+    let found = hotspots::find(&visible, config_dir);
+    if found.is_empty() {
+        println!(
+            "No fallback hotspots among the {} Wi-Fi networks in range.",
+            visible.len()
+        );
+    }
+    for hotspot in found {
+        println!(
+            "{}
+",
+            hotspot.guide()
+        );
+    }
+    ExitCode::SUCCESS
+}
+
+/// One line per device: `name  [tag, tag]  note`.
+fn describe(device: &str, store: &NotesStore) -> String {
+    let notes = store.get(device);
+    let mut line = device.to_string();
+    if !notes.tags.is_empty() {
+        let tags: Vec<&str> = notes.tags.iter().map(String::as_str).collect();
+        line.push_str(&format!("  [{}]", tags.join(", ")));
+    }
+    if let Some(note) = &notes.note {
+        line.push_str(&format!("  {note}"));
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn size_bars_fill_by_percent() {
+        assert_eq!(size_bar(50), "[##########..........]");
+        assert_eq!(size_bar(120), "[####################]");
+    }
+
+    #[test]
+    fn describe_shows_tags_then_note() {
+        let mut store = NotesStore::default();
+        assert_eq!(describe("kitchen", &store), "kitchen");
+        store.add_tags("kitchen", ["rev-b", "attic"]);
+        store.set_note("kitchen", "Above the fridge");
+        assert_eq!(
+            describe("kitchen", &store),
+            "kitchen  [attic, rev-b]  Above the fridge"
+        );
+    }
+
+    #[test]
+    fn batch_targets_merge_names_and_group_members_once() {
+        let mut store = NotesStore::default();
+        store.add_to_group("outdoor", ["porch", "shed"]);
+        let target = BatchTarget {
+            devices: vec!["shed".into(), "kitchen".into()],
+            group: Some("outdoor".into()),
+        };
+        assert_eq!(
+            batch_devices(&target, &store).unwrap(),
+            vec!["shed", "kitchen", "porch"]
+        );
+        let missing = BatchTarget {
+            devices: vec![],
+            group: Some("attic".into()),
+        };
+        assert!(batch_devices(&missing, &store).is_err());
+    }
+
+    #[test]
+    fn sources_are_marked_by_the_allow_list() {
+        let found = vec![
+            "github.com/esphome/esphome".to_string(),
+            "github.com/someone/sensors".to_string(),
+        ];
+        assert_eq!(
+            describe_sources(&found, &AllowList::default()),
+            [
+                "github.com/esphome/esphome  allowed",
+                "github.com/someone/sensors  NOT ALLOWED"
+            ]
+        );
+    }
+}
@@ -0,0 +1,42 @@
+//! The `email-password` subcommand: store or remove the SMTP password the
+//! email digest logs in with.
+//!
+//! The keychain is the OS's, so this works whether or not the app is running;
+//! the app reads the password each time it sends.
+
+use std::io::{BufRead, Write};
+use std::process::ExitCode;
+
+use super::fail;
+use crate::email;
+
+pub(super) fn run(username: &str, remove: bool) -> ExitCode {
+    if remove {
+        return match email::delete_password(username) {
+            Ok(()) => {
+                println!("Removed the SMTP password for {username}");
+                ExitCode::SUCCESS
+            }
+            Err(e) => fail(format!("{e:#}")),
+        };
+    }
+    // Read from standard input rather than the command line, so the password
+    // stays out of the shell history and the process list.
+    eprint!("SMTP password for {username}: ");
+    let _ = std::io::stderr().flush();
+    let mut password = String::new();
+    if let Err(e) = std::io::stdin().lock().read_line(&mut password) {
+        return fail(format!("could not read the password: {e}"));
+    }
+    let password = password.trim_end_matches(['\r', '\n']);
+    if password.is_empty() {
+        return fail("no password given");
+    }
+    match email::store_password(username, password) {
+        Ok(()) => {
+            println!("Stored the SMTP password for {username}");
+            ExitCode::SUCCESS
+        }
+        Err(e) => fail(format!("{e:#}")),
+    }
+}
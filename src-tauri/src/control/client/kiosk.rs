@@ -0,0 +1,193 @@
+//! Kiosk mode on the command line. The subcommands that go through the
+//! control channel are refused by the app; the ones that change things
+//! without it (the data files, the keychain, the config folder) are refused
+//! here, before they touch anything.
+
+use crate::{CliCommand, DeviceAction};
+
+/// Whether `command` changes something without asking the app.
+pub(super) fn changes_offline(command: &CliCommand) -> bool {
+    match command {
+        CliCommand::Reset { .. }
+        | CliCommand::UninstallData { .. }
+        | CliCommand::EmailPassword { .. }
+        | CliCommand::ProxyPassword { .. }
+        | CliCommand::SkipUpdate { .. } => true,
+        CliCommand::Device {
+            action: Some(action),
+        } => device_changes(action),
+        CliCommand::Device { action: None }
+        | CliCommand::Open
+        | CliCommand::Backend { .. }
+        | CliCommand::ReleaseChannel { .. }
+        | CliCommand::Startup { .. }
+        | CliCommand::LanguageServer { .. }
+        | CliCommand::Compare { .. }
+        | CliCommand::Update
+        | CliCommand::Logs { .. }
+        | CliCommand::Audit
+        | CliCommand::Restart
+        | CliCommand::CancelBuild
+        | CliCommand::Quit
+        | CliCommand::Status { .. }
+        | CliCommand::Api(_) => false,
+    }
+}
+
+/// Whether the `device` subcommand `action` changes something without asking
+/// the app. The batch actions, diffs and recovery run in it.
+fn device_changes(action: &DeviceAction) -> bool {
+    match action {
+        DeviceAction::Note { .. }
+        | DeviceAction::Tag { .. }
+        | DeviceAction::Untag { .. }
+        | DeviceAction::New { .. }
+        | DeviceAction::Group { .. }
+        | DeviceAction::Ungroup { .. }
+        | DeviceAction::Add { .. }
+        | DeviceAction::Forget { .. }
+        | DeviceAction::Archive { .. }
+        | DeviceAction::Restore { .. }
+        | DeviceAction::Undo
+        | DeviceAction::ImportKeys { .. }
+        | DeviceAction::Allow { .. }
+        | DeviceAction::Disallow { .. } => true,
+        DeviceAction::Partitions { write, .. } => write.is_some(),
+        DeviceAction::Key { key, remove, .. } => key.is_some() || *remove,
+        DeviceAction::BlockUnlisted { state } => state.is_some(),
+        DeviceAction::List
+        | DeviceAction::Edit { .. }
+        | DeviceAction::Editors
+        | DeviceAction::Groups
+        | DeviceAction::Sizes { .. }
+        | DeviceAction::Inventory { .. }
+        | DeviceAction::Diff { .. }
+        | DeviceAction::Recover { .. }
+        | DeviceAction::Watch { .. }
+        | DeviceAction::Latency { .. }
+        | DeviceAction::Hotspots
+        | DeviceAction::Ota
+        | DeviceAction::Archived
+        | DeviceAction::Validate(_)
+        | DeviceAction::Compile(_)
+        | DeviceAction::Upload { .. }
+        | DeviceAction::Sources => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{OnOff, UpdateComponentArg};
+
+    fn device(action: DeviceAction) -> bool {
+        changes_offline(&CliCommand::Device {
+            action: Some(action),
+        })
+    }
+
+    #[test]
+    fn reset_and_uninstall_data_are_refused() {
+        assert!(changes_offline(&CliCommand::Reset {
+            include_config: false,
+            yes: true,
+        }));
+        assert!(changes_offline(&CliCommand::UninstallData {
+            include_config: false,
+            include_toolchains: false,
+            yes: true,
+        }));
+    }
+
+    #[test]
+    fn stored_passwords_and_skipped_updates_are_refused() {
+        assert!(changes_offline(&CliCommand::EmailPassword {
+            username: "me".into(),
+            remove: false,
+        }));
+        assert!(changes_offline(&CliCommand::ProxyPassword {
+            username: "me".into(),
+            remove: true,
+        }));
+        assert!(changes_offline(&CliCommand::SkipUpdate {
+            component: UpdateComponentArg::Esphome,
+            version: None,
+            clear: false,
+        }));
+    }
+
+    #[test]
+    fn archive_restore_and_undo_are_refused() {
+        assert!(device(DeviceAction::Archive {
+            device: "porch".into()
+        }));
+        assert!(device(DeviceAction::Restore {
+            device: "porch".into()
+        }));
+        assert!(device(DeviceAction::Undo));
+        assert!(!device(DeviceAction::Archived));
+    }
+
+    #[test]
+    fn notes_tags_groups_and_the_registry_are_refused() {
+        assert!(device(DeviceAction::Note {
+            device: "porch".into(),
+            text: vec!["attic".into()],
+        }));
+        assert!(device(DeviceAction::Tag {
+            device: "porch".into(),
+            tags: vec!["attic".into()],
+        }));
+        assert!(device(DeviceAction::Group {
+            group: "outdoor".into(),
+            devices: vec!["porch".into()],
+        }));
+        assert!(device(DeviceAction::Add {
+            device: "porch".into(),
+            address: "192.168.1.40".into(),
+            key_secret: None,
+        }));
+        assert!(device(DeviceAction::Forget {
+            device: "porch".into()
+        }));
+        assert!(!device(DeviceAction::List));
+        assert!(!changes_offline(&CliCommand::Device { action: None }));
+    }
+
+    #[test]
+    fn only_the_writing_forms_of_show_or_set_actions_are_refused() {
+        assert!(device(DeviceAction::Partitions {
+            device: Some("porch".into()),
+            write: Some("huge_app".into()),
+        }));
+        assert!(!device(DeviceAction::Partitions {
+            device: Some("porch".into()),
+            write: None,
+        }));
+        assert!(device(DeviceAction::Key {
+            device: "porch".into(),
+            key: None,
+            remove: true,
+        }));
+        assert!(!device(DeviceAction::Key {
+            device: "porch".into(),
+            key: None,
+            remove: false,
+        }));
+        assert!(device(DeviceAction::BlockUnlisted {
+            state: Some(OnOff::On)
+        }));
+        assert!(!device(DeviceAction::BlockUnlisted { state: None }));
+    }
+
+    #[test]
+    fn the_channels_subcommands_are_left_to_the_app() {
+        assert!(!changes_offline(&CliCommand::Update));
+        assert!(!changes_offline(&CliCommand::Quit));
+        assert!(!device(DeviceAction::Diff {
+            device: "porch".into(),
+            from: "2024.6.4".into(),
+            to: "2024.10.0".into(),
+        }));
+    }
+}
@@ -3,12 +3,13 @@
 //! This never touches the control channel — the log paths are deterministic
 //! from the bundle identifier, so it works even when the app is not running.
 
-use std::io::{Read, Seek, SeekFrom, Write};
+use std::io::{IsTerminal, Read, Seek, SeekFrom, Write};
 use std::path::Path;
 use std::process::ExitCode;
 use std::time::Duration;
 
 use super::fail;
+use crate::settings::{LogLevel, LogPalette};
 
 /// Lines shown by the default (non-follow) `logs` tail.
 const TAIL_LINES: usize = 50;
@@ -16,7 +17,7 @@ const TAIL_LINES: usize = 50;
 const TAIL_WINDOW_BYTES: u64 = 64 * 1024;
 
 pub(super) fn run(follow: bool, open_dir: bool) -> ExitCode {
-    let Some(logs_dir) = crate::platform::data_dir_no_handle().map(|d| d.join("logs")) else {
+    let Some(logs_dir) = crate::platform::logs_dir_no_handle() else {
         return fail("could not resolve the logs directory");
     };
     if open_dir {
@@ -30,9 +31,10 @@ pub(super) fn run(follow: bool, open_dir: bool) -> ExitCode {
     }
 
     let log_path = logs_dir.join(crate::daemon::DASHBOARD_LOG_NAME);
+    let palette = effective_palette();
     println!("Dashboard log: {}", log_path.display());
     println!();
-    let pos = match print_tail(&log_path) {
+    let pos = match print_tail(&log_path, palette) {
         Ok(pos) => pos,
         Err(e) => {
             if !follow {
@@ -45,12 +47,54 @@ pub(super) fn run(follow: bool, open_dir: bool) -> ExitCode {
     if !follow {
         return ExitCode::SUCCESS;
     }
-    follow_log(&log_path, pos)
+    follow_log(&log_path, pos, palette)
+}
+
+/// The configured palette, or plain text when stdout is not a terminal or
+/// `NO_COLOR` is set (<https://no-color.org>) so pipes and scripts never see
+/// escape codes.
+fn effective_palette() -> LogPalette {
+    if !std::io::stdout().is_terminal()
+        || std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty())
+    {
+        return LogPalette::None;
+    }
+    crate::platform::settings_path_no_handle()
+        .and_then(|path| crate::settings::peek_settings_file(&path))
+        .map_or(LogPalette::default(), |s| s.log_palette)
+}
+
+/// SGR parameters for a level in a palette; `None` leaves the line unstyled.
+fn level_style(palette: LogPalette, level: LogLevel) -> Option<&'static str> {
+    match (palette, level) {
+        (LogPalette::None, _) => None,
+        (LogPalette::Standard, LogLevel::Error) => Some("31"),
+        (LogPalette::Standard, LogLevel::Warning) => Some("33"),
+        (LogPalette::Standard, LogLevel::Info) => Some("32"),
+        (LogPalette::Standard, LogLevel::Debug) => Some("2"),
+        // Okabe-Ito vermillion, yellow and blue, as 256-colour approximations.
+        (LogPalette::Colorblind, LogLevel::Error) => Some("1;38;5;202"),
+        (LogPalette::Colorblind, LogLevel::Warning) => Some("38;5;214"),
+        (LogPalette::Colorblind, LogLevel::Info) => Some("38;5;32"),
+        (LogPalette::Colorblind, LogLevel::Debug) => Some("38;5;246"),
+        (LogPalette::HighContrast, LogLevel::Error) => Some("1;97;41"),
+        (LogPalette::HighContrast, LogLevel::Warning) => Some("1;30;103"),
+        (LogPalette::HighContrast, LogLevel::Info) => Some("1;97"),
+        (LogPalette::HighContrast, LogLevel::Debug) => None,
+    }
+}
+
+/// Print one line, coloured for its level.
+fn print_line(line: &str, palette: LogPalette) {
+    match LogLevel::of(line).and_then(|level| level_style(palette, level)) {
+        Some(sgr) => println!("\x1b[{sgr}m{line}\x1b[0m"),
+        None => println!("{line}"),
+    }
 }
 
 /// Print the last [`TAIL_LINES`] lines of the file and return the offset the
 /// follow loop should continue from (the end of the file at read time).
-fn print_tail(path: &Path) -> std::io::Result<u64> {
+fn print_tail(path: &Path, palette: LogPalette) -> std::io::Result<u64> {
     let mut file = std::fs::File::open(path)?;
     let len = file.metadata()?.len();
     let start = len.saturating_sub(TAIL_WINDOW_BYTES);
@@ -59,7 +103,7 @@ fn print_tail(path: &Path) -> std::io::Result<u64> {
     file.read_to_end(&mut buf)?;
     let text = String::from_utf8_lossy(&buf);
     for line in tail_lines(&text, TAIL_LINES, start > 0) {
-        println!("{line}");
+        print_line(line, palette);
     }
     // Continue from what was actually printed, not the pre-read length —
     // bytes appended during the read would otherwise print twice.
@@ -82,7 +126,11 @@ fn tail_lines(text: &str, n: usize, truncated: bool) -> Vec<&str> {
 /// platform exposes one — a shrunk length alone misses the case where the
 /// fresh file outgrows the old offset within one poll, which would silently
 /// skip its head — with the length check as the fallback.
-fn follow_log(path: &Path, mut pos: u64) -> ExitCode {
+///
+/// With a palette, output is line-buffered so a line split across two polls is
+/// still coloured as a whole; plain output streams through as it arrives.
+fn follow_log(path: &Path, mut pos: u64, palette: LogPalette) -> ExitCode {
+    let mut partial = String::new();
     let mut identity = std::fs::metadata(path)
         .ok()
         .as_ref()
@@ -97,6 +145,9 @@ fn follow_log(path: &Path, mut pos: u64) -> ExitCode {
         let current = file_identity(&meta);
         if (current.is_some() && current != identity) || meta.len() < pos {
             if pos > 0 {
+                if !partial.is_empty() {
+                    print_line(&std::mem::take(&mut partial), palette);
+                }
                 println!("--- log rotated ---");
             }
             pos = 0;
@@ -116,7 +167,16 @@ fn follow_log(path: &Path, mut pos: u64) -> ExitCode {
             continue;
         }
         pos += buf.len() as u64;
-        print!("{}", String::from_utf8_lossy(&buf));
+        if palette == LogPalette::None {
+            print!("{}", String::from_utf8_lossy(&buf));
+        } else {
+            partial.push_str(&String::from_utf8_lossy(&buf));
+            while let Some(end) = partial.find('\n') {
+                let rest = partial.split_off(end + 1);
+                print_line(partial.trim_end_matches(['\r', '\n']), palette);
+                partial = rest;
+            }
+        }
         let _ = std::io::stdout().flush();
     }
 }
@@ -151,4 +211,17 @@ mod tests {
         let text = "tial line\nb\nc\n";
         assert_eq!(tail_lines(text, 10, true), vec!["b", "c"]);
     }
+
+    #[test]
+    fn no_palette_styles_nothing() {
+        for level in [
+            LogLevel::Debug,
+            LogLevel::Info,
+            LogLevel::Warning,
+            LogLevel::Error,
+        ] {
+            assert_eq!(level_style(LogPalette::None, level), None);
+            assert!(level_style(LogPalette::Colorblind, level).is_some());
+        }
+    }
 }
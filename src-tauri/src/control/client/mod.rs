@@ -10,12 +10,23 @@ use std::io::{BufRead, BufReader, Write};
 use std::process::ExitCode;
 use std::time::Duration;
 
-use super::protocol::{
-    self, backend_name, channel_name, ErrCode, Reply, Request, StatusReply, STEP_APP_RESTARTING,
-};
-use crate::{ApiMethod, CliCommand, OnOff};
-
+use super::protocol::{self, ErrCode, Reply, Request, StatusReply, STEP_APP_RESTARTING};
+use crate::cleanup::Purpose;
+use crate::settings::{Policy, KIOSK_REFUSAL};
+use crate::{ApiMethod, CliCommand, CompareAction, OnOff};
+
+mod audit;
+mod auth;
+mod cleanup;
+mod devices;
+mod email;
+mod kiosk;
 mod logs;
+mod ota;
+mod proxy;
+mod skip;
+mod status;
+mod watch;
 
 /// The operation succeeded.
 const EXIT_SUCCESS: u8 = 0;
@@ -28,17 +39,24 @@ const EXIT_BUSY: u8 = 4;
 
 /// Read timeout for quick requests.
 const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
-/// `restart` drains the child for up to 30s and waits up to 60s for readiness.
+/// `restart` drains the child for its grace period (30s by default) and waits
+/// up to 60s for readiness.
 const RESTART_TIMEOUT: Duration = Duration::from_secs(180);
 /// Switches and updates download and pip-install packages.
 const UPDATE_TIMEOUT: Duration = Duration::from_secs(600);
 /// `check-update` hits GitHub and PyPI and spawns Python for the installed
 /// versions; more headroom than a local request, far less than an install.
 const CHECK_TIMEOUT: Duration = Duration::from_secs(120);
+/// A batch reports once per device, and a single cold compile of a large
+/// config can take this long.
+const BATCH_TIMEOUT: Duration = Duration::from_secs(1800);
 
 /// Entry point for all subcommands; returns the process exit code.
 /// (On Windows, `main` has already attached the parent console.)
 pub(crate) fn run(command: CliCommand) -> ExitCode {
+    if kiosk::changes_offline(&command) && Policy::load().kiosk_mode {
+        return fail(KIOSK_REFUSAL);
+    }
     match command {
         CliCommand::Open => open_cmd(),
         CliCommand::Backend { channel } => match channel {
@@ -68,11 +86,47 @@ pub(crate) fn run(command: CliCommand) -> ExitCode {
                 DEFAULT_TIMEOUT,
             ),
         },
+        CliCommand::LanguageServer { state, port } => match state {
+            None => simple(Request::GetLanguageServer, DEFAULT_TIMEOUT),
+            Some(state) => simple(
+                Request::SetLanguageServer {
+                    enable: matches!(state, OnOff::On),
+                    port,
+                },
+                DEFAULT_TIMEOUT,
+            ),
+        },
+        CliCommand::Compare { action } => match action {
+            None => simple(Request::GetComparison, DEFAULT_TIMEOUT),
+            Some(CompareAction::Start { version, port }) => {
+                simple(Request::StartComparison { version, port }, UPDATE_TIMEOUT)
+            }
+            Some(CompareAction::Stop) => simple(Request::StopComparison, DEFAULT_TIMEOUT),
+        },
         CliCommand::Update => simple(Request::Update, UPDATE_TIMEOUT),
         CliCommand::Logs { follow, open } => logs::run(follow, open),
+        CliCommand::Audit => audit::run(),
+        CliCommand::EmailPassword { username, remove } => email::run(&username, remove),
+        CliCommand::ProxyPassword { username, remove } => proxy::run(&username, remove),
+        CliCommand::SkipUpdate {
+            component,
+            version,
+            clear,
+        } => skip::run(component, version.as_deref(), clear),
+        CliCommand::Device { action } => devices::run(action),
         CliCommand::Restart => simple(Request::Restart, RESTART_TIMEOUT),
+        CliCommand::CancelBuild => simple(Request::CancelBuild, DEFAULT_TIMEOUT),
         CliCommand::Quit => simple(Request::Quit, DEFAULT_TIMEOUT),
-        CliCommand::Status { json } => status_cmd(json),
+        CliCommand::Reset {
+            include_config,
+            yes,
+        } => cleanup::run(Purpose::Reset, include_config, false, yes),
+        CliCommand::UninstallData {
+            include_config,
+            include_toolchains,
+            yes,
+        } => cleanup::run(Purpose::Uninstall, include_config, include_toolchains, yes),
+        CliCommand::Status { json } => status::run(json),
         CliCommand::Api(method) => api(method),
     }
 }
@@ -101,7 +155,7 @@ enum ConnectError {
     ///
     /// Gating was tried and does not work: it leaves `ConnectError`
     /// single-valued on Windows, so the `Err(ConnectError::NotRunning)` arms in
-    /// `open_cmd` and `status_cmd` become exhaustive and the catch-all
+    /// `open_cmd` and `status::run` become exhaustive and the catch-all
     /// `Err(e) => connect_failed(e)` after each one is an unreachable-pattern
     /// error. Those two arms never name `BadPath`, so they don't turn up when
     /// you grep for it. A uniform enum shape keeps every match identical on
@@ -145,7 +199,7 @@ fn report(outcome: Outcome) -> ExitCode {
         }
         Outcome::Status(reply) => {
             // Only `status` requests expect this reply; print it sanely anyway.
-            print_status(&reply);
+            status::print(&reply);
             ExitCode::SUCCESS
         }
     }
@@ -266,7 +320,7 @@ fn read_replies<R: BufRead>(mut reader: R) -> Outcome {
                 println!("  {detail}");
             }
             Ok(Reply::Ok { message }) => return Outcome::Ok(message),
-            Ok(Reply::Err { message, code }) => {
+            Ok(Reply::Err { message, code, .. }) => {
                 return match code {
                     ErrCode::Busy => Outcome::Busy(message),
                     ErrCode::Failed => Outcome::Failed(message),
@@ -477,124 +531,6 @@ fn app_launch_command() -> Result<std::process::Command, String> {
     Ok(std::process::Command::new(exe))
 }
 
-/// `status`: rich output from the running app, or a best-effort offline
-/// summary read straight from settings.json when it isn't running.
-fn status_cmd(json: bool) -> ExitCode {
-    match exchange(&Request::Status, DEFAULT_TIMEOUT) {
-        Ok(Outcome::Status(reply)) => {
-            if json {
-                // One stable schema for scripts: both the online and offline
-                // forms carry `app_running`.
-                let mut value = serde_json::to_value(reply.as_ref()).unwrap_or_default();
-                if let Some(object) = value.as_object_mut() {
-                    object.insert("app_running".to_string(), true.into());
-                }
-                println!("{value}");
-            } else {
-                print_status(&reply);
-            }
-            ExitCode::SUCCESS
-        }
-        Ok(other) => report(other),
-        Err(ConnectError::NotRunning) => offline_status(json),
-        Err(e) => connect_failed(e),
-    }
-}
-
-fn print_status(status: &StatusReply) {
-    println!("App:             running ({})", status.app_version);
-    let backend_state = match (status.backend_running, status.backend_healthy) {
-        (true, true) => "running, healthy",
-        (true, false) => "running, not responding",
-        (false, true) => "stopped, but something is answering on the port",
-        (false, false) => "stopped",
-    };
-    println!("Backend:         {backend_state}");
-    println!("Dashboard:       http://localhost:{}", status.port);
-    println!(
-        "ESPHome:         {} ({} channel)",
-        status.esphome_version.as_deref().unwrap_or("unknown"),
-        channel_name(status.release_channel)
-    );
-    println!(
-        "Device builder:  {} ({} channel)",
-        status
-            .device_builder_version
-            .as_deref()
-            .unwrap_or("not installed"),
-        backend_name(status.backend)
-    );
-    println!(
-        "Launch at login: {}",
-        if status.launch_at_startup {
-            "on"
-        } else {
-            "off"
-        }
-    );
-    println!("Config dir:      {}", status.config_dir.display());
-    println!("Logs dir:        {}", status.logs_dir.display());
-}
-
-fn offline_status(json: bool) -> ExitCode {
-    let data_dir = crate::platform::data_dir_no_handle();
-    let settings = data_dir
-        .as_ref()
-        .and_then(|dir| crate::settings::peek_settings_file(&dir.join("settings.json")));
-
-    if json {
-        // Same field names and value formats as the online StatusReply form,
-        // so scripts keep one stable schema whether or not the app is up;
-        // only the fields knowable from settings.json are present.
-        let value = match (&data_dir, &settings) {
-            (Some(data_dir), Some(settings)) => {
-                let config_dir = settings
-                    .config_dir
-                    .clone()
-                    .unwrap_or_else(crate::settings::default_config_dir);
-                serde_json::json!({
-                    "app_running": false,
-                    "port": settings.port,
-                    "release_channel": settings.release_channel,
-                    "backend": settings.backend,
-                    "config_dir": config_dir,
-                    "logs_dir": data_dir.join("logs"),
-                })
-            }
-            _ => serde_json::json!({ "app_running": false }),
-        };
-        println!("{value}");
-        return ExitCode::from(EXIT_NOT_RUNNING);
-    }
-
-    println!("App:             not running");
-    if let (Some(data_dir), Some(settings)) = (data_dir, settings) {
-        println!("Dashboard:       http://localhost:{}", settings.port);
-        println!(
-            "Release channel: {}",
-            channel_name(settings.release_channel)
-        );
-        println!(
-            "Backend:         {} channel",
-            backend_name(settings.backend)
-        );
-        let config_dir = settings
-            .config_dir
-            .unwrap_or_else(crate::settings::default_config_dir);
-        println!("Config dir:      {}", config_dir.display());
-        println!("Logs dir:        {}", data_dir.join("logs").display());
-        let addr = std::net::SocketAddr::from(([127, 0, 0, 1], settings.port));
-        if std::net::TcpStream::connect_timeout(&addr, Duration::from_secs(1)).is_ok() {
-            println!(
-                "Note: something is listening on port {}; if the app was killed, \
-                 its backend may still be running.",
-                settings.port
-            );
-        }
-    }
-    ExitCode::from(EXIT_NOT_RUNNING)
-}
-
 /// `api <method>`: the machine-readable contract the device-builder dashboard
 /// codes against. Emits newline-delimited JSON only — one object per line, on
 /// stdout, valid JSON even for errors — so the human CLI's wording stays free
@@ -900,13 +836,24 @@ mod tests {
             backend_running: true,
             backend_healthy: true,
             port: 6052,
+            url: None,
             esphome_version: None,
+            pinned_esphome: None,
             device_builder_version: None,
             release_channel: ReleaseChannel::Stable,
             backend: Backend::BuilderBeta,
             launch_at_startup: false,
             config_dir: PathBuf::from("/tmp/esphome"),
             logs_dir: PathBuf::from("/tmp/logs"),
+            timezone: None,
+            locale: None,
+            uptime_secs: None,
+            restarts: 0,
+            last_crash: None,
+            restart_history: Vec::new(),
+            last_startup: None,
+            slow_start: None,
+            last_health_check: None,
         })))
         .unwrap();
         let outcome = outcome_for(&format!("{raw}\n"));
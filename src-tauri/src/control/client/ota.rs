@@ -0,0 +1,62 @@
+//! The `device ota` subcommand: how often batch uploads failed, per device
+//! and per subnet and tag, worst first, to tell a bad spot in the network
+//! from a bad device (see [`crate::devices::ota`]).
+//!
+//! It reads the histories in the data directory, so like `device latency`
+//! it works whether or not the app is running.
+
+use std::path::Path;
+use std::process::ExitCode;
+
+use super::fail;
+use crate::devices::discovery::LatencyHistory;
+use crate::devices::ota::{self, OtaHistory, Rate};
+use crate::devices::NotesStore;
+use crate::util::ConfigStore;
+
+pub(super) fn run(data_dir: &Path, store: &NotesStore) -> ExitCode {
+    let loaded = OtaHistory::load(&OtaHistory::path(data_dir)).and_then(|ota| {
+        let latency = LatencyHistory::load(&LatencyHistory::path(data_dir))?;
+        Ok((ota, latency))
+    });
+    let (ota, latency) = match loaded {
+        Ok(loaded) => loaded,
+        Err(e) => return fail(format!("{e:#}")),
+    };
+    let report = ota::report(&ota, &latency, store);
+    if report.devices.is_empty() {
+        println!("No uploads recorded yet (only batch uploads, `device upload`, are)");
+        return ExitCode::SUCCESS;
+    }
+    print_rates("Devices", &report.devices, false);
+    print_rates("Subnets", &report.subnets, true);
+    print_rates("Tags", &report.tags, true);
+    if latency.devices().next().is_none() {
+        println!("\nSet monitor_latency to true in settings.json to see probe loss alongside.");
+    }
+    ExitCode::SUCCESS
+}
+
+fn print_rates(title: &str, rates: &[Rate], groups: bool) {
+    if rates.is_empty() {
+        return;
+    }
+    println!("{title}:");
+    for rate in rates {
+        let devices = if groups {
+            format!("  {} devices", rate.devices)
+        } else {
+            String::new()
+        };
+        let loss = rate
+            .loss
+            .map_or(String::new(), |loss| format!("  probe loss {loss:>3}%"));
+        println!(
+            "  {:<24} {:>3}% failed ({} of {} uploads){devices}{loss}",
+            rate.label,
+            rate.failure_percent(),
+            rate.failures,
+            rate.attempts
+        );
+    }
+}
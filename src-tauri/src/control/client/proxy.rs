@@ -0,0 +1,41 @@
+//! The `proxy-password` subcommand: store or remove the password the app
+//! logs in to the HTTP proxy with.
+//!
+//! Like `email-password`, this works whether or not the app is running; the
+//! app reads the password when it starts.
+
+use std::io::{BufRead, Write};
+use std::process::ExitCode;
+
+use super::fail;
+use crate::network;
+
+pub(super) fn run(username: &str, remove: bool) -> ExitCode {
+    if remove {
+        return match network::delete_password(username) {
+            Ok(()) => {
+                println!("Removed the proxy password for {username}");
+                ExitCode::SUCCESS
+            }
+            Err(e) => fail(format!("{e:#}")),
+        };
+    }
+    // From standard input, out of the shell history and the process list.
+    eprint!("Proxy password for {username}: ");
+    let _ = std::io::stderr().flush();
+    let mut password = String::new();
+    if let Err(e) = std::io::stdin().lock().read_line(&mut password) {
+        return fail(format!("could not read the password: {e}"));
+    }
+    let password = password.trim_end_matches(['\r', '\n']);
+    if password.is_empty() {
+        return fail("no password given");
+    }
+    match network::store_password(username, password) {
+        Ok(()) => {
+            println!("Stored the proxy password for {username}; restart the app to use it");
+            ExitCode::SUCCESS
+        }
+        Err(e) => fail(format!("{e:#}")),
+    }
+}
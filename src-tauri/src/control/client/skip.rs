@@ -0,0 +1,41 @@
+//! The `skip-update` subcommand: skip an update the daily check notified
+//! about, as "Skip This Version" does in the tray's update dialog, for a
+//! machine without a tray to show that dialog.
+//!
+//! Like `audit`, this edits the file directly and works whether or not the
+//! app is running; the next check reads it.
+
+use std::process::ExitCode;
+
+use super::fail;
+use crate::update;
+use crate::UpdateComponentArg;
+
+pub(super) fn run(component: UpdateComponentArg, version: Option<&str>, clear: bool) -> ExitCode {
+    let Some(data_dir) = crate::platform::data_dir_no_handle() else {
+        return fail("could not resolve the data directory");
+    };
+    let (key, name) = match component {
+        UpdateComponentArg::Esphome => (update::ESPHOME_KEY, "ESPHome"),
+        UpdateComponentArg::DeviceBuilder => (update::DEVICE_BUILDER_KEY, "ESPHome Device Builder"),
+        UpdateComponentArg::Desktop => (update::DESKTOP_KEY, "ESPHome Device Builder desktop"),
+    };
+    match update::skip(&data_dir, key, version, clear) {
+        Ok(Some(version)) if clear => {
+            println!("No longer skipping {name} {version}");
+            ExitCode::SUCCESS
+        }
+        Ok(Some(version)) => {
+            println!("Skipping {name} {version}; a newer version is notified about again");
+            ExitCode::SUCCESS
+        }
+        Ok(None) if clear => {
+            println!("No {name} version is skipped");
+            ExitCode::SUCCESS
+        }
+        Ok(None) => fail(format!(
+            "no {name} update has been notified about yet; name the version to skip"
+        )),
+        Err(e) => fail(format!("{e:#}")),
+    }
+}
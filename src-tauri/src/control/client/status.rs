@@ -0,0 +1,203 @@
+//! The `status` subcommand: the running app's status reply, or what
+//! settings.json says when the app isn't running.
+
+use std::process::ExitCode;
+use std::time::Duration;
+
+use super::{
+    connect_failed, exchange, report, ConnectError, Outcome, DEFAULT_TIMEOUT, EXIT_NOT_RUNNING,
+};
+use crate::control::protocol::{backend_name, channel_name, Request, StatusReply};
+
+/// `status`: rich output from the running app, or a best-effort offline
+/// summary read straight from settings.json when it isn't running.
+pub(super) fn run(json: bool) -> ExitCode {
+    match exchange(&Request::Status, DEFAULT_TIMEOUT) {
+        Ok(Outcome::Status(reply)) => {
+            if json {
+                // One stable schema for scripts: both the online and offline
+                // forms carry `app_running`.
+                let mut value = serde_json::to_value(reply.as_ref()).unwrap_or_default();
+                if let Some(object) = value.as_object_mut() {
+                    object.insert("app_running".to_string(), true.into());
+                }
+                println!("{value}");
+            } else {
+                print(&reply);
+            }
+            ExitCode::SUCCESS
+        }
+        Ok(other) => report(other),
+        Err(ConnectError::NotRunning) => offline(json),
+        Err(e) => connect_failed(e),
+    }
+}
+
+pub(super) fn print(status: &StatusReply) {
+    println!("App:             running ({})", status.app_version);
+    let backend_state = match (status.backend_running, status.backend_healthy) {
+        (true, true) => "running, healthy",
+        (true, false) => "running, not responding",
+        (false, true) => "stopped, but something is answering on the port",
+        (false, false) => "stopped",
+    };
+    println!("Backend:         {backend_state}");
+    if let Some(secs) = status.uptime_secs {
+        println!(
+            "Uptime:          {}",
+            crate::daemon::format_uptime(std::time::Duration::from_secs(secs))
+        );
+    }
+    if let Some(check) = &status.last_health_check {
+        println!(
+            "Health check:    {} {} ago",
+            if check.passed { "passed" } else { "failed" },
+            crate::daemon::format_uptime(std::time::Duration::from_secs(check.secs_ago))
+        );
+    }
+    println!("Restarts:        {} this session", status.restarts);
+    for restart in &status.restart_history {
+        println!(
+            "                 #{} at {} ({})",
+            restart.number,
+            restart.time,
+            restart.reason.name()
+        );
+    }
+    if let Some(crash) = &status.last_crash {
+        println!("Last crash:      {crash}");
+    }
+    if let Some(startup) = &status.last_startup {
+        println!("Last startup:    {}", startup.describe());
+    }
+    if let Some(slow) = &status.slow_start {
+        println!("Slow start:      {}", slow.describe());
+    }
+    let url = status
+        .url
+        .clone()
+        .unwrap_or_else(|| format!("http://localhost:{}", status.port));
+    println!("Dashboard:       {url}");
+    match &status.pinned_esphome {
+        Some(pinned) => println!(
+            "ESPHome:         {pinned} (pinned in {})",
+            crate::daemon::PIN_FILE
+        ),
+        None => println!(
+            "ESPHome:         {} ({} channel)",
+            status.esphome_version.as_deref().unwrap_or("unknown"),
+            channel_name(status.release_channel)
+        ),
+    }
+    println!(
+        "Device builder:  {} ({} channel)",
+        status
+            .device_builder_version
+            .as_deref()
+            .unwrap_or("not installed"),
+        backend_name(status.backend)
+    );
+    println!(
+        "Launch at login: {}",
+        if status.launch_at_startup {
+            "on"
+        } else {
+            "off"
+        }
+    );
+    println!("Config dir:      {}", status.config_dir.display());
+    println!("Logs dir:        {}", status.logs_dir.display());
+    println!(
+        "Timezone:        {}",
+        describe_timezone(status.timezone.as_deref(), cfg!(windows))
+    );
+    println!(
+        "Locale:          {}",
+        status.locale.as_deref().unwrap_or("system default")
+    );
+}
+
+/// The backend's timezone as `status` shows it. Python on Windows doesn't
+/// read an IANA name such as `Europe/Berlin` from `TZ`, so there the setting
+/// only reaches a backend in WSL or Docker.
+fn describe_timezone(timezone: Option<&str>, windows: bool) -> String {
+    match timezone {
+        None => "system default".into(),
+        Some(tz) if windows && tz.contains('/') => {
+            format!("{tz} (WSL and Docker backends only; Python on Windows ignores it)")
+        }
+        Some(tz) => tz.into(),
+    }
+}
+
+fn offline(json: bool) -> ExitCode {
+    let logs_dir = crate::platform::logs_dir_no_handle();
+    let settings = crate::platform::settings_path_no_handle()
+        .and_then(|path| crate::settings::peek_settings_file(&path));
+
+    if json {
+        // Same field names and value formats as the online StatusReply form,
+        // so scripts keep one stable schema whether or not the app is up;
+        // only the fields knowable from settings.json are present.
+        let value = match (&logs_dir, &settings) {
+            (Some(logs_dir), Some(settings)) => {
+                let config_dir = settings.workspace.resolved_config_dir();
+                serde_json::json!({
+                    "app_running": false,
+                    "port": settings.port,
+                    "release_channel": settings.release_channel,
+                    "backend": settings.backend,
+                    "config_dir": config_dir,
+                    "logs_dir": logs_dir,
+                })
+            }
+            _ => serde_json::json!({ "app_running": false }),
+        };
+        println!("{value}");
+        return ExitCode::from(EXIT_NOT_RUNNING);
+    }
+
+    println!("App:             not running");
+    if let (Some(logs_dir), Some(settings)) = (logs_dir, settings) {
+        println!(
+            "Dashboard:       {}",
+            crate::daemon::browser_url(settings.dashboard_address())
+        );
+        println!(
+            "Release channel: {}",
+            channel_name(settings.release_channel)
+        );
+        println!(
+            "Backend:         {} channel",
+            backend_name(settings.backend)
+        );
+        let config_dir = settings.workspace.resolved_config_dir();
+        println!("Config dir:      {}", config_dir.display());
+        println!("Logs dir:        {}", logs_dir.display());
+        let addr = std::net::SocketAddr::from(([127, 0, 0, 1], settings.port));
+        if std::net::TcpStream::connect_timeout(&addr, Duration::from_secs(1)).is_ok() {
+            println!(
+                "Note: something is listening on port {}; if the app was killed, \
+                 its backend may still be running.",
+                settings.port
+            );
+        }
+    }
+    ExitCode::from(EXIT_NOT_RUNNING)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn an_iana_timezone_is_flagged_on_windows() {
+        assert_eq!(describe_timezone(None, true), "system default");
+        assert_eq!(
+            describe_timezone(Some("Europe/Berlin"), false),
+            "Europe/Berlin"
+        );
+        assert!(describe_timezone(Some("Europe/Berlin"), true).contains("WSL and Docker"));
+        assert_eq!(describe_timezone(Some("CET-1CEST"), true), "CET-1CEST");
+    }
+}
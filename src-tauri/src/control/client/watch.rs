@@ -0,0 +1,96 @@
+//! The `device watch` subcommand: a device's entities and their live states,
+//! its Bluetooth proxy's connection slots if it is one, and optionally its
+//! logs, straight from its native API.
+//!
+//! Like `logs`, this never touches the control channel: it talks to the
+//! device itself, so it works whether or not the app is running.
+
+use std::process::ExitCode;
+
+use super::fail;
+use crate::devices::api::{Client, Event, LOG_LEVELS};
+
+pub(super) fn run(host: &str, port: u16, password: &str, logs: Option<&str>) -> ExitCode {
+    let level = match logs.map(|name| LOG_LEVELS.iter().position(|l| *l == name)) {
+        None => None,
+        Some(Some(level)) => Some(level as u64),
+        Some(None) => {
+            return fail(format!(
+                "unknown log level {:?} (use {})",
+                logs.unwrap_or_default(),
+                LOG_LEVELS.join(", ")
+            ))
+        }
+    };
+    match watch(host, port, password, level) {
+        Ok(()) => {
+            println!("{host} disconnected");
+            ExitCode::SUCCESS
+        }
+        Err(e) => fail(format!("{e:#}")),
+    }
+}
+
+fn watch(host: &str, port: u16, password: &str, level: Option<u64>) -> anyhow::Result<()> {
+    let mut client = Client::connect(host, port, password)?;
+    let info = client.device_info()?;
+    let title = match info.friendly_name.as_str() {
+        "" => info.name.clone(),
+        friendly => format!("{friendly} ({})", info.name),
+    };
+    println!("{title}: ESPHome {}, {}", info.esphome_version, info.model);
+    println!(
+        "  compiled {}, MAC {}",
+        info.compilation_time, info.mac_address
+    );
+    let entities = client.list_entities()?;
+    println!("  {} entities", entities.len());
+    for entity in &entities {
+        println!("    {:<14} {}", entity.kind, entity.name);
+    }
+    let features = info.proxy_features();
+    if !features.is_empty() {
+        println!("  Bluetooth proxy: {}", features.join(", "));
+        if info.bluetooth_proxy & 2 == 0 {
+            println!(
+                "  (passive only: BLE devices that need a connection, like locks and \
+                 some thermometers, can't reach Home Assistant through it)"
+            );
+        }
+    }
+    client.subscribe_states()?;
+    if !features.is_empty() {
+        client.subscribe_proxy_connections()?;
+    }
+    if let Some(level) = level {
+        client.subscribe_logs(level)?;
+    }
+    println!("\nWatching {host}; Ctrl+C to stop.");
+    while let Some(event) = client.next_event()? {
+        match event {
+            Event::State { key, state } => {
+                let name = client.entity(key).map_or("?", |e| e.name.as_str());
+                println!("{name}: {state}");
+            }
+            Event::Log { level, message } => println!("[{level}] {message}"),
+            Event::ProxyConnections {
+                free,
+                limit,
+                connected,
+            } => {
+                let mut line = format!(
+                    "Bluetooth proxy: {} of {limit} connection slots in use",
+                    limit.saturating_sub(free)
+                );
+                if !connected.is_empty() {
+                    line += &format!(" ({})", connected.join(", "));
+                }
+                if free == 0 && limit > 0 {
+                    line += "; a BLE device needing a connection has to wait";
+                }
+                println!("{line}");
+            }
+        }
+    }
+    Ok(())
+}
@@ -4,22 +4,24 @@
 //! Channel / Switch Backend / Restart Dashboard items and their CLI
 //! equivalents. The tray arms wrap these with confirmation dialogs; the
 //! control server wraps them with streamed progress replies. Keeping the
-//! sequences here means both surfaces stay in lockstep, including the tray
-//! label updates (which are safe no-ops when the app runs without a tray —
-//! exactly the situation the CLI exists for).
+//! sequences here means both surfaces stay in lockstep, including the
+//! [`Event`]s they publish (which nothing shows when the app runs without a
+//! tray — exactly the situation the CLI exists for).
 
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use tauri::AppHandle;
 use tauri_plugin_autostart::ManagerExt;
-use tauri_plugin_updater::UpdaterExt;
 use tracing::{error, info, warn};
 
 use super::update_check::{
     device_builder_update_available, esphome_install_action, esphome_update_available,
     install_action, InstallAction,
 };
+use crate::daemon::DaemonError;
 use crate::settings::ReleaseChannel;
+use crate::store::{Event, RestartReason};
+use crate::update::UpdateError;
 use crate::{tray, AppState};
 
 /// Progress sink for long-running operations: `(step, detail)`. The tray
@@ -96,17 +98,17 @@ pub(crate) enum SwitchOutcome {
     /// reports `true` without probing, matching the previous tray behavior).
     Success { ready: bool },
     /// The dashboard could not be stopped; nothing was installed.
-    StopFailed(String),
+    StopFailed(DaemonError),
     /// The install failed; `restarted` is whether the previous version's
     /// dashboard came back up.
-    InstallFailed { error: String, restarted: bool },
+    InstallFailed { error: UpdateError, restarted: bool },
     /// The install succeeded but the dashboard failed to start afterwards.
-    StartFailed(String),
+    StartFailed(DaemonError),
 }
 
 /// Stop prologue shared by the switch flows: report progress and stop the
-/// daemon (which reflects the stop in the tray status line itself). On
-/// failure run `revert` (restores the tray radio checks) and hand back the
+/// daemon (which publishes the stop itself). On failure run `revert`
+/// (republishes the previous selection) and hand back the
 /// [`SwitchOutcome::StopFailed`] for the caller to return. `stop_what` names
 /// what failed to stop in the log line.
 async fn stop_or_revert(
@@ -120,24 +122,25 @@ async fn stop_or_revert(
     if let Err(e) = state.daemon.stop().await {
         error!("Failed to stop {}: {}", stop_what, e);
         revert();
-        return Err(SwitchOutcome::StopFailed(e.to_string()));
+        return Err(SwitchOutcome::StopFailed(e));
     }
     Ok(())
 }
 
 /// Install-failure epilogue shared by the switch flows: run `revert` to
-/// restore the tray radio checks, then attempt a best-effort restart of the
-/// previous install (`context` feeds the restart-failure log), folding both
-/// into [`SwitchOutcome::InstallFailed`]. Callers log their flow-specific
-/// error line before calling.
+/// republish the previous selection, then attempt a best-effort restart of the
+/// previous install for `reason` (`context` feeds the restart-failure log),
+/// folding both into [`SwitchOutcome::InstallFailed`]. Callers log their
+/// flow-specific error line before calling.
 async fn install_failed(
     state: &Arc<AppState>,
-    error: String,
+    error: UpdateError,
+    reason: RestartReason,
     context: &str,
     revert: impl FnOnce(),
 ) -> SwitchOutcome {
     revert();
-    let restarted = restart_after_failure(state, context).await;
+    let restarted = restart_after_failure(state, reason, context).await;
     SwitchOutcome::InstallFailed { error, restarted }
 }
 
@@ -157,6 +160,115 @@ where
     }
 }
 
+/// Pause notifications for the given span, or resume them with `None`, and
+/// persist a timed pause so it survives a restart.
+pub(crate) async fn set_notifications_paused(
+    app: &AppHandle,
+    state: &Arc<AppState>,
+    pause: Option<crate::notifications::Pause>,
+) {
+    let until = crate::notifications::set_paused(pause);
+    info!(
+        "Notifications {}",
+        if pause.is_some() { "paused" } else { "resumed" }
+    );
+    set_and_save(app, state, |settings| {
+        let changed = settings.notifications.paused_until != until;
+        settings.notifications.paused_until = until;
+        changed
+    })
+    .await;
+    state.store.publish(Event::Notifications(
+        pause != Some(crate::notifications::Pause::UntilResumed),
+    ));
+    state
+        .store
+        .publish(Event::Pause(crate::notifications::pause_state()));
+}
+
+/// Flip one of the plain on/off settings the tray's Preferences offer
+/// (`open_on_start`, `check_updates`) and save it. Returns the new value.
+pub(crate) async fn toggle_setting(
+    app: &AppHandle,
+    state: &Arc<AppState>,
+    field: fn(&mut crate::settings::Settings) -> &mut bool,
+) -> bool {
+    let mut value = false;
+    set_and_save(app, state, |settings| {
+        let field = field(settings);
+        *field = !*field;
+        value = *field;
+        true
+    })
+    .await;
+    value
+}
+
+/// Start or stop the editor language server and save the choice; `port`
+/// replaces `language_server_port` when given. Returns the port it listens
+/// on, `None` when stopped.
+pub(crate) async fn set_language_server(
+    app: &AppHandle,
+    state: &Arc<AppState>,
+    enable: bool,
+    port: Option<u16>,
+) -> anyhow::Result<Option<u16>> {
+    let mut saved_port = 0;
+    set_and_save(app, state, |settings| {
+        let port = port.unwrap_or(settings.language_server_port);
+        let changed = settings.language_server != enable || settings.language_server_port != port;
+        settings.language_server = enable;
+        settings.language_server_port = port;
+        saved_port = port;
+        changed
+    })
+    .await;
+    let result = if enable {
+        state
+            .language_server
+            .start(state, saved_port)
+            .await
+            .map(|()| Some(saved_port))
+    } else {
+        state.language_server.stop();
+        Ok(None)
+    };
+    state.store.publish(Event::LanguageServer(
+        state.language_server.port().is_some(),
+    ));
+    result
+}
+
+/// Gate a destructive action behind OS authentication when `require_auth`
+/// is on; `reason` is shown in the prompt where the platform allows it.
+/// Anything but a successful authentication, including a platform that
+/// can't ask, is a refusal.
+pub(crate) async fn authorize(
+    state: &Arc<AppState>,
+    reason: String,
+    progress: Progress<'_>,
+) -> bool {
+    if !state.settings.read().await.require_auth {
+        return true;
+    }
+    progress("auth", "waiting for OS authentication on this machine");
+    match tokio::task::spawn_blocking(move || crate::platform::authenticate(&reason)).await {
+        Ok(Ok(true)) => true,
+        Ok(Ok(false)) => {
+            info!("OS authentication declined");
+            false
+        }
+        Ok(Err(e)) => {
+            warn!("OS authentication unavailable: {e:#}");
+            false
+        }
+        Err(e) => {
+            error!("OS authentication task failed: {}", e);
+            false
+        }
+    }
+}
+
 /// Switch the ESPHome release channel: stop the dashboard, install the new
 /// channel's version, persist the setting, and restart. Tray radio labels and
 /// the status line are updated (and reverted on failure) along the way.
@@ -173,14 +285,14 @@ pub(crate) async fn switch_release_channel(
     }
 
     // Show the new selection immediately; reverted on failure below.
-    tray::update_channel_checks(new_channel);
+    state.store.publish(Event::Channel(new_channel));
 
     if let Err(outcome) = stop_or_revert(
         state,
         progress,
         "stopping the dashboard",
         "backend for channel switch",
-        || tray::update_channel_checks(old_channel),
+        || state.store.publish(Event::Channel(old_channel)),
     )
     .await
     {
@@ -204,17 +316,21 @@ pub(crate) async fn switch_release_channel(
             refresh_version_display_blocking(app).await;
 
             progress("start", "starting the dashboard");
-            if let Err(e) = state.daemon.start().await {
+            if let Err(e) = state.daemon.start_for(RestartReason::Update).await {
                 error!("Failed to restart backend after channel switch: {}", e);
-                return SwitchOutcome::StartFailed(e.to_string());
+                return SwitchOutcome::StartFailed(e);
             }
             SwitchOutcome::Success { ready: true }
         }
         Err(e) => {
             error!("Channel switch failed: {}", e);
-            install_failed(state, e.to_string(), "failed channel switch", || {
-                tray::update_channel_checks(old_channel)
-            })
+            install_failed(
+                state,
+                e,
+                RestartReason::Update,
+                "failed channel switch",
+                || state.store.publish(Event::Channel(old_channel)),
+            )
             .await
         }
     }
@@ -235,14 +351,14 @@ pub(crate) async fn switch_backend(
         return SwitchOutcome::Unchanged;
     }
 
-    tray::update_backend_checks(new_backend);
+    state.store.publish(Event::Backend(new_backend));
 
     if let Err(outcome) = stop_or_revert(
         state,
         progress,
         "stopping the backend",
         "daemon for backend switch",
-        || tray::update_backend_checks(old_backend),
+        || state.store.publish(Event::Backend(old_backend)),
     )
     .await
     {
@@ -260,12 +376,16 @@ pub(crate) async fn switch_backend(
         .await
     {
         error!("Failed to install esphome-device-builder: {}", e);
-        return install_failed(state, e.to_string(), "failed backend switch", || {
-            tray::update_backend_checks(old_backend)
-        })
+        return install_failed(
+            state,
+            e,
+            RestartReason::Settings,
+            "failed backend switch",
+            || state.store.publish(Event::Backend(old_backend)),
+        )
         .await;
     }
-    // Install succeeded — refresh the tray version display.
+    // Install succeeded — refresh the version display.
     tray::refresh_builder_version_display(app).await;
 
     // Persist the new backend channel.
@@ -276,15 +396,14 @@ pub(crate) async fn switch_backend(
     .await;
 
     progress("start", "starting the backend");
-    if let Err(e) = state.daemon.start().await {
+    if let Err(e) = state.daemon.start_for(RestartReason::Settings).await {
         error!("Failed to start daemon after backend switch: {}", e);
-        return SwitchOutcome::StartFailed(e.to_string());
+        return SwitchOutcome::StartFailed(e);
     }
     info!("Switched backend to {}", new_backend);
 
     progress("wait", "waiting for the backend to become ready");
-    let port = state.daemon.port();
-    let ready = crate::wait_for_dashboard_ready(port, READY_TIMEOUT_SECS).await;
+    let ready = crate::wait_for_dashboard_ready(state.daemon.address(), READY_TIMEOUT_SECS).await;
     SwitchOutcome::Success { ready }
 }
 
@@ -295,16 +414,14 @@ pub(crate) async fn restart_daemon(
     wait_ready: bool,
     _guard: &UpdateGuard,
     progress: Progress<'_>,
-) -> Result<bool, String> {
+) -> Result<bool, DaemonError> {
     progress("restart", "restarting the dashboard");
-    if let Err(e) = state.daemon.restart().await {
-        return Err(e.to_string());
-    }
+    state.daemon.restart().await?;
     if !wait_ready {
         return Ok(true);
     }
     progress("wait", "waiting for the dashboard to become ready");
-    Ok(crate::wait_for_dashboard_ready(state.daemon.port(), READY_TIMEOUT_SECS).await)
+    Ok(crate::wait_for_dashboard_ready(state.daemon.address(), READY_TIMEOUT_SECS).await)
 }
 
 /// Serializes launch-at-login toggles: concurrent toggles (two fast tray
@@ -314,8 +431,8 @@ pub(crate) async fn restart_daemon(
 /// so a slow OS call doesn't block unrelated settings readers.
 static STARTUP_TOGGLE: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
 
-/// Persist the autostart preference, reconcile the OS login item, and refresh
-/// the tray radio labels. Returns the actual post-call OS state, which is what
+/// Persist the autostart preference, reconcile the OS login item, and publish
+/// the result. Returns the actual post-call OS state, which is what
 /// callers should report — enable/disable can fail (permissions, policy,
 /// platform limits), and reporting the requested state would mislead.
 pub(crate) async fn set_launch_at_startup(
@@ -361,7 +478,7 @@ pub(crate) async fn set_launch_at_startup(
     })
     .await
     .unwrap_or(enable);
-    tray::update_startup_checks(actual);
+    state.store.publish(Event::Startup(actual));
     actual
 }
 
@@ -418,7 +535,7 @@ pub(crate) async fn run_full_update(
     // overwrites the user's `python/` directory, so any pip bump done now
     // would be wiped by the relaunch (same ordering as the tray arm).
     progress("desktop", "checking for a desktop app update");
-    match app.updater() {
+    match crate::network::updater(app) {
         Ok(updater) => match updater.check().await {
             Ok(Some(update)) => {
                 let version = update.version.clone();
@@ -562,7 +679,7 @@ async fn run_package_phase<D, F, Fut, R, RFut>(
 ) where
     D: FnOnce(&str) -> String,
     F: FnOnce(String) -> Fut,
-    Fut: std::future::Future<Output = anyhow::Result<()>>,
+    Fut: std::future::Future<Output = Result<(), UpdateError>>,
     R: FnOnce() -> RFut,
     RFut: std::future::Future<Output = ()>,
 {
@@ -611,16 +728,19 @@ async fn run_package_phase<D, F, Fut, R, RFut>(
 /// Stop the dashboard, run `install`, then start the dashboard again. The
 /// start is attempted even after a failed install so the user isn't left
 /// without a dashboard.
-async fn stop_install_start<F, Fut>(state: &Arc<AppState>, install: F) -> Result<(), String>
+pub(crate) async fn stop_install_start<F, Fut>(
+    state: &Arc<AppState>,
+    install: F,
+) -> Result<(), String>
 where
     F: FnOnce() -> Fut,
-    Fut: std::future::Future<Output = anyhow::Result<()>>,
+    Fut: std::future::Future<Output = Result<(), UpdateError>>,
 {
     if let Err(e) = state.daemon.stop().await {
         return Err(format!("failed to stop the dashboard: {e}"));
     }
     let install_result = install().await;
-    let start_result = state.daemon.start().await;
+    let start_result = state.daemon.start_for(RestartReason::Update).await;
     match (install_result, start_result) {
         (Ok(()), Ok(())) => Ok(()),
         (Ok(()), Err(e)) => Err(format!("updated, but the dashboard failed to start: {e}")),
@@ -633,8 +753,12 @@ where
 
 /// Best-effort dashboard restart after a failed install, so the user isn't
 /// left without a backend. Returns whether the restart succeeded.
-async fn restart_after_failure(state: &Arc<AppState>, context: &str) -> bool {
-    match state.daemon.start().await {
+async fn restart_after_failure(
+    state: &Arc<AppState>,
+    reason: RestartReason,
+    context: &str,
+) -> bool {
+    match state.daemon.start_for(reason).await {
         Ok(()) => true,
         Err(e) => {
             error!("Failed to restart backend after {}: {}", context, e);
@@ -643,9 +767,9 @@ async fn restart_after_failure(state: &Arc<AppState>, context: &str) -> bool {
     }
 }
 
-/// Re-detect the installed ESPHome version and update the tray display, off
+/// Re-detect the installed ESPHome version and publish it, off
 /// the async executor (the detection spawns a Python subprocess).
-async fn refresh_version_display_blocking(app: &AppHandle) {
+pub(crate) async fn refresh_version_display_blocking(app: &AppHandle) {
     let app = app.clone();
     let _ = tokio::task::spawn_blocking(move || tray::refresh_version_display(&app)).await;
 }
@@ -10,7 +10,10 @@
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
+use crate::devices::batch::BatchAction;
 use crate::settings::{Backend, ReleaseChannel};
+use crate::startup::{SlowStart, Startup};
+use crate::store::Restart;
 
 /// Upper bound on a single protocol line. Requests and replies are tiny; a
 /// line this long means a confused peer, not a real client.
@@ -55,6 +58,17 @@ pub enum Request {
     GetStartup,
     /// Enable or disable launching at login.
     SetStartup { enable: bool },
+    /// Report whether the editor language server runs, and on which port.
+    GetLanguageServer,
+    /// Start or stop the editor language server, optionally on a new port.
+    SetLanguageServer { enable: bool, port: Option<u16> },
+    /// Report the comparison dashboard's ESPHome version and port.
+    GetComparison,
+    /// Start a comparison dashboard on ESPHome `version`, on `port` or the
+    /// one after the main dashboard's.
+    StartComparison { version: String, port: Option<u16> },
+    /// Stop the comparison dashboard.
+    StopComparison,
     /// Update the desktop app, ESPHome, and the device builder.
     Update,
     /// Report whether an update is available for any component, without
@@ -62,12 +76,64 @@ pub enum Request {
     CheckUpdate,
     /// Restart the dashboard backend.
     Restart,
+    /// Stop the dashboard's running compile jobs.
+    CancelBuild,
+    /// Validate, compile or upload each of `devices` in turn.
+    DeviceBatch {
+        action: BatchAction,
+        devices: Vec<String>,
+        /// Flash a device that keeps failing OTA over USB instead (see
+        /// [`crate::devices::serial`]).
+        #[serde(default)]
+        serial_fallback: bool,
+    },
+    /// Compile `device` with ESPHome `from` and `to` and report what differs.
+    DeviceDiff {
+        device: String,
+        from: String,
+        to: String,
+    },
+    /// Query the device at `host` over the native API and scaffold a config
+    /// for it, as `<name>.yaml` or under the name it reports.
+    DeviceRecover {
+        host: String,
+        port: u16,
+        key: Option<String>,
+        password: Option<String>,
+        name: Option<String>,
+    },
     /// Quit the app.
     Quit,
     /// Report app and backend status.
     Status,
 }
 
+impl Request {
+    /// The request's `cmd` tag, e.g. `set_channel`.
+    pub fn name(&self) -> String {
+        serde_json::to_value(self)
+            .ok()
+            .and_then(|v| v["cmd"].as_str().map(str::to_string))
+            .unwrap_or_default()
+    }
+
+    /// Whether the request only opens the dashboard or reads state. Kiosk
+    /// mode refuses everything else.
+    pub fn is_read_only(&self) -> bool {
+        matches!(
+            self,
+            Request::Open
+                | Request::GetBackend
+                | Request::GetChannel
+                | Request::GetStartup
+                | Request::GetLanguageServer
+                | Request::GetComparison
+                | Request::CheckUpdate
+                | Request::Status
+        )
+    }
+}
+
 /// Error category for [`Reply::Err`], mapped to distinct CLI exit codes.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -84,8 +150,15 @@ pub enum ErrCode {
 pub enum Reply {
     /// Terminal: the operation succeeded.
     Ok { message: String },
-    /// Terminal: the operation failed or was rejected.
-    Err { message: String, code: ErrCode },
+    /// Terminal: the operation failed or was rejected. `kind` identifies a
+    /// failure of the daemon, an update or the platform layer
+    /// (`daemon.stop_timeout`); absent for everything else.
+    Err {
+        message: String,
+        code: ErrCode,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        kind: Option<String>,
+    },
     /// Non-terminal progress note for long-running operations.
     Progress { step: String, detail: String },
     /// Terminal reply to [`Request::Status`].
@@ -107,6 +180,16 @@ impl Reply {
         Reply::Err {
             message: message.into(),
             code: ErrCode::Failed,
+            kind: None,
+        }
+    }
+
+    /// [`Reply::failed`] for a typed error of that `kind`.
+    pub fn failed_as(message: impl Into<String>, kind: &str) -> Self {
+        Reply::Err {
+            message: message.into(),
+            code: ErrCode::Failed,
+            kind: Some(kind.to_string()),
         }
     }
 }
@@ -119,13 +202,59 @@ pub struct StatusReply {
     /// Whether the dashboard actually answered an HTTP probe.
     pub backend_healthy: bool,
     pub port: u16,
+    /// Where the dashboard opens in a browser (`None` from an older app,
+    /// which only listened on `localhost`).
+    #[serde(default)]
+    pub url: Option<String>,
     pub esphome_version: Option<String>,
+    /// The ESPHome the config directory pins, which the backend runs instead
+    /// of `esphome_version`.
+    #[serde(default)]
+    pub pinned_esphome: Option<String>,
     pub device_builder_version: Option<String>,
     pub release_channel: ReleaseChannel,
     pub backend: Backend,
     pub launch_at_startup: bool,
     pub config_dir: PathBuf,
     pub logs_dir: PathBuf,
+    /// `TZ` the backend runs with (`None` = the system timezone). Defaulted
+    /// so a client still parses a status reply from an older app.
+    #[serde(default)]
+    pub timezone: Option<String>,
+    /// Locale the backend runs with (`None` = the system default).
+    #[serde(default)]
+    pub locale: Option<String>,
+    /// Seconds the backend has been up (`None` while stopped).
+    #[serde(default)]
+    pub uptime_secs: Option<u64>,
+    /// Backend restarts this app session, user-initiated or after a crash.
+    #[serde(default)]
+    pub restarts: u32,
+    /// How the backend last exited unexpectedly this session, if it did.
+    #[serde(default)]
+    pub last_crash: Option<String>,
+    /// The session's latest restarts and why each happened, oldest first.
+    #[serde(default)]
+    pub restart_history: Vec<Restart>,
+    /// How long the latest launch took to get the dashboard up.
+    #[serde(default)]
+    pub last_startup: Option<Startup>,
+    /// The phase of it that took far longer than usual, if one did.
+    #[serde(default)]
+    pub slow_start: Option<SlowStart>,
+    /// The latest periodic health check of the running backend, if one ran
+    /// this session.
+    #[serde(default)]
+    pub last_health_check: Option<HealthCheck>,
+}
+
+/// A periodic health check, in [`StatusReply`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HealthCheck {
+    /// Seconds since it ran.
+    pub secs_ago: u64,
+    /// Whether the dashboard answered.
+    pub passed: bool,
 }
 
 /// Availability of an update for one component, returned inside
@@ -291,9 +420,38 @@ mod tests {
             },
             Request::GetStartup,
             Request::SetStartup { enable: false },
+            Request::GetLanguageServer,
+            Request::SetLanguageServer {
+                enable: true,
+                port: Some(6055),
+            },
+            Request::GetComparison,
+            Request::StartComparison {
+                version: "2024.6.4".into(),
+                port: None,
+            },
+            Request::StopComparison,
             Request::Update,
             Request::CheckUpdate,
             Request::Restart,
+            Request::CancelBuild,
+            Request::DeviceBatch {
+                action: BatchAction::Upload,
+                devices: vec!["porch".into(), "shed".into()],
+                serial_fallback: true,
+            },
+            Request::DeviceDiff {
+                device: "porch".into(),
+                from: "2024.6.4".into(),
+                to: "2024.10.0".into(),
+            },
+            Request::DeviceRecover {
+                host: "192.168.1.40".into(),
+                port: 6053,
+                key: Some("a2V5".into()),
+                password: None,
+                name: None,
+            },
             Request::Quit,
             Request::Status,
         ];
@@ -304,6 +462,26 @@ mod tests {
         }
     }
 
+    #[test]
+    fn only_status_and_open_requests_are_read_only() {
+        assert!(Request::Open.is_read_only());
+        assert!(Request::CheckUpdate.is_read_only());
+        assert!(!Request::Update.is_read_only());
+        assert!(!Request::SetStartup { enable: true }.is_read_only());
+    }
+
+    #[test]
+    fn name_is_the_wire_tag() {
+        assert_eq!(Request::CancelBuild.name(), "cancel_build");
+        assert_eq!(
+            Request::SetChannel {
+                channel: ReleaseChannel::Dev
+            }
+            .name(),
+            "set_channel"
+        );
+    }
+
     #[test]
     fn reply_round_trips() {
         let replies = vec![
@@ -313,10 +491,12 @@ mod tests {
             Reply::Err {
                 message: "busy".into(),
                 code: ErrCode::Busy,
+                kind: None,
             },
             Reply::Err {
                 message: "boom".into(),
                 code: ErrCode::Failed,
+                kind: Some("daemon.stop_timeout".into()),
             },
             Reply::Progress {
                 step: "esphome".into(),
@@ -327,13 +507,40 @@ mod tests {
                 backend_running: true,
                 backend_healthy: false,
                 port: 6052,
+                url: Some("http://[::1]:6052".into()),
                 esphome_version: Some("2026.6.2".into()),
+                pinned_esphome: None,
                 device_builder_version: None,
                 release_channel: ReleaseChannel::Beta,
                 backend: Backend::BuilderBeta,
                 launch_at_startup: true,
                 config_dir: PathBuf::from("/home/x/esphome"),
                 logs_dir: PathBuf::from("/home/x/.local/share/io.esphome.builder/logs"),
+                timezone: Some("Europe/Berlin".into()),
+                locale: None,
+                uptime_secs: Some(3600),
+                restarts: 2,
+                last_crash: Some("exited with exit status: 1".into()),
+                restart_history: vec![Restart {
+                    number: 2,
+                    time: "2026-10-16T09:00:00Z".into(),
+                    reason: crate::store::RestartReason::CrashRecovery,
+                }],
+                last_startup: Some(Startup {
+                    time: "2026-10-16T08:00:00Z".into(),
+                    python_ms: 1_200,
+                    spawn_ms: 300,
+                    ready_ms: None,
+                }),
+                slow_start: Some(SlowStart {
+                    phase: crate::startup::Phase::Ready,
+                    secs: 600,
+                    usual_secs: 8,
+                }),
+                last_health_check: Some(HealthCheck {
+                    secs_ago: 12,
+                    passed: false,
+                }),
             })),
             Reply::UpdateCheck(Box::new(UpdateCheckReply {
                 any_available: true,
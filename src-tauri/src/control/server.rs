@@ -5,6 +5,7 @@
 //! This is what makes the app controllable on Linux systems without a
 //! working system tray.
 
+use std::net::Ipv4Addr;
 use std::sync::Arc;
 use tauri::{AppHandle, Manager};
 use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
@@ -13,9 +14,19 @@ use tracing::{info, warn};
 
 use super::ops::{self, SwitchOutcome, UpdateGuard};
 use super::protocol::{
-    self, backend_name, channel_name, ErrCode, Reply, Request, StatusReply, UpdateCheckReply,
+    self, backend_name, channel_name, ErrCode, HealthCheck, Reply, Request, StatusReply,
+    UpdateCheckReply,
 };
 use super::update_check;
+use crate::devices::batch::BatchAction;
+use crate::devices::registry::Registry;
+use crate::devices::sources::AllowList;
+use crate::error::UserError;
+use crate::i18n::{t, t_with};
+use crate::settings::KIOSK_REFUSAL;
+use crate::startup::StartupHistory;
+use crate::store::Event;
+use crate::util::ConfigStore;
 use crate::AppState;
 
 /// Backoff before retrying a failed accept or pipe re-create, so a
@@ -242,11 +253,20 @@ where
         }
     };
 
+    // Anything that can change the install is audited with its outcome,
+    // refusals included.
+    let audit = (!request.is_read_only()).then(|| (app.clone(), request.name()));
+
     // Replies flow through a channel to a dedicated writer task so
     // long-running handlers can stream Progress lines while they work.
     let (tx, mut rx) = mpsc::unbounded_channel::<Reply>();
     let writer = tauri::async_runtime::spawn(async move {
         while let Some(reply) = rx.recv().await {
+            if let (Some((app, action)), Reply::Ok { message } | Reply::Err { message, .. }) =
+                (&audit, &reply)
+            {
+                crate::audit::record(app, crate::audit::Source::Cli, action, message);
+            }
             if write_reply(&mut write_half, &reply).await.is_err() {
                 break;
             }
@@ -297,6 +317,10 @@ async fn dispatch(
         return None;
     };
     let state: Arc<AppState> = state.inner().clone();
+    if state.kiosk && !request.is_read_only() {
+        let _ = tx.send(Reply::failed(KIOSK_REFUSAL));
+        return None;
+    }
 
     /// Acquire the `UpdateGuard` or reply busy and bail out of `dispatch`,
     /// mirroring the tray arms' `guard_or_return!`.
@@ -308,6 +332,7 @@ async fn dispatch(
                     let _ = tx.send(Reply::Err {
                         message: "another update or switch is already in progress".to_string(),
                         code: ErrCode::Busy,
+                        kind: None,
                     });
                     return None;
                 }
@@ -322,11 +347,24 @@ async fn dispatch(
         });
     };
 
+    /// Ask for OS authentication when `require_auth` is on, replying failed
+    /// and bailing out of `dispatch` if it isn't given.
+    macro_rules! authorize_or_fail {
+        ($reason:expr) => {
+            if !ops::authorize(&state, $reason, &progress).await {
+                let _ = tx.send(Reply::failed(
+                    "OS authentication was declined or isn't available",
+                ));
+                return None;
+            }
+        };
+    }
+
     match request {
         Request::Open => {
-            let port = state.settings.read().await.port;
-            crate::open_dashboard(port);
-            let _ = tx.send(Reply::ok(format!("opening http://localhost:{port}")));
+            crate::open_dashboard(&state);
+            let url = state.hostname.url(state.daemon.address());
+            let _ = tx.send(Reply::ok(format!("opening {url}")));
         }
         Request::GetChannel => {
             let channel = state.settings.read().await.release_channel;
@@ -334,6 +372,10 @@ async fn dispatch(
         }
         Request::SetChannel { channel } => {
             let guard = guard_or_busy!();
+            authorize_or_fail!(t_with(
+                "auth.switch_channel",
+                &[("channel", &channel.to_string())]
+            ));
             let outcome =
                 ops::switch_release_channel(app, &state, channel, &guard, &progress).await;
             let _ = tx.send(switch_reply(
@@ -348,6 +390,10 @@ async fn dispatch(
         }
         Request::SetBackend { backend } => {
             let guard = guard_or_busy!();
+            authorize_or_fail!(t_with(
+                "auth.switch_backend",
+                &[("backend", &backend.to_string())]
+            ));
             let outcome = ops::switch_backend(app, &state, backend, &guard, &progress).await;
             let _ = tx.send(switch_reply(
                 outcome,
@@ -373,8 +419,64 @@ async fn dispatch(
             };
             let _ = tx.send(reply);
         }
+        Request::GetLanguageServer => {
+            let _ = tx.send(Reply::ok(match state.language_server.port() {
+                Some(port) => format!("on, port {port}"),
+                None => "off".to_string(),
+            }));
+        }
+        Request::SetLanguageServer { enable, port } => {
+            let reply = match ops::set_language_server(app, &state, enable, port).await {
+                Ok(Some(port)) => Reply::ok(format!("language server listening on port {port}")),
+                Ok(None) => Reply::ok("language server stopped"),
+                Err(e) => Reply::failed(format!("{e:#}")),
+            };
+            let _ = tx.send(reply);
+        }
+        Request::GetComparison => {
+            let _ = tx.send(Reply::ok(match state.comparison.current() {
+                Some((version, port)) => format!("ESPHome {version} on port {port}"),
+                None => "off".to_string(),
+            }));
+        }
+        Request::StartComparison { version, port } => {
+            // Holds the guard so an update can't replace the Python the
+            // venv is created from mid-install.
+            let _guard = guard_or_busy!();
+            let port = port.unwrap_or(state.daemon.port().saturating_add(1));
+            progress(
+                "compare",
+                &format!("starting ESPHome {version}; the first start installs it"),
+            );
+            let reply = match state
+                .comparison
+                .start(app, &state.daemon, &version, port)
+                .await
+            {
+                Err(e) => Reply::failed(format!("{e:#}")),
+                Ok(())
+                    if crate::wait_for_dashboard_ready((Ipv4Addr::LOCALHOST, port).into(), 60)
+                        .await =>
+                {
+                    Reply::ok(format!(
+                        "ESPHome {version} dashboard ready at {}",
+                        crate::daemon::loopback_url(port)
+                    ))
+                }
+                Ok(()) => Reply::failed(format!(
+                    "ESPHome {version} dashboard started on port {port} but isn't responding; \
+                     see compare.log in the logs folder"
+                )),
+            };
+            let _ = tx.send(reply);
+        }
+        Request::StopComparison => {
+            state.comparison.stop();
+            let _ = tx.send(Reply::ok("comparison dashboard stopped"));
+        }
         Request::Update => {
             let guard = guard_or_busy!();
+            authorize_or_fail!(t("auth.update"));
             let report = ops::run_full_update(app, &state, &guard, &progress).await;
             let summary = report.lines.join("; ");
             if report.app_update_installed {
@@ -409,12 +511,117 @@ async fn dispatch(
                     )));
                 }
                 Err(e) => {
-                    let _ = tx.send(Reply::failed(format!(
-                        "failed to restart the dashboard: {e}"
-                    )));
+                    let _ = tx.send(Reply::failed_as(
+                        format!("failed to restart the dashboard: {e}"),
+                        e.kind(),
+                    ));
                 }
             }
         }
+        Request::CancelBuild => {
+            let _ = tx.send(match state.daemon.cancel_build().await {
+                Ok(0) => Reply::failed("no build is running"),
+                Ok(count) => Reply::ok(format!("cancelled {count} build job(s)")),
+                Err(e) => Reply::failed_as(format!("failed to cancel the build: {e}"), e.kind()),
+            });
+        }
+        Request::DeviceBatch {
+            action,
+            devices,
+            serial_fallback,
+        } => {
+            // Holds the guard so an update can't swap ESPHome out mid-batch.
+            let _guard = guard_or_busy!();
+            // Read fresh: `device allow` and `device add` edit the files
+            // while the app runs.
+            let loaded = crate::platform::get_data_dir(app)
+                .map_err(anyhow::Error::from)
+                .and_then(|dir| {
+                    let allow = AllowList::load(&AllowList::path(&dir))?;
+                    let registry = Registry::load(&Registry::path(&dir))?;
+                    Ok((allow, registry, dir))
+                });
+            let (allow, registry, data_dir) = match loaded {
+                Ok(loaded) => loaded,
+                Err(e) => {
+                    let _ = tx.send(Reply::failed(format!("{e:#}")));
+                    return None;
+                }
+            };
+            let mut report = crate::devices::batch::run(
+                &state.daemon,
+                action,
+                &devices,
+                &allow,
+                &registry,
+                &data_dir,
+                &progress,
+            )
+            .await;
+            if action == BatchAction::Upload {
+                crate::devices::serial::fallback(
+                    &state.daemon,
+                    &mut report,
+                    &data_dir,
+                    serial_fallback,
+                    &progress,
+                )
+                .await;
+            }
+            let summary = format!(
+                "{} {} of {} device(s)",
+                action.done(),
+                report.succeeded.len(),
+                devices.len()
+            );
+            let _ = tx.send(if report.failed.is_empty() {
+                Reply::ok(summary)
+            } else {
+                Reply::failed(format!("{summary}; failed: {}", report.failed.join(", ")))
+            });
+        }
+        Request::DeviceDiff { device, from, to } => {
+            // Holds the guard so an update can't replace the Python the
+            // venvs are created from mid-install.
+            let _guard = guard_or_busy!();
+            let outcome =
+                crate::devices::diff::run(app, &state.daemon, &device, &from, &to, &progress).await;
+            let _ = tx.send(match outcome {
+                Ok((path, summary)) => {
+                    Reply::ok(format!("{summary}; report in {}", path.display()))
+                }
+                Err(e) => Reply::failed(format!("{e:#}")),
+            });
+        }
+        Request::DeviceRecover {
+            host,
+            port,
+            key,
+            password,
+            name,
+        } => {
+            let target = crate::devices::recover::Target {
+                host: &host,
+                port,
+                key: key.as_deref(),
+                password: password.as_deref(),
+            };
+            progress("recover", &format!("querying {host}"));
+            let outcome = match crate::platform::get_data_dir(app) {
+                Ok(data_dir) => {
+                    crate::devices::recover::run(&state.daemon, &data_dir, target, name.as_deref())
+                        .await
+                }
+                Err(e) => Err(e.into()),
+            };
+            let _ = tx.send(match outcome {
+                Ok((path, entities)) => Reply::ok(format!(
+                    "wrote {} with {entities} entities to fill in",
+                    path.display()
+                )),
+                Err(e) => Reply::failed(format!("{e:#}")),
+            });
+        }
         Request::Quit => {
             // Refuse to quit while an update/switch is in flight: tearing the
             // process down now would orphan a pip install mid-write and corrupt
@@ -451,21 +658,27 @@ fn switch_reply(outcome: SwitchOutcome, unchanged_msg: String, success_msg: Stri
             "{success_msg}, but the dashboard {}",
             ops::not_ready_note()
         )),
-        SwitchOutcome::StopFailed(e) => Reply::failed(format!("failed to stop the dashboard: {e}")),
-        SwitchOutcome::InstallFailed { error, restarted } => Reply::failed(if restarted {
-            format!("install failed: {error}; the previous version was restarted")
-        } else {
-            format!("install failed: {error}; the dashboard could not be restarted")
-        }),
-        SwitchOutcome::StartFailed(e) => {
-            Reply::failed(format!("installed, but the dashboard failed to start: {e}"))
+        SwitchOutcome::StopFailed(e) => {
+            Reply::failed_as(format!("failed to stop the dashboard: {e}"), e.kind())
         }
+        SwitchOutcome::InstallFailed { error, restarted } => Reply::failed_as(
+            if restarted {
+                format!("install failed: {error}; the previous version was restarted")
+            } else {
+                format!("install failed: {error}; the dashboard could not be restarted")
+            },
+            error.kind(),
+        ),
+        SwitchOutcome::StartFailed(e) => Reply::failed_as(
+            format!("installed, but the dashboard failed to start: {e}"),
+            e.kind(),
+        ),
     }
 }
 
 /// Assemble the full status snapshot. Version detection spawns Python
 /// subprocesses, so it runs on blocking threads.
-async fn build_status(app: &AppHandle, state: &Arc<AppState>) -> StatusReply {
+pub(crate) async fn build_status(app: &AppHandle, state: &Arc<AppState>) -> StatusReply {
     let (port, release_channel, backend, launch_fallback) = {
         let settings = state.settings.read().await;
         (
@@ -479,26 +692,57 @@ async fn build_status(app: &AppHandle, state: &Arc<AppState>) -> StatusReply {
     // The probe and the two Python version detections are independent; run
     // them concurrently so `status` pays the slowest, not the sum.
     let (backend_healthy, esphome_version, device_builder_version, launch_at_startup) = tokio::join!(
-        async { crate::daemon::health_check(port).await.unwrap_or(false) },
+        async {
+            crate::daemon::health_check(state.daemon.address())
+                .await
+                .unwrap_or(false)
+        },
         update_check::detect(app, crate::update::installed_esphome_version),
         update_check::detect(app, crate::update::get_installed_device_builder_version),
         ops::startup_enabled(app, launch_fallback),
     );
     let esphome_version = esphome_version.ok().flatten();
     let device_builder_version = device_builder_version.ok().flatten();
+    let stats = state.daemon.stats();
+    let startups = crate::platform::get_data_dir(app)
+        .ok()
+        .and_then(|dir| StartupHistory::load(&StartupHistory::path(&dir)).ok());
+
+    // Fresh detections; pass them on to whatever else shows them.
+    if let Some(version) = &esphome_version {
+        state.store.publish(Event::EsphomeVersion(version.clone()));
+    }
+    if let Some(version) = &device_builder_version {
+        state.store.publish(Event::BuilderVersion(version.clone()));
+    }
+    state.store.publish(Event::Startup(launch_at_startup));
 
     StatusReply {
         app_version: app.package_info().version.to_string(),
-        backend_running: state.daemon.is_running(),
+        backend_running: state.store.snapshot().daemon.is_running(),
         backend_healthy,
         port,
+        url: Some(state.hostname.url(state.daemon.address())),
         esphome_version,
+        pinned_esphome: state.daemon.pinned_esphome().map(str::to_string),
         device_builder_version,
         release_channel,
         backend,
         launch_at_startup,
         config_dir: state.daemon.config_dir().clone(),
         logs_dir: state.daemon.logs_dir().clone(),
+        timezone: state.daemon.timezone(),
+        locale: state.daemon.locale(),
+        uptime_secs: stats.uptime.map(|u| u.as_secs()),
+        restarts: stats.restarts,
+        last_crash: stats.last_crash,
+        restart_history: state.store.snapshot().restarts,
+        last_startup: startups.as_ref().and_then(|h| h.latest().cloned()),
+        slow_start: startups.and_then(|h| h.slow_start()),
+        last_health_check: stats.last_health_check.map(|(ago, passed)| HealthCheck {
+            secs_ago: ago.as_secs(),
+            passed,
+        }),
     }
 }
 
@@ -506,7 +750,7 @@ async fn build_status(app: &AppHandle, state: &Arc<AppState>) -> StatusReply {
 /// Read-only, so it takes no [`UpdateGuard`] and is safe to run even while an
 /// update is in flight. The three checks hit the network (GitHub, PyPI) and
 /// spawn Python for the installed versions, so run them concurrently.
-async fn build_update_check(app: &AppHandle, state: &Arc<AppState>) -> UpdateCheckReply {
+pub(crate) async fn build_update_check(app: &AppHandle, state: &Arc<AppState>) -> UpdateCheckReply {
     let (channel, backend) = {
         let settings = state.settings.read().await;
         (settings.release_channel, settings.backend)
@@ -10,7 +10,6 @@
 
 use std::sync::Arc;
 use tauri::AppHandle;
-use tauri_plugin_updater::UpdaterExt;
 
 use super::protocol::ComponentUpdate;
 use crate::settings::ReleaseChannel;
@@ -66,7 +65,7 @@ fn compare(installed: String, latest: String) -> ComponentUpdate {
 /// the same `updater().check()` the update flow uses, but only reports.
 pub(crate) async fn desktop_update_available(app: &AppHandle) -> ComponentUpdate {
     let installed = app.package_info().version.to_string();
-    match app.updater() {
+    match crate::network::updater(app) {
         Ok(updater) => match updater.check().await {
             Ok(Some(update)) => ComponentUpdate::upgradable(installed, update.version),
             Ok(None) => ComponentUpdate::current(installed.clone(), installed),
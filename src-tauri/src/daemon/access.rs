@@ -0,0 +1,261 @@
+//! Who uses the dashboard, from the request lines in `dashboard.log`.
+//!
+//! The dashboard logs every request it answers as e.g. `200 GET /devices
+//! (192.168.1.20) 3.12ms`. With `access_summary` on, a task reads what the
+//! log gained every [`POLL_INTERVAL`] and publishes a [`Usage`]: how long ago
+//! a browser last asked for anything, and which other machines did. The app's
+//! own requests (the health probe's `GET /`, a stop's `POST /shutdown`) don't
+//! count, so neither does a bare page load from this machine; the requests
+//! the page makes once open do. The tray's "Last used" item shows it, and an
+//! idle shutdown would go by it.
+//!
+//! Behind the startup page every request in the log comes from this machine,
+//! the proxy's, so the proxy reports the other machines that connect to it
+//! as [`Peers`].
+
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tracing::debug;
+
+use crate::store::{Event, Store, Usage};
+use crate::tasks::Tasks;
+
+/// Time between reads of the log.
+const POLL_INTERVAL: Duration = Duration::from_secs(15);
+
+/// A request line from the log.
+#[derive(Debug, PartialEq, Eq)]
+struct Request<'a> {
+    method: &'a str,
+    path: &'a str,
+    client: &'a str,
+}
+
+/// The request logged on `line`, if it is a request line: a status, a method,
+/// a path, the client in parentheses and the time taken, wherever they start
+/// (after a timestamp and level, say).
+fn parse(line: &str) -> Option<Request<'_>> {
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    tokens.windows(5).find_map(|window| {
+        let &[status, method, path, client, took] = window else {
+            return None;
+        };
+        let client = client.strip_prefix('(')?.strip_suffix(')')?;
+        let request = status.len() == 3
+            && status.bytes().all(|b| b.is_ascii_digit())
+            && !method.is_empty()
+            && method.bytes().all(|b| b.is_ascii_uppercase())
+            && path.starts_with('/')
+            && took.ends_with("ms");
+        request.then_some(Request {
+            method,
+            path,
+            client,
+        })
+    })
+}
+
+fn is_loopback(client: &str) -> bool {
+    client.starts_with("127.") || client == "::1"
+}
+
+/// The clients the startup page's proxy accepted since the last read.
+#[derive(Debug, Clone, Default)]
+pub(super) struct Peers(Arc<Mutex<Vec<IpAddr>>>);
+
+impl Peers {
+    pub(super) fn push(&self, client: IpAddr) {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(client);
+    }
+
+    /// The clients accepted since the last call.
+    pub(super) fn take(&self) -> Vec<IpAddr> {
+        std::mem::take(&mut *self.0.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+/// Whether the app itself sent `request`.
+fn is_own(request: &Request) -> bool {
+    is_loopback(request.client)
+        && matches!(
+            (request.method, request.path),
+            ("GET", "/") | ("POST", "/shutdown")
+        )
+}
+
+/// What the requests seen so far add up to.
+#[derive(Debug, Default)]
+struct Tracker {
+    last_used: Option<Instant>,
+    remote_clients: BTreeSet<String>,
+}
+
+impl Tracker {
+    /// Take in the log lines in `text`, read at `now`.
+    fn observe(&mut self, text: &str, now: Instant) {
+        for request in text.lines().filter_map(parse) {
+            if is_own(&request) {
+                continue;
+            }
+            self.last_used = Some(now);
+            if !is_loopback(request.client) {
+                self.remote_clients.insert(request.client.to_string());
+            }
+        }
+    }
+
+    /// Take in the clients the proxy accepted by `now`. This machine's are
+    /// left to the log, which tells the app's own requests from a browser's.
+    fn observe_peers(&mut self, peers: &[IpAddr], now: Instant) {
+        for peer in peers.iter().filter(|peer| !peer.is_loopback()) {
+            self.last_used = Some(now);
+            self.remote_clients.insert(peer.to_string());
+        }
+    }
+
+    fn usage(&self, now: Instant) -> Usage {
+        Usage {
+            idle_minutes: self
+                .last_used
+                .map(|at| now.duration_since(at).as_secs() / 60),
+            remote_clients: self.remote_clients.iter().cloned().collect(),
+        }
+    }
+}
+
+/// Reads what a log file gained since the last read. Each start rotates the
+/// log, so a file shorter than what was read is a new one, read from the top.
+struct Follower {
+    path: PathBuf,
+    offset: u64,
+}
+
+impl Follower {
+    /// Follow `path` from its current end: what it already holds is from an
+    /// earlier session.
+    fn new(path: PathBuf) -> Self {
+        let offset = std::fs::metadata(&path).map_or(0, |m| m.len());
+        Self { path, offset }
+    }
+
+    /// The complete lines added since the last read.
+    fn read_new(&mut self) -> std::io::Result<String> {
+        let mut file = File::open(&self.path)?;
+        if file.metadata()?.len() < self.offset {
+            self.offset = 0;
+        }
+        file.seek(SeekFrom::Start(self.offset))?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+        // A line still being written is read whole next time.
+        let complete = bytes.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+        self.offset += complete as u64;
+        Ok(String::from_utf8_lossy(&bytes[..complete]).into_owned())
+    }
+}
+
+/// Follow the dashboard log at `log_path` and the clients in `peers` for as
+/// long as `tasks` lives, publishing the [`Usage`] to `store` after each read.
+pub(super) fn spawn(tasks: &Tasks, log_path: PathBuf, peers: Peers, store: Store) {
+    tasks.spawn("access summary", async move {
+        let mut log = Follower::new(log_path);
+        let mut tracker = Tracker::default();
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            match log.read_new() {
+                Ok(text) => tracker.observe(&text, Instant::now()),
+                Err(e) => debug!("Could not read the dashboard log: {}", e),
+            }
+            tracker.observe_peers(&peers.take(), Instant::now());
+            store.publish(Event::Usage(tracker.usage(Instant::now())));
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_request_lines_after_any_prefix() {
+        assert_eq!(
+            parse("2026-10-16 09:00:00,123 INFO 200 GET /devices (192.168.1.20) 3.12ms"),
+            Some(Request {
+                method: "GET",
+                path: "/devices",
+                client: "192.168.1.20",
+            })
+        );
+        assert_eq!(
+            parse("INFO 101 GET /compile (::1) 0.85ms").map(|r| r.client),
+            Some("::1")
+        );
+        assert_eq!(
+            parse("INFO Starting dashboard web server on port 6052"),
+            None
+        );
+        assert_eq!(parse("Compiling .pioenvs/x/src/main.o (took 12ms)"), None);
+    }
+
+    #[test]
+    fn the_apps_own_requests_are_not_use() {
+        let start = Instant::now();
+        let mut tracker = Tracker::default();
+        tracker.observe(
+            "INFO 200 GET / (127.0.0.1) 1.00ms\nINFO 200 POST /shutdown (127.0.0.1) 0.50ms\n",
+            start,
+        );
+        assert_eq!(tracker.usage(start), Usage::default());
+
+        tracker.observe("INFO 200 GET /devices (127.0.0.1) 2.00ms\n", start);
+        tracker.observe("INFO 200 GET / (192.168.1.20) 2.00ms\n", start);
+        let usage = tracker.usage(start + Duration::from_secs(150));
+        assert_eq!(usage.idle_minutes, Some(2));
+        assert_eq!(usage.remote_clients, vec!["192.168.1.20".to_string()]);
+    }
+
+    #[test]
+    fn behind_the_startup_page_the_proxys_peers_are_the_remote_clients() {
+        let start = Instant::now();
+        let mut tracker = Tracker::default();
+        let peers = Peers::default();
+        peers.push("127.0.0.1".parse().unwrap());
+        peers.push("192.168.1.20".parse().unwrap());
+        tracker.observe("INFO 200 GET /devices (127.0.0.1) 2.00ms\n", start);
+        tracker.observe_peers(&peers.take(), start);
+        let usage = tracker.usage(start);
+        assert_eq!(usage.idle_minutes, Some(0));
+        assert_eq!(usage.remote_clients, vec!["192.168.1.20".to_string()]);
+        assert!(peers.take().is_empty());
+
+        // The app's own probe through the proxy is not use.
+        let mut tracker = Tracker::default();
+        tracker.observe("INFO 200 GET / (127.0.0.1) 1.00ms\n", start);
+        tracker.observe_peers(&["127.0.0.1".parse().unwrap()], start);
+        assert_eq!(tracker.usage(start), Usage::default());
+    }
+
+    #[test]
+    fn follows_the_log_across_rotation() {
+        let dir = crate::util::unique_temp_dir("access");
+        let path = dir.join("dashboard.log");
+        std::fs::write(&path, "from an earlier session\n").unwrap();
+
+        let mut log = Follower::new(path.clone());
+        std::fs::write(&path, "from an earlier session\nnew\npart").unwrap();
+        assert_eq!(log.read_new().unwrap(), "new\n");
+        std::fs::write(&path, "rotated\n").unwrap();
+        assert_eq!(log.read_new().unwrap(), "rotated\n");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,284 @@
+//! Where the device builder runs, behind [`DaemonBackend`].
+//!
+//! [`DaemonManager`](super::DaemonManager) is the supervisor every runtime
+//! shares: it spawns the command a backend hands it, owns the child, logs its
+//! output, notices it exiting and health-checks the dashboard on the loopback
+//! port. A backend only knows how to get ready (provision a venv, create a
+//! volume), what to run, and how to reach the real process on a stop when
+//! signalling the child isn't enough. The `runtime` setting picks one:
+//!
+//! - `native`: [`NativeBackend`], the bundled (or pinned) Python on this machine,
+//!   with the workspace's extra packages ([`super::extras`])
+//! - `wsl2`: [`WslRuntime`], a venv inside a WSL2 distro
+//! - `docker`: [`DockerBackend`], a venv in a volume of a Python container
+//! - `ssh`: [`SshBackend`], a venv on another machine, tunnelled over SSH
+//! - `remote`: [`RemoteBackend`], a dashboard already running elsewhere,
+//!   relayed to the loopback port
+
+use anyhow::{bail, Context, Result};
+use std::fmt;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use tokio::process::Command;
+use tracing::debug;
+
+use super::docker::DockerBackend;
+use super::pinned::PinnedEnv;
+use super::remote::RemoteBackend;
+use super::ssh::SshBackend;
+use super::wsl::WslRuntime;
+use crate::settings::{Runtime, Settings};
+
+/// Package providing `esphome_device_builder`, installed into a runtime's
+/// venv on first start.
+pub(super) const BACKEND_PACKAGE: &str = "esphome-device-builder";
+
+/// What [`DaemonBackend::prepare`] returns: a trait method can't be `async`
+/// and stay object safe.
+pub(super) type Prepare<'a> = Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>;
+
+/// One way of running the device builder.
+pub(super) trait DaemonBackend: Send + Sync + fmt::Debug {
+    /// The setting that selected it, for messages.
+    fn runtime(&self) -> Runtime;
+
+    /// Get ready to run; awaited before every start, so it should be cheap
+    /// once done.
+    fn prepare(&self) -> Prepare<'_>;
+
+    /// The command serving `config_dir` at `address` on this machine. Only a
+    /// backend that runs the dashboard somewhere a host `nice` can't reach
+    /// applies `low_priority` itself.
+    fn command(&self, config_dir: &Path, address: SocketAddr, low_priority: bool) -> Command;
+
+    /// The interpreter running the dashboard on this machine, if it runs
+    /// here. The features that work on its process tree or run ESPHome next
+    /// to it (sandbox, priority, the watchdog and thermal monitor, cancelling
+    /// builds, batches, workspaces) need one.
+    fn host_python(&self) -> Option<&PathBuf> {
+        None
+    }
+
+    /// The ESPHome version the config directory pins, if the backend honours
+    /// pins.
+    fn pinned_esphome(&self) -> Option<&str> {
+        None
+    }
+
+    /// Best-effort signal to the dashboard itself, for when it isn't the
+    /// child (or the child's process group) and the stop paths' signal
+    /// doesn't reach it.
+    fn terminate_blocking(&self) {}
+}
+
+/// The backend the settings ask for. `python_path` and `python_bin_dir` are
+/// the bundled interpreter's, for the native runtime.
+pub(super) fn from_settings(
+    settings: &Settings,
+    config_dir: &Path,
+    data_dir: &Path,
+    python_path: PathBuf,
+    python_bin_dir: PathBuf,
+) -> Box<dyn DaemonBackend> {
+    match settings.runtime {
+        Runtime::Native => {}
+        Runtime::Wsl2 => {
+            if let Some(wsl) = WslRuntime::from_settings(settings) {
+                return Box::new(wsl);
+            }
+        }
+        Runtime::Docker => return Box::new(DockerBackend::from_settings(settings)),
+        Runtime::Ssh => return Box::new(SshBackend::from_settings(settings)),
+        Runtime::Remote => return Box::new(RemoteBackend::from_settings(settings)),
+    }
+    let (pinned, pin_error) = PinnedEnv::resolve(config_dir, data_dir, &python_path);
+    Box::new(NativeBackend::new(
+        python_path,
+        python_bin_dir,
+        pinned,
+        pin_error,
+        config_dir,
+    ))
+}
+
+/// The bundled Python on this machine, or the venv of the ESPHome the config
+/// directory pins.
+#[derive(Debug)]
+pub(super) struct NativeBackend {
+    python_path: PathBuf,
+    /// Only logged; the interpreter finds its own bin directory
+    python_bin_dir: PathBuf,
+    pinned: Option<PinnedEnv>,
+    /// Why the pin file couldn't be used; the backend won't start
+    pin_error: Option<String>,
+    /// For its extra Python packages, read at every start.
+    config_dir: PathBuf,
+}
+
+impl NativeBackend {
+    /// Over `python_path`, or over the pinned venv's interpreter if there is
+    /// a pin.
+    pub(super) fn new(
+        python_path: PathBuf,
+        python_bin_dir: PathBuf,
+        pinned: Option<PinnedEnv>,
+        pin_error: Option<String>,
+        config_dir: &Path,
+    ) -> Self {
+        let (python_path, python_bin_dir) = match &pinned {
+            Some(pinned) => (pinned.python(), pinned.bin_dir()),
+            None => (python_path, python_bin_dir),
+        };
+        Self {
+            python_path,
+            python_bin_dir,
+            pinned,
+            pin_error,
+            config_dir: config_dir.to_path_buf(),
+        }
+    }
+}
+
+impl DaemonBackend for NativeBackend {
+    fn runtime(&self) -> Runtime {
+        Runtime::Native
+    }
+
+    fn prepare(&self) -> Prepare<'_> {
+        Box::pin(async move {
+            debug!("Python path: {:?}", self.python_path);
+            debug!("Python bin: {:?}", self.python_bin_dir);
+            if let Some(e) = &self.pin_error {
+                bail!("{e}");
+            }
+            if let Some(pinned) = &self.pinned {
+                pinned.ensure().await?;
+            }
+            if !self.python_path.exists() {
+                bail!("Python not found at {:?}", self.python_path);
+            }
+            super::extras::ensure(&self.python_path, &self.config_dir).await;
+            Ok(())
+        })
+    }
+
+    /// The interpreter running the backend at `address`. The supervisor
+    /// lowers its priority after the spawn.
+    fn command(&self, config_dir: &Path, address: SocketAddr, _low_priority: bool) -> Command {
+        let mut cmd = Command::new(&self.python_path);
+        cmd.args(["-m", "esphome_device_builder"])
+            .arg(config_dir.to_str().unwrap_or("."))
+            .args(["--host", &address.ip().to_string()])
+            .args(["--port", &address.port().to_string()]);
+        cmd
+    }
+
+    fn host_python(&self) -> Option<&PathBuf> {
+        Some(&self.python_path)
+    }
+
+    fn pinned_esphome(&self) -> Option<&str> {
+        self.pinned.as_ref().map(|p| p.version.as_str())
+    }
+}
+
+/// `sh` script creating a venv at `venv` and installing the backend into it,
+/// skipped once the venv's python can import the backend. For the runtimes
+/// that provision their own venv on a POSIX system.
+pub(super) fn provision_script(venv: &str) -> String {
+    format!(
+        "{venv}/bin/python -c 'import esphome_device_builder' 2>/dev/null && exit 0; \
+         python3 -m venv {venv} && {venv}/bin/python -m pip install --upgrade {BACKEND_PACKAGE}"
+    )
+}
+
+/// `sh` script replacing itself with the backend from `venv`, serving
+/// `config_dir` on `host`:`port`. `nice`/`ionice` exec the backend when
+/// `low_priority` is set, so its compiles inherit both.
+pub(super) fn launch_script(
+    venv: &str,
+    config_dir: &str,
+    host: &str,
+    port: u16,
+    low_priority: bool,
+) -> String {
+    let priority = if low_priority {
+        "nice -n 10 ionice -c 2 -n 7 "
+    } else {
+        ""
+    };
+    format!(
+        "exec {priority}{venv}/bin/python -m esphome_device_builder {} --host {host} --port {port}",
+        sh_quote(config_dir)
+    )
+}
+
+/// `value` as one single-quoted `sh` word.
+pub(super) fn sh_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// `value` trimmed, or `None` if that leaves nothing.
+pub(super) fn non_blank(value: Option<&str>) -> Option<String> {
+    value
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+}
+
+/// Fail a start whose runtime is missing the setting it needs.
+pub(super) fn require<'a>(value: &'a Option<String>, setting: &str) -> Result<&'a str> {
+    value
+        .as_deref()
+        .with_context(|| format!("`{setting}` must be set for this runtime"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_survive_the_shell() {
+        assert_eq!(sh_quote("it's"), r"'it'\''s'");
+        let script = launch_script("/venv", "/home/me/my configs", "0.0.0.0", 6052, true);
+        assert_eq!(
+            script,
+            "exec nice -n 10 ionice -c 2 -n 7 /venv/bin/python -m esphome_device_builder \
+             '/home/me/my configs' --host 0.0.0.0 --port 6052"
+        );
+    }
+
+    #[test]
+    fn the_native_runtime_runs_the_bundled_python() {
+        let dir = std::env::temp_dir().join("esphome-desktop-native-backend");
+        let backend = from_settings(
+            &Settings::default(),
+            &dir,
+            &dir,
+            PathBuf::from("/opt/python/bin/python3"),
+            PathBuf::from("/opt/python/bin"),
+        );
+        assert_eq!(backend.runtime(), Runtime::Native);
+        assert_eq!(
+            backend.host_python(),
+            Some(&PathBuf::from("/opt/python/bin/python3"))
+        );
+        assert_eq!(backend.pinned_esphome(), None);
+    }
+
+    #[test]
+    fn other_runtimes_have_no_host_python() {
+        let dir = std::env::temp_dir();
+        for runtime in [Runtime::Docker, Runtime::Ssh, Runtime::Remote] {
+            let settings = Settings {
+                runtime,
+                ..Settings::default()
+            };
+            let backend = from_settings(&settings, &dir, &dir, dir.clone(), dir.clone());
+            assert_eq!(backend.runtime(), runtime);
+            assert!(backend.host_python().is_none());
+        }
+    }
+}
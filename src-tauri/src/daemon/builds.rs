@@ -0,0 +1,126 @@
+//! Finding and cancelling the dashboard's running compile jobs.
+//!
+//! The dashboard runs each compile as a child `esphome` process, and closing
+//! the browser tab that started it doesn't stop it. The dashboard has no API
+//! to cancel one, so jobs are found in the process tree instead: a direct
+//! child of the backend counts as a build while a compiler, linker or build
+//! tool is running somewhere under it. That leaves alone the other children
+//! the dashboard keeps around (log streams, config validation).
+
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::platform::{self, ProcInfo};
+
+/// Snapshots to take before concluding nothing is building. A compile spends
+/// short stretches between tool invocations in PlatformIO's own Python, where
+/// a single snapshot would miss it.
+const SNAPSHOT_ATTEMPTS: u32 = 5;
+
+/// Delay between those snapshots.
+const SNAPSHOT_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Executables that only run as part of a build, matched against the name
+/// without its `.exe` suffix. Toolchain binaries carry a target prefix
+/// (`xtensa-esp32-elf-gcc`, `riscv32-esp-elf-ld`), hence the suffix match.
+const BUILD_TOOL_SUFFIXES: &[&str] = &[
+    "gcc", "g++", "cc1", "cc1plus", "as", "ld", "ar", "cmake", "ninja", "scons", "esptool",
+];
+
+/// Whether `name` is a compiler, linker or build tool.
+fn is_build_tool(name: &str) -> bool {
+    let name = name.strip_suffix(".exe").unwrap_or(name);
+    BUILD_TOOL_SUFFIXES
+        .iter()
+        .any(|tool| name == *tool || name.ends_with(&format!("-{tool}")))
+}
+
+/// The backend's direct children that are running a build.
+pub(super) fn active_builds(table: &[ProcInfo], backend_pid: u32) -> Vec<&ProcInfo> {
+    table
+        .iter()
+        .filter(|p| p.ppid == backend_pid && p.pid != backend_pid)
+        .filter(|job| {
+            platform::descendants(table, job.pid)
+                .iter()
+                .any(|p| is_build_tool(&p.name))
+        })
+        .collect()
+}
+
+/// Whether anything is building under `backend_pid`, sampled like
+/// [`cancel_builds`]. Blocking for the same reason.
+pub(super) fn any_builds(backend_pid: u32) -> Result<bool> {
+    for attempt in 1..=SNAPSHOT_ATTEMPTS {
+        let table = platform::process_table()?;
+        if !active_builds(&table, backend_pid).is_empty() {
+            return Ok(true);
+        }
+        if attempt < SNAPSHOT_ATTEMPTS {
+            std::thread::sleep(SNAPSHOT_INTERVAL);
+        }
+    }
+    Ok(false)
+}
+
+/// Terminate every running build job under `backend_pid`, tools first, and
+/// return how many there were. Blocking: it shells out to `ps` on Unix.
+pub(super) fn cancel_builds(backend_pid: u32) -> Result<usize> {
+    for attempt in 1..=SNAPSHOT_ATTEMPTS {
+        let table = platform::process_table()?;
+        let jobs = active_builds(&table, backend_pid);
+        if !jobs.is_empty() {
+            for job in &jobs {
+                tracing::info!("Cancelling build job {} (pid {})", job.name, job.pid);
+                let mut pids = vec![job.pid];
+                pids.extend(platform::descendants(&table, job.pid).iter().map(|p| p.pid));
+                platform::terminate_pids(&pids);
+            }
+            return Ok(jobs.len());
+        }
+        if attempt < SNAPSHOT_ATTEMPTS {
+            std::thread::sleep(SNAPSHOT_INTERVAL);
+        }
+    }
+    Ok(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proc(pid: u32, ppid: u32, name: &str) -> ProcInfo {
+        ProcInfo {
+            pid,
+            ppid,
+            cpu_time: Duration::ZERO,
+            name: name.into(),
+        }
+    }
+
+    #[test]
+    fn toolchain_binaries_count_as_build_tools() {
+        assert!(is_build_tool("xtensa-esp32-elf-g++"));
+        assert!(is_build_tool("riscv32-esp-elf-gcc.exe"));
+        assert!(is_build_tool("ninja"));
+        assert!(!is_build_tool("python3"));
+        // Suffix match is on a dash boundary, not any substring.
+        assert!(!is_build_tool("bash"));
+    }
+
+    #[test]
+    fn only_children_running_tools_are_builds() {
+        let table = vec![
+            proc(100, 1, "python3"),
+            // A compile: esphome -> scons (python) -> compiler.
+            proc(200, 100, "esphome"),
+            proc(201, 200, "python3"),
+            proc(202, 201, "xtensa-esp32-elf-g++"),
+            // A log stream, left alone.
+            proc(300, 100, "esphome"),
+        ];
+        let pids: Vec<u32> = active_builds(&table, 100).iter().map(|p| p.pid).collect();
+        assert_eq!(pids, vec![200]);
+    }
+}
@@ -0,0 +1,423 @@
+//! Construction of the device builder command.
+//!
+//! Split out of `mod.rs` to keep that file under the file-size cap.
+
+use anyhow::Result;
+use std::fs::File;
+use std::process::Stdio;
+use tokio::process::Command;
+use tracing::{info, warn};
+
+use super::{DaemonError, DaemonManager, BACKEND_NAME, DASHBOARD_LOG_NAME, LOG_HISTORY};
+use crate::platform;
+use crate::settings::Settings;
+
+/// Timezone and locale overrides from settings, handed to the backend and so
+/// to every compile it runs. Some components bake the build machine's local
+/// time into the firmware, which confuses users whose devices live elsewhere.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LocaleEnv {
+    timezone: Option<String>,
+    locale: Option<String>,
+}
+
+impl LocaleEnv {
+    /// The overrides from settings; blank values count as unset.
+    pub(super) fn from_settings(settings: &Settings) -> Self {
+        let non_blank = |v: &Option<String>| {
+            v.as_deref()
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+        };
+        Self {
+            timezone: non_blank(&settings.timezone),
+            locale: non_blank(&settings.locale),
+        }
+    }
+
+    /// Set the overrides on the backend command. Unset ones are left alone so
+    /// the backend inherits ours.
+    fn apply(&self, cmd: &mut Command) {
+        if let Some(tz) = &self.timezone {
+            cmd.env("TZ", tz);
+        }
+        if let Some(locale) = &self.locale {
+            cmd.env("LANG", locale);
+            cmd.env("LC_ALL", locale);
+        }
+    }
+
+    /// The `TZ` the backend runs with: the override, else the inherited one.
+    /// `None` means the backend falls back to the system timezone.
+    pub(crate) fn effective_timezone(&self) -> Option<String> {
+        self.timezone
+            .clone()
+            .or_else(|| std::env::var("TZ").ok().filter(|v| !v.is_empty()))
+    }
+
+    /// The locale the backend runs with, resolved the way libc does it
+    /// (`LC_ALL` before `LANG`). `None` means the system default.
+    pub(crate) fn effective_locale(&self) -> Option<String> {
+        self.locale.clone().or_else(|| {
+            ["LC_ALL", "LANG"]
+                .into_iter()
+                .find_map(|k| std::env::var(k).ok().filter(|v| !v.is_empty()))
+        })
+    }
+}
+
+/// Environment capping a compile at `jobs` parallel jobs, for each build tool
+/// a compile can reach: SCons (PlatformIO's builder), CMake-driven ESP-IDF
+/// builds, and anything that shells out to `make`.
+fn build_jobs_env(jobs: u32) -> [(&'static str, String); 3] {
+    [
+        ("SCONSFLAGS", format!("-j{jobs}")),
+        ("CMAKE_BUILD_PARALLEL_LEVEL", jobs.to_string()),
+        ("MAKEFLAGS", format!("-j{jobs}")),
+    ]
+}
+
+impl DaemonManager {
+    /// Build the command that launches the backend in the configured runtime,
+    /// with stdout/stderr going to a freshly rotated `dashboard.log` and the
+    /// environment the backend expects. Has the runtime get ready first:
+    /// provision its venv, or verify the bundled Python exists.
+    pub(super) async fn build_command(&self) -> Result<Command, DaemonError> {
+        self.backend.prepare().await.map_err(DaemonError::Prepare)?;
+
+        // Open log file for stdout and stderr combined.
+        //
+        // `File::create` truncates, so without rotating first every start wipes
+        // the previous run's logs — leaving nothing to inspect after a failed
+        // restart (issue #203). Rotate the prior `dashboard.log` to a numbered
+        // backup first; best-effort, since losing old logs must never block the
+        // backend from starting.
+        let log_path = self.logs_dir.join(DASHBOARD_LOG_NAME);
+        if let Err(e) = crate::util::rotate_log(&log_path, LOG_HISTORY) {
+            warn!("Failed to rotate {:?}: {}", log_path, e);
+        }
+        let log_file = File::create(&log_path).map_err(DaemonError::Log)?;
+        let log_file_clone = log_file.try_clone().map_err(DaemonError::Log)?;
+
+        info!("{} logs: {:?}", BACKEND_NAME, log_path);
+
+        // Build the command
+        let backend_address = self.backend_address.claim();
+        let mut cmd = self
+            .backend
+            .command(&self.config_dir, backend_address, self.low_priority);
+        if self.backend.host_python().is_some() {
+            self.sandbox(&mut cmd);
+        }
+        cmd
+            // Set working directory to config dir (required for PlatformIO)
+            .current_dir(&self.config_dir)
+            // Redirect stdout/stderr to single log file
+            .stdout(Stdio::from(log_file))
+            .stderr(Stdio::from(log_file_clone));
+
+        // Give the daemon a null stdin instead of inheriting ours. The
+        // dashboard/device-builder never reads stdin, so there is no reason to
+        // hold a handle to it on any platform.
+        //
+        // On Windows this is also load-bearing for restart: the shutdown path
+        // calls `platform::send_ctrl_break`, whose `AttachConsole`/`FreeConsole`
+        // dance mutates this (GUI, console-less) process's standard handles.
+        // `STD_INPUT_HANDLE` starts out NULL but is left dangling once we attach
+        // to and then free the child's console. A subsequent restart respawn
+        // would inherit that invalid handle, and because stdout/stderr are
+        // redirected (so `STARTF_USESTDHANDLES` is set and *all three* handles
+        // must be valid) `CreateProcess` fails with ERROR_INVALID_HANDLE (os
+        // error 6) — leaving the daemon dead after every restart. Pinning stdin
+        // to a known-good handle makes the spawn independent of our
+        // console-handle state.
+        cmd.stdin(Stdio::null());
+
+        // On Unix, intentionally NOT setting `kill_on_drop(true)`. That
+        // would have tokio send SIGKILL to the Child when it gets
+        // dropped (either when stop()'s wait times out, or when
+        // AppState drops at process teardown), which force-kills the
+        // dashboard and corrupts its state. Our Unix shutdown is
+        // SIGTERM only — see `stop()` and `terminate_blocking()`.
+        //
+        // On Windows the graceful signal is CTRL_BREAK_EVENT (see `stop()`
+        // and `terminate_blocking()`), with TerminateProcess as the hard
+        // fallback. Keep `kill_on_drop(true)` as a last-ditch drop-time net
+        // for any path that drops the Child without going through those
+        // (note it does NOT fire on the normal quit path, which calls
+        // `std::process::exit()` and skips Drop).
+        #[cfg(windows)]
+        cmd.kill_on_drop(true);
+
+        // Create new process group on Unix so we can kill all children
+        #[cfg(unix)]
+        cmd.process_group(0);
+
+        // Prevent a console window from staying open on Windows, and put the
+        // child in its own process group so we can later deliver a graceful
+        // CTRL_BREAK_EVENT to it on shutdown (see daemon stop/terminate).
+        platform::configure_daemon_tokio_command(&mut cmd);
+
+        // Keep the managed interpreter on its own tree: a stale package in the
+        // user site directory otherwise shadows our pinned one and the backend
+        // dies at import before it can serve anything (#318). A backend
+        // elsewhere runs from a venv of its own, which the host's Python env
+        // never reaches.
+        if self.backend.host_python().is_some() {
+            platform::isolate_python_tokio_command(&mut cmd);
+        }
+
+        // Set environment variables
+        cmd.env("ESPHOME_DASHBOARD", "1");
+        // Surface the desktop app version to the backend so it can be shown
+        // in the frontend (e.g. an "About" page).
+        if let Some(app) = &self.app_handle {
+            cmd.env(
+                "ESPHOME_DESKTOP_VERSION",
+                app.package_info().version.to_string(),
+            );
+        }
+        // Tell the backend where the esphome-desktop CLI lives so the dashboard
+        // can check for and trigger updates through the stable `api` interface
+        // (esphome-desktop api check-update / api update). Set beside the other
+        // backend env vars and re-applied on every respawn like them; the
+        // backend's own child processes inherit it too. See control::client.
+        if let Some(bin) = crate::control::cli_invocation_path() {
+            cmd.env("ESPHOME_DESKTOP_BIN", bin);
+        }
+
+        self.locale_env.apply(&mut cmd);
+        crate::network::apply_env(&mut cmd);
+        if let Some(jobs) = self.build_jobs {
+            cmd.envs(build_jobs_env(jobs));
+        }
+
+        // On Windows, force the spawned Python (and any subprocesses it
+        // spawns for compile/logs) to use UTF-8 for stdin/stdout/stderr.
+        // Without this, Python falls back to the locale codec (cp1252 on
+        // Western installs) when stdout is a redirected pipe — which the
+        // dashboard always is — and any non-ASCII output (e.g. the wifi
+        // signal-bar block characters U+2582..U+2588) raises
+        // UnicodeEncodeError and drops the device's log connection.
+        #[cfg(target_os = "windows")]
+        cmd.env("PYTHONIOENCODING", "utf-8");
+
+        Ok(cmd)
+    }
+
+    /// A one-off `python -m esphome <args>` against the config directory, in
+    /// the backend's environment (isolation, locale, build jobs) so a batch
+    /// compile builds the same firmware the dashboard would. Output is piped
+    /// for the caller. Native runtime only: the others have no host-side
+    /// interpreter to run.
+    pub(crate) fn esphome_command<S: AsRef<std::ffi::OsStr>>(
+        &self,
+        args: impl IntoIterator<Item = S>,
+    ) -> Result<Command> {
+        let Some(python) = self.backend.host_python() else {
+            anyhow::bail!(
+                "batch device actions aren't supported with the {} runtime",
+                self.runtime()
+            );
+        };
+        self.esphome_command_in(python, &self.config_dir, args)
+    }
+
+    /// [`Self::esphome_command`] with another interpreter and config
+    /// directory: a comparison [`Workspace`](super::Workspace)'s.
+    pub(crate) fn esphome_command_in<S: AsRef<std::ffi::OsStr>>(
+        &self,
+        python: &std::path::Path,
+        config_dir: &std::path::Path,
+        args: impl IntoIterator<Item = S>,
+    ) -> Result<Command> {
+        let mut cmd = self.python_command_in(python, config_dir)?;
+        cmd.args(["-m", "esphome"]).args(args);
+        Ok(cmd)
+    }
+
+    /// The bundled interpreter, set up like [`Self::esphome_command`], for a
+    /// script that drives one of ESPHome's libraries directly.
+    pub(crate) fn python_command(&self) -> Result<Command> {
+        let Some(python) = self.backend.host_python() else {
+            anyhow::bail!("this isn't supported with the {} runtime", self.runtime());
+        };
+        self.python_command_in(python, &self.config_dir)
+    }
+
+    /// `words` as the terminal window runs them, set up like
+    /// [`Self::esphome_command`] with the interpreter's directories first on
+    /// `PATH`, so `python`, `pip` and `esphome` are the backend's own. The
+    /// program is found on that `PATH`; there is no shell.
+    pub(crate) fn terminal_command(&self, words: &[String]) -> Result<Command> {
+        let Some(python) = self.backend.host_python() else {
+            anyhow::bail!(
+                "the terminal needs the native runtime, not {}",
+                self.runtime()
+            );
+        };
+        let Some((program, args)) = words.split_first() else {
+            anyhow::bail!("no command to run");
+        };
+        let dir = python.parent().unwrap_or(std::path::Path::new("."));
+        // Windows keeps the console scripts in the interpreter's Scripts
+        // folder (a venv's interpreter is in it already); elsewhere they share
+        // `bin` with it.
+        let mut dirs = vec![dir.to_path_buf()];
+        if cfg!(target_os = "windows") {
+            dirs.push(dir.join("Scripts"));
+        }
+        dirs.extend(std::env::split_paths(
+            &std::env::var_os("PATH").unwrap_or_default(),
+        ));
+        let path = std::env::join_paths(dirs)?;
+        let program = which_in(program, &path)
+            .ok_or_else(|| anyhow::anyhow!("{program} not found on the terminal's PATH"))?;
+        let mut cmd = self.command_in(&program, &self.config_dir);
+        cmd.args(args).env("PATH", path);
+        Ok(cmd)
+    }
+
+    fn python_command_in(
+        &self,
+        python: &std::path::Path,
+        config_dir: &std::path::Path,
+    ) -> Result<Command> {
+        if !python.exists() {
+            anyhow::bail!("Python not found at {:?}", python);
+        }
+        Ok(self.command_in(python, config_dir))
+    }
+
+    /// `program` in `config_dir` with the backend's environment.
+    fn command_in(&self, program: &std::path::Path, config_dir: &std::path::Path) -> Command {
+        let mut cmd = Command::new(program);
+        self.sandbox(&mut cmd);
+        cmd.current_dir(config_dir)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        // Own process group, so `lower_priority` can reach the compilers too.
+        #[cfg(unix)]
+        cmd.process_group(0);
+        platform::configure_no_window_tokio_command(&mut cmd);
+        platform::isolate_python_tokio_command(&mut cmd);
+        self.locale_env.apply(&mut cmd);
+        crate::network::apply_env(&mut cmd);
+        if let Some(jobs) = self.build_jobs {
+            cmd.envs(build_jobs_env(jobs));
+        }
+        #[cfg(target_os = "windows")]
+        cmd.env("PYTHONIOENCODING", "utf-8");
+        cmd
+    }
+
+    /// Reduce what a native backend command may do (`sandbox_backend`): scrub
+    /// its environment and, on Linux, confine its writes. Call before setting
+    /// the backend's own variables. A backend elsewhere is left alone; the
+    /// distro, container or machine it runs in is its own boundary.
+    pub(super) fn sandbox(&self, cmd: &mut Command) {
+        if !self.sandbox {
+            return;
+        }
+        platform::scrub_env_tokio_command(cmd);
+        #[cfg(target_os = "linux")]
+        {
+            let mut writable = vec![self.config_dir.clone(), self.logs_dir.clone()];
+            if let Some(app) = &self.app_handle {
+                // Pinned venvs, the Python tree and comparison copies.
+                writable.extend(
+                    [
+                        platform::get_data_dir(app),
+                        platform::get_python_parent_dir(app),
+                        platform::get_compare_dir(app),
+                    ]
+                    .into_iter()
+                    .filter_map(Result::ok),
+                );
+            }
+            writable.extend(platform::home_write_dirs());
+            platform::confine_writes_tokio_command(cmd, &writable);
+        }
+    }
+
+    /// Whether builds should run at background priority.
+    pub(crate) fn low_priority(&self) -> bool {
+        self.low_priority
+    }
+}
+
+/// `program` as found on `path`, or as given when it names a file itself.
+/// `Command` would search the app's own `PATH` rather than the one it is
+/// handed.
+fn which_in(program: &str, path: &std::ffi::OsStr) -> Option<std::path::PathBuf> {
+    let given = std::path::Path::new(program);
+    if given.components().count() > 1 {
+        return given.is_file().then(|| given.to_path_buf());
+    }
+    let names: Vec<String> = if cfg!(target_os = "windows") && given.extension().is_none() {
+        vec![
+            format!("{program}.exe"),
+            format!("{program}.cmd"),
+            format!("{program}.bat"),
+        ]
+    } else {
+        vec![program.to_string()]
+    };
+    std::env::split_paths(path)
+        .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+        .find(|candidate| candidate.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blank_overrides_are_unset() {
+        let env = LocaleEnv::from_settings(&Settings {
+            timezone: Some("  ".into()),
+            locale: Some(String::new()),
+            ..Settings::default()
+        });
+        assert!(env.timezone.is_none());
+        assert!(env.locale.is_none());
+    }
+
+    #[test]
+    fn overrides_win_over_the_inherited_environment() {
+        let env = LocaleEnv::from_settings(&Settings {
+            timezone: Some(" Europe/Berlin ".into()),
+            locale: Some("de_DE.UTF-8".into()),
+            ..Settings::default()
+        });
+        assert_eq!(env.effective_timezone().as_deref(), Some("Europe/Berlin"));
+        assert_eq!(env.effective_locale().as_deref(), Some("de_DE.UTF-8"));
+    }
+
+    #[test]
+    fn build_jobs_reach_every_build_tool() {
+        let env = build_jobs_env(6);
+        assert!(env.contains(&("SCONSFLAGS", "-j6".into())));
+        assert!(env.contains(&("CMAKE_BUILD_PARALLEL_LEVEL", "6".into())));
+        assert!(env.contains(&("MAKEFLAGS", "-j6".into())));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn terminal_programs_are_found_on_the_given_path() {
+        let first = crate::util::unique_temp_dir("terminal-path-first");
+        let second = crate::util::unique_temp_dir("terminal-path-second");
+        std::fs::write(second.join("esphome"), "").unwrap();
+        let path = std::env::join_paths([&first, &second]).unwrap();
+        assert_eq!(which_in("esphome", &path), Some(second.join("esphome")));
+
+        std::fs::write(first.join("esphome"), "").unwrap();
+        assert_eq!(which_in("esphome", &path), Some(first.join("esphome")));
+        assert_eq!(which_in("pip", &path), None);
+        let _ = std::fs::remove_dir_all(&first);
+        let _ = std::fs::remove_dir_all(&second);
+    }
+}
@@ -0,0 +1,221 @@
+//! A second, temporary dashboard on another ESPHome release, beside the main
+//! one, for chasing a regression: the same configs validated and compiled by
+//! both releases, in two browser tabs.
+//!
+//! It runs on a copy of the config directory, made fresh on each start under
+//! `compare/config` in the app's cache directory, so the other release can't
+//! rewrite the real configs or their build state. Its ESPHome comes from the same
+//! per-version venvs as pins (see [`super::pinned`]). It isn't supervised like
+//! the main dashboard (no restarts, no watchdog) and it goes away with the
+//! app. Native runtime only.
+//!
+//! Build diffs ([`crate::devices::diff`]) prepare their copies the same way.
+
+use anyhow::{bail, Context, Result};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Mutex;
+use tauri::AppHandle;
+use tokio::process::{Child, Command};
+use tracing::{info, warn};
+
+use super::pinned::{check_version, PinnedEnv};
+use super::DaemonManager;
+use crate::platform;
+
+/// The comparison dashboard's log, beside `dashboard.log`.
+const LOG_NAME: &str = "compare.log";
+
+/// The comparison dashboard, if one is running.
+#[derive(Default)]
+pub struct Comparison {
+    running: Mutex<Option<Running>>,
+}
+
+struct Running {
+    version: String,
+    port: u16,
+    child: Child,
+}
+
+impl Comparison {
+    /// The running comparison's ESPHome version and port. One that exited
+    /// on its own (a port in use, a crash) is forgotten.
+    pub(crate) fn current(&self) -> Option<(String, u16)> {
+        let mut running = self.lock();
+        if let Some(r) = running.as_mut() {
+            if !matches!(r.child.try_wait(), Ok(None)) {
+                warn!("Comparison dashboard (ESPHome {}) exited", r.version);
+                *running = None;
+            }
+        }
+        running.as_ref().map(|r| (r.version.clone(), r.port))
+    }
+
+    /// Start a dashboard on ESPHome `version` and `port`, replacing a
+    /// comparison already running. The first start of a version installs it,
+    /// which takes a few minutes.
+    pub(crate) async fn start(
+        &self,
+        app: &AppHandle,
+        daemon: &DaemonManager,
+        version: &str,
+        port: u16,
+    ) -> Result<()> {
+        if port == daemon.port() {
+            bail!("port {port} is the main dashboard's; pick another");
+        }
+        self.stop();
+        let Workspace { python, config_dir } =
+            Workspace::prepare(app, daemon, version, "config").await?;
+
+        let log_path = daemon.logs_dir().join(LOG_NAME);
+        let log = File::create(&log_path).context("Failed to create the comparison log")?;
+        let mut cmd = Command::new(python);
+        cmd.args(["-m", "esphome_device_builder"])
+            .arg(&config_dir)
+            .args(["--host", "127.0.0.1", "--port", &port.to_string()])
+            .current_dir(&config_dir)
+            .stdin(Stdio::null())
+            .stdout(Stdio::from(log.try_clone()?))
+            .stderr(Stdio::from(log))
+            .env("ESPHOME_DASHBOARD", "1");
+        daemon.sandbox(&mut cmd);
+        // Own process group, like the main dashboard, so its compiles are
+        // its descendants and `stop` can find them.
+        #[cfg(unix)]
+        cmd.process_group(0);
+        platform::configure_daemon_tokio_command(&mut cmd);
+        platform::isolate_python_tokio_command(&mut cmd);
+        let child = cmd
+            .spawn()
+            .context("Failed to start the comparison dashboard")?;
+        info!(
+            "Comparison dashboard (ESPHome {}) on port {}, logs: {:?}",
+            version, port, log_path
+        );
+        *self.lock() = Some(Running {
+            version: version.to_string(),
+            port,
+            child,
+        });
+        Ok(())
+    }
+
+    /// Stop the comparison dashboard and any compile it is running. Its
+    /// config copy stays until the next start.
+    pub(crate) fn stop(&self) {
+        let Some(running) = self.lock().take() else {
+            return;
+        };
+        let Some(pid) = running.child.id() else {
+            return;
+        };
+        let mut pids = vec![pid];
+        match platform::process_table() {
+            Ok(table) => pids.extend(platform::descendants(&table, pid).iter().map(|p| p.pid)),
+            Err(e) => warn!("Couldn't list the comparison dashboard's builds: {:#}", e),
+        }
+        platform::terminate_pids(&pids);
+        info!("Comparison dashboard (ESPHome {}) stopped", running.version);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<Running>> {
+        self.running.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// A copy of the configs and an ESPHome release to run on it.
+pub(crate) struct Workspace {
+    pub python: PathBuf,
+    pub config_dir: PathBuf,
+}
+
+impl Workspace {
+    /// Install ESPHome `version` unless an earlier pin or comparison did, and
+    /// make a fresh copy of the configs under `compare/<name>` in the cache
+    /// directory.
+    pub(crate) async fn prepare(
+        app: &AppHandle,
+        daemon: &DaemonManager,
+        version: &str,
+        name: &str,
+    ) -> Result<Self> {
+        if daemon.host_python().is_none() {
+            bail!(
+                "comparing ESPHome releases isn't supported with the {} runtime",
+                daemon.runtime()
+            );
+        }
+        check_version(version)?;
+        let data_dir = platform::get_data_dir(app)?;
+        let base_python = platform::get_python_path(app)?;
+        let env = PinnedEnv::new(version.to_string(), &data_dir, &base_python);
+        env.ensure().await?;
+
+        let config_dir = platform::get_compare_dir(app)?.join(name);
+        let (from, to) = (daemon.config_dir().clone(), config_dir.clone());
+        tokio::task::spawn_blocking(move || fresh_copy(&from, &to))
+            .await
+            .context("Copying the configs panicked")??;
+        Ok(Self {
+            python: env.python(),
+            config_dir,
+        })
+    }
+}
+
+/// Replace `to` with a copy of the configs in `from`, leaving out hidden
+/// entries: `.esphome` holds the main dashboard's builds, which the other
+/// release must not reuse, and `.git` or editor folders aren't needed.
+fn fresh_copy(from: &Path, to: &Path) -> Result<()> {
+    if to.exists() {
+        std::fs::remove_dir_all(to).with_context(|| format!("Failed to remove {to:?}"))?;
+    }
+    copy_visible(from, to)
+}
+
+fn copy_visible(from: &Path, to: &Path) -> Result<()> {
+    std::fs::create_dir_all(to).with_context(|| format!("Failed to create {to:?}"))?;
+    for entry in std::fs::read_dir(from).with_context(|| format!("Failed to read {from:?}"))? {
+        let entry = entry?;
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let (src, dst) = (entry.path(), to.join(entry.file_name()));
+        if entry.file_type()?.is_dir() {
+            copy_visible(&src, &dst)?;
+        } else {
+            std::fs::copy(&src, &dst).with_context(|| format!("Failed to copy {src:?}"))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::unique_temp_dir;
+    use std::fs;
+
+    #[test]
+    fn copies_configs_without_build_state() {
+        let from = unique_temp_dir("compare-from");
+        let to = unique_temp_dir("compare-to");
+        fs::write(from.join("porch.yaml"), "esphome:\n").unwrap();
+        fs::create_dir_all(from.join("common")).unwrap();
+        fs::write(from.join("common/wifi.yaml"), "wifi:\n").unwrap();
+        fs::create_dir_all(from.join(".esphome/build")).unwrap();
+        fs::write(to.join("stale.yaml"), "").unwrap();
+
+        fresh_copy(&from, &to).unwrap();
+        assert!(to.join("porch.yaml").exists());
+        assert!(to.join("common/wifi.yaml").exists());
+        assert!(!to.join(".esphome").exists());
+        assert!(!to.join("stale.yaml").exists());
+
+        let _ = fs::remove_dir_all(&from);
+        let _ = fs::remove_dir_all(&to);
+    }
+}
@@ -0,0 +1,180 @@
+//! Running the device builder in a Docker container.
+//!
+//! With `runtime = "docker"` the backend runs from a venv in a stock Python
+//! image (`docker_image`), for a host whose own Python can't run it or that
+//! should keep the toolchains off its filesystem. The venv is created in the
+//! `esphome-desktop` volume on first start, and PlatformIO keeps its
+//! toolchains in the same volume, so both outlive the container, which is
+//! removed when it stops. The config directory is bind-mounted at `/config`.
+//!
+//! The port is published on the loopback interface only, so the tray, the
+//! health check and the browser keep using the loopback URL and the dashboard
+//! stays off the LAN. mDNS doesn't reach a container on Docker's default
+//! network, so the dashboard can't see which devices are online.
+
+use anyhow::{Context, Result};
+use std::net::SocketAddr;
+use std::path::Path;
+use tokio::process::Command;
+use tracing::{info, warn};
+
+use super::backend::{self, DaemonBackend, Prepare};
+use crate::platform;
+use crate::settings::{Runtime, Settings};
+
+/// Image used when `docker_image` is unset.
+const DEFAULT_IMAGE: &str = "python:3.13-slim";
+
+/// The container's name, so a stop can reach it by name.
+const CONTAINER: &str = "esphome-desktop";
+
+/// Volume holding the venv and PlatformIO's toolchains, mounted at `/data`.
+const VOLUME: &str = "esphome-desktop:/data";
+
+/// The venv inside the volume.
+const VENV_DIR: &str = "/data/venv";
+
+/// Environment passed into the container from the `docker` client's, which
+/// the supervisor sets up like a native backend's. Unset ones are skipped.
+const FORWARDED_ENV: [&str; 8] = [
+    "ESPHOME_DASHBOARD",
+    "ESPHOME_DESKTOP_VERSION",
+    "TZ",
+    "LANG",
+    "LC_ALL",
+    "SCONSFLAGS",
+    "CMAKE_BUILD_PARALLEL_LEVEL",
+    "MAKEFLAGS",
+];
+
+#[derive(Debug, Clone)]
+pub(crate) struct DockerBackend {
+    image: String,
+}
+
+impl DockerBackend {
+    pub(crate) fn from_settings(settings: &Settings) -> Self {
+        Self {
+            image: backend::non_blank(settings.docker_image.as_deref())
+                .unwrap_or_else(|| DEFAULT_IMAGE.to_string()),
+        }
+    }
+
+    /// Remove a container a crash left behind, whose name would fail the
+    /// start, then create the venv in the volume unless it already has the
+    /// backend. The first run also pulls the image.
+    async fn ensure_venv(&self) -> Result<()> {
+        let mut remove = Command::new("docker");
+        remove.args(["rm", "--force", CONTAINER]);
+        platform::configure_no_window_tokio_command(&mut remove);
+        // Fails when there is nothing to remove.
+        let _ = remove.output().await;
+
+        let mut cmd = Command::new("docker");
+        cmd.args(["run", "--rm", "--volume", VOLUME, &self.image])
+            .args(["sh", "-c", &backend::provision_script(VENV_DIR)]);
+        platform::configure_no_window_tokio_command(&mut cmd);
+        info!("Checking the Docker venv ({})", self.image);
+        let output = cmd
+            .output()
+            .await
+            .context("Failed to run docker; is Docker installed?")?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "Failed to provision the venv in {}: {}",
+                self.image,
+                platform::pip_output_report(&output)
+            );
+        }
+        Ok(())
+    }
+}
+
+impl DaemonBackend for DockerBackend {
+    fn runtime(&self) -> Runtime {
+        Runtime::Docker
+    }
+
+    fn prepare(&self) -> Prepare<'_> {
+        Box::pin(self.ensure_venv())
+    }
+
+    /// `docker run` in the foreground, so the child is the client and its
+    /// output is the container's. `--init` gives the backend a PID 1 that
+    /// passes on the SIGTERM the client proxies on a stop.
+    fn command(&self, config_dir: &Path, address: SocketAddr, low_priority: bool) -> Command {
+        let port = address.port();
+        let mut cmd = Command::new("docker");
+        cmd.args(["run", "--rm", "--init", "--name", CONTAINER])
+            .args(["--publish", &format!("{address}:{port}")])
+            .args(["--volume", VOLUME])
+            .arg("--volume")
+            .arg(format!("{}:/config", config_dir.display()))
+            .args(["--env", "PLATFORMIO_CORE_DIR=/data/platformio"]);
+        for name in FORWARDED_ENV {
+            cmd.args(["--env", name]);
+        }
+        cmd.arg(&self.image).args([
+            "sh",
+            "-c",
+            &backend::launch_script(VENV_DIR, "/config", "0.0.0.0", port, low_priority),
+        ]);
+        cmd
+    }
+
+    /// The client doesn't proxy a Windows `CTRL_BREAK`, and a client that
+    /// is gone leaves the container running; ask the daemon to stop it.
+    fn terminate_blocking(&self) {
+        let mut cmd = std::process::Command::new("docker");
+        cmd.args(["kill", "--signal", "TERM", CONTAINER]);
+        platform::configure_no_window_command(&mut cmd);
+        if let Err(e) = cmd.output() {
+            warn!("Failed to signal the backend container: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(cmd: &Command) -> Vec<String> {
+        cmd.as_std()
+            .get_args()
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn publishes_the_port_on_loopback_only() {
+        let docker = DockerBackend::from_settings(&Settings {
+            docker_image: Some(" ".into()),
+            ..Settings::default()
+        });
+        let args = args(&docker.command(
+            Path::new("/home/me/esphome"),
+            ([127, 0, 0, 1], 6052).into(),
+            false,
+        ));
+        assert!(
+            args.contains(&"127.0.0.1:6052:6052".to_string()),
+            "{args:?}"
+        );
+        assert!(args.contains(&"/home/me/esphome:/config".to_string()));
+        // A blank image falls back to the default.
+        let image = args.iter().position(|a| a == DEFAULT_IMAGE).unwrap();
+        assert_eq!(args[image + 1..image + 3], ["sh", "-c"]);
+        assert!(args[image + 3].ends_with("'/config' --host 0.0.0.0 --port 6052"));
+    }
+
+    #[test]
+    fn forwards_the_backend_environment() {
+        let docker = DockerBackend::from_settings(&Settings {
+            docker_image: Some("python:3.12".into()),
+            ..Settings::default()
+        });
+        let args = args(&docker.command(Path::new("/cfg"), ([127, 0, 0, 1], 6052).into(), false));
+        assert!(args.contains(&"python:3.12".to_string()));
+        assert!(args.windows(2).any(|w| w == ["--env", "TZ"]));
+    }
+}
@@ -0,0 +1,90 @@
+//! Why a start, stop or build cancel failed.
+
+use crate::error::UserError;
+use crate::i18n::{t, t_with};
+use crate::settings::Runtime;
+
+use super::BACKEND_NAME;
+
+/// A failure of one of [`DaemonManager`](super::DaemonManager)'s lifecycle
+/// calls.
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum DaemonError {
+    /// The runtime couldn't be made ready: no interpreter, no Docker, an
+    /// unreachable host.
+    #[error(transparent)]
+    Prepare(anyhow::Error),
+    #[error("Failed to create log file: {0}")]
+    Log(std::io::Error),
+    #[error("Failed to spawn ESPHome process: {0}")]
+    Spawn(std::io::Error),
+    /// The backend ignored every stop request for the whole grace period of
+    /// `secs` and is still running.
+    #[error("timed out waiting for {} to stop after {secs} s", BACKEND_NAME)]
+    StopTimeout { secs: u64 },
+    #[error("{} is not running", BACKEND_NAME)]
+    NotRunning,
+    #[error("{action} isn't supported with the {runtime} runtime")]
+    Unsupported {
+        action: &'static str,
+        runtime: Runtime,
+    },
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+impl UserError for DaemonError {
+    fn kind(&self) -> &'static str {
+        match self {
+            DaemonError::Prepare(_) => "daemon.prepare",
+            DaemonError::Log(_) => "daemon.log",
+            DaemonError::Spawn(_) => "daemon.spawn",
+            DaemonError::StopTimeout { .. } => "daemon.stop_timeout",
+            DaemonError::NotRunning => "daemon.not_running",
+            DaemonError::Unsupported { .. } => "daemon.unsupported",
+            DaemonError::Other(_) => "daemon.other",
+        }
+    }
+
+    fn user_message(&self) -> String {
+        match self {
+            DaemonError::Prepare(e) => {
+                t_with("errors.daemon_prepare", &[("error", &e.to_string())])
+            }
+            DaemonError::Log(e) => t_with("errors.daemon_log", &[("error", &e.to_string())]),
+            DaemonError::Spawn(e) => t_with("errors.daemon_spawn", &[("error", &e.to_string())]),
+            DaemonError::StopTimeout { secs } => t_with(
+                "errors.daemon_stop_timeout",
+                &[("seconds", &secs.to_string())],
+            ),
+            DaemonError::NotRunning => t("errors.daemon_not_running"),
+            DaemonError::Unsupported { runtime, .. } => t_with(
+                "errors.daemon_unsupported",
+                &[("runtime", &runtime.to_string())],
+            ),
+            DaemonError::Other(e) => e.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_message_for_logs_keeps_the_cause() {
+        let cause = std::io::Error::from(std::io::ErrorKind::NotFound);
+        let cause_text = cause.to_string();
+        let err = DaemonError::Spawn(cause);
+        assert_eq!(err.kind(), "daemon.spawn");
+        assert_eq!(
+            err.to_string(),
+            format!("Failed to spawn ESPHome process: {cause_text}")
+        );
+        assert!(err.user_message().contains(&cause_text));
+
+        let err = DaemonError::from(anyhow::anyhow!("Python not found").context("preparing"));
+        assert_eq!(err.kind(), "daemon.other");
+        assert_eq!(format!("{err:#}"), "preparing: Python not found");
+    }
+}
@@ -0,0 +1,147 @@
+//! Extra Python packages a workspace needs.
+//!
+//! Some components import packages ESPHome doesn't depend on, `pillow` for
+//! `image:` and `animation:` above all. A `.esphome-requirements` file at the
+//! top of the config directory lists them, one pip requirement per line
+//! (`pillow`, `pillow>=10.4`), with `#` comments. Before the backend starts
+//! they are installed into the interpreter it runs on, the app's own or the
+//! pinned venv.
+//!
+//! What was installed is noted next to the interpreter, so later starts skip
+//! pip until the list changes. An ESPHome update forgets the note
+//! ([`forget`]) so the next start makes sure the update didn't take an extra
+//! away; a fresh copy of the bundled tree comes without one.
+
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+use crate::platform;
+
+/// The requirements file, at the top of the config directory.
+pub(crate) const EXTRAS_FILE: &str = ".esphome-requirements";
+
+/// The note of what was installed, next to the interpreter.
+const INSTALLED_FILE: &str = ".esphome-extras";
+
+/// The requirements in `config_dir`'s extras file; none without one. Only
+/// requirements go to pip: an option such as `--index-url` is an error.
+fn read(config_dir: &Path) -> Result<Vec<String>> {
+    let path = config_dir.join(EXTRAS_FILE);
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {path:?}")),
+    };
+    let mut requirements = Vec::new();
+    for line in text.lines() {
+        // As pip reads them: a `#` starts a comment only after whitespace,
+        // so a URL's `#egg=` fragment stays.
+        let requirement = if line.trim_start().starts_with('#') {
+            ""
+        } else {
+            line.split_once(" #")
+                .map_or(line, |(before, _)| before)
+                .trim()
+        };
+        if requirement.is_empty() {
+            continue;
+        }
+        if requirement.starts_with('-') {
+            bail!("{path:?} should list packages, not pip options like {requirement:?}");
+        }
+        requirements.push(requirement.to_string());
+    }
+    Ok(requirements)
+}
+
+fn installed_file(python: &Path) -> Option<PathBuf> {
+    Some(python.parent()?.join(INSTALLED_FILE))
+}
+
+/// Install `config_dir`'s extras into `python`'s environment unless they
+/// already were. A failure is logged and tried again at the next start; the
+/// dashboard runs without them meanwhile.
+pub(super) async fn ensure(python: &Path, config_dir: &Path) {
+    let requirements = match read(config_dir) {
+        Ok(requirements) if requirements.is_empty() => return,
+        Ok(requirements) => requirements,
+        Err(e) => {
+            warn!("Not installing the extra Python packages: {:#}", e);
+            return;
+        }
+    };
+    let note = requirements.join("\n");
+    let installed_file = installed_file(python);
+    if let Some(path) = &installed_file {
+        if std::fs::read_to_string(path).is_ok_and(|installed| installed == note) {
+            return;
+        }
+    }
+    info!(
+        "Installing the extra Python packages from {}: {}",
+        EXTRAS_FILE,
+        requirements.join(", ")
+    );
+    let mut pip = platform::pip_command(python);
+    pip.args(&requirements);
+    crate::network::apply_env(&mut pip);
+    match platform::run_pip(pip).await {
+        Ok(output) if output.status.success() => {
+            info!("Extra Python packages installed");
+            if let Some(path) = &installed_file {
+                if let Err(e) = std::fs::write(path, note) {
+                    warn!("Failed to note the extra Python packages: {}", e);
+                }
+            }
+        }
+        Ok(output) => warn!(
+            "Failed to install the extra Python packages from {}: {}",
+            EXTRAS_FILE,
+            platform::pip_output_report(&output)
+        ),
+        Err(e) => warn!("Failed to run pip for the extra Python packages: {}", e),
+    }
+}
+
+/// Have the next start install the extras into `python`'s environment
+/// again, after an update that may have replaced or removed them.
+pub(crate) fn forget(python: &Path) {
+    let Some(path) = installed_file(python) else {
+        return;
+    };
+    if let Err(e) = std::fs::remove_file(&path) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            warn!("Failed to remove {:?}: {}", path, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::unique_temp_dir;
+    use std::fs;
+
+    #[test]
+    fn reads_requirements_and_rejects_pip_options() {
+        let dir = unique_temp_dir("extras");
+        assert!(read(&dir).unwrap().is_empty());
+
+        fs::write(
+            dir.join(EXTRAS_FILE),
+            "# for image: and animation:\npillow>=10.4  # 11 breaks fonts\n\n  cairosvg\n",
+        )
+        .unwrap();
+        assert_eq!(read(&dir).unwrap(), ["pillow>=10.4", "cairosvg"]);
+
+        fs::write(
+            dir.join(EXTRAS_FILE),
+            "pillow\n--index-url https://example.com\n",
+        )
+        .unwrap();
+        assert!(read(&dir).is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
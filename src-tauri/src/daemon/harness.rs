@@ -0,0 +1,128 @@
+//! A [`DaemonManager`] over a [`FakeVenv`], and the start/stop/health tests
+//! that drive it.
+//!
+//! The fake backend only sleeps, so the port it is given belongs to a
+//! [`crate::testing::MockDashboard`] and the health probes see whatever the
+//! mock is told to answer.
+
+use super::*;
+use crate::error::UserError;
+use crate::testing::FakeVenv;
+
+impl DaemonManager {
+    /// A native-runtime manager running `venv`'s interpreter against a
+    /// config directory and logs inside it, on `port`. Its stops are short:
+    /// a mock dashboard agrees to shut down and the fake backend never does.
+    pub(crate) fn for_test(venv: &FakeVenv, port: u16) -> Self {
+        let config_dir = venv.root().join("config");
+        let logs_dir = venv.root().join("logs");
+        std::fs::create_dir_all(&config_dir).unwrap();
+        std::fs::create_dir_all(&logs_dir).unwrap();
+        Self {
+            stop_grace: Duration::from_secs(3),
+            ..Self::standalone(venv.python(), venv.bin_dir(), config_dir, logs_dir, port)
+        }
+    }
+}
+
+#[cfg(unix)]
+mod tests {
+    use super::*;
+    use crate::testing::MockDashboard;
+    use std::time::Duration;
+
+    /// For tests that never probe: the fake backend doesn't listen.
+    const UNSERVED_PORT: u16 = 6052;
+
+    /// Poll `check` for up to five seconds.
+    async fn eventually(check: impl Fn() -> bool) -> bool {
+        for _ in 0..50 {
+            if check() {
+                return true;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        check()
+    }
+
+    #[tokio::test]
+    async fn starts_and_stops_the_backend() {
+        let venv = FakeVenv::new();
+        let dashboard = MockDashboard::start();
+        let daemon = DaemonManager::for_test(&venv, dashboard.port());
+
+        daemon.start().await.unwrap();
+        assert!(daemon.is_running());
+        assert!(crate::wait_for_dashboard_ready(daemon.address(), 5).await);
+        let port_arg = format!("--port {}", dashboard.port());
+        assert!(
+            eventually(|| venv.calls().iter().any(|call| call.contains(&port_arg))).await,
+            "backend never launched: {:?}",
+            venv.calls()
+        );
+        assert!(daemon.stats().uptime.is_some());
+
+        daemon.stop().await.unwrap();
+        assert!(!daemon.is_running());
+        assert_eq!(daemon.stats().uptime, None);
+        // A native backend is asked over HTTP before any signal.
+        assert!(dashboard
+            .requests()
+            .iter()
+            .any(|line| line.starts_with("POST /shutdown ")));
+        // A second stop is a no-op.
+        daemon.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn restarting_rotates_the_log_and_counts() {
+        let venv = FakeVenv::new();
+        let daemon = DaemonManager::for_test(&venv, UNSERVED_PORT);
+
+        daemon.start().await.unwrap();
+        daemon.restart().await.unwrap();
+        assert!(daemon.is_running());
+        assert!(daemon.logs_dir().join("dashboard.log.1").exists());
+        assert_eq!(daemon.stats().restarts, 1);
+        daemon.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn notices_a_crashed_backend() {
+        let venv = FakeVenv::new();
+        venv.crash_dashboard(3);
+        let daemon = DaemonManager::for_test(&venv, UNSERVED_PORT);
+
+        daemon.start().await.unwrap();
+        assert!(eventually(|| !daemon.is_running()).await);
+        let crash = daemon.stats().last_crash.expect("crash not recorded");
+        assert!(crash.contains('3'), "{crash}");
+    }
+
+    #[tokio::test]
+    async fn health_follows_the_dashboard() {
+        let dashboard = MockDashboard::start();
+        let address = SocketAddr::from((Ipv4Addr::LOCALHOST, dashboard.port()));
+        assert!(health_check(address).await.unwrap());
+
+        dashboard.set_status(503);
+        assert!(!health_check(address).await.unwrap());
+        assert!(!crate::wait_for_dashboard_ready(address, 1).await);
+
+        // Gone altogether, like a dead backend.
+        drop(dashboard);
+        assert!(!health_check(address).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn refuses_to_start_without_an_interpreter() {
+        let venv = FakeVenv::new();
+        let daemon = DaemonManager::for_test(&venv, UNSERVED_PORT);
+        std::fs::remove_file(venv.python()).unwrap();
+
+        let err = daemon.start().await.unwrap_err();
+        assert!(err.to_string().contains("Python not found"), "{err}");
+        assert_eq!(err.kind(), "daemon.prepare");
+        assert!(!daemon.is_running());
+    }
+}
@@ -0,0 +1,140 @@
+//! Probing whether the dashboard answers, and the URLs it is reached at.
+
+use anyhow::Result;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{debug, warn};
+
+use super::stats::Stats;
+use crate::tasks::Tasks;
+
+/// Time between the periodic health checks.
+const INTERVAL: Duration = Duration::from_secs(30);
+
+/// Where to connect to a dashboard listening on `address`: the loopback of
+/// the same family for one listening on every interface (`0.0.0.0`, `::`).
+pub(crate) fn reachable(address: SocketAddr) -> SocketAddr {
+    match address.ip() {
+        IpAddr::V4(ip) if ip.is_unspecified() => (Ipv4Addr::LOCALHOST, address.port()).into(),
+        IpAddr::V6(ip) if ip.is_unspecified() => (Ipv6Addr::LOCALHOST, address.port()).into(),
+        _ => address,
+    }
+}
+
+/// Build the URL used to probe the dashboard listening on `address` (both
+/// the startup readiness poll and the periodic health check), an IPv6
+/// address in brackets.
+///
+/// The literal address the backend binds (see `DaemonManager::start()`)
+/// rather than the `localhost` hostname avoids a resolver detour: on
+/// IPv6-first hosts `localhost` resolves to `::1` first, where nothing is
+/// listening for an IPv4 dashboard, producing spurious probe failures (and a
+/// connect stall per attempt before the IPv4 fallback), and the other way
+/// round for an IPv6 one.
+pub(crate) fn probe_url(address: SocketAddr) -> String {
+    format!("http://{}/", reachable(address))
+}
+
+/// [`probe_url`] for a dashboard on the IPv4 loopback, such as a comparison
+/// dashboard.
+pub(crate) fn loopback_url(port: u16) -> String {
+    probe_url((Ipv4Addr::LOCALHOST, port).into())
+}
+
+/// The URL to open the dashboard listening on `address` in a browser. On
+/// the default IPv4 loopback that stays `localhost`, so the browser keeps
+/// the dashboard's site data from before `listen_address` existed.
+pub(crate) fn browser_url(address: SocketAddr) -> String {
+    let address = reachable(address);
+    if address.ip() == Ipv4Addr::LOCALHOST {
+        format!("http://localhost:{}", address.port())
+    } else {
+        format!("http://{address}")
+    }
+}
+
+/// Perform a health check on the dashboard. Also used by the control
+/// server's `status` reply.
+pub(crate) async fn health_check(address: SocketAddr) -> Result<bool> {
+    let client = crate::network::loopback_client()
+        .timeout(Duration::from_secs(5))
+        .build()?;
+
+    let url = probe_url(address);
+    match client.get(&url).send().await {
+        Ok(response) => Ok(response.status().is_success()),
+        Err(_) => Ok(false),
+    }
+}
+
+/// Check the backend at `address` every [`INTERVAL`] while `running`, for
+/// the life of `tasks`, logging the outcome and recording it in `stats` for
+/// the status. `address` is the backend's own, not the startup page's, which
+/// would hold the probe through a restart.
+pub(super) fn spawn(
+    tasks: &Tasks,
+    running: Arc<AtomicBool>,
+    stats: Arc<Mutex<Stats>>,
+    address: SocketAddr,
+) {
+    tasks.spawn("health checks", async move {
+        loop {
+            tokio::time::sleep(INTERVAL).await;
+            if !running.load(Ordering::SeqCst) {
+                break;
+            }
+            let passed = match health_check(address).await {
+                Ok(true) => {
+                    debug!("Health check passed");
+                    true
+                }
+                Ok(false) => {
+                    warn!("Health check failed - backend may be starting");
+                    false
+                }
+                Err(e) => {
+                    warn!("Health check error: {}", e);
+                    false
+                }
+            };
+            stats
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .record_health(passed);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loopback_url_targets_ipv4_loopback() {
+        // Must match the address the backend binds (`127.0.0.1`), not the
+        // `localhost` hostname, so the probe doesn't get steered to `::1`
+        // on IPv6-first hosts where the daemon isn't listening.
+        let url = loopback_url(6052);
+        assert_eq!(url, "http://127.0.0.1:6052/");
+        assert!(!url.contains("localhost"));
+    }
+
+    #[test]
+    fn ipv6_urls_bracket_the_address() {
+        let v6: SocketAddr = "[::1]:6052".parse().unwrap();
+        assert_eq!(probe_url(v6), "http://[::1]:6052/");
+        assert_eq!(browser_url(v6), "http://[::1]:6052");
+        let any: SocketAddr = "[::]:6052".parse().unwrap();
+        assert_eq!(probe_url(any), "http://[::1]:6052/");
+        assert_eq!(
+            browser_url((Ipv4Addr::UNSPECIFIED, 6052).into()),
+            "http://localhost:6052"
+        );
+        assert_eq!(
+            browser_url(([192, 168, 1, 5], 6052).into()),
+            "http://192.168.1.5:6052"
+        );
+    }
+}
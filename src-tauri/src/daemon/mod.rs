@@ -3,19 +3,57 @@
 //! Handles starting, stopping, and monitoring the ESPHome dashboard process.
 
 use anyhow::{Context, Result};
-use std::fs::File;
+use std::net::{Ipv4Addr, SocketAddr};
 use std::path::PathBuf;
-use std::process::Stdio;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use tauri::AppHandle;
-use tauri_plugin_notification::NotificationExt;
-use tokio::process::{Child, Command};
+use tokio::process::Child;
 use tokio::sync::Mutex;
 use tracing::{debug, error, info, warn};
 
 use crate::platform;
-use crate::settings::Settings;
+use crate::settings::{Runtime, Settings};
+use crate::store::{DaemonState, Event, RestartReason, Store};
+use crate::tasks::Tasks;
+
+mod access;
+mod backend;
+mod builds;
+mod command;
+mod compare;
+mod docker;
+mod error;
+mod extras;
+#[cfg(test)]
+mod harness;
+mod health;
+mod pinned;
+pub(crate) mod port;
+mod proxy;
+mod remote;
+mod shutdown;
+mod ssh;
+mod standalone;
+mod stats;
+mod thermal;
+mod watchdog;
+mod wsl;
+
+use backend::DaemonBackend;
+use command::LocaleEnv;
+pub(crate) use compare::{Comparison, Workspace};
+pub(crate) use error::DaemonError;
+pub(crate) use extras::forget as forget_extras;
+pub(crate) use health::{browser_url, health_check, loopback_url, probe_url, reachable};
+pub(crate) use remote::run_relay;
+pub(crate) use ssh::SSH_OPTIONS;
+use stats::Stats;
+pub(crate) use stats::{format_uptime, StatsSnapshot};
+pub(crate) use watchdog::Runaway;
+
+pub(crate) use pinned::{current_pin, installed as installed_pins, write_pin, PIN_FILE};
 
 /// Width-correct atomic and integer types for the dashboard child PID.
 /// Windows PIDs are a `DWORD` (`u32`); Unix PIDs are a `pid_t` (`i32`).
@@ -33,7 +71,7 @@ type PidInt = u32;
 type PidInt = i32;
 
 /// Human-readable name of the backend process, for log messages.
-const BACKEND_NAME: &str = "ESPHome device builder";
+pub(crate) const BACKEND_NAME: &str = "ESPHome device builder";
 
 /// File name of the backend's combined stdout+stderr log inside the logs
 /// directory. The CLI's `logs` subcommand tails this file by name, so the
@@ -49,16 +87,37 @@ const LOG_HISTORY: usize = 3;
 pub struct DaemonManager {
     /// The running process, if any
     process: Arc<Mutex<Option<Child>>>,
-    /// Path to bundled Python executable
-    python_path: PathBuf,
-    /// Path to bundled Python bin directory (for PATH)
-    python_bin_dir: PathBuf,
     /// Path to config directory
     config_dir: PathBuf,
     /// Path to logs directory
     logs_dir: PathBuf,
-    /// Dashboard port
-    port: u16,
+    /// Where the dashboard listens (`listen_address` and `port`)
+    address: SocketAddr,
+    /// Where the backend itself listens: `address`, or a loopback port
+    /// behind the startup page (see [`proxy`]). The health check and a stop
+    /// go here, past the page's hold; `address` is for browsers
+    backend_address: proxy::Upstream,
+    /// Where the backend runs (see [`backend`]). Fixed at construction like
+    /// the port.
+    backend: Box<dyn DaemonBackend>,
+    /// Timezone/locale overrides for the backend environment
+    locale_env: LocaleEnv,
+    /// Run the backend at background CPU/IO priority
+    low_priority: bool,
+    /// Cap on parallel compile jobs (`None` = the build tools' default, one
+    /// per core)
+    build_jobs: Option<u32>,
+    /// CPU temperature (°C) above which builds are paused
+    thermal_limit: Option<u32>,
+    /// Run the native backend with reduced privileges (`sandbox_backend`)
+    sandbox: bool,
+    /// How long a stop waits for the backend to exit (`stop_timeout`)
+    stop_grace: Duration,
+    /// Uptime, restart and crash counters for this session. A std mutex: it
+    /// is never held across an await.
+    stats: Arc<std::sync::Mutex<Stats>>,
+    /// Build processes the watchdog flagged as stuck, cleared on each start
+    runaways: Arc<std::sync::Mutex<Vec<Runaway>>>,
     /// Whether the daemon is running
     running: Arc<AtomicBool>,
     /// PID of the device builder child, mirrored as an atomic so synchronous
@@ -67,59 +126,121 @@ pub struct DaemonManager {
     /// group without locking the tokio mutex. Zero when no child is
     /// running.
     dashboard_pid: Arc<AtomicPid>,
-    /// AppHandle for emitting notifications / updating the tray when the
-    /// child process exits independently of an explicit `stop()`. Also used
-    /// to read the desktop app version (forwarded to the backend via
-    /// `ESPHOME_DESKTOP_VERSION` at `start()` time).
-    app_handle: AppHandle,
+    /// Where the running state is published, for the tray and notifications
+    store: Store,
+    /// The daemon's task scope, a child of the app's
+    tasks: Tasks,
+    /// The current run's health check and watchers, a child of `tasks`.
+    /// Replaced on each start and cancelled on a confirmed stop.
+    run_tasks: std::sync::Mutex<Tasks>,
+    /// AppHandle used to read the desktop app version (forwarded to the
+    /// backend via `ESPHOME_DESKTOP_VERSION` at `start()` time). `None` for a
+    /// [`Self::standalone`] manager, which runs without a Tauri app.
+    app_handle: Option<AppHandle>,
 }
 
 impl DaemonManager {
     /// Create a new daemon manager
-    pub fn new(app_handle: &AppHandle, settings: &Settings) -> Result<Self> {
+    pub fn new(
+        app_handle: &AppHandle,
+        settings: &Settings,
+        store: Store,
+        tasks: Tasks,
+    ) -> Result<Self> {
         let data_dir = platform::get_data_dir(app_handle)?;
         let python_path = platform::get_python_path(app_handle)?;
         let python_bin_dir = platform::get_python_bin(app_handle)?;
 
-        // Use ~/esphome as the default config directory
-        let config_dir = settings
-            .config_dir
-            .clone()
-            .unwrap_or_else(crate::settings::default_config_dir);
+        // ~/esphome unless another config directory is set
+        let config_dir = settings.workspace.resolved_config_dir();
         std::fs::create_dir_all(&config_dir).context("Failed to create config directory")?;
 
-        // Create logs directory in app data
-        let logs_dir = data_dir.join("logs");
-        std::fs::create_dir_all(&logs_dir).context("Failed to create logs directory")?;
+        let backend = backend::from_settings(
+            settings,
+            &config_dir,
+            &data_dir,
+            python_path,
+            python_bin_dir,
+        );
+
+        let logs_dir = platform::get_logs_dir(app_handle)?;
+
+        let peers = settings.access_summary.then(access::Peers::default);
+        if let Some(peers) = &peers {
+            let log = logs_dir.join(DASHBOARD_LOG_NAME);
+            access::spawn(&tasks, log, peers.clone(), store.clone());
+        }
+        let address = settings.dashboard_address();
+        let backend_address = if settings.startup_page {
+            proxy::spawn(&tasks, address, store.clone(), peers).unwrap_or_else(|e| {
+                warn!("Startup page not served: {:#}", e);
+                proxy::Upstream::fixed(address)
+            })
+        } else {
+            proxy::Upstream::fixed(address)
+        };
 
         Ok(Self {
             process: Arc::new(Mutex::new(None)),
-            python_path,
-            python_bin_dir,
             config_dir,
             logs_dir,
-            port: settings.port,
+            address,
+            backend_address,
+            backend,
+            locale_env: LocaleEnv::from_settings(settings),
+            low_priority: settings.low_priority_builds,
+            build_jobs: settings.build_jobs,
+            thermal_limit: settings.thermal_limit,
+            sandbox: settings.sandbox_backend,
+            stop_grace: settings
+                .stop_timeout
+                .map_or(shutdown::DEFAULT_GRACE, |secs| {
+                    Duration::from_secs(secs.into())
+                }),
+            stats: Arc::new(std::sync::Mutex::new(Stats::default())),
+            runaways: Arc::new(std::sync::Mutex::new(Vec::new())),
             running: Arc::new(AtomicBool::new(false)),
             dashboard_pid: Arc::new(AtomicPid::new(0)),
-            app_handle: app_handle.clone(),
+            store,
+            run_tasks: std::sync::Mutex::new(tasks.child()),
+            tasks,
+            app_handle: Some(app_handle.clone()),
         })
     }
 
     /// Start the ESPHome device builder.
     ///
-    /// Emits the tray status itself — "Running" on success, "Stopped" on
+    /// Publishes the state itself — running on success, stopped on
     /// failure — so callers don't have to pair every start with a
-    /// `tray::update_status` call (a forgotten pairing leaves the tray
-    /// stale). The status reflects the actual post-call state, not the
-    /// intent.
-    pub async fn start(&self) -> Result<()> {
-        let result = self.start_inner().await;
-        crate::tray::update_status(&self.app_handle, self.is_running());
+    /// [`Event::Daemon`] (a forgotten pairing leaves the tray stale). The
+    /// state reflects the actual post-call state, not the intent.
+    ///
+    /// A start after the session's first counts as a user's restart; see
+    /// [`Self::start_for`].
+    pub async fn start(&self) -> Result<(), DaemonError> {
+        self.start_for(RestartReason::User).await
+    }
+
+    /// [`Self::start`], recording `reason` in the store's restart history if
+    /// this isn't the session's first start.
+    pub(crate) async fn start_for(&self, reason: RestartReason) -> Result<(), DaemonError> {
+        let result = self.start_inner(reason).await;
+        self.show_status(self.is_running());
         result
     }
 
+    /// Publish whether the backend runs.
+    fn show_status(&self, running: bool) {
+        let state = if running {
+            DaemonState::Running
+        } else {
+            DaemonState::Stopped
+        };
+        self.store.publish(Event::Daemon(state));
+    }
+
     /// The start sequence proper; see [`Self::start`] for the tray wrapper.
-    async fn start_inner(&self) -> Result<()> {
+    async fn start_inner(&self, reason: RestartReason) -> Result<(), DaemonError> {
         // Hold the process lock for the entire start sequence (check ->
         // spawn -> store) so two concurrent start() calls can't both pass
         // the running check and each spawn a child. Without this, the
@@ -128,12 +249,13 @@ impl DaemonManager {
         // child is never signaled and orphans a stray dashboard process.
         // stop() also takes this lock, so start()/stop() are serialized too.
         //
-        // Consequence: stop() holds this lock across its up-to-30s child drain,
-        // so a stop-then-start sequence (Restart, or rapid Stop->Start) makes
-        // start() await the lock until stop() finishes — up to 30s. This is the
-        // intended serialization (prevents the new dashboard racing the old one
-        // for the port). start() is async, so it yields rather than blocking a
-        // thread, keeping the tray/UI responsive.
+        // Consequence: stop() holds this lock across its child drain (the
+        // grace period, 30s by default), so a stop-then-start sequence
+        // (Restart, or rapid Stop->Start) makes start() await the lock until
+        // stop() finishes. This is the intended serialization (prevents the
+        // new dashboard racing the old one for the port). start() is async,
+        // so it yields rather than blocking a thread, keeping the tray/UI
+        // responsive.
         let mut process = self.process.lock().await;
 
         if self.running.load(Ordering::SeqCst) {
@@ -142,131 +264,14 @@ impl DaemonManager {
         }
 
         let backend_name = BACKEND_NAME;
-        info!("Starting {} on port {}", backend_name, self.port);
-        debug!("Python path: {:?}", self.python_path);
-        debug!("Python bin: {:?}", self.python_bin_dir);
+        info!("Starting {} on {}", backend_name, self.address);
+        debug!("Runtime: {:?}", self.backend);
         debug!("Config dir: {:?}", self.config_dir);
         debug!("Logs dir: {:?}", self.logs_dir);
 
-        // Verify Python exists
-        if !self.python_path.exists() {
-            anyhow::bail!("Python not found at {:?}", self.python_path);
-        }
-
-        // Open log file for stdout and stderr combined.
-        //
-        // `File::create` truncates, so without rotating first every start wipes
-        // the previous run's logs — leaving nothing to inspect after a failed
-        // restart (issue #203). Rotate the prior `dashboard.log` to a numbered
-        // backup first; best-effort, since losing old logs must never block the
-        // backend from starting.
-        let log_path = self.logs_dir.join(DASHBOARD_LOG_NAME);
-        if let Err(e) = crate::util::rotate_log(&log_path, LOG_HISTORY) {
-            warn!("Failed to rotate {:?}: {}", log_path, e);
-        }
-        let log_file = File::create(&log_path).context("Failed to create log file")?;
-        let log_file_clone = log_file
-            .try_clone()
-            .context("Failed to clone log file handle")?;
-
-        info!("{} logs: {:?}", backend_name, log_path);
-
-        let config_arg = self.config_dir.to_str().unwrap_or(".");
-        let port_arg = self.port.to_string();
-
-        // Build the command
-        let mut cmd = Command::new(&self.python_path);
-        cmd.args([
-            "-m",
-            "esphome_device_builder",
-            config_arg,
-            "--host",
-            "127.0.0.1",
-            "--port",
-            &port_arg,
-        ]);
-        cmd
-            // Set working directory to config dir (required for PlatformIO)
-            .current_dir(&self.config_dir)
-            // Redirect stdout/stderr to single log file
-            .stdout(Stdio::from(log_file))
-            .stderr(Stdio::from(log_file_clone));
-
-        // Give the daemon a null stdin instead of inheriting ours. The
-        // dashboard/device-builder never reads stdin, so there is no reason to
-        // hold a handle to it on any platform.
-        //
-        // On Windows this is also load-bearing for restart: the shutdown path
-        // calls `platform::send_ctrl_break`, whose `AttachConsole`/`FreeConsole`
-        // dance mutates this (GUI, console-less) process's standard handles.
-        // `STD_INPUT_HANDLE` starts out NULL but is left dangling once we attach
-        // to and then free the child's console. A subsequent restart respawn
-        // would inherit that invalid handle, and because stdout/stderr are
-        // redirected (so `STARTF_USESTDHANDLES` is set and *all three* handles
-        // must be valid) `CreateProcess` fails with ERROR_INVALID_HANDLE (os
-        // error 6) — leaving the daemon dead after every restart. Pinning stdin
-        // to a known-good handle makes the spawn independent of our
-        // console-handle state.
-        cmd.stdin(Stdio::null());
-
-        // On Unix, intentionally NOT setting `kill_on_drop(true)`. That
-        // would have tokio send SIGKILL to the Child when it gets
-        // dropped (either when stop()'s wait times out, or when
-        // AppState drops at process teardown), which force-kills the
-        // dashboard and corrupts its state. Our Unix shutdown is
-        // SIGTERM only — see `stop()` and `terminate_blocking()`.
-        //
-        // On Windows the graceful signal is CTRL_BREAK_EVENT (see `stop()`
-        // and `terminate_blocking()`), with TerminateProcess as the hard
-        // fallback. Keep `kill_on_drop(true)` as a last-ditch drop-time net
-        // for any path that drops the Child without going through those
-        // (note it does NOT fire on the normal quit path, which calls
-        // `std::process::exit()` and skips Drop).
-        #[cfg(windows)]
-        cmd.kill_on_drop(true);
-
-        // Create new process group on Unix so we can kill all children
-        #[cfg(unix)]
-        cmd.process_group(0);
-
-        // Prevent a console window from staying open on Windows, and put the
-        // child in its own process group so we can later deliver a graceful
-        // CTRL_BREAK_EVENT to it on shutdown (see daemon stop/terminate).
-        platform::configure_daemon_tokio_command(&mut cmd);
-
-        // Keep the managed interpreter on its own tree: a stale package in the
-        // user site directory otherwise shadows our pinned one and the backend
-        // dies at import before it can serve anything (#318).
-        platform::isolate_python_tokio_command(&mut cmd);
-
-        // Set environment variables
-        cmd.env("ESPHOME_DASHBOARD", "1");
-        // Surface the desktop app version to the backend so it can be shown
-        // in the frontend (e.g. an "About" page).
-        cmd.env(
-            "ESPHOME_DESKTOP_VERSION",
-            self.app_handle.package_info().version.to_string(),
-        );
-        // Tell the backend where the esphome-desktop CLI lives so the dashboard
-        // can check for and trigger updates through the stable `api` interface
-        // (esphome-desktop api check-update / api update). Set beside the other
-        // backend env vars and re-applied on every respawn like them; the
-        // backend's own child processes inherit it too. See control::client.
-        if let Some(bin) = crate::control::cli_invocation_path() {
-            cmd.env("ESPHOME_DESKTOP_BIN", bin);
-        }
-
-        // On Windows, force the spawned Python (and any subprocesses it
-        // spawns for compile/logs) to use UTF-8 for stdin/stdout/stderr.
-        // Without this, Python falls back to the locale codec (cp1252 on
-        // Western installs) when stdout is a redirected pipe — which the
-        // dashboard always is — and any non-ASCII output (e.g. the wifi
-        // signal-bar block characters U+2582..U+2588) raises
-        // UnicodeEncodeError and drops the device's log connection.
-        #[cfg(target_os = "windows")]
-        cmd.env("PYTHONIOENCODING", "utf-8");
+        let mut cmd = self.build_command().await?;
 
-        let child = cmd.spawn().context("Failed to spawn ESPHome process")?;
+        let child = cmd.spawn().map_err(DaemonError::Spawn)?;
 
         // Tie the backend's lifetime to ours so it can never be orphaned by an
         // exit path that doesn't run our code (uninstaller force-kill, crash,
@@ -293,88 +298,91 @@ impl DaemonManager {
                  the desktop if this process is killed without running its shutdown path"
             );
         }
+        #[cfg(windows)]
+        if self.sandbox && self.backend.host_python().is_some() {
+            platform::restrict_backend_job_ui();
+        }
 
-        if let Some(pid) = child.id() {
+        // Elsewhere the backend sets the priority where it runs (see
+        // `DaemonBackend::command`); lowering `wsl.exe` or `ssh` wouldn't
+        // reach it.
+        if self.low_priority && self.backend.host_python().is_some() {
+            platform::lower_priority(&child);
+        }
+
+        let child_pid = child.id();
+        if let Some(pid) = child_pid {
             self.dashboard_pid.store(pid as PidInt, Ordering::SeqCst);
         }
 
         *process = Some(child);
         self.running.store(true, Ordering::SeqCst);
+        let restart = self
+            .stats
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .record_start(reason);
+        if let Some(restart) = restart {
+            info!(
+                "{} restart #{} this session ({})",
+                backend_name,
+                restart.number,
+                restart.reason.name()
+            );
+            self.store.publish(Event::Restarted(restart));
+        }
+        self.runaways
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
         // Release the lock before spawning the watcher tasks below; they
         // re-acquire it on their own polling cadence.
         drop(process);
 
-        // Start health check task.
-        //
-        // Like the exit-watcher below, this captures the child's PID at spawn
-        // time and exits once `dashboard_pid` no longer matches. A
-        // stop()/start() pair faster than the 30s poll interval flips `running`
-        // false then back to true, so the `running` check alone wouldn't retire
-        // a superseded task: the old health-check loop would wake to
-        // running=true (set by the new start()) and keep probing forever,
-        // leaking one task per restart. The PID guard retires it as soon as a
-        // newer start() installs its own watcher.
-        let running = self.running.clone();
-        let port = self.port;
-        let health_dashboard_pid = self.dashboard_pid.clone();
-        let health_watcher_pid = self.dashboard_pid.load(Ordering::SeqCst);
-        tokio::spawn(async move {
-            loop {
-                tokio::time::sleep(tokio::time::Duration::from_secs(30)).await;
-                if !running.load(Ordering::SeqCst) {
-                    break;
-                }
-                if health_dashboard_pid.load(Ordering::SeqCst) != health_watcher_pid {
-                    // Superseded by a newer start(); its task probes now.
-                    break;
-                }
-                match health_check(port).await {
-                    Ok(true) => debug!("Health check passed"),
-                    Ok(false) => warn!("Health check failed - backend may be starting"),
-                    Err(e) => warn!("Health check error: {}", e),
-                }
+        // This run's tasks, in a scope of their own: they end when it is
+        // cancelled on a confirmed stop, or replaced by the next start, so a
+        // fast stop()/start() pair can't leave the previous run's watchers
+        // reporting on the new child. Replacing the scope drops, and so
+        // cancels, the previous one.
+        let run = self.tasks.child();
+
+        let (running, stats) = (self.running.clone(), self.stats.clone());
+        health::spawn(&run, running, stats, self.backend_address.get());
+
+        // Start the runaway-process watchdog. Only for a backend on this
+        // machine: elsewhere
+        // the build is invisible to the host's process table.
+        if let (Some(_), Some(child_pid), Some(app)) =
+            (self.backend.host_python(), child_pid, &self.app_handle)
+        {
+            watchdog::spawn(
+                &run,
+                app.clone(),
+                self.runaways.clone(),
+                self.logs_dir.join(DASHBOARD_LOG_NAME),
+                child_pid,
+            );
+            if let Some(limit) = self.thermal_limit {
+                thermal::spawn(&run, app.clone(), child_pid, limit);
             }
-        });
+        }
 
         // Start exit watcher. Polls `child.try_wait()` so an unexpected
         // exit (e.g. the dashboard process dying on startup because of a
         // missing module) flips the running flag back to false instead
-        // of leaving the tray stuck on "Status: Running". Exits cleanly
-        // when `stop()` clears the running flag.
-        //
-        // Captures the child's PID at spawn time and exits as soon as
-        // `dashboard_pid` no longer matches. Without this, a stop()/start()
-        // pair faster than the 500 ms poll interval would let an old
-        // watcher wake up to a new child (running=true again, fresh PID,
-        // possibly different backend) and start reporting on it with the
-        // stale `backend_label` and log path it captured at its own start.
-        let watcher_pid = self.dashboard_pid.load(Ordering::SeqCst);
+        // of leaving the tray stuck on "Status: Running".
         let process = self.process.clone();
         let running = self.running.clone();
         let dashboard_pid = self.dashboard_pid.clone();
-        let app_handle = self.app_handle.clone();
-        let log_path_for_watcher = log_path.clone();
+        let store = self.store.clone();
+        let stats = self.stats.clone();
+        let log_path_for_watcher = self.logs_dir.join(DASHBOARD_LOG_NAME);
         let backend_label = backend_name.to_string();
-        tokio::spawn(async move {
+        let run_token = run.token();
+        run.spawn("exit watcher", async move {
             loop {
                 tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
-                if !running.load(Ordering::SeqCst) {
-                    // stop() already cleaned up.
-                    return;
-                }
-                if dashboard_pid.load(Ordering::SeqCst) != watcher_pid {
-                    // The child this watcher was created for has been
-                    // replaced by a newer start(); the new spawn has its
-                    // own watcher.
-                    return;
-                }
                 let mut guard = process.lock().await;
-                // Re-check under the lock: stop() takes the child and
-                // resets the PID without holding the lock for the entire
-                // window, so a stale watcher could otherwise still race in.
-                if dashboard_pid.load(Ordering::SeqCst) != watcher_pid {
-                    return;
-                }
                 let exited = match guard.as_mut() {
                     Some(child) => match child.try_wait() {
                         Ok(Some(status)) => Some(status),
@@ -398,29 +406,18 @@ impl DaemonManager {
                 drop(guard);
                 running.store(false, Ordering::SeqCst);
                 dashboard_pid.store(0, Ordering::SeqCst);
-
-                crate::tray::update_status(&app_handle, false);
-                if let Err(e) = app_handle
-                    .notification()
-                    .builder()
-                    .title(crate::i18n::t_with(
-                        "daemon.stopped_title",
-                        &[("backend", &backend_label)],
-                    ))
-                    .body(crate::i18n::t_with(
-                        "daemon.stopped_body",
-                        &[
-                            ("backend", backend_label.as_str()),
-                            ("status", &status.to_string()),
-                        ],
-                    ))
-                    .show()
-                {
-                    warn!("Failed to show daemon-crash notification: {}", e);
-                }
+                stats
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .record_crash(format!("exited with {status}"));
+
+                store.publish(Event::Daemon(DaemonState::Crashed(status.to_string())));
+                // The run is over; retire its other tasks with it.
+                run_token.cancel();
                 return;
             }
         });
+        *self.run_tasks.lock().unwrap_or_else(|e| e.into_inner()) = run;
 
         info!("{} started", backend_name);
         Ok(())
@@ -428,28 +425,28 @@ impl DaemonManager {
 
     /// Stop the ESPHome dashboard.
     ///
-    /// Emits the tray status itself: "Stopped" optimistically up front (the
-    /// graceful drain below can take up to 30s, during which the tray should
-    /// not claim the backend is running), restored to the actual state if the
-    /// stop fails — after a failed stop the backend may well still be
-    /// running, so the optimistic label must not stand.
+    /// Publishes the state itself: stopped optimistically up front (the
+    /// graceful drain below can take the whole grace period, during which
+    /// the tray should not claim the backend is running), restored to the
+    /// actual state if the stop fails — after a failed stop the backend may
+    /// well still be running, so the optimistic label must not stand.
     ///
     /// Returns `Err` when the stop could not be confirmed: on Unix, if the
-    /// backend ignores SIGTERM for the full 30s drain window (we never
+    /// backend ignores every stop request for the full grace period (we never
     /// escalate to SIGKILL by design), the process is left running and this
     /// reports the failure so callers can abort rather than act as if the
     /// backend were down.
-    pub async fn stop(&self) -> Result<()> {
-        crate::tray::update_status(&self.app_handle, false);
+    pub async fn stop(&self) -> Result<(), DaemonError> {
+        self.show_status(false);
         let result = self.stop_inner().await;
         if result.is_err() {
-            crate::tray::update_status(&self.app_handle, self.is_running());
+            self.show_status(self.is_running());
         }
         result
     }
 
     /// The stop sequence proper; see [`Self::stop`] for the tray wrapper.
-    async fn stop_inner(&self) -> Result<()> {
+    async fn stop_inner(&self) -> Result<(), DaemonError> {
         // Acquire the process lock *before* reading/mutating `running` so the
         // check-and-act is fully atomic against start(), which also reads
         // `running` under this lock. The check is done post-lock (no lockless
@@ -469,56 +466,40 @@ impl DaemonManager {
         let backend_name = BACKEND_NAME;
         info!("Stopping {}", backend_name);
 
-        // Do NOT clear `running` yet. The health-check and exit-watcher tasks
-        // spawned in start() retire themselves when `running` goes false, and
-        // the Unix drain below may time out with the backend still alive — in
-        // which case we keep the process and must keep its watchers. Clearing
-        // `running` only after a *confirmed* stop (see the bottom of this fn)
-        // means the timeout path leaves both the flag and the watchers intact,
+        // Do NOT clear `running` or cancel the run's tasks yet. The Unix
+        // drain below may time out with the backend still alive — in which
+        // case we keep the process and must keep its watchers. Doing both
+        // only after a *confirmed* stop (see the bottom of this fn) means the
+        // timeout path leaves the flag and the watchers intact,
         // so a later backend exit still clears state and monitoring survives a
         // failed stop attempt. The tray already shows "Stopped" optimistically
         // via stop()'s wrapper, so the label isn't tied to this flag.
         if let Some(mut child) = process.take() {
-            // Try graceful shutdown first - kill the process group on Unix
-            #[cfg(unix)]
-            {
-                use nix::sys::signal::{killpg, Signal};
-                use nix::unistd::Pid;
-
-                if let Some(pid) = child.id() {
-                    // Send SIGTERM to the process group to kill all children
-                    let _ = killpg(Pid::from_raw(pid as i32), Signal::SIGTERM);
+            let pid = child.id();
+            let steps = shutdown::plan(self.stop_grace, self.backend.host_python().is_some());
+            let address = self.backend_address.get();
+            let exited = shutdown::drain(&mut child, address, &steps, self.stop_grace, |step| {
+                if let Some(pid) = pid {
+                    shutdown::send(pid, step);
                 }
-            }
-
-            // On Windows the graceful signal is CTRL_BREAK_EVENT to the
-            // child's process group (Python surfaces it as SIGBREAK). Send
-            // it up front, then wait the same patient window as Unix. Until
-            // the backend installs a SIGBREAK handler the default action
-            // terminates the child, so the wait returns promptly; once it
-            // drains gracefully, the window gives it time. TerminateProcess
-            // is the hard fallback on timeout (the only guarantee Windows
-            // offers for a child that ignored the break).
-            #[cfg(windows)]
-            {
-                if let Some(pid) = child.id() {
-                    let _ = crate::platform::send_ctrl_break(pid);
+                // The signal reaches `wsl.exe`, `docker` or `ssh` rather than
+                // the backend itself; signal that directly so the wait sees
+                // the child exit with it.
+                if step == shutdown::Step::Interrupt {
+                    self.backend.terminate_blocking();
                 }
-            }
-
-            // Wait up to 30 s for the child to honor the signal and drain
-            // in-flight work (firmware queue, partial writes, lock release);
-            // we have measured up to 30 s in the wild.
-            let timeout = tokio::time::timeout(tokio::time::Duration::from_secs(30), child.wait());
+            })
+            .await;
+            let grace = self.stop_grace.as_secs();
 
-            match timeout.await {
-                Ok(Ok(status)) => info!("{} exited with status: {}", backend_name, status),
+            match exited {
+                Some(Ok(status)) => info!("{} exited with status: {}", backend_name, status),
                 // A wait() error on Unix almost always means the child was
                 // already reaped (ECHILD) — i.e. it exited before we waited —
                 // so we treat this as a confirmed stop and fall through to
-                // clear state below, unlike the timeout arm which bail!s.
-                Ok(Err(e)) => warn!("Error waiting for process: {}", e),
-                Err(_) => {
+                // clear state below, unlike the timeout arm, which fails.
+                Some(Err(e)) => warn!("Error waiting for process: {}", e),
+                None => {
                     // On Unix we do NOT escalate to SIGKILL — force-killing
                     // corrupts dashboard state. The backend is still alive, so
                     // we cannot honestly report a successful stop: put the child
@@ -533,20 +514,20 @@ impl DaemonManager {
                     #[cfg(unix)]
                     {
                         warn!(
-                            "Timeout waiting for {} to honor SIGTERM after 30 s; \
+                            "Timeout waiting for {} to stop after {} s; \
                              not force-killing (would corrupt dashboard state) — \
                              reporting stop failure so callers can abort.",
-                            backend_name
+                            backend_name, grace
                         );
                         *process = Some(child);
-                        anyhow::bail!("timed out waiting for {} to stop", backend_name);
+                        return Err(DaemonError::StopTimeout { secs: grace });
                     }
                     #[cfg(windows)]
                     {
                         warn!(
-                            "Timeout waiting for {} to honor CTRL_BREAK after 30 s; \
+                            "Timeout waiting for {} to honor CTRL_BREAK after {} s; \
                              force-killing.",
-                            backend_name
+                            backend_name, grace
                         );
                         let _ = child.kill().await;
                     }
@@ -555,11 +536,19 @@ impl DaemonManager {
         }
 
         // Confirmed stop (child exited, wait errored as already-reaped, or the
-        // Windows force-kill fired). Only now clear `running`, which retires the
+        // Windows force-kill fired). Only now clear `running` and retire the
         // watcher tasks; the Unix drain-timeout path bailed out above and left
-        // this flag true so its watchers live on.
+        // both so its watchers live on.
         self.running.store(false, Ordering::SeqCst);
+        self.run_tasks
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .cancel();
         self.dashboard_pid.store(0, Ordering::SeqCst);
+        self.stats
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .record_stop();
         info!("{} stopped", backend_name);
         Ok(())
     }
@@ -615,6 +604,7 @@ impl DaemonManager {
         if pid == 0 {
             return;
         }
+        self.backend.terminate_blocking();
         #[cfg(unix)]
         {
             use nix::sys::signal::{killpg, Signal};
@@ -623,6 +613,7 @@ impl DaemonManager {
             match getpgid(Some(pid_t)) {
                 Ok(pgid) if pgid == pid_t => {
                     let _ = killpg(pid_t, Signal::SIGTERM);
+                    let _ = killpg(pid_t, Signal::SIGCONT);
                 }
                 _ => {
                     warn!(
@@ -671,12 +662,51 @@ impl DaemonManager {
     }
 
     /// Restart the daemon
-    pub async fn restart(&self) -> Result<()> {
+    pub async fn restart(&self) -> Result<(), DaemonError> {
         self.stop().await?;
         tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
         self.start().await
     }
 
+    /// Terminate the dashboard's running compile jobs, leaving the backend
+    /// up, and return how many there were
+    pub async fn cancel_build(&self) -> Result<usize, DaemonError> {
+        if self.backend.host_python().is_none() {
+            return Err(DaemonError::Unsupported {
+                action: "cancelling builds",
+                runtime: self.runtime(),
+            });
+        }
+        let backend_pid = self.process.lock().await.as_ref().and_then(Child::id);
+        let Some(backend_pid) = backend_pid else {
+            return Err(DaemonError::NotRunning);
+        };
+        Ok(
+            tokio::task::spawn_blocking(move || builds::cancel_builds(backend_pid))
+                .await
+                .context("Build cancellation task panicked")??,
+        )
+    }
+
+    /// Whether the dashboard is compiling anything; a stopped one isn't
+    pub async fn is_building(&self) -> Result<bool, DaemonError> {
+        if self.backend.host_python().is_none() {
+            return Err(DaemonError::Unsupported {
+                action: "looking for builds",
+                runtime: self.runtime(),
+            });
+        }
+        let backend_pid = self.process.lock().await.as_ref().and_then(Child::id);
+        let Some(backend_pid) = backend_pid else {
+            return Ok(false);
+        };
+        Ok(
+            tokio::task::spawn_blocking(move || builds::any_builds(backend_pid))
+                .await
+                .context("Build lookup task panicked")??,
+        )
+    }
+
     /// Check if the daemon is running
     pub fn is_running(&self) -> bool {
         self.running.load(Ordering::SeqCst)
@@ -684,7 +714,56 @@ impl DaemonManager {
 
     /// Get the port the daemon is running on
     pub fn port(&self) -> u16 {
-        self.port
+        self.address.port()
+    }
+
+    /// Get the address the daemon listens on
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Uptime, restart and crash counters for this session
+    pub fn stats(&self) -> StatsSnapshot {
+        self.stats
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .snapshot()
+    }
+
+    /// Build processes the watchdog has flagged since the backend started
+    pub fn runaways(&self) -> Vec<Runaway> {
+        self.runaways
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Terminate a flagged build process and its children, leaving the
+    /// backend itself running
+    pub fn kill_runaway(&self, pid: u32) -> Result<()> {
+        let runaway = {
+            let mut runaways = self.runaways.lock().unwrap_or_else(|e| e.into_inner());
+            let index = runaways
+                .iter()
+                .position(|r| r.pid == pid)
+                .context("Process is no longer flagged")?;
+            runaways.remove(index)
+        };
+        info!(
+            "Stopping runaway build process {} (pid {}) and its children",
+            runaway.name, pid
+        );
+        watchdog::kill_subtree(&runaway)
+    }
+
+    /// The timezone the backend runs with (`None` = the system default)
+    pub fn timezone(&self) -> Option<String> {
+        self.locale_env.effective_timezone()
+    }
+
+    /// The locale the backend runs with (`None` = the system default)
+    pub fn locale(&self) -> Option<String> {
+        self.locale_env.effective_locale()
     }
 
     /// Get the config directory
@@ -692,50 +771,19 @@ impl DaemonManager {
         &self.config_dir
     }
 
-    /// Get the logs directory
-    pub fn logs_dir(&self) -> &PathBuf {
-        &self.logs_dir
+    /// The Python the backend runs with on this machine, or `None` when it
+    /// runs elsewhere (WSL2, a container, another machine)
+    pub fn host_python(&self) -> Option<&PathBuf> {
+        self.backend.host_python()
     }
-}
-
-/// Build the loopback URL used to probe the dashboard (both the startup
-/// readiness poll and the periodic health check).
-///
-/// The backend is spawned with `--address 127.0.0.1` / `--host 127.0.0.1`
-/// (see `DaemonManager::start()`), so it only listens on the IPv4 loopback.
-/// Probing the literal `127.0.0.1` rather than the `localhost` hostname
-/// avoids a resolver detour: on IPv6-first hosts `localhost` resolves to
-/// `::1` first, where nothing is listening, producing spurious probe
-/// failures (and a connect stall per attempt before the IPv4 fallback).
-pub(crate) fn loopback_url(port: u16) -> String {
-    format!("http://127.0.0.1:{}/", port)
-}
 
-/// Perform a health check on the dashboard. Also used by the control
-/// server's `status` reply.
-pub(crate) async fn health_check(port: u16) -> Result<bool> {
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(5))
-        .build()?;
-
-    let url = loopback_url(port);
-    match client.get(&url).send().await {
-        Ok(response) => Ok(response.status().is_success()),
-        Err(_) => Ok(false),
+    /// Where the backend runs
+    pub fn runtime(&self) -> Runtime {
+        self.backend.runtime()
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::loopback_url;
-
-    #[test]
-    fn loopback_url_targets_ipv4_loopback() {
-        // Must match the address the backend binds (`127.0.0.1`), not the
-        // `localhost` hostname, so the probe doesn't get steered to `::1`
-        // on IPv6-first hosts where the daemon isn't listening.
-        let url = loopback_url(6052);
-        assert_eq!(url, "http://127.0.0.1:6052/");
-        assert!(!url.contains("localhost"));
+    /// Get the logs directory
+    pub fn logs_dir(&self) -> &PathBuf {
+        &self.logs_dir
     }
 }
@@ -0,0 +1,326 @@
+//! Per-workspace ESPHome pins.
+//!
+//! A `.esphome-version` file in the config directory (e.g. `2024.6.4`) pins
+//! that workspace to an ESPHome release, so a legacy project keeps compiling
+//! on the release it was written for. The backend then runs from a venv of
+//! its own under `pinned/<version>` in the app data, created from the bundled
+//! Python the first time that workspace starts: that ESPHome, plus whichever
+//! device builder pip finds compatible with it. The venv is kept for the next
+//! start, and the updates leave it alone; delete the pin file to go back to
+//! the app's own ESPHome. The Versions window ([`crate::versions`]) lists
+//! the venvs, switches the pin and deletes those no longer wanted.
+//!
+//! Native runtime only: the others run from a venv of their own wherever the
+//! backend runs.
+
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tokio::process::Command;
+use tracing::{info, warn};
+
+use super::backend::BACKEND_PACKAGE;
+use crate::platform;
+
+/// The pin file, at the top of the config directory.
+pub(crate) const PIN_FILE: &str = ".esphome-version";
+
+/// Where the per-version venvs are, in the app data.
+const PINNED_DIR: &str = "pinned";
+
+/// When a venv last ran a dashboard, inside it.
+const LAST_USED_FILE: &str = ".last_used";
+
+/// A workspace's pinned ESPHome and the venv it runs from.
+#[derive(Debug, Clone)]
+pub(crate) struct PinnedEnv {
+    pub version: String,
+    /// The venv.
+    dir: PathBuf,
+    /// The app's Python, which creates the venv.
+    base_python: PathBuf,
+}
+
+impl PinnedEnv {
+    /// The pin in `config_dir`, if it has one.
+    pub(crate) fn find(
+        config_dir: &Path,
+        data_dir: &Path,
+        base_python: &Path,
+    ) -> Result<Option<Self>> {
+        let Some(version) = read_pin(config_dir)? else {
+            return Ok(None);
+        };
+        Ok(Some(Self::new(version, data_dir, base_python)))
+    }
+
+    /// The venv for ESPHome `version`, shared by every workspace (and
+    /// comparison dashboard) that asks for that release.
+    pub(super) fn new(version: String, data_dir: &Path, base_python: &Path) -> Self {
+        Self {
+            dir: data_dir.join(PINNED_DIR).join(&version),
+            version,
+            base_python: base_python.to_path_buf(),
+        }
+    }
+
+    /// [`Self::find`] for the daemon: a bad pin is kept as the error to
+    /// fail the start with, rather than failing the whole app or silently
+    /// running the wrong ESPHome.
+    pub(crate) fn resolve(
+        config_dir: &Path,
+        data_dir: &Path,
+        base_python: &Path,
+    ) -> (Option<Self>, Option<String>) {
+        match Self::find(config_dir, data_dir, base_python) {
+            Ok(pinned) => {
+                if let Some(pinned) = &pinned {
+                    info!("{} pins ESPHome {}", PIN_FILE, pinned.version);
+                }
+                (pinned, None)
+            }
+            Err(e) => (None, Some(format!("{e:#}"))),
+        }
+    }
+
+    /// The venv's interpreter.
+    pub(crate) fn python(&self) -> PathBuf {
+        if cfg!(target_os = "windows") {
+            self.bin_dir().join("python.exe")
+        } else {
+            self.bin_dir().join("python")
+        }
+    }
+
+    /// The venv's scripts directory, for `PATH`.
+    pub(crate) fn bin_dir(&self) -> PathBuf {
+        if cfg!(target_os = "windows") {
+            self.dir.join("Scripts")
+        } else {
+            self.dir.join("bin")
+        }
+    }
+
+    /// Create the venv and install the pinned ESPHome into it, unless a
+    /// previous start already did. A half-finished venv (an install that
+    /// failed or was cut off) is finished rather than trusted.
+    pub(crate) async fn ensure(&self) -> Result<()> {
+        let python = self.python();
+        if python.exists() && has_backend(&python).await {
+            self.mark_used();
+            return Ok(());
+        }
+        info!(
+            "Creating the ESPHome {} venv in {:?}; this takes a few minutes",
+            self.version, self.dir
+        );
+        let mut venv = Command::new(&self.base_python);
+        venv.args(["-m", "venv"]).arg(&self.dir);
+        platform::isolate_python_tokio_command(&mut venv);
+        platform::configure_no_window_tokio_command(&mut venv);
+        let output = platform::run_pip(venv)
+            .await
+            .context("Failed to run Python to create the venv")?;
+        if !output.status.success() {
+            bail!(
+                "Failed to create the ESPHome {} venv: {}",
+                self.version,
+                platform::pip_output_report(&output)
+            );
+        }
+
+        let mut pip = platform::pip_command(&python);
+        pip.arg(format!("esphome=={}", self.version))
+            .arg(BACKEND_PACKAGE);
+        crate::network::apply_env(&mut pip);
+        let output = platform::run_pip(pip).await.context("Failed to run pip")?;
+        if !output.status.success() {
+            bail!(
+                "Failed to install ESPHome {} (pinned in {PIN_FILE}): {}",
+                self.version,
+                platform::pip_output_report(&output)
+            );
+        }
+        info!("ESPHome {} venv ready", self.version);
+        self.mark_used();
+        Ok(())
+    }
+
+    /// Note that the venv is about to run, for the Versions window.
+    fn mark_used(&self) {
+        let now = crate::audit::rfc3339(SystemTime::now());
+        if let Err(e) = std::fs::write(self.dir.join(LAST_USED_FILE), now) {
+            warn!(
+                "Failed to note the ESPHome {} venv's use: {}",
+                self.version, e
+            );
+        }
+    }
+}
+
+/// A per-version venv in the app data.
+#[derive(Debug, Clone)]
+pub(crate) struct InstalledPin {
+    pub version: String,
+    pub dir: PathBuf,
+    /// When it last ran a dashboard, as RFC 3339; `None` before this was
+    /// recorded.
+    pub last_used: Option<String>,
+}
+
+/// The venvs pins and comparisons have installed, newest version first.
+pub(crate) fn installed(data_dir: &Path) -> Vec<InstalledPin> {
+    let Ok(entries) = std::fs::read_dir(data_dir.join(PINNED_DIR)) else {
+        return Vec::new();
+    };
+    let mut pins: Vec<InstalledPin> = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().is_dir())
+        .filter_map(|e| {
+            let version = e.file_name().to_str()?.to_string();
+            check_version(&version).ok()?;
+            let dir = e.path();
+            let last_used = std::fs::read_to_string(dir.join(LAST_USED_FILE))
+                .ok()
+                .map(|text| text.trim().to_string());
+            Some(InstalledPin {
+                version,
+                dir,
+                last_used,
+            })
+        })
+        .collect();
+    pins.sort_by(|a, b| version_key(&b.version).cmp(&version_key(&a.version)));
+    pins
+}
+
+/// `2024.10.1` sorts after `2024.6.4`: the numbers in a version, compared as
+/// numbers.
+fn version_key(version: &str) -> Vec<u64> {
+    version
+        .split(|c: char| !c.is_ascii_digit())
+        .filter_map(|part| part.parse().ok())
+        .collect()
+}
+
+/// Pin `config_dir` to ESPHome `version`, or drop its pin with `None`. The
+/// running backend keeps its ESPHome until the app next starts.
+pub(crate) fn write_pin(config_dir: &Path, version: Option<&str>) -> Result<()> {
+    let path = config_dir.join(PIN_FILE);
+    match version {
+        Some(version) => {
+            check_version(version)?;
+            crate::util::atomic_write(&path, format!("{version}\n"))
+        }
+        None => match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(e).with_context(|| format!("Failed to remove {path:?}"))
+            }
+            _ => Ok(()),
+        },
+    }
+}
+
+/// The version `config_dir` pins, or `None` without a valid pin.
+pub(crate) fn current_pin(config_dir: &Path) -> Option<String> {
+    read_pin(config_dir).ok().flatten()
+}
+
+impl super::DaemonManager {
+    /// The ESPHome version the config directory pins, if it does
+    pub fn pinned_esphome(&self) -> Option<&str> {
+        self.backend.pinned_esphome()
+    }
+}
+
+async fn has_backend(python: &Path) -> bool {
+    let mut cmd = Command::new(python);
+    cmd.args(["-c", "import esphome_device_builder"]);
+    platform::isolate_python_tokio_command(&mut cmd);
+    platform::configure_no_window_tokio_command(&mut cmd);
+    platform::run_pip(cmd)
+        .await
+        .is_ok_and(|output| output.status.success())
+}
+
+/// The version in the pin file, if there is one. A pin that isn't a plain
+/// version is an error rather than ignored: running the workspace on the
+/// wrong ESPHome is what the pin is there to prevent.
+fn read_pin(config_dir: &Path) -> Result<Option<String>> {
+    let path = config_dir.join(PIN_FILE);
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {path:?}")),
+    };
+    let version = text.trim();
+    check_version(version).with_context(|| format!("{path:?} should hold an ESPHome version"))?;
+    Ok(Some(version.to_string()))
+}
+
+/// Refuse anything but a plain release like `2024.6.4` or `2025.2.0b1`; the
+/// version goes into a pip requirement and a directory name.
+pub(super) fn check_version(version: &str) -> Result<()> {
+    let valid = version.starts_with(|c: char| c.is_ascii_digit())
+        && version
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.');
+    if !valid {
+        bail!("{version:?} isn't an ESPHome version like 2024.6.4");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::unique_temp_dir;
+    use std::fs;
+
+    #[test]
+    fn reads_a_version_and_rejects_anything_else() {
+        let dir = unique_temp_dir("pin");
+        assert_eq!(read_pin(&dir).unwrap(), None);
+
+        fs::write(dir.join(PIN_FILE), "2024.6.4\n").unwrap();
+        let pinned = PinnedEnv::find(&dir, Path::new("/data"), Path::new("/py"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(pinned.version, "2024.6.4");
+        assert!(pinned.python().starts_with("/data/pinned/2024.6.4"));
+
+        fs::write(dir.join(PIN_FILE), "2025.2.0b1").unwrap();
+        assert!(read_pin(&dir).is_ok());
+        for bad in ["", "latest", ">=2024.1", "2024.6.4 --index-url x"] {
+            fs::write(dir.join(PIN_FILE), bad).unwrap();
+            assert!(read_pin(&dir).is_err(), "{bad}");
+        }
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn lists_venvs_and_switches_the_pin() {
+        let data = unique_temp_dir("pinned-data");
+        for version in ["2024.6.4", "2024.10.1", "not-a-version"] {
+            fs::create_dir_all(data.join(PINNED_DIR).join(version)).unwrap();
+        }
+        PinnedEnv::new("2024.6.4".into(), &data, Path::new("/py")).mark_used();
+        let pins = installed(&data);
+        let versions: Vec<&str> = pins.iter().map(|p| p.version.as_str()).collect();
+        assert_eq!(versions, ["2024.10.1", "2024.6.4"]);
+        assert!(pins[0].last_used.is_none());
+        assert!(pins[1].last_used.is_some());
+
+        let config = unique_temp_dir("pinned-config");
+        write_pin(&config, Some("2024.6.4")).unwrap();
+        assert_eq!(current_pin(&config).as_deref(), Some("2024.6.4"));
+        assert!(write_pin(&config, Some("latest")).is_err());
+        write_pin(&config, None).unwrap();
+        write_pin(&config, None).unwrap();
+        assert_eq!(current_pin(&config), None);
+
+        let _ = fs::remove_dir_all(&data);
+        let _ = fs::remove_dir_all(&config);
+    }
+}
@@ -0,0 +1,113 @@
+//! Moving the dashboard off a taken port. The address is fixed once the
+//! [`DaemonManager`](super::DaemonManager) is built, so the port is probed
+//! at launch, before that: when another program holds it, the dashboard
+//! moves to the first free port of `port_range` (or of the ten after it),
+//! and the move is saved, so the tray, the URLs the app opens and the next
+//! launch all agree on it. Without a free one the dashboard is left to fail
+//! on the port it has, and the user is told why.
+
+use std::net::{SocketAddr, TcpListener};
+
+use tauri::AppHandle;
+use tracing::{info, warn};
+
+use crate::i18n::{t, t_with};
+use crate::settings::Settings;
+
+/// Ports tried after the configured one without a `port_range`.
+const FALLBACK_SPAN: u16 = 10;
+
+/// What probing the dashboard's port found.
+#[derive(Debug, PartialEq, Eq)]
+enum Probe {
+    /// The port is free.
+    Free,
+    /// The port is taken; this one is free.
+    Moved(u16),
+    /// The port and every fallback are taken.
+    Taken,
+}
+
+/// Probe `address`, then the ports of `range` (or the ten after it), with
+/// `free` telling whether an address can be listened on.
+fn probe(
+    address: SocketAddr,
+    range: Option<(u16, u16)>,
+    free: impl Fn(SocketAddr) -> bool,
+) -> Probe {
+    if free(address) {
+        return Probe::Free;
+    }
+    let port = address.port();
+    let (first, last) =
+        range.unwrap_or((port.saturating_add(1), port.saturating_add(FALLBACK_SPAN)));
+    (first..=last)
+        .filter(|&candidate| candidate != port && candidate != 0)
+        .find(|&candidate| free(SocketAddr::new(address.ip(), candidate)))
+        .map_or(Probe::Taken, Probe::Moved)
+}
+
+/// Move `settings` off a taken dashboard port, saving the move and telling
+/// the user about it.
+pub(crate) fn ensure_free(app_handle: &AppHandle, settings: &mut Settings) {
+    let address = settings.dashboard_address();
+    let taken = address.port().to_string();
+    match probe(address, settings.port_range, |a| {
+        TcpListener::bind(a).is_ok()
+    }) {
+        Probe::Free => {}
+        Probe::Moved(port) => {
+            warn!("Port {} is in use; moving the dashboard to {}", taken, port);
+            settings.port = port;
+            if let Err(e) = settings.save(app_handle) {
+                warn!("Failed to save the dashboard's new port: {:#}", e);
+            }
+            notify(
+                app_handle,
+                t_with(
+                    "daemon.port_moved",
+                    &[("taken", &taken), ("port", &port.to_string())],
+                ),
+            );
+        }
+        Probe::Taken => {
+            warn!("Port {} and its fallbacks are in use", taken);
+            notify(app_handle, t_with("daemon.port_taken", &[("port", &taken)]));
+        }
+    }
+}
+
+fn notify(app_handle: &AppHandle, body: String) {
+    info!("{}", body);
+    if let Err(e) = crate::notifications::show(app_handle, t("daemon.port_title"), body) {
+        warn!("Failed to show the port notification: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moves_to_the_first_free_fallback() {
+        let address: SocketAddr = "127.0.0.1:6052".parse().unwrap();
+        let taken = |busy: Vec<u16>| move |a: SocketAddr| !busy.contains(&a.port());
+
+        assert_eq!(probe(address, None, taken(vec![])), Probe::Free);
+        assert_eq!(
+            probe(address, None, taken(vec![6052, 6053])),
+            Probe::Moved(6054)
+        );
+        assert_eq!(
+            probe(address, Some((7000, 7001)), taken(vec![6052, 7000])),
+            Probe::Moved(7001)
+        );
+        // A range holding the port itself skips it.
+        assert_eq!(
+            probe(address, Some((6052, 6052)), taken(vec![6052])),
+            Probe::Taken
+        );
+        let all = (6052..=6062).collect();
+        assert_eq!(probe(address, None, taken(all)), Probe::Taken);
+    }
+}
@@ -0,0 +1,362 @@
+//! The startup page (`startup_page`): the app keeps the dashboard's address
+//! for itself and forwards each connection to the backend, which listens on
+//! a loopback port of its own. While nothing answers there (the backend is
+//! starting, restarting or stopped) a connection gets a small page that
+//! says so and reloads itself, with `503` and `Retry-After`, instead of the
+//! browser's connection error. The backend then sees every client as this
+//! machine, so with `access_summary` on the proxy reports who connected (see
+//! [`super::access::Peers`]).
+//!
+//! Across a restart the backend is away for a few seconds, so a connection
+//! first waits for it ([`Hold`]): a dashboard tab's requests and reconnects
+//! then land on the new backend instead of failing, and a reload shows the
+//! dashboard rather than the page.
+
+use anyhow::{Context, Result};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{debug, info, warn};
+
+use super::access::Peers;
+use crate::i18n::t;
+use crate::store::{DaemonState, Store};
+use crate::tasks::Tasks;
+
+/// Seconds the page waits before reloading, and the `Retry-After` it sends.
+const RETRY_SECS: u64 = 2;
+
+/// The most of a request read before answering with the page.
+const MAX_HEAD: usize = 8 * 1024;
+
+/// How long a client gets to send its request line and headers.
+const HEAD_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often a held connection tries the backend again.
+const HOLD_POLL: Duration = Duration::from_millis(250);
+
+/// How long a connection waits for the backend before it gets the page.
+#[derive(Debug, Clone, Copy)]
+struct Hold {
+    /// While the backend is starting or running: long enough for most
+    /// restarts.
+    starting: Duration,
+    /// While it is stopped or crashed. A restart passes through stopped too,
+    /// so this isn't zero, but a backend that stays down gets the page soon.
+    stopped: Duration,
+}
+
+impl Hold {
+    const DEFAULT: Hold = Hold {
+        starting: Duration::from_secs(20),
+        stopped: Duration::from_secs(5),
+    };
+}
+
+/// Where the backend listens: behind the page a loopback port that was free
+/// when picked, shared with the connections forwarded to it; otherwise the
+/// dashboard's address.
+#[derive(Debug, Clone)]
+pub(super) struct Upstream {
+    address: Arc<Mutex<SocketAddr>>,
+    /// Whether the port was picked here, so [`Upstream::claim`] may move it.
+    picked: bool,
+}
+
+impl Upstream {
+    pub(super) fn fixed(address: SocketAddr) -> Self {
+        Self {
+            address: Arc::new(Mutex::new(address)),
+            picked: false,
+        }
+    }
+
+    pub(super) fn get(&self) -> SocketAddr {
+        *self.address.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The address for the next start. A picked port was only free when it
+    /// was picked; if another program has bound it since, the backend moves
+    /// to a fresh one rather than fail to start on it.
+    pub(super) fn claim(&self) -> SocketAddr {
+        let mut address = self.address.lock().unwrap_or_else(|e| e.into_inner());
+        if self.picked && std::net::TcpListener::bind(*address).is_err() {
+            match free_loopback_port(address.ip()) {
+                Ok(fresh) => {
+                    warn!("Backend port {} is taken; moving it to {}", address, fresh);
+                    *address = fresh;
+                }
+                Err(e) => warn!("Backend port {} is taken: {:#}", address, e),
+            }
+        }
+        *address
+    }
+}
+
+/// Listen on `address` for the life of `tasks`, forwarding to where the
+/// backend should listen, which this returns. Each client's address goes to
+/// `peers`, if given.
+pub(super) fn spawn(
+    tasks: &Tasks,
+    address: SocketAddr,
+    store: Store,
+    peers: Option<Peers>,
+) -> Result<Upstream> {
+    listen(tasks, address, store, peers, Hold::DEFAULT)
+}
+
+fn listen(
+    tasks: &Tasks,
+    address: SocketAddr,
+    store: Store,
+    peers: Option<Peers>,
+    hold: Hold,
+) -> Result<Upstream> {
+    let listener = std::net::TcpListener::bind(address)
+        .with_context(|| format!("Failed to listen on {address}"))?;
+    listener.set_nonblocking(true)?;
+    let upstream = Upstream {
+        address: Arc::new(Mutex::new(free_loopback_port(address.ip())?)),
+        picked: true,
+    };
+    info!(
+        "Serving the startup page on {}, backend on {}",
+        address,
+        upstream.get()
+    );
+    let forwarded = upstream.clone();
+    tasks.spawn("startup page", async move {
+        let listener = match TcpListener::from_std(listener) {
+            Ok(listener) => listener,
+            Err(e) => {
+                warn!("Startup page not served: {}", e);
+                return;
+            }
+        };
+        loop {
+            match listener.accept().await {
+                Ok((client, peer)) => {
+                    if let Some(peers) = &peers {
+                        peers.push(peer.ip());
+                    }
+                    tokio::spawn(forward(client, forwarded.clone(), store.clone(), hold));
+                }
+                Err(e) => warn!("Failed to accept a dashboard connection: {}", e),
+            }
+        }
+    });
+    Ok(upstream)
+}
+
+/// A loopback address of the same family as `ip` with a port nothing uses
+/// right now.
+fn free_loopback_port(ip: IpAddr) -> Result<SocketAddr> {
+    let loopback: IpAddr = match ip {
+        IpAddr::V4(_) => Ipv4Addr::LOCALHOST.into(),
+        IpAddr::V6(_) => Ipv6Addr::LOCALHOST.into(),
+    };
+    let probe = std::net::TcpListener::bind((loopback, 0))
+        .context("Failed to find a free port for the backend")?;
+    Ok(probe.local_addr()?)
+}
+
+/// Pipe `client` to the backend, or answer with the page if it doesn't
+/// listen within the hold.
+async fn forward(mut client: TcpStream, upstream: Upstream, store: Store, hold: Hold) {
+    let started = Instant::now();
+    loop {
+        let stopped = is_stopped(&store);
+        match TcpStream::connect(upstream.get()).await {
+            Ok(mut backend) => {
+                let _ = tokio::io::copy_bidirectional(&mut client, &mut backend).await;
+                return;
+            }
+            Err(e) => {
+                let limit = if stopped { hold.stopped } else { hold.starting };
+                if started.elapsed() >= limit {
+                    debug!("Backend not answering ({}); serving the startup page", e);
+                    if let Err(e) = placeholder(&mut client, stopped).await {
+                        debug!("Failed to serve the startup page: {}", e);
+                    }
+                    return;
+                }
+            }
+        }
+        tokio::time::sleep(HOLD_POLL).await;
+    }
+}
+
+fn is_stopped(store: &Store) -> bool {
+    matches!(
+        store.snapshot().daemon,
+        DaemonState::Stopped | DaemonState::Crashed(_)
+    )
+}
+
+/// Read the request head, then answer with the page.
+async fn placeholder(client: &mut TcpStream, stopped: bool) -> std::io::Result<()> {
+    let mut head = Vec::new();
+    let mut buf = [0u8; 1024];
+    let read = async {
+        while head.len() < MAX_HEAD && !head.windows(4).any(|w| w == b"\r\n\r\n") {
+            let n = client.read(&mut buf).await?;
+            if n == 0 {
+                break;
+            }
+            head.extend_from_slice(&buf[..n]);
+        }
+        Ok::<_, std::io::Error>(())
+    };
+    let _ = tokio::time::timeout(HEAD_TIMEOUT, read).await;
+    let (title, body) = if stopped {
+        (t("proxy.stopped_title"), t("proxy.stopped_body"))
+    } else {
+        (t("proxy.starting_title"), t("proxy.starting_body"))
+    };
+    client.write_all(response(&title, &body).as_bytes()).await?;
+    client.shutdown().await
+}
+
+fn response(title: &str, body: &str) -> String {
+    let page = format!(
+        "<!doctype html><html><head><meta charset=\"utf-8\">\
+         <meta http-equiv=\"refresh\" content=\"{RETRY_SECS}\"><title>{title}</title>\
+         <style>body{{font-family:system-ui,sans-serif;margin:20vh auto;max-width:32em;\
+         padding:0 1em;text-align:center;color:#333}}</style></head>\
+         <body><h1>{title}</h1><p>{body}</p></body></html>",
+        title = escape(title),
+        body = escape(body),
+    );
+    format!(
+        "HTTP/1.1 503 Service Unavailable\r\nRetry-After: {RETRY_SECS}\r\n\
+         Content-Type: text/html; charset=utf-8\r\nCache-Control: no-store\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n{page}",
+        page.len()
+    )
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::store::Event;
+
+    const SHORT: Hold = Hold {
+        starting: Duration::from_secs(10),
+        stopped: Duration::ZERO,
+    };
+
+    fn front_address() -> SocketAddr {
+        let front = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        front.local_addr().unwrap()
+    }
+
+    async fn get(address: SocketAddr) -> String {
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        stream
+            .write_all(b"GET / HTTP/1.1\r\nHost: x\r\n\r\n")
+            .await
+            .unwrap();
+        let mut reply = String::new();
+        stream.read_to_string(&mut reply).await.unwrap();
+        reply
+    }
+
+    async fn backend(upstream: SocketAddr) {
+        let backend = TcpListener::bind(upstream).await.unwrap();
+        tokio::spawn(async move {
+            let (mut conn, _) = backend.accept().await.unwrap();
+            let mut buf = [0u8; 64];
+            let _ = conn.read(&mut buf).await.unwrap();
+            conn.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok")
+                .await
+                .unwrap();
+        });
+    }
+
+    #[tokio::test]
+    async fn serves_the_page_until_the_backend_listens() {
+        let address = front_address();
+        let tasks = Tasks::default();
+        let store = Store::default();
+        store.publish(Event::Daemon(DaemonState::Stopped));
+        let upstream = listen(&tasks, address, store, None, SHORT).unwrap();
+
+        let reply = get(address).await;
+        assert!(reply.starts_with("HTTP/1.1 503"), "{reply}");
+        assert!(reply.contains("Retry-After: 2"), "{reply}");
+        assert!(reply.contains("http-equiv=\"refresh\""), "{reply}");
+
+        backend(upstream.get()).await;
+        assert!(get(address).await.starts_with("HTTP/1.1 200 OK"));
+        tasks.cancel();
+    }
+
+    #[tokio::test]
+    async fn holds_requests_while_the_backend_restarts() {
+        let address = front_address();
+        let tasks = Tasks::default();
+        let upstream = listen(&tasks, address, Store::default(), None, SHORT).unwrap();
+
+        let request = tokio::spawn(get(address));
+        tokio::time::sleep(Duration::from_millis(600)).await;
+        backend(upstream.get()).await;
+        let reply = request.await.unwrap();
+        assert!(reply.starts_with("HTTP/1.1 200 OK"), "{reply}");
+        tasks.cancel();
+    }
+
+    #[tokio::test]
+    async fn reports_its_clients_with_the_access_summary_on() {
+        let address = front_address();
+        let tasks = Tasks::default();
+        let peers = Peers::default();
+        let upstream = listen(
+            &tasks,
+            address,
+            Store::default(),
+            Some(peers.clone()),
+            SHORT,
+        )
+        .unwrap();
+        backend(upstream.get()).await;
+
+        assert!(get(address).await.starts_with("HTTP/1.1 200 OK"));
+        assert_eq!(peers.take(), [IpAddr::from(Ipv4Addr::LOCALHOST)]);
+        tasks.cancel();
+    }
+
+    #[test]
+    fn moves_a_picked_port_another_program_took() {
+        let taken = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = taken.local_addr().unwrap();
+        let picked = Upstream {
+            address: Arc::new(Mutex::new(port)),
+            picked: true,
+        };
+        let moved = picked.claim();
+        assert_ne!(moved, port);
+        assert!(moved.ip().is_loopback());
+        assert_eq!(picked.get(), moved);
+
+        // The dashboard's own address is never moved.
+        assert_eq!(Upstream::fixed(port).claim(), port);
+    }
+
+    #[test]
+    fn escapes_the_page_text() {
+        let page = response("Starting", "A <b>\"quoted\"</b> & more");
+        assert!(page.contains("A &lt;b&gt;&quot;quoted&quot;&lt;/b&gt; &amp; more"));
+        let length = page.split("\r\n\r\n").nth(1).unwrap().len();
+        assert!(page.contains(&format!("Content-Length: {length}")));
+    }
+}
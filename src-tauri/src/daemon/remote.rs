@@ -0,0 +1,210 @@
+//! Using a dashboard that already runs elsewhere.
+//!
+//! With `runtime = "remote"` nothing is provisioned or started: the backend is
+//! the device builder at `remote_address` (`host:port`), e.g. one a home
+//! server runs in a container. So that the tray, the health check and the
+//! browser keep using the local URL, and the supervisor still has a child
+//! to start, stop and watch, the child is this app run as a relay
+//! (`--relay <ip:port>=<address>`) forwarding the dashboard's local address
+//! to it. A stop stops the relay; the remote dashboard keeps running.
+
+use anyhow::{Context, Result};
+use std::io;
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Duration;
+use tokio::process::Command;
+use tracing::{info, warn};
+
+use super::backend::{self, DaemonBackend, Prepare};
+use crate::settings::{Runtime, Settings};
+
+/// How long the start waits to reach the remote dashboard.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone)]
+pub(crate) struct RemoteBackend {
+    /// `None` fails the start.
+    address: Option<String>,
+}
+
+impl RemoteBackend {
+    pub(crate) fn from_settings(settings: &Settings) -> Self {
+        Self {
+            address: backend::non_blank(settings.remote_address.as_deref()),
+        }
+    }
+
+    /// Fail the start early, with the address in the message, when the
+    /// dashboard can't be reached; the relay alone would start fine and
+    /// leave the health check to find out.
+    async fn check_reachable(&self) -> Result<()> {
+        let address = backend::require(&self.address, "remote_address")?;
+        let connect = tokio::net::TcpStream::connect(address);
+        match tokio::time::timeout(CONNECT_TIMEOUT, connect).await {
+            Ok(Ok(_)) => Ok(()),
+            Ok(Err(e)) => Err(e).with_context(|| format!("Can't reach the dashboard at {address}")),
+            Err(_) => anyhow::bail!("Can't reach the dashboard at {address}: timed out"),
+        }
+    }
+}
+
+impl DaemonBackend for RemoteBackend {
+    fn runtime(&self) -> Runtime {
+        Runtime::Remote
+    }
+
+    fn prepare(&self) -> Prepare<'_> {
+        Box::pin(self.check_reachable())
+    }
+
+    fn command(&self, _config_dir: &Path, listen: SocketAddr, _low_priority: bool) -> Command {
+        let exe = std::env::current_exe().unwrap_or_else(|_| PathBuf::from("esphome-desktop"));
+        let mut cmd = Command::new(exe);
+        // `prepare` has failed the start already without an address.
+        let address = self.address.as_deref().unwrap_or_default();
+        cmd.arg("--relay").arg(relay_spec(listen, address));
+        cmd
+    }
+}
+
+fn relay_spec(listen: SocketAddr, address: &str) -> String {
+    format!("{listen}={address}")
+}
+
+/// The local address to listen on and the address of a `--relay` spec.
+fn parse_relay_spec(spec: &str) -> Result<(SocketAddr, &str)> {
+    let (listen, address) = spec
+        .split_once('=')
+        .with_context(|| format!("expected <ip:port>=<host:port>, got {spec:?}"))?;
+    let listen = listen
+        .parse()
+        .with_context(|| format!("bad listen address in {spec:?}"))?;
+    if address.is_empty() {
+        anyhow::bail!("no address in {spec:?}");
+    }
+    Ok((listen, address))
+}
+
+/// `--relay`: forward connections to the loopback port to the address until
+/// killed. Logs to stderr, which the supervisor sends to `dashboard.log`.
+pub(crate) fn run_relay(spec: &str) -> ExitCode {
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_ansi(false)
+        .init();
+    match relay(spec) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            tracing::error!("Relay failed: {e:#}");
+            ExitCode::from(1)
+        }
+    }
+}
+
+fn relay(spec: &str) -> Result<()> {
+    let (listen, address) = parse_relay_spec(spec)?;
+    let listener =
+        TcpListener::bind(listen).with_context(|| format!("Failed to listen on {listen}"))?;
+    info!("Relaying {listen} to {address}");
+    for client in listener.incoming() {
+        let client = match client {
+            Ok(client) => client,
+            Err(e) => {
+                warn!("Failed to accept a connection: {e}");
+                continue;
+            }
+        };
+        let address = address.to_string();
+        std::thread::spawn(move || {
+            if let Err(e) = pipe(client, &address) {
+                warn!("Failed to relay a connection to {address}: {e}");
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Copy both ways between `client` and a new connection to `address` until
+/// both sides are done. A WebSocket (the dashboard's logs and compile output)
+/// is just a long-lived connection to this.
+fn pipe(client: TcpStream, address: &str) -> io::Result<()> {
+    let server = TcpStream::connect(address)?;
+    let (mut from_client, mut to_server) = (client.try_clone()?, server.try_clone()?);
+    let upstream = std::thread::spawn(move || {
+        let _ = io::copy(&mut from_client, &mut to_server);
+        let _ = to_server.shutdown(Shutdown::Write);
+    });
+    let (mut from_server, mut to_client) = (server, client);
+    let _ = io::copy(&mut from_server, &mut to_client);
+    let _ = to_client.shutdown(Shutdown::Write);
+    let _ = upstream.join();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+
+    #[test]
+    fn relay_specs_round_trip() {
+        let loopback = SocketAddr::from(([127, 0, 0, 1], 6052));
+        let spec = relay_spec(loopback, "192.168.1.20:6052");
+        assert_eq!(
+            parse_relay_spec(&spec).unwrap(),
+            (loopback, "192.168.1.20:6052")
+        );
+        assert_eq!(
+            parse_relay_spec("[::1]:6052=[fd00::2]:6052").unwrap(),
+            ("[::1]:6052".parse().unwrap(), "[fd00::2]:6052")
+        );
+        assert!(parse_relay_spec("127.0.0.1:6052").is_err());
+        assert!(parse_relay_spec("6052=host:1").is_err());
+        assert!(parse_relay_spec("127.0.0.1:6052=").is_err());
+    }
+
+    #[test]
+    fn pipes_both_ways() {
+        let server = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = server.local_addr().unwrap().to_string();
+        let echo = std::thread::spawn(move || {
+            let (mut conn, _) = server.accept().unwrap();
+            let mut request = [0; 4];
+            conn.read_exact(&mut request).unwrap();
+            conn.write_all(b"pong").unwrap();
+            request
+        });
+
+        let front = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = front.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            let (client, _) = front.accept().unwrap();
+            pipe(client, &address).unwrap();
+        });
+
+        let mut client = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        client.write_all(b"ping").unwrap();
+        let mut reply = String::new();
+        client.read_to_string(&mut reply).unwrap();
+        assert_eq!(reply, "pong");
+        assert_eq!(&echo.join().unwrap(), b"ping");
+    }
+
+    #[tokio::test]
+    async fn a_start_fails_when_the_dashboard_is_unreachable() {
+        // Bound and dropped again: nothing listens there.
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let remote = RemoteBackend::from_settings(&Settings {
+            remote_address: Some(format!("127.0.0.1:{port}")),
+            ..Settings::default()
+        });
+        let err = remote.prepare().await.unwrap_err();
+        assert!(format!("{err:#}").contains("Can't reach"), "{err:#}");
+    }
+}
@@ -0,0 +1,178 @@
+//! Asking the backend to exit.
+//!
+//! A stop asks first and escalates only as the grace period runs out: the
+//! dashboard needs time to finish an OTA upload, flush its state and release
+//! its locks, and cutting it short mid-write corrupts it. The steps, by when
+//! they are taken:
+//!
+//! 1. A native backend is asked over HTTP (`POST /shutdown` on the loopback
+//!    port). A dashboard without that endpoint answers an error, and the next
+//!    step follows at once.
+//! 2. The platform's interrupt: `SIGINT` to the process group on Unix, which
+//!    Python turns into a `KeyboardInterrupt` the server loop exits on, and
+//!    `CTRL_BREAK_EVENT` on Windows.
+//! 3. On Unix, `SIGTERM` halfway through what is left.
+//!
+//! Nothing follows on Unix: a backend that outlasts the grace period is left
+//! running and the stop reported as failed, never `SIGKILL`ed. On Windows the
+//! caller falls back to `TerminateProcess`, the only guarantee it offers.
+
+use std::net::SocketAddr;
+use std::process::ExitStatus;
+use std::time::Duration;
+use tokio::process::Child;
+use tokio::time::Instant;
+use tracing::{debug, info};
+
+use super::health::probe_url;
+
+/// How long a stop waits without the `stop_timeout` setting. We have measured
+/// up to 30 s of draining in the wild.
+pub(crate) const DEFAULT_GRACE: Duration = Duration::from_secs(30);
+
+/// Path of the dashboard's shutdown endpoint, under [`probe_url`].
+const SHUTDOWN_PATH: &str = "shutdown";
+
+/// How long the shutdown request may take before the signals go out instead.
+const HTTP_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// One way of asking.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Step {
+    Http,
+    /// `SIGINT` on Unix, `CTRL_BREAK_EVENT` on Windows.
+    Interrupt,
+    /// `SIGTERM`. Unix only.
+    Terminate,
+}
+
+/// The steps of a stop with `grace` to spare, each with how long into the
+/// stop it is taken. `http` is whether the shutdown endpoint is reachable
+/// from here.
+pub(super) fn plan(grace: Duration, http: bool) -> Vec<(Duration, Step)> {
+    let mut steps = Vec::new();
+    let mut rest = grace;
+    if http {
+        steps.push((Duration::ZERO, Step::Http));
+        rest = grace * 2 / 3;
+    }
+    let interrupt_at = grace - rest;
+    steps.push((interrupt_at, Step::Interrupt));
+    if cfg!(unix) {
+        steps.push((interrupt_at + rest / 2, Step::Terminate));
+    }
+    steps
+}
+
+/// Take `steps` until `child` exits or `grace` is up. The HTTP step goes to
+/// the backend's own `address`: the startup page would hold it while the
+/// backend winds down. `signal` takes the signal steps. `None` when the
+/// child is still running.
+pub(super) async fn drain(
+    child: &mut Child,
+    address: SocketAddr,
+    steps: &[(Duration, Step)],
+    grace: Duration,
+    mut signal: impl FnMut(Step),
+) -> Option<std::io::Result<ExitStatus>> {
+    let start = Instant::now();
+    // Set after a shutdown request nobody honoured, so the next step doesn't
+    // wait for an exit that isn't coming.
+    let mut hurry = false;
+    for &(at, step) in steps {
+        if !hurry {
+            if let Ok(status) = tokio::time::timeout_at(start + at, child.wait()).await {
+                return Some(status);
+            }
+        }
+        debug!("Stopping the backend: {:?}", step);
+        hurry = match step {
+            Step::Http => !request_shutdown(address).await,
+            step => {
+                signal(step);
+                false
+            }
+        };
+    }
+    tokio::time::timeout_at(start + grace, child.wait())
+        .await
+        .ok()
+}
+
+/// Ask the dashboard at `address` to shut down. Whether it agreed.
+async fn request_shutdown(address: SocketAddr) -> bool {
+    let client = match crate::network::loopback_client()
+        .timeout(HTTP_TIMEOUT)
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => {
+            debug!("No HTTP client for the shutdown request: {}", e);
+            return false;
+        }
+    };
+    let url = format!("{}{SHUTDOWN_PATH}", probe_url(address));
+    match client.post(&url).send().await {
+        Ok(response) if response.status().is_success() => {
+            info!("Asked the dashboard to shut down");
+            true
+        }
+        Ok(response) => {
+            debug!(
+                "Dashboard declined the shutdown request: {}",
+                response.status()
+            );
+            false
+        }
+        Err(e) => {
+            debug!("Shutdown request failed: {}", e);
+            false
+        }
+    }
+}
+
+/// Send `step`'s signal to the process group led by `pid`, then continue any
+/// build the thermal monitor paused so it can act on it.
+#[cfg(unix)]
+pub(super) fn send(pid: u32, step: Step) {
+    use nix::sys::signal::{killpg, Signal};
+    use nix::unistd::Pid;
+
+    let signal = match step {
+        Step::Interrupt => Signal::SIGINT,
+        Step::Terminate => Signal::SIGTERM,
+        Step::Http => return,
+    };
+    let _ = killpg(Pid::from_raw(pid as i32), signal);
+    let _ = killpg(Pid::from_raw(pid as i32), Signal::SIGCONT);
+}
+
+/// Deliver `CTRL_BREAK_EVENT` to the process group led by `pid` (Python
+/// surfaces it as `SIGBREAK`).
+#[cfg(windows)]
+pub(super) fn send(pid: u32, step: Step) {
+    if step == Step::Interrupt {
+        let _ = crate::platform::send_ctrl_break(pid);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps_escalate_within_the_grace_period() {
+        let grace = Duration::from_secs(30);
+        let steps = plan(grace, true);
+        assert_eq!(steps[0], (Duration::ZERO, Step::Http));
+        assert_eq!(steps[1], (Duration::from_secs(10), Step::Interrupt));
+        if cfg!(unix) {
+            assert_eq!(steps[2], (Duration::from_secs(20), Step::Terminate));
+        }
+        assert!(steps.windows(2).all(|w| w[0].0 <= w[1].0));
+        assert!(steps.iter().all(|&(at, _)| at < grace));
+
+        // Without the endpoint the interrupt goes out at once.
+        assert_eq!(plan(grace, false)[0], (Duration::ZERO, Step::Interrupt));
+    }
+}
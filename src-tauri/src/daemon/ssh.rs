@@ -0,0 +1,157 @@
+//! Running the device builder on another machine over SSH.
+//!
+//! With `runtime = "ssh"` the backend runs on `ssh_host` (anything `ssh`
+//! accepts: `user@host`, or a `Host` from `~/.ssh/config`), for offloading
+//! compiles to a faster box. It runs from a venv there, at the same place as
+//! the WSL2 runtime's and provisioned the same way on first start, and serves
+//! `ssh_config_dir`, a directory on that machine (relative to its home). The
+//! local config directory isn't used by the dashboard.
+//!
+//! The dashboard listens on the remote loopback interface and `ssh` forwards
+//! the local port to it, so the tray, the health check and the browser keep
+//! using the loopback URL and nothing is exposed on either network. `ssh`
+//! runs in batch mode: authentication has to work without a prompt, e.g.
+//! with a key the agent holds.
+
+use anyhow::{Context, Result};
+use std::net::SocketAddr;
+use std::path::Path;
+use tokio::process::Command;
+use tracing::{info, warn};
+
+use super::backend::{self, DaemonBackend, Prepare};
+use super::wsl::{VENV_DIR, VENV_PROCESS_PATTERN};
+use crate::platform;
+use crate::settings::{Runtime, Settings};
+
+/// Config directory on the remote machine when `ssh_config_dir` is unset.
+const DEFAULT_CONFIG_DIR: &str = "esphome";
+
+/// Options for every `ssh` run: never prompt, and notice a dead connection
+/// instead of leaving the tunnel hanging.
+pub(crate) const SSH_OPTIONS: [&str; 4] = ["-o", "BatchMode=yes", "-o", "ServerAliveInterval=15"];
+
+#[derive(Debug, Clone)]
+pub(crate) struct SshBackend {
+    /// `None` fails the start.
+    host: Option<String>,
+    config_dir: String,
+}
+
+impl SshBackend {
+    pub(crate) fn from_settings(settings: &Settings) -> Self {
+        Self {
+            host: backend::non_blank(settings.ssh_host.as_deref()),
+            config_dir: backend::non_blank(settings.ssh_config_dir.as_deref())
+                .unwrap_or_else(|| DEFAULT_CONFIG_DIR.to_string()),
+        }
+    }
+
+    /// Create the config directory and the venv on the host if they aren't
+    /// there yet.
+    async fn ensure_venv(&self) -> Result<()> {
+        let host = backend::require(&self.host, "ssh_host")?;
+        let script = format!(
+            "mkdir -p {}; {}",
+            backend::sh_quote(&self.config_dir),
+            backend::provision_script(VENV_DIR)
+        );
+        let mut cmd = Command::new("ssh");
+        cmd.args(SSH_OPTIONS).arg(host).arg(script);
+        platform::configure_no_window_tokio_command(&mut cmd);
+        info!("Checking the venv on {host}");
+        let output = cmd
+            .output()
+            .await
+            .context("Failed to run ssh; is an OpenSSH client installed?")?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "Failed to provision the venv on {host}: {}",
+                platform::pip_output_report(&output)
+            );
+        }
+        Ok(())
+    }
+}
+
+impl DaemonBackend for SshBackend {
+    fn runtime(&self) -> Runtime {
+        Runtime::Ssh
+    }
+
+    fn prepare(&self) -> Prepare<'_> {
+        Box::pin(self.ensure_venv())
+    }
+
+    /// `ssh` forwarding the port and running the backend in the foreground,
+    /// so the child is the connection and its output is the backend's. Quits
+    /// if the port can't be forwarded rather than serving nothing.
+    fn command(&self, _config_dir: &Path, address: SocketAddr, low_priority: bool) -> Command {
+        let port = address.port();
+        // `prepare` has failed the start already without a host.
+        let host = self.host.as_deref().unwrap_or_default();
+        let mut cmd = Command::new("ssh");
+        cmd.args(SSH_OPTIONS)
+            .args(["-o", "ExitOnForwardFailure=yes", "-L"])
+            .arg(format!("{address}:127.0.0.1:{port}"))
+            .arg(host)
+            .arg(backend::launch_script(
+                VENV_DIR,
+                &self.config_dir,
+                "127.0.0.1",
+                port,
+                low_priority,
+            ));
+        cmd
+    }
+
+    /// Without a terminal, the backend outlives a connection that is closed
+    /// under it; stop it over a connection of its own.
+    fn terminate_blocking(&self) {
+        let Some(host) = &self.host else { return };
+        let mut cmd = std::process::Command::new("ssh");
+        cmd.args(SSH_OPTIONS).arg(host);
+        cmd.args(["pkill", "-TERM", "-f"])
+            .arg(backend::sh_quote(VENV_PROCESS_PATTERN));
+        platform::configure_no_window_command(&mut cmd);
+        if let Err(e) = cmd.output() {
+            warn!("Failed to signal the backend on {}: {}", host, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tunnels_the_port_to_the_remote_loopback() {
+        let ssh = SshBackend::from_settings(&Settings {
+            ssh_host: Some("builder@buildbox".into()),
+            ..Settings::default()
+        });
+        let args: Vec<String> = ssh
+            .command(Path::new("/unused"), ([127, 0, 0, 1], 6052).into(), false)
+            .as_std()
+            .get_args()
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect();
+        assert!(args
+            .windows(2)
+            .any(|w| w == ["-L", "127.0.0.1:6052:127.0.0.1:6052"]));
+        // Options go before the host; everything after it runs remotely.
+        assert_eq!(args[args.len() - 2], "builder@buildbox");
+        let script = args.last().unwrap();
+        assert!(
+            script.ends_with("'esphome' --host 127.0.0.1 --port 6052"),
+            "{script}"
+        );
+    }
+
+    #[tokio::test]
+    async fn a_start_without_a_host_fails() {
+        let ssh = SshBackend::from_settings(&Settings::default());
+        let err = ssh.prepare().await.unwrap_err();
+        assert!(err.to_string().contains("ssh_host"), "{err}");
+    }
+}
@@ -0,0 +1,47 @@
+//! A manager built from explicit paths rather than the app's settings, for
+//! runs without a Tauri app: `--self-test` and the test harness.
+
+use super::*;
+
+impl DaemonManager {
+    /// A native-runtime manager running `python_path` on `config_dir`, logging
+    /// to `logs_dir`, on the IPv4 loopback, with every optional setting off.
+    /// Nothing subscribes to its store, so there is no tray to update and no
+    /// notification on a crash.
+    pub(crate) fn standalone(
+        python_path: PathBuf,
+        python_bin_dir: PathBuf,
+        config_dir: PathBuf,
+        logs_dir: PathBuf,
+        port: u16,
+    ) -> Self {
+        let address = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+        Self {
+            process: Arc::new(Mutex::new(None)),
+            config_dir,
+            logs_dir,
+            address,
+            backend_address: proxy::Upstream::fixed(address),
+            backend: Box::new(backend::NativeBackend::new(
+                python_path,
+                python_bin_dir,
+                None,
+                None,
+            )),
+            locale_env: LocaleEnv::default(),
+            low_priority: false,
+            build_jobs: None,
+            thermal_limit: None,
+            sandbox: false,
+            stop_grace: shutdown::DEFAULT_GRACE,
+            stats: Arc::new(std::sync::Mutex::new(Stats::default())),
+            runaways: Arc::new(std::sync::Mutex::new(Vec::new())),
+            running: Arc::new(AtomicBool::new(false)),
+            dashboard_pid: Arc::new(AtomicPid::new(0)),
+            store: Store::default(),
+            tasks: Tasks::default(),
+            run_tasks: std::sync::Mutex::new(Tasks::default()),
+            app_handle: None,
+        }
+    }
+}
@@ -0,0 +1,163 @@
+//! Per-session backend counters: uptime, restarts, the last crash and the
+//! last health check.
+//!
+//! Kept in memory only. They describe this app session, which is what matters
+//! when debugging a flaky setup ("it restarted six times since lunch"). Each
+//! restart's reason goes to the store's history, which answers the follow-up
+//! question of why.
+
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::store::{Restart, RestartReason};
+
+/// Mutable counters, shared with the exit watcher behind a mutex.
+#[derive(Debug, Default)]
+pub(super) struct Stats {
+    /// When the running backend was spawned; `None` while stopped.
+    started_at: Option<Instant>,
+    /// Successful spawns this session.
+    starts: u32,
+    /// Why the backend last exited without being asked to.
+    last_crash: Option<String>,
+    /// Whether the last run ended in a crash rather than a stop.
+    crashed: bool,
+    /// When the latest periodic health check ran, and whether it passed.
+    last_health: Option<(Instant, bool)>,
+}
+
+/// A copy of the counters for status reporting.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StatsSnapshot {
+    /// How long the current backend has been up; `None` while stopped.
+    pub uptime: Option<Duration>,
+    /// Spawns after the first one this session, whether user-initiated
+    /// (Restart, updates, switches) or after a crash.
+    pub restarts: u32,
+    pub last_crash: Option<String>,
+    /// How long ago the latest periodic health check ran, and whether the
+    /// dashboard answered it.
+    pub last_health_check: Option<(Duration, bool)>,
+}
+
+impl Stats {
+    /// Record a successful spawn for `reason`; returns the restart it was,
+    /// if it wasn't the session's first start. A user's restart after a crash
+    /// counts as crash recovery.
+    pub(super) fn record_start(&mut self, reason: RestartReason) -> Option<Restart> {
+        self.started_at = Some(Instant::now());
+        self.starts += 1;
+        let crashed = std::mem::take(&mut self.crashed);
+        let number = self.restarts();
+        (number > 0).then(|| Restart {
+            number,
+            time: crate::audit::rfc3339(SystemTime::now()),
+            reason: match reason {
+                RestartReason::User if crashed => RestartReason::CrashRecovery,
+                reason => reason,
+            },
+        })
+    }
+
+    /// Record a confirmed stop.
+    pub(super) fn record_stop(&mut self) {
+        self.started_at = None;
+    }
+
+    /// Record an unexpected exit.
+    pub(super) fn record_crash(&mut self, reason: String) {
+        self.started_at = None;
+        self.last_crash = Some(reason);
+        self.crashed = true;
+    }
+
+    /// Record a periodic health check's outcome.
+    pub(super) fn record_health(&mut self, passed: bool) {
+        self.last_health = Some((Instant::now(), passed));
+    }
+
+    fn restarts(&self) -> u32 {
+        self.starts.saturating_sub(1)
+    }
+
+    pub(super) fn snapshot(&self) -> StatsSnapshot {
+        StatsSnapshot {
+            uptime: self.started_at.map(|t| t.elapsed()),
+            restarts: self.restarts(),
+            last_crash: self.last_crash.clone(),
+            last_health_check: self.last_health.map(|(at, passed)| (at.elapsed(), passed)),
+        }
+    }
+}
+
+/// Compact uptime for status output: the two most significant units, e.g.
+/// `2d 3h`, `4h 05m`, `6m 07s`, `8s`.
+pub(crate) fn format_uptime(uptime: Duration) -> String {
+    let secs = uptime.as_secs();
+    let (d, h, m, s) = (secs / 86_400, secs / 3600 % 24, secs / 60 % 60, secs % 60);
+    if d > 0 {
+        format!("{d}d {h}h")
+    } else if h > 0 {
+        format!("{h}h {m:02}m")
+    } else if m > 0 {
+        format!("{m}m {s:02}s")
+    } else {
+        format!("{s}s")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_start_is_not_a_restart() {
+        let mut stats = Stats::default();
+        assert_eq!(stats.record_start(RestartReason::User), None);
+        stats.record_crash("exit status: 1".into());
+        let restart = stats.record_start(RestartReason::User).unwrap();
+        assert_eq!(restart.number, 1);
+        assert_eq!(restart.reason, RestartReason::CrashRecovery);
+        let restart = stats.record_start(RestartReason::User).unwrap();
+        assert_eq!(restart.reason, RestartReason::User);
+        stats.record_crash("exit status: 1".into());
+        let restart = stats.record_start(RestartReason::Update).unwrap();
+        assert_eq!(restart.reason, RestartReason::Update);
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.restarts, 3);
+        assert!(snapshot.uptime.is_some());
+        assert_eq!(snapshot.last_crash.as_deref(), Some("exit status: 1"));
+    }
+
+    #[test]
+    fn stopped_backend_has_no_uptime() {
+        let mut stats = Stats::default();
+        stats.record_start(RestartReason::User);
+        stats.record_stop();
+        assert_eq!(stats.snapshot().uptime, None);
+    }
+
+    #[test]
+    fn keeps_the_latest_health_check() {
+        let mut stats = Stats::default();
+        assert_eq!(stats.snapshot().last_health_check, None);
+        stats.record_health(true);
+        stats.record_health(false);
+        let (ago, passed) = stats.snapshot().last_health_check.unwrap();
+        assert!(!passed);
+        assert!(ago < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn uptime_shows_two_most_significant_units() {
+        assert_eq!(format_uptime(Duration::from_secs(8)), "8s");
+        assert_eq!(format_uptime(Duration::from_secs(6 * 60 + 7)), "6m 07s");
+        assert_eq!(
+            format_uptime(Duration::from_secs(4 * 3600 + 5 * 60)),
+            "4h 05m"
+        );
+        assert_eq!(
+            format_uptime(Duration::from_secs(2 * 86_400 + 3 * 3600)),
+            "2d 3h"
+        );
+    }
+}
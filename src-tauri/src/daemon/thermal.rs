@@ -0,0 +1,152 @@
+//! Thermal build throttling (`thermal_limit`).
+//!
+//! Back-to-back builds on a fanless mini-PC can run it into a thermal
+//! shutdown. While the CPU is over the configured limit, running build jobs
+//! (see [`super::builds`]) are paused with SIGSTOP and continued once it has
+//! cooled by [`HYSTERESIS_C`]. A paused compile picks up exactly where it
+//! stopped, so the worst case is a slower build, never a failed one.
+//!
+//! Linux only: it is where unprivileged code can read the CPU temperature
+//! (`/sys/class/thermal`), and it is what these headless boxes run. macOS has
+//! no public sensor API and Windows only exposes one to administrators.
+
+use tauri::AppHandle;
+use tracing::warn;
+
+use crate::tasks::Tasks;
+
+/// Time between temperature readings.
+#[cfg(target_os = "linux")]
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// How far below the limit the CPU must cool before builds continue, so they
+/// don't flap around the threshold.
+#[cfg(any(target_os = "linux", test))]
+const HYSTERESIS_C: f64 = 10.0;
+
+/// What the monitor should do after a reading.
+#[cfg(any(target_os = "linux", test))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    Pause,
+    Resume,
+    Nothing,
+}
+
+#[cfg(any(target_os = "linux", test))]
+fn decide(paused: bool, temp_c: f64, limit_c: f64) -> Action {
+    if !paused && temp_c >= limit_c {
+        Action::Pause
+    } else if paused && temp_c <= limit_c - HYSTERESIS_C {
+        Action::Resume
+    } else {
+        Action::Nothing
+    }
+}
+
+/// The hottest thermal zone, in °C. Taking the maximum rather than guessing
+/// which zone is the CPU package keeps this right across boards that name
+/// their zones differently (`x86_pkg_temp`, `cpu-thermal`, `soc_thermal`).
+#[cfg(target_os = "linux")]
+fn read_cpu_temp() -> Option<f64> {
+    std::fs::read_dir("/sys/class/thermal")
+        .ok()?
+        .flatten()
+        .filter(|e| e.file_name().to_string_lossy().starts_with("thermal_zone"))
+        .filter_map(|e| std::fs::read_to_string(e.path().join("temp")).ok())
+        .filter_map(|raw| raw.trim().parse::<f64>().ok())
+        // Millidegrees; a zero or negative reading is a sensor that isn't wired.
+        .filter(|&milli| milli > 0.0)
+        .map(|milli| milli / 1000.0)
+        .reduce(f64::max)
+}
+
+/// Watch the CPU temperature until `run`, the backend run's scope, ends,
+/// pausing and continuing its build jobs around `limit_c`.
+#[cfg(target_os = "linux")]
+pub(super) fn spawn(run: &Tasks, app_handle: AppHandle, backend_pid: u32, limit_c: u32) {
+    use crate::i18n::{t, t_with};
+    use crate::platform;
+    use tracing::info;
+
+    if read_cpu_temp().is_none() {
+        warn!("thermal_limit is set but no CPU temperature sensor is readable; ignoring it");
+        return;
+    }
+    let limit = f64::from(limit_c);
+    // Cancelled with the run: stopping the backend continues its whole
+    // process group, so nothing is left paused behind it.
+    run.spawn("thermal monitor", async move {
+        let mut paused: Vec<u32> = Vec::new();
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            let Some(temp) = read_cpu_temp() else {
+                continue;
+            };
+            let action = decide(!paused.is_empty(), temp, limit);
+            let temp_label = format!("{temp:.0}");
+            match action {
+                Action::Nothing => continue,
+                Action::Pause => {
+                    let table = match tokio::task::spawn_blocking(platform::process_table).await {
+                        Ok(Ok(table)) => table,
+                        _ => continue,
+                    };
+                    for job in super::builds::active_builds(&table, backend_pid) {
+                        paused.push(job.pid);
+                        paused.extend(platform::descendants(&table, job.pid).iter().map(|p| p.pid));
+                    }
+                    if paused.is_empty() {
+                        continue;
+                    }
+                    info!("CPU at {temp:.0}°C (limit {limit_c}°C); pausing builds");
+                    platform::suspend_pids(&paused);
+                    notify(
+                        &app_handle,
+                        t("daemon.thermal_paused_title"),
+                        t_with(
+                            "daemon.thermal_paused_body",
+                            &[("temp", &temp_label), ("limit", &limit_c.to_string())],
+                        ),
+                    );
+                }
+                Action::Resume => {
+                    info!("CPU down to {temp:.0}°C; resuming builds");
+                    platform::resume_pids(&paused);
+                    paused.clear();
+                    notify(
+                        &app_handle,
+                        t("daemon.thermal_resumed_title"),
+                        t_with("daemon.thermal_resumed_body", &[("temp", &temp_label)]),
+                    );
+                }
+            }
+        }
+    });
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(super) fn spawn(_run: &Tasks, _app_handle: AppHandle, _backend_pid: u32, _limit_c: u32) {
+    warn!("thermal_limit is only supported on Linux; ignoring it");
+}
+
+#[cfg(target_os = "linux")]
+fn notify(app_handle: &AppHandle, title: String, body: String) {
+    if let Err(e) = crate::notifications::show(app_handle, title, body) {
+        warn!("Failed to show thermal notification: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pauses_at_the_limit_and_resumes_only_once_cooled() {
+        assert_eq!(decide(false, 84.0, 85.0), Action::Nothing);
+        assert_eq!(decide(false, 85.0, 85.0), Action::Pause);
+        // Still warm: stay paused rather than flap.
+        assert_eq!(decide(true, 80.0, 85.0), Action::Nothing);
+        assert_eq!(decide(true, 75.0, 85.0), Action::Resume);
+    }
+}
@@ -0,0 +1,215 @@
+//! Watchdog for runaway build processes.
+//!
+//! A failed build occasionally leaves a `cmake`/`ninja` (or compiler) child
+//! spinning on a core forever, long after the dashboard has given up on it.
+//! The watchdog samples the backend's descendants once a minute and flags one
+//! that has used a whole core for [`SUSTAINED_SAMPLES`] samples in a row while
+//! `dashboard.log` stayed silent. Flagging only notifies; killing the subtree
+//! is the user's call, offered from the Status Overview.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tauri::AppHandle;
+use tracing::{debug, warn};
+
+use crate::i18n::{t, t_with};
+use crate::platform::{self, ProcInfo};
+use crate::tasks::Tasks;
+
+/// Time between process-table samples.
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Consecutive busy samples before a process is flagged.
+const SUSTAINED_SAMPLES: u32 = 5;
+
+/// Share of one core a sample must use to count as busy. Just under 1.0 so
+/// scheduling jitter doesn't reset the streak.
+const BUSY_RATIO: f64 = 0.95;
+
+/// A descendant flagged as stuck.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Runaway {
+    pub pid: u32,
+    pub name: String,
+    /// The backend it was found under, to re-check ancestry before killing.
+    backend_pid: u32,
+}
+
+/// Per-process history between samples.
+#[derive(Debug)]
+pub(super) struct Watchdog {
+    backend_pid: u32,
+    /// Last seen CPU time and busy streak, by PID.
+    seen: HashMap<u32, (Duration, u32)>,
+}
+
+impl Watchdog {
+    pub(super) fn new(backend_pid: u32) -> Self {
+        Self {
+            backend_pid,
+            seen: HashMap::new(),
+        }
+    }
+
+    /// Feed one sample. Returns the processes that just crossed the
+    /// threshold; each is reported once per streak.
+    pub(super) fn observe(
+        &mut self,
+        procs: &[&ProcInfo],
+        elapsed: Duration,
+        log_grew: bool,
+    ) -> Vec<Runaway> {
+        let mut flagged = Vec::new();
+        let mut next = HashMap::with_capacity(procs.len());
+        for proc in procs {
+            let streak = match self.seen.get(&proc.pid) {
+                Some(&(prev, streak)) if !log_grew && busy(prev, proc.cpu_time, elapsed) => {
+                    streak + 1
+                }
+                _ => 0,
+            };
+            if streak == SUSTAINED_SAMPLES {
+                flagged.push(Runaway {
+                    pid: proc.pid,
+                    name: proc.name.clone(),
+                    backend_pid: self.backend_pid,
+                });
+            }
+            next.insert(proc.pid, (proc.cpu_time, streak));
+        }
+        // Exited processes drop out here, so a recycled PID starts fresh.
+        self.seen = next;
+        flagged
+    }
+}
+
+fn busy(prev: Duration, now: Duration, elapsed: Duration) -> bool {
+    !elapsed.is_zero()
+        && now.saturating_sub(prev).as_secs_f64() >= elapsed.as_secs_f64() * BUSY_RATIO
+}
+
+/// Sample the backend's descendants until `run`, the backend run's scope,
+/// ends, adding flagged processes to `runaways` and notifying about each.
+pub(super) fn spawn(
+    run: &Tasks,
+    app_handle: AppHandle,
+    runaways: Arc<Mutex<Vec<Runaway>>>,
+    log_path: PathBuf,
+    backend_pid: u32,
+) {
+    run.spawn("runaway watchdog", async move {
+        let mut watchdog = Watchdog::new(backend_pid);
+        let log_len = || std::fs::metadata(&log_path).map(|m| m.len()).ok();
+        let mut last_log_len = log_len();
+        loop {
+            tokio::time::sleep(SAMPLE_INTERVAL).await;
+            let table = match tokio::task::spawn_blocking(platform::process_table).await {
+                Ok(Ok(table)) => table,
+                Ok(Err(e)) => {
+                    debug!("Watchdog could not read the process table: {}", e);
+                    continue;
+                }
+                Err(_) => continue,
+            };
+            let log_now = log_len();
+            let log_grew = log_now != last_log_len;
+            last_log_len = log_now;
+            let procs = platform::descendants(&table, backend_pid);
+            for runaway in watchdog.observe(&procs, SAMPLE_INTERVAL, log_grew) {
+                warn!(
+                    "{} (pid {}) has used a full core for {} minutes with no build output",
+                    runaway.name, runaway.pid, SUSTAINED_SAMPLES
+                );
+                if let Err(e) = crate::notifications::show(
+                    &app_handle,
+                    t("daemon.runaway_title"),
+                    t_with(
+                        "daemon.runaway_body",
+                        &[
+                            ("name", &runaway.name),
+                            ("pid", &runaway.pid.to_string()),
+                            ("minutes", &SUSTAINED_SAMPLES.to_string()),
+                        ],
+                    ),
+                ) {
+                    warn!("Failed to show runaway-process notification: {}", e);
+                }
+                runaways
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .push(runaway);
+            }
+        }
+    });
+}
+
+/// Terminate a flagged process and everything under it, if it is still one
+/// of the backend's descendants (it may have exited and had its PID reused).
+pub(super) fn kill_subtree(runaway: &Runaway) -> anyhow::Result<()> {
+    let pid = runaway.pid;
+    let table = platform::process_table()?;
+    if !platform::descendants(&table, runaway.backend_pid)
+        .iter()
+        .any(|p| p.pid == pid)
+    {
+        anyhow::bail!("process {pid} is no longer part of the build");
+    }
+    let mut pids = vec![pid];
+    pids.extend(platform::descendants(&table, pid).iter().map(|p| p.pid));
+    platform::terminate_pids(&pids);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proc(pid: u32, cpu_secs: u64) -> ProcInfo {
+        ProcInfo {
+            pid,
+            ppid: 1,
+            cpu_time: Duration::from_secs(cpu_secs),
+            name: "ninja".into(),
+        }
+    }
+
+    #[test]
+    fn flags_after_sustained_busy_samples_only_once() {
+        let mut watchdog = Watchdog::new(1);
+        let mut flagged = Vec::new();
+        for i in 0..=SUSTAINED_SAMPLES + 2 {
+            let p = proc(42, 60 * u64::from(i));
+            flagged.extend(watchdog.observe(&[&p], SAMPLE_INTERVAL, false));
+        }
+        assert_eq!(
+            flagged,
+            vec![Runaway {
+                pid: 42,
+                name: "ninja".into(),
+                backend_pid: 1,
+            }]
+        );
+    }
+
+    #[test]
+    fn log_output_or_idle_samples_reset_the_streak() {
+        let mut watchdog = Watchdog::new(1);
+        let mut cpu = 0;
+        for i in 0..SUSTAINED_SAMPLES * 3 {
+            cpu += 60;
+            // Output every third sample: a long but healthy compile.
+            let p = proc(42, cpu);
+            assert!(watchdog
+                .observe(&[&p], SAMPLE_INTERVAL, i % 3 == 0)
+                .is_empty());
+        }
+        for _ in 0..SUSTAINED_SAMPLES * 2 {
+            cpu += 10;
+            let p = proc(42, cpu);
+            assert!(watchdog.observe(&[&p], SAMPLE_INTERVAL, false).is_empty());
+        }
+    }
+}
@@ -0,0 +1,260 @@
+//! Running the device builder inside a WSL2 distro (Windows only).
+//!
+//! Compiles on the Linux side are several times faster than on the host:
+//! PlatformIO touches tens of thousands of small files per build, and NTFS plus
+//! Defender scanning is where the native Windows build spends most of its time.
+//! With `runtime = "wsl2"` the backend runs from a venv inside the distro, and
+//! the desktop keeps owning its lifecycle through `wsl.exe`.
+//!
+//! The venv is provisioned on first start (`python3 -m venv` + `pip install`).
+//! The config directory stays on the Windows side and is reached through the
+//! distro's `/mnt/<drive>` mount, so the same configs work in either runtime.
+//! WSL2 forwards `localhost` ports to the host by default, so the tray, the
+//! health check and the browser keep using the loopback URL unchanged, and
+//! the backend binds the distro's loopback unless `listen_address` asks for
+//! the network.
+
+use anyhow::{Context, Result};
+use std::net::SocketAddr;
+use std::path::Path;
+use tokio::process::Command;
+use tracing::{info, warn};
+
+use super::backend::{self, DaemonBackend, Prepare};
+use crate::platform;
+use crate::settings::{Runtime, Settings};
+
+/// Venv location inside the distro. Under `$HOME` so it lives on the Linux
+/// filesystem (a venv on `/mnt/c` would give back the speedup we came for).
+/// The SSH runtime puts its venv at the same place on the remote machine.
+pub(super) const VENV_DIR: &str = "$HOME/.local/share/esphome-desktop/venv";
+
+/// `pkill -f` pattern for the backend started from [`VENV_DIR`].
+pub(super) const VENV_PROCESS_PATTERN: &str =
+    "esphome-desktop/venv/bin/python -m esphome_device_builder";
+
+/// Environment variables forwarded from the Windows side into the distro.
+/// `wsl.exe` only passes variables listed in `WSLENV`; `/p` translates a
+/// Windows path to its `/mnt/...` form. Unset ones are skipped.
+const WSLENV: &str =
+    "ESPHOME_DASHBOARD:ESPHOME_DESKTOP_VERSION:ESPHOME_DESKTOP_BIN/p:TZ:LANG:LC_ALL:\
+     SCONSFLAGS:CMAKE_BUILD_PARALLEL_LEVEL:MAKEFLAGS";
+
+/// A configured WSL2 runtime. Only ever constructed on Windows; see
+/// [`WslRuntime::from_settings`].
+#[derive(Debug, Clone)]
+pub(crate) struct WslRuntime {
+    /// Distro to run in; `None` uses the user's default distro.
+    distro: Option<String>,
+    /// Whether `listen_address` asks for the network rather than a loopback.
+    exposed: bool,
+}
+
+impl WslRuntime {
+    /// The WSL2 runtime the settings ask for, or `None` for the native one.
+    /// A `wsl2` setting on a non-Windows host is ignored with a warning, so a
+    /// settings file synced across machines doesn't stop the backend starting.
+    pub(crate) fn from_settings(settings: &Settings) -> Option<Self> {
+        if settings.runtime != Runtime::Wsl2 {
+            return None;
+        }
+        if !cfg!(windows) {
+            warn!("runtime \"wsl2\" is only supported on Windows; using the native runtime");
+            return None;
+        }
+        Some(Self {
+            distro: backend::non_blank(settings.wsl_distro.as_deref()),
+            exposed: !settings.listen_address.is_loopback(),
+        })
+    }
+
+    /// A `wsl.exe` command running `script` under `sh -c` in the distro.
+    fn shell(&self, script: &str) -> Command {
+        let mut cmd = Command::new("wsl.exe");
+        if let Some(distro) = &self.distro {
+            cmd.args(["-d", distro]);
+        }
+        cmd.args(["--", "sh", "-c", script]);
+        cmd
+    }
+
+    /// Create the venv and install the backend into it if that hasn't
+    /// happened yet. A no-op once the venv's python can import the backend.
+    async fn ensure_venv(&self) -> Result<()> {
+        let mut cmd = self.shell(&backend::provision_script(VENV_DIR));
+        platform::configure_no_window_tokio_command(&mut cmd);
+        info!("Checking the WSL2 venv ({})", self.distro_label());
+        let output = cmd
+            .output()
+            .await
+            .context("Failed to run wsl.exe; is WSL2 installed?")?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "Failed to provision the WSL2 venv in {}: {}",
+                self.distro_label(),
+                platform::pip_output_report(&output)
+            );
+        }
+        Ok(())
+    }
+
+    /// Distro name for log and error messages.
+    fn distro_label(&self) -> &str {
+        self.distro.as_deref().unwrap_or("the default WSL distro")
+    }
+}
+
+impl DaemonBackend for WslRuntime {
+    fn runtime(&self) -> Runtime {
+        Runtime::Wsl2
+    }
+
+    fn prepare(&self) -> Prepare<'_> {
+        Box::pin(self.ensure_venv())
+    }
+
+    /// The command that runs the backend in the distro. Binds the distro's
+    /// loopback, which WSL2's localhost forwarding reaches from the host. A
+    /// `listen_address` that isn't a loopback one, confirmed in the settings
+    /// window like the native runtime's, binds every interface instead: with
+    /// mirrored networking those are the host's, so the LAN reaches it.
+    fn command(&self, config_dir: &Path, address: SocketAddr, low_priority: bool) -> Command {
+        let config = to_wsl_path(config_dir);
+        let host = if self.exposed { "0.0.0.0" } else { "127.0.0.1" };
+        let mut cmd = self.shell(&backend::launch_script(
+            VENV_DIR,
+            &config,
+            host,
+            address.port(),
+            low_priority,
+        ));
+        cmd.env("WSLENV", WSLENV);
+        cmd
+    }
+
+    /// Best-effort SIGTERM to the backend inside the distro. Signalling
+    /// `wsl.exe` on the host doesn't reliably reach the Linux process, so the
+    /// stop paths call this as well.
+    fn terminate_blocking(&self) {
+        let mut cmd = std::process::Command::new("wsl.exe");
+        if let Some(distro) = &self.distro {
+            cmd.args(["-d", distro]);
+        }
+        // Match on the venv interpreter so a backend the user runs by hand
+        // elsewhere in the distro is left alone.
+        cmd.args(["--", "pkill", "-TERM", "-f", VENV_PROCESS_PATTERN]);
+        platform::configure_no_window_command(&mut cmd);
+        if let Err(e) = cmd.status() {
+            warn!("Failed to signal the backend in WSL2: {}", e);
+        }
+    }
+}
+
+/// Translate a Windows path into the path the distro sees it at.
+///
+/// Drive paths go through the automount (`C:\x` -> `/mnt/c/x`); paths already
+/// on a distro's filesystem (`\\wsl$\<distro>\x`, `\\wsl.localhost\<distro>\x`)
+/// map back to their Linux path. Anything else is passed through with the
+/// separators flipped, which is the best we can do for e.g. network shares.
+pub(crate) fn to_wsl_path(path: &Path) -> String {
+    let raw = path.to_string_lossy();
+    let raw = raw.strip_prefix(r"\\?\").unwrap_or(&raw);
+    for prefix in [r"\\wsl$\", r"\\wsl.localhost\"] {
+        if let Some(rest) = strip_prefix_ignore_case(raw, prefix) {
+            // Drop the distro name; what's left is rooted at `/`.
+            let rest = rest.split_once('\\').map_or("", |(_, r)| r);
+            return format!("/{}", rest.replace('\\', "/"));
+        }
+    }
+    let bytes = raw.as_bytes();
+    if bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' {
+        let drive = (bytes[0] as char).to_ascii_lowercase();
+        let rest = raw[2..].trim_start_matches(['\\', '/']).replace('\\', "/");
+        return if rest.is_empty() {
+            format!("/mnt/{drive}")
+        } else {
+            format!("/mnt/{drive}/{rest}")
+        };
+    }
+    raw.replace('\\', "/")
+}
+
+fn strip_prefix_ignore_case<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+    let head = s.get(..prefix.len())?;
+    head.eq_ignore_ascii_case(prefix)
+        .then(|| &s[prefix.len()..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drive_paths_map_to_the_automount() {
+        assert_eq!(
+            to_wsl_path(Path::new(r"C:\Users\me\esphome")),
+            "/mnt/c/Users/me/esphome"
+        );
+        assert_eq!(to_wsl_path(Path::new(r"D:\")), "/mnt/d");
+        assert_eq!(
+            to_wsl_path(Path::new(r"\\?\E:\configs\esphome")),
+            "/mnt/e/configs/esphome"
+        );
+    }
+
+    #[test]
+    fn distro_unc_paths_map_to_linux_paths() {
+        assert_eq!(
+            to_wsl_path(Path::new(r"\\wsl$\Ubuntu\home\me\esphome")),
+            "/home/me/esphome"
+        );
+        assert_eq!(
+            to_wsl_path(Path::new(r"\\WSL.localhost\Debian\srv\esphome")),
+            "/srv/esphome"
+        );
+    }
+
+    #[test]
+    fn the_backend_binds_the_loopback_unless_exposed() {
+        let script = |exposed| {
+            let runtime = WslRuntime {
+                distro: None,
+                exposed,
+            };
+            let cmd = runtime.command(
+                Path::new(r"C:\esphome"),
+                "127.0.0.1:6052".parse().unwrap(),
+                false,
+            );
+            cmd.as_std()
+                .get_args()
+                .last()
+                .unwrap()
+                .to_string_lossy()
+                .into_owned()
+        };
+        assert!(script(false).contains("--host 127.0.0.1 --port 6052"));
+        assert!(script(true).contains("--host 0.0.0.0 --port 6052"));
+    }
+
+    #[test]
+    fn native_runtime_builds_no_wsl_runtime() {
+        assert!(WslRuntime::from_settings(&Settings::default()).is_none());
+    }
+
+    #[test]
+    fn wsl2_runtime_is_ignored_off_windows() {
+        let settings = Settings {
+            runtime: Runtime::Wsl2,
+            wsl_distro: Some("  ".to_string()),
+            ..Settings::default()
+        };
+        let runtime = WslRuntime::from_settings(&settings);
+        if cfg!(windows) {
+            // A blank distro name means "the default distro".
+            assert!(runtime.is_some_and(|r| r.distro.is_none()));
+        } else {
+            assert!(runtime.is_none());
+        }
+    }
+}
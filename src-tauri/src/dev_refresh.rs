@@ -0,0 +1,213 @@
+//! The opt-in nightly dev refresh (`dev_refresh_hour`): on the dev channel,
+//! the latest dev snapshot is reinstalled once a day, so a testing install
+//! keeps up with ESPHome's development without anyone checking by hand. A
+//! notification says what the reinstall changed.
+//!
+//! The app checks every [`CHECK_INTERVAL`] whether the day's refresh hour
+//! (UTC; there is no local time without a time zone database) has passed
+//! since the last refresh, kept in `dev_refresh.json` in the app data so a
+//! restart doesn't refresh twice. The first comes at the first refresh hour
+//! after the setting is turned on. A refresh never interrupts work: one due
+//! while an update or batch runs, or while the dashboard compiles, waits for
+//! the next check.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tracing::{info, warn};
+
+use crate::audit::{self, Source};
+use crate::control::ops::{self, UpdateGuard};
+use crate::i18n::{t, t_with};
+use crate::settings::ReleaseChannel;
+use crate::tasks::Tasks;
+use crate::util::ConfigStore;
+
+/// Time between checks for a refresh being due.
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Seconds in a day.
+const DAY_SECS: u64 = 24 * 60 * 60;
+
+/// When the last refresh ran.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Refreshed {
+    /// Seconds since the epoch.
+    #[serde(default)]
+    last: Option<u64>,
+}
+
+impl ConfigStore for Refreshed {
+    const FILE_NAME: &'static str = "dev_refresh.json";
+    const WHAT: &'static str = "dev refresh state";
+}
+
+/// The latest refresh time at or before `now`: today's `hour`, or
+/// yesterday's when today's is still to come. Both in seconds since the
+/// epoch, UTC.
+fn last_slot(now: u64, hour: u8) -> u64 {
+    let slot = now - now % DAY_SECS + u64::from(hour) * 60 * 60;
+    if slot <= now {
+        slot
+    } else {
+        slot - DAY_SECS
+    }
+}
+
+/// Whether a refresh last run at `last` is due again at `now`.
+fn due(now: u64, last: u64, hour: u8) -> bool {
+    last < last_slot(now, hour)
+}
+
+/// Refresh the dev snapshot every day at `hour` for as long as `tasks`
+/// lives.
+pub(crate) fn spawn(app: AppHandle, tasks: &Tasks, hour: u8) {
+    tasks.spawn("dev refresh", async move {
+        let path = match crate::platform::get_data_dir(&app) {
+            Ok(dir) => Refreshed::path(&dir),
+            Err(e) => {
+                warn!("Not refreshing the dev snapshot: {:#}", e);
+                return;
+            }
+        };
+        loop {
+            let now = unix_now();
+            let last = match Refreshed::load(&path) {
+                Ok(Refreshed { last: Some(last) }) => last,
+                Ok(Refreshed { last: None }) => {
+                    // Turned on just now: the first refresh is the next slot.
+                    save(&path, now);
+                    now
+                }
+                Err(e) => {
+                    warn!("Failed to read the dev refresh state: {:#}", e);
+                    now
+                }
+            };
+            if due(now, last, hour) && refresh(&app).await {
+                save(&path, now);
+            }
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
+
+/// Reinstall the dev snapshot and say what changed. Returns whether the
+/// day's refresh is done: `false` while something holds it off, so the next
+/// check tries again.
+async fn refresh(app: &AppHandle) -> bool {
+    let Ok(state) = crate::window::app_state(app) else {
+        return false;
+    };
+    if state.settings.read().await.release_channel != ReleaseChannel::Dev {
+        // The setting only applies on the dev channel, which the user can
+        // leave and rejoin without a restart.
+        return true;
+    }
+    let Some(_guard) = UpdateGuard::try_acquire(state.update_in_flight.clone()) else {
+        info!("Update or batch in progress; the dev refresh waits");
+        return false;
+    };
+    match state.daemon.is_building().await {
+        Ok(false) => {}
+        Ok(true) => {
+            info!("Build in progress; the dev refresh waits");
+            return false;
+        }
+        Err(e) => {
+            warn!("Not refreshing the dev snapshot: {}", e);
+            return false;
+        }
+    }
+
+    let before = installed_version(app).await;
+    info!("Refreshing the dev snapshot (installed: {:?})", before);
+    let result = ops::stop_install_start(&state, || {
+        state
+            .update_checker
+            .update_to(app, "dev", ReleaseChannel::Dev)
+    })
+    .await;
+    let body = match result {
+        Ok(()) => {
+            ops::refresh_version_display_blocking(app).await;
+            let after = installed_version(app).await;
+            let summary = summary(before.as_deref(), after.as_deref());
+            audit::record(app, Source::Tray, "update", summary.clone());
+            summary
+        }
+        Err(e) => {
+            warn!("Dev refresh failed: {}", e);
+            t_with("dev_refresh.failed", &[("error", &e)])
+        }
+    };
+    info!("Dev refresh: {}", body);
+    if let Err(e) = crate::notifications::show(app, t("dev_refresh.title"), body) {
+        warn!("Failed to show the dev refresh: {}", e);
+    }
+    true
+}
+
+/// The notification's body for a refresh from `before` to `after`.
+fn summary(before: Option<&str>, after: Option<&str>) -> String {
+    let unknown = t("dev_refresh.unknown_version");
+    let after = after.unwrap_or(&unknown);
+    match before {
+        Some(before) if before != after => {
+            t_with("dev_refresh.updated", &[("from", before), ("to", after)])
+        }
+        _ => t_with("dev_refresh.reinstalled", &[("version", after)]),
+    }
+}
+
+/// The installed ESPHome version, `None` when it can't be told.
+async fn installed_version(app: &AppHandle) -> Option<String> {
+    let app = app.clone();
+    tokio::task::spawn_blocking(move || crate::update::installed_esphome_version(&app))
+        .await
+        .ok()
+        .and_then(Result::ok)
+        .flatten()
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Best-effort: a refresh that can't be recorded runs again next check.
+fn save(path: &std::path::Path, at: u64) {
+    if let Err(e) = (Refreshed { last: Some(at) }).save(path) {
+        warn!("Failed to record the dev refresh: {:#}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn due_once_a_day_after_the_hour() {
+        // 2026-10-16T00:00:00Z.
+        let midnight = 1_792_108_800;
+        let hour = 3600;
+        // Turned on at 01:00 with a 03:00 slot: not before 03:00.
+        let on = midnight + hour;
+        assert!(!due(midnight + 2 * hour, on, 3));
+        assert!(due(midnight + 3 * hour, on, 3));
+        // Refreshed at 03:00: not again until the next day's.
+        let refreshed = midnight + 3 * hour;
+        assert!(!due(midnight + 23 * hour, refreshed, 3));
+        assert!(!due(midnight + 26 * hour, refreshed, 3));
+        assert!(due(midnight + 27 * hour, refreshed, 3));
+        // Held off past 03:00 and refreshed at 05:00: the next is still
+        // the next day's 03:00.
+        let late = midnight + 5 * hour;
+        assert!(!due(midnight + 26 * hour, late, 3));
+        assert!(due(midnight + 27 * hour, late, 3));
+        // Off for days: one refresh catches up.
+        assert!(due(midnight + 100 * hour, refreshed, 3));
+    }
+}
@@ -0,0 +1,640 @@
+//! A minimal client for ESPHome's native API, enough to watch a device on
+//! the bench without Home Assistant: its info, its entities with their live
+//! states, and its logs.
+//!
+//! The API is protobuf messages over TCP (port 6053). Plaintext frames are a
+//! zero byte, the message length and the message type as varints, then the
+//! message. Only the handful of messages a watcher needs are encoded here,
+//! by field number from ESPHome's `api.proto`, and replies are decoded
+//! generically: list and state messages share their first fields (`key`,
+//! `name`, `state`) across entity kinds, so new kinds still show up.
+//!
+//! Devices with API encryption answer in Noise frames, which this client
+//! doesn't speak; it says so rather than hanging. Blocking std sockets, like
+//! the rest of the CLI.
+
+use anyhow::{bail, Context, Result};
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+/// The native API's default port.
+pub(crate) const DEFAULT_PORT: u16 = 6053;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long the handshake and the entity list may take; streaming after
+/// that waits as long as the device is quiet.
+const REPLY_TIMEOUT: Duration = Duration::from_secs(20);
+
+const HELLO_REQUEST: u32 = 1;
+const HELLO_RESPONSE: u32 = 2;
+const CONNECT_REQUEST: u32 = 3;
+const CONNECT_RESPONSE: u32 = 4;
+const DISCONNECT_REQUEST: u32 = 5;
+const DISCONNECT_RESPONSE: u32 = 6;
+const PING_REQUEST: u32 = 7;
+const PING_RESPONSE: u32 = 8;
+const DEVICE_INFO_REQUEST: u32 = 9;
+const DEVICE_INFO_RESPONSE: u32 = 10;
+const LIST_ENTITIES_REQUEST: u32 = 11;
+const LIST_ENTITIES_DONE: u32 = 19;
+const SUBSCRIBE_STATES_REQUEST: u32 = 20;
+const SUBSCRIBE_LOGS_REQUEST: u32 = 28;
+const SUBSCRIBE_LOGS_RESPONSE: u32 = 29;
+const SUBSCRIBE_BLUETOOTH_CONNECTIONS_FREE: u32 = 80;
+const BLUETOOTH_CONNECTIONS_FREE: u32 = 81;
+
+/// Entity kinds by their list message type, with their state message type.
+const KINDS: &[(u32, u32, &str)] = &[
+    (12, 21, "binary_sensor"),
+    (13, 22, "cover"),
+    (14, 23, "fan"),
+    (15, 24, "light"),
+    (16, 25, "sensor"),
+    (17, 26, "switch"),
+    (18, 27, "text_sensor"),
+    (46, 47, "climate"),
+    (49, 50, "number"),
+    (52, 53, "select"),
+    (55, 56, "siren"),
+    (58, 59, "lock"),
+    (61, 0, "button"),
+    (63, 64, "media_player"),
+];
+
+/// ESPHome's log levels, by number.
+pub(crate) const LOG_LEVELS: &[&str] = &[
+    "none",
+    "error",
+    "warn",
+    "info",
+    "config",
+    "debug",
+    "verbose",
+    "very_verbose",
+];
+
+/// Bluetooth proxy features by their bit in `bluetooth_proxy_feature_flags`.
+const PROXY_FEATURES: &[(u32, &str)] = &[
+    (1, "passive scanning"),
+    (2, "active connections"),
+    (4, "remote caching"),
+    (8, "pairing"),
+    (16, "cache clearing"),
+    (32, "raw advertisements"),
+];
+
+/// What the device says about itself.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct DeviceInfo {
+    pub name: String,
+    pub friendly_name: String,
+    pub esphome_version: String,
+    pub compilation_time: String,
+    pub model: String,
+    pub mac_address: String,
+    /// Bluetooth proxy feature bits, 0 for a device that isn't one.
+    pub bluetooth_proxy: u32,
+}
+
+impl DeviceInfo {
+    /// The proxy's features by name, e.g. `["passive scanning"]`.
+    pub(crate) fn proxy_features(&self) -> Vec<&'static str> {
+        PROXY_FEATURES
+            .iter()
+            .filter(|(bit, _)| self.bluetooth_proxy & bit != 0)
+            .map(|(_, name)| *name)
+            .collect()
+    }
+}
+
+/// One of the device's entities.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Entity {
+    pub key: u32,
+    pub kind: &'static str,
+    pub name: String,
+    /// Sensors only.
+    pub unit: String,
+    /// Sensors only.
+    pub accuracy_decimals: Option<i32>,
+}
+
+/// Something the device sent while watched.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Event {
+    /// An entity's new state, rendered for display.
+    State { key: u32, state: String },
+    /// A log line, with its level's name.
+    Log {
+        level: &'static str,
+        message: String,
+    },
+    /// A Bluetooth proxy's connection slots: free of `limit`, and the
+    /// addresses of the BLE devices holding the others.
+    ProxyConnections {
+        free: u32,
+        limit: u32,
+        connected: Vec<String>,
+    },
+}
+
+/// A connection to one device.
+pub(crate) struct Client {
+    stream: TcpStream,
+    /// The device's entities by key, once listed.
+    entities: BTreeMap<u32, Entity>,
+}
+
+impl Client {
+    /// Connect to `host`, introduce ourselves and log in with `password`
+    /// (empty for a device without one).
+    pub(crate) fn connect(host: &str, port: u16, password: &str) -> Result<Self> {
+        let addr = (host, port)
+            .to_socket_addrs()
+            .with_context(|| format!("Failed to resolve {host}"))?
+            .next()
+            .with_context(|| format!("{host} has no address"))?;
+        let stream = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)
+            .with_context(|| format!("Failed to connect to {host}:{port}"))?;
+        stream.set_read_timeout(Some(REPLY_TIMEOUT))?;
+        stream.set_nodelay(true)?;
+        let mut client = Self {
+            stream,
+            entities: BTreeMap::new(),
+        };
+
+        let mut hello = Vec::new();
+        put_string(&mut hello, 1, "esphome-desktop");
+        put_varint_field(&mut hello, 2, 1);
+        put_varint_field(&mut hello, 3, 10);
+        client.send(HELLO_REQUEST, &hello)?;
+        client.expect(HELLO_RESPONSE)?;
+
+        let mut login = Vec::new();
+        put_string(&mut login, 1, password);
+        client.send(CONNECT_REQUEST, &login)?;
+        let reply = client.expect(CONNECT_RESPONSE)?;
+        if varint(&reply, 1) != 0 {
+            bail!("{host} refused the API password");
+        }
+        Ok(client)
+    }
+
+    pub(crate) fn device_info(&mut self) -> Result<DeviceInfo> {
+        self.send(DEVICE_INFO_REQUEST, &[])?;
+        let fields = self.expect(DEVICE_INFO_RESPONSE)?;
+        Ok(DeviceInfo {
+            name: string(&fields, 2),
+            mac_address: string(&fields, 3),
+            esphome_version: string(&fields, 4),
+            compilation_time: string(&fields, 5),
+            model: string(&fields, 6),
+            friendly_name: string(&fields, 13),
+            // Devices from before the feature flags only had a version,
+            // which meant passive scanning.
+            bluetooth_proxy: match varint(&fields, 15) {
+                0 if varint(&fields, 11) != 0 => 1,
+                flags => flags as u32,
+            },
+        })
+    }
+
+    /// The device's entities, in the order it lists them.
+    pub(crate) fn list_entities(&mut self) -> Result<Vec<Entity>> {
+        self.send(LIST_ENTITIES_REQUEST, &[])?;
+        let mut listed = Vec::new();
+        loop {
+            let (kind, fields) = self.receive()?;
+            if kind == LIST_ENTITIES_DONE {
+                break;
+            }
+            let Some(&(_, _, name)) = KINDS.iter().find(|(list, _, _)| *list == kind) else {
+                continue;
+            };
+            let sensor = name == "sensor";
+            listed.push(Entity {
+                key: fixed32(&fields, 2),
+                kind: name,
+                name: string(&fields, 3),
+                unit: if sensor {
+                    string(&fields, 6)
+                } else {
+                    String::new()
+                },
+                accuracy_decimals: sensor.then(|| varint(&fields, 7) as i32),
+            });
+        }
+        self.entities = listed.iter().map(|e| (e.key, e.clone())).collect();
+        Ok(listed)
+    }
+
+    /// Ask for state updates: the current state of each entity first, then
+    /// each change.
+    pub(crate) fn subscribe_states(&mut self) -> Result<()> {
+        self.send(SUBSCRIBE_STATES_REQUEST, &[])
+    }
+
+    /// Ask for log lines at `level` (an index into [`LOG_LEVELS`]) and up.
+    pub(crate) fn subscribe_logs(&mut self, level: u64) -> Result<()> {
+        let mut request = Vec::new();
+        put_varint_field(&mut request, 1, level);
+        self.send(SUBSCRIBE_LOGS_REQUEST, &request)
+    }
+
+    /// Ask a Bluetooth proxy for its connection slots, now and on each
+    /// change. Unlike advertisements, this doesn't take anything from Home
+    /// Assistant's own subscription.
+    pub(crate) fn subscribe_proxy_connections(&mut self) -> Result<()> {
+        self.send(SUBSCRIBE_BLUETOOTH_CONNECTIONS_FREE, &[])
+    }
+
+    /// Wait for the next state, log line or proxy update, answering the device's pings on
+    /// the way. `None` once the device disconnects.
+    pub(crate) fn next_event(&mut self) -> Result<Option<Event>> {
+        self.stream.set_read_timeout(None)?;
+        loop {
+            let (kind, fields) = self.receive()?;
+            match kind {
+                PING_REQUEST => self.send(PING_RESPONSE, &[])?,
+                DISCONNECT_REQUEST => {
+                    let _ = self.send(DISCONNECT_RESPONSE, &[]);
+                    return Ok(None);
+                }
+                SUBSCRIBE_LOGS_RESPONSE => {
+                    let level = LOG_LEVELS
+                        .get(varint(&fields, 1) as usize)
+                        .copied()
+                        .unwrap_or("log");
+                    let message = strip_ansi(&string(&fields, 3));
+                    return Ok(Some(Event::Log { level, message }));
+                }
+                BLUETOOTH_CONNECTIONS_FREE => {
+                    return Ok(Some(Event::ProxyConnections {
+                        free: varint(&fields, 1) as u32,
+                        limit: varint(&fields, 2) as u32,
+                        connected: varints(&fields, 3)
+                            .into_iter()
+                            .filter(|&address| address != 0)
+                            .map(mac)
+                            .collect(),
+                    }));
+                }
+                _ => {
+                    let Some(&(_, _, name)) = KINDS.iter().find(|(_, state, _)| *state == kind)
+                    else {
+                        continue;
+                    };
+                    let key = fixed32(&fields, 1);
+                    let state = match self.entities.get(&key) {
+                        Some(entity) => render_state(entity, &fields),
+                        None => render_state(&unlisted(key, name), &fields),
+                    };
+                    return Ok(Some(Event::State { key, state }));
+                }
+            }
+        }
+    }
+
+    /// An entity by key, once [`Self::list_entities`] has run.
+    pub(crate) fn entity(&self, key: u32) -> Option<&Entity> {
+        self.entities.get(&key)
+    }
+
+    fn send(&mut self, kind: u32, message: &[u8]) -> Result<()> {
+        let mut frame = vec![0];
+        put_varint(&mut frame, message.len() as u64);
+        put_varint(&mut frame, u64::from(kind));
+        frame.extend_from_slice(message);
+        self.stream
+            .write_all(&frame)
+            .context("Failed to write to the device")
+    }
+
+    /// The next message of type `kind`, skipping others.
+    fn expect(&mut self, kind: u32) -> Result<Fields> {
+        loop {
+            let (got, fields) = self.receive()?;
+            if got == kind {
+                return Ok(fields);
+            }
+        }
+    }
+
+    fn receive(&mut self) -> Result<(u32, Fields)> {
+        let mut indicator = [0u8];
+        self.stream
+            .read_exact(&mut indicator)
+            .context("The device closed the connection")?;
+        match indicator[0] {
+            0 => {}
+            1 => bail!(
+                "the device uses API encryption, which this client doesn't support yet \
+                 (watch it in Home Assistant or the dashboard's logs instead)"
+            ),
+            other => bail!("unexpected frame start {other:#04x}"),
+        }
+        let len = read_varint(&mut self.stream)? as usize;
+        let kind = read_varint(&mut self.stream)? as u32;
+        let mut message = vec![0; len];
+        self.stream
+            .read_exact(&mut message)
+            .context("The device closed the connection")?;
+        Ok((kind, parse(&message)?))
+    }
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        let _ = self.send(DISCONNECT_REQUEST, &[]);
+    }
+}
+
+fn unlisted(key: u32, kind: &'static str) -> Entity {
+    Entity {
+        key,
+        kind,
+        name: String::new(),
+        unit: String::new(),
+        accuracy_decimals: None,
+    }
+}
+
+/// A state message's value as text: `on`, `21.4 °C`, `unknown`.
+fn render_state(entity: &Entity, fields: &Fields) -> String {
+    // `missing_state` is field 3 where the kind has one.
+    let has_missing = matches!(
+        entity.kind,
+        "binary_sensor" | "sensor" | "text_sensor" | "number" | "select"
+    );
+    if has_missing && varint(fields, 3) != 0 {
+        return "unknown".to_string();
+    }
+    let on_off = |on: bool| if on { "on" } else { "off" }.to_string();
+    match entity.kind {
+        "binary_sensor" | "switch" | "light" | "fan" | "siren" => on_off(varint(fields, 2) != 0),
+        "sensor" | "number" => {
+            let value = f32::from_bits(fixed32(fields, 2));
+            let decimals = entity.accuracy_decimals.unwrap_or(2).clamp(0, 6) as usize;
+            let number = format!("{value:.decimals$}");
+            match entity.unit.as_str() {
+                "" => number,
+                unit => format!("{number} {unit}"),
+            }
+        }
+        "text_sensor" | "select" => string(fields, 2),
+        "cover" => format!("{:.0}% open", f32::from_bits(fixed32(fields, 3)) * 100.0),
+        "lock" => pick(
+            &[
+                "none",
+                "locked",
+                "unlocked",
+                "jammed",
+                "locking",
+                "unlocking",
+            ],
+            varint(fields, 2),
+        ),
+        "climate" => format!(
+            "{}, {:.1} now",
+            pick(
+                &[
+                    "off",
+                    "heat_cool",
+                    "cool",
+                    "heat",
+                    "fan_only",
+                    "dry",
+                    "auto"
+                ],
+                varint(fields, 2)
+            ),
+            f32::from_bits(fixed32(fields, 3))
+        ),
+        "media_player" => pick(&["none", "idle", "playing", "paused"], varint(fields, 2)),
+        _ => String::new(),
+    }
+}
+
+fn pick(names: &[&str], value: u64) -> String {
+    names
+        .get(value as usize)
+        .map_or_else(|| value.to_string(), |name| name.to_string())
+}
+
+/// ESPHome colours its log lines; drop the escape codes for plain output.
+fn strip_ansi(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// A decoded protobuf field's value.
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Varint(u64),
+    Fixed32(u32),
+    Bytes(Vec<u8>),
+}
+
+/// A message's fields by number, each with every value it was sent with:
+/// one, unless the field is repeated.
+type Fields = BTreeMap<u32, Vec<Value>>;
+
+fn parse(mut message: &[u8]) -> Result<Fields> {
+    let mut fields = Fields::new();
+    while !message.is_empty() {
+        let tag = read_varint(&mut message)?;
+        let number = (tag >> 3) as u32;
+        let value = match tag & 7 {
+            0 => Value::Varint(read_varint(&mut message)?),
+            // Nothing a watcher reads is a 64-bit fixed field.
+            1 => {
+                take(&mut message, 8)?;
+                continue;
+            }
+            2 => {
+                let len = read_varint(&mut message)? as usize;
+                Value::Bytes(take(&mut message, len)?.to_vec())
+            }
+            5 => Value::Fixed32(u32::from_le_bytes(take(&mut message, 4)?.try_into()?)),
+            other => bail!("unsupported protobuf wire type {other}"),
+        };
+        fields.entry(number).or_default().push(value);
+    }
+    Ok(fields)
+}
+
+fn take<'a>(message: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+    if message.len() < len {
+        bail!("truncated message");
+    }
+    let (head, rest) = message.split_at(len);
+    *message = rest;
+    Ok(head)
+}
+
+/// Proto3 leaves out fields at their default, so a missing one is 0 or "".
+fn varint(fields: &Fields, number: u32) -> u64 {
+    match fields.get(&number).and_then(|values| values.last()) {
+        Some(Value::Varint(v)) => *v,
+        _ => 0,
+    }
+}
+
+fn fixed32(fields: &Fields, number: u32) -> u32 {
+    match fields.get(&number).and_then(|values| values.last()) {
+        Some(Value::Fixed32(v)) => *v,
+        _ => 0,
+    }
+}
+
+fn string(fields: &Fields, number: u32) -> String {
+    match fields.get(&number).and_then(|values| values.last()) {
+        Some(Value::Bytes(b)) => String::from_utf8_lossy(b).into_owned(),
+        _ => String::new(),
+    }
+}
+
+/// A repeated integer field, whether sent packed or one value at a time.
+fn varints(fields: &Fields, number: u32) -> Vec<u64> {
+    let mut out = Vec::new();
+    for value in fields.get(&number).into_iter().flatten() {
+        match value {
+            Value::Varint(v) => out.push(*v),
+            Value::Bytes(packed) => {
+                let mut packed = packed.as_slice();
+                while let Ok(v) = read_varint(&mut packed) {
+                    out.push(v);
+                }
+            }
+            Value::Fixed32(_) => {}
+        }
+    }
+    out
+}
+
+/// A Bluetooth address, sent as the low six bytes of an integer.
+fn mac(address: u64) -> String {
+    address.to_be_bytes()[2..]
+        .iter()
+        .map(|b| format!("{b:02X}"))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+fn read_varint(reader: &mut impl Read) -> Result<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0u8];
+        reader
+            .read_exact(&mut byte)
+            .context("The device closed the connection")?;
+        value |= u64::from(byte[0] & 0x7f) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    bail!("varint too long")
+}
+
+fn put_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn put_varint_field(out: &mut Vec<u8>, number: u32, value: u64) {
+    put_varint(out, u64::from(number) << 3);
+    put_varint(out, value);
+}
+
+fn put_string(out: &mut Vec<u8>, number: u32, value: &str) {
+    put_varint(out, u64::from(number) << 3 | 2);
+    put_varint(out, value.len() as u64);
+    out.extend_from_slice(value.as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fields_round_trip() {
+        let mut message = Vec::new();
+        put_string(&mut message, 1, "esphome-desktop");
+        put_varint_field(&mut message, 2, 300);
+        message.extend_from_slice(&[(3 << 3) | 5]);
+        message.extend_from_slice(&21.5f32.to_bits().to_le_bytes());
+        let fields = parse(&message).unwrap();
+        assert_eq!(string(&fields, 1), "esphome-desktop");
+        assert_eq!(varint(&fields, 2), 300);
+        assert_eq!(f32::from_bits(fixed32(&fields, 3)), 21.5);
+        assert_eq!(varint(&fields, 9), 0);
+        assert!(parse(&[(1 << 3) | 2, 5, b'a']).is_err());
+    }
+
+    #[test]
+    fn renders_states_by_kind() {
+        let sensor = Entity {
+            unit: "°C".into(),
+            accuracy_decimals: Some(1),
+            ..unlisted(7, "sensor")
+        };
+        let mut fields = Fields::new();
+        fields.insert(2, vec![Value::Fixed32(21.44f32.to_bits())]);
+        assert_eq!(render_state(&sensor, &fields), "21.4 °C");
+        fields.insert(3, vec![Value::Varint(1)]);
+        assert_eq!(render_state(&sensor, &fields), "unknown");
+
+        // A switch that is off sends no state field at all.
+        assert_eq!(render_state(&unlisted(8, "switch"), &Fields::new()), "off");
+        let mut lock = Fields::new();
+        lock.insert(2, vec![Value::Varint(3)]);
+        assert_eq!(render_state(&unlisted(9, "lock"), &lock), "jammed");
+    }
+
+    #[test]
+    fn reads_repeated_fields_either_way() {
+        let mut message = Vec::new();
+        put_varint_field(&mut message, 3, 0xAABB_CCDD_EEFF);
+        put_varint_field(&mut message, 3, 1);
+        put_varint(&mut message, (3 << 3) | 2);
+        put_varint(&mut message, 2);
+        message.extend_from_slice(&[5, 6]);
+        let fields = parse(&message).unwrap();
+        assert_eq!(varints(&fields, 3), [0xAABB_CCDD_EEFF, 1, 5, 6]);
+        assert_eq!(mac(0xAABB_CCDD_EEFF), "AA:BB:CC:DD:EE:FF");
+
+        let info = DeviceInfo {
+            bluetooth_proxy: 3,
+            ..Default::default()
+        };
+        assert_eq!(
+            info.proxy_features(),
+            ["passive scanning", "active connections"]
+        );
+    }
+
+    #[test]
+    fn strips_log_colours() {
+        assert_eq!(
+            strip_ansi("\x1b[0;36m[D][sensor:094]: 'Temp': 21.4\x1b[0m"),
+            "[D][sensor:094]: 'Temp': 21.4"
+        );
+    }
+}
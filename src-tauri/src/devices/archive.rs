@@ -0,0 +1,183 @@
+//! Archiving a device that is retired but whose config may be wanted again:
+//! the config, its build files and its build size history move into
+//! `archive/` in the config directory, out of the dashboard, the device list
+//! and batch actions, and [`restore`] moves them back.
+//!
+//! `archive/<config>` is where the dashboard's own Archive puts a config, so
+//! either can bring back what the other archived. The dashboard deletes the
+//! build files; here they go to `archive/.esphome/`, laid out as in the
+//! config directory. Notes, groups, a `device add` entry and a stored API key
+//! stay where they are: they are kept for a name whose config has gone. Both
+//! can be undone ([`undo`](super::undo)).
+
+use anyhow::{bail, Context, Result};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use super::sizes::{Sample, SizeHistory};
+use super::undo::Change;
+use crate::util::ConfigStore;
+
+/// The archive, in the config directory.
+pub(crate) const ARCHIVE_DIR: &str = "archive";
+
+/// The archived devices, sorted.
+pub(crate) fn archived(config_dir: &Path) -> Result<Vec<String>> {
+    let archive = config_dir.join(ARCHIVE_DIR);
+    if !archive.is_dir() {
+        return Ok(Vec::new());
+    }
+    super::list_devices(&archive)
+}
+
+/// Move `device` into the archive, with its size history from `data_dir`.
+/// Returns where its config went.
+pub(crate) fn archive(config_dir: &Path, data_dir: &Path, device: &str) -> Result<PathBuf> {
+    let config = super::config_path(config_dir, device)
+        .with_context(|| format!("no config for {device}"))?;
+    let archive = config_dir.join(ARCHIVE_DIR);
+    super::undo::transaction(data_dir, &format!("archive {device}"), |change| {
+        let archived = move_device(change, &config, config_dir, &archive)?;
+        let history = SizeHistory::path(data_dir);
+        if !SizeHistory::load(&history)?.samples(device).is_empty() {
+            change.track(&history)?;
+            let samples = SizeHistory::replace(&history, device, Vec::new())?;
+            let content = serde_json::to_string_pretty(&samples)
+                .context("Failed to serialize build sizes")?;
+            change.write(&sizes_path(&archive, device), content)?;
+        }
+        Ok(archived)
+    })
+}
+
+/// Move `device` out of the archive, with its size history back into
+/// `data_dir`. Returns its config.
+pub(crate) fn restore(config_dir: &Path, data_dir: &Path, device: &str) -> Result<PathBuf> {
+    let archive = config_dir.join(ARCHIVE_DIR);
+    let config = super::config_path(&archive, device)
+        .with_context(|| format!("no archived config for {device}"))?;
+    super::undo::transaction(data_dir, &format!("restore {device}"), |change| {
+        let restored = move_device(change, &config, &archive, config_dir)?;
+        let sizes = sizes_path(&archive, device);
+        match std::fs::read_to_string(&sizes) {
+            Ok(content) => {
+                let samples: Vec<Sample> = serde_json::from_str(&content)
+                    .with_context(|| format!("Failed to parse {sizes:?}"))?;
+                std::fs::create_dir_all(data_dir).context("Failed to create data directory")?;
+                let history = SizeHistory::path(data_dir);
+                change.track(&history)?;
+                SizeHistory::replace(&history, device, samples)?;
+                change.remove(&sizes)?;
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {sizes:?}")),
+        }
+        Ok(restored)
+    })
+}
+
+/// Where an archived device's build sizes are kept.
+fn sizes_path(archive: &Path, device: &str) -> PathBuf {
+    archive
+        .join(".esphome")
+        .join("sizes")
+        .join(format!("{device}.json"))
+}
+
+/// Move `config` from the directory `from` into `to`, with its build files.
+fn move_device(change: &mut Change, config: &Path, from: &Path, to: &Path) -> Result<PathBuf> {
+    let file_name = config
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned();
+    let device =
+        super::device_name(config).with_context(|| format!("{config:?} isn't a config"))?;
+    if super::config_path(to, &device).is_some() {
+        bail!("{} already has a config for {device}", to.display());
+    }
+    // The build directory is named for `esphome: name:`, which is usually,
+    // but not always, the file's.
+    let name = std::fs::read_to_string(config)
+        .ok()
+        .and_then(|yaml| super::rename::name(&yaml))
+        .map(|(_, name)| name)
+        .unwrap_or_else(|| device.clone());
+    for part in [
+        Path::new("build").join(&name),
+        Path::new("storage").join(format!("{file_name}.json")),
+    ] {
+        let (source, target) = (
+            from.join(".esphome").join(&part),
+            to.join(".esphome").join(&part),
+        );
+        if !source.exists() {
+            continue;
+        }
+        // What is at the target is from an earlier round trip, and stale.
+        if target.exists() {
+            change.discard(&target)?;
+        }
+        change.rename(&source, &target)?;
+    }
+    let target = to.join(&file_name);
+    change.rename(config, &target)?;
+    Ok(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn archives_a_device_and_restores_it() {
+        let dir = crate::util::unique_temp_dir("archive");
+        let config_dir = dir.join("config");
+        let data_dir = dir.join("data");
+        let esphome = config_dir.join(".esphome");
+        fs::create_dir_all(esphome.join("build").join("porch-light")).unwrap();
+        fs::create_dir_all(esphome.join("storage")).unwrap();
+        fs::create_dir_all(&data_dir).unwrap();
+        fs::write(esphome.join("storage").join("porch.yaml.json"), "{}").unwrap();
+        let yaml = "substitutions:\n  name: porch-light\nesphome:\n  name: ${name}\n";
+        fs::write(config_dir.join("porch.yaml"), yaml).unwrap();
+        let history = SizeHistory::path(&data_dir);
+        let sample = Sample {
+            time: "2024-10-01T12:00:00Z".into(),
+            esphome: None,
+            flash: None,
+            ram: None,
+        };
+        SizeHistory::record(&history, "porch", sample).unwrap();
+
+        let archived = archive(&config_dir, &data_dir, "porch").unwrap();
+        let archive_dir = config_dir.join(ARCHIVE_DIR);
+        assert_eq!(archived, archive_dir.join("porch.yaml"));
+        assert_eq!(
+            super::super::list_devices(&config_dir).unwrap(),
+            Vec::<String>::new()
+        );
+        assert_eq!(super::archived(&config_dir).unwrap(), ["porch"]);
+        assert!(archive_dir.join(".esphome/build/porch-light").is_dir());
+        assert!(archive_dir
+            .join(".esphome/storage/porch.yaml.json")
+            .is_file());
+        assert!(SizeHistory::load(&history)
+            .unwrap()
+            .samples("porch")
+            .is_empty());
+        assert!(archive(&config_dir, &data_dir, "porch").is_err());
+
+        let restored = restore(&config_dir, &data_dir, "porch").unwrap();
+        assert_eq!(restored, config_dir.join("porch.yaml"));
+        assert_eq!(fs::read_to_string(&restored).unwrap(), yaml);
+        assert!(esphome.join("build").join("porch-light").is_dir());
+        assert_eq!(
+            SizeHistory::load(&history).unwrap().samples("porch").len(),
+            1
+        );
+        assert!(super::archived(&config_dir).unwrap().is_empty());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,317 @@
+//! Batch device actions: validate, compile or upload a set of devices (a
+//! group, usually) in one go.
+//!
+//! Devices run one after another through the managed ESPHome CLI rather than
+//! the dashboard, so a batch doesn't need the dashboard up and a failure on
+//! one device is reported without stopping the rest. Running them
+//! sequentially is deliberate: each compile already uses every core.
+//!
+//! Each config's external component sources are checked against the
+//! allow-list ([`sources`](super::sources)) first: an unlisted one is a
+//! warning, or with the list's `block` set a failure before ESPHome runs.
+//! Each build's flash and RAM use goes into the size history
+//! ([`sizes`](super::sizes)), and a firmware too big for its app partition
+//! fails with advice on the partition scheme ([`partitions`](super::partitions)).
+//! Each upload that gets as far as flashing goes into the OTA history
+//! ([`ota`](super::ota)).
+//! A device in the registry ([`registry`](super::registry)) is flashed at its
+//! address there rather than the one ESPHome would look up, and one using
+//! signed OTA gets its key from the keychain ([`signing`](super::signing)).
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::SystemTime;
+use tracing::{info, warn};
+
+use super::ota::{Attempt, OtaHistory};
+use super::partitions::{self, Scheme};
+use super::registry::Registry;
+use super::signing;
+use super::sizes::{Sample, SizeHistory};
+use super::sources::AllowList;
+use crate::control::ops::Progress;
+use crate::daemon::DaemonManager;
+use crate::hooks::Hook;
+use crate::platform;
+use crate::util::ConfigStore;
+
+/// Lines of a failed device's output passed back with the failure.
+pub(super) const FAILURE_TAIL_LINES: usize = 8;
+
+/// What to do to each device in a batch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchAction {
+    /// Check the config without building.
+    Validate,
+    /// Generate the firmware's C++ without building it: catches what a
+    /// compile would short of the compiler's own errors, in seconds.
+    Generate,
+    /// Build the firmware.
+    Compile,
+    /// Build the firmware and flash it over the air.
+    Upload,
+}
+
+impl BatchAction {
+    /// ESPHome CLI arguments for one config file, uploads going to `address`
+    /// if given.
+    fn esphome_args<'a>(self, config: &'a str, address: Option<&'a str>) -> Vec<&'a str> {
+        match self {
+            Self::Validate => vec!["config", config],
+            Self::Generate => vec!["compile", config, "--only-generate"],
+            Self::Compile => vec!["compile", config],
+            Self::Upload => vec![
+                "run",
+                config,
+                "--no-logs",
+                "--device",
+                address.unwrap_or("OTA"),
+            ],
+        }
+    }
+
+    /// Progress verb, e.g. "compiling".
+    pub(crate) fn doing(self) -> &'static str {
+        match self {
+            Self::Validate => "validating",
+            Self::Generate => "generating",
+            Self::Compile => "compiling",
+            Self::Upload => "uploading",
+        }
+    }
+
+    /// Summary verb, e.g. "compiled".
+    pub(crate) fn done(self) -> &'static str {
+        match self {
+            Self::Validate => "validated",
+            Self::Generate => "generated",
+            Self::Compile => "compiled",
+            Self::Upload => "uploaded",
+        }
+    }
+}
+
+/// Outcome of a batch, by device.
+#[derive(Debug, Default)]
+pub(crate) struct BatchReport {
+    pub succeeded: Vec<String>,
+    pub failed: Vec<String>,
+}
+
+/// Run `action` on each device in turn, reporting each start and any failure
+/// (with the tail of its output) through `progress`. Builds record their
+/// flash and RAM use, and uploads their outcome, in the histories in
+/// `data_dir`; generating records nothing.
+pub(crate) async fn run(
+    daemon: &DaemonManager,
+    action: BatchAction,
+    devices: &[String],
+    allow: &AllowList,
+    registry: &Registry,
+    data_dir: &Path,
+    progress: Progress<'_>,
+) -> BatchReport {
+    let mut report = BatchReport::default();
+    for (i, device) in devices.iter().enumerate() {
+        progress(
+            "device",
+            &format!("{} {device} ({}/{})", action.doing(), i + 1, devices.len()),
+        );
+        let address = registry.get(device).map(|d| d.address.as_str());
+        match run_one(daemon, action, device, address, allow, data_dir, progress).await {
+            Ok(()) => report.succeeded.push(device.clone()),
+            Err(e) => {
+                // One progress line per output line, so the client's
+                // indentation holds.
+                let message = format!("{device} failed: {e:#}");
+                for line in message.lines() {
+                    progress("device", line);
+                }
+                let verb = match action {
+                    BatchAction::Compile => Some("compile"),
+                    BatchAction::Upload => Some("upload"),
+                    BatchAction::Validate | BatchAction::Generate => None,
+                };
+                if let Some(verb) = verb {
+                    crate::hooks::fire(
+                        Hook::CompileFailed,
+                        &[
+                            ("ESPHOME_DEVICE", device),
+                            ("ESPHOME_ACTION", verb),
+                            ("ESPHOME_ERROR", &format!("{e:#}")),
+                        ],
+                    );
+                }
+                report.failed.push(device.clone());
+            }
+        }
+    }
+    info!(
+        "Batch {:?}: {} succeeded, {} failed",
+        action,
+        report.succeeded.len(),
+        report.failed.len()
+    );
+    report
+}
+
+async fn run_one(
+    daemon: &DaemonManager,
+    action: BatchAction,
+    device: &str,
+    address: Option<&str>,
+    allow: &AllowList,
+    data_dir: &Path,
+    progress: Progress<'_>,
+) -> Result<()> {
+    let config = super::config_path(daemon.config_dir(), device)
+        .with_context(|| format!("no config for {device}"))?;
+    let unlisted = allow.unlisted(&config)?;
+    if !unlisted.is_empty() {
+        let unlisted = unlisted.join(", ");
+        if allow.block {
+            anyhow::bail!(
+                "external components from sources not on the allow-list: {unlisted} \
+                 (allow them with `esphome-desktop device allow <source>`)"
+            );
+        }
+        warn!("{device} uses external components from unlisted sources: {unlisted}");
+        progress(
+            "device",
+            &format!(
+                "warning: {device} uses external components from unlisted sources: {unlisted}"
+            ),
+        );
+    }
+    let signing = signing::esphome_args(device, &std::fs::read_to_string(&config)?)?;
+    let config_text = config.to_string_lossy();
+    let args = action.esphome_args(&config_text, address);
+    let child = daemon
+        .esphome_command(signing.iter().map(String::as_str).chain(args))?
+        .spawn()
+        .context("Failed to start ESPHome")?;
+    if daemon.low_priority() {
+        platform::lower_priority(&child);
+    }
+    let output = child
+        .wait_with_output()
+        .await
+        .context("Failed to wait for ESPHome")?;
+    let text = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    if action == BatchAction::Upload {
+        record_ota(device, &text, output.status.success(), data_dir);
+    }
+    if output.status.success() {
+        record_size(device, &text, &SizeHistory::path(data_dir), progress);
+        return Ok(());
+    }
+    if let Some((size, max)) = partitions::overflow(&text) {
+        let scheme = Scheme::scan(&std::fs::read_to_string(&config).unwrap_or_default());
+        anyhow::bail!(
+            "the firmware ({size} bytes) is bigger than its app partition ({max} bytes), \
+             so nothing was flashed. It builds for {}.\n{}",
+            scheme.describe(),
+            scheme.advice(device)
+        );
+    }
+    anyhow::bail!("{}\n{}", output.status, tail(&text, FAILURE_TAIL_LINES))
+}
+
+/// Flash `device`'s last build over the serial `port` (see
+/// [`serial`](super::serial)).
+pub(crate) async fn upload_serial(daemon: &DaemonManager, device: &str, port: &str) -> Result<()> {
+    let config = super::config_path(daemon.config_dir(), device)
+        .with_context(|| format!("no config for {device}"))?;
+    let signing = signing::esphome_args(device, &std::fs::read_to_string(&config)?)?;
+    let config_text = config.to_string_lossy();
+    let args = ["upload", &*config_text, "--device", port];
+    let output = daemon
+        .esphome_command(signing.iter().map(String::as_str).chain(args))?
+        .spawn()
+        .context("Failed to start ESPHome")?
+        .wait_with_output()
+        .await
+        .context("Failed to wait for ESPHome")?;
+    if output.status.success() {
+        return Ok(());
+    }
+    let text = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    anyhow::bail!("{}\n{}", output.status, tail(&text, FAILURE_TAIL_LINES))
+}
+
+/// Add a build's memory summary to the size history, warning about a region
+/// close to its limit. Validation and generation have no summary and record
+/// nothing.
+fn record_size(device: &str, output: &str, sizes: &Path, progress: Progress<'_>) {
+    let Some(sample) = Sample::parse(output, crate::audit::rfc3339(SystemTime::now())) else {
+        return;
+    };
+    for (region, percent) in sample.near_limit() {
+        warn!("{device} {region} is {percent}% full");
+        progress(
+            "device",
+            &format!("warning: {device} {region} is {percent}% full"),
+        );
+    }
+    if let Err(e) = SizeHistory::record(sizes, device, sample) {
+        warn!("Failed to record {device}'s build size: {e:#}");
+    }
+}
+
+/// Add an upload's outcome to the OTA history, unless it failed before
+/// flashing.
+fn record_ota(device: &str, output: &str, ok: bool, data_dir: &Path) {
+    let Some(attempt) = Attempt::parse(output, ok, crate::audit::rfc3339(SystemTime::now())) else {
+        return;
+    };
+    if let Err(e) = OtaHistory::record(&OtaHistory::path(data_dir), device, attempt) {
+        warn!("Failed to record {device}'s upload: {e:#}");
+    }
+}
+
+/// The last `n` non-blank lines of `text`.
+pub(super) fn tail(text: &str, n: usize) -> String {
+    let lines: Vec<&str> = text.lines().filter(|l| !l.trim().is_empty()).collect();
+    lines[lines.len().saturating_sub(n)..].join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn upload_builds_and_flashes_over_the_air() {
+        assert_eq!(
+            BatchAction::Upload.esphome_args("porch.yaml", None),
+            ["run", "porch.yaml", "--no-logs", "--device", "OTA"]
+        );
+        assert_eq!(
+            BatchAction::Upload.esphome_args("barn.yaml", Some("10.20.0.15")),
+            ["run", "barn.yaml", "--no-logs", "--device", "10.20.0.15"]
+        );
+        assert_eq!(
+            BatchAction::Validate.esphome_args("porch.yaml", None),
+            ["config", "porch.yaml"]
+        );
+        assert_eq!(
+            BatchAction::Generate.esphome_args("porch.yaml", None),
+            ["compile", "porch.yaml", "--only-generate"]
+        );
+    }
+
+    #[test]
+    fn tail_keeps_the_last_non_blank_lines() {
+        assert_eq!(tail("a\n\nb\nc\n\n", 2), "b\nc");
+        assert_eq!(tail("only", 8), "only");
+    }
+}
@@ -0,0 +1,291 @@
+//! Build diffs: compile one device with two ESPHome releases and compare what
+//! they produced, to pin down which release brought a size or behaviour
+//! regression.
+//!
+//! Each release compiles in a comparison workspace of its own (see
+//! [`Workspace`]), so neither touches the real build state. The report
+//! compares the firmware size, the ESP-IDF `sdkconfig` option by option, and
+//! the generated `main.cpp`. The `main.cpp` comparison is by line, not a
+//! positional diff: the lines only one release generated, which is what
+//! shows a changed default or a new component.
+
+use anyhow::{bail, Context, Result};
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::OsStr;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+use tracing::info;
+
+use crate::control::ops::Progress;
+use crate::daemon::{DaemonManager, Workspace};
+use crate::platform;
+
+/// What one release built.
+#[derive(Debug, Default)]
+struct Build {
+    version: String,
+    firmware_size: Option<u64>,
+    /// `None` for builds without ESP-IDF, which have no sdkconfig.
+    sdkconfig: Option<BTreeMap<String, String>>,
+    main_cpp: String,
+}
+
+/// Compile `device` with ESPHome `from` and then `to`, write the report
+/// under `compare/` in the cache directory and return its path with a
+/// one-line summary.
+pub(crate) async fn run(
+    app: &AppHandle,
+    daemon: &DaemonManager,
+    device: &str,
+    from: &str,
+    to: &str,
+    progress: Progress<'_>,
+) -> Result<(PathBuf, String)> {
+    if from == to {
+        bail!("pick two different ESPHome versions");
+    }
+    let old = build(app, daemon, device, from, progress).await?;
+    let new = build(app, daemon, device, to, progress).await?;
+    let text = report(device, &old, &new);
+    let path = platform::get_compare_dir(app)?.join(format!("{device}-{from}-vs-{to}.txt"));
+    crate::util::atomic_write(&path, &text)?;
+    info!("Build diff for {} written to {:?}", device, path);
+    Ok((path, size_line(&old, &new)))
+}
+
+async fn build(
+    app: &AppHandle,
+    daemon: &DaemonManager,
+    device: &str,
+    version: &str,
+    progress: Progress<'_>,
+) -> Result<Build> {
+    progress(
+        "diff",
+        &format!("preparing ESPHome {version}; the first use installs it"),
+    );
+    let workspace = Workspace::prepare(app, daemon, version, &format!("build-{version}")).await?;
+    let config = super::config_path(&workspace.config_dir, device)
+        .with_context(|| format!("no config for {device}"))?;
+    progress(
+        "diff",
+        &format!("compiling {device} with ESPHome {version}"),
+    );
+    let child = daemon
+        .esphome_command_in(
+            &workspace.python,
+            &workspace.config_dir,
+            [OsStr::new("compile"), config.as_os_str()],
+        )?
+        .spawn()
+        .context("Failed to start ESPHome")?;
+    if daemon.low_priority() {
+        platform::lower_priority(&child);
+    }
+    let output = child
+        .wait_with_output()
+        .await
+        .context("Failed to wait for ESPHome")?;
+    if !output.status.success() {
+        let text = format!(
+            "{}{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        );
+        bail!(
+            "ESPHome {version} failed to compile {device}: {}\n{}",
+            output.status,
+            super::batch::tail(&text, super::batch::FAILURE_TAIL_LINES)
+        );
+    }
+    collect(&workspace.config_dir, version)
+}
+
+/// Read a finished build's artifacts. The workspace is a fresh copy, so the
+/// one directory under `.esphome/build` is this device's, whatever its
+/// `esphome: name:` is.
+fn collect(config_dir: &Path, version: &str) -> Result<Build> {
+    let builds = config_dir.join(".esphome").join("build");
+    let dir = std::fs::read_dir(&builds)
+        .with_context(|| format!("Failed to read {builds:?}"))?
+        .flatten()
+        .map(|e| e.path())
+        .find(|p| p.is_dir())
+        .with_context(|| format!("no build in {builds:?}"))?;
+    let name = dir.file_name().unwrap_or_default().to_string_lossy();
+    let main_cpp = std::fs::read_to_string(dir.join("src").join("main.cpp"))
+        .context("Failed to read the generated main.cpp")?;
+    let sdkconfig = std::fs::read_to_string(dir.join(format!("sdkconfig.{name}")))
+        .ok()
+        .map(|text| parse_sdkconfig(&text));
+    let firmware_size = std::fs::read_dir(dir.join(".pioenvs"))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .find_map(|env| std::fs::metadata(env.path().join("firmware.bin")).ok())
+        .map(|m| m.len());
+    Ok(Build {
+        version: version.to_string(),
+        firmware_size,
+        sdkconfig,
+        main_cpp,
+    })
+}
+
+/// `CONFIG_X=y` lines by option. `# CONFIG_X is not set` counts as `n`, so
+/// an option switched off shows as a change rather than a removal.
+fn parse_sdkconfig(text: &str) -> BTreeMap<String, String> {
+    text.lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            if let Some(option) = line
+                .strip_prefix("# ")
+                .and_then(|l| l.strip_suffix(" is not set"))
+            {
+                return Some((option.to_string(), "n".to_string()));
+            }
+            let (option, value) = line.split_once('=')?;
+            (!line.starts_with('#')).then(|| (option.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+fn size_line(old: &Build, new: &Build) -> String {
+    match (old.firmware_size, new.firmware_size) {
+        (Some(a), Some(b)) => format!(
+            "firmware {a} bytes with {}, {b} bytes with {} ({:+})",
+            old.version,
+            new.version,
+            b as i64 - a as i64
+        ),
+        _ => "no firmware size to compare".to_string(),
+    }
+}
+
+fn report(device: &str, old: &Build, new: &Build) -> String {
+    let (a, b) = (&old.version, &new.version);
+    let mut out = format!("{device}: ESPHome {a} vs {b}\n\n{}\n", size_line(old, new));
+
+    match (&old.sdkconfig, &new.sdkconfig) {
+        (Some(before), Some(after)) => {
+            let changes = sdkconfig_changes(before, after);
+            let _ = writeln!(out, "\nsdkconfig: {} option(s) differ", changes.len());
+            for line in changes {
+                let _ = writeln!(out, "  {line}");
+            }
+        }
+        (None, None) => out += "\nsdkconfig: none (not an ESP-IDF build)\n",
+        _ => out += "\nsdkconfig: only one release built with ESP-IDF\n",
+    }
+
+    let (only_old, only_new) = line_changes(&old.main_cpp, &new.main_cpp);
+    let _ = writeln!(
+        out,
+        "\nmain.cpp: {} line(s) only from {a}, {} only from {b}",
+        only_old.len(),
+        only_new.len()
+    );
+    for line in only_old {
+        let _ = writeln!(out, "- {line}");
+    }
+    for line in only_new {
+        let _ = writeln!(out, "+ {line}");
+    }
+    out
+}
+
+/// Each option that differs, as `CONFIG_X: a -> b`, with `(unset)` for one
+/// only one side has.
+fn sdkconfig_changes(
+    before: &BTreeMap<String, String>,
+    after: &BTreeMap<String, String>,
+) -> Vec<String> {
+    let unset = "(unset)".to_string();
+    let mut options: Vec<&String> = before.keys().chain(after.keys()).collect();
+    options.sort();
+    options.dedup();
+    options
+        .into_iter()
+        .filter_map(|option| {
+            let (a, b) = (
+                before.get(option).unwrap_or(&unset),
+                after.get(option).unwrap_or(&unset),
+            );
+            (a != b).then(|| format!("{option}: {a} -> {b}"))
+        })
+        .collect()
+}
+
+/// The lines only `old` has and the lines only `new` has, counting repeats,
+/// each in its file's order. Blank lines and indentation are ignored.
+fn line_changes<'a>(old: &'a str, new: &'a str) -> (Vec<&'a str>, Vec<&'a str>) {
+    (only_in(old, new), only_in(new, old))
+}
+
+/// The lines of `text` left over once each line of `other` has cancelled
+/// one equal line.
+fn only_in<'a>(text: &'a str, other: &str) -> Vec<&'a str> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for line in lines(other) {
+        *counts.entry(line).or_default() += 1;
+    }
+    lines(text)
+        .filter(|line| match counts.get_mut(line) {
+            Some(n) if *n > 0 => {
+                *n -= 1;
+                false
+            }
+            _ => true,
+        })
+        .collect()
+}
+
+fn lines(text: &str) -> impl Iterator<Item = &str> {
+    text.lines().map(str::trim).filter(|l| !l.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sdkconfig_changes_include_switched_off_options() {
+        let before = parse_sdkconfig("# comment\nCONFIG_A=y\nCONFIG_B=4\nCONFIG_C=y\n");
+        let after =
+            parse_sdkconfig("CONFIG_A=y\nCONFIG_B=8\n# CONFIG_C is not set\nCONFIG_D=\"x\"\n");
+        assert_eq!(
+            sdkconfig_changes(&before, &after),
+            [
+                "CONFIG_B: 4 -> 8",
+                "CONFIG_C: y -> n",
+                "CONFIG_D: (unset) -> \"x\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn line_changes_count_repeats() {
+        let old = "setup();\n  App.loop();\nlog();\nlog();\n";
+        let new = "setup();\nApp.loop();\nlog();\nwifi();\n";
+        assert_eq!(line_changes(old, new), (vec!["log();"], vec!["wifi();"]));
+    }
+
+    #[test]
+    fn report_shows_the_size_change() {
+        let old = Build {
+            version: "2024.6.4".into(),
+            firmware_size: Some(1000),
+            ..Default::default()
+        };
+        let new = Build {
+            version: "2024.10.0".into(),
+            firmware_size: Some(1200),
+            ..Default::default()
+        };
+        let text = report("porch", &old, &new);
+        assert!(text.starts_with("porch: ESPHome 2024.6.4 vs 2024.10.0\n"));
+        assert!(text.contains("1000 bytes with 2024.6.4, 1200 bytes with 2024.10.0 (+200)"));
+        assert!(text.contains("sdkconfig: none"));
+    }
+}
@@ -0,0 +1,433 @@
+//! Finding devices on the network and how well they answer.
+//!
+//! A device's address comes from its config the way ESPHome picks it for
+//! OTA: `wifi: use_address:`, else `manual_ip: static_ip:`, else
+//! `<name>.local` through the OS resolver (mDNS), with the `discovery:
+//! static_addresses` entry for a device that doesn't answer mDNS. A device in
+//! the registry ([`registry`](super::registry)) is reached at its address
+//! there first, and one without a config only there. With
+//! `monitor_latency`, the app probes every device every `discovery:
+//! interval_secs` (skipping those outside `discovery: subnets`, if set): a
+//! few TCP connects to its native API port, timed. A refused connection answers as fast as an
+//! accepted one, so this needs neither ICMP privileges nor the API password.
+//! Each round is kept in `latency.json` in the app data (a day's worth per
+//! device), so `device latency` can show which nodes drop out and when.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::io::ErrorKind;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+use tauri::AppHandle;
+use tracing::{debug, warn};
+
+use super::registry::Registry;
+use super::sources::strip_comment;
+use crate::hooks::Hook;
+use crate::settings::DiscoverySettings;
+use crate::tasks::Tasks;
+use crate::util::ConfigStore;
+
+/// File name of the history inside the data directory.
+pub(crate) const LATENCY_FILE_NAME: &str = "latency.json";
+
+/// The shortest time between rounds `interval_secs` may set.
+const MIN_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Probes per device and round.
+const PROBES: u32 = 4;
+
+/// Time between a device's probes.
+const PROBE_GAP: Duration = Duration::from_millis(250);
+
+/// How long a probe waits before counting as lost.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The port probed: the native API's.
+const PROBE_PORT: u16 = super::api::DEFAULT_PORT;
+
+/// Rounds kept per device: a day at the default five-minute interval.
+const HISTORY_LEN: usize = 288;
+
+/// One round of probes to one device.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Round {
+    /// When it ran, `YYYY-MM-DDTHH:MM:SSZ`.
+    pub time: String,
+    pub sent: u32,
+    pub received: u32,
+    /// Mean and worst connect time of the answered probes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avg_ms: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_ms: Option<u32>,
+    /// The address probed, once resolved.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+}
+
+impl Round {
+    /// Lost probes, in percent.
+    pub(crate) fn loss(&self) -> u32 {
+        (self.sent - self.received.min(self.sent)) * 100 / self.sent.max(1)
+    }
+}
+
+/// Rounds over a stretch of history, summed up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Summary {
+    pub rounds: usize,
+    /// Lost probes over all rounds, in percent.
+    pub loss: u32,
+    /// Rounds that lost every probe.
+    pub outages: usize,
+    pub avg_ms: Option<u32>,
+    pub max_ms: Option<u32>,
+}
+
+/// Sum up `rounds`.
+pub(crate) fn summarize(rounds: &[Round]) -> Summary {
+    let sent: u32 = rounds.iter().map(|r| r.sent).sum();
+    let received: u32 = rounds.iter().map(|r| r.received).sum();
+    let answered: Vec<u32> = rounds.iter().filter_map(|r| r.avg_ms).collect();
+    Summary {
+        rounds: rounds.len(),
+        loss: (sent - received.min(sent)) * 100 / sent.max(1),
+        outages: rounds.iter().filter(|r| r.received == 0).count(),
+        avg_ms: (!answered.is_empty())
+            .then(|| answered.iter().sum::<u32>() / answered.len() as u32),
+        max_ms: rounds.iter().filter_map(|r| r.max_ms).max(),
+    }
+}
+
+/// Every device's recorded rounds, oldest first.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct LatencyHistory {
+    #[serde(default)]
+    devices: BTreeMap<String, Vec<Round>>,
+}
+
+impl ConfigStore for LatencyHistory {
+    const FILE_NAME: &'static str = LATENCY_FILE_NAME;
+    const WHAT: &'static str = "latency history";
+}
+
+impl LatencyHistory {
+    /// Add a round per device to the history at `path`, dropping the oldest
+    /// beyond [`HISTORY_LEN`].
+    pub(crate) fn record(path: &Path, rounds: Vec<(String, Round)>) -> Result<()> {
+        let mut history = Self::load(path)?;
+        for (device, round) in rounds {
+            let kept = history.devices.entry(device).or_default();
+            kept.push(round);
+            let excess = kept.len().saturating_sub(HISTORY_LEN);
+            kept.drain(..excess);
+        }
+        history.save(path)
+    }
+
+    /// `device`'s rounds, oldest first.
+    pub(crate) fn rounds(&self, device: &str) -> &[Round] {
+        self.devices.get(device).map_or(&[], Vec::as_slice)
+    }
+
+    /// Each device with its rounds.
+    pub(crate) fn devices(&self) -> impl Iterator<Item = (&str, &[Round])> {
+        self.devices
+            .iter()
+            .map(|(device, rounds)| (device.as_str(), rounds.as_slice()))
+    }
+}
+
+/// Where `device` is reached, from its config in `config_dir`, then its
+/// entry in `static_addresses` to fall back on.
+pub(crate) fn addresses(
+    config_dir: &Path,
+    device: &str,
+    static_addresses: &BTreeMap<String, String>,
+) -> Vec<String> {
+    let yaml = super::config_path(config_dir, device)
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .unwrap_or_default();
+    let mut addresses = vec![config_address(&yaml).unwrap_or_else(|| format!("{device}.local"))];
+    addresses.extend(static_addresses.get(device).cloned());
+    addresses
+}
+
+/// An IPv4 subnet, `192.168.1.0/24`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Subnet {
+    network: u32,
+    mask: u32,
+}
+
+impl Subnet {
+    pub(crate) fn parse(text: &str) -> Option<Self> {
+        let (ip, bits) = text.trim().split_once('/')?;
+        let ip: Ipv4Addr = ip.parse().ok()?;
+        let bits: u32 = bits.parse().ok().filter(|&b| b <= 32)?;
+        let mask = u32::MAX.checked_shl(32 - bits).unwrap_or(0);
+        Some(Self {
+            network: u32::from(ip) & mask,
+            mask,
+        })
+    }
+
+    fn contains(&self, ip: IpAddr) -> bool {
+        match ip {
+            IpAddr::V4(ip) => u32::from(ip) & self.mask == self.network,
+            IpAddr::V6(_) => false,
+        }
+    }
+}
+
+/// The configured subnets, warning about entries that aren't one.
+fn subnets(settings: &DiscoverySettings) -> Vec<Subnet> {
+    settings
+        .subnets
+        .iter()
+        .filter_map(|text| {
+            let subnet = Subnet::parse(text);
+            if subnet.is_none() {
+                warn!(
+                    "Ignoring discovery subnet {:?}: not like 192.168.1.0/24",
+                    text
+                );
+            }
+            subnet
+        })
+        .collect()
+}
+
+/// `use_address:` or `static_ip:` under `wifi:`, else `<esphome: name>.local`.
+/// A value from a substitution (`${name}`) can't be resolved here and is
+/// left to the caller's default.
+pub(super) fn config_address(yaml: &str) -> Option<String> {
+    let (mut block, mut name, mut static_ip, mut use_address) = ("", None, None, None);
+    for line in yaml.lines().map(strip_comment) {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        if !line.starts_with(char::is_whitespace) {
+            block = trimmed.strip_suffix(':').unwrap_or("");
+            continue;
+        }
+        let Some((key, value)) = trimmed.split_once(':') else {
+            continue;
+        };
+        let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+        if value.is_empty() || value.contains("${") || value.starts_with('!') {
+            continue;
+        }
+        match (block, key.trim()) {
+            ("esphome", "name") => name = Some(format!("{value}.local")),
+            ("wifi", "static_ip") => static_ip = Some(value.to_string()),
+            ("wifi", "use_address") => use_address = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    use_address.or(static_ip).or(name)
+}
+
+/// Probe the first of `addresses` that resolves [`PROBES`] times. `None`
+/// when it resolves outside `subnets` (if any are set); an address that
+/// doesn't resolve at all is a round with every probe lost. Resolving isn't
+/// timed: a cached mDNS answer would flatter one round and a fresh one
+/// penalize the next.
+pub(crate) async fn measure(addresses: &[String], subnets: &[Subnet]) -> Option<Round> {
+    let mut target = None;
+    for address in addresses {
+        if let Ok(mut resolved) = tokio::net::lookup_host((address.as_str(), PROBE_PORT)).await {
+            target = resolved.next();
+            if target.is_some() {
+                break;
+            }
+        }
+    }
+    if let Some(target) = target {
+        if !subnets.is_empty() && !subnets.iter().any(|s| s.contains(target.ip())) {
+            return None;
+        }
+    }
+    let mut times = Vec::new();
+    for i in 0..PROBES {
+        if i > 0 {
+            tokio::time::sleep(PROBE_GAP).await;
+        }
+        if let Some(time) = probe(target).await {
+            times.push(time.as_millis() as u32);
+        }
+    }
+    Some(Round {
+        time: crate::audit::rfc3339(SystemTime::now()),
+        sent: PROBES,
+        received: times.len() as u32,
+        avg_ms: (!times.is_empty()).then(|| times.iter().sum::<u32>() / times.len() as u32),
+        max_ms: times.iter().copied().max(),
+        address: target.map(|t| t.ip().to_string()),
+    })
+}
+
+/// One timed connect.
+async fn probe(target: Option<SocketAddr>) -> Option<Duration> {
+    let target = target?;
+    let start = Instant::now();
+    match tokio::time::timeout(PROBE_TIMEOUT, tokio::net::TcpStream::connect(target)).await {
+        Ok(Ok(_)) => Some(start.elapsed()),
+        Ok(Err(e)) if e.kind() == ErrorKind::ConnectionRefused => Some(start.elapsed()),
+        _ => None,
+    }
+}
+
+/// Probe every device each `interval_secs` while the app runs, recording
+/// the rounds in the app data.
+pub(crate) fn spawn(
+    tasks: &Tasks,
+    app: AppHandle,
+    config_dir: PathBuf,
+    settings: DiscoverySettings,
+) {
+    let interval = Duration::from_secs(settings.interval_secs).max(MIN_INTERVAL);
+    let subnets = subnets(&settings);
+    tasks.spawn("latency monitor", async move {
+        let data_dir = match crate::platform::get_data_dir(&app) {
+            Ok(dir) => dir,
+            Err(e) => {
+                warn!("Not monitoring latency: {:#}", e);
+                return;
+            }
+        };
+        let path = LatencyHistory::path(&data_dir);
+        // Devices whose last round got an answer, so a device that was
+        // never up isn't reported as going offline every round.
+        let mut answering = HashSet::new();
+        loop {
+            // Read each round: `device add` edits it while the app runs.
+            let registry = Registry::load(&Registry::path(&data_dir)).unwrap_or_else(|e| {
+                warn!("Ignoring the device registry: {:#}", e);
+                Registry::default()
+            });
+            let configs = super::list_devices(&config_dir).unwrap_or_default();
+            let probes: Vec<_> = super::registry::with_manual(configs, &registry)
+                .into_iter()
+                .map(|device| {
+                    let mut addresses = addresses(&config_dir, &device, &settings.static_addresses);
+                    if let Some(manual) = registry.get(&device) {
+                        addresses.insert(0, manual.address.clone());
+                    }
+                    let subnets = subnets.clone();
+                    tokio::spawn(async move { (device, measure(&addresses, &subnets).await) })
+                })
+                .collect();
+            let mut rounds = Vec::new();
+            for probe in probes {
+                if let Ok((device, Some(round))) = probe.await {
+                    rounds.push((device, round));
+                }
+            }
+            debug!("Measured latency to {} devices", rounds.len());
+            for (device, round) in &rounds {
+                if round.received > 0 {
+                    answering.insert(device.clone());
+                } else if answering.remove(device) {
+                    crate::hooks::fire(Hook::DeviceOffline, &[("ESPHOME_DEVICE", device)]);
+                }
+            }
+            if let Err(e) = LatencyHistory::record(&path, rounds) {
+                warn!("Failed to record latency: {:#}", e);
+            }
+            tokio::time::sleep(interval).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round(received: u32, avg_ms: Option<u32>) -> Round {
+        Round {
+            time: String::new(),
+            sent: PROBES,
+            received,
+            avg_ms,
+            max_ms: avg_ms.map(|ms| ms * 2),
+            address: None,
+        }
+    }
+
+    #[test]
+    fn addresses_come_from_the_config() {
+        let yaml = "esphome:\n  name: porch\nwifi:\n  manual_ip:\n    static_ip: 192.168.1.40\n";
+        assert_eq!(config_address(yaml).as_deref(), Some("192.168.1.40"));
+        let yaml = "esphome:\n  name: porch\nwifi:\n  use_address: porch.lan\n";
+        assert_eq!(config_address(yaml).as_deref(), Some("porch.lan"));
+        assert_eq!(
+            config_address("esphome:\n  name: porch # hallway\n").as_deref(),
+            Some("porch.local")
+        );
+        assert_eq!(config_address("esphome:\n  name: ${name}\n"), None);
+    }
+
+    #[test]
+    fn static_addresses_are_the_fallback() {
+        let dir = crate::util::unique_temp_dir("discovery");
+        std::fs::write(dir.join("porch.yaml"), "esphome:\n  name: porch\n").unwrap();
+        let fallback = [("porch".to_string(), "192.168.1.40".to_string())].into();
+        assert_eq!(
+            addresses(&dir, "porch", &fallback),
+            ["porch.local", "192.168.1.40"]
+        );
+        assert_eq!(addresses(&dir, "shed", &fallback), ["shed.local"]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn subnets_match_ipv4_addresses() {
+        let lan = Subnet::parse("192.168.1.0/24").unwrap();
+        assert!(lan.contains("192.168.1.40".parse().unwrap()));
+        assert!(!lan.contains("192.168.2.40".parse().unwrap()));
+        assert!(!lan.contains("::1".parse().unwrap()));
+        assert!(Subnet::parse("0.0.0.0/0")
+            .unwrap()
+            .contains("10.0.0.1".parse().unwrap()));
+        for bad in ["192.168.1.0", "192.168.1.0/33", "lan/24"] {
+            assert_eq!(Subnet::parse(bad), None, "{bad}");
+        }
+    }
+
+    #[test]
+    fn summarizes_loss_and_outages() {
+        let rounds = [round(4, Some(20)), round(2, Some(40)), round(0, None)];
+        assert_eq!(rounds[1].loss(), 50);
+        assert_eq!(
+            summarize(&rounds),
+            Summary {
+                rounds: 3,
+                loss: 50,
+                outages: 1,
+                avg_ms: Some(30),
+                max_ms: Some(80),
+            }
+        );
+    }
+
+    #[test]
+    fn history_keeps_a_day() {
+        let dir = crate::util::unique_temp_dir("latency");
+        let path = LatencyHistory::path(&dir);
+        for _ in 0..2 {
+            let rounds = (0..HISTORY_LEN / 2 + 1)
+                .map(|_| ("porch".to_string(), round(4, Some(10))))
+                .collect();
+            LatencyHistory::record(&path, rounds).unwrap();
+        }
+        let history = LatencyHistory::load(&path).unwrap();
+        assert_eq!(history.rounds("porch").len(), HISTORY_LEN);
+        assert_eq!(history.devices().count(), 1);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,206 @@
+//! Devices that are one device twice: configs giving the same name, and so
+//! the same hostname and build directory (usually one copied from the other
+//! and never renamed); configs with the same `use_address` or `static_ip`;
+//! and a device added by address ([`registry`](super::registry)) that is a
+//! configured device under another name, so it is listed, probed and
+//! uploaded to twice.
+//!
+//! Configs with `name_add_mac_suffix: true` are left out, as each board adds
+//! its own suffix to the name.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use super::registry::Registry;
+use super::sources::strip_comment;
+
+/// A config in a clash, at the line setting its name.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct Config {
+    pub device: String,
+    /// The config's file name.
+    pub file: String,
+    /// From 1.
+    pub line: usize,
+}
+
+/// Configs sharing a name or an address.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct Clash {
+    /// The name or address.
+    pub value: String,
+    pub configs: Vec<Config>,
+}
+
+/// A device added by address that is `device`'s.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct Added {
+    /// Its name in the registry.
+    pub name: String,
+    pub address: String,
+    pub device: String,
+}
+
+/// What [`find`] found, each list sorted.
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+pub(crate) struct Report {
+    pub names: Vec<Clash>,
+    /// Addresses shared by configs with different names; one name shared is
+    /// in `names`.
+    pub addresses: Vec<Clash>,
+    pub added: Vec<Added>,
+}
+
+/// The duplicates among the configs in `config_dir` and the entries in
+/// `registry`.
+pub(crate) fn find(config_dir: &Path, registry: &Registry) -> Result<Report> {
+    let mut names: BTreeMap<String, Vec<Config>> = BTreeMap::new();
+    let mut addresses: BTreeMap<String, Vec<(String, Config)>> = BTreeMap::new();
+    let devices = super::list_devices(config_dir)?;
+    for device in &devices {
+        let Some(path) = super::config_path(config_dir, device) else {
+            continue;
+        };
+        let Ok(yaml) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        let Some((line, name)) = super::rename::name(&yaml) else {
+            continue;
+        };
+        if has_mac_suffix(&yaml) {
+            continue;
+        }
+        let config = Config {
+            device: device.clone(),
+            file: path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+            line,
+        };
+        let address =
+            super::discovery::config_address(&yaml).unwrap_or_else(|| format!("{name}.local"));
+        addresses
+            .entry(normalize(&address))
+            .or_default()
+            .push((name.clone(), config.clone()));
+        names.entry(name).or_default().push(config);
+    }
+
+    let added = registry
+        .entries()
+        .filter(|(name, _)| !devices.iter().any(|d| d.as_str() == *name))
+        .filter_map(|(name, entry)| {
+            let (_, config) = addresses.get(&normalize(&entry.address))?.first()?;
+            Some(Added {
+                name: name.to_string(),
+                address: entry.address.clone(),
+                device: config.device.clone(),
+            })
+        })
+        .collect();
+    Ok(Report {
+        names: names
+            .into_iter()
+            .filter(|(_, configs)| configs.len() > 1)
+            .map(|(value, configs)| Clash { value, configs })
+            .collect(),
+        addresses: addresses
+            .into_iter()
+            .filter(|(_, configs)| {
+                configs
+                    .iter()
+                    .map(|(name, _)| name)
+                    .collect::<BTreeSet<_>>()
+                    .len()
+                    > 1
+            })
+            .map(|(value, configs)| Clash {
+                value,
+                configs: configs.into_iter().map(|(_, config)| config).collect(),
+            })
+            .collect(),
+        added,
+    })
+}
+
+/// Hostnames compare without case or a trailing dot.
+fn normalize(address: &str) -> String {
+    address.trim().trim_end_matches('.').to_lowercase()
+}
+
+fn has_mac_suffix(yaml: &str) -> bool {
+    yaml.lines()
+        .map(|line| strip_comment(line).trim().replace(['"', '\''], ""))
+        .any(|line| line == "name_add_mac_suffix: true")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::devices::registry::ManualDevice;
+    use std::fs;
+
+    #[test]
+    fn finds_configs_and_added_devices_that_are_the_same() {
+        let dir = crate::util::unique_temp_dir("duplicates");
+        for (file, yaml) in [
+            ("porch.yaml", "esphome:\n  name: porch\n"),
+            ("porch-copy.yaml", "esphome:\n  name: porch\n"),
+            (
+                "garage.yaml",
+                "esphome:\n  name: garage\nwifi:\n  use_address: 10.0.0.5\n",
+            ),
+            (
+                "shed.yaml",
+                "substitutions:\n  name: shed\nesphome:\n  name: ${name}\nwifi:\n  use_address: 10.0.0.5\n",
+            ),
+            (
+                "plug.yaml",
+                "esphome:\n  name: plug\n  name_add_mac_suffix: true\n",
+            ),
+            (
+                "plug-2.yaml",
+                "esphome:\n  name: plug\n  name_add_mac_suffix: true\n",
+            ),
+        ] {
+            fs::write(dir.join(file), yaml).unwrap();
+        }
+        let mut registry = Registry::default();
+        for (name, address) in [
+            ("barn", "10.0.0.9"),
+            ("old-porch", "Porch.local."),
+            ("garage", "10.0.0.5"),
+        ] {
+            let entry = ManualDevice {
+                address: address.into(),
+                key_secret: None,
+            };
+            registry.add(name, entry).unwrap();
+        }
+
+        let report = find(&dir, &registry).unwrap();
+        let devices = |clash: &Clash| -> Vec<String> {
+            clash.configs.iter().map(|c| c.device.clone()).collect()
+        };
+        assert_eq!(report.names.len(), 1);
+        assert_eq!(report.names[0].value, "porch");
+        assert_eq!(devices(&report.names[0]), ["porch", "porch-copy"]);
+        assert_eq!(report.addresses.len(), 1);
+        assert_eq!(report.addresses[0].value, "10.0.0.5");
+        assert_eq!(devices(&report.addresses[0]), ["garage", "shed"]);
+        assert_eq!(report.addresses[0].configs[1].line, 2);
+        assert_eq!(
+            report.added,
+            [Added {
+                name: "old-porch".into(),
+                address: "Porch.local.".into(),
+                device: "porch".into(),
+            }]
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,302 @@
+//! Opening a device's config in an editor.
+//!
+//! The `editor_command` setting wins. Without it the first editor found on
+//! this machine is used (VS Code, Sublime Text, Notepad++), and without one
+//! of those whatever the system opens `.yaml` files with, which is often a
+//! plain text viewer or nothing at all.
+//!
+//! The known editors open a file at a line (for search results); a custom one
+//! does where `editor_command` has a `{line}`.
+
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Placeholder for the file in `editor_command`; without one the file is
+/// passed last.
+const FILE_PLACEHOLDER: &str = "{file}";
+
+/// Placeholder for the line to open the file at in `editor_command`.
+const LINE_PLACEHOLDER: &str = "{line}";
+
+/// An editor we look for.
+struct Known {
+    /// What `device edit --editor` takes.
+    id: &'static str,
+    name: &'static str,
+    /// Command looked up on `PATH`.
+    command: &'static str,
+    /// Its arguments to open a file at a line.
+    args: &'static [&'static str],
+}
+
+const KNOWN: &[Known] = &[
+    Known {
+        id: "code",
+        name: "VS Code",
+        command: "code",
+        args: &["--goto", "{file}:{line}"],
+    },
+    Known {
+        id: "sublime",
+        name: "Sublime Text",
+        command: "subl",
+        args: &["{file}:{line}"],
+    },
+    Known {
+        id: "notepad++",
+        name: "Notepad++",
+        command: "notepad++",
+        args: &["-n{line}", "{file}"],
+    },
+];
+
+/// An editor to open configs in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Editor {
+    /// `code`, `sublime`, `notepad++`, or `custom` for `editor_command`.
+    pub id: &'static str,
+    pub name: String,
+    program: PathBuf,
+    /// Arguments, with [`FILE_PLACEHOLDER`] where the file goes and maybe
+    /// [`LINE_PLACEHOLDER`] where the line does.
+    args: Vec<String>,
+}
+
+impl Editor {
+    /// The editor `editor_command` describes, e.g. `code --goto` or
+    /// `"C:\Tools\Vim\gvim.exe" --remote-tab {file}`.
+    pub(crate) fn custom(command: &str) -> Result<Self> {
+        let mut words = split_command(command).into_iter();
+        let Some(program) = words.next() else {
+            bail!("editor_command is empty");
+        };
+        let mut args: Vec<String> = words.collect();
+        if !args.iter().any(|a| a.contains(FILE_PLACEHOLDER)) {
+            args.push(FILE_PLACEHOLDER.to_string());
+        }
+        let program = PathBuf::from(program);
+        let name = program
+            .file_stem()
+            .map_or_else(|| command.to_string(), |s| s.to_string_lossy().into_owned());
+        Ok(Self {
+            id: "custom",
+            name,
+            program,
+            args,
+        })
+    }
+
+    fn known(known: &Known, program: PathBuf) -> Self {
+        Self {
+            id: known.id,
+            name: known.name.to_string(),
+            program,
+            args: known.args.iter().map(|a| a.to_string()).collect(),
+        }
+    }
+
+    /// Open `file` in the editor, without waiting for it.
+    pub(crate) fn open(&self, file: &Path) -> Result<()> {
+        self.open_at(file, 1)
+    }
+
+    /// Open `file` at `line` (from 1) in the editor, without waiting for it.
+    pub(crate) fn open_at(&self, file: &Path, line: usize) -> Result<()> {
+        let mut cmd = Command::new(&self.program);
+        cmd.args(self.args_for(&file.to_string_lossy(), line))
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        crate::platform::configure_no_window_command(&mut cmd);
+        cmd.spawn()
+            .with_context(|| format!("Failed to start {}", self.name))?;
+        Ok(())
+    }
+
+    fn args_for(&self, file: &str, line: usize) -> Vec<String> {
+        let line = line.to_string();
+        self.args
+            .iter()
+            .map(|a| {
+                a.replace(FILE_PLACEHOLDER, file)
+                    .replace(LINE_PLACEHOLDER, &line)
+            })
+            .collect()
+    }
+}
+
+/// The editors installed here, in order of preference.
+pub(crate) fn detect() -> Vec<Editor> {
+    KNOWN
+        .iter()
+        .filter_map(|known| {
+            let program = find_on_path(known.command)
+                .or_else(|| install_paths(known.id).into_iter().find(|p| p.is_file()))?;
+            Some(Editor::known(known, program))
+        })
+        .collect()
+}
+
+/// The editor to use: `editor_command` if set, else the `id` one if given
+/// (which must be installed), else the first one installed. `None` means
+/// the system's default for the file.
+pub(crate) fn choose(editor_command: Option<&str>, id: Option<&str>) -> Result<Option<Editor>> {
+    match (id, editor_command) {
+        (Some("default"), _) => Ok(None),
+        (Some("custom") | None, Some(command)) => Editor::custom(command).map(Some),
+        (Some("custom"), None) => bail!("editor_command is not set"),
+        (Some(id), _) => match detect().into_iter().find(|e| e.id == id) {
+            Some(editor) => Ok(Some(editor)),
+            None if KNOWN.iter().any(|k| k.id == id) => bail!("{id} is not installed"),
+            None => bail!("unknown editor {id:?} (use code, sublime, notepad++ or default)"),
+        },
+        (None, None) => Ok(detect().into_iter().next()),
+    }
+}
+
+/// Open `file` in `editor`, or with the system's default for it.
+pub(crate) fn open(editor: Option<&Editor>, file: &Path) -> Result<()> {
+    open_at(editor, file, 1)
+}
+
+/// [`open`] at `line`, where the editor can; the system's default can't.
+pub(crate) fn open_at(editor: Option<&Editor>, file: &Path, line: usize) -> Result<()> {
+    match editor {
+        Some(editor) => editor.open_at(file, line),
+        None => open::that_detached(file).with_context(|| format!("Failed to open {file:?}")),
+    }
+}
+
+/// `command` split into words at whitespace outside double quotes.
+pub(crate) fn split_command(command: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quoted = false;
+    let mut started = false;
+    for c in command.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                started = true;
+            }
+            c if c.is_whitespace() && !quoted => {
+                if started {
+                    words.push(std::mem::take(&mut word));
+                    started = false;
+                }
+            }
+            c => {
+                word.push(c);
+                started = true;
+            }
+        }
+    }
+    if started {
+        words.push(word);
+    }
+    words
+}
+
+fn find_on_path(command: &str) -> Option<PathBuf> {
+    let name = if cfg!(target_os = "windows") {
+        format!("{command}.exe")
+    } else {
+        command.to_string()
+    };
+    std::env::split_paths(&std::env::var_os("PATH")?)
+        .map(|dir| dir.join(&name))
+        .find(|p| p.is_file())
+}
+
+/// Where the editor's installer puts it, for when it isn't on `PATH` (the
+/// usual case on Windows and macOS).
+fn install_paths(id: &str) -> Vec<PathBuf> {
+    #[cfg(target_os = "windows")]
+    {
+        let env = |var: &str| std::env::var_os(var).map(PathBuf::from);
+        let program_files: Vec<PathBuf> = ["ProgramFiles", "ProgramFiles(x86)"]
+            .into_iter()
+            .filter_map(env)
+            .collect();
+        let under = |rel: &str| {
+            program_files
+                .iter()
+                .map(|d| d.join(rel))
+                .collect::<Vec<_>>()
+        };
+        match id {
+            "code" => env("LOCALAPPDATA")
+                .map(|d| d.join(r"Programs\Microsoft VS Code\Code.exe"))
+                .into_iter()
+                .chain(under(r"Microsoft VS Code\Code.exe"))
+                .collect(),
+            "sublime" => [
+                under(r"Sublime Text\subl.exe"),
+                under(r"Sublime Text 3\subl.exe"),
+            ]
+            .concat(),
+            "notepad++" => under(r"Notepad++\notepad++.exe"),
+            _ => Vec::new(),
+        }
+    }
+    #[cfg(target_os = "macos")]
+    {
+        let bundle = match id {
+            "code" => "Visual Studio Code.app/Contents/Resources/app/bin/code",
+            "sublime" => "Sublime Text.app/Contents/SharedSupport/bin/subl",
+            _ => return Vec::new(),
+        };
+        std::iter::once(PathBuf::from("/Applications"))
+            .chain(dirs::home_dir().map(|h| h.join("Applications")))
+            .map(|apps| apps.join(bundle))
+            .collect()
+    }
+    #[cfg(target_os = "linux")]
+    {
+        match id {
+            "code" => vec![PathBuf::from("/snap/bin/code")],
+            "sublime" => vec![PathBuf::from("/opt/sublime_text/sublime_text")],
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn custom_command_takes_quotes_and_a_file_placeholder() {
+        let editor = Editor::custom(r#""C:\Tools\Vim\gvim.exe" --remote-tab {file}"#).unwrap();
+        assert_eq!(editor.program, PathBuf::from(r"C:\Tools\Vim\gvim.exe"));
+        assert_eq!(editor.args, ["--remote-tab", "{file}"]);
+
+        let editor = Editor::custom("code --goto").unwrap();
+        assert_eq!(editor.name, "code");
+        assert_eq!(editor.args, ["--goto", "{file}"]);
+
+        assert!(Editor::custom("  ").is_err());
+    }
+
+    #[test]
+    fn known_editors_open_at_a_line() {
+        let code = Editor::known(&KNOWN[0], PathBuf::from("code"));
+        assert_eq!(code.args_for("porch.yaml", 12), ["--goto", "porch.yaml:12"]);
+        let notepad = Editor::known(&KNOWN[2], PathBuf::from("notepad++"));
+        assert_eq!(notepad.args_for("porch.yaml", 3), ["-n3", "porch.yaml"]);
+        let custom = Editor::custom("gvim --remote-tab +{line}").unwrap();
+        assert_eq!(
+            custom.args_for("porch.yaml", 7),
+            ["--remote-tab", "+7", "porch.yaml"]
+        );
+    }
+
+    #[test]
+    fn editor_command_wins_unless_another_is_asked_for() {
+        let chosen = choose(Some("micro"), None).unwrap().unwrap();
+        assert_eq!(chosen.id, "custom");
+        assert!(choose(Some("micro"), Some("default")).unwrap().is_none());
+        assert!(choose(None, Some("emacs")).is_err());
+    }
+}
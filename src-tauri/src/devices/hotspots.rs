@@ -0,0 +1,205 @@
+//! Fallback hotspots: a device that can't join its Wi-Fi for a minute opens
+//! an access point of its own (`wifi: ap:`) with a captive portal to give it
+//! new credentials. Spotting that AP from this machine tells the user right
+//! away, rather than hours later when they notice the device is offline.
+//!
+//! A visible SSID counts when it is a config's `ap: ssid:`, a device's name
+//! (ESPHome's default when `ap:` has none), or looks like one: ESPHome's
+//! wizard names them `<Name> Fallback Hotspot`, and firmware from elsewhere
+//! often `ESPHome-XXXXXX`. The app checks every few minutes while it runs
+//! (`watch_fallback_hotspots`) and `device hotspots` checks on demand.
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::AppHandle;
+use tracing::{debug, info, warn};
+
+use super::sources::strip_comment;
+use crate::i18n::{t, t_with};
+use crate::tasks::Tasks;
+
+/// Time between Wi-Fi checks.
+const SCAN_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Where ESPHome's captive portal answers on its own AP.
+pub(crate) const PORTAL_URL: &str = "http://192.168.4.1";
+
+/// A fallback hotspot in range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Hotspot {
+    pub ssid: String,
+    /// The device it belongs to, when a config says so.
+    pub device: Option<String>,
+}
+
+impl Hotspot {
+    /// How to get the device back onto the network.
+    pub(crate) fn guide(&self) -> String {
+        let whose = self.device.as_deref().unwrap_or("a device");
+        format!(
+            "{ssid} is {whose}'s fallback hotspot: it can't join your Wi-Fi.\n\
+             1. Connect this computer or a phone to {ssid} (its password is ap: password \
+             in the config, if it has one).\n\
+             2. A sign-in page should open; if not, browse to {PORTAL_URL}.\n\
+             3. Pick your network and enter its password. The device restarts and joins it.\n\
+             If your network's name or password changed, update wifi: in the config too, or \
+             the next upload puts the old one back.",
+            ssid = self.ssid
+        )
+    }
+}
+
+/// The fallback hotspots among `visible` SSIDs, matched to the devices in
+/// `config_dir` where possible.
+pub(crate) fn find(visible: &[String], config_dir: &Path) -> Vec<Hotspot> {
+    let mut known: Vec<(String, String)> = Vec::new();
+    for device in super::list_devices(config_dir).unwrap_or_default() {
+        let ap = super::config_path(config_dir, &device)
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|yaml| ap_ssid(&yaml));
+        if let Some(ssid) = ap {
+            known.push((ssid, device.clone()));
+        }
+        known.push((device.clone(), device));
+    }
+    visible
+        .iter()
+        .filter_map(|ssid| {
+            let device = known
+                .iter()
+                .find(|(known, _)| known.eq_ignore_ascii_case(ssid))
+                .map(|(_, device)| device.clone());
+            (device.is_some() || looks_like_fallback(ssid)).then(|| Hotspot {
+                ssid: ssid.clone(),
+                device,
+            })
+        })
+        .collect()
+}
+
+fn looks_like_fallback(ssid: &str) -> bool {
+    let lower = ssid.to_ascii_lowercase();
+    lower.ends_with("fallback hotspot") || lower.starts_with("esphome-")
+}
+
+/// `ssid:` under `wifi:` → `ap:`, unless it is a `!secret`.
+fn ap_ssid(yaml: &str) -> Option<String> {
+    let mut in_wifi = false;
+    let mut ap_indent: Option<usize> = None;
+    for line in yaml.lines().map(strip_comment) {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let indent = line.len() - line.trim_start().len();
+        if indent == 0 {
+            in_wifi = trimmed == "wifi:";
+            ap_indent = None;
+            continue;
+        }
+        if !in_wifi {
+            continue;
+        }
+        match ap_indent {
+            Some(ap) if indent > ap => {
+                if let Some(value) = trimmed.strip_prefix("ssid:") {
+                    let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+                    return (!value.is_empty() && !value.starts_with('!'))
+                        .then(|| value.to_string());
+                }
+            }
+            _ => ap_indent = (trimmed == "ap:").then_some(indent),
+        }
+    }
+    None
+}
+
+/// Check for fallback hotspots every [`SCAN_INTERVAL`] while the app runs,
+/// with a notification when one appears. One that stays up isn't announced
+/// again; one that goes and comes back is. Stops for good if this machine
+/// can't list Wi-Fi networks.
+pub(crate) fn spawn(tasks: &Tasks, app: AppHandle, config_dir: PathBuf) {
+    tasks.spawn("hotspot watch", async move {
+        let mut announced: BTreeSet<String> = BTreeSet::new();
+        loop {
+            let visible = match tokio::task::spawn_blocking(crate::platform::visible_ssids).await {
+                Ok(Ok(visible)) => visible,
+                Ok(Err(e)) => {
+                    info!("Not watching for fallback hotspots: {:#}", e);
+                    return;
+                }
+                Err(e) => {
+                    warn!("Wi-Fi scan panicked: {}", e);
+                    return;
+                }
+            };
+            let hotspots = find(&visible, &config_dir);
+            announced.retain(|ssid| hotspots.iter().any(|h| &h.ssid == ssid));
+            for hotspot in hotspots {
+                if announced.insert(hotspot.ssid.clone()) {
+                    info!("Fallback hotspot in range: {}", hotspot.ssid);
+                    notify(&app, &hotspot);
+                }
+            }
+            debug!(
+                "Checked {} Wi-Fi networks for fallback hotspots",
+                visible.len()
+            );
+            tokio::time::sleep(SCAN_INTERVAL).await;
+        }
+    });
+}
+
+fn notify(app: &AppHandle, hotspot: &Hotspot) {
+    let body = match &hotspot.device {
+        Some(device) => t_with(
+            "hotspot.device_body",
+            &[("device", device), ("ssid", &hotspot.ssid)],
+        ),
+        None => t_with("hotspot.body", &[("ssid", &hotspot.ssid)]),
+    };
+    if let Err(e) = crate::notifications::show(app, t("hotspot.title"), body) {
+        warn!("Failed to show hotspot notification: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::unique_temp_dir;
+
+    #[test]
+    fn reads_the_ap_ssid() {
+        let yaml = "esphome:\n  name: porch\n\nwifi:\n  ssid: !secret wifi_ssid\n  ap:\n    \
+                    ssid: \"Porch Fallback Hotspot\" # wizard default\n    password: x\n";
+        assert_eq!(ap_ssid(yaml).as_deref(), Some("Porch Fallback Hotspot"));
+        assert_eq!(ap_ssid("wifi:\n  ssid: home\n  ap:\n"), None);
+        assert_eq!(ap_ssid("wifi:\n  ap:\n    ssid: !secret ap\n"), None);
+    }
+
+    #[test]
+    fn matches_hotspots_to_devices() {
+        let dir = unique_temp_dir("hotspots");
+        std::fs::write(dir.join("porch.yaml"), "wifi:\n  ap:\n    ssid: Porch AP\n").unwrap();
+        std::fs::write(dir.join("shed.yaml"), "wifi:\n  ap:\n").unwrap();
+        let visible: Vec<String> = ["Home", "Porch AP", "shed", "ESPHome-1A2B3C"]
+            .map(String::from)
+            .to_vec();
+        let found = find(&visible, &dir);
+        let pairs: Vec<(&str, Option<&str>)> = found
+            .iter()
+            .map(|h| (h.ssid.as_str(), h.device.as_deref()))
+            .collect();
+        assert_eq!(
+            pairs,
+            [
+                ("Porch AP", Some("porch")),
+                ("shed", Some("shed")),
+                ("ESPHome-1A2B3C", None),
+            ]
+        );
+        assert!(found[0].guide().contains(PORTAL_URL));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
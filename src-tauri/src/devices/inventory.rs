@@ -0,0 +1,273 @@
+//! The device inventory (`device inventory`): a row per device with its chip,
+//! board, framework, the ESPHome release its firmware was built with, when
+//! and where it last answered, and its tags, as CSV, JSON or a Markdown
+//! table, for documentation or an asset list.
+//!
+//! The board and framework come from the config, else from the dashboard's
+//! storage file for it (`.esphome/storage/<file>.json`), which also has the
+//! release of the last build; the size history has it otherwise. "Last seen"
+//! and the resolved address come from the latency history, so they need
+//! `monitor_latency`; without it the address is the registry's or the
+//! config's.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use super::discovery::LatencyHistory;
+use super::registry::Registry;
+use super::sizes::SizeHistory;
+use super::sources::strip_comment;
+use super::NotesStore;
+use crate::util::ConfigStore;
+
+/// One device.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub(crate) struct Row {
+    pub name: String,
+    pub platform: Option<String>,
+    pub board: Option<String>,
+    pub framework: Option<String>,
+    /// The ESPHome release the firmware was last built with.
+    pub firmware: Option<String>,
+    /// The last probe it answered, `YYYY-MM-DDTHH:MM:SSZ`.
+    pub last_seen: Option<String>,
+    pub address: Option<String>,
+    pub tags: Vec<String>,
+}
+
+/// What the dashboard keeps about a config's last build.
+#[derive(Debug, Default, Deserialize)]
+struct Storage {
+    #[serde(default)]
+    esphome_version: Option<String>,
+    #[serde(default)]
+    address: Option<String>,
+    #[serde(default)]
+    board: Option<String>,
+    #[serde(default)]
+    framework: Option<String>,
+}
+
+/// The inventory of `devices`: those with a config in `config_dir` and
+/// those in `registry`, with what `data_dir` knows about them.
+pub(crate) fn rows(
+    devices: &[String],
+    config_dir: &Path,
+    data_dir: &Path,
+    store: &NotesStore,
+    registry: &Registry,
+) -> Vec<Row> {
+    let sizes = SizeHistory::load(&SizeHistory::path(data_dir)).unwrap_or_default();
+    let latency = LatencyHistory::load(&LatencyHistory::path(data_dir)).unwrap_or_default();
+    devices
+        .iter()
+        .map(|device| {
+            let config = super::config_path(config_dir, device);
+            let yaml = config
+                .as_ref()
+                .and_then(|path| std::fs::read_to_string(path).ok())
+                .unwrap_or_default();
+            let hardware = hardware(&yaml);
+            let storage = config
+                .as_ref()
+                .and_then(|path| path.file_name())
+                .map(|file| {
+                    let mut name = file.to_os_string();
+                    name.push(".json");
+                    config_dir.join(".esphome").join("storage").join(name)
+                })
+                .and_then(|path| std::fs::read_to_string(path).ok())
+                .and_then(|content| serde_json::from_str::<Storage>(&content).ok())
+                .unwrap_or_default();
+            let seen = latency
+                .rounds(device)
+                .iter()
+                .rev()
+                .find(|round| round.received > 0);
+            Row {
+                name: device.clone(),
+                platform: hardware.get("platform").cloned(),
+                board: hardware.get("board").cloned().or(storage.board),
+                framework: hardware.get("framework").cloned().or(storage.framework),
+                firmware: storage
+                    .esphome_version
+                    .or_else(|| sizes.samples(device).last()?.esphome.clone()),
+                last_seen: seen.map(|round| round.time.clone()),
+                address: seen
+                    .and_then(|round| round.address.clone())
+                    .or_else(|| registry.get(device).map(|d| d.address.clone()))
+                    .or(storage.address)
+                    .or_else(|| super::discovery::config_address(&yaml)),
+                tags: store.get(device).tags.into_iter().collect(),
+            }
+        })
+        .collect()
+}
+
+/// The platform block of a config (`esp32:`), with its `board:` and
+/// `framework: type:`. ESP8266 builds are always Arduino.
+fn hardware(yaml: &str) -> BTreeMap<&'static str, String> {
+    let mut found = BTreeMap::new();
+    let mut in_platform = false;
+    for line in yaml.lines().map(strip_comment) {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        if !line.starts_with(char::is_whitespace) {
+            let block = trimmed.strip_suffix(':').unwrap_or("");
+            in_platform = super::template::PLATFORMS.contains(&block);
+            if in_platform {
+                found.insert("platform", block.to_string());
+                if block == "esp8266" {
+                    found.insert("framework", "arduino".to_string());
+                }
+            }
+            continue;
+        }
+        let Some((key, value)) = trimmed.split_once(':').filter(|_| in_platform) else {
+            continue;
+        };
+        let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+        if value.is_empty() {
+            continue;
+        }
+        match key.trim() {
+            "board" => {
+                found.insert("board", value.to_string());
+            }
+            // `framework: type:`, the only key of `framework:` with a
+            // scalar named `type`.
+            "type" => {
+                found.insert("framework", value.to_string());
+            }
+            _ => {}
+        }
+    }
+    found
+}
+
+/// `rows` as CSV, a header line first and tags separated by `;`.
+pub(crate) fn csv(rows: &[Row]) -> String {
+    let mut out = String::from("name,platform,board,framework,firmware,last_seen,address,tags\n");
+    for row in rows {
+        let fields = cells(row, ";");
+        let quoted: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        out.push_str(&quoted.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+/// `rows` as a Markdown table.
+pub(crate) fn markdown(rows: &[Row]) -> String {
+    let mut out = String::from(
+        "| Name | Platform | Board | Framework | Firmware | Last seen | Address | Tags |\n\
+         | --- | --- | --- | --- | --- | --- | --- | --- |\n",
+    );
+    for row in rows {
+        let fields: Vec<String> = cells(row, ", ")
+            .iter()
+            .map(|field| field.replace('|', "\\|"))
+            .collect();
+        out.push_str(&format!("| {} |\n", fields.join(" | ")));
+    }
+    out
+}
+
+fn cells(row: &Row, tag_separator: &str) -> [String; 8] {
+    let text = |value: &Option<String>| value.clone().unwrap_or_default();
+    [
+        row.name.clone(),
+        text(&row.platform),
+        text(&row.board),
+        text(&row.framework),
+        text(&row.firmware),
+        text(&row.last_seen),
+        text(&row.address),
+        row.tags.join(tag_separator),
+    ]
+}
+
+/// Quoted when it holds a comma, quote or line break, quotes doubled.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::devices::discovery::Round;
+    use std::fs;
+
+    #[test]
+    fn lists_each_device_from_its_config_storage_and_history() {
+        let dir = crate::util::unique_temp_dir("inventory");
+        let config_dir = dir.join("config");
+        let storage = config_dir.join(".esphome").join("storage");
+        fs::create_dir_all(&storage).unwrap();
+        fs::write(
+            config_dir.join("porch.yaml"),
+            "esphome:\n  name: porch\nesp32:\n  board: esp32-c3-devkitm-1\n  framework:\n    type: esp-idf\n",
+        )
+        .unwrap();
+        fs::write(
+            storage.join("porch.yaml.json"),
+            r#"{"esphome_version": "2026.9.1", "address": "porch.local"}"#,
+        )
+        .unwrap();
+        fs::write(
+            config_dir.join("plug.yaml"),
+            "esphome:\n  name: plug\nesp8266:\n  board: esp01_1m\n",
+        )
+        .unwrap();
+        let round = |time: &str, received| Round {
+            time: time.into(),
+            sent: 4,
+            received,
+            avg_ms: Some(12),
+            max_ms: Some(20),
+            address: Some("192.168.1.40".into()),
+        };
+        let latency = LatencyHistory::path(&dir);
+        LatencyHistory::record(
+            &latency,
+            vec![("porch".into(), round("2026-10-15T08:00:00Z", 4))],
+        )
+        .unwrap();
+        LatencyHistory::record(
+            &latency,
+            vec![("porch".into(), round("2026-10-15T08:05:00Z", 0))],
+        )
+        .unwrap();
+        let mut store = NotesStore::default();
+        store.add_tags("plug", ["attic", "upstairs"]);
+
+        let devices = ["plug".to_string(), "porch".to_string()];
+        let rows = rows(&devices, &config_dir, &dir, &store, &Registry::default());
+        assert_eq!(
+            rows[1],
+            Row {
+                name: "porch".into(),
+                platform: Some("esp32".into()),
+                board: Some("esp32-c3-devkitm-1".into()),
+                framework: Some("esp-idf".into()),
+                firmware: Some("2026.9.1".into()),
+                last_seen: Some("2026-10-15T08:00:00Z".into()),
+                address: Some("192.168.1.40".into()),
+                tags: Vec::new(),
+            }
+        );
+        assert_eq!(rows[0].framework.as_deref(), Some("arduino"));
+        assert_eq!(rows[0].address.as_deref(), Some("plug.local"));
+        let csv = csv(&rows);
+        assert!(csv.contains("\nplug,esp8266,esp01_1m,arduino,,,plug.local,attic;upstairs\n"));
+        assert_eq!(csv_field("say \"hi\", twice"), "\"say \"\"hi\"\", twice\"");
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
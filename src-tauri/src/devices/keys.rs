@@ -0,0 +1,161 @@
+//! Devices' native API encryption keys, kept in the OS keychain.
+//!
+//! A key stored here is used whenever this app talks to the device's API
+//! itself (`device recover`), so it needn't be typed each time. The keys
+//! usually already sit in the configs (`api: encryption: key:`, literally or
+//! as a `!secret`); `device import-keys` copies them over, and a key for a
+//! device whose config is gone can be stored by hand. Entries are filed
+//! under [`SERVICE`] by device name.
+
+use anyhow::{bail, Result};
+use std::path::Path;
+
+use super::registry::{self, Registry};
+use super::sources::strip_comment;
+
+/// The keychain service the keys are filed under.
+const SERVICE: &str = "io.esphome.builder.api-key";
+
+/// Length of a key: 32 bytes, base64 with padding.
+const KEY_LEN: usize = 44;
+
+/// Store `key` for `device`.
+pub(crate) fn store(device: &str, key: &str) -> Result<()> {
+    let key = key.trim();
+    let base64 = |c: char| c.is_ascii_alphanumeric() || c == '+' || c == '/';
+    if key.len() != KEY_LEN || !key.ends_with('=') || !key.trim_end_matches('=').chars().all(base64)
+    {
+        bail!("that isn't an API encryption key: those are 44 characters of base64 ending in =");
+    }
+    crate::platform::keychain::store(SERVICE, device, key)
+}
+
+/// The key stored for `device`, if any.
+pub(crate) fn load(device: &str) -> Result<Option<String>> {
+    crate::platform::keychain::load(SERVICE, device)
+}
+
+/// Remove `device`'s key.
+pub(crate) fn delete(device: &str) -> Result<()> {
+    crate::platform::keychain::delete(SERVICE, device)
+}
+
+/// Where a config keeps its key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum KeyRef {
+    Literal(String),
+    Secret(String),
+}
+
+/// `key:` under `api:` → `encryption:`.
+fn config_key(yaml: &str) -> Option<KeyRef> {
+    let mut in_api = false;
+    let mut encryption_indent: Option<usize> = None;
+    for line in yaml.lines().map(strip_comment) {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let indent = line.len() - line.trim_start().len();
+        if indent == 0 {
+            in_api = trimmed == "api:";
+            encryption_indent = None;
+            continue;
+        }
+        if !in_api {
+            continue;
+        }
+        match encryption_indent {
+            Some(encryption) if indent > encryption => {
+                if let Some(value) = trimmed.strip_prefix("key:") {
+                    let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+                    return match value.strip_prefix("!secret") {
+                        Some(name) => Some(KeyRef::Secret(name.trim().to_string())),
+                        None => (!value.is_empty()).then(|| KeyRef::Literal(value.to_string())),
+                    };
+                }
+            }
+            _ => encryption_indent = (trimmed == "encryption:").then_some(indent),
+        }
+    }
+    None
+}
+
+/// The key `device`'s config, or failing that its `device add` entry, names.
+/// `Ok(None)` when neither has one.
+pub(crate) fn find(config_dir: &Path, registry: &Registry, device: &str) -> Result<Option<String>> {
+    let from_config = super::config_path(config_dir, device)
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|yaml| config_key(&yaml));
+    let from_registry = || {
+        let secret = registry.get(device)?.key_secret.clone()?;
+        Some(KeyRef::Secret(secret))
+    };
+    match from_config.or_else(from_registry) {
+        Some(KeyRef::Literal(key)) => Ok(Some(key)),
+        Some(KeyRef::Secret(name)) => registry::secret(config_dir, &name).map(Some),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::devices::registry::ManualDevice;
+    use crate::util::unique_temp_dir;
+
+    #[test]
+    fn reads_the_key_from_the_config() {
+        let yaml = "api:\n  reboot_timeout: 0s\n  encryption:\n    key: \"a2V5\" # new\n";
+        assert_eq!(config_key(yaml), Some(KeyRef::Literal("a2V5".into())));
+        assert_eq!(
+            config_key("api:\n  encryption:\n    key: !secret porch_key\n"),
+            Some(KeyRef::Secret("porch_key".into()))
+        );
+        assert_eq!(config_key("api:\n  password: x\n"), None);
+        assert_eq!(config_key("mqtt:\n  encryption:\n    key: x\n"), None);
+    }
+
+    #[test]
+    fn finds_keys_through_secrets_and_the_registry() {
+        let dir = unique_temp_dir("keys");
+        std::fs::write(
+            dir.join("secrets.yaml"),
+            "porch_key: cG9yY2g=\nbarn_key: YmFybg==\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("porch.yaml"),
+            "api:\n  encryption:\n    key: !secret porch_key\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("shed.yaml"), "api:\n").unwrap();
+        let mut registry = Registry::default();
+        registry
+            .add(
+                "barn",
+                ManualDevice {
+                    address: "10.20.0.15".into(),
+                    key_secret: Some("barn_key".into()),
+                },
+            )
+            .unwrap();
+
+        assert_eq!(
+            find(&dir, &registry, "porch").unwrap().as_deref(),
+            Some("cG9yY2g=")
+        );
+        assert_eq!(
+            find(&dir, &registry, "barn").unwrap().as_deref(),
+            Some("YmFybg==")
+        );
+        assert_eq!(find(&dir, &registry, "shed").unwrap(), None);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn refuses_what_isnt_a_key() {
+        assert!(store("porch", "hunter2").is_err());
+        assert!(store("porch", &format!("{}=", "a".repeat(42))).is_err());
+    }
+}
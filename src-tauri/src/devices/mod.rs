@@ -0,0 +1,291 @@
+//! Devices and the user's notes about them.
+//!
+//! A device is a YAML config at the top of the config directory, named by its
+//! file stem, the same set the dashboard lists. Notes and tags ("attic",
+//! "rev B", "do not auto-update") are the user's own bookkeeping: they live in
+//! `device_notes.json` in the app data directory rather than in the configs, so
+//! they never end up in a compile or a shared repository. Named groups of
+//! devices ("outdoor sensors", "test bench") live in the same file and are
+//! what batch actions ([`batch`]) target.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use crate::util::ConfigStore;
+
+pub(crate) mod api;
+pub(crate) mod archive;
+pub(crate) mod batch;
+pub(crate) mod diff;
+pub(crate) mod discovery;
+pub(crate) mod duplicates;
+pub(crate) mod editor;
+pub(crate) mod hotspots;
+pub(crate) mod inventory;
+pub(crate) mod keys;
+pub(crate) mod ota;
+pub(crate) mod packages;
+pub(crate) mod partitions;
+pub(crate) mod pins;
+pub(crate) mod recover;
+pub(crate) mod registry;
+pub(crate) mod rename;
+pub(crate) mod search;
+pub(crate) mod secrets;
+pub(crate) mod serial;
+pub(crate) mod signing;
+pub(crate) mod sizes;
+pub(crate) mod snippet;
+pub(crate) mod sources;
+pub(crate) mod template;
+pub(crate) mod undo;
+pub(crate) mod vscode;
+
+/// File name of the notes store inside the data directory.
+pub(crate) const NOTES_FILE_NAME: &str = "device_notes.json";
+
+/// Config-dir YAML files that aren't devices.
+const NON_DEVICE_CONFIGS: &[&str] = &["secrets"];
+
+/// Device names, sorted: every `*.yaml`/`*.yml` at the top of `config_dir`,
+/// minus `secrets.yaml` and hidden files (the same ones the dashboard skips).
+pub(crate) fn list_devices(config_dir: &Path) -> Result<Vec<String>> {
+    let entries = std::fs::read_dir(config_dir)
+        .with_context(|| format!("Failed to read config directory {config_dir:?}"))?;
+    let mut names: Vec<String> = entries
+        .flatten()
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_file()))
+        .filter_map(|e| device_name(&e.path()))
+        .collect();
+    names.sort();
+    names.dedup();
+    Ok(names)
+}
+
+/// The config file for `device`, whichever of `.yaml`/`.yml` it uses.
+pub(crate) fn config_path(config_dir: &Path, device: &str) -> Option<PathBuf> {
+    ["yaml", "yml"]
+        .into_iter()
+        .map(|ext| config_dir.join(format!("{device}.{ext}")))
+        .find(|p| p.is_file())
+}
+
+fn device_name(path: &Path) -> Option<String> {
+    let ext = path.extension()?.to_str()?;
+    if !ext.eq_ignore_ascii_case("yaml") && !ext.eq_ignore_ascii_case("yml") {
+        return None;
+    }
+    let stem = path.file_stem()?.to_str()?;
+    (!stem.starts_with('.') && !NON_DEVICE_CONFIGS.contains(&stem)).then(|| stem.to_string())
+}
+
+/// Notes and tags for one device.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct DeviceNotes {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub tags: BTreeSet<String>,
+}
+
+impl DeviceNotes {
+    fn is_empty(&self) -> bool {
+        self.note.is_none() && self.tags.is_empty()
+    }
+}
+
+/// The notes store, keyed by device name, plus the device groups. Entries
+/// for devices whose config has since gone are kept: a renamed-back or
+/// restored config gets its notes and group memberships back.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct NotesStore {
+    #[serde(default)]
+    devices: BTreeMap<String, DeviceNotes>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    groups: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl ConfigStore for NotesStore {
+    const FILE_NAME: &'static str = NOTES_FILE_NAME;
+    const WHAT: &'static str = "device notes";
+}
+
+impl NotesStore {
+    /// Notes for `device` (empty if it has none).
+    pub(crate) fn get(&self, device: &str) -> DeviceNotes {
+        self.devices.get(device).cloned().unwrap_or_default()
+    }
+
+    /// Set or, with a blank `note`, clear the note for `device`.
+    pub(crate) fn set_note(&mut self, device: &str, note: &str) {
+        let note = note.trim();
+        self.update(device, |n| {
+            n.note = (!note.is_empty()).then(|| note.to_string());
+        });
+    }
+
+    /// Add tags to `device`. Tags are trimmed and lowercased so `Attic` and
+    /// `attic ` are the same tag; blank ones are ignored.
+    pub(crate) fn add_tags<'a>(&mut self, device: &str, tags: impl IntoIterator<Item = &'a str>) {
+        self.update(device, |n| {
+            n.tags.extend(tags.into_iter().filter_map(normalize_tag))
+        });
+    }
+
+    /// Remove tags from `device`.
+    pub(crate) fn remove_tags<'a>(
+        &mut self,
+        device: &str,
+        tags: impl IntoIterator<Item = &'a str>,
+    ) {
+        self.update(device, |n| {
+            for tag in tags.into_iter().filter_map(normalize_tag) {
+                n.tags.remove(&tag);
+            }
+        });
+    }
+
+    /// Every group and its members.
+    pub(crate) fn groups(&self) -> &BTreeMap<String, BTreeSet<String>> {
+        &self.groups
+    }
+
+    /// Members of `group`, or `None` if there is no such group.
+    pub(crate) fn group(&self, group: &str) -> Option<&BTreeSet<String>> {
+        self.groups.get(group.trim())
+    }
+
+    /// Add devices to `group`, creating it if needed.
+    pub(crate) fn add_to_group<'a>(
+        &mut self,
+        group: &str,
+        devices: impl IntoIterator<Item = &'a str>,
+    ) {
+        let group = group.trim();
+        if group.is_empty() {
+            return;
+        }
+        self.groups
+            .entry(group.to_string())
+            .or_default()
+            .extend(devices.into_iter().map(str::to_string));
+    }
+
+    /// Remove devices from `group`; a group left empty is deleted.
+    pub(crate) fn remove_from_group<'a>(
+        &mut self,
+        group: &str,
+        devices: impl IntoIterator<Item = &'a str>,
+    ) {
+        let group = group.trim();
+        if let Some(members) = self.groups.get_mut(group) {
+            for device in devices {
+                members.remove(device);
+            }
+            if members.is_empty() {
+                self.groups.remove(group);
+            }
+        }
+    }
+
+    /// Move `from`'s notes and group memberships to `to`, for a device
+    /// renamed.
+    pub(crate) fn rename(&mut self, from: &str, to: &str) {
+        if let Some(notes) = self.devices.remove(from) {
+            self.devices.insert(to.to_string(), notes);
+        }
+        for members in self.groups.values_mut() {
+            if members.remove(from) {
+                members.insert(to.to_string());
+            }
+        }
+    }
+
+    /// Apply `f` to `device`'s entry, dropping the entry once it is empty so
+    /// the file doesn't accumulate blank records.
+    fn update(&mut self, device: &str, f: impl FnOnce(&mut DeviceNotes)) {
+        let entry = self.devices.entry(device.to_string()).or_default();
+        f(entry);
+        if entry.is_empty() {
+            self.devices.remove(device);
+        }
+    }
+}
+
+fn normalize_tag(tag: &str) -> Option<String> {
+    let tag = tag.trim().to_lowercase();
+    (!tag.is_empty()).then_some(tag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::unique_temp_dir;
+    use std::fs;
+
+    #[test]
+    fn lists_yaml_configs_but_not_secrets_or_hidden_files() {
+        let dir = unique_temp_dir("devices");
+        for name in [
+            "kitchen.yaml",
+            "garage.yml",
+            "secrets.yaml",
+            ".hidden.yaml",
+            "notes.txt",
+        ] {
+            fs::write(dir.join(name), "").unwrap();
+        }
+        fs::create_dir(dir.join("packages.yaml")).unwrap();
+
+        assert_eq!(list_devices(&dir).unwrap(), vec!["garage", "kitchen"]);
+        assert_eq!(config_path(&dir, "garage"), Some(dir.join("garage.yml")));
+        assert_eq!(config_path(&dir, "attic"), None);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn notes_and_tags_round_trip_and_empty_entries_are_dropped() {
+        let dir = unique_temp_dir("device_notes");
+        let path = NotesStore::path(&dir);
+        assert_eq!(NotesStore::load(&path).unwrap(), NotesStore::default());
+
+        let mut store = NotesStore::default();
+        store.set_note("kitchen", " Above the fridge ");
+        store.add_tags("kitchen", ["Rev B", " ", "attic"]);
+        store.remove_tags("kitchen", ["ATTIC"]);
+        store.save(&path).unwrap();
+
+        let loaded = NotesStore::load(&path).unwrap();
+        let kitchen = loaded.get("kitchen");
+        assert_eq!(kitchen.note.as_deref(), Some("Above the fridge"));
+        assert_eq!(kitchen.tags.into_iter().collect::<Vec<_>>(), vec!["rev b"]);
+
+        store.set_note("kitchen", "");
+        store.remove_tags("kitchen", ["rev b"]);
+        assert_eq!(store, NotesStore::default());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn groups_are_created_on_add_and_dropped_when_emptied() {
+        let mut store = NotesStore::default();
+        store.add_to_group(" outdoor sensors ", ["porch", "shed"]);
+        assert_eq!(store.group("outdoor sensors").map(|m| m.len()), Some(2));
+        store.remove_from_group("outdoor sensors", ["porch", "shed"]);
+        assert!(store.group("outdoor sensors").is_none());
+        assert_eq!(store, NotesStore::default());
+    }
+
+    #[test]
+    fn corrupt_store_is_an_error_not_empty() {
+        let dir = unique_temp_dir("device_notes_corrupt");
+        let path = NotesStore::path(&dir);
+        fs::write(&path, "{ not json").unwrap();
+        assert!(NotesStore::load(&path).is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
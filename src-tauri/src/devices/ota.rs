@@ -0,0 +1,224 @@
+//! How reliably over-the-air uploads land, and where they don't.
+//!
+//! Each batch upload that gets as far as flashing (its firmware built) is
+//! kept in `ota.json` in the app data: when, whether it landed, and the
+//! address ESPHome connected to. [`report`] sums the attempts up per device
+//! and, to point at a weak spot in the network rather than a bad device,
+//! per IPv4 /24 subnet and per tag, each with the probe loss the latency
+//! monitor saw there (see [`discovery`](super::discovery)). The app can't see
+//! which access point a device is on, so tags (`garage`, `upstairs`) stand in
+//! for where it is. Uploads started from the dashboard aren't seen.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::Ipv4Addr;
+use std::path::Path;
+
+use super::discovery::{summarize, LatencyHistory, Round};
+use super::NotesStore;
+use crate::util::ConfigStore;
+
+/// File name of the history inside the data directory.
+pub(crate) const OTA_FILE_NAME: &str = "ota.json";
+
+/// Attempts kept per device.
+const HISTORY_LEN: usize = 50;
+
+/// One upload that reached the flashing step.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Attempt {
+    /// When it ran, `YYYY-MM-DDTHH:MM:SSZ`.
+    pub time: String,
+    pub ok: bool,
+    /// The address ESPHome connected to, if it said.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+}
+
+impl Attempt {
+    /// The attempt an upload's output describes, or `None` if it failed
+    /// before flashing (a config or compile error isn't the network's).
+    pub(crate) fn parse(output: &str, ok: bool, time: String) -> Option<Self> {
+        if !ok && !output.contains("Successfully compiled program") {
+            return None;
+        }
+        let address = output
+            .lines()
+            .filter_map(|line| line.split_once("Connecting to ").map(|(_, rest)| rest))
+            .filter_map(|rest| rest.split_whitespace().next())
+            .next_back()
+            .map(str::to_string);
+        Some(Self { time, ok, address })
+    }
+}
+
+/// Every device's recorded attempts, oldest first.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct OtaHistory {
+    #[serde(default)]
+    devices: BTreeMap<String, Vec<Attempt>>,
+}
+
+impl ConfigStore for OtaHistory {
+    const FILE_NAME: &'static str = OTA_FILE_NAME;
+    const WHAT: &'static str = "OTA history";
+}
+
+impl OtaHistory {
+    /// Add an attempt on `device` to the history at `path`, dropping the
+    /// oldest beyond [`HISTORY_LEN`].
+    pub(crate) fn record(path: &Path, device: &str, attempt: Attempt) -> Result<()> {
+        let mut history = Self::load(path)?;
+        let attempts = history.devices.entry(device.to_string()).or_default();
+        attempts.push(attempt);
+        let excess = attempts.len().saturating_sub(HISTORY_LEN);
+        attempts.drain(..excess);
+        history.save(path)
+    }
+
+    /// `device`'s attempts, oldest first.
+    pub(crate) fn attempts(&self, device: &str) -> &[Attempt] {
+        self.devices.get(device).map_or(&[], Vec::as_slice)
+    }
+}
+
+/// Attempts on a device or a group of them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Rate {
+    /// The device, subnet (`192.168.1.0/24`) or tag.
+    pub label: String,
+    pub devices: usize,
+    pub attempts: usize,
+    pub failures: usize,
+    /// Lost probes there over the recorded rounds, in percent, if any were.
+    pub loss: Option<u32>,
+}
+
+impl Rate {
+    /// Failed attempts, in percent.
+    pub(crate) fn failure_percent(&self) -> usize {
+        self.failures * 100 / self.attempts.max(1)
+    }
+}
+
+/// The rates per device, subnet and tag, worst first.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct Report {
+    pub devices: Vec<Rate>,
+    pub subnets: Vec<Rate>,
+    pub tags: Vec<Rate>,
+}
+
+/// Sum up `ota`, with the loss from `latency` and the tags in `store`.
+pub(crate) fn report(ota: &OtaHistory, latency: &LatencyHistory, store: &NotesStore) -> Report {
+    let mut subnets: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+    let mut tags: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+    for (device, attempts) in &ota.devices {
+        let address = latency
+            .rounds(device)
+            .iter()
+            .rev()
+            .find_map(|round| round.address.as_deref())
+            .or_else(|| attempts.iter().rev().find_map(|a| a.address.as_deref()));
+        if let Some(subnet) = address.and_then(subnet) {
+            subnets.entry(subnet).or_default().push(device);
+        }
+        for tag in store.get(device).tags {
+            tags.entry(tag).or_default().push(device);
+        }
+    }
+    let rate = |label: String, devices: &[&str]| {
+        let attempts: Vec<&Attempt> = devices
+            .iter()
+            .flat_map(|device| ota.attempts(device))
+            .collect();
+        let rounds: Vec<Round> = devices
+            .iter()
+            .flat_map(|device| latency.rounds(device).iter().cloned())
+            .collect();
+        Rate {
+            label,
+            devices: devices.len(),
+            attempts: attempts.len(),
+            failures: attempts.iter().filter(|a| !a.ok).count(),
+            loss: (!rounds.is_empty()).then(|| summarize(&rounds).loss),
+        }
+    };
+    let ranked = |mut rates: Vec<Rate>| {
+        rates.sort_by_key(|r| {
+            (
+                std::cmp::Reverse(r.failure_percent()),
+                std::cmp::Reverse(r.attempts),
+            )
+        });
+        rates
+    };
+    Report {
+        devices: ranked(
+            ota.devices
+                .keys()
+                .map(|device| rate(device.clone(), &[device.as_str()]))
+                .collect(),
+        ),
+        subnets: ranked(subnets.into_iter().map(|(s, d)| rate(s, &d)).collect()),
+        tags: ranked(tags.into_iter().map(|(t, d)| rate(t, &d)).collect()),
+    }
+}
+
+/// The /24 an IPv4 address is in, as `192.168.1.0/24`.
+fn subnet(address: &str) -> Option<String> {
+    let ip: Ipv4Addr = address.parse().ok()?;
+    let [a, b, c, _] = ip.octets();
+    Some(format!("{a}.{b}.{c}.0/24"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranks_devices_subnets_and_tags_by_failed_uploads() {
+        let compiled = "INFO Successfully compiled program.\n\
+                        INFO Connecting to 192.168.2.17 port 3232...\n";
+        assert_eq!(
+            Attempt::parse("ERROR Error while reading config", false, "t".into()),
+            None
+        );
+        let failed = Attempt::parse(compiled, false, "t".into()).unwrap();
+        assert_eq!(failed.address.as_deref(), Some("192.168.2.17"));
+
+        let dir = crate::util::unique_temp_dir("ota");
+        let path = OtaHistory::path(&dir);
+        let landed = |address: &str| Attempt {
+            time: "t".into(),
+            ok: true,
+            address: Some(address.into()),
+        };
+        OtaHistory::record(&path, "garage-door", failed.clone()).unwrap();
+        OtaHistory::record(&path, "garage-door", landed("192.168.2.17")).unwrap();
+        OtaHistory::record(&path, "garage-light", failed).unwrap();
+        OtaHistory::record(&path, "kitchen", landed("192.168.1.30")).unwrap();
+        let ota = OtaHistory::load(&path).unwrap();
+        let mut store = NotesStore::default();
+        store.add_tags("garage-door", ["garage"]);
+        store.add_tags("garage-light", ["garage"]);
+
+        let report = report(&ota, &LatencyHistory::default(), &store);
+        let labels =
+            |rates: &[Rate]| -> Vec<String> { rates.iter().map(|r| r.label.clone()).collect() };
+        assert_eq!(
+            labels(&report.devices),
+            ["garage-light", "garage-door", "kitchen"]
+        );
+        assert_eq!(
+            labels(&report.subnets),
+            ["192.168.2.0/24", "192.168.1.0/24"]
+        );
+        assert_eq!(report.subnets[0].devices, 2);
+        assert_eq!(report.subnets[0].failure_percent(), 66);
+        assert_eq!(report.tags[0].label, "garage");
+        assert_eq!(report.tags[0].loss, None);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
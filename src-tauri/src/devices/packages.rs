@@ -0,0 +1,394 @@
+//! The shared files each device's config pulls in, by `!include` (package
+//! and substitution files alike) or under `packages:`, local or remote, and
+//! the other way round: the devices an edit to a shared file reaches.
+//!
+//! Configs are read line by line rather than parsed, like [`super::sources`].
+//! Local files are followed into what they include in turn; remote packages
+//! aren't fetched, so what they include isn't seen, and neither are
+//! `!include_dir_*` directories.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Component, Path};
+
+use super::sources::strip_comment;
+
+/// How many includes deep local files are followed.
+const MAX_DEPTH: usize = 8;
+
+/// What a shared file is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Kind {
+    /// A file on disk.
+    Local,
+    /// A package from a git repository.
+    Remote,
+    /// A local file that isn't there.
+    Missing,
+}
+
+/// A shared file and the devices that use it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct Package {
+    /// Relative to the config directory with `/` separators for a local file,
+    /// as written (`github://…`, or the URL and file) for a remote one.
+    pub name: String,
+    pub kind: Kind,
+    /// Sorted.
+    pub devices: Vec<String>,
+}
+
+/// A device and the shared files it uses, directly or through another.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct DevicePackages {
+    pub device: String,
+    /// Sorted.
+    pub packages: Vec<String>,
+}
+
+/// Every device's shared files, both ways round.
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+pub(crate) struct Graph {
+    /// The most widely used first.
+    pub packages: Vec<Package>,
+    /// By device name; devices without shared files too.
+    pub devices: Vec<DevicePackages>,
+}
+
+/// The shared files of the devices in `config_dir`.
+pub(crate) fn graph(config_dir: &Path) -> Result<Graph> {
+    let mut graph = Graph::default();
+    let mut users: BTreeMap<String, (Kind, BTreeSet<String>)> = BTreeMap::new();
+    for device in super::list_devices(config_dir)? {
+        let Some(path) = super::config_path(config_dir, &device) else {
+            continue;
+        };
+        let mut found = BTreeMap::new();
+        walk(config_dir, &path, 0, &mut found);
+        // A config that a file it includes includes back isn't its own package.
+        found.remove(&display(config_dir, &path));
+        for (name, kind) in &found {
+            users
+                .entry(name.clone())
+                .or_insert_with(|| (*kind, BTreeSet::new()))
+                .1
+                .insert(device.clone());
+        }
+        graph.devices.push(DevicePackages {
+            device,
+            packages: found.into_keys().collect(),
+        });
+    }
+    graph.packages = users
+        .into_iter()
+        .map(|(name, (kind, devices))| Package {
+            name,
+            kind,
+            devices: devices.into_iter().collect(),
+        })
+        .collect();
+    // Stable, so equally used ones stay in name order.
+    graph
+        .packages
+        .sort_by_key(|p| std::cmp::Reverse(p.devices.len()));
+    Ok(graph)
+}
+
+/// Add what `file` includes to `found`, following local files.
+fn walk(config_dir: &Path, file: &Path, depth: usize, found: &mut BTreeMap<String, Kind>) {
+    let Ok(text) = std::fs::read_to_string(file) else {
+        return;
+    };
+    let dir = file.parent().unwrap_or(config_dir);
+    for reference in references(&text) {
+        match reference {
+            Reference::Remote(name) => {
+                found.insert(name, Kind::Remote);
+            }
+            Reference::Local(written) => {
+                let path = dir.join(&written);
+                let name = display(config_dir, &path);
+                if found.contains_key(&name) {
+                    continue;
+                }
+                let exists = path.is_file();
+                found.insert(name, if exists { Kind::Local } else { Kind::Missing });
+                if exists && depth < MAX_DEPTH {
+                    walk(config_dir, &path, depth + 1, found);
+                }
+            }
+        }
+    }
+}
+
+/// `path` relative to `config_dir` with `/` separators, `..` resolved, or
+/// in full when it is outside.
+fn display(config_dir: &Path, path: &Path) -> String {
+    let outside = || path.display().to_string();
+    let Ok(rel) = path.strip_prefix(config_dir) else {
+        return outside();
+    };
+    let mut parts = Vec::new();
+    for component in rel.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if parts.pop().is_none() {
+                    return outside();
+                }
+            }
+            other => parts.push(other.as_os_str().to_string_lossy()),
+        }
+    }
+    parts.join("/")
+}
+
+/// A file a config pulls in.
+#[derive(Debug, PartialEq, Eq)]
+enum Reference {
+    /// As written, relative to the including file.
+    Local(String),
+    Remote(String),
+}
+
+fn unquote(value: &str) -> &str {
+    value.trim().trim_matches(['"', '\''])
+}
+
+fn indent(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// A line's `key` and the value after it, or `None` and the whole line.
+fn key_value(content: &str) -> (Option<&str>, &str) {
+    let split = match content.strip_suffix(':') {
+        Some(key) => Some((key, "")),
+        None => content.split_once(": "),
+    };
+    match split {
+        Some((key, value)) if !key.contains(' ') && !key.starts_with(['"', '\'']) => {
+            (Some(key), value.trim())
+        }
+        _ => (None, content),
+    }
+}
+
+/// The lines after `lines[0]` indented deeper than `than`.
+fn under<'a>(lines: &[&'a str], than: usize) -> Vec<&'a str> {
+    lines
+        .iter()
+        .skip(1)
+        .copied()
+        .filter(|l| !l.trim().is_empty())
+        .take_while(|l| indent(l) > than)
+        .collect()
+}
+
+/// The files `text` includes and the packages it pulls in.
+fn references(text: &str) -> Vec<Reference> {
+    let lines: Vec<&str> = text.lines().map(|l| strip_comment(l).trim_end()).collect();
+    let mut found = Vec::new();
+    let mut in_packages = false;
+    // Column of the `packages:` block's entries, once seen.
+    let mut entry_at = None;
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let indent = indent(line);
+        if indent == 0 {
+            in_packages = trimmed.starts_with("packages:");
+            entry_at = None;
+        }
+        if let Some(at) = trimmed.find("!include") {
+            let rest = &trimmed[at + "!include".len()..];
+            // Not `!include_dir_named` and the like.
+            if rest.is_empty() || rest.starts_with(' ') {
+                let file = match rest.trim() {
+                    // The long form, `file:` and `vars:` below or in braces.
+                    "" => under(&lines[i..], indent).into_iter().find_map(|l| {
+                        match key_value(l.trim_start()) {
+                            (Some("file"), value) => Some(value),
+                            _ => None,
+                        }
+                    }),
+                    rest if rest.starts_with('{') => rest
+                        .trim_matches(['{', '}'])
+                        .split(',')
+                        .find_map(|field| match key_value(field.trim()) {
+                            (Some("file"), value) => Some(value),
+                            _ => None,
+                        }),
+                    rest => Some(rest),
+                };
+                found.extend(
+                    file.map(unquote)
+                        .filter(|f| !f.is_empty())
+                        .map(|f| Reference::Local(f.to_string())),
+                );
+            }
+            continue;
+        }
+        if !in_packages || indent == 0 {
+            continue;
+        }
+        let content = trimmed.strip_prefix("- ").unwrap_or(trimmed).trim_start();
+        let column = indent + trimmed.len() - content.len();
+        if *entry_at.get_or_insert(column) != column {
+            continue;
+        }
+        match key_value(content) {
+            // A list entry that is the long form itself.
+            (Some("url"), _) => {
+                let mut fields = vec![content];
+                fields.extend(under(&lines[i..], indent));
+                found.extend(remote(&fields));
+            }
+            // `name: github://owner/repo/file.yaml@ref`, or a list entry.
+            (_, value) if value.contains("://") => {
+                found.push(Reference::Remote(unquote(value).to_string()));
+            }
+            // `name:` with `url:` and `files:` below.
+            (Some(_), "") => found.extend(remote(&under(&lines[i..], indent))),
+            _ => {}
+        }
+    }
+    found
+}
+
+/// The files of a long-form remote package, from its fields.
+fn remote(fields: &[&str]) -> Vec<Reference> {
+    let field = |name: &str| {
+        fields.iter().find_map(
+            |l| match key_value(l.trim_start().trim_start_matches("- ")) {
+                (Some(key), value) if key == name => Some(unquote(value)),
+                _ => None,
+            },
+        )
+    };
+    let Some(url) = field("url") else {
+        return Vec::new();
+    };
+    let url = url.trim_end_matches('/').trim_end_matches(".git");
+    let at = field("ref").map(|r| format!("@{r}")).unwrap_or_default();
+    let Some(files_at) = fields
+        .iter()
+        .position(|l| key_value(l.trim_start()).0 == Some("files"))
+    else {
+        return Vec::new();
+    };
+    let files: Vec<&str> = match key_value(fields[files_at].trim_start()).1 {
+        "" => under(&fields[files_at..], indent(fields[files_at]))
+            .into_iter()
+            .filter_map(|l| l.trim_start().strip_prefix("- "))
+            .filter_map(|entry| match key_value(entry.trim_start()) {
+                (Some("path"), value) => Some(value),
+                (None, value) => Some(value),
+                _ => None,
+            })
+            .collect(),
+        inline => inline.trim_matches(['[', ']']).split(',').collect(),
+    };
+    files
+        .into_iter()
+        .map(unquote)
+        .filter(|f| !f.is_empty())
+        .map(|f| Reference::Remote(format!("{url}/{f}{at}")))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_includes_and_packages_in_every_form() {
+        let config = "\
+substitutions: !include common/substitutions.yaml
+packages:
+  base: !include common/base.yaml
+  web: github://esphome/firmware/esphome-web/esp32.yaml@v2  # the stock one
+  extra:
+    url: https://github.com/someone/configs.git
+    ref: main
+    files:
+      - sensors.yaml
+      - path: lights.yaml
+        vars:
+          count: 2
+wifi: !include
+  file: wifi.yaml
+  vars:
+    ssid: home
+logger: !include { file: logger.yaml, vars: { level: DEBUG } }
+fonts: !include_dir_named fonts
+";
+        assert_eq!(
+            references(config),
+            [
+                Reference::Local("common/substitutions.yaml".into()),
+                Reference::Local("common/base.yaml".into()),
+                Reference::Remote("github://esphome/firmware/esphome-web/esp32.yaml@v2".into()),
+                Reference::Remote("https://github.com/someone/configs/sensors.yaml@main".into()),
+                Reference::Remote("https://github.com/someone/configs/lights.yaml@main".into()),
+                Reference::Local("wifi.yaml".into()),
+                Reference::Local("logger.yaml".into()),
+            ]
+        );
+        let list = "packages:\n  - url: https://example.com/repo\n    files: [a.yaml, \"b.yaml\"]\n  - github://someone/configs/c.yaml\n";
+        assert_eq!(
+            references(list),
+            [
+                Reference::Remote("https://example.com/repo/a.yaml".into()),
+                Reference::Remote("https://example.com/repo/b.yaml".into()),
+                Reference::Remote("github://someone/configs/c.yaml".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn shared_files_are_followed_and_counted_per_device() {
+        let dir = crate::util::unique_temp_dir("packages");
+        std::fs::create_dir_all(dir.join("common")).unwrap();
+        std::fs::write(
+            dir.join("porch.yaml"),
+            "packages:\n  base: !include common/base.yaml\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("attic.yaml"),
+            "packages:\n  base: !include common/base.yaml\n  gone: !include gone.yaml\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("garage.yaml"), "esphome:\n  name: garage\n").unwrap();
+        std::fs::write(
+            dir.join("common").join("base.yaml"),
+            "wifi: !include ../wifi.yaml\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("wifi.yaml"), "ssid: home\n").unwrap();
+
+        let graph = graph(&dir).unwrap();
+        let packages: Vec<(&str, Kind, usize)> = graph
+            .packages
+            .iter()
+            .map(|p| (p.name.as_str(), p.kind, p.devices.len()))
+            .collect();
+        assert_eq!(
+            packages,
+            [
+                ("common/base.yaml", Kind::Local, 2),
+                ("wifi.yaml", Kind::Local, 2),
+                ("gone.yaml", Kind::Missing, 1),
+            ]
+        );
+        assert_eq!(graph.packages[0].devices, ["attic", "porch"]);
+        let garage = graph.devices.iter().find(|d| d.device == "garage").unwrap();
+        assert!(garage.packages.is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,229 @@
+//! Partition schemes: which flash layout a device builds for, and what to do
+//! when its firmware outgrows the app partition.
+//!
+//! ESPHome lays out an ESP32's flash itself, from `flash_size:` (4MB unless
+//! set), with two app slots so OTA updates can swap between them; a config
+//! can name its own table instead (`esp32: partitions:`, or PlatformIO's
+//! `board_build.partitions`). PlatformIO refuses to produce a firmware bigger
+//! than the app slot, so a config that grew too big (OTA plus large fonts,
+//! say) fails its build and never reaches the device. Batch builds turn that
+//! failure into advice: a larger `flash_size:` if the board has the flash,
+//! else one of the standard Arduino tables with bigger app slots, which
+//! `device partitions --write` puts in the config directory.
+//!
+//! Configs are scanned line by line, like component sources
+//! ([`sources`](super::sources)): only the device's own file is seen.
+
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+
+use super::sources::strip_comment;
+
+/// The standard tables offered for a 4MB ESP32 that outgrew ESPHome's
+/// layout: name, what it trades, and the CSV.
+const STANDARD_TABLES: &[(&str, &str, &str)] = &[
+    (
+        "min_spiffs",
+        "1.9MB app slots, keeps OTA updates",
+        "# Name,   Type, SubType, Offset,  Size, Flags\n\
+         nvs,      data, nvs,     0x9000,  0x5000,\n\
+         otadata,  data, ota,     0xe000,  0x2000,\n\
+         app0,     app,  ota_0,   0x10000, 0x1E0000,\n\
+         app1,     app,  ota_1,   0x1F0000,0x1E0000,\n\
+         spiffs,   data, spiffs,  0x3D0000,0x20000,\n\
+         coredump, data, coredump,0x3F0000,0x10000,\n",
+    ),
+    (
+        "huge_app",
+        "one 3MB app slot, no OTA updates (serial flashing only)",
+        "# Name,   Type, SubType, Offset,  Size, Flags\n\
+         nvs,      data, nvs,     0x9000,  0x5000,\n\
+         otadata,  data, ota,     0xe000,  0x2000,\n\
+         app0,     app,  ota_0,   0x10000, 0x300000,\n\
+         spiffs,   data, spiffs,  0x310000,0xE0000,\n\
+         coredump, data, coredump,0x3F0000,0x10000,\n",
+    ),
+];
+
+/// The flash layout a config builds for.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Scheme {
+    /// The platform block, e.g. `esp32`.
+    pub platform: Option<String>,
+    pub board: Option<String>,
+    /// `flash_size:` under `esp32:`, when set.
+    pub flash_size: Option<String>,
+    /// A table of the config's own: `esp32: partitions:` or PlatformIO's
+    /// `board_build.partitions`.
+    pub table: Option<String>,
+}
+
+impl Scheme {
+    /// The scheme of the config text `yaml`.
+    pub(crate) fn scan(yaml: &str) -> Self {
+        let mut scheme = Self::default();
+        let mut block = "";
+        for line in yaml.lines().map(strip_comment) {
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                continue;
+            }
+            if !line.starts_with(char::is_whitespace) {
+                block = trimmed.strip_suffix(':').unwrap_or("");
+                if super::template::PLATFORMS.contains(&block) {
+                    scheme.platform = Some(block.to_string());
+                }
+                continue;
+            }
+            let Some((key, value)) = trimmed.split_once(':') else {
+                continue;
+            };
+            let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+            if value.is_empty() {
+                continue;
+            }
+            match (block, key.trim()) {
+                (_, "board_build.partitions") => scheme.table = Some(value.to_string()),
+                ("esphome", _) => {}
+                (_, "board") => scheme.board = Some(value.to_string()),
+                ("esp32", "flash_size") => scheme.flash_size = Some(value.to_string()),
+                ("esp32", "partitions") => scheme.table = Some(value.to_string()),
+                _ => {}
+            }
+        }
+        scheme
+    }
+
+    /// E.g. `esp32, board esp32dev, ESPHome's layout for 4MB flash`.
+    pub(crate) fn describe(&self) -> String {
+        let mut parts = vec![self
+            .platform
+            .as_deref()
+            .unwrap_or("unknown platform")
+            .to_string()];
+        if let Some(board) = &self.board {
+            parts.push(format!("board {board}"));
+        }
+        parts.push(match (&self.table, self.platform.as_deref()) {
+            (Some(table), _) => format!("partition table {table}"),
+            (None, Some("esp32")) => format!(
+                "ESPHome's layout for {} flash",
+                self.flash_size.as_deref().unwrap_or("4MB")
+            ),
+            (None, _) => "the board's default layout".to_string(),
+        });
+        parts.join(", ")
+    }
+
+    /// What to change when the firmware outgrew its app slot.
+    pub(crate) fn advice(&self, device: &str) -> String {
+        let shrink = "remove components, or shrink fonts and images";
+        if self.platform.as_deref() != Some("esp32") {
+            return format!("There is no larger standard app partition here: {shrink}.");
+        }
+        if self.table.is_some() {
+            return format!("Give it a larger app partition in its own table, or {shrink}.");
+        }
+        if self.flash_size.as_deref().is_some_and(|size| size != "4MB") {
+            return format!(
+                "Its flash_size: sets the app slots: raise it if the board has more flash, \
+                 or {shrink}."
+            );
+        }
+        let mut advice = String::from(
+            "If the board has more than 4MB of flash, set flash_size: under esp32: to it \
+             for larger app slots. Otherwise use a standard table:",
+        );
+        for (name, trade, _) in STANDARD_TABLES {
+            advice += &format!(
+                "\n  esphome-desktop device partitions {device} --write {name}  ({trade})"
+            );
+        }
+        advice += "\nA new partition table has to be flashed over USB once; OTA can't change it.";
+        advice
+    }
+}
+
+/// The firmware size and the app slot's, from PlatformIO's "The program
+/// size (X bytes) is greater than maximum allowed (Y bytes)".
+pub(crate) fn overflow(output: &str) -> Option<(u64, u64)> {
+    let (_, rest) = output.split_once("The program size (")?;
+    let (size, rest) = rest.split_once(" bytes) is greater than maximum allowed (")?;
+    let (max, _) = rest.split_once(" bytes)")?;
+    Some((size.parse().ok()?, max.parse().ok()?))
+}
+
+/// The standard tables, as `(name, what it trades)`.
+pub(crate) fn standard_tables() -> impl Iterator<Item = (&'static str, &'static str)> {
+    STANDARD_TABLES
+        .iter()
+        .map(|(name, trade, _)| (*name, *trade))
+}
+
+/// Write the standard table `name` into `config_dir` as
+/// `partitions_<name>.csv`, leaving a different file of that name alone.
+/// Returns its path.
+pub(crate) fn write_table(config_dir: &Path, name: &str) -> Result<PathBuf> {
+    let Some((_, _, csv)) = STANDARD_TABLES.iter().find(|(n, _, _)| *n == name) else {
+        let names: Vec<&str> = standard_tables().map(|(n, _)| n).collect();
+        bail!("no standard table {name:?} (use {})", names.join(" or "));
+    };
+    let path = config_dir.join(format!("partitions_{name}.csv"));
+    match std::fs::read_to_string(&path) {
+        Ok(existing) if existing == *csv => return Ok(path),
+        Ok(_) => bail!("{path:?} exists with other contents"),
+        Err(_) => {}
+    }
+    crate::util::atomic_write(&path, csv).with_context(|| format!("Failed to write {path:?}"))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scans_the_layout() {
+        let yaml = "esphome:\n  name: porch\n  platformio_options:\n    board: nope\n\n\
+                    esp32:\n  board: esp32dev # the usual\n  flash_size: 8MB\n";
+        let scheme = Scheme::scan(yaml);
+        assert_eq!(scheme.platform.as_deref(), Some("esp32"));
+        assert_eq!(scheme.board.as_deref(), Some("esp32dev"));
+        assert_eq!(
+            scheme.describe(),
+            "esp32, board esp32dev, ESPHome's layout for 8MB flash"
+        );
+        assert!(scheme
+            .advice("porch")
+            .contains("raise it if the board has more flash"));
+
+        let custom = Scheme::scan(
+            "esphome:\n  platformio_options:\n    board_build.partitions: min_spiffs.csv\n\
+             esp32:\n  board: esp32dev\n",
+        );
+        assert_eq!(custom.table.as_deref(), Some("min_spiffs.csv"));
+        assert!(Scheme::scan("esp32:\n  board: esp32dev\n")
+            .advice("porch")
+            .contains("device partitions porch --write min_spiffs"));
+    }
+
+    #[test]
+    fn finds_platformio_overflow() {
+        let output = "Linking .pioenvs/porch/firmware.elf\n\
+            Error: The program size (1902345 bytes) is greater than maximum allowed (1835008 bytes)\n";
+        assert_eq!(overflow(output), Some((1902345, 1835008)));
+        assert_eq!(overflow("INFO Successfully compiled"), None);
+    }
+
+    #[test]
+    fn writes_a_standard_table_once() {
+        let dir = crate::util::unique_temp_dir("partitions");
+        let path = write_table(&dir, "min_spiffs").unwrap();
+        assert!(std::fs::read_to_string(&path).unwrap().contains("0x1E0000"));
+        assert_eq!(write_table(&dir, "min_spiffs").unwrap(), path);
+        std::fs::write(&path, "mine").unwrap();
+        assert!(write_table(&dir, "min_spiffs").is_err());
+        assert!(write_table(&dir, "tiny").is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
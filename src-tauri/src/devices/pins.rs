@@ -0,0 +1,337 @@
+//! Which GPIO pins each device's config assigns, and to what, so a pin used
+//! twice shows up before a validate or compile fails on it.
+//!
+//! The YAML is read line by line rather than parsed: a `pin:` key or one
+//! ending in `_pin` (and I2C's `sda`/`scl`) with a pin for its value, or a
+//! `number:` under it in the long form. A pin marked `allow_other_uses` is
+//! shared on purpose and isn't a conflict, and one on an I/O expander
+//! (`pcf8574:`, `mcp23xxx:` and the like under it) isn't the chip's.
+//! Pins in packages, and those behind `${substitutions}`, aren't seen.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Keys besides `pin` and `*_pin` whose value is a pin.
+const PIN_KEYS: &[&str] = &["sda", "scl"];
+
+/// Keys of the long pin form that describe the pin; any other names the
+/// expander it is on.
+const PIN_OPTIONS: &[&str] = &[
+    "number",
+    "mode",
+    "inverted",
+    "allow_other_uses",
+    "ignore_strapping_warning",
+    "ignore_pin_validation_error",
+    "drive_strength",
+];
+
+/// One pin assignment.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct PinUse {
+    /// As `GPIO4` for a number, else as written (`D2`, `A0`).
+    pub pin: String,
+    /// The component it belongs to, e.g. `sensor dht (Porch)` or `i2c`.
+    pub component: String,
+    /// From 1.
+    pub line: usize,
+    /// Marked `allow_other_uses`.
+    pub shared: bool,
+}
+
+/// A device's pin assignments.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct DevicePins {
+    pub device: String,
+    /// The config's file name.
+    pub file: String,
+    /// In config order.
+    pub uses: Vec<PinUse>,
+    /// Pins assigned more than once without `allow_other_uses`, sorted.
+    pub conflicts: Vec<String>,
+}
+
+/// Every device's pin assignments, by device name.
+pub(crate) fn overview(config_dir: &Path) -> Result<Vec<DevicePins>> {
+    let mut devices = Vec::new();
+    for device in super::list_devices(config_dir)? {
+        let Some(path) = super::config_path(config_dir, &device) else {
+            continue;
+        };
+        let Ok(text) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        let uses = scan(&text);
+        let conflicts = conflicts(&uses);
+        devices.push(DevicePins {
+            device,
+            file: path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+            uses,
+            conflicts,
+        });
+    }
+    Ok(devices)
+}
+
+/// A line's indent, its `key` if it has one, and the value after it.
+struct Line<'a> {
+    number: usize,
+    indent: usize,
+    /// Whether the line starts a list entry (`- `).
+    item: bool,
+    key: Option<&'a str>,
+    value: &'a str,
+}
+
+fn lines(text: &str) -> Vec<Line<'_>> {
+    text.lines()
+        .enumerate()
+        .filter_map(|(i, raw)| {
+            let content = raw.split(" #").next().unwrap_or(raw).trim_end();
+            let trimmed = content.trim_start();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                return None;
+            }
+            let mut indent = content.len() - trimmed.len();
+            let (item, rest) = match trimmed.strip_prefix("- ") {
+                Some(rest) => {
+                    indent += 2;
+                    (true, rest.trim_start())
+                }
+                None => (false, trimmed),
+            };
+            let (key, value) = match rest.split_once(':') {
+                Some((key, value))
+                    if (value.is_empty() || value.starts_with(' '))
+                        && !key.contains(' ')
+                        && !key.starts_with(['"', '\'']) =>
+                {
+                    (Some(key), value.trim())
+                }
+                _ => (None, rest),
+            };
+            Some(Line {
+                number: i + 1,
+                indent,
+                item,
+                key,
+                value,
+            })
+        })
+        .collect()
+}
+
+fn is_pin_key(key: &str) -> bool {
+    key == "pin" || key.ends_with("_pin") || PIN_KEYS.contains(&key)
+}
+
+/// `value` as a pin: `GPIO4` for `4`, `GPIO04` or `gpio4`, else uppercased
+/// as written. `None` for what isn't a pin here (a substitution, a tag).
+fn normalize(value: &str) -> Option<String> {
+    let value = value.trim_matches(['"', '\'']).trim();
+    if value.is_empty() || value.starts_with(['$', '!', '{', '[']) {
+        return None;
+    }
+    let upper = value.to_ascii_uppercase();
+    let digits = upper.strip_prefix("GPIO").unwrap_or(&upper);
+    match digits.parse::<u32>() {
+        Ok(n) => Some(format!("GPIO{n}")),
+        Err(_) => Some(upper),
+    }
+}
+
+/// The component owning a pin: the top-level key, and the list entry's
+/// `platform` and `name` (or `id`) when it is in one.
+#[derive(Default)]
+struct Owner {
+    top: String,
+    /// Indent of the entry's fields, once in a list entry.
+    fields: Option<usize>,
+    platform: Option<String>,
+    name: Option<String>,
+}
+
+impl Owner {
+    fn label(&self) -> String {
+        let mut label = self.top.clone();
+        if let Some(platform) = &self.platform {
+            label = format!("{label} {platform}");
+        }
+        if let Some(name) = &self.name {
+            label = format!("{label} ({name})");
+        }
+        label
+    }
+}
+
+/// The pins `text` assigns, in order.
+pub(crate) fn scan(text: &str) -> Vec<PinUse> {
+    let lines = lines(text);
+    // Each use with the index of the owner it belongs to; owners are
+    // labelled at the end, as `name:` often follows the pins.
+    let mut owners: Vec<Owner> = Vec::new();
+    let mut found: Vec<(usize, PinUse)> = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        if line.indent == 0 {
+            owners.push(Owner {
+                top: line.key.unwrap_or_default().to_string(),
+                ..Owner::default()
+            });
+            continue;
+        }
+        let Some(owner) = owners.last_mut() else {
+            continue;
+        };
+        // A list entry straight under the top-level key starts a new owner.
+        if line.item && owner.fields.map_or(true, |f| f == line.indent) {
+            if owner.fields.is_some() {
+                let top = owner.top.clone();
+                owners.push(Owner {
+                    top,
+                    ..Owner::default()
+                });
+            }
+            if let Some(owner) = owners.last_mut() {
+                owner.fields = Some(line.indent);
+            }
+        }
+        let owner_index = owners.len() - 1;
+        let owner = &mut owners[owner_index];
+        let Some(key) = line.key else { continue };
+        if owner.fields == Some(line.indent) {
+            match key {
+                "platform" => owner.platform = Some(line.value.to_string()),
+                "name" | "id" if owner.name.is_none() || key == "name" => {
+                    owner.name = Some(line.value.trim_matches(['"', '\'']).to_string());
+                }
+                _ => {}
+            }
+        }
+        if !is_pin_key(key) {
+            continue;
+        }
+        let under: Vec<&Line> = lines[i + 1..]
+            .iter()
+            .take_while(|l| l.indent > line.indent)
+            .collect();
+        let (pin, shared) = if line.value.is_empty() {
+            let option = |name: &str| under.iter().find(|l| l.key == Some(name)).map(|l| l.value);
+            let on_expander = under
+                .iter()
+                .any(|l| l.key.is_some_and(|k| !PIN_OPTIONS.contains(&k)));
+            if on_expander {
+                continue;
+            }
+            (
+                option("number").and_then(normalize),
+                option("allow_other_uses") == Some("true"),
+            )
+        } else {
+            (normalize(line.value), false)
+        };
+        if let Some(pin) = pin {
+            found.push((
+                owner_index,
+                PinUse {
+                    pin,
+                    component: String::new(),
+                    line: line.number,
+                    shared,
+                },
+            ));
+        }
+    }
+    found
+        .into_iter()
+        .map(|(owner, mut pin)| {
+            pin.component = owners[owner].label();
+            pin
+        })
+        .collect()
+}
+
+/// The pins in `uses` assigned more than once where not every use is shared.
+pub(crate) fn conflicts(uses: &[PinUse]) -> Vec<String> {
+    let mut by_pin: BTreeMap<&str, Vec<&PinUse>> = BTreeMap::new();
+    for pin in uses {
+        by_pin.entry(&pin.pin).or_default().push(pin);
+    }
+    by_pin
+        .into_iter()
+        .filter(|(_, uses)| uses.len() > 1 && !uses.iter().all(|u| u.shared))
+        .map(|(pin, _)| pin.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "\
+esphome:
+  name: porch
+
+i2c:
+  sda: GPIO21
+  scl: 22
+
+sensor:
+  - platform: dht
+    pin: GPIO04  # the old board
+    temperature:
+      name: Porch Temperature
+    name: Porch
+  - platform: adc
+    pin:
+      number: 34
+      allow_other_uses: true
+    name: Battery
+
+binary_sensor:
+  - platform: gpio
+    id: door
+    pin:
+      pcf8574: expander
+      number: 4
+  - platform: gpio
+    pin:
+      number: GPIO4
+      inverted: true
+    name: Motion
+";
+
+    #[test]
+    fn finds_each_pin_and_who_uses_it() {
+        let uses = scan(CONFIG);
+        let found: Vec<(&str, &str, usize)> = uses
+            .iter()
+            .map(|u| (u.pin.as_str(), u.component.as_str(), u.line))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("GPIO21", "i2c", 5),
+                ("GPIO22", "i2c", 6),
+                ("GPIO4", "sensor dht (Porch)", 10),
+                ("GPIO34", "sensor adc (Battery)", 15),
+                ("GPIO4", "binary_sensor gpio (Motion)", 27),
+            ]
+        );
+        assert!(uses[3].shared);
+        assert_eq!(conflicts(&uses), ["GPIO4"]);
+    }
+
+    #[test]
+    fn pins_are_normalized() {
+        assert_eq!(normalize("gpio05").as_deref(), Some("GPIO5"));
+        assert_eq!(normalize("\"13\"").as_deref(), Some("GPIO13"));
+        assert_eq!(normalize("d2").as_deref(), Some("D2"));
+        assert_eq!(normalize("${led_pin}"), None);
+    }
+}
@@ -0,0 +1,373 @@
+//! Recovering a lost config: ask a running device what it is over the native
+//! API and scaffold a YAML to start again from.
+//!
+//! A device reports its name, board, ESPHome version and entities (names,
+//! units, device classes, options), but not the pins, buses or components
+//! behind them. The scaffold keeps what it reports: the core blocks filled
+//! in, each sensor, switch, button, number and select as a `template`
+//! placeholder under the same name (so Home Assistant keeps the entity IDs
+//! once the real platforms replace them), and everything else as a comment.
+//!
+//! The API client is `aioesphomeapi`, which ships with ESPHome, run by the
+//! bundled Python. Native runtime only.
+
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::info;
+
+use crate::daemon::DaemonManager;
+
+/// How long the device gets to answer.
+const QUERY_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Connects, prints `{"device": ..., "entities": [...]}` and exits. The
+/// password and key come through the environment rather than argv, which
+/// other users can read.
+const SCRIPT: &str = r#"
+import asyncio, json, os, sys
+from aioesphomeapi import APIClient
+
+async def main():
+    client = APIClient(
+        sys.argv[1],
+        int(sys.argv[2]),
+        os.environ.get("ESPHOME_API_PASSWORD", ""),
+        noise_psk=os.environ.get("ESPHOME_API_KEY") or None,
+    )
+    await client.connect(login=True)
+    try:
+        info = await client.device_info()
+        entities, _ = await client.list_entities_services()
+    finally:
+        await client.disconnect()
+    print(json.dumps({
+        "device": info.to_dict(),
+        "entities": [dict(e.to_dict(), kind=type(e).__name__) for e in entities],
+    }, default=str))
+
+try:
+    asyncio.run(main())
+except Exception as e:
+    sys.exit(f"{type(e).__name__}: {e}")
+"#;
+
+/// Where to reach the device.
+#[derive(Debug, Clone)]
+pub(crate) struct Target<'a> {
+    pub host: &'a str,
+    pub port: u16,
+    /// The API encryption key, for devices that have one.
+    pub key: Option<&'a str>,
+    /// The legacy API password.
+    pub password: Option<&'a str>,
+}
+
+/// Query the device at `target` and write the scaffold into the config
+/// directory as `<name>.yaml`, `name` defaulting to the one the device
+/// reports, as a change undo can take back in `data_dir`. Refuses to
+/// replace a config that exists. Returns its path and the number of
+/// entities found.
+pub(crate) async fn run(
+    daemon: &DaemonManager,
+    data_dir: &Path,
+    target: Target<'_>,
+    name: Option<&str>,
+) -> Result<(PathBuf, usize)> {
+    let mut cmd = daemon.python_command()?;
+    cmd.args(["-c", SCRIPT, target.host, &target.port.to_string()]);
+    if let Some(key) = target.key {
+        cmd.env("ESPHOME_API_KEY", key);
+    }
+    if let Some(password) = target.password {
+        cmd.env("ESPHOME_API_PASSWORD", password);
+    }
+    let child = cmd.spawn().context("Failed to start Python")?;
+    let output = tokio::time::timeout(QUERY_TIMEOUT, child.wait_with_output())
+        .await
+        .with_context(|| format!("{} didn't answer within {QUERY_TIMEOUT:?}", target.host))?
+        .context("Failed to wait for Python")?;
+    if !output.status.success() {
+        bail!(
+            "couldn't query {}: {}",
+            target.host,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let reply: Value =
+        serde_json::from_slice(&output.stdout).context("Failed to parse the device's answer")?;
+    let (reported, yaml) = scaffold(&reply, &target);
+    let name = name.map(str::to_string).unwrap_or(reported);
+    write(daemon.config_dir(), data_dir, &name, &yaml).map(|path| {
+        info!("Recovered a config for {} into {:?}", target.host, path);
+        (path, entity_count(&reply))
+    })
+}
+
+fn write(config_dir: &Path, data_dir: &Path, name: &str, yaml: &str) -> Result<PathBuf> {
+    super::template::check_name(name)?;
+    if super::config_path(config_dir, name).is_some() {
+        bail!("{name} already has a config; pass another name");
+    }
+    let path = config_dir.join(format!("{name}.yaml"));
+    super::undo::transaction(data_dir, &format!("recover {name}"), |change| {
+        change.write(&path, yaml)
+    })?;
+    Ok(path)
+}
+
+fn entity_count(reply: &Value) -> usize {
+    reply["entities"].as_array().map_or(0, Vec::len)
+}
+
+/// The device's name and a config for it, from the script's answer.
+fn scaffold(reply: &Value, target: &Target<'_>) -> (String, String) {
+    let device = &reply["device"];
+    let text = |key: &str| device[key].as_str().filter(|s| !s.is_empty());
+    let name = text("name").unwrap_or("recovered").to_string();
+
+    let mut yaml = format!(
+        "# Recovered from {} on {}. Only what the device reports over the API is\n\
+         # here: fill in the pins, buses and real platforms before flashing it.\n",
+        target.host,
+        crate::audit::rfc3339(std::time::SystemTime::now())
+    );
+    for (label, key) in [
+        ("ESPHome", "esphome_version"),
+        ("Compiled", "compilation_time"),
+        ("MAC", "mac_address"),
+    ] {
+        if let Some(value) = text(key) {
+            let _ = writeln!(yaml, "# {label}: {value}");
+        }
+    }
+
+    let _ = write!(yaml, "\nesphome:\n  name: {name}\n");
+    if let Some(friendly) = text("friendly_name") {
+        let _ = writeln!(yaml, "  friendly_name: {}", quote(friendly));
+    }
+    if let (Some(project), Some(version)) = (text("project_name"), text("project_version")) {
+        let _ = write!(
+            yaml,
+            "  project:\n    name: {}\n    version: {}\n",
+            quote(project),
+            quote(version)
+        );
+    }
+    let board = text("model").unwrap_or("esp32dev");
+    let platform = guess_platform(board, text("manufacturer").unwrap_or(""));
+    let _ = write!(
+        yaml,
+        "\n# The platform is a guess from the board: check it.\n{platform}:\n  board: {board}\n"
+    );
+    yaml += "\nlogger:\n\napi:\n";
+    if let Some(key) = target.key {
+        let _ = write!(yaml, "  encryption:\n    key: {}\n", quote(key));
+    }
+    yaml += "\nota:\n  - platform: esphome\n\nwifi:\n  ssid: !secret wifi_ssid\n  \
+             password: !secret wifi_password\n";
+
+    let entities = reply["entities"].as_array().map_or(&[][..], Vec::as_slice);
+    let mut domains: Vec<(&str, Vec<String>)> = Vec::new();
+    let mut unsupported = Vec::new();
+    for entity in entities {
+        let kind = entity["kind"].as_str().unwrap_or("");
+        let label = entity["name"].as_str().unwrap_or("");
+        match domain(kind) {
+            Some(domain) => {
+                let block = entity_block(domain, entity);
+                match domains.iter_mut().find(|(d, _)| *d == domain) {
+                    Some((_, blocks)) => blocks.push(block),
+                    None => domains.push((domain, vec![block])),
+                }
+            }
+            None => unsupported.push(format!(
+                "{} {}",
+                kind.trim_end_matches("Info"),
+                quote(label)
+            )),
+        }
+    }
+    for (domain, blocks) in domains {
+        let _ = write!(yaml, "\n{domain}:\n");
+        for block in blocks {
+            yaml += &block;
+        }
+    }
+    if !unsupported.is_empty() {
+        yaml += "\n# Also reported, with no placeholder here:\n";
+        for entity in unsupported {
+            let _ = writeln!(yaml, "#   {entity}");
+        }
+    }
+    (name, yaml)
+}
+
+/// The config domain for an `aioesphomeapi` entity class that a template
+/// platform can stand in for.
+fn domain(kind: &str) -> Option<&'static str> {
+    Some(match kind {
+        "SensorInfo" => "sensor",
+        "BinarySensorInfo" => "binary_sensor",
+        "TextSensorInfo" => "text_sensor",
+        "SwitchInfo" => "switch",
+        "ButtonInfo" => "button",
+        "NumberInfo" => "number",
+        "SelectInfo" => "select",
+        _ => return None,
+    })
+}
+
+fn entity_block(domain: &str, entity: &Value) -> String {
+    let mut block = format!(
+        "  - platform: template\n    name: {}\n",
+        quote(entity["name"].as_str().unwrap_or(""))
+    );
+    let text = |key: &str| entity[key].as_str().filter(|s| !s.is_empty());
+    if let Some(id) = text("object_id") {
+        field(&mut block, "id", &id.replace('-', "_"));
+    }
+    if let Some(unit) = text("unit_of_measurement") {
+        field(&mut block, "unit_of_measurement", &quote(unit));
+    }
+    if let Some(class) = text("device_class") {
+        field(&mut block, "device_class", class);
+    }
+    if domain == "sensor" {
+        if let Some(decimals) = entity["accuracy_decimals"].as_i64() {
+            field(&mut block, "accuracy_decimals", &decimals.to_string());
+        }
+    }
+    if let Some(icon) = text("icon") {
+        field(&mut block, "icon", &quote(icon));
+    }
+    if let Some(category) = entity_category(&entity["entity_category"]) {
+        field(&mut block, "entity_category", category);
+    }
+    if matches!(domain, "switch" | "number" | "select") {
+        field(&mut block, "optimistic", "true");
+    }
+    if domain == "number" {
+        for key in ["min_value", "max_value", "step"] {
+            if let Some(value) = entity[key].as_f64() {
+                field(&mut block, key, &value.to_string());
+            }
+        }
+    }
+    if domain == "select" {
+        block += "    options:\n";
+        let options = entity["options"].as_array().map_or(&[][..], Vec::as_slice);
+        for option in options.iter().filter_map(Value::as_str) {
+            let _ = writeln!(block, "      - {}", quote(option));
+        }
+    }
+    block
+}
+
+fn field(block: &mut String, key: &str, value: &str) {
+    let _ = writeln!(block, "    {key}: {value}");
+}
+
+/// `config` or `diagnostic`; the API sends the enum's number, older
+/// clients its name.
+fn entity_category(value: &Value) -> Option<&'static str> {
+    match value {
+        Value::Number(n) => match n.as_u64()? {
+            1 => Some("config"),
+            2 => Some("diagnostic"),
+            _ => None,
+        },
+        Value::String(s) if s.to_ascii_lowercase().ends_with("config") => Some("config"),
+        Value::String(s) if s.to_ascii_lowercase().ends_with("diagnostic") => Some("diagnostic"),
+        _ => None,
+    }
+}
+
+/// The chip family, from the board id the device reports as its model.
+fn guess_platform(board: &str, manufacturer: &str) -> &'static str {
+    let board = board.to_ascii_lowercase();
+    let manufacturer = manufacturer.to_ascii_lowercase();
+    if manufacturer.contains("beken") {
+        "bk72xx"
+    } else if manufacturer.contains("realtek") {
+        "rtl87xx"
+    } else if board.contains("rp2040") || board.contains("pico") {
+        "rp2040"
+    } else if [
+        "8266", "8285", "d1_mini", "nodemcu", "esp01", "sonoff", "wemos",
+    ]
+    .iter()
+    .any(|hint| board.contains(hint))
+    {
+        "esp8266"
+    } else {
+        "esp32"
+    }
+}
+
+/// A YAML string: JSON's quoting is valid YAML.
+fn quote(s: &str) -> String {
+    serde_json::to_string(s).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn scaffolds_what_the_device_reports() {
+        let reply = json!({
+            "device": {
+                "name": "porch",
+                "friendly_name": "Porch",
+                "model": "d1_mini",
+                "manufacturer": "Espressif",
+                "esphome_version": "2024.6.4",
+                "mac_address": "AA:BB:CC:DD:EE:FF",
+            },
+            "entities": [
+                {"kind": "SensorInfo", "name": "Temperature", "object_id": "temperature",
+                 "unit_of_measurement": "°C", "device_class": "temperature",
+                 "accuracy_decimals": 1, "entity_category": 0},
+                {"kind": "SelectInfo", "name": "Mode", "object_id": "mode",
+                 "options": ["Auto", "Off"], "entity_category": 1},
+                {"kind": "LightInfo", "name": "Porch Light"},
+            ],
+        });
+        let target = Target {
+            host: "porch.local",
+            port: 6053,
+            key: Some("a2V5"),
+            password: None,
+        };
+        let (name, yaml) = scaffold(&reply, &target);
+        assert_eq!(name, "porch");
+        assert!(yaml.contains("# ESPHome: 2024.6.4\n"));
+        assert!(yaml.contains("esphome:\n  name: porch\n  friendly_name: \"Porch\"\n"));
+        assert!(yaml.contains("esp8266:\n  board: d1_mini\n"));
+        assert!(yaml.contains("    key: \"a2V5\"\n"));
+        assert!(yaml.contains(
+            "sensor:\n  - platform: template\n    name: \"Temperature\"\n    id: temperature\n    \
+             unit_of_measurement: \"°C\"\n    device_class: temperature\n    accuracy_decimals: 1\n"
+        ));
+        assert!(yaml.contains(
+            "    entity_category: config\n    optimistic: true\n    options:\n      - \"Auto\"\n"
+        ));
+        assert!(yaml.contains("#   Light \"Porch Light\"\n"));
+        assert_eq!(entity_count(&reply), 3);
+    }
+
+    #[test]
+    fn refuses_to_replace_a_config() {
+        let dir = crate::util::unique_temp_dir("recover");
+        let data_dir = dir.join("data");
+        let path = write(&dir, &data_dir, "porch", "esphome:\n").unwrap();
+        assert!(path.ends_with("porch.yaml"));
+        assert!(write(&dir, &data_dir, "porch", "esphome:\n").is_err());
+        assert!(write(&dir, &data_dir, "Porch Light", "esphome:\n").is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,160 @@
+//! Devices added by hand: ones the network doesn't show, such as a device
+//! on another VLAN whose mDNS never reaches this machine.
+//!
+//! An entry gives a device name an address and, optionally, the name in
+//! `secrets.yaml` of its API encryption key. A name with a config in the
+//! config directory is that device, reached at the entry's address; a name
+//! without one is a device known only by its address. Either way it shows in
+//! `device list`, is probed by latency monitoring, can be named to `device
+//! watch` and `device recover`, and batch uploads flash it at its address
+//! instead of looking it up. The entries live in `device_registry.json` in
+//! the app data, next to the notes.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use super::sources::strip_comment;
+use crate::util::ConfigStore;
+
+/// File name of the registry inside the data directory.
+pub(crate) const REGISTRY_FILE_NAME: &str = "device_registry.json";
+
+/// One device added by hand.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct ManualDevice {
+    /// IP address or hostname.
+    pub address: String,
+    /// Name in `secrets.yaml` of its API encryption key.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_secret: Option<String>,
+}
+
+/// The registry, keyed by device name.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Registry {
+    #[serde(default)]
+    devices: BTreeMap<String, ManualDevice>,
+}
+
+impl ConfigStore for Registry {
+    const FILE_NAME: &'static str = REGISTRY_FILE_NAME;
+    const WHAT: &'static str = "device registry";
+}
+
+impl Registry {
+    /// Add `name`, or replace its entry.
+    pub(crate) fn add(&mut self, name: &str, device: ManualDevice) -> Result<()> {
+        super::template::check_name(name)?;
+        if device.address.trim().is_empty() || device.address.contains(char::is_whitespace) {
+            bail!("{:?} isn't an address", device.address);
+        }
+        self.devices.insert(name.to_string(), device);
+        Ok(())
+    }
+
+    /// Remove `name`; false if it wasn't there.
+    pub(crate) fn remove(&mut self, name: &str) -> bool {
+        self.devices.remove(name).is_some()
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<&ManualDevice> {
+        self.devices.get(name)
+    }
+
+    /// Every entry, by name.
+    pub(crate) fn entries(&self) -> impl Iterator<Item = (&str, &ManualDevice)> {
+        self.devices
+            .iter()
+            .map(|(name, device)| (name.as_str(), device))
+    }
+}
+
+/// `configs` plus the registry's devices without a config, sorted.
+pub(crate) fn with_manual(mut configs: Vec<String>, registry: &Registry) -> Vec<String> {
+    configs.extend(registry.devices.keys().cloned());
+    configs.sort();
+    configs.dedup();
+    configs
+}
+
+/// The value of `name` in the config directory's `secrets.yaml`.
+pub(crate) fn secret(config_dir: &Path, name: &str) -> Result<String> {
+    let path = config_dir.join("secrets.yaml");
+    let yaml =
+        std::fs::read_to_string(&path).with_context(|| format!("Failed to read {path:?}"))?;
+    yaml.lines()
+        .map(strip_comment)
+        .filter(|line| !line.starts_with(char::is_whitespace))
+        .find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            (key.trim() == name).then(|| {
+                value
+                    .trim()
+                    .trim_matches(|c| c == '"' || c == '\'')
+                    .to_string()
+            })
+        })
+        .with_context(|| format!("no {name} in {path:?}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::unique_temp_dir;
+
+    #[test]
+    fn entries_round_trip_and_merge_with_configs() {
+        let dir = unique_temp_dir("registry");
+        let path = Registry::path(&dir);
+        assert_eq!(Registry::load(&path).unwrap(), Registry::default());
+
+        let mut registry = Registry::default();
+        let vlan = ManualDevice {
+            address: "10.20.0.15".into(),
+            key_secret: Some("barn_api_key".into()),
+        };
+        registry.add("barn", vlan.clone()).unwrap();
+        assert!(registry.add("Barn Door", vlan.clone()).is_err());
+        assert!(registry
+            .add(
+                "gate",
+                ManualDevice {
+                    address: " ".into(),
+                    key_secret: None
+                }
+            )
+            .is_err());
+        registry.save(&path).unwrap();
+
+        let loaded = Registry::load(&path).unwrap();
+        assert_eq!(loaded.get("barn"), Some(&vlan));
+        assert_eq!(
+            with_manual(vec!["attic".into(), "barn".into()], &loaded),
+            ["attic", "barn"]
+        );
+        assert_eq!(
+            with_manual(vec!["attic".into()], &loaded),
+            ["attic", "barn"]
+        );
+
+        registry.remove("barn");
+        assert_eq!(registry, Registry::default());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn reads_top_level_secrets() {
+        let dir = unique_temp_dir("registry_secrets");
+        std::fs::write(
+            dir.join("secrets.yaml"),
+            "wifi_ssid: Home # the main one\nbarn_api_key: \"a2V5\"\nnested:\n  barn_api_key: x\n",
+        )
+        .unwrap();
+        assert_eq!(secret(&dir, "barn_api_key").unwrap(), "a2V5");
+        assert_eq!(secret(&dir, "wifi_ssid").unwrap(), "Home");
+        assert!(secret(&dir, "missing").is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
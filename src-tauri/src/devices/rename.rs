@@ -0,0 +1,269 @@
+//! Renaming a device: its config file, its `esphome: name:` (or the
+//! substitution that sets it), its notes, groups, `device add` entry and
+//! stored API key, and the old name's build files, which would otherwise
+//! show in the dashboard as a second device.
+//!
+//! The device itself keeps the old name, and the hostname that comes with
+//! it, until it runs firmware built with the new one, so that first upload
+//! has to go to the old address ([`Renamed::old_address`]).
+
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+use super::registry::Registry;
+use super::sources::strip_comment;
+use super::NotesStore;
+use crate::util::ConfigStore;
+
+/// What a rename did.
+#[derive(Debug)]
+pub(crate) struct Renamed {
+    /// The config under its new name.
+    pub config: PathBuf,
+    /// Where the device still answers: its `device add` address, else the
+    /// old name's `.local` hostname.
+    pub old_address: String,
+    /// The old name's build files that were removed.
+    pub removed: Vec<PathBuf>,
+}
+
+/// Rename `device` to `new_name`, with its notes and the rest in `data_dir`.
+pub(crate) fn rename(
+    config_dir: &Path,
+    data_dir: &Path,
+    device: &str,
+    new_name: &str,
+) -> Result<Renamed> {
+    super::template::check_name(new_name)?;
+    let old_config = super::config_path(config_dir, device)
+        .with_context(|| format!("no config for {device}"))?;
+    if device == new_name {
+        bail!("{device} already has that name");
+    }
+    if super::config_path(config_dir, new_name).is_some() {
+        bail!("{new_name} already has a config");
+    }
+    let yaml = std::fs::read_to_string(&old_config)
+        .with_context(|| format!("Failed to read {old_config:?}"))?;
+    let (renamed, old_name) = set_name(&yaml, new_name)?;
+    let ext = old_config
+        .extension()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned();
+    let config = config_dir.join(format!("{new_name}.{ext}"));
+    let label = format!("rename {device} to {new_name}");
+    super::undo::transaction(data_dir, &label, |change| {
+        change.write(&config, renamed)?;
+        change.remove(&old_config)?;
+
+        // The build under the old name, and the dashboard's record of the
+        // old file, which lists it as a device of its own.
+        let esphome = config_dir.join(".esphome");
+        let file_name = old_config.file_name().unwrap_or_default().to_string_lossy();
+        let mut removed = Vec::new();
+        for stale in [
+            esphome.join("build").join(&old_name),
+            esphome.join("storage").join(format!("{file_name}.json")),
+        ] {
+            if !stale.exists() {
+                continue;
+            }
+            match change.discard(&stale) {
+                Ok(()) => removed.push(stale),
+                Err(e) => warn!("Failed to remove {:?}: {:#}", stale, e),
+            }
+        }
+
+        let notes_path = NotesStore::path(data_dir);
+        let mut notes = NotesStore::load(&notes_path)?;
+        notes.rename(device, new_name);
+        change.track(&notes_path)?;
+        notes.save(&notes_path)?;
+
+        let registry_path = Registry::path(data_dir);
+        let mut registry = Registry::load(&registry_path)?;
+        let entry = registry.get(device).cloned();
+        let old_address = match &entry {
+            Some(entry) => entry.address.clone(),
+            None => format!("{old_name}.local"),
+        };
+        if let Some(entry) = entry {
+            registry.remove(device);
+            registry.add(new_name, entry)?;
+            change.track(&registry_path)?;
+            registry.save(&registry_path)?;
+        }
+
+        Ok(Renamed {
+            config: config.clone(),
+            old_address,
+            removed,
+        })
+    })
+}
+
+/// Move the API key stored for `device` to `new_name`. Apart from
+/// [`rename`] as it touches the OS keychain, which may be locked or absent:
+/// a key left behind can be stored again.
+pub(crate) fn move_key(device: &str, new_name: &str) {
+    match super::keys::load(device) {
+        Ok(Some(key)) => {
+            let moved =
+                super::keys::store(new_name, &key).and_then(|()| super::keys::delete(device));
+            if let Err(e) = moved {
+                warn!(
+                    "Failed to move {}'s API key to {}: {:#}",
+                    device, new_name, e
+                );
+            }
+        }
+        Ok(None) => {}
+        Err(e) => warn!("Failed to read {}'s API key: {:#}", device, e),
+    }
+}
+
+/// `yaml` with `new_name` as its `esphome: name:`, or as the substitution
+/// that sets it, and the name it had.
+fn set_name(yaml: &str, new_name: &str) -> Result<(String, String)> {
+    let mut lines: Vec<String> = yaml.lines().map(str::to_string).collect();
+    let (at, old_name) = locate(&lines)?;
+    let line = &lines[at];
+    let colon = line.find(':').unwrap_or(line.len());
+    let end = strip_comment(line).trim_end().len();
+    let updated = format!("{} {new_name}{}", &line[..=colon], &line[end..]);
+    lines[at] = updated;
+
+    let newline = if yaml.contains("\r\n") { "\r\n" } else { "\n" };
+    let mut renamed = lines.join(newline);
+    if yaml.ends_with('\n') {
+        renamed.push_str(newline);
+    }
+    Ok((renamed, old_name))
+}
+
+/// The device's name in `yaml`, through the substitution that sets it, and
+/// the line (from 1) it is set on.
+pub(super) fn name(yaml: &str) -> Option<(usize, String)> {
+    let lines: Vec<String> = yaml.lines().map(str::to_string).collect();
+    locate(&lines).ok().map(|(at, name)| (at + 1, name))
+}
+
+/// The line of `esphome: name:`, or of the substitution that sets it, and
+/// the name there.
+fn locate(lines: &[String]) -> Result<(usize, String)> {
+    let (at, value) = field(lines, "esphome", "name")
+        .context("the config has no `name:` under `esphome:`; rename it by hand")?;
+    if value.contains('$') {
+        // Only a name that is one substitution can be renamed here.
+        let var = value
+            .strip_prefix('$')
+            .map(|v| v.trim_start_matches('{').trim_end_matches('}'))
+            .filter(|v| !v.is_empty() && v.chars().all(|c| c.is_alphanumeric() || c == '_'))
+            .context("the device's name is built from substitutions; rename it by hand")?;
+        field(lines, "substitutions", var).with_context(|| {
+            format!("`{var}` isn't set under `substitutions:` in the config; rename it by hand")
+        })
+    } else {
+        Ok((at, value))
+    }
+}
+
+/// The line index and unquoted value of `key:` directly under the top-level
+/// `block:`.
+fn field(lines: &[String], block: &str, key: &str) -> Option<(usize, String)> {
+    let start = lines
+        .iter()
+        .position(|l| strip_comment(l).trim_end() == format!("{block}:"))?;
+    let mut indent = None;
+    for (i, line) in lines.iter().enumerate().skip(start + 1) {
+        let content = strip_comment(line);
+        let trimmed = content.trim_start();
+        if trimmed.trim_end().is_empty() {
+            continue;
+        }
+        let this = content.len() - trimmed.len();
+        if this == 0 {
+            break;
+        }
+        if *indent.get_or_insert(this) != this {
+            continue;
+        }
+        if let Some(value) = trimmed.strip_prefix(key).and_then(|v| v.strip_prefix(':')) {
+            let value = value.trim().trim_matches(['"', '\'']);
+            return Some((i, value.to_string()));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sets_the_name_or_its_substitution() {
+        let yaml = "esphome:\n  friendly_name: Porch\n  name: \"porch\"  # hostname\n\nwifi:\n  ssid: home\n";
+        let (renamed, old) = set_name(yaml, "front-porch").unwrap();
+        assert_eq!(old, "porch");
+        assert_eq!(
+            renamed,
+            "esphome:\n  friendly_name: Porch\n  name: front-porch  # hostname\n\nwifi:\n  ssid: home\n"
+        );
+
+        let yaml = "substitutions:\n  device_name: porch\nesphome:\n  name: ${device_name}\n";
+        let (renamed, old) = set_name(yaml, "front-porch").unwrap();
+        assert_eq!(old, "porch");
+        assert_eq!(
+            renamed,
+            "substitutions:\n  device_name: front-porch\nesphome:\n  name: ${device_name}\n"
+        );
+
+        assert!(set_name("esphome:\n  name: porch-${suffix}\n", "x").is_err());
+        assert!(set_name("wifi:\n  ssid: home\n", "x").is_err());
+    }
+
+    #[test]
+    fn renames_the_config_and_what_goes_with_it() {
+        let dir = crate::util::unique_temp_dir("rename");
+        let config_dir = dir.join("config");
+        let data_dir = dir.join("data");
+        let build = config_dir.join(".esphome").join("build").join("porch");
+        std::fs::create_dir_all(&build).unwrap();
+        std::fs::create_dir_all(config_dir.join(".esphome").join("storage")).unwrap();
+        std::fs::write(
+            config_dir
+                .join(".esphome")
+                .join("storage")
+                .join("porch.yml.json"),
+            "{}",
+        )
+        .unwrap();
+        std::fs::write(config_dir.join("porch.yml"), "esphome:\n  name: porch\n").unwrap();
+        let mut notes = NotesStore::default();
+        notes.set_note("porch", "by the door");
+        notes.add_to_group("outdoor", ["porch"]);
+        notes.save(&NotesStore::path(&data_dir)).unwrap();
+
+        let renamed = rename(&config_dir, &data_dir, "porch", "front-porch").unwrap();
+        assert_eq!(renamed.config, config_dir.join("front-porch.yml"));
+        assert_eq!(renamed.old_address, "porch.local");
+        assert_eq!(renamed.removed.len(), 2);
+        assert!(!build.exists());
+        assert!(!config_dir.join("porch.yml").exists());
+        assert_eq!(
+            std::fs::read_to_string(&renamed.config).unwrap(),
+            "esphome:\n  name: front-porch\n"
+        );
+        let notes = NotesStore::load(&NotesStore::path(&data_dir)).unwrap();
+        assert_eq!(
+            notes.get("front-porch").note.as_deref(),
+            Some("by the door")
+        );
+        assert!(notes.group("outdoor").unwrap().contains("front-porch"));
+
+        assert!(rename(&config_dir, &data_dir, "front-porch", "Bad Name").is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
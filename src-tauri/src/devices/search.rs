@@ -0,0 +1,224 @@
+//! Full-text search over the config directory: which devices use a
+//! component, a pin or a secret.
+//!
+//! Every YAML file under the config directory is read, the devices at the
+//! top and the packages and includes below, except in hidden directories
+//! (`.esphome` holds the builds), archived devices and `secrets.yaml`, whose
+//! values have no business in a results list. A match is a plain, case-insensitive one, by
+//! default of whole words so `GPIO4` doesn't find `GPIO40`; components, pins
+//! and `!secret` names are all literal in the YAML.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Directory levels below the config directory searched.
+const MAX_DEPTH: usize = 4;
+
+/// Files larger than this aren't configs.
+const MAX_FILE_SIZE: u64 = 1024 * 1024;
+
+/// Hits returned at most; a query that finds more is too broad to read.
+const MAX_HITS: usize = 500;
+
+/// A matching line.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct Hit {
+    /// Relative to the config directory, with `/` separators.
+    pub file: String,
+    /// From 1.
+    pub line: usize,
+    pub text: String,
+}
+
+/// What a search found.
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+pub(crate) struct Results {
+    /// In file order, then line order.
+    pub hits: Vec<Hit>,
+    /// The devices with a hit in their own config, sorted.
+    pub devices: Vec<String>,
+    /// Whether hits past [`MAX_HITS`] were left out.
+    pub truncated: bool,
+}
+
+/// Search the YAML under `config_dir` for `query`.
+pub(crate) fn search(config_dir: &Path, query: &str, whole_word: bool) -> Result<Results> {
+    let query = query.trim().to_lowercase();
+    let mut results = Results::default();
+    if query.is_empty() {
+        return Ok(results);
+    }
+    for path in config_files(config_dir)? {
+        let Ok(text) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        let file = relative(config_dir, &path);
+        let before = results.hits.len();
+        for (i, line) in text.lines().enumerate() {
+            if !matches(&line.to_lowercase(), &query, whole_word) {
+                continue;
+            }
+            if results.hits.len() == MAX_HITS {
+                results.truncated = true;
+                break;
+            }
+            results.hits.push(Hit {
+                file: file.clone(),
+                line: i + 1,
+                text: line.trim().to_string(),
+            });
+        }
+        let top_level = path.parent() == Some(config_dir);
+        if results.hits.len() > before && top_level {
+            results.devices.extend(super::device_name(&path));
+        }
+        if results.truncated {
+            break;
+        }
+    }
+    results.devices.sort();
+    Ok(results)
+}
+
+/// The YAML files searched, sorted: the configs under `config_dir` and
+/// every package and include below it, but not `secrets.yaml` or what is in
+/// hidden directories or the archive.
+pub(crate) fn config_files(config_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    collect(config_dir, 0, &mut files)
+        .with_context(|| format!("Failed to read config directory {config_dir:?}"))?;
+    files.sort();
+    Ok(files)
+}
+
+/// The config directory's YAML files, `depth` levels down.
+fn collect(dir: &Path, depth: usize, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)?.flatten() {
+        let path = entry.path();
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if name.starts_with('.') || (depth == 0 && name == super::archive::ARCHIVE_DIR) {
+            continue;
+        }
+        let Ok(kind) = entry.file_type() else {
+            continue;
+        };
+        if kind.is_dir() {
+            if depth < MAX_DEPTH {
+                // An unreadable subdirectory hides only its own files.
+                let _ = collect(&path, depth + 1, files);
+            }
+        } else if kind.is_file()
+            && is_searched(&path)
+            && entry.metadata().is_ok_and(|m| m.len() <= MAX_FILE_SIZE)
+        {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn is_searched(path: &Path) -> bool {
+    let yaml = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("yaml") || e.eq_ignore_ascii_case("yml"));
+    let secrets = path.file_stem().is_some_and(|s| s == "secrets");
+    yaml && !secrets
+}
+
+/// `path` relative to `config_dir`, with `/` separators.
+pub(super) fn relative(config_dir: &Path, path: &Path) -> String {
+    let rel = path.strip_prefix(config_dir).unwrap_or(path);
+    rel.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Whether lowercased `line` has `query`, alone as a word if `whole_word`.
+fn matches(line: &str, query: &str, whole_word: bool) -> bool {
+    if !whole_word {
+        return line.contains(query);
+    }
+    let word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+    line.match_indices(query).any(|(at, _)| {
+        let before = line[..at].chars().next_back();
+        let after = line[at + query.len()..].chars().next();
+        // A query ending in punctuation (`!secret `) may be followed by
+        // anything, and likewise at its start.
+        (!word(query.chars().next()) || !word(before))
+            && (!word(query.chars().next_back()) || !word(after))
+    })
+}
+
+/// The file a search hit names, if it is one under `config_dir`: the page
+/// hands back what it was given, but nothing outside the config directory
+/// may be opened from it.
+pub(crate) fn resolve(config_dir: &Path, file: &str) -> Option<PathBuf> {
+    let root = config_dir.canonicalize().ok()?;
+    let path = root.join(file).canonicalize().ok()?;
+    (path.starts_with(&root) && is_searched(&path)).then_some(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whole_words_only_unless_asked() {
+        assert!(matches("pin: gpio4", "gpio4", true));
+        assert!(!matches("pin: gpio40", "gpio4", true));
+        assert!(matches("pin: gpio40", "gpio4", false));
+        assert!(matches(
+            "password: !secret wifi_password",
+            "!secret wifi",
+            false
+        ));
+        assert!(!matches(
+            "password: !secret wifi_password",
+            "!secret wifi",
+            true
+        ));
+        assert!(matches("- platform: bme280", "platform: bme280", true));
+    }
+
+    #[test]
+    fn finds_devices_and_skips_secrets_and_builds() {
+        let dir = crate::util::unique_temp_dir("search");
+        std::fs::write(
+            dir.join("porch.yaml"),
+            "sensor:\n  - platform: bme280_i2c\n    address: 0x76\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("attic.yml"), "i2c:\n  sda: GPIO4\n").unwrap();
+        std::fs::write(dir.join("secrets.yaml"), "bme280_i2c: hunter2\n").unwrap();
+        std::fs::create_dir_all(dir.join("packages")).unwrap();
+        std::fs::write(
+            dir.join("packages").join("climate.yaml"),
+            "sensor:\n  - platform: BME280_I2C\n",
+        )
+        .unwrap();
+        std::fs::create_dir_all(dir.join(".esphome")).unwrap();
+        std::fs::write(dir.join(".esphome").join("porch.yaml"), "bme280_i2c\n").unwrap();
+
+        let results = search(&dir, "bme280_i2c", true).unwrap();
+        assert_eq!(results.devices, ["porch"]);
+        assert_eq!(
+            results
+                .hits
+                .iter()
+                .map(|h| (h.file.as_str(), h.line))
+                .collect::<Vec<_>>(),
+            [("packages/climate.yaml", 2), ("porch.yaml", 2)]
+        );
+        assert_eq!(results.hits[1].text, "- platform: bme280_i2c");
+
+        assert!(resolve(&dir, "porch.yaml").is_some());
+        assert!(resolve(&dir, "secrets.yaml").is_none());
+        assert!(resolve(&dir, "../outside.yaml").is_none());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
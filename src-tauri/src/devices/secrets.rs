@@ -0,0 +1,171 @@
+//! `!secret` references checked against `secrets.yaml`: the names configs
+//! use that it lacks, each a validation failure waiting to happen, and the
+//! ones it has that nothing uses any more.
+//!
+//! Every YAML file config search reads ([`super::search::config_files`]),
+//! packages and includes too, is checked against the config directory's own
+//! `secrets.yaml`. Only the names in `secrets.yaml` are read, never a value.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::ErrorKind;
+use std::path::Path;
+
+use super::search::{config_files, relative};
+use super::sources::strip_comment;
+
+/// Where a secret is used.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct Use {
+    /// Relative to the config directory, with `/` separators.
+    pub file: String,
+    /// From 1.
+    pub line: usize,
+}
+
+/// A name configs use that `secrets.yaml` doesn't have.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct Missing {
+    pub name: String,
+    /// In file order, then line order.
+    pub uses: Vec<Use>,
+}
+
+/// How the configs' secrets and `secrets.yaml` line up.
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+pub(crate) struct Report {
+    /// Whether there is a `secrets.yaml` at all.
+    pub secrets_file: bool,
+    /// Secrets used and defined.
+    pub defined: usize,
+    /// By name.
+    pub missing: Vec<Missing>,
+    /// Names in `secrets.yaml` no config uses, sorted.
+    pub unused: Vec<String>,
+}
+
+/// Check the configs under `config_dir` against its `secrets.yaml`.
+pub(crate) fn check(config_dir: &Path) -> Result<Report> {
+    let path = config_dir.join("secrets.yaml");
+    let (secrets_file, defined) = match std::fs::read_to_string(&path) {
+        Ok(yaml) => (true, names(&yaml)),
+        Err(e) if e.kind() == ErrorKind::NotFound => (false, BTreeSet::new()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {path:?}")),
+    };
+    let mut used: BTreeMap<String, Vec<Use>> = BTreeMap::new();
+    for file in config_files(config_dir)? {
+        let Ok(text) = std::fs::read_to_string(&file) else {
+            continue;
+        };
+        let name = relative(config_dir, &file);
+        for (line, secret) in references(&text) {
+            used.entry(secret).or_default().push(Use {
+                file: name.clone(),
+                line,
+            });
+        }
+    }
+    let mut report = Report {
+        secrets_file,
+        unused: defined
+            .iter()
+            .filter(|name| !used.contains_key(*name))
+            .cloned()
+            .collect(),
+        ..Report::default()
+    };
+    for (name, uses) in used {
+        if defined.contains(&name) {
+            report.defined += 1;
+        } else {
+            report.missing.push(Missing { name, uses });
+        }
+    }
+    Ok(report)
+}
+
+/// The top-level names in `secrets.yaml`.
+fn names(yaml: &str) -> BTreeSet<String> {
+    yaml.lines()
+        .map(strip_comment)
+        .filter(|line| !line.starts_with(char::is_whitespace))
+        .filter_map(|line| {
+            let (key, _) = line.split_once(':')?;
+            let key = key.trim().trim_matches(['"', '\'']);
+            (!key.is_empty() && key != "<<").then(|| key.to_string())
+        })
+        .collect()
+}
+
+/// The line (from 1) and name of each `!secret` in `text`.
+fn references(text: &str) -> Vec<(usize, String)> {
+    let mut found = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let mut rest = strip_comment(line);
+        while let Some(at) = rest.find("!secret ") {
+            rest = rest[at + "!secret ".len()..].trim_start();
+            let end = rest
+                .find(|c: char| c.is_whitespace() || matches!(c, ',' | '}' | ']'))
+                .unwrap_or(rest.len());
+            let name = rest[..end].trim_matches(['"', '\'']);
+            if !name.is_empty() {
+                found.push((i + 1, name.to_string()));
+            }
+            rest = &rest[end..];
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_missing_and_unused_secrets() {
+        let dir = crate::util::unique_temp_dir("secrets");
+        std::fs::write(
+            dir.join("secrets.yaml"),
+            "wifi_ssid: home\nwifi_password: \"hunter2\"\n# old_key: x\nold_ota: abc\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("porch.yaml"),
+            "wifi:\n  ssid: !secret wifi_ssid\n  password: !secret wifi_password # ok\n\
+             api:\n  encryption:\n    key: !secret porch_key\n",
+        )
+        .unwrap();
+        std::fs::create_dir_all(dir.join("common")).unwrap();
+        std::fs::write(
+            dir.join("common").join("mqtt.yaml"),
+            "mqtt: { broker: !secret mqtt_host, username: !secret mqtt_host }\n",
+        )
+        .unwrap();
+
+        let report = check(&dir).unwrap();
+        assert!(report.secrets_file);
+        assert_eq!(report.defined, 2);
+        assert_eq!(report.unused, ["old_ota"]);
+        let missing: Vec<(&str, Vec<(&str, usize)>)> = report
+            .missing
+            .iter()
+            .map(|m| {
+                let uses = m.uses.iter().map(|u| (u.file.as_str(), u.line)).collect();
+                (m.name.as_str(), uses)
+            })
+            .collect();
+        assert_eq!(
+            missing,
+            [
+                (
+                    "mqtt_host",
+                    vec![("common/mqtt.yaml", 1), ("common/mqtt.yaml", 1)]
+                ),
+                ("porch_key", vec![("porch.yaml", 6)]),
+            ]
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,125 @@
+//! Flashing over USB when OTA keeps failing. After a batch upload, a device
+//! whose last [`FALLBACK_AFTER`] uploads all failed once they got to
+//! flashing (see [`ota`](super::ota)) is flashed over the USB serial port
+//! instead, with the firmware the batch just built (`esphome upload
+//! --device <port>`), rather than the user starting over in the dashboard.
+//!
+//! Only with `--serial-fallback`, and only with exactly one USB serial port
+//! connected. Which board is on the port can't be told from here, so it is
+//! used for the first such device only. Without the flag, or with several
+//! ports, the batch says what it would do instead.
+
+use std::path::Path;
+use tracing::{info, warn};
+
+use super::batch::{self, BatchReport};
+use super::ota::OtaHistory;
+use crate::control::ops::Progress;
+use crate::daemon::DaemonManager;
+use crate::util::ConfigStore;
+
+/// Failed uploads in a row after which a device is flashed over USB.
+pub(crate) const FALLBACK_AFTER: usize = 2;
+
+/// How many of `device`'s latest uploads failed in a row.
+pub(crate) fn failing_streak(history: &OtaHistory, device: &str) -> usize {
+    history
+        .attempts(device)
+        .iter()
+        .rev()
+        .take_while(|attempt| !attempt.ok)
+        .count()
+}
+
+/// Flash the first device in `report` that keeps failing OTA over USB, if
+/// `enabled`, moving it to the succeeded ones if that works; otherwise say
+/// how.
+pub(crate) async fn fallback(
+    daemon: &DaemonManager,
+    report: &mut BatchReport,
+    data_dir: &Path,
+    enabled: bool,
+    progress: Progress<'_>,
+) {
+    let history = OtaHistory::load(&OtaHistory::path(data_dir)).unwrap_or_default();
+    let Some(device) = report
+        .failed
+        .iter()
+        .find(|device| failing_streak(&history, device) >= FALLBACK_AFTER)
+        .cloned()
+    else {
+        return;
+    };
+    let ports = match crate::platform::usb_serial_ports() {
+        Ok(ports) => ports,
+        Err(e) => {
+            warn!("Failed to list serial ports: {:#}", e);
+            return;
+        }
+    };
+    let [port] = ports.as_slice() else {
+        if !ports.is_empty() {
+            progress(
+                "device",
+                &format!(
+                    "{device} failed OTA {FALLBACK_AFTER} times in a row; to flash it over USB, \
+                     run `esphome upload <config> --device <port>` with its port among {}",
+                    ports.join(", ")
+                ),
+            );
+        }
+        return;
+    };
+    if !enabled {
+        progress(
+            "device",
+            &format!(
+                "{device} failed OTA {FALLBACK_AFTER} times in a row and a board is on {port}: \
+                 if it's this one, upload again with --serial-fallback to flash it over USB"
+            ),
+        );
+        return;
+    }
+    progress(
+        "device",
+        &format!("{device} keeps failing OTA; flashing it over USB at {port}"),
+    );
+    match batch::upload_serial(daemon, &device, port).await {
+        Ok(()) => {
+            info!("Flashed {} over USB at {}", device, port);
+            progress("device", &format!("{device} flashed over USB"));
+            report.failed.retain(|d| *d != device);
+            report.succeeded.push(device);
+        }
+        Err(e) => {
+            let message = format!("{device} failed over USB too: {e:#}");
+            for line in message.lines() {
+                progress("device", line);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::devices::ota::Attempt;
+
+    #[test]
+    fn counts_the_failures_since_the_last_upload_that_landed() {
+        let dir = crate::util::unique_temp_dir("serial");
+        let path = OtaHistory::path(&dir);
+        for ok in [false, true, false, false] {
+            let attempt = Attempt {
+                time: "t".into(),
+                ok,
+                address: None,
+            };
+            OtaHistory::record(&path, "garage", attempt).unwrap();
+        }
+        let history = OtaHistory::load(&path).unwrap();
+        assert_eq!(failing_streak(&history, "garage"), 2);
+        assert_eq!(failing_streak(&history, "porch"), 0);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,111 @@
+//! Firmware signing keys, for ESPHome's signed OTA updates: a device built
+//! with a key accepts only firmware signed with it. One key per device, made
+//! here and kept in the OS keychain under [`SERVICE`], so it never sits in
+//! the config folder next to the YAML.
+//!
+//! A config asks for its key through the `${ota_signing_key}` substitution
+//! ([`SUBSTITUTION`]) and doesn't define it under `substitutions:`. Batch
+//! actions then pass the stored key to ESPHome as that substitution (`-s`),
+//! and refuse a device without one rather than let ESPHome fail on an
+//! undefined substitution. Builds started from the dashboard don't get the
+//! key. A key is never replaced, only removed: a device flashed with it
+//! refuses firmware signed with any other until it is flashed over USB.
+
+use anyhow::{bail, Result};
+
+use super::sources::strip_comment;
+
+/// The keychain service the keys are filed under.
+const SERVICE: &str = "io.esphome.builder.signing-key";
+
+/// The substitution a config takes its key from.
+pub(crate) const SUBSTITUTION: &str = "ota_signing_key";
+
+/// Whether `yaml` takes its signing key from [`SUBSTITUTION`] without
+/// defining it.
+pub(crate) fn uses_signing(yaml: &str) -> bool {
+    let mut in_substitutions = false;
+    let mut referenced = false;
+    for line in yaml.lines().map(strip_comment) {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        if !line.starts_with(char::is_whitespace) {
+            in_substitutions = trimmed == "substitutions:";
+        } else if in_substitutions
+            && trimmed
+                .strip_prefix(SUBSTITUTION)
+                .is_some_and(|rest| rest.trim_start().starts_with(':'))
+        {
+            return false;
+        }
+        referenced |= references(trimmed);
+    }
+    referenced
+}
+
+/// `${ota_signing_key}` or `$ota_signing_key` (not a longer name).
+fn references(text: &str) -> bool {
+    text.contains(&format!("${{{SUBSTITUTION}}}"))
+        || text
+            .match_indices(&format!("${SUBSTITUTION}"))
+            .any(|(at, found)| {
+                !text[at + found.len()..]
+                    .starts_with(|c: char| c.is_ascii_alphanumeric() || c == '_')
+            })
+}
+
+/// Make and store a key for `device`, unless it has one.
+pub(crate) fn generate(device: &str) -> Result<()> {
+    if load(device)?.is_some() {
+        bail!("{device} already has a signing key; remove it first to make a new one");
+    }
+    let mut key = [0u8; 32];
+    crate::platform::random_bytes(&mut key)?;
+    crate::platform::keychain::store(SERVICE, device, &super::template::base64(&key))
+}
+
+/// The key stored for `device`, if any.
+pub(crate) fn load(device: &str) -> Result<Option<String>> {
+    crate::platform::keychain::load(SERVICE, device)
+}
+
+/// Remove `device`'s key.
+pub(crate) fn delete(device: &str) -> Result<()> {
+    crate::platform::keychain::delete(SERVICE, device)
+}
+
+/// The ESPHome options that hand `device` its key, if its config `yaml`
+/// asks for one.
+pub(crate) fn esphome_args(device: &str, yaml: &str) -> Result<Vec<String>> {
+    if !uses_signing(yaml) {
+        return Ok(Vec::new());
+    }
+    let Some(key) = load(device)? else {
+        bail!(
+            "{device} uses signed OTA (${{{SUBSTITUTION}}}) but has no signing key; \
+             make one under Firmware Signing Keys in the tray"
+        );
+    };
+    Ok(vec!["-s".into(), SUBSTITUTION.into(), key])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_config_uses_signing_when_it_references_the_key_without_defining_it() {
+        let signed = "esphome:\n  name: porch\nota:\n  - platform: esphome\n    signing_key: ${ota_signing_key}\n";
+        assert!(uses_signing(signed));
+        assert!(uses_signing(
+            "ota:\n  key: $ota_signing_key # from the app\n"
+        ));
+        assert!(!uses_signing("ota:\n  key: $ota_signing_key_2\n"));
+        assert!(!uses_signing("ota:\n  # key: ${ota_signing_key}\n"));
+        assert!(!uses_signing(&format!(
+            "substitutions:\n  ota_signing_key: abc\n{signed}"
+        )));
+    }
+}
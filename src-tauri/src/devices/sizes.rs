@@ -0,0 +1,201 @@
+//! Flash and RAM use per device over time.
+//!
+//! PlatformIO ends every build with a memory summary:
+//!
+//! ```text
+//! RAM:   [=         ]  10.9% (used 35744 bytes from 327680 bytes)
+//! Flash: [=====     ]  52.5% (used 963177 bytes from 1835008 bytes)
+//! ```
+//!
+//! Compiles the app runs itself (batch `compile` and `upload`) record it in
+//! `build_sizes.json` in the app data, with the ESPHome version that built
+//! it, so a device creeping toward its partition limit shows up before an
+//! update pushes it over. Compiles started from the dashboard print to the
+//! browser rather than to the app, so they aren't recorded.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::util::ConfigStore;
+
+/// File name of the history inside the data directory.
+pub(crate) const SIZES_FILE_NAME: &str = "build_sizes.json";
+
+/// Builds kept per device.
+const HISTORY_LEN: usize = 50;
+
+/// Use, in percent of the partition, that counts as close to the limit.
+const WARN_PERCENT: u64 = 90;
+
+/// Bytes used of a memory region.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Usage {
+    pub used: u64,
+    pub total: u64,
+}
+
+impl Usage {
+    pub(crate) fn percent(&self) -> u64 {
+        (self.used * 100 + self.total / 2)
+            .checked_div(self.total)
+            .unwrap_or(0)
+    }
+}
+
+/// One build's memory summary.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Sample {
+    /// When it was built, `YYYY-MM-DDTHH:MM:SSZ`.
+    pub time: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub esphome: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flash: Option<Usage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ram: Option<Usage>,
+}
+
+impl Sample {
+    /// The memory summary in a build's output, or `None` if it has none (a
+    /// failed build, or an upload that reused the firmware).
+    pub(crate) fn parse(output: &str, time: String) -> Option<Self> {
+        let mut sample = Self {
+            time,
+            esphome: None,
+            flash: None,
+            ram: None,
+        };
+        for line in output.lines().map(str::trim) {
+            if let Some(rest) = line.strip_prefix("RAM:") {
+                sample.ram = usage(rest).or(sample.ram);
+            } else if let Some(rest) = line.strip_prefix("Flash:") {
+                sample.flash = usage(rest).or(sample.flash);
+            } else if let Some((_, rest)) = line.split_once("INFO ESPHome ") {
+                sample.esphome = rest.split_whitespace().next().map(str::to_string);
+            }
+        }
+        (sample.flash.is_some() || sample.ram.is_some()).then_some(sample)
+    }
+
+    /// The regions at or over [`WARN_PERCENT`], as `("flash", percent)`.
+    pub(crate) fn near_limit(&self) -> Vec<(&'static str, u64)> {
+        [("flash", self.flash), ("RAM", self.ram)]
+            .into_iter()
+            .filter_map(|(region, usage)| Some((region, usage?.percent())))
+            .filter(|(_, percent)| *percent >= WARN_PERCENT)
+            .collect()
+    }
+}
+
+/// `(used 35744 bytes from 327680 bytes)` out of the rest of a summary line.
+fn usage(line: &str) -> Option<Usage> {
+    let (_, rest) = line.split_once("(used ")?;
+    let (used, rest) = rest.split_once(" bytes from ")?;
+    let (total, _) = rest.split_once(" bytes")?;
+    Some(Usage {
+        used: used.trim().parse().ok()?,
+        total: total.trim().parse().ok()?,
+    })
+}
+
+/// Every device's recorded builds, oldest first.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct SizeHistory {
+    #[serde(default)]
+    devices: BTreeMap<String, Vec<Sample>>,
+}
+
+impl ConfigStore for SizeHistory {
+    const FILE_NAME: &'static str = SIZES_FILE_NAME;
+    const WHAT: &'static str = "build sizes";
+}
+
+impl SizeHistory {
+    /// Add a build of `device` to the history at `path`, dropping the oldest
+    /// beyond [`HISTORY_LEN`].
+    pub(crate) fn record(path: &Path, device: &str, sample: Sample) -> Result<()> {
+        let mut history = Self::load(path)?;
+        let samples = history.devices.entry(device.to_string()).or_default();
+        samples.push(sample);
+        let excess = samples.len().saturating_sub(HISTORY_LEN);
+        samples.drain(..excess);
+        history.save(path)
+    }
+
+    /// Replace `device`'s builds in the history at `path` with `samples`
+    /// (none drops the device), returning those it had.
+    pub(crate) fn replace(path: &Path, device: &str, samples: Vec<Sample>) -> Result<Vec<Sample>> {
+        let mut history = Self::load(path)?;
+        let old = if samples.is_empty() {
+            history.devices.remove(device)
+        } else {
+            history.devices.insert(device.to_string(), samples)
+        };
+        if old.is_none() && !history.devices.contains_key(device) {
+            return Ok(Vec::new());
+        }
+        history.save(path)?;
+        Ok(old.unwrap_or_default())
+    }
+
+    /// `device`'s builds, oldest first.
+    pub(crate) fn samples(&self, device: &str) -> &[Sample] {
+        self.devices.get(device).map_or(&[], Vec::as_slice)
+    }
+
+    /// Each device with its latest build.
+    pub(crate) fn latest(&self) -> impl Iterator<Item = (&str, &Sample)> {
+        self.devices
+            .iter()
+            .filter_map(|(device, samples)| Some((device.as_str(), samples.last()?)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::unique_temp_dir;
+
+    const OUTPUT: &str = "INFO ESPHome 2024.10.0\n\
+        INFO Compiling app...\n\
+        RAM:   [=         ]  10.9% (used 35744 bytes from 327680 bytes)\n\
+        Flash: [========= ]  93.0% (used 1706557 bytes from 1835008 bytes)\n\
+        ========================= [SUCCESS] Took 81.20 seconds =========================\n";
+
+    #[test]
+    fn parses_the_memory_summary() {
+        let sample = Sample::parse(OUTPUT, "t".into()).unwrap();
+        assert_eq!(sample.esphome.as_deref(), Some("2024.10.0"));
+        assert_eq!(
+            sample.ram,
+            Some(Usage {
+                used: 35744,
+                total: 327680
+            })
+        );
+        assert_eq!(sample.flash.unwrap().percent(), 93);
+        assert_eq!(sample.near_limit(), [("flash", 93)]);
+        assert_eq!(
+            Sample::parse("INFO Successfully compiled", "t".into()),
+            None
+        );
+    }
+
+    #[test]
+    fn history_keeps_the_latest_builds() {
+        let dir = unique_temp_dir("sizes");
+        let path = SizeHistory::path(&dir);
+        for i in 0..HISTORY_LEN + 2 {
+            let sample = Sample::parse(OUTPUT, i.to_string()).unwrap();
+            SizeHistory::record(&path, "porch", sample).unwrap();
+        }
+        let history = SizeHistory::load(&path).unwrap();
+        let samples = history.samples("porch");
+        assert_eq!(samples.len(), HISTORY_LEN);
+        assert_eq!(samples[0].time, "2");
+        assert_eq!(history.latest().next().unwrap().1.time, "51");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,351 @@
+//! YAML snippets copied from the ESPHome docs: telling one from any other
+//! copied text, adding one to a device's config, and checking one against
+//! the installed ESPHome.
+//!
+//! Only the text is looked at, never parsed as YAML: a snippet is a run of
+//! top-level `key:` blocks, at least one of them a component ESPHome has.
+//! Adding one to a config appends each block, or for a list component the
+//! config already has (`sensor:`, `switch:`), its entries to that list.
+
+use anyhow::{bail, Context, Result};
+use std::ffi::OsStr;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::daemon::DaemonManager;
+use crate::settings::NewDevicePreset;
+
+/// Longer than any snippet in the docs; copied logs and files are skipped.
+const MAX_LEN: usize = 16 * 1024;
+
+/// Top-level keys that make copied YAML an ESPHome snippet. Not every
+/// component, just the ones the docs' examples start with often enough.
+const COMPONENTS: &[&str] = &[
+    "api",
+    "binary_sensor",
+    "bluetooth_proxy",
+    "button",
+    "captive_portal",
+    "climate",
+    "cover",
+    "deep_sleep",
+    "display",
+    "esp32",
+    "esp32_ble_tracker",
+    "esp8266",
+    "esphome",
+    "ethernet",
+    "external_components",
+    "fan",
+    "font",
+    "globals",
+    "i2c",
+    "i2s_audio",
+    "image",
+    "interval",
+    "light",
+    "lock",
+    "logger",
+    "media_player",
+    "microphone",
+    "mqtt",
+    "number",
+    "one_wire",
+    "ota",
+    "output",
+    "packages",
+    "remote_receiver",
+    "remote_transmitter",
+    "rp2040",
+    "script",
+    "select",
+    "sensor",
+    "speaker",
+    "spi",
+    "status_led",
+    "substitutions",
+    "switch",
+    "text",
+    "text_sensor",
+    "time",
+    "uart",
+    "voice_assistant",
+    "web_server",
+    "wifi",
+];
+
+/// Chip families' top-level keys; a snippet with one of its own is checked
+/// on that chip rather than the `new_device` one.
+const PLATFORMS: &[&str] = &[
+    "bk72xx", "esp32", "esp8266", "host", "ln882x", "rp2040", "rtl87xx",
+];
+
+/// How long a check may take; external components and packages are fetched
+/// on first use.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(180);
+
+/// Lines of ESPHome's output kept when a snippet fails its check.
+const CHECK_TAIL_LINES: usize = 20;
+
+/// Numbers each check's scratch directory, so the tray and the scratchpad
+/// can check at once.
+static NEXT_CHECK: AtomicU64 = AtomicU64::new(0);
+
+/// A top-level `key:` line and the lines under it (leading comments
+/// included), as found in the text.
+#[derive(Debug)]
+struct Block<'a> {
+    key: &'a str,
+    lines: Vec<&'a str>,
+}
+
+impl<'a> Block<'a> {
+    /// Whether the block's value is a list under its key (`- platform: …`).
+    fn is_list(&self) -> bool {
+        self.body()
+            .find(|l| !is_blank(l))
+            .is_some_and(|l| l.trim_start().starts_with("- "))
+    }
+
+    /// The lines after the key line.
+    fn body(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.lines
+            .iter()
+            .skip_while(|l| top_key(l).is_none())
+            .skip(1)
+            .copied()
+    }
+}
+
+fn is_blank(line: &str) -> bool {
+    let line = line.trim();
+    line.is_empty() || line.starts_with('#')
+}
+
+/// The key of a top-level `key:` line.
+fn top_key(line: &str) -> Option<&str> {
+    let (key, rest) = line.split_once(':')?;
+    let plain = key.starts_with(|c: char| c.is_ascii_lowercase())
+        && key
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'_');
+    (plain && (rest.is_empty() || rest.starts_with(' '))).then_some(key)
+}
+
+fn indent(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+/// `text`'s top-level blocks, or `None` if a top-level line isn't a plain
+/// `key:` or a tab indents a line.
+fn blocks(text: &str) -> Option<Vec<Block<'_>>> {
+    let mut blocks: Vec<Block> = Vec::new();
+    let mut pending = Vec::new();
+    for line in text.lines() {
+        if line.starts_with('\t') {
+            return None;
+        }
+        if is_blank(line) && blocks.last().map_or(true, |_| indent(line) == 0) {
+            pending.push(line);
+            continue;
+        }
+        if indent(line) > 0 {
+            let block = blocks.last_mut()?;
+            block.lines.append(&mut pending);
+            block.lines.push(line);
+            continue;
+        }
+        let key = top_key(line)?;
+        let mut lines = std::mem::take(&mut pending);
+        lines.push(line);
+        blocks.push(Block { key, lines });
+    }
+    Some(blocks)
+}
+
+/// Whether copied `text` is an ESPHome snippet.
+pub(crate) fn is_snippet(text: &str) -> bool {
+    if text.len() > MAX_LEN {
+        return false;
+    }
+    blocks(text).is_some_and(|blocks| blocks.iter().any(|b| COMPONENTS.contains(&b.key)))
+}
+
+/// `config` with `snippet` added. Fails when the snippet sets something the
+/// config already has that isn't a list both ways, or the config has
+/// top-level lines this can't follow (`<<: !include`, `---`): only a person
+/// can reconcile those.
+pub(crate) fn merge(config: &str, snippet: &str) -> Result<String> {
+    let additions = blocks(snippet).context("the snippet isn't ESPHome YAML")?;
+    let mut lines: Vec<String> = config.lines().map(str::to_string).collect();
+    for addition in additions {
+        let text = lines.join("\n");
+        let mut at = 0;
+        let existing = blocks(&text)
+            .context("the config has top-level lines that aren't `key:`; add the snippet by hand")?
+            .into_iter()
+            .find_map(|b| {
+                let start = at;
+                at += b.lines.len();
+                (b.key == addition.key).then(|| (start, b.is_list(), item_indent(&b), b.lines))
+            });
+        match existing {
+            None => {
+                while lines.last().is_some_and(|l| l.trim().is_empty()) {
+                    lines.pop();
+                }
+                if !lines.is_empty() {
+                    lines.push(String::new());
+                }
+                let first = addition
+                    .lines
+                    .iter()
+                    .position(|l| !l.trim().is_empty())
+                    .unwrap_or(0);
+                lines.extend(addition.lines[first..].iter().map(|l| l.to_string()));
+            }
+            Some((start, true, into, block)) if addition.is_list() => {
+                let from = item_indent(&addition);
+                let end = start
+                    + block
+                        .iter()
+                        .rposition(|l| !l.trim().is_empty())
+                        .map_or(block.len(), |i| i + 1);
+                let body = addition.body().map(|line| {
+                    if line.trim().is_empty() {
+                        String::new()
+                    } else {
+                        let depth = indent(line).saturating_sub(from) + into;
+                        format!("{}{}", " ".repeat(depth), line.trim_start_matches(' '))
+                    }
+                });
+                lines.splice(end..end, body.collect::<Vec<_>>());
+            }
+            Some(_) => bail!(
+                "the config already has `{}:`; add the snippet by hand",
+                addition.key
+            ),
+        }
+    }
+    let mut merged = lines.join("\n");
+    merged.push('\n');
+    Ok(merged)
+}
+
+/// How far a list block's entries are indented.
+fn item_indent(block: &Block) -> usize {
+    block.body().find(|l| !is_blank(l)).map_or(2, indent)
+}
+
+/// Check `snippet` with the installed ESPHome's `esphome config`, in a
+/// scratch config under `data_dir` that adds whatever a config needs and
+/// the snippet lacks, on `preset`'s board. The user's `secrets.yaml` is
+/// copied in for `!secret`. `Ok(None)` means it validates, `Ok(Some(_))`
+/// carries ESPHome's complaint.
+pub(crate) async fn check(
+    daemon: &DaemonManager,
+    data_dir: &Path,
+    preset: &NewDevicePreset,
+    snippet: &str,
+) -> Result<Option<String>> {
+    let Some(python) = daemon.host_python() else {
+        bail!(
+            "checking a snippet isn't supported with the {} runtime",
+            daemon.runtime()
+        );
+    };
+    let n = NEXT_CHECK.fetch_add(1, Ordering::Relaxed);
+    let dir = data_dir.join(format!("snippet-check-{n}"));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {dir:?}"))?;
+    let secrets = daemon.config_dir().join("secrets.yaml");
+    if secrets.exists() {
+        std::fs::copy(&secrets, dir.join("secrets.yaml")).context("Failed to copy secrets.yaml")?;
+    }
+    let config = dir.join("snippet.yaml");
+    std::fs::write(&config, scratch_config(preset, snippet))
+        .with_context(|| format!("Failed to write {config:?}"))?;
+
+    let child = daemon
+        .esphome_command_in(python, &dir, [OsStr::new("config"), config.as_os_str()])?
+        .spawn()
+        .context("Failed to start ESPHome")?;
+    let output = tokio::time::timeout(CHECK_TIMEOUT, child.wait_with_output())
+        .await
+        .context("ESPHome took too long to check the snippet")?
+        .context("Failed to wait for ESPHome")?;
+    let _ = std::fs::remove_dir_all(&dir);
+    if output.status.success() {
+        return Ok(None);
+    }
+    let text = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    Ok(Some(super::batch::tail(&text, CHECK_TAIL_LINES)))
+}
+
+/// A config of `snippet` plus an `esphome:` block and `preset`'s chip, each
+/// unless the snippet has its own.
+fn scratch_config(preset: &NewDevicePreset, snippet: &str) -> String {
+    let keys: Vec<&str> = blocks(snippet)
+        .unwrap_or_default()
+        .iter()
+        .map(|b| b.key)
+        .collect();
+    let mut config = String::new();
+    if !keys.contains(&"esphome") {
+        config.push_str("esphome:\n  name: snippet-check\n\n");
+    }
+    if !keys.iter().any(|k| PLATFORMS.contains(k)) {
+        config.push_str(&format!(
+            "{}:\n  board: {}\n\n",
+            preset.platform, preset.board
+        ));
+    }
+    config.push_str(snippet);
+    config
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tells_snippets_from_other_text() {
+        assert!(is_snippet(
+            "# Example configuration entry\nsensor:\n  - platform: dht\n    pin: D2\n"
+        ));
+        assert!(is_snippet(
+            "wifi:\n  ssid: !secret wifi_ssid\n\ncaptive_portal:\n"
+        ));
+        assert!(!is_snippet("name: build\non:\n  push:\n"));
+        assert!(!is_snippet("Just a sentence: nothing more"));
+        assert!(!is_snippet("  - platform: dht\n    pin: D2\n"));
+        assert!(!is_snippet("sensor:\n\t- platform: dht\n"));
+    }
+
+    #[test]
+    fn merges_into_lists_and_appends_the_rest() {
+        let config = "esphome:\n  name: porch\n\nsensor:\n    - platform: uptime\n      name: Uptime\n\nwifi:\n  ssid: home\n";
+        let snippet = "sensor:\n  - platform: dht\n    pin: D2\n    temperature:\n      name: Temp\n\nbutton:\n  - platform: restart\n";
+        assert_eq!(
+            merge(config, snippet).unwrap(),
+            "esphome:\n  name: porch\n\nsensor:\n    - platform: uptime\n      name: Uptime\n    - platform: dht\n      pin: D2\n      temperature:\n        name: Temp\n\nwifi:\n  ssid: home\n\nbutton:\n  - platform: restart\n"
+        );
+        assert!(merge(config, "wifi:\n  ssid: other\n").is_err());
+    }
+
+    #[test]
+    fn the_scratch_config_fills_in_only_what_is_missing() {
+        let preset = NewDevicePreset::default();
+        let config = scratch_config(&preset, "i2c:\n  sda: 21\n");
+        assert!(
+            config.starts_with("esphome:\n  name: snippet-check\n\nesp32:\n  board: esp32dev\n\n")
+        );
+        let config = scratch_config(&preset, "esp8266:\n  board: d1_mini\n");
+        assert!(!config.contains("esp32:"));
+    }
+}
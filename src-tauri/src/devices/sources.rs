@@ -0,0 +1,243 @@
+//! External component sources and the user's allow-list of them.
+//!
+//! An `external_components:` entry pulls Python from a git repository, and
+//! ESPHome runs that code while it validates and generates the config, on
+//! this machine. The allow-list (`component_sources.json` in the data
+//! directory) names the repositories the user trusts; batch actions warn
+//! about, or with `block` set refuse, a config pulling from anywhere else.
+//!
+//! Configs are scanned line by line rather than parsed: only the top-level
+//! `external_components:` block of the device's own file is seen, not ones
+//! brought in by `!include` or `packages:`.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use crate::util::ConfigStore;
+
+/// File name of the allow-list inside the data directory.
+pub(crate) const SOURCES_FILE_NAME: &str = "component_sources.json";
+
+/// What a missing allow-list allows: ESPHome's own repositories, which
+/// `github://pr#...` sources come from.
+const DEFAULT_ALLOWED: &str = "github.com/esphome";
+
+/// The allow-list.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct AllowList {
+    /// Allowed sources. An entry covers itself and everything beneath it, so
+    /// `github.com/someone` allows all of their repositories.
+    #[serde(default)]
+    allowed: BTreeSet<String>,
+    /// Refuse to run batch actions on configs with unlisted sources, rather
+    /// than only warning
+    #[serde(default)]
+    pub block: bool,
+}
+
+impl Default for AllowList {
+    fn default() -> Self {
+        Self {
+            allowed: BTreeSet::from([DEFAULT_ALLOWED.to_string()]),
+            block: false,
+        }
+    }
+}
+
+impl ConfigStore for AllowList {
+    const FILE_NAME: &'static str = SOURCES_FILE_NAME;
+    const WHAT: &'static str = "allow-list";
+}
+
+impl AllowList {
+    /// The allowed sources.
+    pub(crate) fn allowed(&self) -> &BTreeSet<String> {
+        &self.allowed
+    }
+
+    /// Allow `source`, in any form a config would write it. Returns `false`
+    /// if it isn't a git source.
+    pub(crate) fn allow(&mut self, source: &str) -> bool {
+        let Some(source) = normalize_entry(source) else {
+            return false;
+        };
+        self.allowed.insert(source);
+        true
+    }
+
+    /// Stop allowing `source`. Returns whether it was on the list.
+    pub(crate) fn disallow(&mut self, source: &str) -> bool {
+        normalize_entry(source).is_some_and(|s| self.allowed.remove(&s))
+    }
+
+    /// Whether the (normalized) `source` is allowed.
+    pub(crate) fn allows(&self, source: &str) -> bool {
+        self.allowed.iter().any(|entry| {
+            source == entry
+                || source
+                    .strip_prefix(entry.as_str())
+                    .is_some_and(|rest| rest.starts_with('/'))
+        })
+    }
+
+    /// The sources in `config` that aren't allowed.
+    pub(crate) fn unlisted(&self, config: &Path) -> Result<Vec<String>> {
+        let yaml = std::fs::read_to_string(config)
+            .with_context(|| format!("Failed to read {config:?}"))?;
+        Ok(scan(&yaml)
+            .into_iter()
+            .filter(|s| !self.allows(s))
+            .collect())
+    }
+}
+
+/// The git sources of the top-level `external_components:` block, normalized
+/// to `host/owner/repo`. Local sources are skipped.
+pub(crate) fn scan(yaml: &str) -> Vec<String> {
+    let mut sources = Vec::new();
+    let mut in_block = false;
+    // Column of a `source:` key whose value is a nested mapping.
+    let mut nested_at: Option<usize> = None;
+    for line in yaml.lines().map(strip_comment) {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let indent = line.len() - trimmed.len();
+        if indent == 0 {
+            in_block = trimmed.trim_end() == "external_components:";
+            nested_at = None;
+            continue;
+        }
+        if !in_block {
+            continue;
+        }
+        let content = trimmed.strip_prefix("- ").unwrap_or(trimmed).trim_start();
+        let column = indent + trimmed.len() - content.len();
+        if let Some(at) = nested_at {
+            if column <= at {
+                nested_at = None;
+            } else if let Some(url) = content.strip_prefix("url:") {
+                sources.extend(normalize(url));
+                nested_at = None;
+                continue;
+            }
+        }
+        if let Some(value) = content.strip_prefix("source:") {
+            if value.trim().is_empty() {
+                nested_at = Some(column);
+            } else {
+                sources.extend(normalize(value));
+            }
+        }
+    }
+    sources.sort();
+    sources.dedup();
+    sources
+}
+
+/// `line` without a trailing comment. YAML needs whitespace before `#`, which
+/// keeps the one in `github://pr#123`.
+pub(super) fn strip_comment(line: &str) -> &str {
+    if line.trim_start().starts_with('#') {
+        return "";
+    }
+    line.find(" #").map_or(line, |i| &line[..i])
+}
+
+/// A source value as `host/owner/repo`, or `None` for a local one.
+fn normalize(value: &str) -> Option<String> {
+    let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+    if value.starts_with("github://pr#") {
+        return Some("github.com/esphome/esphome".to_string());
+    }
+    let rest = if let Some(rest) = value.strip_prefix("github://") {
+        // `owner/repo@ref`
+        format!("github.com/{}", rest.split('@').next().unwrap_or(rest))
+    } else if let Some((_, rest)) = value.split_once("://") {
+        // Drop any `user@` in front of the host.
+        rest.rsplit_once('@')
+            .filter(|(user, _)| !user.contains('/'))
+            .map_or(rest, |(_, host)| host)
+            .to_string()
+    } else if let Some(rest) = value.strip_prefix("git@") {
+        rest.replacen(':', "/", 1)
+    } else {
+        return None;
+    };
+    Some(trim_repo(&rest))
+}
+
+/// An allow-list entry the way [`normalize`] writes sources. A bare
+/// `host/owner[/repo]` is taken as it is, as long as it starts with a host.
+fn normalize_entry(entry: &str) -> Option<String> {
+    let entry = entry.trim();
+    normalize(entry).or_else(|| {
+        let (host, _) = entry.split_once('/')?;
+        host.contains('.').then(|| trim_repo(entry))
+    })
+}
+
+fn trim_repo(path: &str) -> String {
+    let path = path.trim_end_matches('/');
+    path.strip_suffix(".git")
+        .unwrap_or(path)
+        .trim_end_matches('/')
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+esphome:
+  name: porch
+  # source: https://example.com/not-this-one
+
+external_components:
+  - source: github://pr#2639 # a fix
+    components: [ bme280 ]
+  - source: github://Someone/Sensors@v1.2
+  - source:
+      type: git
+      url: https://user@gitlab.com/team/widgets.git
+      ref: main
+  - source: git@github.com:other/thing.git
+  - source: my_components
+  - source:
+      type: local
+      path: components
+
+sensor:
+  - platform: bme280
+    source: https://example.com/also-not-this-one
+"#;
+
+    #[test]
+    fn scans_git_sources_of_the_external_components_block() {
+        assert_eq!(
+            scan(CONFIG),
+            [
+                "github.com/esphome/esphome",
+                "github.com/other/thing",
+                "github.com/someone/sensors",
+                "gitlab.com/team/widgets",
+            ]
+        );
+    }
+
+    #[test]
+    fn entries_cover_their_subtree() {
+        let mut list = AllowList::default();
+        assert!(list.allows("github.com/esphome/esphome"));
+        assert!(!list.allows("github.com/esphome-fork/esphome"));
+        assert!(list.allow("https://github.com/Someone/"));
+        assert!(list.allows("github.com/someone/sensors"));
+        assert!(!list.allow("my_components"));
+        assert!(list.disallow("github.com/someone"));
+        assert!(!list.allows("github.com/someone/sensors"));
+    }
+}
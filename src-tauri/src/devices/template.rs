@@ -0,0 +1,180 @@
+//! New device configs from the user's preset (`new_device`), so every new
+//! YAML starts with their board, framework and secret names instead of
+//! ESPHome's generic ones.
+//!
+//! Only the `device new` subcommand uses this: the dashboard's own wizard
+//! is ESPHome's and writes its own defaults.
+
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+
+use crate::settings::NewDevicePreset;
+
+/// Platforms that take a `board:` block of their own name.
+pub(super) const PLATFORMS: &[&str] =
+    &["esp32", "esp8266", "rp2040", "bk72xx", "rtl87xx", "ln882x"];
+
+/// ESPHome's cap on a device name, which becomes its hostname.
+const MAX_NAME_LEN: usize = 31;
+
+/// Write `<name>.yaml` in `config_dir` from `preset`, refusing to replace a
+/// device that exists, as a change undo can take back in `data_dir`.
+pub(crate) fn create(
+    config_dir: &Path,
+    data_dir: &Path,
+    name: &str,
+    preset: &NewDevicePreset,
+) -> Result<PathBuf> {
+    check_name(name)?;
+    if super::config_path(config_dir, name).is_some() {
+        bail!("{name} already has a config");
+    }
+    let api_key = if preset.api_encryption {
+        let mut key = [0u8; 32];
+        crate::platform::random_bytes(&mut key)?;
+        Some(base64(&key))
+    } else {
+        None
+    };
+    let yaml = render(name, preset, api_key.as_deref())?;
+    let path = config_dir.join(format!("{name}.yaml"));
+    std::fs::create_dir_all(config_dir)
+        .with_context(|| format!("Failed to create {config_dir:?}"))?;
+    super::undo::transaction(data_dir, &format!("create {name}"), |change| {
+        change.write(&path, yaml)
+    })?;
+    Ok(path)
+}
+
+/// ESPHome's rule: lowercase letters, digits and hyphens, not at the ends.
+pub(super) fn check_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && !name.starts_with('-')
+        && !name.ends_with('-')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+    if !valid {
+        bail!(
+            "{name:?} isn't a valid device name: use up to {MAX_NAME_LEN} lowercase letters, \
+             digits and hyphens, e.g. kitchen-sensor"
+        );
+    }
+    Ok(())
+}
+
+fn render(name: &str, preset: &NewDevicePreset, api_key: Option<&str>) -> Result<String> {
+    let platform = preset.platform.as_str();
+    if !PLATFORMS.contains(&platform) {
+        bail!(
+            "unknown platform {platform:?} (use {})",
+            PLATFORMS.join(", ")
+        );
+    }
+    let mut yaml = format!(
+        "esphome:\n  name: {name}\n  friendly_name: {}\n\n{platform}:\n  board: {}\n",
+        friendly_name(name),
+        preset.board
+    );
+    if let (Some(framework), "esp32") = (&preset.framework, platform) {
+        yaml += &format!("  framework:\n    type: {framework}\n");
+    }
+    yaml += "\nlogger:\n\napi:\n";
+    if let Some(key) = api_key {
+        yaml += &format!("  encryption:\n    key: \"{key}\"\n");
+    }
+    yaml += &format!(
+        "\nota:\n  - platform: esphome\n\nwifi:\n  ssid: !secret {}\n  password: !secret {}\n",
+        preset.wifi_ssid_secret, preset.wifi_password_secret
+    );
+    Ok(yaml)
+}
+
+/// `kitchen-sensor` → `Kitchen Sensor`.
+fn friendly_name(name: &str) -> String {
+    name.split('-')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Standard base64 with padding, the form ESPHome expects API keys in.
+pub(crate) fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::new();
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (u32::from(b) << (16 - 8 * i)));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[((n >> (18 - 6 * i)) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_the_preset() {
+        let preset = NewDevicePreset {
+            board: "esp32-c3-devkitm-1".into(),
+            framework: Some("esp-idf".into()),
+            wifi_ssid_secret: "iot_ssid".into(),
+            ..Default::default()
+        };
+        let yaml = render("porch-light", &preset, Some("a2V5")).unwrap();
+        assert!(yaml.starts_with("esphome:\n  name: porch-light\n  friendly_name: Porch Light\n"));
+        assert!(
+            yaml.contains("esp32:\n  board: esp32-c3-devkitm-1\n  framework:\n    type: esp-idf\n")
+        );
+        assert!(yaml.contains("  encryption:\n    key: \"a2V5\"\n"));
+        assert!(yaml.contains("ssid: !secret iot_ssid\n"));
+
+        let esp8266 = NewDevicePreset {
+            platform: "esp8266".into(),
+            board: "d1_mini".into(),
+            framework: Some("esp-idf".into()),
+            ..Default::default()
+        };
+        let yaml = render("plug", &esp8266, None).unwrap();
+        assert!(yaml.contains("esp8266:\n  board: d1_mini\n\n"));
+        assert!(!yaml.contains("encryption"));
+    }
+
+    #[test]
+    fn names_follow_esphome_rules() {
+        assert!(check_name("kitchen-sensor-2").is_ok());
+        for bad in [
+            "",
+            "Kitchen",
+            "kitchen_sensor",
+            "-kitchen",
+            "a".repeat(32).as_str(),
+        ] {
+            assert!(check_name(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn base64_pads_like_the_standard() {
+        assert_eq!(base64(b"key"), "a2V5");
+        assert_eq!(base64(b"keys"), "a2V5cw==");
+        assert_eq!(base64(&[0xff; 32]).len(), 44);
+    }
+}
@@ -0,0 +1,351 @@
+//! Undo for what the app does to the config directory.
+//!
+//! Renames, archives and restores, added snippets and new or recovered
+//! configs each run as one [`transaction`]: every file it writes, moves or
+//! removes, in the config directory or the notes and registry it updates
+//! alongside, is recorded, with a copy of what it wrote over or removed, in
+//! `undo/` in the app data. A change that fails part way is rolled back; one
+//! that succeeds goes on a stack of the last [`MAX_CHANGES`], and [`undo`]
+//! reverts the latest. Nothing the app removes from the config directory is
+//! deleted outright: it goes to the OS trash, or to `undo/trash/` in the app
+//! data when there is none. Build files, which a compile recreates, go to the
+//! trash too but aren't kept for undo.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+/// Directory of the stack and the copies, in the data directory.
+pub(crate) const UNDO_DIR: &str = "undo";
+
+/// File of the stack, in [`UNDO_DIR`].
+const STACK_FILE: &str = "changes.json";
+
+/// Changes kept; older ones can't be undone.
+const MAX_CHANGES: usize = 20;
+
+/// One file operation, as undo needs it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum Step {
+    /// A file that wasn't there before.
+    Created { path: PathBuf },
+    /// A file written over; `backup` is what it was.
+    Replaced { path: PathBuf, backup: PathBuf },
+    /// A file removed; `backup` is a copy.
+    Removed { path: PathBuf, backup: PathBuf },
+    /// A file or directory moved.
+    Moved { from: PathBuf, to: PathBuf },
+}
+
+/// A change on the stack.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Entry {
+    id: String,
+    /// When it was made, `YYYY-MM-DDTHH:MM:SSZ`.
+    pub time: String,
+    /// What it was, e.g. `rename porch to front-porch`.
+    pub label: String,
+    steps: Vec<Step>,
+}
+
+/// A change being made: what [`transaction`] hands its closure.
+pub(crate) struct Change {
+    /// Where this change's copies go.
+    dir: PathBuf,
+    /// Where removed files go without an OS trash.
+    trash: PathBuf,
+    steps: Vec<Step>,
+}
+
+impl Change {
+    /// Write `contents` to `path`, keeping what was there.
+    pub(crate) fn write(&mut self, path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+        self.track(path)?;
+        crate::util::atomic_write(path, contents)
+            .with_context(|| format!("Failed to write {path:?}"))
+    }
+
+    /// Keep `path` as it is before something else writes it, such as a
+    /// store's own `save`, so undo puts it back.
+    pub(crate) fn track(&mut self, path: &Path) -> Result<()> {
+        let step = if path.is_file() {
+            Step::Replaced {
+                path: path.to_path_buf(),
+                backup: self.backup(path)?,
+            }
+        } else {
+            Step::Created {
+                path: path.to_path_buf(),
+            }
+        };
+        self.steps.push(step);
+        Ok(())
+    }
+
+    /// Remove the file at `path` to the trash, keeping a copy for undo.
+    pub(crate) fn remove(&mut self, path: &Path) -> Result<()> {
+        let backup = self.backup(path)?;
+        dispose(path, &self.trash)?;
+        self.steps.push(Step::Removed {
+            path: path.to_path_buf(),
+            backup,
+        });
+        Ok(())
+    }
+
+    /// Move the file or directory `from` to `to`, which mustn't exist.
+    pub(crate) fn rename(&mut self, from: &Path, to: &Path) -> Result<()> {
+        if to.exists() {
+            bail!("{to:?} is in the way");
+        }
+        if let Some(parent) = to.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {parent:?}"))?;
+        }
+        std::fs::rename(from, to).with_context(|| format!("Failed to move {from:?} to {to:?}"))?;
+        self.steps.push(Step::Moved {
+            from: from.to_path_buf(),
+            to: to.to_path_buf(),
+        });
+        Ok(())
+    }
+
+    /// Throw away build files at `path`: to the trash where there is one,
+    /// and not kept for undo, as a compile makes them again.
+    pub(crate) fn discard(&mut self, path: &Path) -> Result<()> {
+        if let Err(e) = to_os_trash(path) {
+            warn!(
+                "Failed to move {:?} to the trash, removing it: {:#}",
+                path, e
+            );
+            if path.is_dir() {
+                std::fs::remove_dir_all(path)
+            } else {
+                std::fs::remove_file(path)
+            }
+            .with_context(|| format!("Failed to remove {path:?}"))?;
+        }
+        Ok(())
+    }
+
+    fn backup(&mut self, path: &Path) -> Result<PathBuf> {
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create {:?}", self.dir))?;
+        let backup = self.dir.join(self.steps.len().to_string());
+        std::fs::copy(path, &backup).with_context(|| format!("Failed to copy {path:?}"))?;
+        Ok(backup)
+    }
+}
+
+/// Run `f` as one change labelled `label`, with its undo record in
+/// `data_dir`: rolled back if it fails, else put on the stack.
+pub(crate) fn transaction<T>(
+    data_dir: &Path,
+    label: &str,
+    f: impl FnOnce(&mut Change) -> Result<T>,
+) -> Result<T> {
+    let root = data_dir.join(UNDO_DIR);
+    let now = SystemTime::now();
+    let id = now
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_micros())
+        .to_string();
+    let mut change = Change {
+        dir: root.join(&id),
+        trash: root.join("trash"),
+        steps: Vec::new(),
+    };
+    let result = f(&mut change);
+    let Change { dir, trash, steps } = change;
+    let value = match result {
+        Ok(value) => value,
+        Err(e) => {
+            if let Err(rollback) = revert(&steps, &trash) {
+                warn!("Failed to roll back {}: {:#}", label, rollback);
+            }
+            let _ = std::fs::remove_dir_all(&dir);
+            return Err(e);
+        }
+    };
+    if steps.is_empty() {
+        return Ok(value);
+    }
+    let mut stack = load(&root)?;
+    stack.push(Entry {
+        id,
+        time: crate::audit::rfc3339(now),
+        label: label.to_string(),
+        steps,
+    });
+    let excess = stack.len().saturating_sub(MAX_CHANGES);
+    for old in stack.drain(..excess) {
+        let _ = std::fs::remove_dir_all(root.join(&old.id));
+    }
+    save(&root, &stack)?;
+    Ok(value)
+}
+
+/// The latest change, the one [`undo`] would revert.
+pub(crate) fn last(data_dir: &Path) -> Result<Option<Entry>> {
+    Ok(load(&data_dir.join(UNDO_DIR))?.pop())
+}
+
+/// Revert the latest change. Returns it, or `None` with nothing to undo. A
+/// change that can't be reverted, say because a file it would put back is
+/// there again, stays on the stack.
+pub(crate) fn undo(data_dir: &Path) -> Result<Option<Entry>> {
+    let root = data_dir.join(UNDO_DIR);
+    let mut stack = load(&root)?;
+    let Some(entry) = stack.pop() else {
+        return Ok(None);
+    };
+    revert(&entry.steps, &root.join("trash"))
+        .with_context(|| format!("Failed to undo {}", entry.label))?;
+    save(&root, &stack)?;
+    let _ = std::fs::remove_dir_all(root.join(&entry.id));
+    Ok(Some(entry))
+}
+
+/// Undo `steps`, last first.
+fn revert(steps: &[Step], trash: &Path) -> Result<()> {
+    for step in steps.iter().rev() {
+        match step {
+            Step::Created { path } => {
+                if path.exists() {
+                    dispose(path, trash)?;
+                }
+            }
+            Step::Replaced { path, backup } => {
+                if path.exists() {
+                    dispose(path, trash)?;
+                }
+                std::fs::copy(backup, path)
+                    .with_context(|| format!("Failed to restore {path:?}"))?;
+            }
+            Step::Removed { path, backup } => {
+                if path.exists() {
+                    bail!("{path:?} is there again; move it away first");
+                }
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)
+                        .with_context(|| format!("Failed to create {parent:?}"))?;
+                }
+                std::fs::copy(backup, path)
+                    .with_context(|| format!("Failed to restore {path:?}"))?;
+            }
+            Step::Moved { from, to } => {
+                if from.exists() {
+                    bail!("{from:?} is there again; move it away first");
+                }
+                std::fs::rename(to, from)
+                    .with_context(|| format!("Failed to move {to:?} back to {from:?}"))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Move the file at `path` to the OS trash or, failing that, into `trash`.
+fn dispose(path: &Path, trash: &Path) -> Result<()> {
+    let Err(e) = to_os_trash(path) else {
+        return Ok(());
+    };
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_micros());
+    let target = trash.join(format!("{stamp}-{name}"));
+    warn!(
+        "Failed to move {:?} to the trash, keeping it as {:?}: {:#}",
+        path, target, e
+    );
+    std::fs::create_dir_all(trash).with_context(|| format!("Failed to create {trash:?}"))?;
+    // The data directory may be on another file system than the configs.
+    if std::fs::rename(path, &target).is_err() {
+        std::fs::copy(path, &target).with_context(|| format!("Failed to copy {path:?}"))?;
+        std::fs::remove_file(path).with_context(|| format!("Failed to remove {path:?}"))?;
+    }
+    Ok(())
+}
+
+fn to_os_trash(path: &Path) -> Result<()> {
+    // Tests' files have no business in the user's trash.
+    if cfg!(test) {
+        bail!("no trash in tests");
+    }
+    crate::platform::trash::move_to_trash(path)
+}
+
+fn load(root: &Path) -> Result<Vec<Entry>> {
+    let path = root.join(STACK_FILE);
+    match std::fs::read_to_string(&path) {
+        Ok(content) => {
+            serde_json::from_str(&content).with_context(|| format!("Failed to parse {path:?}"))
+        }
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {path:?}")),
+    }
+}
+
+fn save(root: &Path, stack: &[Entry]) -> Result<()> {
+    std::fs::create_dir_all(root).with_context(|| format!("Failed to create {root:?}"))?;
+    let content = serde_json::to_string_pretty(stack).context("Failed to serialize changes")?;
+    crate::util::atomic_write(root.join(STACK_FILE), content)
+        .context("Failed to write the undo stack")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn undoes_changes_last_first_and_rolls_back_failures() {
+        let dir = crate::util::unique_temp_dir("undo");
+        let (config_dir, data_dir) = (dir.join("config"), dir.join("data"));
+        fs::create_dir_all(&config_dir).unwrap();
+        let porch = config_dir.join("porch.yaml");
+        fs::write(&porch, "esphome:\n  name: porch\n").unwrap();
+
+        let moved = config_dir.join("archive").join("porch.yaml");
+        transaction(&data_dir, "archive porch", |change| {
+            change.rename(&porch, &moved)
+        })
+        .unwrap();
+        let garage = config_dir.join("garage.yaml");
+        transaction(&data_dir, "create garage", |change| {
+            change.write(&garage, "esphome:\n  name: garage\n")?;
+            change.write(&moved, "esphome:\n  name: old-porch\n")
+        })
+        .unwrap();
+        let failed = transaction(&data_dir, "remove garage", |change| {
+            change.remove(&garage)?;
+            change.rename(&moved, &config_dir.join("archive"))
+        });
+        assert!(failed.is_err());
+        assert!(garage.is_file());
+        assert_eq!(last(&data_dir).unwrap().unwrap().label, "create garage");
+
+        assert_eq!(undo(&data_dir).unwrap().unwrap().label, "create garage");
+        assert!(!garage.exists());
+        assert_eq!(
+            fs::read_to_string(&moved).unwrap(),
+            "esphome:\n  name: porch\n"
+        );
+        assert_eq!(undo(&data_dir).unwrap().unwrap().label, "archive porch");
+        assert!(porch.is_file() && !moved.exists());
+        assert_eq!(undo(&data_dir).unwrap(), None);
+        // Nothing was deleted: what undo threw away is in the app's trash.
+        assert_eq!(
+            fs::read_dir(data_dir.join(UNDO_DIR).join("trash"))
+                .unwrap()
+                .count(),
+            3
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
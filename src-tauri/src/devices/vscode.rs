@@ -0,0 +1,220 @@
+//! A ready-made VS Code workspace in the config directory.
+//!
+//! Writes `.vscode/settings.json`, `extensions.json` and `tasks.json`, the
+//! setup the ESPHome docs walk through by hand: the ESPHome extension
+//! validating against this app's dashboard, ESPHome's YAML tags known to the
+//! YAML extension, build output hidden, and tasks that run the app's own
+//! ESPHome on the open config.
+//!
+//! Existing files are merged, not replaced: our keys, recommendations and
+//! tasks (by label) win, everything else the user has is kept. A file we
+//! can't parse (VS Code allows comments, serde_json doesn't) is left alone
+//! and reported.
+
+use anyhow::{Context, Result};
+use serde_json::{json, Map, Value};
+use std::io::ErrorKind;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+
+/// Extensions the workspace recommends.
+const EXTENSIONS: &[&str] = &["esphome.esphome-vscode", "redhat.vscode-yaml"];
+
+/// ESPHome's YAML tags, so the YAML extension doesn't flag them.
+const CUSTOM_TAGS: &[&str] = &[
+    "!secret scalar",
+    "!lambda scalar",
+    "!extend scalar",
+    "!remove scalar",
+    "!include scalar",
+    "!include mapping",
+    "!include_dir_list scalar",
+    "!include_dir_merge_list scalar",
+    "!include_dir_named scalar",
+    "!include_dir_merge_named scalar",
+];
+
+/// Tasks on the config open in the editor: label and ESPHome arguments.
+const TASKS: &[(&str, &[&str])] = &[
+    ("ESPHome: Validate", &["config", "${file}"]),
+    ("ESPHome: Compile", &["compile", "${file}"]),
+    ("ESPHome: Upload", &["run", "${file}"]),
+    ("ESPHome: Logs", &["logs", "${file}"]),
+];
+
+/// What [`set_up`] did.
+#[derive(Debug, Default)]
+pub(crate) struct Outcome {
+    /// Files written or updated.
+    pub written: Vec<PathBuf>,
+    /// Files left alone because they aren't plain JSON.
+    pub skipped: Vec<PathBuf>,
+}
+
+/// Set up `.vscode` in `config_dir` for a dashboard on `address`. The tasks run
+/// `python -m esphome`; without a host Python (any runtime but native) there
+/// are none.
+pub(crate) fn set_up(
+    config_dir: &Path,
+    address: SocketAddr,
+    python: Option<&Path>,
+) -> Result<Outcome> {
+    let dir = config_dir.join(".vscode");
+    std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {dir:?}"))?;
+    let mut outcome = Outcome::default();
+    let mut update = |name: &str, merge: &dyn Fn(&mut Map<String, Value>)| -> Result<()> {
+        let path = dir.join(name);
+        let Some(mut file) = load(&path)? else {
+            outcome.skipped.push(path);
+            return Ok(());
+        };
+        merge(&mut file);
+        let text = serde_json::to_string_pretty(&Value::Object(file))? + "\n";
+        crate::util::atomic_write(&path, text)?;
+        outcome.written.push(path);
+        Ok(())
+    };
+    update("settings.json", &|file| merge_settings(file, address))?;
+    update("extensions.json", &merge_extensions)?;
+    if let Some(python) = python {
+        update("tasks.json", &|file| merge_tasks(file, python))?;
+    }
+    Ok(outcome)
+}
+
+/// The file's JSON object: empty if there is no file, `None` if it isn't a
+/// plain JSON object.
+fn load(path: &Path) -> Result<Option<Map<String, Value>>> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => Ok(match serde_json::from_str(&text) {
+            Ok(Value::Object(map)) => Some(map),
+            _ => None,
+        }),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(Some(Map::new())),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {path:?}")),
+    }
+}
+
+fn merge_settings(file: &mut Map<String, Value>, address: SocketAddr) {
+    // The extension wants `host:port`, no scheme.
+    let url = crate::daemon::browser_url(address);
+    let uri = url.trim_start_matches("http://");
+    file.insert("esphome.validator".into(), json!("dashboard"));
+    file.insert("esphome.dashboardUri".into(), json!(uri));
+    file.insert("yaml.customTags".into(), json!(CUSTOM_TAGS));
+    let exclude = object(file, "files.exclude");
+    exclude.insert("**/.esphome".into(), json!(true));
+}
+
+fn merge_extensions(file: &mut Map<String, Value>) {
+    let list = array(file, "recommendations");
+    for id in EXTENSIONS {
+        if !list.iter().any(|v| v.as_str() == Some(id)) {
+            list.push(json!(id));
+        }
+    }
+}
+
+fn merge_tasks(file: &mut Map<String, Value>, python: &Path) {
+    file.insert("version".into(), json!("2.0.0"));
+    let list = array(file, "tasks");
+    list.retain(|task| {
+        !TASKS
+            .iter()
+            .any(|(label, _)| task["label"].as_str() == Some(label))
+    });
+    for (label, args) in TASKS {
+        let args: Vec<&str> = ["-m", "esphome"].iter().chain(*args).copied().collect();
+        let mut task = json!({
+            "label": label,
+            "type": "process",
+            "command": python,
+            "args": args,
+            "options": { "cwd": "${workspaceFolder}" },
+            "problemMatcher": [],
+        });
+        if *label == "ESPHome: Compile" {
+            task["group"] = json!("build");
+        }
+        list.push(task);
+    }
+}
+
+/// The object at `key`, replacing whatever else is there.
+fn object<'a>(file: &'a mut Map<String, Value>, key: &str) -> &'a mut Map<String, Value> {
+    let value = file.entry(key).or_insert_with(|| json!({}));
+    if !value.is_object() {
+        *value = json!({});
+    }
+    value.as_object_mut().expect("just made an object")
+}
+
+/// The array at `key`, replacing whatever else is there.
+fn array<'a>(file: &'a mut Map<String, Value>, key: &str) -> &'a mut Vec<Value> {
+    let value = file.entry(key).or_insert_with(|| json!([]));
+    if !value.is_array() {
+        *value = json!([]);
+    }
+    value.as_array_mut().expect("just made an array")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::unique_temp_dir;
+    use std::fs;
+
+    #[test]
+    fn merges_into_existing_files_and_skips_ones_with_comments() {
+        let dir = unique_temp_dir("vscode");
+        let vscode = dir.join(".vscode");
+        fs::create_dir(&vscode).unwrap();
+        fs::write(
+            vscode.join("settings.json"),
+            r#"{"editor.tabSize": 2, "files.exclude": {"*.bak": true}}"#,
+        )
+        .unwrap();
+        fs::write(vscode.join("extensions.json"), "// mine\n{}").unwrap();
+
+        let outcome = set_up(
+            &dir,
+            ([127, 0, 0, 1], 6052).into(),
+            Some(Path::new("/venv/bin/python")),
+        )
+        .unwrap();
+        assert_eq!(outcome.skipped, [vscode.join("extensions.json")]);
+
+        let read = |name: &str| -> Value {
+            serde_json::from_str(&fs::read_to_string(vscode.join(name)).unwrap()).unwrap()
+        };
+        let settings = read("settings.json");
+        assert_eq!(settings["editor.tabSize"], 2);
+        assert_eq!(settings["esphome.dashboardUri"], "localhost:6052");
+        assert_eq!(settings["files.exclude"]["*.bak"], true);
+        assert_eq!(settings["files.exclude"]["**/.esphome"], true);
+        assert_eq!(read("tasks.json")["tasks"][1]["args"][2], "compile");
+
+        // Running it again replaces our tasks rather than adding more.
+        set_up(
+            &dir,
+            ([127, 0, 0, 1], 6052).into(),
+            Some(Path::new("/venv/bin/python")),
+        )
+        .unwrap();
+        assert_eq!(read("tasks.json")["tasks"].as_array().unwrap().len(), 4);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn recommendations_are_added_once() {
+        let mut file = Map::new();
+        file.insert("recommendations".into(), json!(["redhat.vscode-yaml"]));
+        merge_extensions(&mut file);
+        merge_extensions(&mut file);
+        assert_eq!(
+            file["recommendations"],
+            json!(["redhat.vscode-yaml", "esphome.esphome-vscode"])
+        );
+    }
+}
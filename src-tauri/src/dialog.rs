@@ -1,7 +1,9 @@
 //! Small helpers around `tauri-plugin-dialog`.
 
 use tauri::AppHandle;
-use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+use tauri_plugin_dialog::{
+    DialogExt, MessageDialogButtons, MessageDialogKind, MessageDialogResult,
+};
 
 /// Show a modal two-button confirmation dialog and wait for the user's choice.
 ///
@@ -33,6 +35,39 @@ pub(crate) async fn confirm(
     .unwrap_or(false)
 }
 
+/// Show a modal three-button dialog and wait for the user's choice: the
+/// index into `labels`, or `None` when the dialog failed. Closing it may read
+/// as the third button, so that should be the harmless one. Like
+/// [`confirm`], it runs on a blocking thread.
+pub(crate) async fn choose(
+    app_handle: &AppHandle,
+    title: &str,
+    message: String,
+    labels: [&str; 3],
+) -> Option<usize> {
+    let app = app_handle.clone();
+    let title = title.to_string();
+    let [first, second, third] = labels.map(str::to_string);
+    let result = tokio::task::spawn_blocking(move || {
+        app.dialog()
+            .message(message)
+            .title(title)
+            .buttons(MessageDialogButtons::YesNoCancelCustom(
+                first, second, third,
+            ))
+            .blocking_show_with_result()
+    })
+    .await
+    .ok()?;
+    // Platforms report a custom button either by its role or by its label.
+    match result {
+        MessageDialogResult::Yes | MessageDialogResult::Ok => Some(0),
+        MessageDialogResult::No => Some(1),
+        MessageDialogResult::Cancel => Some(2),
+        MessageDialogResult::Custom(label) => labels.iter().position(|l| *l == label),
+    }
+}
+
 /// Show a modal single-button notice (informational or error) and wait for the
 /// user to dismiss it. Like [`confirm`], `blocking_show` is synchronous so it
 /// runs on a blocking thread; the result is discarded since a notice has nothing
@@ -55,3 +90,48 @@ pub(crate) async fn notice(
     })
     .await;
 }
+
+/// Show a native open-file dialog for a YAML file, starting in `dir`, and
+/// wait for the pick. `None` when the user cancels (or the dialog fails).
+pub(crate) async fn pick_yaml(
+    app_handle: &AppHandle,
+    title: &str,
+    dir: std::path::PathBuf,
+) -> Option<std::path::PathBuf> {
+    let app = app_handle.clone();
+    let title = title.to_string();
+    tokio::task::spawn_blocking(move || {
+        app.dialog()
+            .file()
+            .set_title(title)
+            .set_directory(dir)
+            .add_filter("YAML", &["yaml", "yml"])
+            .blocking_pick_file()
+    })
+    .await
+    .ok()
+    .flatten()
+    .and_then(|path| path.into_path().ok())
+}
+
+/// Show a native folder picker, starting in `dir`, and wait for the pick.
+/// `None` when the user cancels (or the dialog fails).
+pub(crate) async fn pick_folder(
+    app_handle: &AppHandle,
+    title: &str,
+    dir: std::path::PathBuf,
+) -> Option<std::path::PathBuf> {
+    let app = app_handle.clone();
+    let title = title.to_string();
+    tokio::task::spawn_blocking(move || {
+        app.dialog()
+            .file()
+            .set_title(title)
+            .set_directory(dir)
+            .blocking_pick_folder()
+    })
+    .await
+    .ok()
+    .flatten()
+    .and_then(|path| path.into_path().ok())
+}
@@ -0,0 +1,351 @@
+//! The opt-in weekly digest (`weekly_summary`): once a week a notification
+//! sums up the fleet, so it can be kept healthy without opening the
+//! dashboard every day. It counts the updates applied and the devices built
+//! or flashed (from the audit log and the size history), the devices whose
+//! last probe went unanswered (from the latency history, so only with
+//! `monitor_latency`), the configs `esphome config` rejects, and the disk the
+//! build caches and toolchains take.
+//!
+//! The app checks every [`CHECK_INTERVAL`] whether a week has passed since
+//! the last digest, kept in `digest.json` in the app data so a restart
+//! doesn't start the week over. The first comes a week after the setting is
+//! turned on. Validating every config holds ESPHome in place like an update
+//! does, so a digest due while one runs waits for the next check. The email
+//! digest ([`email`](crate::email)) sums up the same way on its own schedule.
+
+use std::future::Future;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tracing::{info, warn};
+
+use crate::cleanup::{self, Kind, Locations, Purpose};
+use crate::control::ops::UpdateGuard;
+use crate::devices::batch::{self, BatchAction};
+use crate::devices::discovery::LatencyHistory;
+use crate::devices::registry::Registry;
+use crate::devices::sizes::SizeHistory;
+use crate::devices::sources::AllowList;
+use crate::i18n::{t, t_with};
+use crate::tasks::Tasks;
+use crate::util::ConfigStore;
+
+/// File name of the digest's state inside the data directory.
+pub(crate) const DIGEST_FILE_NAME: &str = "digest.json";
+
+/// Time between checks for a digest being due.
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Time between digests.
+pub(crate) const WEEK: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// When the last digest went out.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Sent {
+    /// Seconds since the epoch.
+    #[serde(default)]
+    last: Option<u64>,
+}
+
+/// A week of the fleet.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct Digest {
+    /// Updates of ESPHome, the device builder or the app, from the tray or
+    /// the CLI.
+    pub updates: usize,
+    /// Devices with a build recorded.
+    pub built: Vec<String>,
+    /// Devices whose latest probe round lost every probe.
+    pub offline: Vec<String>,
+    /// Devices whose config failed validation.
+    pub failing: Vec<String>,
+    /// Bytes under the build caches and PlatformIO's toolchains.
+    pub cache_bytes: u64,
+}
+
+impl Digest {
+    /// The notification's body, a line per finding.
+    pub(crate) fn body(&self) -> String {
+        let mut lines = vec![t_with(
+            "digest.activity",
+            &[
+                ("updates", &self.updates.to_string()),
+                ("built", &self.built.len().to_string()),
+            ],
+        )];
+        if !self.offline.is_empty() {
+            lines.push(t_with(
+                "digest.offline",
+                &[("devices", &self.offline.join(", "))],
+            ));
+        }
+        if self.failing.is_empty() {
+            lines.push(t("digest.all_valid"));
+        } else {
+            lines.push(t_with(
+                "digest.failing",
+                &[("devices", &self.failing.join(", "))],
+            ));
+        }
+        lines.push(t_with(
+            "digest.caches",
+            &[("size", &cleanup::format_size(self.cache_bytes))],
+        ));
+        lines.join("\n")
+    }
+}
+
+/// What the logs in `data_dir` hold from `since` (`YYYY-MM-DDTHH:MM:SSZ`)
+/// on. Validation and the caches are left to the caller.
+pub(crate) fn collect(data_dir: &Path, since: &str) -> Result<Digest> {
+    let audit = crate::audit::read(&crate::audit::path(data_dir))?;
+    let sizes = SizeHistory::load(&SizeHistory::path(data_dir))?;
+    let latency = LatencyHistory::load(&LatencyHistory::path(data_dir))?;
+    Ok(Digest {
+        updates: audit
+            .entries
+            .iter()
+            .filter(|e| e.action == "update" && e.time.as_str() >= since)
+            .count(),
+        built: sizes
+            .latest()
+            .filter(|(_, sample)| sample.time.as_str() >= since)
+            .map(|(device, _)| device.to_string())
+            .collect(),
+        offline: latency
+            .devices()
+            .filter(|(_, rounds)| {
+                rounds
+                    .last()
+                    .is_some_and(|r| r.received == 0 && r.time.as_str() >= since)
+            })
+            .map(|(device, _)| device.to_string())
+            .collect(),
+        ..Digest::default()
+    })
+}
+
+/// Bytes the build caches and toolchains take.
+fn cache_bytes() -> u64 {
+    let Some(locations) = Locations::resolve() else {
+        return 0;
+    };
+    cleanup::targets(&locations, Purpose::Uninstall, false, true)
+        .iter()
+        .filter(|target| matches!(target.kind, Kind::BuildCache | Kind::Toolchains))
+        .map(|target| target.bytes)
+        .sum()
+}
+
+/// Post a digest every week for as long as `tasks` lives.
+pub(crate) fn spawn(app: AppHandle, tasks: &Tasks) {
+    every(
+        app,
+        tasks,
+        "weekly digest",
+        DIGEST_FILE_NAME,
+        WEEK,
+        |app, since| async move { send(&app, since).await },
+    );
+}
+
+/// Run `send` every `period` for as long as `tasks` lives, keeping when it
+/// last went out in `file` in the app data. `send` gets the time of the last
+/// one and returns whether it went out; one that didn't is tried again at
+/// the next check.
+pub(crate) fn every<F, Fut>(
+    app: AppHandle,
+    tasks: &Tasks,
+    what: &'static str,
+    file: &'static str,
+    period: Duration,
+    send: F,
+) where
+    F: Fn(AppHandle, SystemTime) -> Fut + Send + 'static,
+    Fut: Future<Output = bool> + Send + 'static,
+{
+    tasks.spawn(what, async move {
+        let data_dir = match crate::platform::get_data_dir(&app) {
+            Ok(dir) => dir,
+            Err(e) => {
+                warn!("Not sending the {}: {:#}", what, e);
+                return;
+            }
+        };
+        let path = data_dir.join(file);
+        loop {
+            let now = SystemTime::now();
+            let last = match load(&path) {
+                Ok(Sent { last: Some(last) }) => UNIX_EPOCH + Duration::from_secs(last),
+                Ok(Sent { last: None }) => {
+                    // Turned on just now: the first period starts here.
+                    save(&path, now);
+                    now
+                }
+                Err(e) => {
+                    warn!("Failed to read the {} state: {:#}", what, e);
+                    now
+                }
+            };
+            if now.duration_since(last).unwrap_or_default() >= period
+                && send(app.clone(), last).await
+            {
+                save(&path, now);
+            }
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
+
+/// Validate every config, sum up the week since `since` and show it.
+/// Returns whether a digest went out.
+async fn send(app: &AppHandle, since: SystemTime) -> bool {
+    let Some(digest) = summarize(app, since).await else {
+        return false;
+    };
+    info!("Weekly digest: {:?}", digest);
+    if let Err(e) = crate::notifications::show(app, t("digest.title"), digest.body()) {
+        warn!("Failed to show the weekly digest: {}", e);
+        return false;
+    }
+    true
+}
+
+/// Validate every config and sum up the fleet since `since`, or `None`,
+/// logged, when that can't be done now.
+pub(crate) async fn summarize(app: &AppHandle, since: SystemTime) -> Option<Digest> {
+    let Ok(state) = crate::window::app_state(app) else {
+        return None;
+    };
+    let Some(_guard) = UpdateGuard::try_acquire(state.update_in_flight.clone()) else {
+        info!("Update or batch in progress; the digest waits");
+        return None;
+    };
+    let config_dir = state.daemon.config_dir().clone();
+    let since = crate::audit::rfc3339(since);
+    let loaded = crate::platform::get_data_dir(app)
+        .map_err(anyhow::Error::from)
+        .and_then(|dir| {
+            let allow = AllowList::load(&AllowList::path(&dir))?;
+            let registry = Registry::load(&Registry::path(&dir))?;
+            let devices = crate::devices::list_devices(&config_dir)?;
+            Ok((dir, allow, registry, devices))
+        });
+    let (data_dir, allow, registry, devices) = match loaded {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            warn!("Failed to load the devices for the digest: {:#}", e);
+            return None;
+        }
+    };
+    let report = batch::run(
+        &state.daemon,
+        BatchAction::Validate,
+        &devices,
+        &allow,
+        &registry,
+        &data_dir,
+        &|_: &str, _: &str| {},
+    )
+    .await;
+    let collected = tokio::task::spawn_blocking(move || {
+        collect(&data_dir, &since).map(|digest| Digest {
+            cache_bytes: cache_bytes(),
+            ..digest
+        })
+    })
+    .await;
+    match collected {
+        Ok(Ok(digest)) => Some(Digest {
+            failing: report.failed,
+            ..digest
+        }),
+        Ok(Err(e)) => {
+            warn!("Failed to sum up the fleet: {:#}", e);
+            None
+        }
+        Err(e) => {
+            warn!("Digest panicked: {}", e);
+            None
+        }
+    }
+}
+
+fn load(path: &Path) -> Result<Sent> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => {
+            serde_json::from_str(&content).with_context(|| format!("Failed to parse {path:?}"))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Sent::default()),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {path:?}")),
+    }
+}
+
+/// Best-effort: a digest that can't be recorded goes out again next check.
+fn save(path: &Path, at: SystemTime) {
+    let sent = Sent {
+        last: Some(at.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())),
+    };
+    let written = serde_json::to_string(&sent)
+        .map_err(anyhow::Error::from)
+        .and_then(|content| crate::util::atomic_write(path, content));
+    if let Err(e) = written {
+        warn!("Failed to record the digest: {:#}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::Source;
+    use crate::devices::discovery::Round;
+    use crate::devices::sizes::Sample;
+    use crate::util::unique_temp_dir;
+
+    #[test]
+    fn counts_only_what_happened_since() {
+        let dir = unique_temp_dir("digest");
+        crate::audit::record_in(&dir, Source::Cli, "update", "ESPHome updated to 2026.10.0");
+        crate::audit::record_in(&dir, Source::Cli, "restart", "restarted");
+        let sizes = SizeHistory::path(&dir);
+        for (device, time) in [
+            ("porch", "2026-10-01T08:00:00Z"),
+            ("porch", "2026-10-14T08:00:00Z"),
+            ("garage", "2026-10-02T08:00:00Z"),
+        ] {
+            let sample = Sample {
+                time: time.into(),
+                esphome: None,
+                flash: None,
+                ram: None,
+            };
+            SizeHistory::record(&sizes, device, sample).unwrap();
+        }
+        let round = |time: &str, received| Round {
+            time: time.into(),
+            sent: 4,
+            received,
+            avg_ms: None,
+            max_ms: None,
+            address: None,
+        };
+        LatencyHistory::record(
+            &LatencyHistory::path(&dir),
+            vec![
+                ("porch".into(), round("2026-10-15T08:00:00Z", 4)),
+                ("garage".into(), round("2026-10-15T08:00:00Z", 0)),
+                ("shed".into(), round("2026-10-03T08:00:00Z", 0)),
+            ],
+        )
+        .unwrap();
+
+        let digest = collect(&dir, "2026-10-09T00:00:00Z").unwrap();
+        assert_eq!(digest.updates, 1);
+        assert_eq!(digest.built, ["porch"]);
+        assert_eq!(digest.offline, ["garage"]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,91 @@
+//! The "Duplicate Devices..." window: configs that give the same name or
+//! address, and devices added by address that are configured ones under
+//! another name, each with what to do about it (see
+//! [`crate::devices::duplicates`]). An added duplicate can be merged into the
+//! configured device from here. The page is `dist/duplicates.html`.
+
+use tauri::AppHandle;
+use tracing::{error, info};
+
+use crate::audit::{self, Source};
+use crate::devices::duplicates::{self, Report};
+use crate::devices::registry::Registry;
+use crate::i18n::{t, t_with};
+use crate::util::ConfigStore;
+use crate::window::app_state;
+
+/// Show the duplicates window, opening it if it isn't.
+pub(crate) fn open(app: &AppHandle) -> tauri::Result<()> {
+    let texts = serde_json::json!({
+        "intro": t("duplicates.intro"),
+        "none": t("duplicates.none"),
+        "names": t("duplicates.names"),
+        "names_help": t("duplicates.names_help"),
+        "addresses": t("duplicates.addresses"),
+        "addresses_help": t("duplicates.addresses_help"),
+        "added": t("duplicates.added"),
+        "added_help": t("duplicates.added_help"),
+        "merge": t("duplicates.merge"),
+        "merged": t("duplicates.merged"),
+    });
+    crate::window::open(
+        app,
+        "duplicates",
+        "duplicates.html",
+        t("duplicates.title"),
+        texts,
+    )
+}
+
+/// The duplicates, read afresh so fixes made since the window opened show.
+#[tauri::command]
+pub(crate) async fn find_duplicates(app: AppHandle) -> Result<Report, String> {
+    let config_dir = app_state(&app)?.daemon.config_dir().clone();
+    let data_dir = crate::platform::get_data_dir(&app).map_err(|e| e.to_string())?;
+    let report = tauri::async_runtime::spawn_blocking(move || {
+        let registry = Registry::load(&Registry::path(&data_dir))?;
+        duplicates::find(&config_dir, &registry)
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    report.map_err(|e| {
+        error!("Failed to look for duplicate devices: {:#}", e);
+        t_with("duplicates.failed", &[("error", &format!("{e:#}"))])
+    })
+}
+
+/// Fold the added device `name` into the configured `device`: its address
+/// and key secret become `device`'s, unless `device` has an entry of its own,
+/// and `name` goes.
+#[tauri::command]
+pub(crate) async fn merge_added(
+    app: AppHandle,
+    name: String,
+    device: String,
+) -> Result<(), String> {
+    let data_dir = crate::platform::get_data_dir(&app).map_err(|e| e.to_string())?;
+    let path = Registry::path(&data_dir);
+    let merged = Registry::load(&path).and_then(|mut registry| {
+        let entry = registry.get(&name).cloned();
+        registry.remove(&name);
+        if let Some(entry) = entry.filter(|_| registry.get(&device).is_none()) {
+            registry.add(&device, entry)?;
+        }
+        registry.save(&path)
+    });
+    if let Err(e) = merged {
+        error!("Failed to merge {} into {}: {:#}", name, device, e);
+        return Err(t_with(
+            "duplicates.merge_failed",
+            &[("error", &format!("{e:#}"))],
+        ));
+    }
+    info!("Merged added device {} into {}", name, device);
+    audit::record(
+        &app,
+        Source::Tray,
+        "merge_device",
+        format!("merged added device {name} into {device}"),
+    );
+    Ok(())
+}
@@ -0,0 +1,207 @@
+//! The email digest (`email_digest`): on an always-on builder nobody sits
+//! at, where notifications go unseen, a daily or weekly email sums up how the
+//! dashboard has been doing (whether it runs, and the restarts and crashes
+//! since the last email) along with the weekly digest's findings
+//! ([`digest`](crate::digest)): updates, builds, devices not answering,
+//! configs failing validation and cache disk use.
+//!
+//! It goes out over SMTP, with TLS from the start on port 465 and STARTTLS
+//! on any other. The login password lives in the OS keychain under
+//! [`SERVICE`], stored by `esphome-desktop email-password`, never in the
+//! settings. When it last went out is kept in `email_digest.json` in the app
+//! data; a send that fails is logged and tried again at the next check.
+
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Context, Result};
+use lettre::message::header::ContentType;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use tauri::AppHandle;
+use tracing::{info, warn};
+
+use crate::digest::{self, Digest};
+use crate::i18n::{t, t_with};
+use crate::settings::{DigestInterval, EmailDigest};
+use crate::store::{DaemonState, Restart, RestartReason, Snapshot};
+use crate::tasks::Tasks;
+
+/// File name of the email digest's state inside the data directory.
+pub(crate) const EMAIL_DIGEST_FILE_NAME: &str = "email_digest.json";
+
+/// The keychain service the SMTP password is filed under, by user name.
+const SERVICE: &str = "io.esphome.builder.smtp";
+
+/// Port that speaks TLS from the start; any other upgrades with STARTTLS.
+const SMTPS_PORT: u16 = 465;
+
+/// Mail submission port, used unless `smtp_port` says otherwise.
+const SUBMISSION_PORT: u16 = 587;
+
+/// How long the SMTP exchange may take.
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Store the SMTP `password` for `username`.
+pub(crate) fn store_password(username: &str, password: &str) -> Result<()> {
+    crate::platform::keychain::store(SERVICE, username, password)
+}
+
+/// Remove `username`'s SMTP password.
+pub(crate) fn delete_password(username: &str) -> Result<()> {
+    crate::platform::keychain::delete(SERVICE, username)
+}
+
+/// Mail a digest every day or week, per `settings`, for as long as `tasks`
+/// lives.
+pub(crate) fn spawn(app: AppHandle, tasks: &Tasks, settings: EmailDigest) {
+    let period = match settings.every {
+        DigestInterval::Daily => DAY,
+        DigestInterval::Weekly => digest::WEEK,
+    };
+    info!(
+        "Mailing the digest to {} ({:?})",
+        settings.to, settings.every
+    );
+    digest::every(
+        app,
+        tasks,
+        "email digest",
+        EMAIL_DIGEST_FILE_NAME,
+        period,
+        move |app, since| {
+            let settings = settings.clone();
+            async move { send(&app, &settings, since).await }
+        },
+    );
+}
+
+/// Sum up the time since `since` and mail it. Returns whether it went out.
+async fn send(app: &AppHandle, settings: &EmailDigest, since: SystemTime) -> bool {
+    let Ok(state) = crate::window::app_state(app) else {
+        return false;
+    };
+    let Some(digest) = digest::summarize(app, since).await else {
+        return false;
+    };
+    let body = body(
+        &state.store.snapshot(),
+        &crate::audit::rfc3339(since),
+        &digest,
+    );
+    let subject = match settings.every {
+        DigestInterval::Daily => t("email.subject_daily"),
+        DigestInterval::Weekly => t("email.subject_weekly"),
+    };
+    match deliver(settings, subject, body).await {
+        Ok(()) => {
+            info!("Mailed the digest to {}", settings.to);
+            true
+        }
+        Err(e) => {
+            warn!("Failed to mail the digest: {:#}", e);
+            false
+        }
+    }
+}
+
+/// The email's text: the dashboard's health since `since`
+/// (`YYYY-MM-DDTHH:MM:SSZ`), then the digest's findings.
+fn body(snapshot: &Snapshot, since: &str, digest: &Digest) -> String {
+    let health = match &snapshot.daemon {
+        DaemonState::Running => t("email.dashboard_running"),
+        DaemonState::Starting => t("email.dashboard_starting"),
+        DaemonState::Stopped => t("email.dashboard_stopped"),
+        DaemonState::Crashed(status) => t_with("email.dashboard_crashed", &[("status", status)]),
+    };
+    let restarts: Vec<&Restart> = snapshot
+        .restarts
+        .iter()
+        .filter(|restart| restart.time.as_str() >= since)
+        .collect();
+    let crashes = restarts
+        .iter()
+        .filter(|restart| restart.reason == RestartReason::CrashRecovery)
+        .count();
+    let restarts = t_with(
+        "email.restarts",
+        &[
+            ("count", &restarts.len().to_string()),
+            ("crashes", &crashes.to_string()),
+        ],
+    );
+    format!("{health}\n{restarts}\n\n{}\n", digest.body())
+}
+
+/// Send one email over SMTP.
+async fn deliver(settings: &EmailDigest, subject: String, body: String) -> Result<()> {
+    let from = settings.from.as_deref().unwrap_or(&settings.to);
+    let message = Message::builder()
+        .from(
+            from.parse()
+                .with_context(|| format!("Bad sender address {from:?}"))?,
+        )
+        .to(settings
+            .to
+            .parse()
+            .with_context(|| format!("Bad recipient address {:?}", settings.to))?)
+        .subject(subject)
+        .header(ContentType::TEXT_PLAIN)
+        .body(body)
+        .context("Failed to build the email")?;
+
+    let port = settings.smtp_port.unwrap_or(SUBMISSION_PORT);
+    let relay = if port == SMTPS_PORT {
+        AsyncSmtpTransport::<Tokio1Executor>::relay(&settings.smtp_host)
+    } else {
+        AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&settings.smtp_host)
+    };
+    let mut transport = relay
+        .with_context(|| format!("Bad SMTP server {:?}", settings.smtp_host))?
+        .port(port)
+        .timeout(Some(TIMEOUT));
+    if let Some(username) = &settings.username {
+        let password = crate::platform::keychain::load(SERVICE, username)?.with_context(|| {
+            format!("No SMTP password stored for {username}; run `esphome-desktop email-password`")
+        })?;
+        transport = transport.credentials(Credentials::new(username.clone(), password));
+    }
+    transport
+        .build()
+        .send(message)
+        .await
+        .with_context(|| format!("Failed to send through {}:{port}", settings.smtp_host))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn body_counts_restarts_since_the_last_email() {
+        let restart = |number, time: &str, reason| Restart {
+            number,
+            time: time.into(),
+            reason,
+        };
+        let snapshot = Snapshot {
+            daemon: DaemonState::Running,
+            restarts: vec![
+                restart(1, "2026-10-10T08:00:00Z", RestartReason::Update),
+                restart(2, "2026-10-15T08:00:00Z", RestartReason::CrashRecovery),
+                restart(3, "2026-10-15T09:00:00Z", RestartReason::User),
+            ],
+            ..Snapshot::default()
+        };
+        let body = body(&snapshot, "2026-10-15T00:00:00Z", &Digest::default());
+        let mut lines = body.lines();
+        assert_eq!(lines.next(), Some(t("email.dashboard_running").as_str()));
+        assert_eq!(
+            lines.next(),
+            Some(t_with("email.restarts", &[("count", "2"), ("crashes", "1")]).as_str())
+        );
+        assert!(body.ends_with(&format!("{}\n", Digest::default().body())));
+    }
+}
@@ -0,0 +1,26 @@
+//! Typed errors at module boundaries.
+//!
+//! Inside a module, failures are `anyhow` chains with context. Where one
+//! leaves the daemon, the updater or the platform layer it becomes a
+//! [`DaemonError`](crate::daemon::DaemonError),
+//! [`UpdateError`](crate::update::UpdateError) or
+//! [`PlatformError`](crate::platform::PlatformError), so callers can tell the
+//! cases apart without matching on message text. Each also implements
+//! [`UserError`], which is how every surface presents it:
+//!
+//! - logs, the audit log and the control channel's `message` use the English
+//!   `Display`, as before;
+//! - dialogs and notifications use [`UserError::user_message`], translated;
+//! - the control channel adds [`UserError::kind`] to its error replies, for
+//!   scripts that branch on what went wrong.
+
+/// An error with a stable identifier and a message for the UI.
+pub(crate) trait UserError: std::error::Error {
+    /// Machine-readable kind, `<area>.<case>` (`daemon.stop_timeout`). Part of
+    /// the control protocol: don't rename one.
+    fn kind(&self) -> &'static str;
+
+    /// What went wrong in the UI language, phrased to fill the `{error}` of a
+    /// dialog or notification.
+    fn user_message(&self) -> String;
+}
@@ -14,7 +14,6 @@
 use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
 use tauri::AppHandle;
-use tauri_plugin_notification::NotificationExt;
 use tracing::{info, warn};
 
 use crate::i18n::{t, t_with};
@@ -150,13 +149,7 @@ fn command_line_tools_installed() -> bool {
 
 /// Show the git-missing notification with the given body.
 fn show_git_missing_notification(app_handle: &AppHandle, body: String) {
-    if let Err(e) = app_handle
-        .notification()
-        .builder()
-        .title(t("git_check.missing_title"))
-        .body(body)
-        .show()
-    {
+    if let Err(e) = crate::notifications::show(app_handle, t("git_check.missing_title"), body) {
         warn!("Failed to show git-missing notification: {}", e);
     }
 }
@@ -340,13 +333,7 @@ pub fn notify_if_config_dir_in_git_repo(app_handle: &AppHandle, config_dir: &Pat
         ],
     );
 
-    if let Err(e) = app_handle
-        .notification()
-        .builder()
-        .title(t("git_check.parent_repo_title"))
-        .body(body)
-        .show()
-    {
+    if let Err(e) = crate::notifications::show(app_handle, t("git_check.parent_repo_title"), body) {
         warn!("Failed to show parent-git-repo notification: {}", e);
     }
 }
@@ -0,0 +1,193 @@
+//! Commands run on the app's events (`hooks` in the settings), to hook the
+//! app into the user's own automations, e.g. posting to a chat webhook. The
+//! command is split into words like `editor_command` and run without a
+//! shell, with environment variables describing the event:
+//!
+//! - `ESPHOME_EVENT`: the event's name, as in the settings
+//! - `ESPHOME_DEVICE`: the device, for `compile_failed` and `device_offline`
+//! - `ESPHOME_ACTION`: `compile` or `upload`, for `compile_failed`
+//! - `ESPHOME_COMPONENT`: `esphome` or `device-builder`, for `update_applied`
+//! - `ESPHOME_VERSION` and `ESPHOME_PREVIOUS_VERSION`, for `update_applied`
+//! - `ESPHOME_ERROR`: what went wrong, for `daemon_crashed` and
+//!   `compile_failed`
+//!
+//! Hooks are best-effort: one that fails or runs past [`TIMEOUT`] is logged
+//! and otherwise ignored, and nothing waits for one.
+
+use std::collections::BTreeMap;
+use std::process::Stdio;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use tokio::process::Command;
+use tracing::{info, warn};
+
+use crate::store::{self, DaemonState, Event, Store};
+
+/// How long a hook may run before it is killed.
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+/// An event a hook can run on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Hook {
+    /// The dashboard started (or restarted) and answers.
+    DaemonStarted,
+    /// The dashboard exited on its own.
+    DaemonCrashed,
+    /// A new ESPHome or device builder was installed.
+    UpdateApplied,
+    /// A compile or upload the app ran failed (not one from the dashboard,
+    /// whose output goes to the browser).
+    CompileFailed,
+    /// A device stopped answering the latency probes (`monitor_latency`).
+    DeviceOffline,
+}
+
+impl Hook {
+    const ALL: [Self; 5] = [
+        Self::DaemonStarted,
+        Self::DaemonCrashed,
+        Self::UpdateApplied,
+        Self::CompileFailed,
+        Self::DeviceOffline,
+    ];
+
+    /// The event's name in the settings and in `ESPHOME_EVENT`.
+    fn name(self) -> &'static str {
+        match self {
+            Self::DaemonStarted => "daemon_started",
+            Self::DaemonCrashed => "daemon_crashed",
+            Self::UpdateApplied => "update_applied",
+            Self::CompileFailed => "compile_failed",
+            Self::DeviceOffline => "device_offline",
+        }
+    }
+}
+
+/// The configured commands, by event name; set once at launch.
+static COMMANDS: OnceLock<BTreeMap<String, String>> = OnceLock::new();
+
+/// Run `hooks` on their events from now on, following the dashboard and
+/// the installed versions in `store`.
+pub(crate) fn init(hooks: &BTreeMap<String, String>, store: &Store) {
+    fn known(name: &str) -> bool {
+        Hook::ALL.iter().any(|hook| hook.name() == name)
+    }
+    for name in hooks.keys().filter(|name| !known(name)) {
+        warn!("Ignoring the hook for unknown event {:?}", name);
+    }
+    let hooks: BTreeMap<String, String> = hooks
+        .iter()
+        .filter(|(name, command)| known(name) && !command.trim().is_empty())
+        .map(|(name, command)| (name.clone(), command.clone()))
+        .collect();
+    if hooks.is_empty() {
+        return;
+    }
+    info!("Hooks on {:?}", hooks.keys().collect::<Vec<_>>());
+    let _ = COMMANDS.set(hooks);
+
+    // Subscribe before reading the snapshot, so nothing published in
+    // between is lost.
+    let events = store.subscribe();
+    let snapshot = store.snapshot();
+    let mut esphome = snapshot.esphome_version;
+    let mut builder = snapshot.builder_version;
+    store::listen(events, move |event| match event {
+        Event::Daemon(DaemonState::Running) => fire(Hook::DaemonStarted, &[]),
+        Event::Daemon(DaemonState::Crashed(reason)) => {
+            fire(Hook::DaemonCrashed, &[("ESPHOME_ERROR", &reason)]);
+        }
+        Event::EsphomeVersion(version) => updated("esphome", &mut esphome, version),
+        Event::BuilderVersion(version) => updated("device-builder", &mut builder, version),
+        _ => {}
+    });
+}
+
+/// Note that `component` is now at `version`, running `update_applied` when
+/// it moved on from the `last` one known.
+fn updated(component: &str, last: &mut Option<String>, version: String) {
+    if let Some(previous) = moved(last, &version) {
+        fire(
+            Hook::UpdateApplied,
+            &[
+                ("ESPHOME_COMPONENT", component),
+                ("ESPHOME_VERSION", &version),
+                ("ESPHOME_PREVIOUS_VERSION", &previous),
+            ],
+        );
+    }
+}
+
+/// Make `version` the `last` known, returning the one it replaces if it
+/// differs. Versions start with a digit; "unknown" and the like say nothing
+/// and are passed over.
+fn moved(last: &mut Option<String>, version: &str) -> Option<String> {
+    if !version.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    last.replace(version.to_string())
+        .filter(|previous| previous != version)
+}
+
+/// Run the hook on `hook`, if there is one, with `vars` in its environment.
+pub(crate) fn fire(hook: Hook, vars: &[(&str, &str)]) {
+    let Some(command) = COMMANDS.get().and_then(|hooks| hooks.get(hook.name())) else {
+        return;
+    };
+    let words = crate::devices::editor::split_command(command);
+    let Some((program, args)) = words.split_first() else {
+        return;
+    };
+    let mut cmd = Command::new(program);
+    cmd.args(args)
+        .env("ESPHOME_EVENT", hook.name())
+        .envs(vars.iter().copied())
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    crate::platform::configure_no_window_tokio_command(&mut cmd);
+    info!("Running the {} hook", hook.name());
+    tauri::async_runtime::spawn(async move {
+        let output = match cmd.spawn() {
+            Ok(child) => tokio::time::timeout(TIMEOUT, child.wait_with_output()).await,
+            Err(e) => {
+                warn!("Failed to run the {} hook: {}", hook.name(), e);
+                return;
+            }
+        };
+        match output {
+            Ok(Ok(output)) if output.status.success() => {}
+            Ok(Ok(output)) => warn!(
+                "The {} hook failed ({}): {}",
+                hook.name(),
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+            Ok(Err(e)) => warn!("Failed to wait for the {} hook: {}", hook.name(), e),
+            Err(_) => warn!(
+                "The {} hook ran past {:?} and was stopped",
+                hook.name(),
+                TIMEOUT
+            ),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn an_update_is_a_change_between_known_versions() {
+        let mut last = None;
+        // Detected at launch: nothing to compare with.
+        assert_eq!(moved(&mut last, "2025.1.0"), None);
+        assert_eq!(moved(&mut last, "2025.1.0"), None);
+        // Shown as unknown while the update runs.
+        assert_eq!(moved(&mut last, "unknown"), None);
+        assert_eq!(moved(&mut last, "2025.2.0"), Some("2025.1.0".into()));
+        assert_eq!(last.as_deref(), Some("2025.2.0"));
+    }
+}
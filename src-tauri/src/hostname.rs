@@ -0,0 +1,311 @@
+//! A friendly name for the dashboard (`dashboard_hostname`), so a bookmark
+//! names the dashboard rather than whatever address it listens on.
+//!
+//! * A name ending in `.local` is announced over mDNS for as long as the
+//!   app runs, by `avahi-publish` on Linux and Bonjour's `dns-sd` on macOS
+//!   and Windows, so other machines on the network resolve it too. That
+//!   needs a `listen_address` they can reach.
+//! * Any other name, such as `esphome.localhost`, is added to this
+//!   machine's hosts file, once, behind the system's administrator prompt.
+//!
+//! The port still goes in the URL. Until the name resolves, the tray and
+//! `status` keep opening the dashboard by address.
+
+use anyhow::{bail, Context, Result};
+use std::io::ErrorKind;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::AppHandle;
+use tokio::process::Command;
+use tracing::{info, warn};
+
+use crate::i18n::{t, t_with};
+use crate::platform::hosts;
+use crate::AppState;
+
+/// Ends the hosts file line the app adds, so it can be told from the
+/// user's own.
+const HOSTS_COMMENT: &str = "# added by ESPHome Device Builder";
+
+/// Records a name the user declined to add to the hosts file, so they are
+/// asked again only for another name.
+const DECLINED_MARKER: &str = ".hostname_declined";
+
+/// How long the announcer gets to fail (a name taken, no mDNS daemon)
+/// before it counts as announcing.
+const ANNOUNCE_SETTLE: Duration = Duration::from_secs(2);
+
+/// The name the dashboard is reachable at, once it is.
+#[derive(Default)]
+pub struct Hostname {
+    active: Mutex<Option<String>>,
+}
+
+impl Hostname {
+    /// The registered name, or `None` before it resolves.
+    pub(crate) fn active(&self) -> Option<String> {
+        self.lock().clone()
+    }
+
+    /// The URL to open the dashboard listening on `address` at: by name
+    /// once there is one.
+    pub(crate) fn url(&self, address: SocketAddr) -> String {
+        match self.active() {
+            Some(name) => format!("http://{name}:{}", address.port()),
+            None => crate::daemon::browser_url(address),
+        }
+    }
+
+    fn set(&self, name: Option<String>) {
+        *self.lock() = name;
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<String>> {
+        self.active.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Register `dashboard_hostname` at launch, when it is set.
+pub(crate) fn restore(app: &AppHandle, state: &Arc<AppState>) {
+    let app = app.clone();
+    let state = state.clone();
+    tauri::async_runtime::spawn(async move {
+        let name = state.settings.read().await.dashboard_hostname.clone();
+        let Some(name) = name.map(|n| n.trim().to_string()).filter(|n| !n.is_empty()) else {
+            return;
+        };
+        if !valid(&name) {
+            warn!(
+                "dashboard_hostname {:?} isn't a hostname; ignoring it",
+                name
+            );
+            return;
+        }
+        let address = state.daemon.address();
+        let registered = if is_mdns(&name) {
+            announce(&state, &name, address).await
+        } else {
+            add_to_hosts(&app, &name, address).await
+        };
+        match registered {
+            Ok(true) => {
+                info!("The dashboard is reachable as {}", name);
+                state.hostname.set(Some(name));
+            }
+            Ok(false) => {}
+            Err(e) => warn!("Dashboard hostname {} not registered: {:#}", name, e),
+        }
+    });
+}
+
+/// Whether `name` is a DNS hostname: dot-separated labels of letters,
+/// digits and inner hyphens.
+fn valid(name: &str) -> bool {
+    name.len() <= 253
+        && name.split('.').all(|label| {
+            (1..=63).contains(&label.len())
+                && label
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'-')
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+        })
+}
+
+fn is_mdns(name: &str) -> bool {
+    name.to_ascii_lowercase().ends_with(".local")
+}
+
+/// The address the hosts file maps `name` to, if it has a line for it.
+fn lookup(hosts: &str, name: &str) -> Option<IpAddr> {
+    hosts.lines().find_map(|line| {
+        let line = line.split('#').next().unwrap_or_default();
+        let mut fields = line.split_whitespace();
+        let ip = fields.next()?;
+        if fields.any(|field| field.eq_ignore_ascii_case(name)) {
+            ip.parse().ok()
+        } else {
+            None
+        }
+    })
+}
+
+/// `hosts` with a line mapping `name` to `ip` appended.
+fn with_entry(hosts: &str, name: &str, ip: IpAddr) -> String {
+    let mut text = hosts.to_string();
+    if !text.is_empty() && !text.ends_with('\n') {
+        text.push('\n');
+    }
+    text.push_str(&format!("{ip}\t{name}\t{HOSTS_COMMENT}\n"));
+    text
+}
+
+/// Map `name` to the dashboard in the hosts file, asking first. `false`
+/// when the user declines or the file maps it elsewhere already.
+async fn add_to_hosts(app: &AppHandle, name: &str, address: SocketAddr) -> Result<bool> {
+    let path = hosts::path();
+    let text =
+        std::fs::read_to_string(&path).with_context(|| format!("Failed to read {path:?}"))?;
+    let ip = crate::daemon::reachable(address).ip();
+    match lookup(&text, name) {
+        Some(mapped) if mapped == ip => return Ok(true),
+        Some(mapped) => {
+            warn!(
+                "{:?} maps {} to {}, not the dashboard's {}; leaving it alone",
+                path, name, mapped, ip
+            );
+            return Ok(false);
+        }
+        None => {}
+    }
+
+    let marker = crate::platform::get_data_dir(app)?.join(DECLINED_MARKER);
+    if std::fs::read_to_string(&marker).is_ok_and(|declined| declined == name) {
+        return Ok(false);
+    }
+    let agreed = crate::dialog::confirm(
+        app,
+        &t("hostname.title"),
+        t_with(
+            "hostname.prompt",
+            &[("name", name), ("ip", &ip.to_string())],
+        ),
+        &t("hostname.add"),
+        &t("common.cancel"),
+    )
+    .await;
+    if !agreed {
+        info!("Adding {} to the hosts file declined", name);
+        if let Err(e) = std::fs::write(&marker, name) {
+            warn!("Failed to write hostname-prompt marker: {}", e);
+        }
+        return Ok(false);
+    }
+
+    let updated = with_entry(&text, name, ip);
+    tokio::task::spawn_blocking(move || hosts::write_elevated(&updated)).await??;
+    // A declined prompt doesn't always show in the exit status.
+    let text =
+        std::fs::read_to_string(&path).with_context(|| format!("Failed to read {path:?}"))?;
+    if lookup(&text, name) != Some(ip) {
+        bail!("{path:?} still doesn't map {name}");
+    }
+    Ok(true)
+}
+
+/// Announce `name` over mDNS until the app exits.
+async fn announce(state: &Arc<AppState>, name: &str, address: SocketAddr) -> Result<bool> {
+    let Some(ip) = network_address(address) else {
+        bail!(
+            "listen_address {} can't be reached from the network; a .local name needs a LAN \
+             address, 0.0.0.0 or ::",
+            address.ip()
+        );
+    };
+    let mut cmd = announcer(name, ip, address.port());
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true);
+    crate::platform::configure_no_window_tokio_command(&mut cmd);
+    let program = cmd.as_std().get_program().to_string_lossy().into_owned();
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            bail!("{program} is not installed; it announces .local names")
+        }
+        Err(e) => return Err(anyhow::Error::new(e).context(format!("Failed to run {program}"))),
+    };
+    if let Ok(status) = tokio::time::timeout(ANNOUNCE_SETTLE, child.wait()).await {
+        bail!("{program} {}", status.context("Failed to wait for it")?);
+    }
+
+    // The task owns the announcer; cancelling it at exit kills it.
+    let owner = state.clone();
+    let name = name.to_string();
+    state.tasks.spawn("hostname announcement", async move {
+        match child.wait().await {
+            Ok(status) => warn!("The mDNS announcement of {} ended: {}", name, status),
+            Err(e) => warn!("The mDNS announcement of {} failed: {}", name, e),
+        }
+        owner.hostname.set(None);
+    });
+    Ok(true)
+}
+
+/// The address other machines reach a dashboard listening on `address` at:
+/// the one this machine routes from when it listens everywhere, nothing on
+/// loopback.
+fn network_address(address: SocketAddr) -> Option<IpAddr> {
+    let ip = address.ip();
+    if ip.is_loopback() {
+        return None;
+    }
+    if !ip.is_unspecified() {
+        return Some(ip);
+    }
+    // Connecting a UDP socket sends nothing; it only picks the route.
+    let (bind, probe): (IpAddr, SocketAddr) = match ip {
+        IpAddr::V4(_) => (Ipv4Addr::UNSPECIFIED.into(), ([192, 0, 2, 1], 9).into()),
+        IpAddr::V6(_) => (
+            Ipv6Addr::UNSPECIFIED.into(),
+            (Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1), 9).into(),
+        ),
+    };
+    let socket = UdpSocket::bind((bind, 0)).ok()?;
+    socket.connect(probe).ok()?;
+    Some(socket.local_addr().ok()?.ip())
+}
+
+#[cfg(target_os = "linux")]
+fn announcer(name: &str, ip: IpAddr, _port: u16) -> Command {
+    let mut cmd = Command::new("avahi-publish");
+    cmd.args(["--address", "--no-reverse", name, &ip.to_string()]);
+    cmd
+}
+
+/// `dns-sd -P` registers the dashboard's `_http._tcp` service and, with
+/// it, an address record for `name`.
+#[cfg(not(target_os = "linux"))]
+fn announcer(name: &str, ip: IpAddr, port: u16) -> Command {
+    let mut cmd = Command::new("dns-sd");
+    cmd.args(["-P", "ESPHome Device Builder", "_http._tcp", "local"])
+        .arg(port.to_string())
+        .args([name, &ip.to_string()]);
+    cmd
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_hostnames() {
+        assert!(valid("esphome.localhost"));
+        assert!(valid("esphome-builder.local"));
+        assert!(!valid("esphome..local"));
+        assert!(!valid("-esphome.local"));
+        assert!(!valid("esphome builder.local"));
+        assert!(is_mdns("ESPHome-Builder.LOCAL"));
+        assert!(!is_mdns("esphome.localhost"));
+    }
+
+    #[test]
+    fn finds_and_adds_hosts_entries() {
+        let hosts = "127.0.0.1\tlocalhost\n# 10.0.0.2 esphome.localhost\n::1 ip6-localhost";
+        assert_eq!(lookup(hosts, "esphome.localhost"), None);
+        assert_eq!(lookup(hosts, "LOCALHOST"), Some([127, 0, 0, 1].into()));
+
+        let updated = with_entry(hosts, "esphome.localhost", [127, 0, 0, 1].into());
+        assert!(updated.starts_with(hosts), "{updated}");
+        assert!(updated.ends_with(
+            "ip6-localhost\n127.0.0.1\tesphome.localhost\t# added by ESPHome Device Builder\n"
+        ));
+        assert_eq!(
+            lookup(&updated, "esphome.localhost"),
+            Some([127, 0, 0, 1].into())
+        );
+    }
+}
@@ -0,0 +1,107 @@
+//! A language server for ESPHome YAML, for editors other than VS Code.
+//!
+//! ESPHome checks configs for editors with `esphome vscode`: line-delimited
+//! JSON over stdio, the protocol the VS Code extension speaks. This serves
+//! the Language Server Protocol on a loopback port and translates (see
+//! [`session`]). Each connection gets its own `esphome vscode` from the app's
+//! Python, so diagnostics come from exactly the ESPHome the dashboard runs,
+//! against the editor's unsaved text. Configs are checked when opened and
+//! saved. There are no completions: ESPHome's editor protocol only
+//! validates.
+//!
+//! Off by default. `language_server` turns it on and `language_server_port`
+//! picks the port; the tray and `esphome-desktop language-server` start and
+//! stop it (and save the choice) while the app runs.
+
+use anyhow::{Context, Result};
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpListener;
+use tokio::task::{JoinHandle, JoinSet};
+use tracing::{info, warn};
+
+use crate::AppState;
+
+mod session;
+
+/// The running server, if any.
+#[derive(Default)]
+pub struct LanguageServer {
+    running: Mutex<Option<Running>>,
+}
+
+struct Running {
+    port: u16,
+    /// The accept loop. Aborting it drops its sessions, and with them their
+    /// ESPHome processes.
+    task: JoinHandle<()>,
+}
+
+impl LanguageServer {
+    /// The port it listens on, or `None` when it isn't running.
+    pub(crate) fn port(&self) -> Option<u16> {
+        self.lock().as_ref().map(|r| r.port)
+    }
+
+    /// Listen on `port`, replacing a server already running.
+    pub(crate) async fn start(&self, state: &Arc<AppState>, port: u16) -> Result<()> {
+        self.stop();
+        let listener = TcpListener::bind(("127.0.0.1", port))
+            .await
+            .with_context(|| format!("Failed to listen on port {port}"))?;
+        info!("Language server listening on 127.0.0.1:{}", port);
+        let task = tokio::spawn(accept(listener, state.clone()));
+        *self.lock() = Some(Running { port, task });
+        Ok(())
+    }
+
+    /// Stop listening and end every session.
+    pub(crate) fn stop(&self) {
+        if let Some(running) = self.lock().take() {
+            running.task.abort();
+            info!("Language server on port {} stopped", running.port);
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<Running>> {
+        self.running.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+async fn accept(listener: TcpListener, state: Arc<AppState>) {
+    let mut sessions = JoinSet::new();
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, peer)) => {
+                    info!("Language server client connected from {}", peer);
+                    sessions.spawn(session::run(stream, state.clone()));
+                }
+                Err(e) => warn!("Language server failed to accept a client: {}", e),
+            },
+            Some(ended) = sessions.join_next() => {
+                if let Ok(Err(e)) = ended {
+                    warn!("Language server session ended: {:#}", e);
+                }
+            }
+        }
+    }
+}
+
+/// Start the server at launch when `language_server` is on.
+pub(crate) fn restore(state: &Arc<AppState>) {
+    let state = state.clone();
+    tauri::async_runtime::spawn(async move {
+        let (enabled, port) = {
+            let settings = state.settings.read().await;
+            (settings.language_server, settings.language_server_port)
+        };
+        if enabled {
+            if let Err(e) = state.language_server.start(&state, port).await {
+                warn!("Language server not started: {:#}", e);
+                state
+                    .store
+                    .publish(crate::store::Event::LanguageServer(false));
+            }
+        }
+    });
+}
@@ -0,0 +1,367 @@
+//! One editor connection: LSP from the editor, `esphome vscode` behind it.
+//!
+//! The editor's open documents are kept here, and ESPHome asks for every
+//! file it loads (`read_file`), so a config is checked as the editor has it,
+//! saved or not; files the editor doesn't have open, like `secrets.yaml`, are
+//! read from disk. ESPHome is started on the first check and reused; if it
+//! dies or hangs it is replaced on the next one.
+
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::Url;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::io::{BufReader, Lines};
+use tokio::net::TcpStream;
+use tokio::process::{Child, ChildStdin, ChildStdout};
+use tracing::warn;
+
+use crate::AppState;
+
+/// How long ESPHome gets to check a config before it is replaced.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// JSON-RPC's "method not found".
+const METHOD_NOT_FOUND: i64 = -32601;
+
+pub(super) async fn run(stream: TcpStream, state: Arc<AppState>) -> Result<()> {
+    let (read, mut write) = stream.into_split();
+    let mut read = BufReader::new(read);
+    let mut session = Session {
+        state,
+        documents: HashMap::new(),
+        esphome: None,
+    };
+    while let Some(message) = read_message(&mut read).await? {
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        match (method, message.get("id").cloned()) {
+            ("initialize", Some(id)) => respond(&mut write, id, capabilities()).await?,
+            ("shutdown", Some(id)) => respond(&mut write, id, Value::Null).await?,
+            ("exit", _) => break,
+            ("textDocument/didOpen", _) => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                if let Some(path) = file_path(uri) {
+                    session.documents.insert(path.clone(), text.to_string());
+                    session.check(&mut write, uri, &path).await?;
+                }
+            }
+            // Full sync: the last change is the whole text. Checked on save,
+            // not per keystroke; a check takes ESPHome a second or more.
+            ("textDocument/didChange", _) => {
+                let changes = params["contentChanges"].as_array();
+                let text = changes
+                    .and_then(|c| c.last())
+                    .and_then(|c| c["text"].as_str());
+                if let (Some(path), Some(text)) = (file_path(uri), text) {
+                    session.documents.insert(path, text.to_string());
+                }
+            }
+            ("textDocument/didSave", _) => {
+                if let Some(path) = file_path(uri) {
+                    session.check(&mut write, uri, &path).await?;
+                }
+            }
+            ("textDocument/didClose", _) => {
+                if let Some(path) = file_path(uri) {
+                    session.documents.remove(&path);
+                }
+                publish(&mut write, uri, Vec::new()).await?;
+            }
+            ("", _) => {} // A response; we send no requests.
+            (_, Some(id)) => {
+                let error = json!({ "code": METHOD_NOT_FOUND, "message": "not supported" });
+                let reply = json!({ "jsonrpc": "2.0", "id": id, "error": error });
+                write_message(&mut write, &reply).await?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+struct Session {
+    state: Arc<AppState>,
+    /// The editor's text of each open document.
+    documents: HashMap<PathBuf, String>,
+    esphome: Option<Esphome>,
+}
+
+impl Session {
+    /// Check the config at `path` and publish what ESPHome found.
+    async fn check<W: AsyncWrite + Unpin>(
+        &mut self,
+        write: &mut W,
+        uri: &str,
+        path: &Path,
+    ) -> Result<()> {
+        let diagnostics = match self.validate(path).await {
+            Ok(result) => diagnostics(&result, path),
+            Err(e) => {
+                warn!("Language server couldn't check {:?}: {:#}", path, e);
+                self.esphome = None;
+                vec![on_first_line(format!(
+                    "ESPHome couldn't check this config: {e:#}"
+                ))]
+            }
+        };
+        publish(write, uri, diagnostics).await
+    }
+
+    async fn validate(&mut self, path: &Path) -> Result<Value> {
+        if self.esphome.is_none() {
+            self.esphome = Some(Esphome::spawn(&self.state)?);
+        }
+        let esphome = self.esphome.as_mut().expect("just started");
+        tokio::time::timeout(CHECK_TIMEOUT, esphome.validate(path, &self.documents))
+            .await
+            .context("ESPHome took too long")?
+    }
+}
+
+/// `esphome vscode`, reading requests on stdin and answering on stdout.
+struct Esphome {
+    /// Held so the process lives as long as this; killed on drop.
+    _child: Child,
+    stdin: ChildStdin,
+    stdout: Lines<BufReader<ChildStdout>>,
+}
+
+impl Esphome {
+    fn spawn(state: &AppState) -> Result<Self> {
+        let config_dir = state.daemon.config_dir();
+        let mut cmd = state
+            .daemon
+            .esphome_command([OsStr::new("vscode"), config_dir.as_os_str()])?;
+        cmd.stdin(Stdio::piped()).stderr(Stdio::null());
+        let mut child = cmd.spawn().context("Failed to start ESPHome")?;
+        let stdin = child.stdin.take().context("ESPHome has no stdin")?;
+        let stdout = child.stdout.take().context("ESPHome has no stdout")?;
+        Ok(Self {
+            _child: child,
+            stdin,
+            stdout: BufReader::new(stdout).lines(),
+        })
+    }
+
+    /// ESPHome's `result` for the config at `path`, answering its requests
+    /// for files on the way.
+    async fn validate(
+        &mut self,
+        path: &Path,
+        documents: &HashMap<PathBuf, String>,
+    ) -> Result<Value> {
+        self.send(json!({ "type": "validate", "file": path }))
+            .await?;
+        loop {
+            let Some(line) = self.stdout.next_line().await? else {
+                bail!("ESPHome exited");
+            };
+            // Anything that isn't a message is ESPHome logging.
+            let Ok(message) = serde_json::from_str::<Value>(&line) else {
+                continue;
+            };
+            match message["type"].as_str() {
+                Some("read_file") => {
+                    let file = Path::new(message["path"].as_str().unwrap_or_default());
+                    let content = match documents.get(file) {
+                        Some(text) => text.clone(),
+                        None => std::fs::read_to_string(file).unwrap_or_default(),
+                    };
+                    self.send(json!({ "type": "file_response", "content": content }))
+                        .await?;
+                }
+                Some("result") => return Ok(message),
+                _ => {}
+            }
+        }
+    }
+
+    async fn send(&mut self, message: Value) -> Result<()> {
+        let line = message.to_string() + "\n";
+        self.stdin.write_all(line.as_bytes()).await?;
+        self.stdin.flush().await?;
+        Ok(())
+    }
+}
+
+/// LSP diagnostics for `path` from ESPHome's `result`. Errors ESPHome places
+/// in another file (an included package) go on the first line, naming it.
+fn diagnostics(result: &Value, path: &Path) -> Vec<Value> {
+    let mut found = Vec::new();
+    for error in result["yaml_errors"].as_array().into_iter().flatten() {
+        let message = error["message"].as_str().unwrap_or_default();
+        found.push(on_first_line(message.to_string()));
+    }
+    for error in result["validation_errors"].as_array().into_iter().flatten() {
+        let message = error["message"].as_str().unwrap_or_default();
+        let range = &error["range"];
+        let at = |key: &str| range[key].as_u64().unwrap_or(0);
+        let document = range["document"].as_str().map(Path::new);
+        found.push(match document {
+            Some(document) if document != path => {
+                on_first_line(format!("{}: {message}", document.display()))
+            }
+            _ => diagnostic(
+                at("start_line"),
+                at("start_col"),
+                at("end_line"),
+                at("end_col"),
+                message.to_string(),
+            ),
+        });
+    }
+    found
+}
+
+/// A diagnostic for the whole file rather than a place in it.
+fn on_first_line(message: String) -> Value {
+    diagnostic(0, 0, 0, 0, message)
+}
+
+fn diagnostic(line: u64, col: u64, end_line: u64, end_col: u64, message: String) -> Value {
+    json!({
+        "range": {
+            "start": { "line": line, "character": col },
+            "end": { "line": end_line, "character": end_col },
+        },
+        "severity": 1,
+        "source": "esphome",
+        "message": message,
+    })
+}
+
+fn capabilities() -> Value {
+    json!({
+        "capabilities": {
+            // Full text on change; checks on open and save.
+            "textDocumentSync": { "openClose": true, "change": 1, "save": true },
+        },
+        "serverInfo": { "name": "esphome-desktop", "version": env!("CARGO_PKG_VERSION") },
+    })
+}
+
+fn file_path(uri: &str) -> Option<PathBuf> {
+    Url::parse(uri).ok()?.to_file_path().ok()
+}
+
+async fn publish<W: AsyncWrite + Unpin>(
+    write: &mut W,
+    uri: &str,
+    diagnostics: Vec<Value>,
+) -> Result<()> {
+    let params = json!({ "uri": uri, "diagnostics": diagnostics });
+    let message = json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": params,
+    });
+    write_message(write, &message).await
+}
+
+async fn respond<W: AsyncWrite + Unpin>(write: &mut W, id: Value, result: Value) -> Result<()> {
+    write_message(
+        write,
+        &json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+    )
+    .await
+}
+
+/// The next message, or `None` once the editor has hung up.
+async fn read_message<R: AsyncBufRead + Unpin>(read: &mut R) -> Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if read.read_line(&mut header).await? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = Some(
+                    value
+                        .trim()
+                        .parse::<usize>()
+                        .context("bad Content-Length")?,
+                );
+            }
+        }
+    }
+    let Some(length) = length else {
+        bail!("message without a Content-Length");
+    };
+    let mut body = vec![0; length];
+    read.read_exact(&mut body).await?;
+    Ok(Some(
+        serde_json::from_slice(&body).context("message isn't JSON")?,
+    ))
+}
+
+async fn write_message<W: AsyncWrite + Unpin>(write: &mut W, message: &Value) -> Result<()> {
+    let body = message.to_string();
+    let framed = format!("Content-Length: {}\r\n\r\n{body}", body.len());
+    write.write_all(framed.as_bytes()).await?;
+    write.flush().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn messages_round_trip_through_the_framing() {
+        let mut framed = Vec::new();
+        let message = json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize" });
+        write_message(&mut framed, &message).await.unwrap();
+        write_message(&mut framed, &json!({ "method": "exit" }))
+            .await
+            .unwrap();
+
+        let mut read = &framed[..];
+        assert_eq!(read_message(&mut read).await.unwrap(), Some(message));
+        assert!(read_message(&mut read).await.unwrap().is_some());
+        assert_eq!(read_message(&mut read).await.unwrap(), None);
+    }
+
+    #[test]
+    fn esphome_errors_become_diagnostics() {
+        let result = json!({
+            "type": "result",
+            "yaml_errors": [],
+            "validation_errors": [
+                {
+                    "range": {
+                        "document": "/c/porch.yaml",
+                        "start_line": 4, "start_col": 2, "end_line": 4, "end_col": 9,
+                    },
+                    "message": "Unknown platform",
+                },
+                {
+                    "range": {
+                        "document": "/c/common.yaml",
+                        "start_line": 1, "start_col": 0, "end_line": 1, "end_col": 3,
+                    },
+                    "message": "Bad key",
+                },
+            ],
+        });
+        let found = diagnostics(&result, Path::new("/c/porch.yaml"));
+        assert_eq!(
+            found[0]["range"]["start"],
+            json!({ "line": 4, "character": 2 })
+        );
+        assert_eq!(found[0]["message"], "Unknown platform");
+        assert_eq!(found[1]["range"]["start"]["line"], 0);
+        assert_eq!(found[1]["message"], "/c/common.yaml: Bad key");
+    }
+}
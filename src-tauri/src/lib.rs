@@ -4,17 +4,56 @@
 //! with system tray integration.
 
 mod app_update;
+mod audit;
+mod cleanup;
 mod cli;
+mod command_palette;
 mod control;
 mod daemon;
+mod dev_refresh;
+mod devices;
 mod dialog;
+mod digest;
+mod duplicates;
+mod email;
+mod error;
 mod git_check;
+mod hooks;
+mod hostname;
 mod i18n;
+mod language_server;
+mod logging;
+mod migration;
+mod network;
+mod notifications;
+mod package_watch;
+mod packages;
+mod pins;
 mod platform;
+mod push;
+mod python_packages;
+mod rename;
+mod scratchpad;
+mod search;
+mod self_test;
+mod session;
 mod settings;
+mod settings_window;
+mod setup;
+mod share;
+mod signing;
+mod snippets;
+mod startup;
+mod store;
+mod tasks;
+mod terminal;
+#[cfg(test)]
+mod testing;
 mod tray;
 mod update;
 mod util;
+mod versions;
+mod window;
 
 // The CLI argument model and pre-parse launch helpers live in `cli`; re-export
 // them so `esphome_desktop_lib::Cli`, `crate::CliCommand`, etc. resolve as
@@ -22,20 +61,15 @@ mod util;
 pub use cli::*;
 
 use anyhow::Result;
+use std::net::SocketAddr;
 use std::sync::Arc;
-use tauri::{
-    async_runtime,
-    tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
-    AppHandle, Manager, RunEvent,
-};
-use tauri_plugin_autostart::{MacosLauncher, ManagerExt};
+use tauri::{async_runtime, AppHandle, Manager, RunEvent};
+use tauri_plugin_autostart::MacosLauncher;
 use tokio::sync::RwLock;
 use tracing::{error, info, warn};
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 use daemon::DaemonManager;
-use settings::{Backend, Settings};
-use tray::build_tray_menu;
+use settings::{Backend, Policy, Settings};
 use update::UpdateChecker;
 
 /// Application state shared across the app
@@ -52,12 +86,40 @@ pub struct AppState {
     /// `await` points (e.g. one switch's `start()` racing another's
     /// mid-install). See `control::ops::UpdateGuard`.
     pub update_in_flight: Arc<std::sync::atomic::AtomicBool>,
+    /// Kiosk mode from the administrator's policy: the tray and control
+    /// channel only open the dashboard and report status. Fixed for the
+    /// process lifetime.
+    pub kiosk: bool,
+    /// The editor language server, when running.
+    pub language_server: language_server::LanguageServer,
+    /// The comparison dashboard on another ESPHome release, when running.
+    pub comparison: daemon::Comparison,
+    /// The tunnel sharing the dashboard for a while, when open.
+    pub share: share::Share,
+    /// The dashboard's friendly name, once registered.
+    pub hostname: hostname::Hostname,
+    /// What the tray, notifications and the control API show, and the bus
+    /// its changes go out on (see [`store`]).
+    pub store: store::Store,
+    /// The app's background tasks (see [`tasks`]), cancelled on exit. The
+    /// daemon's scope is a child of this one.
+    pub tasks: tasks::Tasks,
 }
 
 impl AppState {
     pub fn new(app_handle: &AppHandle) -> Result<Self> {
-        let settings = Settings::load(app_handle)?;
-        let daemon = DaemonManager::new(app_handle, &settings)?;
+        let mut settings = Settings::load(app_handle)?;
+        notifications::restore(settings.notifications.paused_until);
+        window::set_ui_scale(app_handle, settings.ui_scale);
+        window::set_log_palette(settings.log_palette);
+        daemon::port::ensure_free(app_handle, &mut settings);
+        let store = store::Store::from_settings(&settings);
+        notifications::listen(app_handle, &store);
+        hooks::init(&settings.notifications.hooks, &store);
+        network::init(app_handle, &settings);
+        push::init(settings.notifications.push.as_ref());
+        let tasks = tasks::Tasks::default();
+        let daemon = DaemonManager::new(app_handle, &settings, store.clone(), tasks.child())?;
         let update_checker = UpdateChecker::new();
 
         Ok(Self {
@@ -65,6 +127,13 @@ impl AppState {
             settings: RwLock::new(settings),
             update_checker,
             update_in_flight: Arc::new(std::sync::atomic::AtomicBool::new(false)),
+            kiosk: Policy::load().kiosk_mode,
+            language_server: Default::default(),
+            comparison: Default::default(),
+            share: Default::default(),
+            hostname: Default::default(),
+            store,
+            tasks,
         })
     }
 }
@@ -86,19 +155,20 @@ pub(crate) fn updates_menu_hint(tray_available: bool) -> String {
     }
 }
 
-/// Open the ESPHome dashboard in the default browser. Detached: `open::that`
-/// waits for the opener process to exit, which can block the calling thread
-/// (including a tokio worker when invoked from the control server).
-pub(crate) fn open_dashboard(port: u16) {
-    let url = format!("http://localhost:{}", port);
+/// Open the ESPHome dashboard in the default browser, by its friendly name
+/// once it has one. Detached: `open::that` waits for the opener process to
+/// exit, which can block the calling thread (including a tokio worker when
+/// invoked from the control server).
+pub(crate) fn open_dashboard(state: &AppState) {
+    let url = state.hostname.url(state.daemon.address());
     if let Err(e) = open::that_detached(&url) {
         error!("Failed to open browser: {}", e);
     }
 }
 
 /// Wait for the dashboard to be ready by polling the health endpoint
-pub(crate) async fn wait_for_dashboard_ready(port: u16, timeout_secs: u64) -> bool {
-    let client = match reqwest::Client::builder()
+pub(crate) async fn wait_for_dashboard_ready(address: SocketAddr, timeout_secs: u64) -> bool {
+    let client = match network::loopback_client()
         .timeout(std::time::Duration::from_secs(2))
         .build()
     {
@@ -106,7 +176,7 @@ pub(crate) async fn wait_for_dashboard_ready(port: u16, timeout_secs: u64) -> bo
         Err(_) => return false,
     };
 
-    let url = daemon::loopback_url(port);
+    let url = daemon::probe_url(address);
     let start = std::time::Instant::now();
     let timeout = std::time::Duration::from_secs(timeout_secs);
 
@@ -124,63 +194,23 @@ pub(crate) async fn wait_for_dashboard_ready(port: u16, timeout_secs: u64) -> bo
     false
 }
 
-/// Number of rotated app-log files to retain (one per day of activity).
-const APP_LOG_HISTORY: usize = 7;
-
-/// Build the rolling app-level log appender (`<data>/logs/app.<date>.log`).
-///
-/// Resolved without an `AppHandle` (logging is initialised before Tauri builds
-/// one) using the same bundle identifier Tauri's `app_data_dir()` uses, so this
-/// sits next to the dashboard logs and stays inspectable across a self-update
-/// restart — issue #203. Daily rotation with [`APP_LOG_HISTORY`] retained keeps
-/// it bounded even when the filter is raised to `debug` to chase a failure.
-/// Best-effort: returns None if the dir or appender can't be built, leaving
-/// stderr logging.
-fn app_log_appender() -> Option<tracing_appender::rolling::RollingFileAppender> {
-    let dir = platform::data_dir_no_handle()?.join("logs");
-    std::fs::create_dir_all(&dir).ok()?;
-    tracing_appender::rolling::Builder::new()
-        .rotation(tracing_appender::rolling::Rotation::DAILY)
-        .filename_prefix("app")
-        .filename_suffix("log")
-        .max_log_files(APP_LOG_HISTORY)
-        .build(dir)
-        .ok()
-}
-
-/// Initialize logging
-fn init_logging() {
-    // Optional rolling file layer beside stderr: a no-op when the appender can't
-    // be built, so a path failure never blocks startup. The appender is its own
-    // `MakeWriter`, so there's no per-event handle clone and no panic path in
-    // the logging hot loop.
-    let file_layer = app_log_appender().map(|appender| {
-        tracing_subscriber::fmt::layer()
-            .with_ansi(false)
-            .with_writer(appender)
-    });
-
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "esphome_desktop=info".into()),
-        )
-        .with(tracing_subscriber::fmt::layer())
-        .with(file_layer)
-        .init();
-}
-
-/// Handle tray icon left-click (open dashboard)
-fn handle_tray_click(_app: &AppHandle, state: &AppState) {
-    let settings = async_runtime::block_on(state.settings.read());
-    open_dashboard(settings.port);
-}
+/// How long an exit waits for the background tasks to wind down before
+/// stopping the daemon.
+const SHUTDOWN_GRACE: std::time::Duration = std::time::Duration::from_secs(2);
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run(cli: Cli) {
-    init_logging();
+    // Before logging opens its file where the layout now puts it.
+    let layout_moves = platform::migrate_layout();
+    logging::init();
     info!("Starting ESPHome Device Builder");
     info!("CLI args: {:?}", cli);
+    for moved in layout_moves {
+        match moved {
+            Ok(line) => info!("{}", line),
+            Err(line) => warn!("{}", line),
+        }
+    }
 
     // Capture CLI flags before closure
     let no_open_dashboard = cli.no_open_dashboard;
@@ -195,6 +225,7 @@ pub fn run(cli: Cli) {
         .plugin(tauri_plugin_notification::init())
         .plugin(tauri_plugin_process::init())
         .plugin(tauri_plugin_dialog::init())
+        .plugin(command_palette::plugin())
         .plugin(tauri_plugin_updater::Builder::new().build())
         // Launch silently on login (tray only, no browser) so a remote builder
         // comes back online after a reboot; manual launches still open the
@@ -211,10 +242,48 @@ pub fn run(cli: Cli) {
                 cwd, args
             );
             if let Some(state) = app.try_state::<Arc<AppState>>() {
-                let settings = async_runtime::block_on(state.settings.read());
-                open_dashboard(settings.port);
+                open_dashboard(&state);
             }
         }))
+        .invoke_handler(window::kiosk_gate(tauri::generate_handler![
+            scratchpad::check_snippet,
+            scratchpad::check_secrets,
+            search::search_configs,
+            search::open_config_at,
+            pins::pin_overview,
+            packages::package_graph,
+            duplicates::find_duplicates,
+            duplicates::merge_added,
+            rename::rename_devices,
+            rename::rename_device,
+            rename::select_rename_device,
+            rename::upload_renamed,
+            signing::signing_keys,
+            signing::generate_signing_key,
+            signing::remove_signing_key,
+            versions::esphome_versions,
+            versions::activate_esphome_version,
+            versions::update_esphome_version,
+            versions::delete_esphome_version,
+            versions::esphome_releases,
+            versions::install_esphome_version,
+            python_packages::python_packages,
+            python_packages::python_package_releases,
+            python_packages::install_python_package,
+            update::progress::update_progress,
+            update::progress::cancel_update,
+            terminal::terminal_intro,
+            terminal::terminal_output,
+            terminal::terminal_run,
+            terminal::terminal_stop,
+            terminal::terminal_clear,
+            settings_window::settings_form,
+            settings_window::pick_config_dir,
+            settings_window::save_settings_form,
+            command_palette::palette_actions,
+            command_palette::run_palette_action,
+            command_palette::close_palette,
+        ]))
         .setup(move |app| {
             info!("Setting up ESPHome Device Builder");
 
@@ -231,10 +300,12 @@ pub fn run(cli: Cli) {
 
             // Ensure user Python exists (copied from the bundle on first run).
             // This must happen before AppState::new() so paths are correct
+            let python_check = std::time::Instant::now();
             if let Err(e) = platform::ensure_user_python(app.handle(), refresh_reason) {
                 error!("Failed to set up user Python: {}", e);
                 // Continue anyway - might work with bundled Python
             }
+            let python_time = python_check.elapsed();
 
             // Make a git available to the ESPHome backend. On Windows this
             // always prepends the bundled MinGit to PATH; no-op elsewhere. Runs
@@ -279,6 +350,10 @@ pub fn run(cli: Cli) {
             // can drive this instance — the only control surface on systems
             // where the tray is unavailable.
             control::server::spawn(app.handle().clone());
+            language_server::restore(&state);
+            hostname::restore(app.handle(), &state);
+            migration::offer(app.handle());
+            setup::spawn_features(app.handle(), &state);
 
             // If we just migrated a classic-backend user, persist the migrated
             // settings (loaded as the default device builder) so the legacy
@@ -294,6 +369,13 @@ pub fn run(cli: Cli) {
             // This runs before the daemon starts so the new backend takes
             // effect immediately, and before the tray menu is built so the
             // radio buttons reflect the override.
+            let cli_backend_override = match cli_backend_override {
+                Some(_) if state.kiosk => {
+                    warn!("Kiosk mode is on; ignoring --use-builder");
+                    None
+                }
+                other => other,
+            };
             let cli_override_needs_install = if let Some(new_backend) = cli_backend_override {
                 let mut settings = async_runtime::block_on(state.settings.write());
                 if settings.backend != new_backend {
@@ -314,112 +396,9 @@ pub fn run(cli: Cli) {
                 false
             };
 
-            // Reconcile the OS login item to the persisted preference. This
-            // applies the on-by-default on first run and re-asserts a user's
-            // choice on every launch (so an "off" sticks and drift self-heals).
-            {
-                let want = async_runtime::block_on(state.settings.read()).launch_at_startup;
-                let manager = app.autolaunch();
-                match manager.is_enabled() {
-                    Ok(current) if current != want => {
-                        let result = if want {
-                            manager.enable()
-                        } else {
-                            manager.disable()
-                        };
-                        if let Err(e) = result {
-                            warn!("Failed to set autostart to {}: {}", want, e);
-                        }
-                    }
-                    Err(e) => warn!("Failed to query autostart state: {}", e),
-                    _ => {}
-                }
-            }
-
-            // Build and set up the tray menu (if tray support is available)
-            let tray_available = if platform::is_tray_supported() {
-                // Create the tray icon programmatically.
-                // We wrap this in catch_unwind as a safety net: on Linux the
-                // underlying libappindicator-sys crate will panic!() if the
-                // shared library fails to load (e.g. GLIBC version mismatch).
-                let tray_result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-                    // The macOS menu bar expects a monochrome "template" image
-                    // whose alpha channel the system recolors to match the
-                    // light/dark theme. Linux tray pixmaps are rendered
-                    // literally with no theme recoloring, so it gets a fixed
-                    // white glyph suited to the (near-universal) dark panels.
-                    // Windows keeps the full-color bundled icon.
-                    #[cfg(target_os = "macos")]
-                    let (icon, icon_as_template) = (
-                        tauri::image::Image::from_bytes(include_bytes!("../icons/tray-mac.png"))?,
-                        true,
-                    );
-                    #[cfg(target_os = "linux")]
-                    let (icon, icon_as_template) = (
-                        tauri::image::Image::from_bytes(include_bytes!("../icons/tray-linux.png"))?,
-                        false,
-                    );
-                    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
-                    let (icon, icon_as_template) = (
-                        app.default_window_icon()
-                            .cloned()
-                            .ok_or_else(|| anyhow::anyhow!("No default icon available for tray"))?,
-                        false,
-                    );
+            setup::reconcile_autostart(app.handle(), &state);
 
-                    let tray = TrayIconBuilder::with_id("main")
-                        .icon(icon)
-                        .icon_as_template(icon_as_template)
-                        .tooltip("ESPHome Device Builder")
-                        .build(app)?;
-
-                    let menu = build_tray_menu(app.handle(), &state)?;
-                    tray.set_menu(Some(menu))?;
-
-                    // Set up click handler
-                    let state_clone = state.clone();
-                    let app_handle = app.handle().clone();
-                    tray.on_tray_icon_event(move |_tray, event| {
-                        if let TrayIconEvent::Click {
-                            button: MouseButton::Left,
-                            button_state: MouseButtonState::Up,
-                            ..
-                        } = event
-                        {
-                            handle_tray_click(&app_handle, &state_clone);
-                        }
-                    });
-
-                    Ok::<(), anyhow::Error>(())
-                }));
-
-                match tray_result {
-                    Ok(Ok(())) => {
-                        info!("System tray icon created successfully");
-                        true
-                    }
-                    Ok(Err(e)) => {
-                        warn!(
-                            "Failed to create system tray icon: {}. Running without tray.",
-                            e
-                        );
-                        false
-                    }
-                    Err(_) => {
-                        warn!(
-                            "System tray creation panicked (appindicator library not usable?). \
-                             Running without tray."
-                        );
-                        false
-                    }
-                }
-            } else {
-                warn!(
-                    "System tray not supported (appindicator library not found). \
-                     Running without tray."
-                );
-                false
-            };
+            let tray_available = setup::create_tray(app, &state);
 
             // Start the daemon
             let daemon_state = state.clone();
@@ -442,10 +421,12 @@ pub fn run(cli: Cli) {
                 // reinstall without blocking the launch, and inside the guard,
                 // before the daemon exists: nothing is holding the packages open
                 // yet, and a broken tree has nothing worth serving.
+                let repair = std::time::Instant::now();
                 daemon_state
                     .update_checker
                     .repair_python_tree_if_broken(&daemon_app)
                     .await;
+                let python_time = python_time + repair.elapsed();
 
                 // If a CLI override switched us into a builder backend, ensure
                 // the package is installed/upgraded before starting the daemon.
@@ -461,10 +442,24 @@ pub fn run(cli: Cli) {
                     }
                 }
 
+                let spawn = std::time::Instant::now();
                 let start_result = daemon_state.daemon.start().await;
+                let spawn_time = spawn.elapsed();
                 drop(startup_guard);
                 match start_result {
                     Ok(()) => {
+                        // Times the wait for the first response and keeps
+                        // the launch's timings (see `startup`).
+                        daemon_state.tasks.spawn(
+                            "startup timings",
+                            startup::finish(
+                                daemon_app.clone(),
+                                daemon_state.daemon.address(),
+                                python_time,
+                                spawn_time,
+                            ),
+                        );
+
                         // Warn (non-blocking) if git is missing. ESPHome needs
                         // it for external components, remote packages, and other
                         // deps, so many configs won't compile without it; absent
@@ -491,77 +486,10 @@ pub fn run(cli: Cli) {
                 }
             });
 
-            // Start update checker (check after 30s, then every 24 hours)
-            // Order matters: check the desktop app first. A self-update ships
-            // a fresh Python bundle that overwrites the user's `python/`
-            // directory, so any pip-installed ESPHome / device-builder bump
-            // we'd do now would be wiped by the next launch. Skip the Python
-            // checks while an app update is pending.
-            // The dev channel skips automatic update checks entirely. When
-            // the active backend is a builder variant, the
-            // `esphome-device-builder` package is checked on the same schedule.
-            let update_state = state.clone();
-            let update_app = app.handle().clone();
-            // Captured so background update notifications can adapt their
-            // "how to update" hint when there is no tray menu to point at
-            // (issue #87).
-            let update_tray_available = tray_available;
-            async_runtime::spawn(async move {
-                tokio::time::sleep(tokio::time::Duration::from_secs(30)).await;
-                let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(86400));
-                loop {
-                    interval.tick().await;
-                    if app_update::check_and_notify(&update_app, update_tray_available).await
-                        == app_update::NextStep::Skip
-                    {
-                        // App update pending — leave the Python packages alone.
-                        continue;
-                    }
-                    let (channel, backend) = {
-                        let settings = update_state.settings.read().await;
-                        (settings.release_channel, settings.backend)
-                    };
-                    update_state
-                        .update_checker
-                        .check_and_notify(&update_app, channel, update_tray_available)
-                        .await;
-                    update_state
-                        .update_checker
-                        .check_and_notify_device_builder(
-                            &update_app,
-                            backend,
-                            update_tray_available,
-                        )
-                        .await;
-                }
-            });
+            setup::spawn_update_checks(app.handle(), &state, tray_available);
 
-            // Set up signal handlers for graceful shutdown on Ctrl+C.
-            // The daemon-stop is handled by the RunEvent::ExitRequested
-            // branch in run() below; we just trip the exit here.
             #[cfg(unix)]
-            {
-                let signal_app = app.handle().clone();
-                async_runtime::spawn(async move {
-                    let mut sigint =
-                        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::interrupt())
-                            .expect("Failed to set up SIGINT handler");
-                    let mut sigterm =
-                        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
-                            .expect("Failed to set up SIGTERM handler");
-
-                    tokio::select! {
-                        _ = sigint.recv() => {
-                            info!("Received SIGINT, shutting down...");
-                        }
-                        _ = sigterm.recv() => {
-                            info!("Received SIGTERM, shutting down...");
-                        }
-                    }
-
-                    signal_app.exit(0);
-                });
-            }
+            setup::handle_signals(app.handle());
 
             // Open dashboard on first start (after it's ready)
             let settings = async_runtime::block_on(state.settings.read());
@@ -569,26 +497,57 @@ pub fn run(cli: Cli) {
             // way to interact with the app), unless explicitly suppressed.
             let should_open = (settings.open_on_start || !tray_available) && !no_open_dashboard;
             if should_open {
-                let port = settings.port;
                 info!("Opening backend in browser on startup");
                 // Wait for dashboard to be ready, then open browser
-                async_runtime::spawn(async move {
-                    if wait_for_dashboard_ready(port, 60).await {
-                        open_dashboard(port);
-                    } else {
-                        // Open anyway after timeout - user can refresh
-                        open_dashboard(port);
-                    }
-                });
+                state.tasks.spawn(
+                    "dashboard readiness",
+                    startup::open_when_ready(app.handle().clone(), state.clone()),
+                );
             } else if no_open_dashboard {
                 info!("Browser opening suppressed by --no-open-dashboard flag");
             }
 
+            // Kiosk mode has no windows of its own to bring back.
+            if !state.kiosk {
+                session::restore(app.handle(), settings.restore_windows);
+            }
+
+            // Snippets are used from the tray, so without one there is
+            // nothing to offer them with.
+            if settings.clipboard_snippets && tray_available && !state.kiosk {
+                snippets::spawn(app.handle().clone(), &state.tasks, state.store.clone());
+            }
+            // Likewise the check of a changed shared package's devices.
+            if settings.check_shared_packages && tray_available && !state.kiosk {
+                package_watch::spawn(
+                    app.handle().clone(),
+                    &state.tasks,
+                    state.store.clone(),
+                    state.daemon.config_dir().clone(),
+                );
+            }
+
             Ok(())
         })
         .build(tauri::generate_context!())
         .expect("error while building tauri application")
         .run(|app_handle, event| {
+            // Closing the last window asks to exit; the tray app carries on.
+            if let RunEvent::ExitRequested {
+                code: None, api, ..
+            } = &event
+            {
+                if window::closed_last_window() {
+                    api.prevent_exit();
+                    return;
+                }
+            }
+
+            // While the windows are still there to be counted.
+            if let RunEvent::ExitRequested { .. } = &event {
+                session::save(app_handle, true);
+            }
+
             // Synchronously SIGTERM the dashboard's process group on any
             // exit-related event so the signal is in the kernel before
             // we attempt anything else. Covers two scenarios:
@@ -605,10 +564,15 @@ pub fn run(cli: Cli) {
             // stored PID), doesn't touch the `running` flag, and is
             // a no-op once `stop()` has already cleared the PID — so
             // calling it on both events is safe and double-firing the
-            // SIGTERM is harmless (the kernel coalesces).
+            // SIGTERM is harmless (the kernel coalesces). The background
+            // tasks are cancelled alongside, so none starts another step
+            // (an update check, a probe) while the app winds down.
             if matches!(event, RunEvent::Exit | RunEvent::ExitRequested { .. }) {
                 if let Some(state) = app_handle.try_state::<Arc<AppState>>() {
+                    state.tasks.cancel();
                     state.daemon.terminate_blocking();
+                    state.comparison.stop();
+                    state.share.stop();
                 }
             }
 
@@ -635,6 +599,7 @@ pub fn run(cli: Cli) {
                         let state_clone: Arc<AppState> = state.inner().clone();
                         let app = app_handle.clone();
                         async_runtime::spawn(async move {
+                            state_clone.tasks.shutdown(SHUTDOWN_GRACE).await;
                             info!("Stopping ESPHome daemon before exit");
                             if let Err(e) = state_clone.daemon.stop().await {
                                 warn!("Error stopping daemon: {}", e);
@@ -26,6 +26,12 @@ fn main() -> std::process::ExitCode {
     let arg_count = std::env::args_os().count();
 
     let cli = Cli::parse();
+    if cli.self_test {
+        return esphome_desktop_lib::run_self_test();
+    }
+    if let Some(spec) = &cli.relay {
+        return esphome_desktop_lib::run_relay(spec);
+    }
     // A subcommand means "control the running app": run the short-lived CLI
     // client and exit without ever starting Tauri.
     if let Some(command) = cli.command.clone() {
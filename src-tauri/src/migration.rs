@@ -0,0 +1,392 @@
+//! Data left in the layouts of earlier builds, moved into the current one.
+//!
+//! Two kinds turn up:
+//!
+//! * Data directories named after the app rather than its bundle identifier
+//!   ([`LEGACY_NAMES`]), as development and pre-release builds made them.
+//!   Their settings, logs and other files move to where the current layout
+//!   keeps them (see [`platform::get_config_dir`]), without replacing
+//!   anything there (settings only replace ones never changed from the
+//!   defaults); their logs go under `legacy/` in the logs directory.
+//! * Python environments where the app no longer looks: any in a legacy
+//!   directory, and on Windows a `python/` tree in the roaming data
+//!   directory from before it moved to local data. A venv can't be moved, as
+//!   it records its own path, so these are deleted; the app sets up its own
+//!   again from the bundle and pins reinstall theirs on the next start.
+//!
+//! The user is asked once. Declining is remembered for the same directories;
+//! the move is recorded in the audit log. Settings that moved take effect
+//! after a restart, which the app offers.
+
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use tauri::AppHandle;
+use tauri_plugin_dialog::MessageDialogKind;
+use tracing::{info, warn};
+
+use crate::audit::{self, Source};
+use crate::cleanup::{dir_size, format_size};
+use crate::i18n::{t, t_with};
+use crate::platform::{self, PYTHON_TREE_DIRNAME};
+
+/// Data directory names earlier builds used, under the OS data directories.
+const LEGACY_NAMES: &[&str] = &[
+    "ESPHome Builder",
+    "ESPHome Device Builder",
+    "esphome-builder",
+    "esphome-desktop",
+];
+
+/// Entries of a legacy directory that are Python environments.
+const VENV_NAMES: &[&str] = &[PYTHON_TREE_DIRNAME, "pinned"];
+
+/// Where a legacy directory's logs go, in the logs directory.
+const LEGACY_LOGS: &str = "legacy";
+
+const SETTINGS_FILE: &str = platform::SETTINGS_FILE_NAME;
+
+/// Records the paths the user declined to migrate, one per line, so they are
+/// asked again only when something else turns up.
+const DECLINED_MARKER: &str = ".migration_declined";
+
+/// One thing the migration does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Step {
+    Move { from: PathBuf, to: PathBuf },
+    Remove(PathBuf),
+}
+
+/// What the migration would do, and where it finds it.
+#[derive(Debug, Default)]
+struct Plan {
+    /// The legacy directories, emptied by the steps.
+    sources: Vec<PathBuf>,
+    steps: Vec<Step>,
+}
+
+impl Plan {
+    fn moves_settings(&self) -> bool {
+        self.steps.iter().any(|step| match step {
+            Step::Move { from, .. } => from.file_name().is_some_and(|n| n == SETTINGS_FILE),
+            Step::Remove(_) => false,
+        })
+    }
+
+    /// What the migration touches, one line each, for the prompt.
+    fn describe(&self) -> String {
+        self.steps
+            .iter()
+            .map(|step| match step {
+                Step::Move { from, .. } => {
+                    t_with("migration.move", &[("path", &from.display().to_string())])
+                }
+                Step::Remove(path) => t_with(
+                    "migration.remove",
+                    &[
+                        ("path", &path.display().to_string()),
+                        ("size", &format_size(dir_size(path))),
+                    ],
+                ),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Look for data in old layouts and offer to migrate it, in the background.
+pub(crate) fn offer(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let resolved = platform::get_data_dir(&app).and_then(|data_dir| {
+            Ok(Layout {
+                python_parent: platform::get_python_parent_dir(&app)?,
+                settings_dir: platform::get_config_dir(&app)?,
+                logs_dir: platform::get_logs_dir(&app)?,
+                data_dir,
+            })
+        });
+        let layout = match resolved {
+            Ok(layout) => layout,
+            Err(e) => {
+                warn!("Skipping the legacy data check: {}", e);
+                return;
+            }
+        };
+        let data_dir = layout.data_dir.clone();
+        let legacy = legacy_dirs(&layout);
+        let plan = tauri::async_runtime::spawn_blocking(move || plan(&legacy, &layout)).await;
+        let plan = match plan {
+            Ok(plan) if !plan.steps.is_empty() => plan,
+            Ok(_) => return,
+            Err(e) => {
+                warn!("Legacy data check failed: {}", e);
+                return;
+            }
+        };
+        run(&app, &data_dir, plan).await;
+    });
+}
+
+/// Ask, migrate and report.
+async fn run(app: &AppHandle, data_dir: &Path, plan: Plan) {
+    let marker = data_dir.join(DECLINED_MARKER);
+    let sources = plan
+        .steps
+        .iter()
+        .map(|step| match step {
+            Step::Move { from, .. } | Step::Remove(from) => from.display().to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    if std::fs::read_to_string(&marker).is_ok_and(|declined| declined == sources) {
+        return;
+    }
+    info!("Found data in an old layout: {:?}", plan.steps);
+    let agreed = crate::dialog::confirm(
+        app,
+        &t("migration.title"),
+        t_with("migration.prompt", &[("steps", &plan.describe())]),
+        &t("migration.migrate"),
+        &t("migration.not_now"),
+    )
+    .await;
+    if !agreed {
+        info!("Migrating legacy data declined");
+        if let Err(e) = std::fs::write(&marker, &sources) {
+            warn!("Failed to write migration marker: {}", e);
+        }
+        return;
+    }
+
+    let moves_settings = plan.moves_settings();
+    let count = plan.steps.len();
+    let failures = match tauri::async_runtime::spawn_blocking(move || apply(&plan)).await {
+        Ok(failures) => failures,
+        Err(e) => {
+            warn!("Legacy data migration failed: {}", e);
+            return;
+        }
+    };
+    audit::record(
+        app,
+        Source::Tray,
+        "migrate",
+        format!(
+            "moved or removed {} legacy item(s), {} failed",
+            count - failures.len(),
+            failures.len()
+        ),
+    );
+    if !failures.is_empty() {
+        let paths: Vec<String> = failures
+            .iter()
+            .map(|(path, e)| format!("{} ({e})", path.display()))
+            .collect();
+        crate::dialog::notice(
+            app,
+            &t("migration.title"),
+            t_with("migration.partial", &[("paths", &paths.join("\n"))]),
+            MessageDialogKind::Warning,
+        )
+        .await;
+        return;
+    }
+    if moves_settings {
+        let restart = crate::dialog::confirm(
+            app,
+            &t("migration.title"),
+            t("migration.restart_prompt"),
+            &t("migration.restart"),
+            &t("migration.later"),
+        )
+        .await;
+        if restart {
+            platform::relaunch_for_update(app);
+        }
+    }
+}
+
+/// Where the app keeps things now.
+struct Layout {
+    data_dir: PathBuf,
+    python_parent: PathBuf,
+    settings_dir: PathBuf,
+    logs_dir: PathBuf,
+}
+
+/// The legacy directories that exist, never the current ones.
+fn legacy_dirs(layout: &Layout) -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    for root in [dirs::data_dir(), dirs::data_local_dir()]
+        .into_iter()
+        .flatten()
+    {
+        for name in LEGACY_NAMES {
+            let dir = root.join(name);
+            if dir.is_dir()
+                && dir != layout.data_dir
+                && dir != layout.python_parent
+                && !found.contains(&dir)
+            {
+                found.push(dir);
+            }
+        }
+    }
+    found
+}
+
+/// What to do with `legacy` and with stale Python in the data directory.
+fn plan(legacy: &[PathBuf], layout: &Layout) -> Plan {
+    let mut plan = Plan::default();
+    let roaming_python = layout.data_dir.join(PYTHON_TREE_DIRNAME);
+    if layout.data_dir != layout.python_parent && roaming_python.is_dir() {
+        plan.steps.push(Step::Remove(roaming_python));
+    }
+    for dir in legacy {
+        plan.sources.push(dir.clone());
+        for entry in children(dir) {
+            let Some(name) = entry.file_name().map(|n| n.to_os_string()) else {
+                continue;
+            };
+            if VENV_NAMES.iter().any(|venv| name == *venv) {
+                plan.steps.push(Step::Remove(entry));
+            } else if name == "logs" {
+                let logs = layout.logs_dir.join(LEGACY_LOGS);
+                for log in children(&entry) {
+                    if let Some(file) = log.file_name() {
+                        let to = logs.join(file);
+                        if !to.exists() {
+                            plan.steps.push(Step::Move { from: log, to });
+                        }
+                    }
+                }
+            } else {
+                let to = if name == SETTINGS_FILE {
+                    layout.settings_dir.join(&name)
+                } else {
+                    layout.data_dir.join(&name)
+                };
+                let replace = name == SETTINGS_FILE && is_default_settings(&to);
+                if !to.exists() || replace {
+                    plan.steps.push(Step::Move { from: entry, to });
+                }
+            }
+        }
+    }
+    plan
+}
+
+/// Whether the settings file at `path` holds nothing but the defaults.
+fn is_default_settings(path: &Path) -> bool {
+    let Ok(text) = std::fs::read_to_string(path) else {
+        return false;
+    };
+    let Ok(current) = serde_json::from_str::<serde_json::Value>(&text) else {
+        return false;
+    };
+    serde_json::to_value(crate::settings::Settings::default()).is_ok_and(|d| d == current)
+}
+
+fn children(dir: &Path) -> Vec<PathBuf> {
+    std::fs::read_dir(dir)
+        .map(|entries| entries.filter_map(|e| e.ok()).map(|e| e.path()).collect())
+        .unwrap_or_default()
+}
+
+/// Carry out `plan`, past failures, then remove the legacy directories left
+/// empty. Returns what failed, with why.
+fn apply(plan: &Plan) -> Vec<(PathBuf, std::io::Error)> {
+    let mut failures = Vec::new();
+    for step in &plan.steps {
+        let result = match step {
+            Step::Move { from, to } => to
+                .parent()
+                .map_or(Ok(()), std::fs::create_dir_all)
+                .and_then(|()| std::fs::rename(from, to)),
+            Step::Remove(path) => std::fs::remove_dir_all(path),
+        };
+        match result {
+            Ok(()) => info!("Migrated {:?}", step),
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => {
+                warn!("Failed to migrate {:?}: {}", step, e);
+                let path = match step {
+                    Step::Move { from, .. } | Step::Remove(from) => from.clone(),
+                };
+                failures.push((path, e));
+            }
+        }
+    }
+    for dir in &plan.sources {
+        // Only succeeds on what is now empty; what stayed, stays.
+        let _ = std::fs::remove_dir(dir.join("logs"));
+        if std::fs::remove_dir(dir).is_ok() {
+            info!("Removed the emptied legacy directory {:?}", dir);
+        }
+    }
+    failures
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::unique_temp_dir;
+    use std::fs;
+
+    #[test]
+    fn moves_data_and_removes_stale_venvs() {
+        let root = unique_temp_dir("migration");
+        let legacy = root.join("ESPHome Builder");
+        let data = root.join("io.esphome.builder");
+        let layout = Layout {
+            data_dir: data.clone(),
+            python_parent: root.join("local"),
+            settings_dir: root.join("config"),
+            logs_dir: root.join("state/logs"),
+        };
+        fs::create_dir_all(legacy.join("logs")).unwrap();
+        fs::create_dir_all(legacy.join("python/bin")).unwrap();
+        fs::create_dir_all(legacy.join("pinned/2024.6.4")).unwrap();
+        fs::create_dir_all(data.join("python")).unwrap();
+        fs::write(legacy.join("logs/app.log"), "old").unwrap();
+        fs::write(legacy.join("device_notes.json"), "{}").unwrap();
+        fs::write(legacy.join("audit.log"), "old").unwrap();
+        fs::write(data.join("audit.log"), "new").unwrap();
+        fs::write(legacy.join(SETTINGS_FILE), r#"{"port": 6053}"#).unwrap();
+        let defaults = serde_json::to_string(&crate::settings::Settings::default()).unwrap();
+        fs::create_dir_all(&layout.settings_dir).unwrap();
+        fs::write(layout.settings_dir.join(SETTINGS_FILE), defaults).unwrap();
+
+        let first = plan(&[legacy.clone()], &layout);
+        assert!(first.steps.contains(&Step::Remove(data.join("python"))));
+        assert!(first.steps.contains(&Step::Remove(legacy.join("pinned"))));
+        assert!(first.moves_settings());
+        assert!(!first
+            .steps
+            .iter()
+            .any(|step| matches!(step, Step::Move { from, .. } if from.ends_with("audit.log"))));
+
+        assert!(apply(&first).is_empty());
+        assert_eq!(
+            fs::read_to_string(layout.logs_dir.join("legacy/app.log")).unwrap(),
+            "old"
+        );
+        assert!(data.join("device_notes.json").exists());
+        assert!(fs::read_to_string(layout.settings_dir.join(SETTINGS_FILE))
+            .unwrap()
+            .contains("6053"));
+        assert!(!data.join("python").exists());
+        // The audit log it kept holds the legacy directory open.
+        assert!(legacy.join("audit.log").exists());
+
+        // Nothing left to do once done.
+        fs::remove_file(legacy.join("audit.log")).unwrap();
+        let again = plan(&[legacy.clone()], &layout);
+        assert!(again.steps.is_empty());
+        assert!(apply(&again).is_empty());
+        assert!(!legacy.exists());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,226 @@
+//! Desktop notifications, gated by the tray's "Pause notifications" toggle
+//! and its "Show Notifications" preference.
+//!
+//! Every notification the app shows goes through [`show`], so pausing silences
+//! update, backend and setup notifications alike, and so does the push service
+//! each is posted to as well ([`push`](crate::push)). Modal dialogs are not
+//! affected: they only answer something the user just asked for.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use tauri::AppHandle;
+use tauri_plugin_notification::NotificationExt;
+use tracing::{debug, warn};
+
+use crate::i18n::t_with;
+use crate::store::{self, DaemonState, Event, Store};
+
+/// Unix time (seconds) notifications are paused until; `0` when not paused.
+/// A process-wide atomic rather than a settings read so the synchronous
+/// notification sites (exit watcher, startup checks) can consult it without
+/// the async settings lock.
+static PAUSED_UNTIL: AtomicU64 = AtomicU64::new(0);
+
+/// [`PAUSED_UNTIL`] value for "until the app restarts". Never persisted.
+const UNTIL_RESTART: u64 = u64::MAX;
+
+/// [`PAUSED_UNTIL`] value for "until turned back on", from the tray's
+/// Preferences. Persisted like a timed pause, and never reached as a time.
+const UNTIL_RESUMED: u64 = u64::MAX - 1;
+
+/// How often the tray's countdown of a timed pause is refreshed.
+const PAUSE_TICK: Duration = Duration::from_secs(30);
+
+/// How long to pause notifications for, as offered in the tray.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Pause {
+    OneHour,
+    EightHours,
+    UntilRestart,
+    /// Notifications turned off in Preferences.
+    UntilResumed,
+}
+
+impl Pause {
+    /// The pause deadline this choice sets, as Unix seconds from `now`.
+    fn until(self, now: u64) -> u64 {
+        match self {
+            Pause::OneHour => now + Duration::from_secs(60 * 60).as_secs(),
+            Pause::EightHours => now + Duration::from_secs(8 * 60 * 60).as_secs(),
+            Pause::UntilRestart => UNTIL_RESTART,
+            Pause::UntilResumed => UNTIL_RESUMED,
+        }
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Restore a timed pause persisted in settings. A deadline already in the
+/// past is simply ignored.
+pub(crate) fn restore(paused_until: Option<u64>) {
+    if let Some(until) = paused_until.filter(|&u| u > now_secs()) {
+        PAUSED_UNTIL.store(until, Ordering::SeqCst);
+    }
+}
+
+/// Pause notifications, or resume them with `None`. Returns the deadline to
+/// persist in settings: `None` when resuming or pausing until restart.
+pub(crate) fn set_paused(pause: Option<Pause>) -> Option<u64> {
+    let until = pause.map_or(0, |p| p.until(now_secs()));
+    PAUSED_UNTIL.store(until, Ordering::SeqCst);
+    (until != 0 && until != UNTIL_RESTART).then_some(until)
+}
+
+/// Whether the persisted `paused_until` has notifications turned off rather
+/// than paused for a while.
+pub(crate) fn turned_off(paused_until: Option<u64>) -> bool {
+    paused_until == Some(UNTIL_RESUMED)
+}
+
+/// Whether notifications are currently paused.
+pub(crate) fn is_paused() -> bool {
+    is_paused_at(PAUSED_UNTIL.load(Ordering::SeqCst), now_secs())
+}
+
+fn is_paused_at(until: u64, now: u64) -> bool {
+    until > now
+}
+
+/// Whether notifications show and, when paused, until when: what the tray's
+/// Notifications submenu reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum PauseState {
+    #[default]
+    On,
+    /// Paused for this many more minutes, rounded up.
+    PausedFor(u64),
+    UntilRestart,
+    /// Turned off in the tray's Preferences.
+    TurnedOff,
+}
+
+/// The current [`PauseState`].
+pub(crate) fn pause_state() -> PauseState {
+    pause_state_at(PAUSED_UNTIL.load(Ordering::SeqCst), now_secs())
+}
+
+fn pause_state_at(until: u64, now: u64) -> PauseState {
+    match until {
+        UNTIL_RESTART => PauseState::UntilRestart,
+        UNTIL_RESUMED => PauseState::TurnedOff,
+        until if is_paused_at(until, now) => PauseState::PausedFor((until - now).div_ceil(60)),
+        _ => PauseState::On,
+    }
+}
+
+/// Show a notification unless notifications are paused. A suppressed
+/// notification counts as shown; callers keep their own failure log wording
+/// for real errors.
+pub(crate) fn show(
+    app_handle: &AppHandle,
+    title: impl Into<String>,
+    body: impl Into<String>,
+) -> tauri_plugin_notification::Result<()> {
+    let title = title.into();
+    if is_paused() {
+        debug!("Notifications paused; suppressed '{}'", title);
+        return Ok(());
+    }
+    let body = body.into();
+    crate::push::send(&title, &body);
+    app_handle
+        .notification()
+        .builder()
+        .title(title)
+        .body(body)
+        .show()
+}
+
+/// Show the notifications that follow from [`Store`] events: for now, the
+/// backend stopping on its own. Also counts a timed pause down on the store,
+/// so the tray says how long is left and when it has run out.
+pub(crate) fn listen(app_handle: &AppHandle, store: &Store) {
+    let ticking = store.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            ticking.publish(Event::Pause(pause_state()));
+            tokio::time::sleep(PAUSE_TICK).await;
+        }
+    });
+    let app_handle = app_handle.clone();
+    store::listen(store.subscribe(), move |event| {
+        let Event::Daemon(DaemonState::Crashed(status)) = event else {
+            return;
+        };
+        let backend = crate::daemon::BACKEND_NAME;
+        if let Err(e) = show(
+            &app_handle,
+            t_with("daemon.stopped_title", &[("backend", backend)]),
+            t_with(
+                "daemon.stopped_body",
+                &[("backend", backend), ("status", &status)],
+            ),
+        ) {
+            warn!("Failed to show daemon-crash notification: {}", e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timed_pauses_expire() {
+        let now = 1_000_000;
+        let until = Pause::OneHour.until(now);
+        assert!(is_paused_at(until, now));
+        assert!(is_paused_at(until, now + 3599));
+        assert!(!is_paused_at(until, now + 3600));
+        assert_eq!(Pause::EightHours.until(now), now + 8 * 3600);
+    }
+
+    #[test]
+    fn until_restart_never_expires() {
+        assert!(is_paused_at(Pause::UntilRestart.until(0), u64::MAX - 1));
+    }
+
+    #[test]
+    fn turned_off_lasts_until_turned_on() {
+        let until = Pause::UntilResumed.until(now_secs());
+        assert!(is_paused_at(until, now_secs()));
+        assert!(turned_off(Some(until)));
+        assert!(!turned_off(Some(Pause::OneHour.until(0))));
+    }
+
+    #[test]
+    fn pause_state_counts_whole_minutes_down() {
+        let now = 1_000_000;
+        let until = Pause::OneHour.until(now);
+        assert_eq!(pause_state_at(until, now), PauseState::PausedFor(60));
+        assert_eq!(pause_state_at(until, now + 1), PauseState::PausedFor(60));
+        assert_eq!(pause_state_at(until, now + 3540), PauseState::PausedFor(1));
+        assert_eq!(pause_state_at(until, now + 3600), PauseState::On);
+        assert_eq!(pause_state_at(0, now), PauseState::On);
+        assert_eq!(
+            pause_state_at(Pause::UntilRestart.until(now), now),
+            PauseState::UntilRestart
+        );
+        assert_eq!(
+            pause_state_at(Pause::UntilResumed.until(now), now),
+            PauseState::TurnedOff
+        );
+    }
+
+    #[test]
+    fn not_paused_by_default() {
+        assert!(!is_paused_at(0, 0));
+        assert!(!is_paused_at(0, now_secs()));
+    }
+}
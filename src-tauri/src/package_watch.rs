@@ -0,0 +1,92 @@
+//! The opt-in shared package watcher (`check_shared_packages`): when a local
+//! file several devices pull in (see [`crate::devices::packages`]) changes,
+//! the tray offers to check every one of them before any is flashed.
+//!
+//! The config directory is rescanned every [`POLL_INTERVAL`] and the shared
+//! files' modification times compared with the last scan's. A change becomes
+//! the pending [`Event::PackageChanged`], which enables the tray's "Check
+//! Devices Using Changed Package..." item, and a notification points there,
+//! as for copied snippets ([`crate::snippets`]). A file only one device uses
+//! isn't watched: checking that device is what the dashboard is for.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use anyhow::Result;
+use tauri::AppHandle;
+use tracing::{debug, info, warn};
+
+use crate::devices::packages::{self, Kind};
+use crate::i18n::{t, t_with};
+use crate::store::{Event, Store};
+use crate::tasks::Tasks;
+
+/// Time between scans of the config directory.
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Each shared local file's modification time and how many devices use it.
+type Times = BTreeMap<String, (SystemTime, usize)>;
+
+/// Watch the shared files under `config_dir` for as long as `tasks` lives.
+pub(crate) fn spawn(app: AppHandle, tasks: &Tasks, store: Store, config_dir: PathBuf) {
+    tasks.spawn("package watcher", async move {
+        let mut seen = scan(&config_dir).await;
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            let now = scan(&config_dir).await;
+            let changed = now
+                .iter()
+                .filter(|(file, (time, _))| seen.get(*file).is_some_and(|(was, _)| was != time))
+                .map(|(file, (_, devices))| (file.clone(), *devices))
+                .next_back();
+            seen = now;
+            let Some((file, devices)) = changed else {
+                continue;
+            };
+            info!(
+                "Shared package {} changed; {} devices use it",
+                file, devices
+            );
+            store.publish(Event::PackageChanged(Some(file.clone())));
+            let body = t_with(
+                "dependents.changed",
+                &[("file", &file), ("count", &devices.to_string())],
+            );
+            if let Err(e) = crate::notifications::show(&app, t("dependents.changed_title"), body) {
+                warn!("Failed to show shared package notification: {}", e);
+            }
+        }
+    });
+}
+
+/// The shared files' times now; none when the directory can't be read, so
+/// a file isn't taken for changed once it can be again.
+async fn scan(config_dir: &Path) -> Times {
+    let dir = config_dir.to_path_buf();
+    match tokio::task::spawn_blocking(move || shared_times(&dir)).await {
+        Ok(Ok(times)) => times,
+        Ok(Err(e)) => {
+            debug!("Failed to scan for shared packages: {:#}", e);
+            Times::new()
+        }
+        Err(e) => {
+            warn!("Shared package scan panicked: {}", e);
+            Times::new()
+        }
+    }
+}
+
+fn shared_times(config_dir: &Path) -> Result<Times> {
+    Ok(packages::graph(config_dir)?
+        .packages
+        .into_iter()
+        .filter(|p| p.kind == Kind::Local && p.devices.len() > 1)
+        .filter_map(|p| {
+            let modified = std::fs::metadata(config_dir.join(&p.name))
+                .and_then(|m| m.modified())
+                .ok()?;
+            Some((p.name, (modified, p.devices.len())))
+        })
+        .collect())
+}
@@ -0,0 +1,39 @@
+//! The "Shared Packages..." window: which devices each shared package or
+//! included file reaches, and what each device pulls in, so the devices an
+//! edit to a shared file touches are known before it is made (see
+//! [`crate::devices::packages`]). The page is `dist/packages.html`.
+
+use tauri::AppHandle;
+use tracing::error;
+
+use crate::devices::packages::{self, Graph};
+use crate::i18n::{t, t_with};
+
+/// Show the package window, opening it if it isn't.
+pub(crate) fn open(app: &AppHandle) -> tauri::Result<()> {
+    let texts = serde_json::json!({
+        "intro": t("packages.intro"),
+        "by_package": t("packages.by_package"),
+        "by_device": t("packages.by_device"),
+        "devices": t("packages.devices"),
+        "remote": t("packages.remote"),
+        "missing": t("packages.missing"),
+        "none": t("packages.none"),
+        "no_packages": t("packages.no_packages"),
+    });
+    crate::window::open(app, "packages", "packages.html", t("packages.title"), texts)
+}
+
+/// Every device's shared files, read afresh so edits since the window
+/// opened show.
+#[tauri::command]
+pub(crate) async fn package_graph(app: AppHandle) -> Result<Graph, String> {
+    let config_dir = crate::window::app_state(&app)?.daemon.config_dir().clone();
+    let graph = tauri::async_runtime::spawn_blocking(move || packages::graph(&config_dir))
+        .await
+        .map_err(|e| e.to_string())?;
+    graph.map_err(|e| {
+        error!("Failed to read shared packages: {:#}", e);
+        t_with("packages.failed", &[("error", &format!("{e:#}"))])
+    })
+}
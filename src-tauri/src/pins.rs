@@ -0,0 +1,38 @@
+//! The "Pin Usage..." window: each device's GPIO pins and what uses them,
+//! pins assigned twice flagged, each row opening the config at its line
+//! (see [`crate::devices::pins`]). The page is `dist/pins.html`.
+
+use tauri::AppHandle;
+use tracing::error;
+
+use crate::devices::pins::{self, DevicePins};
+use crate::i18n::{t, t_with};
+
+/// Show the pin window, opening it if it isn't.
+pub(crate) fn open(app: &AppHandle) -> tauri::Result<()> {
+    let texts = serde_json::json!({
+        "intro": t("pins.intro"),
+        "pin": t("pins.pin"),
+        "component": t("pins.component"),
+        "line": t("pins.line"),
+        "shared": t("pins.shared"),
+        "conflict": t("pins.conflict"),
+        "conflicts": t("pins.conflicts"),
+        "no_pins": t("pins.no_pins"),
+        "no_devices": t("pins.no_devices"),
+    });
+    crate::window::open(app, "pins", "pins.html", t("pins.title"), texts)
+}
+
+/// Every device's pins, read afresh so edits since the window opened show.
+#[tauri::command]
+pub(crate) async fn pin_overview(app: AppHandle) -> Result<Vec<DevicePins>, String> {
+    let config_dir = crate::window::app_state(&app)?.daemon.config_dir().clone();
+    let overview = tauri::async_runtime::spawn_blocking(move || pins::overview(&config_dir))
+        .await
+        .map_err(|e| e.to_string())?;
+    overview.map_err(|e| {
+        error!("Failed to read pin usage: {:#}", e);
+        t_with("pins.failed", &[("error", &format!("{e:#}"))])
+    })
+}
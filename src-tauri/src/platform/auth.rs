@@ -0,0 +1,86 @@
+//! OS authentication for destructive actions (`require_auth`).
+//!
+//! Each platform asks through its own trusted prompt, so the app never sees a
+//! password: Windows Hello (face, fingerprint or PIN) on Windows, the system
+//! administrator prompt on macOS (which offers Touch ID where the Mac has it),
+//! and polkit on Linux. All of them block until the user answers, so callers
+//! run this off the async executor.
+
+use anyhow::Result;
+
+/// Ask the user to authenticate for `reason`. `Ok(false)` means they
+/// cancelled or failed; `Err` means the platform can't ask at all (no Windows
+/// Hello set up, no polkit), which callers treat as a refusal too.
+pub fn authenticate(reason: &str) -> Result<bool> {
+    imp::authenticate(reason)
+}
+
+#[cfg(target_os = "windows")]
+mod imp {
+    use ::windows::core::HSTRING;
+    use ::windows::Security::Credentials::UI::{
+        UserConsentVerificationResult, UserConsentVerifier, UserConsentVerifierAvailability,
+    };
+    use anyhow::Result;
+
+    pub(super) fn authenticate(reason: &str) -> Result<bool> {
+        let availability = UserConsentVerifier::CheckAvailabilityAsync()?.get()?;
+        if availability != UserConsentVerifierAvailability::Available {
+            anyhow::bail!("Windows Hello is not available ({availability:?})");
+        }
+        let result =
+            UserConsentVerifier::RequestVerificationAsync(&HSTRING::from(reason))?.get()?;
+        Ok(result == UserConsentVerificationResult::Verified)
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod imp {
+    use anyhow::{Context, Result};
+    use std::process::Command;
+
+    /// A no-op run through the administrator prompt. Authorization Services
+    /// shows `reason` and takes Touch ID or the password; cancelling makes
+    /// osascript exit non-zero (error -128).
+    pub(super) fn authenticate(reason: &str) -> Result<bool> {
+        let reason = reason.replace('\\', "\\\\").replace('"', "\\\"");
+        let script = format!(
+            "do shell script \"true\" with prompt \"{reason}\" with administrator privileges"
+        );
+        let status = Command::new("osascript")
+            .args(["-e", &script])
+            .status()
+            .context("Failed to run osascript")?;
+        Ok(status.success())
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod imp {
+    use anyhow::{Context, Result};
+    use std::io::ErrorKind;
+    use std::process::Command;
+
+    /// pkexec exit code when no authentication agent could ask the user.
+    const PKEXEC_NO_AGENT: i32 = 127;
+
+    /// A no-op run through pkexec, which has the session's polkit agent ask
+    /// for an administrator's password. polkit only shows its own message for
+    /// the action, so `reason` goes to the log instead.
+    pub(super) fn authenticate(reason: &str) -> Result<bool> {
+        tracing::info!("Asking polkit to authenticate: {}", reason);
+        let status = match Command::new("pkexec").arg("/bin/true").status() {
+            Ok(status) => status,
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                anyhow::bail!("pkexec is not installed; install polkit to use require_auth")
+            }
+            Err(e) => return Err(e).context("Failed to run pkexec"),
+        };
+        if status.code() == Some(PKEXEC_NO_AGENT) {
+            // Also the "not authorized" code, but without an agent the user
+            // never saw a prompt, which is worth saying in the log.
+            tracing::warn!("pkexec refused; is a polkit authentication agent running?");
+        }
+        Ok(status.success())
+    }
+}
@@ -0,0 +1,98 @@
+//! The text on the clipboard, for the opt-in snippet watcher
+//! ([`crate::snippets`]).
+//!
+//! Windows reads the Win32 clipboard directly and has a sequence number, so
+//! an unchanged clipboard costs one call. Elsewhere each platform's own tool
+//! runs: `pbpaste` on macOS, `wl-paste` under Wayland and `xclip` under X11.
+//! A Linux desktop without the right one gets an error.
+
+use anyhow::{Context, Result};
+
+/// A number that changes whenever the clipboard does, where the platform
+/// keeps one; the watcher skips reading while it stays the same.
+pub fn clipboard_sequence() -> Option<u32> {
+    #[cfg(target_os = "windows")]
+    {
+        // SAFETY: takes no arguments and only reads a counter.
+        Some(unsafe { ::windows::Win32::System::DataExchange::GetClipboardSequenceNumber() })
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        None
+    }
+}
+
+/// The clipboard's text, or `None` when it holds something else or nothing.
+pub fn clipboard_text() -> Result<Option<String>> {
+    #[cfg(target_os = "windows")]
+    {
+        read_win32()
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        read_with_tool()
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn read_with_tool() -> Result<Option<String>> {
+    let (program, args): (&str, &[&str]) = if cfg!(target_os = "macos") {
+        ("pbpaste", &[])
+    } else if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        ("wl-paste", &["--no-newline", "--type", "text"])
+    } else {
+        ("xclip", &["-selection", "clipboard", "-out"])
+    };
+    let output = std::process::Command::new(program)
+        .args(args)
+        .stdin(std::process::Stdio::null())
+        .output()
+        .with_context(|| format!("Failed to run {program}"))?;
+    // `wl-paste` and `xclip` both fail on an empty clipboard or one without
+    // text.
+    if !output.status.success() {
+        return Ok(None);
+    }
+    let text = String::from_utf8_lossy(&output.stdout).into_owned();
+    Ok((!text.is_empty()).then_some(text))
+}
+
+#[cfg(target_os = "windows")]
+fn read_win32() -> Result<Option<String>> {
+    use ::windows::Win32::Foundation::HGLOBAL;
+    use ::windows::Win32::System::DataExchange::{
+        CloseClipboard, GetClipboardData, IsClipboardFormatAvailable, OpenClipboard,
+    };
+    use ::windows::Win32::System::Memory::{GlobalLock, GlobalSize, GlobalUnlock};
+
+    /// `CF_UNICODETEXT`: UTF-16, NUL-terminated.
+    const UNICODE_TEXT: u32 = 13;
+
+    // SAFETY: the clipboard is opened before its data is read and closed on
+    // every path after; the handle it returns stays valid while it is open,
+    // and the locked memory is read only within its `GlobalSize`.
+    unsafe {
+        if IsClipboardFormatAvailable(UNICODE_TEXT).is_err() {
+            return Ok(None);
+        }
+        // Another program holding the clipboard open fails this; the next
+        // poll tries again.
+        OpenClipboard(None).context("Failed to open the clipboard")?;
+        let text = (|| -> Result<Option<String>> {
+            let handle = GetClipboardData(UNICODE_TEXT).context("Failed to read the clipboard")?;
+            let memory = HGLOBAL(handle.0);
+            let units = GlobalLock(memory) as *const u16;
+            if units.is_null() {
+                return Ok(None);
+            }
+            let len = GlobalSize(memory) / 2;
+            let units = std::slice::from_raw_parts(units, len);
+            let end = units.iter().position(|&u| u == 0).unwrap_or(len);
+            let text = String::from_utf16_lossy(&units[..end]);
+            let _ = GlobalUnlock(memory);
+            Ok(Some(text))
+        })();
+        let _ = CloseClipboard();
+        text
+    }
+}
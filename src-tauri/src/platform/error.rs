@@ -0,0 +1,51 @@
+//! Why an app directory or the interpreter couldn't be resolved.
+
+use std::path::PathBuf;
+
+use crate::error::UserError;
+use crate::i18n::t_with;
+
+/// A failure resolving one of the app's directories or its Python.
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum PlatformError {
+    /// The OS has no such directory for the app (no home, no profile).
+    #[error("Failed to get app {dir} directory: {error}")]
+    Locate {
+        dir: &'static str,
+        error: tauri::Error,
+    },
+    #[error("Failed to create {dir} directory {path:?}: {error}")]
+    Create {
+        dir: &'static str,
+        path: PathBuf,
+        error: std::io::Error,
+    },
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+impl UserError for PlatformError {
+    fn kind(&self) -> &'static str {
+        match self {
+            PlatformError::Locate { .. } => "platform.locate",
+            PlatformError::Create { .. } => "platform.create",
+            PlatformError::Other(_) => "platform.other",
+        }
+    }
+
+    fn user_message(&self) -> String {
+        match self {
+            PlatformError::Locate { error, .. } => {
+                t_with("errors.platform_locate", &[("error", &error.to_string())])
+            }
+            PlatformError::Create { path, error, .. } => t_with(
+                "errors.platform_create",
+                &[
+                    ("path", &path.display().to_string()),
+                    ("error", &error.to_string()),
+                ],
+            ),
+            PlatformError::Other(e) => e.to_string(),
+        }
+    }
+}
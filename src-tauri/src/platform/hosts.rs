@@ -0,0 +1,124 @@
+//! The system hosts file, where a `dashboard_hostname` that isn't announced
+//! over mDNS is added. Reading it needs no privileges; writing it is one
+//! elevated copy of the new text over it: `osascript` with administrator
+//! privileges on macOS, `pkexec` on Linux and a UAC prompt on Windows.
+
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+
+/// Where the hosts file is.
+pub(crate) fn path() -> PathBuf {
+    imp::path()
+}
+
+/// Replace the hosts file with `text`, asking for administrator rights.
+pub(crate) fn write_elevated(text: &str) -> Result<()> {
+    let staged = std::env::temp_dir().join("esphome-desktop-hosts");
+    std::fs::write(&staged, text).with_context(|| format!("Failed to write {staged:?}"))?;
+    let copied = imp::copy_elevated(&staged);
+    let _ = std::fs::remove_file(&staged);
+    copied
+}
+
+#[cfg(target_os = "windows")]
+mod imp {
+    use anyhow::{bail, Context, Result};
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+
+    use crate::platform::windows::system32;
+
+    pub(super) fn path() -> PathBuf {
+        system32(r"drivers\etc\hosts")
+    }
+
+    /// `Start-Process -Verb RunAs` elevates, as for the firewall rule.
+    pub(super) fn copy_elevated(staged: &Path) -> Result<()> {
+        // PowerShell single-quoted strings escape ' by doubling it.
+        let quote = |path: &Path| path.display().to_string().replace('\'', "''");
+        let command = format!(
+            "Start-Process -FilePath '{}' -ArgumentList '/c copy /y \"{}\" \"{}\"' -Verb RunAs -Wait",
+            quote(&system32("cmd.exe")),
+            quote(staged),
+            quote(&path()),
+        );
+        let mut cmd = Command::new(system32(r"WindowsPowerShell\v1.0\powershell.exe"));
+        cmd.args(["-NoProfile", "-NonInteractive", "-Command", &command]);
+        crate::platform::configure_no_window_command(&mut cmd);
+        let output = cmd.output().context("Failed to run powershell")?;
+        if !output.status.success() {
+            bail!(
+                "elevation failed or was declined: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod imp {
+    use anyhow::{bail, Context, Result};
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+
+    pub(super) fn path() -> PathBuf {
+        PathBuf::from("/etc/hosts")
+    }
+
+    pub(super) fn copy_elevated(staged: &Path) -> Result<()> {
+        let output = Command::new("osascript")
+            .args([
+                "-e",
+                "on run argv",
+                "-e",
+                "do shell script \"cp \" & quoted form of (item 1 of argv) & \" /etc/hosts\" with administrator privileges",
+                "-e",
+                "end run",
+            ])
+            .arg(staged)
+            .output()
+            .context("Failed to run osascript")?;
+        if !output.status.success() {
+            bail!(
+                "writing /etc/hosts failed or was declined: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod imp {
+    use anyhow::{bail, Result};
+    use std::io::ErrorKind;
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+
+    pub(super) fn path() -> PathBuf {
+        PathBuf::from("/etc/hosts")
+    }
+
+    pub(super) fn copy_elevated(staged: &Path) -> Result<()> {
+        let output = match Command::new("pkexec")
+            .args(["cp", "--"])
+            .arg(staged)
+            .arg(path())
+            .output()
+        {
+            Ok(output) => output,
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                bail!("pkexec is not installed; add the entry to /etc/hosts yourself")
+            }
+            Err(e) => return Err(anyhow::Error::new(e).context("Failed to run pkexec")),
+        };
+        if !output.status.success() {
+            bail!(
+                "writing /etc/hosts failed or was declined: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(())
+    }
+}
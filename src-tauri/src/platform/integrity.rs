@@ -0,0 +1,272 @@
+//! Is the install intact? Checks the bundled resources against the manifest
+//! the build wrote (`build-scripts/write_bundle_manifest.py`).
+//!
+//! Bad downloads and antivirus quarantine leave an install with files missing
+//! or truncated, which otherwise surfaces much later as a backend that won't
+//! start or a compile that dies with an import error. Every listed file's size
+//! is checked; only the native binaries outside `site-packages` carry a hash,
+//! which keeps the check to a fraction of a second.
+//!
+//! Two trees are checked: the bundle itself, which nothing should ever change,
+//! and the user's copy of it, whose binaries should still match. The bundle
+//! being damaged needs a reinstall; only the copy being damaged, a repair. A
+//! build without a manifest (a development build) is not checked.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::io::{ErrorKind, Read};
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+use super::python_env::PYTHON_VERSION_MARKER;
+use super::{get_bundled_resource_dir, get_python_parent_dir, PYTHON_TREE_DIRNAME};
+
+/// The manifest's path inside the `python` resource.
+const MANIFEST_NAME: &str = ".bundle-manifest.json";
+
+#[derive(Debug, Deserialize)]
+struct Manifest {
+    /// Keyed by path relative to the resource directory, `/`-separated.
+    files: BTreeMap<String, FileEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileEntry {
+    size: u64,
+    #[serde(default)]
+    sha256: Option<String>,
+}
+
+/// What the check found, one line per damaged file (`path: problem`).
+#[derive(Debug, Default)]
+pub struct IntegrityReport {
+    /// Damage to the bundled resources.
+    pub bundle: Vec<String>,
+    /// Damage to the user's copy of the Python tree.
+    pub user_tree: Vec<String>,
+}
+
+/// Check the bundle and the user's Python tree against the manifest. `None`
+/// when the build shipped no manifest.
+pub fn verify_install(app_handle: &AppHandle) -> Result<Option<IntegrityReport>> {
+    let resource_dir = get_bundled_resource_dir(app_handle)?;
+    let user_tree = get_python_parent_dir(app_handle)?.join(PYTHON_TREE_DIRNAME);
+    verify(&resource_dir, &user_tree)
+}
+
+fn verify(resource_dir: &Path, user_tree: &Path) -> Result<Option<IntegrityReport>> {
+    let manifest_path = resource_dir.join("python").join(MANIFEST_NAME);
+    let manifest: Manifest = match std::fs::read_to_string(&manifest_path) {
+        Ok(content) => serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {manifest_path:?}"))?,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {manifest_path:?}")),
+    };
+
+    let bundle = manifest
+        .files
+        .iter()
+        .filter_map(|(path, entry)| check(&join(resource_dir, path), entry).map(|p| (path, p)))
+        .map(|(path, problem)| format!("{path}: {problem}"))
+        .collect();
+
+    // A copy from another version's bundle (the refresh was deferred) is
+    // expected to differ.
+    let copy_is_current = std::fs::read_to_string(user_tree.join(PYTHON_VERSION_MARKER))
+        .is_ok_and(|v| v.trim() == env!("CARGO_PKG_VERSION"));
+    let user_tree = if copy_is_current {
+        manifest
+            .files
+            .iter()
+            .filter(|(_, entry)| entry.sha256.is_some())
+            .filter_map(|(path, entry)| Some((path, path.strip_prefix("python/")?, entry)))
+            .filter_map(|(path, rest, entry)| {
+                check(&join(user_tree, rest), entry).map(|p| (path, p))
+            })
+            .map(|(path, problem)| format!("{path}: {problem}"))
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    Ok(Some(IntegrityReport { bundle, user_tree }))
+}
+
+fn join(root: &Path, relative: &str) -> PathBuf {
+    relative
+        .split('/')
+        .fold(root.to_path_buf(), |p, c| p.join(c))
+}
+
+/// What's wrong with the file at `path`, if anything.
+fn check(path: &Path, entry: &FileEntry) -> Option<String> {
+    let size = match std::fs::metadata(path) {
+        Ok(meta) => meta.len(),
+        Err(e) if e.kind() == ErrorKind::NotFound => return Some("missing".into()),
+        Err(e) => return Some(format!("unreadable ({e})")),
+    };
+    if size != entry.size {
+        return Some(format!("{size} bytes, expected {}", entry.size));
+    }
+    let expected = entry.sha256.as_deref()?;
+    match std::fs::File::open(path).and_then(sha256) {
+        Ok(actual) if actual.eq_ignore_ascii_case(expected) => None,
+        Ok(_) => Some("contents changed".into()),
+        Err(e) => Some(format!("unreadable ({e})")),
+    }
+}
+
+/// SHA-256 of everything `reader` yields, hex. Hand-rolled (FIPS 180-4) for
+/// the one place that needs it, rather than a crypto dependency.
+fn sha256(mut reader: impl Read) -> std::io::Result<String> {
+    let mut state = H0;
+    let mut total: u64 = 0;
+    let mut pending = Vec::with_capacity(64);
+    let mut buf = vec![0u8; 1 << 16];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        total += n as u64;
+        pending.extend_from_slice(&buf[..n]);
+        let full = pending.len() - pending.len() % 64;
+        pending[..full]
+            .chunks_exact(64)
+            .for_each(|block| compress(&mut state, block));
+        pending.drain(..full);
+    }
+    pending.push(0x80);
+    while pending.len() % 64 != 56 {
+        pending.push(0);
+    }
+    pending.extend_from_slice(&(total * 8).to_be_bytes());
+    pending
+        .chunks_exact(64)
+        .for_each(|block| compress(&mut state, block));
+    Ok(state.iter().map(|word| format!("{word:08x}")).collect())
+}
+
+const H0: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+fn compress(state: &mut [u32; 8], block: &[u8]) {
+    let mut w = [0u32; 64];
+    for (word, bytes) in w.iter_mut().zip(block.chunks_exact(4)) {
+        *word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    }
+    for i in 16..64 {
+        let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+        let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+        w[i] = w[i - 16]
+            .wrapping_add(s0)
+            .wrapping_add(w[i - 7])
+            .wrapping_add(s1);
+    }
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+    for (k, w) in K.iter().zip(w) {
+        let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+        let ch = (e & f) ^ (!e & g);
+        let t1 = h
+            .wrapping_add(s1)
+            .wrapping_add(ch)
+            .wrapping_add(*k)
+            .wrapping_add(w);
+        let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+        let t2 = s0.wrapping_add(maj);
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(t1);
+        d = c;
+        c = b;
+        b = a;
+        a = t1.wrapping_add(t2);
+    }
+    for (s, v) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+        *s = s.wrapping_add(v);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::unique_temp_dir;
+    use std::fs;
+
+    #[test]
+    fn sha256_matches_known_digests() {
+        assert_eq!(
+            sha256(&b""[..]).unwrap(),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            sha256(&b"abc"[..]).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            sha256(&[b'a'; 1000][..]).unwrap(),
+            "41edece42d63e8d9bf515a9ba6932e1c20cbc9f5a5d134645adb5db1b9737ea3"
+        );
+    }
+
+    #[test]
+    fn reports_missing_truncated_and_changed_files() {
+        let dir = unique_temp_dir("integrity");
+        let resources = dir.join("resources");
+        let user_tree = dir.join("user");
+        fs::create_dir_all(resources.join("python/bin")).unwrap();
+        fs::create_dir_all(user_tree.join("bin")).unwrap();
+        fs::write(resources.join("python/bin/python3"), "abc").unwrap();
+        fs::write(resources.join("python/short.py"), "x").unwrap();
+        fs::write(user_tree.join("bin/python3"), "abd").unwrap();
+        fs::write(
+            user_tree.join(PYTHON_VERSION_MARKER),
+            env!("CARGO_PKG_VERSION"),
+        )
+        .unwrap();
+        let manifest = serde_json::json!({ "files": {
+            "python/bin/python3": {
+                "size": 3,
+                "sha256": "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            },
+            "python/short.py": { "size": 2 },
+            "python/gone.py": { "size": 1 },
+        }});
+        fs::write(
+            resources.join("python").join(MANIFEST_NAME),
+            manifest.to_string(),
+        )
+        .unwrap();
+
+        let report = verify(&resources, &user_tree).unwrap().unwrap();
+        assert_eq!(
+            report.bundle,
+            [
+                "python/gone.py: missing",
+                "python/short.py: 1 bytes, expected 2"
+            ]
+        );
+        assert_eq!(report.user_tree, ["python/bin/python3: contents changed"]);
+
+        // No manifest, no check.
+        fs::remove_file(resources.join("python").join(MANIFEST_NAME)).unwrap();
+        assert!(verify(&resources, &user_tree).unwrap().is_none());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,194 @@
+//! Secrets in the OS credential store, one per account name.
+//!
+//! Each platform's own store, so a secret is encrypted at rest and unlocked
+//! with the user's login: the login keychain through `security` on macOS,
+//! the Secret Service (GNOME Keyring, KWallet) through `secret-tool` on
+//! Linux, and the Credential Locker on Windows. Entries are filed under
+//! `service`, so the user can find and remove them in the platform's own
+//! keychain app too.
+
+use anyhow::Result;
+
+/// Store `secret` for `account`, replacing any stored before.
+pub(crate) fn store(service: &str, account: &str, secret: &str) -> Result<()> {
+    imp::store(service, account, secret)
+}
+
+/// The secret stored for `account`, if any.
+pub(crate) fn load(service: &str, account: &str) -> Result<Option<String>> {
+    imp::load(service, account)
+}
+
+/// Remove `account`'s secret; removing one that isn't there is fine.
+pub(crate) fn delete(service: &str, account: &str) -> Result<()> {
+    imp::delete(service, account)
+}
+
+#[cfg(target_os = "windows")]
+mod imp {
+    use ::windows::core::HSTRING;
+    use ::windows::Security::Credentials::{PasswordCredential, PasswordVault};
+    use ::windows::Win32::Foundation::ERROR_NOT_FOUND;
+    use anyhow::{Context, Result};
+
+    pub(super) fn store(service: &str, account: &str, secret: &str) -> Result<()> {
+        let credential = PasswordCredential::CreatePasswordCredential(
+            &HSTRING::from(service),
+            &HSTRING::from(account),
+            &HSTRING::from(secret),
+        )?;
+        PasswordVault::new()?
+            .Add(&credential)
+            .context("Failed to store the credential")
+    }
+
+    pub(super) fn load(service: &str, account: &str) -> Result<Option<String>> {
+        match retrieve(service, account)? {
+            Some(credential) => {
+                credential.RetrievePassword()?;
+                Ok(Some(credential.Password()?.to_string()))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub(super) fn delete(service: &str, account: &str) -> Result<()> {
+        if let Some(credential) = retrieve(service, account)? {
+            PasswordVault::new()?
+                .Remove(&credential)
+                .context("Failed to remove the credential")?;
+        }
+        Ok(())
+    }
+
+    fn retrieve(service: &str, account: &str) -> Result<Option<PasswordCredential>> {
+        match PasswordVault::new()?.Retrieve(&HSTRING::from(service), &HSTRING::from(account)) {
+            Ok(credential) => Ok(Some(credential)),
+            Err(e) if e.code() == ERROR_NOT_FOUND.to_hresult() => Ok(None),
+            Err(e) => Err(e).context("Failed to read the credential"),
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod imp {
+    use anyhow::{bail, Context, Result};
+    use std::process::Command;
+
+    /// `security` exit code for an item that isn't in the keychain.
+    const NOT_FOUND: i32 = 44;
+
+    /// `security` only takes the secret as an argument, so it shows in the
+    /// process list for the moment this runs.
+    pub(super) fn store(service: &str, account: &str, secret: &str) -> Result<()> {
+        let status = Command::new("security")
+            .args(["add-generic-password", "-U", "-s", service, "-a", account])
+            .args(["-w", secret])
+            .status()
+            .context("Failed to run security")?;
+        if !status.success() {
+            bail!("security add-generic-password exited with {status}");
+        }
+        Ok(())
+    }
+
+    pub(super) fn load(service: &str, account: &str) -> Result<Option<String>> {
+        let output = Command::new("security")
+            .args(["find-generic-password", "-s", service, "-a", account, "-w"])
+            .output()
+            .context("Failed to run security")?;
+        match output.status.code() {
+            Some(0) => Ok(Some(
+                String::from_utf8_lossy(&output.stdout).trim().to_string(),
+            )),
+            Some(NOT_FOUND) => Ok(None),
+            _ => bail!(
+                "security find-generic-password exited with {}",
+                output.status
+            ),
+        }
+    }
+
+    pub(super) fn delete(service: &str, account: &str) -> Result<()> {
+        let status = Command::new("security")
+            .args(["delete-generic-password", "-s", service, "-a", account])
+            .output()
+            .context("Failed to run security")?
+            .status;
+        match status.code() {
+            Some(0) | Some(NOT_FOUND) => Ok(()),
+            _ => bail!("security delete-generic-password exited with {status}"),
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod imp {
+    use anyhow::{bail, Context, Result};
+    use std::io::{ErrorKind, Write};
+    use std::process::{Command, Stdio};
+
+    fn secret_tool(args: &[&str]) -> Command {
+        let mut cmd = Command::new("secret-tool");
+        cmd.args(args);
+        cmd
+    }
+
+    /// Why `secret-tool` didn't start, with a hint when it isn't installed.
+    fn not_started(e: std::io::Error) -> anyhow::Error {
+        if e.kind() == ErrorKind::NotFound {
+            anyhow::anyhow!(
+                "secret-tool is not installed; install libsecret-tools (or libsecret) to \
+                 keep keys in the keyring"
+            )
+        } else {
+            anyhow::Error::new(e).context("Failed to run secret-tool")
+        }
+    }
+
+    /// `secret-tool` reads the secret from stdin, so it never shows in the
+    /// process list.
+    pub(super) fn store(service: &str, account: &str, secret: &str) -> Result<()> {
+        let label = format!("{service} {account}");
+        let mut child = secret_tool(&["store", "--label", &label])
+            .args(["service", service, "account", account])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .spawn()
+            .map_err(not_started)?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(secret.as_bytes())
+                .context("Failed to pass the secret to secret-tool")?;
+        }
+        let status = child.wait().context("Failed to wait for secret-tool")?;
+        if !status.success() {
+            bail!("secret-tool store exited with {status}; is a keyring running?");
+        }
+        Ok(())
+    }
+
+    /// `secret-tool lookup` exits 1 with no output for a missing item.
+    pub(super) fn load(service: &str, account: &str) -> Result<Option<String>> {
+        let output = secret_tool(&["lookup", "service", service, "account", account])
+            .output()
+            .map_err(not_started)?;
+        let secret = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if output.status.success() || (secret.is_empty() && output.stderr.is_empty()) {
+            return Ok((!secret.is_empty()).then_some(secret));
+        }
+        bail!(
+            "secret-tool lookup exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+    }
+
+    pub(super) fn delete(service: &str, account: &str) -> Result<()> {
+        // Also exits 1 when there was nothing to clear.
+        secret_tool(&["clear", "service", service, "account", account])
+            .output()
+            .map_err(not_started)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,210 @@
+//! Where each kind of file goes.
+//!
+//! On Linux the XDG base directories decide, so backups and dotfile managers
+//! can tell settings from logs and throwaway copies: `settings.json` in
+//! `$XDG_CONFIG_HOME/io.esphome.builder/`, the logs in
+//! `$XDG_STATE_HOME/io.esphome.builder/logs/`, the comparison copies and
+//! build diffs in `$XDG_CACHE_HOME/io.esphome.builder/`, and everything else,
+//! the Python tree and venvs included, in `$XDG_DATA_HOME` as before.
+//! Elsewhere it all stays in the data directory.
+//!
+//! Each is derived like [`super::data_dir_no_handle`], so the CLI finds the
+//! same places as the app. [`migrate_layout`] moves files from the single
+//! directory earlier builds used.
+
+use std::path::{Path, PathBuf};
+
+use tauri::AppHandle;
+
+use super::{create_dir, data_dir_no_handle, get_data_dir, PlatformError, BUNDLE_IDENTIFIER};
+
+/// The settings file, in [`config_dir_no_handle`].
+pub const SETTINGS_FILE_NAME: &str = "settings.json";
+
+const LOGS_DIR_NAME: &str = "logs";
+
+/// Under [`cache_dir_no_handle`].
+const COMPARE_DIR_NAME: &str = "compare";
+
+/// Where `settings.json` is.
+pub fn config_dir_no_handle() -> Option<PathBuf> {
+    if cfg!(target_os = "linux") {
+        dirs::config_dir().map(|d| d.join(BUNDLE_IDENTIFIER))
+    } else {
+        data_dir_no_handle()
+    }
+}
+
+/// Where the app's and the dashboard's logs are.
+pub fn logs_dir_no_handle() -> Option<PathBuf> {
+    if cfg!(target_os = "linux") {
+        dirs::state_dir().map(|d| d.join(BUNDLE_IDENTIFIER).join(LOGS_DIR_NAME))
+    } else {
+        data_dir_no_handle().map(|d| d.join(LOGS_DIR_NAME))
+    }
+}
+
+/// Where files the app can make again go.
+pub fn cache_dir_no_handle() -> Option<PathBuf> {
+    if cfg!(target_os = "linux") {
+        dirs::cache_dir().map(|d| d.join(BUNDLE_IDENTIFIER))
+    } else {
+        data_dir_no_handle()
+    }
+}
+
+/// The settings file to read: where it belongs, or where an earlier build
+/// left it if the app hasn't moved it yet.
+pub fn settings_path_no_handle() -> Option<PathBuf> {
+    let path = config_dir_no_handle()?.join(SETTINGS_FILE_NAME);
+    if path.exists() {
+        return Some(path);
+    }
+    let legacy = data_dir_no_handle()?.join(SETTINGS_FILE_NAME);
+    Some(if legacy.exists() { legacy } else { path })
+}
+
+/// Resolve the directory for `settings.json`, creating it if needed.
+pub fn get_config_dir(app_handle: &AppHandle) -> Result<PathBuf, PlatformError> {
+    let path = if cfg!(target_os = "linux") {
+        xdg("config", config_dir_no_handle())?
+    } else {
+        get_data_dir(app_handle)?
+    };
+    create_dir("config", &path)?;
+    Ok(path)
+}
+
+/// Resolve the logs directory, creating it if needed.
+pub fn get_logs_dir(app_handle: &AppHandle) -> Result<PathBuf, PlatformError> {
+    let path = if cfg!(target_os = "linux") {
+        xdg("logs", logs_dir_no_handle())?
+    } else {
+        get_data_dir(app_handle)?.join(LOGS_DIR_NAME)
+    };
+    create_dir("logs", &path)?;
+    Ok(path)
+}
+
+/// Resolve where comparison dashboards' config copies and build diffs go,
+/// creating it if needed.
+pub fn get_compare_dir(app_handle: &AppHandle) -> Result<PathBuf, PlatformError> {
+    let path = if cfg!(target_os = "linux") {
+        xdg("cache", cache_dir_no_handle())?
+    } else {
+        get_data_dir(app_handle)?
+    };
+    let path = path.join(COMPARE_DIR_NAME);
+    create_dir("cache", &path)?;
+    Ok(path)
+}
+
+fn xdg(dir: &'static str, path: Option<PathBuf>) -> Result<PathBuf, PlatformError> {
+    path.ok_or_else(|| anyhow::anyhow!("The system has no {dir} base directory").into())
+}
+
+/// Move what an earlier build kept in the data directory to where it goes
+/// now: the settings unless there are some already, each log not there yet,
+/// and the comparison copies. Runs before logging starts, so it returns what
+/// it did, `Err` for what failed, to be logged once it has.
+pub fn migrate_layout() -> Vec<Result<String, String>> {
+    match (
+        data_dir_no_handle(),
+        config_dir_no_handle(),
+        logs_dir_no_handle(),
+        cache_dir_no_handle(),
+    ) {
+        (Some(data), Some(config), Some(logs), Some(cache)) => {
+            migrate(&data, &config, &logs, &cache.join(COMPARE_DIR_NAME))
+        }
+        _ => Vec::new(),
+    }
+}
+
+fn migrate(data: &Path, config: &Path, logs: &Path, compare: &Path) -> Vec<Result<String, String>> {
+    let mut moves = vec![(
+        data.join(SETTINGS_FILE_NAME),
+        config.join(SETTINGS_FILE_NAME),
+    )];
+    let old_logs = data.join(LOGS_DIR_NAME);
+    if old_logs != logs {
+        if let Ok(entries) = std::fs::read_dir(&old_logs) {
+            moves.extend(
+                entries
+                    .filter_map(|e| e.ok())
+                    .map(|e| (e.path(), logs.join(e.file_name()))),
+            );
+        }
+    }
+    moves.push((data.join(COMPARE_DIR_NAME), compare.to_path_buf()));
+
+    let mut done = Vec::new();
+    for (from, to) in moves {
+        if from == to || !from.exists() || to.exists() {
+            continue;
+        }
+        done.push(match move_path(&from, &to) {
+            Ok(()) => Ok(format!("Moved {from:?} to {to:?}")),
+            Err(e) => Err(format!("Failed to move {from:?} to {to:?}: {e}")),
+        });
+    }
+    // Only goes once emptied.
+    let _ = std::fs::remove_dir(&old_logs);
+    done
+}
+
+/// Rename, or copy and remove a file the rename fails on (another
+/// filesystem, usually).
+fn move_path(from: &Path, to: &Path) -> std::io::Result<()> {
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    match std::fs::rename(from, to) {
+        Err(_) if from.is_file() => {
+            std::fs::copy(from, to)?;
+            std::fs::remove_file(from)
+        }
+        result => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::unique_temp_dir;
+    use std::fs;
+
+    #[test]
+    fn moves_the_single_directory_layout() {
+        let root = unique_temp_dir("layout");
+        let data = root.join("share");
+        let (config, logs, compare) = (
+            root.join("config"),
+            root.join("state/logs"),
+            root.join("cache/compare"),
+        );
+        fs::create_dir_all(data.join("logs")).unwrap();
+        fs::create_dir_all(data.join("compare/config")).unwrap();
+        fs::create_dir_all(&logs).unwrap();
+        fs::write(data.join(SETTINGS_FILE_NAME), "{}").unwrap();
+        fs::write(data.join("logs/dashboard.log"), "old").unwrap();
+        fs::write(data.join("logs/app.log"), "old").unwrap();
+        fs::write(logs.join("app.log"), "new").unwrap();
+
+        let done = migrate(&data, &config, &logs, &compare);
+        assert_eq!(done.len(), 3, "{done:?}");
+        assert!(done.iter().all(Result::is_ok));
+        assert!(config.join(SETTINGS_FILE_NAME).exists());
+        assert_eq!(
+            fs::read_to_string(logs.join("dashboard.log")).unwrap(),
+            "old"
+        );
+        // A log already there stays, and so does the old one beside it.
+        assert_eq!(fs::read_to_string(logs.join("app.log")).unwrap(), "new");
+        assert!(data.join("logs/app.log").exists());
+        assert!(compare.join("config").is_dir());
+
+        assert!(migrate(&data, &config, &logs, &compare).is_empty());
+        let _ = fs::remove_dir_all(&root);
+    }
+}
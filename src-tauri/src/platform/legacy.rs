@@ -0,0 +1,91 @@
+//! Leftovers of the app's earlier names.
+
+use tauri::AppHandle;
+
+/// One-shot cleanup of the legacy `/Applications/ESPHome Builder.app` bundle
+/// left behind when the desktop app was renamed to "ESPHome Device Builder".
+///
+/// On the first launch after the rename the user is prompted (via a native
+/// dialog) to move the old bundle to the Trash. The decision is recorded as
+/// a marker file in the app data directory so the prompt is not repeated.
+///
+/// User settings and the bundled Python tree live under the bundle
+/// identifier (`io.esphome.builder/`), which did not change with the rename,
+/// so no data migration is needed.
+pub fn cleanup_legacy_macos_app(app_handle: &AppHandle) {
+    #[cfg(target_os = "macos")]
+    {
+        use std::path::PathBuf;
+        use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+        use tracing::{debug, info, warn};
+
+        const OLD_APP: &str = "/Applications/ESPHome Builder.app";
+        const MARKER_NAME: &str = ".legacy_macos_app_cleanup";
+
+        if !PathBuf::from(OLD_APP).exists() {
+            return;
+        }
+
+        let data_dir = match super::get_data_dir(app_handle) {
+            Ok(d) => d,
+            Err(e) => {
+                debug!("Skipping legacy app cleanup; data dir unavailable: {}", e);
+                return;
+            }
+        };
+
+        let marker = data_dir.join(MARKER_NAME);
+        if marker.exists() {
+            return;
+        }
+
+        info!("Legacy {} detected; prompting user to remove it", OLD_APP);
+
+        let dialog_app = app_handle.clone();
+        std::thread::spawn(move || {
+            let confirmed = dialog_app
+                .dialog()
+                .message(crate::i18n::t("platform.remove_legacy_prompt"))
+                .title(crate::i18n::t("platform.remove_legacy_title"))
+                .kind(MessageDialogKind::Info)
+                .buttons(MessageDialogButtons::OkCancelCustom(
+                    crate::i18n::t("platform.move_to_trash"),
+                    crate::i18n::t("platform.keep"),
+                ))
+                .blocking_show();
+
+            if confirmed {
+                let script = format!(
+                    "tell application \"Finder\" to delete POSIX file \"{}\"",
+                    OLD_APP
+                );
+                match std::process::Command::new("osascript")
+                    .args(["-e", &script])
+                    .output()
+                {
+                    Ok(out) if out.status.success() => {
+                        info!("Moved {} to Trash", OLD_APP);
+                    }
+                    Ok(out) => {
+                        warn!(
+                            "Failed to move {} to Trash: {}",
+                            OLD_APP,
+                            String::from_utf8_lossy(&out.stderr).trim()
+                        );
+                    }
+                    Err(e) => warn!("Failed to spawn osascript: {}", e),
+                }
+            }
+
+            // Marker is written regardless so the user is not nagged.
+            if let Err(e) = std::fs::write(&marker, "") {
+                warn!("Failed to write legacy-cleanup marker: {}", e);
+            }
+        });
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        let _ = app_handle;
+    }
+}
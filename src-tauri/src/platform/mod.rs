@@ -8,29 +8,66 @@ use std::path::{Path, PathBuf};
 use tauri::{AppHandle, Manager};
 use tracing::debug;
 
+mod auth;
+mod clipboard;
+mod error;
 mod health;
+pub(crate) mod hosts;
+mod integrity;
+pub(crate) mod keychain;
+mod layout;
+mod legacy;
 #[cfg(target_os = "linux")]
 mod linux;
 #[cfg(target_os = "macos")]
 mod macos;
 mod pip;
+mod priority;
+mod proc_table;
 mod process;
 mod python_env;
+mod random;
+mod sandbox;
+mod serial;
+pub(crate) mod trash;
+mod wifi;
 #[cfg(target_os = "windows")]
 mod windows;
 
+pub use auth::authenticate;
+pub use clipboard::{clipboard_sequence, clipboard_text};
+pub(crate) use error::PlatformError;
 pub use health::{
     clear_repair_count, esphome_config_probe, is_managed_python_tree, may_repair_tree,
     repair_budget_left,
 };
-pub use pip::{pip_command, pip_output_report, run_pip};
+pub use integrity::{verify_install, IntegrityReport};
+pub use layout::{
+    cache_dir_no_handle, config_dir_no_handle, get_compare_dir, get_config_dir, get_logs_dir,
+    logs_dir_no_handle, migrate_layout, settings_path_no_handle, SETTINGS_FILE_NAME,
+};
+pub use legacy::cleanup_legacy_macos_app;
+pub use pip::{pip_command, pip_output_report, run_pip, run_pip_watched};
+pub use priority::lower_priority;
+pub use proc_table::{descendants, process_table, terminate_pids, ProcInfo};
+#[cfg(target_os = "linux")]
+pub use proc_table::{resume_pids, suspend_pids};
 #[cfg(target_os = "windows")]
 pub use process::{assign_to_kill_on_close_job, send_ctrl_break};
 pub use process::{
-    configure_daemon_tokio_command, isolate_python_tokio_command, run_python_capture_stdout,
+    configure_daemon_tokio_command, configure_no_window_command, configure_no_window_tokio_command,
+    isolate_python_tokio_command, run_python_capture_stdout,
 };
 pub(crate) use python_env::{dedupe_dist_info, detect_device_builder_version, DistInfoDedupeScope};
 pub use python_env::{ensure_user_python, interpreter_is_usable, RefreshReason};
+pub(crate) use random::random_bytes;
+#[cfg(target_os = "windows")]
+pub use sandbox::restrict_backend_job_ui;
+pub use sandbox::scrub_env_tokio_command;
+#[cfg(target_os = "linux")]
+pub use sandbox::{confine_writes_tokio_command, home_write_dirs};
+pub use serial::usb_serial_ports;
+pub use wifi::visible_ssids;
 
 /// Application bundle identifier. Must match the `identifier` field in
 /// `tauri.conf.json`; Tauri derives `app_data_dir()` from it, and code that
@@ -42,14 +79,14 @@ pub const BUNDLE_IDENTIFIER: &str = "io.esphome.builder";
 /// - macOS: `~/Library/Application Support/io.esphome.builder/`
 /// - Windows: `%APPDATA%\io.esphome.builder\`
 /// - Linux: `~/.local/share/io.esphome.builder/`
-pub fn get_data_dir(app_handle: &AppHandle) -> Result<PathBuf> {
+pub fn get_data_dir(app_handle: &AppHandle) -> Result<PathBuf, PlatformError> {
     let path = app_handle
         .path()
         .app_data_dir()
-        .context("Failed to get app data directory")?;
+        .map_err(|error| PlatformError::Locate { dir: "data", error })?;
 
     // Ensure directory exists
-    std::fs::create_dir_all(&path).context("Failed to create data directory")?;
+    create_dir("data", &path)?;
 
     debug!("Data directory: {:?}", path);
     Ok(path)
@@ -60,12 +97,18 @@ pub fn get_data_dir(app_handle: &AppHandle) -> Result<PathBuf> {
 /// The CLI client mode (`esphome-desktop <subcommand>`) runs without a Tauri
 /// app, so it cannot use `app_data_dir()`. Joining the bundle identifier onto
 /// the OS data dir is the same derivation Tauri uses, and the same one
-/// `app_log_appender` in `lib.rs` already relies on. Does not create the
+/// `app_log_appender` in `logging.rs` already relies on. Does not create the
 /// directory.
 pub fn data_dir_no_handle() -> Option<PathBuf> {
     dirs::data_dir().map(|d| d.join(BUNDLE_IDENTIFIER))
 }
 
+/// [`get_python_parent_dir`] without an `AppHandle`, derived the same way as
+/// [`data_dir_no_handle`]. Does not create the directory.
+pub fn local_data_dir_no_handle() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|d| d.join(BUNDLE_IDENTIFIER))
+}
+
 /// Parent directory of the managed Python tree (`<dir>/python`) and its repair
 /// counter.
 ///
@@ -74,19 +117,31 @@ pub fn data_dir_no_handle() -> Option<PathBuf> {
 /// tree is hundreds of MB of interpreter and packages, all reproducible from
 /// the bundle, so it belongs in machine-local data where a roaming profile
 /// never syncs it. Settings and logs stay under [`get_data_dir`].
-pub fn get_python_parent_dir(app_handle: &AppHandle) -> Result<PathBuf> {
+pub fn get_python_parent_dir(app_handle: &AppHandle) -> Result<PathBuf, PlatformError> {
     let path = app_handle
         .path()
         .app_local_data_dir()
-        .context("Failed to get app local data directory")?;
+        .map_err(|error| PlatformError::Locate {
+            dir: "local data",
+            error,
+        })?;
 
     // Ensure directory exists
-    std::fs::create_dir_all(&path).context("Failed to create local data directory")?;
+    create_dir("local data", &path)?;
 
     debug!("Python parent directory: {:?}", path);
     Ok(path)
 }
 
+/// `create_dir_all` for the app directory `dir` names.
+fn create_dir(dir: &'static str, path: &Path) -> Result<(), PlatformError> {
+    std::fs::create_dir_all(path).map_err(|error| PlatformError::Create {
+        dir,
+        path: path.to_path_buf(),
+        error,
+    })
+}
+
 /// Name of the managed Python tree's directory under
 /// [`get_python_parent_dir`]. One spelling, because it also appears in the
 /// user-facing "delete this folder" repair hint (`update::repair_hint`), where
@@ -142,57 +197,90 @@ fn interpreter_in_tree(root: &Path) -> PathBuf {
 /// On macOS and Windows, Tauri's `resource_dir()` works correctly.
 fn get_bundled_resource_dir(app_handle: &AppHandle) -> Result<PathBuf> {
     #[cfg(target_os = "linux")]
-    {
-        // 1. Prefer APPDIR (set by sharun-based AppImage at runtime)
-        if let Ok(appdir) = std::env::var("APPDIR") {
-            let resource_dir = PathBuf::from(&appdir).join("lib/esphome-desktop");
-            if resource_dir.is_dir() {
-                debug!("Bundled resource dir (APPDIR): {:?}", resource_dir);
-                return Ok(resource_dir);
-            }
-            debug!(
-                "APPDIR set to {:?} but {:?} does not exist",
-                appdir, resource_dir
-            );
-        }
+    if let Some(resource_dir) = linux_resource_dir() {
+        return Ok(resource_dir);
+    }
 
-        // 2. Resolve relative to the real executable (deb/AUR installs)
-        let exe = std::env::current_exe().context("Failed to get current executable path")?;
-        let exe_dir = exe.parent().context("Failed to get executable directory")?;
-        // bin/esphome-desktop -> ../lib/esphome-desktop/
-        let resource_dir = exe_dir.join("../lib/esphome-desktop");
-        if let Ok(resolved) = resource_dir.canonicalize() {
-            debug!("Bundled resource dir (resolved): {:?}", resolved);
-            return Ok(resolved);
+    // Tauri's resource_dir: the bundle's on macOS and Windows, and the
+    // fallback for development builds on Linux
+    let resource_dir = app_handle
+        .path()
+        .resource_dir()
+        .context("Failed to get resource directory")?;
+    debug!("Bundled resource dir: {:?}", resource_dir);
+    Ok(resource_dir)
+}
+
+/// The Linux resource dir from `APPDIR` or relative to the executable; `None`
+/// in a development build. See [`get_bundled_resource_dir`].
+#[cfg(target_os = "linux")]
+fn linux_resource_dir() -> Option<PathBuf> {
+    // 1. Prefer APPDIR (set by sharun-based AppImage at runtime)
+    if let Ok(appdir) = std::env::var("APPDIR") {
+        let resource_dir = PathBuf::from(&appdir).join("lib/esphome-desktop");
+        if resource_dir.is_dir() {
+            debug!("Bundled resource dir (APPDIR): {:?}", resource_dir);
+            return Some(resource_dir);
         }
+        debug!(
+            "APPDIR set to {:?} but {:?} does not exist",
+            appdir, resource_dir
+        );
+    }
 
-        // 3. Fallback to Tauri's resource_dir for development builds
-        let fallback = app_handle
-            .path()
-            .resource_dir()
-            .context("Failed to get resource directory")?;
-        debug!("Bundled resource dir (fallback): {:?}", fallback);
-        Ok(fallback)
+    // 2. Resolve relative to the real executable (deb/AUR installs)
+    let exe = std::env::current_exe().ok()?;
+    // bin/esphome-desktop -> ../lib/esphome-desktop/
+    let resolved = exe
+        .parent()?
+        .join("../lib/esphome-desktop")
+        .canonicalize()
+        .ok()?;
+    debug!("Bundled resource dir (resolved): {:?}", resolved);
+    Some(resolved)
+}
+
+/// [`get_bundled_resource_dir`] without an `AppHandle`, for `--self-test`,
+/// which runs without a Tauri app and so without a display. On macOS and
+/// Windows this is Tauri's own derivation: `Contents/Resources` beside
+/// `Contents/MacOS`, and the executable's directory.
+fn bundled_resource_dir_no_handle() -> Result<PathBuf> {
+    #[cfg(target_os = "linux")]
+    {
+        linux_resource_dir().context("No bundled resources beside this executable")
     }
 
     #[cfg(not(target_os = "linux"))]
     {
-        let resource_dir = app_handle
-            .path()
-            .resource_dir()
-            .context("Failed to get resource directory")?;
-        debug!("Bundled resource dir: {:?}", resource_dir);
-        Ok(resource_dir)
+        let exe = std::env::current_exe().context("Failed to get current executable path")?;
+        let exe_dir = exe.parent().context("Failed to get executable directory")?;
+        if cfg!(target_os = "macos") {
+            Ok(exe_dir.join("../Resources"))
+        } else {
+            Ok(exe_dir.to_path_buf())
+        }
     }
 }
 
+/// Copy the bundled Python into `<parent>/python` the way the first-run copy
+/// does, without an `AppHandle`, and return its interpreter and bin dir.
+pub fn provision_python_no_handle(parent: &Path) -> Result<(PathBuf, PathBuf)> {
+    let root = parent.join(PYTHON_TREE_DIRNAME);
+    python_env::refresh_python_tree(
+        &root,
+        || Ok(bundled_resource_dir_no_handle()?.join("python")),
+        RefreshReason::Startup,
+    )?;
+    Ok((interpreter_in_tree(&root), bin_dir_in_tree(&root)))
+}
+
 /// Root of the pristine Python tree inside the bundled resources.
 ///
 /// The `"python"` here is the resource name listed in `tauri.conf.json`'s
 /// `bundle.resources`, deliberately NOT [`PYTHON_TREE_DIRNAME`]: renaming the
 /// managed tree in app data must not change where the shipped bundle is
 /// looked up, and vice versa.
-fn get_bundled_python_root(app_handle: &AppHandle) -> Result<PathBuf> {
+pub(crate) fn get_bundled_python_root(app_handle: &AppHandle) -> Result<PathBuf> {
     Ok(get_bundled_resource_dir(app_handle)?.join("python"))
 }
 
@@ -203,7 +291,7 @@ fn get_bundled_python_root(app_handle: &AppHandle) -> Result<PathBuf> {
 /// the Windows firewall rule, which must scope its program filter to the
 /// exact path the daemon executes; a second copy is how the two would drift
 /// apart.
-fn managed_interpreter_path(app_handle: &AppHandle) -> Result<PathBuf> {
+fn managed_interpreter_path(app_handle: &AppHandle) -> Result<PathBuf, PlatformError> {
     Ok(interpreter_in_tree(
         &get_python_parent_dir(app_handle)?.join(PYTHON_TREE_DIRNAME),
     ))
@@ -213,7 +301,7 @@ fn managed_interpreter_path(app_handle: &AppHandle) -> Result<PathBuf> {
 /// keeps under [`get_python_parent_dir`], falling back to the bundled tree
 /// before the first-run copy exists and to a bare system Python in development
 /// builds with no bundle.
-pub fn get_python_path(app_handle: &AppHandle) -> Result<PathBuf> {
+pub fn get_python_path(app_handle: &AppHandle) -> Result<PathBuf, PlatformError> {
     let python_path = managed_interpreter_path(app_handle)?;
 
     if python_path.exists() {
@@ -647,7 +735,7 @@ pub fn init(app_handle: &AppHandle) {
     linux::init();
 }
 
-/// Relaunch the app after a desktop update.
+/// Relaunch the app after a desktop update (or a reset, see `tray::cleanup`).
 ///
 /// On macOS this goes through LaunchServices (`open`) instead of Tauri's
 /// [`tauri::AppHandle::restart`], which respawns the inner Mach-O directly. A
@@ -670,93 +758,6 @@ pub fn relaunch_for_update(app_handle: &AppHandle) {
     app_handle.restart();
 }
 
-/// One-shot cleanup of the legacy `/Applications/ESPHome Builder.app` bundle
-/// left behind when the desktop app was renamed to "ESPHome Device Builder".
-///
-/// On the first launch after the rename the user is prompted (via a native
-/// dialog) to move the old bundle to the Trash. The decision is recorded as
-/// a marker file in the app data directory so the prompt is not repeated.
-///
-/// User settings and the bundled Python tree live under the bundle
-/// identifier (`io.esphome.builder/`), which did not change with the rename,
-/// so no data migration is needed.
-pub fn cleanup_legacy_macos_app(app_handle: &AppHandle) {
-    #[cfg(target_os = "macos")]
-    {
-        use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
-        use tracing::{info, warn};
-
-        const OLD_APP: &str = "/Applications/ESPHome Builder.app";
-        const MARKER_NAME: &str = ".legacy_macos_app_cleanup";
-
-        if !PathBuf::from(OLD_APP).exists() {
-            return;
-        }
-
-        let data_dir = match get_data_dir(app_handle) {
-            Ok(d) => d,
-            Err(e) => {
-                debug!("Skipping legacy app cleanup; data dir unavailable: {}", e);
-                return;
-            }
-        };
-
-        let marker = data_dir.join(MARKER_NAME);
-        if marker.exists() {
-            return;
-        }
-
-        info!("Legacy {} detected; prompting user to remove it", OLD_APP);
-
-        let dialog_app = app_handle.clone();
-        std::thread::spawn(move || {
-            let confirmed = dialog_app
-                .dialog()
-                .message(crate::i18n::t("platform.remove_legacy_prompt"))
-                .title(crate::i18n::t("platform.remove_legacy_title"))
-                .kind(MessageDialogKind::Info)
-                .buttons(MessageDialogButtons::OkCancelCustom(
-                    crate::i18n::t("platform.move_to_trash"),
-                    crate::i18n::t("platform.keep"),
-                ))
-                .blocking_show();
-
-            if confirmed {
-                let script = format!(
-                    "tell application \"Finder\" to delete POSIX file \"{}\"",
-                    OLD_APP
-                );
-                match std::process::Command::new("osascript")
-                    .args(["-e", &script])
-                    .output()
-                {
-                    Ok(out) if out.status.success() => {
-                        info!("Moved {} to Trash", OLD_APP);
-                    }
-                    Ok(out) => {
-                        warn!(
-                            "Failed to move {} to Trash: {}",
-                            OLD_APP,
-                            String::from_utf8_lossy(&out.stderr).trim()
-                        );
-                    }
-                    Err(e) => warn!("Failed to spawn osascript: {}", e),
-                }
-            }
-
-            // Marker is written regardless so the user is not nagged.
-            if let Err(e) = std::fs::write(&marker, "") {
-                warn!("Failed to write legacy-cleanup marker: {}", e);
-            }
-        });
-    }
-
-    #[cfg(not(target_os = "macos"))]
-    {
-        let _ = app_handle;
-    }
-}
-
 /// Returns `true` on Linux when the appindicator library required for system
 /// tray support is available, and always `true` on non-Linux platforms (which
 /// use native APIs that don't require a separate shared library).
@@ -27,6 +27,8 @@ const PIP_INSTALL_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(
 pub(super) fn pip_install_blocking(python_bin: &Path, package: &str, version: &str) -> Result<()> {
     let spec = format!("{}=={}", package, version);
     let mut cmd = python_command(python_bin, ["-m", "pip", "install", &spec]);
+    cmd.args(index_url_args());
+    crate::network::apply_std_env(&mut cmd);
     // The builder isolates the interpreter; pip needs its own env off too, and
     // every edit is an idempotent `env`/`env_remove`, so layering is a no-op.
     isolate_pip_command(&mut cmd);
@@ -156,14 +158,23 @@ pub fn isolate_pip_tokio_command(cmd: &mut tokio::process::Command) {
     isolate_pip_command(cmd.as_std_mut());
 }
 
+/// `--index-url` for the PyPI mirror in the settings' `index_url`, if any.
+fn index_url_args() -> Vec<&'static str> {
+    crate::network::index_url()
+        .map(|url| vec!["--index-url", url])
+        .unwrap_or_default()
+}
+
 /// Build a tokio `pip install` command for the given Python interpreter,
-/// prefilled with `-m pip install` and the Windows no-window flag, and
+/// prefilled with `-m pip install` (and the mirror's `--index-url`) and the
+/// Windows no-window flag, and
 /// isolated from the ambient Python/pip environment so the install lands in
 /// the managed tree (see [`isolate_pip_command`]). Callers append their own
 /// package specs and flags before running it.
 pub fn pip_command(python: &Path) -> tokio::process::Command {
     let mut cmd = tokio::process::Command::new(python);
     cmd.args(["-m", "pip", "install"]);
+    cmd.args(index_url_args());
     isolate_pip_tokio_command(&mut cmd);
     configure_no_window_tokio_command(&mut cmd);
     cmd
@@ -187,7 +198,69 @@ pub fn pip_command(python: &Path) -> tokio::process::Command {
 /// apply, so a failed assignment warns and carries on rather than failing the
 /// install. Job membership is a per-child policy, which is why this is a named
 /// seam the pip sites opt into rather than something every spawn inherits.
-pub async fn run_pip(mut cmd: tokio::process::Command) -> std::io::Result<std::process::Output> {
+pub async fn run_pip(cmd: tokio::process::Command) -> std::io::Result<std::process::Output> {
+    spawn_pip(cmd)?.wait_with_output().await
+}
+
+/// [`run_pip`], handing each line pip prints, on either stream, to `on_line`
+/// as it comes, for an install the user watches. Killed when `cancel`
+/// resolves first, which fails the run with
+/// [`std::io::ErrorKind::Interrupted`].
+///
+/// The output is still returned whole, so callers parse it exactly as
+/// [`run_pip`]'s. Lines are split on `\n` and decoded lossily: pip on Windows
+/// prints in the console's code page, and a stray byte must not end the run.
+pub async fn run_pip_watched(
+    cmd: tokio::process::Command,
+    mut on_line: impl FnMut(&str),
+    cancel: impl std::future::Future<Output = ()>,
+) -> std::io::Result<std::process::Output> {
+    use tokio::io::AsyncBufReadExt;
+
+    let mut child = spawn_pip(cmd)?;
+    let mut stdout =
+        tokio::io::BufReader::new(child.stdout.take().expect("stdout is piped")).split(b'\n');
+    let mut stderr =
+        tokio::io::BufReader::new(child.stderr.take().expect("stderr is piped")).split(b'\n');
+    let (mut out, mut err) = (Vec::new(), Vec::new());
+    let (mut out_open, mut err_open) = (true, true);
+    tokio::pin!(cancel);
+    while out_open || err_open {
+        tokio::select! {
+            line = stdout.next_segment(), if out_open => match line? {
+                Some(line) => keep_line(&mut out, line, &mut on_line),
+                None => out_open = false,
+            },
+            line = stderr.next_segment(), if err_open => match line? {
+                Some(line) => keep_line(&mut err, line, &mut on_line),
+                None => err_open = false,
+            },
+            () = &mut cancel => {
+                child.kill().await?;
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Interrupted,
+                    "pip was cancelled",
+                ));
+            }
+        }
+    }
+    Ok(std::process::Output {
+        status: child.wait().await?,
+        stdout: out,
+        stderr: err,
+    })
+}
+
+/// Append one line `split` took its `\n` from back onto `buffer`, and report
+/// it.
+fn keep_line(buffer: &mut Vec<u8>, line: Vec<u8>, on_line: &mut impl FnMut(&str)) {
+    on_line(String::from_utf8_lossy(&line).trim_end_matches('\r'));
+    buffer.extend_from_slice(&line);
+    buffer.push(b'\n');
+}
+
+/// Spawn `cmd` with [`run_pip`]'s capture, in the kill-on-close job.
+fn spawn_pip(mut cmd: tokio::process::Command) -> std::io::Result<tokio::process::Child> {
     cmd.stdin(std::process::Stdio::null());
     cmd.stdout(std::process::Stdio::piped());
     cmd.stderr(std::process::Stdio::piped());
@@ -204,7 +277,7 @@ pub async fn run_pip(mut cmd: tokio::process::Command) -> std::io::Result<std::p
         );
     }
 
-    child.wait_with_output().await
+    Ok(child)
 }
 
 #[cfg(test)]
@@ -256,6 +329,49 @@ mod tests {
         );
     }
 
+    /// The watched run sees every line of both streams as it comes and still
+    /// returns them whole, like [`run_pip`].
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn run_pip_watched_reports_lines_and_captures_output() {
+        let mut cmd = tokio::process::Command::new("sh");
+        cmd.args([
+            "-c",
+            "echo Collecting esphome; echo oops 1>&2; echo Installing",
+        ]);
+        let mut lines = Vec::new();
+        let output = run_pip_watched(
+            cmd,
+            |line| lines.push(line.to_string()),
+            std::future::pending(),
+        )
+        .await
+        .expect("run_pip_watched should spawn and wait");
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"Collecting esphome\nInstalling\n");
+        assert_eq!(output.stderr, b"oops\n");
+        lines.sort();
+        assert_eq!(lines, ["Collecting esphome", "Installing", "oops"]);
+    }
+
+    /// Cancelling kills pip rather than waiting it out.
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn run_pip_watched_kills_pip_when_cancelled() {
+        let mut cmd = tokio::process::Command::new("sh");
+        cmd.args(["-c", "echo Collecting esphome; sleep 30"]);
+        let started = std::time::Instant::now();
+        let err = run_pip_watched(
+            cmd,
+            |_| {},
+            tokio::time::sleep(std::time::Duration::from_millis(200)),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::Interrupted);
+        assert!(started.elapsed() < std::time::Duration::from_secs(10));
+    }
+
     /// pip isolation is a superset of Python isolation: a pip install that
     /// lands outside the managed tree is as broken as an import that resolves
     /// outside it.
@@ -0,0 +1,107 @@
+//! Background scheduling priority for the backend (`low_priority_builds`).
+//!
+//! Applied to the freshly spawned child rather than through the command, so
+//! it composes with the creation flags and process group the spawn already
+//! sets. The backend spends its first second importing Python before it can
+//! spawn anything, and every compile it starts later inherits the lowered
+//! priority.
+
+use tracing::warn;
+
+/// `nice` value for the backend's process group: well below normal without
+/// going all the way to 19, so a build on an otherwise idle machine still
+/// gets the whole CPU.
+#[cfg(unix)]
+const NICE: i32 = 10;
+
+/// Lower the CPU (and where the OS allows it, I/O) priority of a just-spawned
+/// backend. Best-effort: a failure is logged and the backend runs at normal
+/// priority.
+pub fn lower_priority(child: &tokio::process::Child) {
+    #[cfg(unix)]
+    if let Some(pid) = child.id() {
+        // The backend leads its own process group (`process_group(0)`).
+        lower_group_priority(pid);
+    }
+    #[cfg(windows)]
+    if let Some(handle) = child.raw_handle() {
+        lower_process_priority(handle);
+    }
+}
+
+#[cfg(unix)]
+fn lower_group_priority(pgid: u32) {
+    use nix::libc;
+
+    // SAFETY: plain syscalls on a process group we own; no memory is passed.
+    let rc = unsafe { libc::setpriority(libc::PRIO_PGRP, pgid as libc::id_t, NICE) };
+    if rc != 0 {
+        warn!(
+            "Failed to lower the backend's CPU priority: {}",
+            std::io::Error::last_os_error()
+        );
+    }
+
+    // I/O priority is Linux-only; libc has no wrapper for ioprio_set.
+    #[cfg(target_os = "linux")]
+    {
+        const IOPRIO_WHO_PGRP: libc::c_int = 2;
+        const IOPRIO_CLASS_BE: libc::c_int = 2;
+        const IOPRIO_CLASS_SHIFT: libc::c_int = 13;
+        // Lowest level of the best-effort class. Not the idle class: that
+        // starves a build outright while anything else touches the disk.
+        const IOPRIO_BE_LOWEST: libc::c_int = (IOPRIO_CLASS_BE << IOPRIO_CLASS_SHIFT) | 7;
+        // SAFETY: as above.
+        let rc = unsafe {
+            libc::syscall(
+                libc::SYS_ioprio_set,
+                IOPRIO_WHO_PGRP,
+                pgid as libc::c_int,
+                IOPRIO_BE_LOWEST,
+            )
+        };
+        if rc != 0 {
+            warn!(
+                "Failed to lower the backend's I/O priority: {}",
+                std::io::Error::last_os_error()
+            );
+        }
+    }
+}
+
+/// Below-normal priority class, which children inherit, plus EcoQoS
+/// (execution-speed throttling), which schedules the backend onto efficiency
+/// cores where the CPU has them. Windows has no I/O priority knob for another
+/// process.
+#[cfg(windows)]
+fn lower_process_priority(process: std::os::windows::io::RawHandle) {
+    use ::windows::Win32::Foundation::HANDLE;
+    use ::windows::Win32::System::Threading::{
+        ProcessPowerThrottling, SetPriorityClass, SetProcessInformation,
+        BELOW_NORMAL_PRIORITY_CLASS, PROCESS_POWER_THROTTLING_CURRENT_VERSION,
+        PROCESS_POWER_THROTTLING_EXECUTION_SPEED, PROCESS_POWER_THROTTLING_STATE,
+    };
+
+    let handle = HANDLE(process);
+    // SAFETY: `process` is a live child handle the caller keeps open; both
+    // calls borrow it, and `state` outlives the call that reads it.
+    unsafe {
+        if let Err(e) = SetPriorityClass(handle, BELOW_NORMAL_PRIORITY_CLASS) {
+            warn!("Failed to lower the backend's priority class: {e}");
+        }
+        let state = PROCESS_POWER_THROTTLING_STATE {
+            Version: PROCESS_POWER_THROTTLING_CURRENT_VERSION,
+            ControlMask: PROCESS_POWER_THROTTLING_EXECUTION_SPEED,
+            StateMask: PROCESS_POWER_THROTTLING_EXECUTION_SPEED,
+        };
+        if let Err(e) = SetProcessInformation(
+            handle,
+            ProcessPowerThrottling,
+            std::ptr::from_ref(&state).cast(),
+            std::mem::size_of::<PROCESS_POWER_THROTTLING_STATE>() as u32,
+        ) {
+            // Older than Windows 11 / Server 2022: no EcoQoS, which is fine.
+            tracing::debug!("EcoQoS not applied to the backend: {e}");
+        }
+    }
+}
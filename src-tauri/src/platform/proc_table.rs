@@ -0,0 +1,261 @@
+//! A snapshot of the OS process table: just enough (parent links and
+//! cumulative CPU time) to find the backend's descendants and spot one that
+//! is spinning.
+//!
+//! Unix reads `ps`, which has the same columns on Linux and macOS and saves a
+//! per-platform `/proc` / `libproc` walk. Windows walks a ToolHelp snapshot and
+//! asks each process for its times.
+
+use anyhow::Result;
+use std::time::Duration;
+
+/// One row of the process table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcInfo {
+    pub pid: u32,
+    pub ppid: u32,
+    /// User plus kernel CPU time used so far.
+    pub cpu_time: Duration,
+    /// Executable name, for messages.
+    pub name: String,
+}
+
+/// Every process `root` (transitively) spawned, not including `root` itself.
+/// Parents come before their children.
+pub fn descendants(table: &[ProcInfo], root: u32) -> Vec<&ProcInfo> {
+    let mut out: Vec<&ProcInfo> = Vec::new();
+    let mut frontier = vec![root];
+    while let Some(parent) = frontier.pop() {
+        for proc in table.iter().filter(|p| p.ppid == parent && p.pid != parent) {
+            // A PID recycled into a cycle would otherwise loop forever.
+            if proc.pid != root && !out.iter().any(|seen| seen.pid == proc.pid) {
+                out.push(proc);
+                frontier.push(proc.pid);
+            }
+        }
+    }
+    out
+}
+
+/// Terminate `pids`, last first, so children go before the parents that
+/// would otherwise respawn or wait on them. SIGTERM on Unix (like the backend
+/// itself, never SIGKILL); `TerminateProcess` on Windows, which has no
+/// graceful equivalent for an arbitrary process.
+pub fn terminate_pids(pids: &[u32]) {
+    for &pid in pids.iter().rev() {
+        terminate_pid(pid);
+    }
+}
+
+#[cfg(unix)]
+fn terminate_pid(pid: u32) {
+    use nix::sys::signal::{kill, Signal};
+    use nix::unistd::Pid;
+    if let Err(e) = kill(Pid::from_raw(pid as i32), Signal::SIGTERM) {
+        tracing::warn!("SIGTERM to pid {} failed: {}", pid, e);
+    }
+    // A process the thermal monitor paused holds the SIGTERM until continued.
+    let _ = kill(Pid::from_raw(pid as i32), Signal::SIGCONT);
+}
+
+/// Pause `pids` with SIGSTOP, parents first so none spawns a child that
+/// escapes the snapshot.
+#[cfg(target_os = "linux")]
+pub fn suspend_pids(pids: &[u32]) {
+    signal_pids(pids.iter(), nix::sys::signal::Signal::SIGSTOP);
+}
+
+/// Continue processes paused by [`suspend_pids`], children first so a parent
+/// never wakes to find them still stopped. Ones that exited meanwhile are
+/// skipped.
+#[cfg(target_os = "linux")]
+pub fn resume_pids(pids: &[u32]) {
+    signal_pids(pids.iter().rev(), nix::sys::signal::Signal::SIGCONT);
+}
+
+#[cfg(target_os = "linux")]
+fn signal_pids<'a>(pids: impl Iterator<Item = &'a u32>, signal: nix::sys::signal::Signal) {
+    use nix::unistd::Pid;
+    for &pid in pids {
+        if let Err(e) = nix::sys::signal::kill(Pid::from_raw(pid as i32), signal) {
+            tracing::debug!("{} to pid {} failed: {}", signal, pid, e);
+        }
+    }
+}
+
+#[cfg(windows)]
+fn terminate_pid(pid: u32) {
+    use ::windows::Win32::Foundation::CloseHandle;
+    use ::windows::Win32::System::Threading::{OpenProcess, TerminateProcess, PROCESS_TERMINATE};
+    // SAFETY: FFI into Win32; the handle is closed before leaving the block.
+    unsafe {
+        match OpenProcess(PROCESS_TERMINATE, false, pid) {
+            Ok(handle) => {
+                if let Err(e) = TerminateProcess(handle, 1) {
+                    tracing::warn!("TerminateProcess on pid {} failed: {}", pid, e);
+                }
+                let _ = CloseHandle(handle);
+            }
+            Err(e) => tracing::warn!("OpenProcess on pid {} failed: {}", pid, e),
+        }
+    }
+}
+
+/// Snapshot the process table.
+#[cfg(unix)]
+pub fn process_table() -> Result<Vec<ProcInfo>> {
+    use anyhow::Context;
+    let output = std::process::Command::new("ps")
+        .args([
+            "-A", "-o", "pid=", "-o", "ppid=", "-o", "time=", "-o", "comm=",
+        ])
+        .output()
+        .context("Failed to run ps")?;
+    if !output.status.success() {
+        anyhow::bail!("ps exited with {}", output.status);
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(parse_ps_line)
+        .collect())
+}
+
+/// Parse one `pid ppid time comm` row; `comm` may contain spaces (macOS
+/// prints the full path).
+#[cfg(any(unix, test))]
+fn parse_ps_line(line: &str) -> Option<ProcInfo> {
+    let mut fields = line.split_whitespace();
+    let pid = fields.next()?.parse().ok()?;
+    let ppid = fields.next()?.parse().ok()?;
+    let cpu_time = parse_ps_time(fields.next()?)?;
+    let comm = fields.collect::<Vec<_>>().join(" ");
+    let name = comm.rsplit('/').next().unwrap_or(&comm).to_string();
+    Some(ProcInfo {
+        pid,
+        ppid,
+        cpu_time,
+        name,
+    })
+}
+
+/// Parse `ps`'s `time` column: `[DD-][HH:]MM:SS[.ss]`. Linux prints
+/// `00:01:02` or `1-02:03:04`, macOS `1:02.34`.
+#[cfg(any(unix, test))]
+fn parse_ps_time(raw: &str) -> Option<Duration> {
+    let (days, clock) = match raw.split_once('-') {
+        Some((d, rest)) => (d.parse::<u64>().ok()?, rest),
+        None => (0, raw),
+    };
+    let mut secs = 0.0_f64;
+    for part in clock.split(':') {
+        secs = secs * 60.0 + part.parse::<f64>().ok()?;
+    }
+    Some(Duration::from_secs(days * 86_400) + Duration::from_secs_f64(secs))
+}
+
+/// Snapshot the process table.
+#[cfg(windows)]
+pub fn process_table() -> Result<Vec<ProcInfo>> {
+    use ::windows::Win32::Foundation::{CloseHandle, FILETIME};
+    use ::windows::Win32::System::Diagnostics::ToolHelp::{
+        CreateToolhelp32Snapshot, Process32FirstW, Process32NextW, PROCESSENTRY32W,
+        TH32CS_SNAPPROCESS,
+    };
+    use ::windows::Win32::System::Threading::{
+        GetProcessTimes, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+
+    fn filetime(ft: FILETIME) -> Duration {
+        // 100 ns ticks.
+        let ticks = (u64::from(ft.dwHighDateTime) << 32) | u64::from(ft.dwLowDateTime);
+        Duration::from_nanos(ticks.saturating_mul(100))
+    }
+
+    let mut table = Vec::new();
+    // SAFETY: FFI into Win32. The snapshot and every process handle opened
+    // here are closed before returning; nothing escapes.
+    unsafe {
+        let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0)?;
+        let mut entry = PROCESSENTRY32W {
+            dwSize: std::mem::size_of::<PROCESSENTRY32W>() as u32,
+            ..Default::default()
+        };
+        let mut more = Process32FirstW(snapshot, &mut entry).is_ok();
+        while more {
+            let pid = entry.th32ProcessID;
+            let name_len = entry
+                .szExeFile
+                .iter()
+                .position(|&c| c == 0)
+                .unwrap_or(entry.szExeFile.len());
+            let name = String::from_utf16_lossy(&entry.szExeFile[..name_len]);
+            let mut cpu_time = Duration::ZERO;
+            if let Ok(handle) = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) {
+                let mut created = FILETIME::default();
+                let mut exited = FILETIME::default();
+                let mut kernel = FILETIME::default();
+                let mut user = FILETIME::default();
+                if GetProcessTimes(handle, &mut created, &mut exited, &mut kernel, &mut user)
+                    .is_ok()
+                {
+                    cpu_time = filetime(kernel) + filetime(user);
+                }
+                let _ = CloseHandle(handle);
+            }
+            table.push(ProcInfo {
+                pid,
+                ppid: entry.th32ParentProcessID,
+                cpu_time,
+                name,
+            });
+            more = Process32NextW(snapshot, &mut entry).is_ok();
+        }
+        let _ = CloseHandle(snapshot);
+    }
+    Ok(table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proc(pid: u32, ppid: u32) -> ProcInfo {
+        ProcInfo {
+            pid,
+            ppid,
+            cpu_time: Duration::ZERO,
+            name: format!("p{pid}"),
+        }
+    }
+
+    #[test]
+    fn descendants_walks_the_whole_subtree() {
+        let table = vec![
+            proc(1, 0),
+            proc(10, 1),
+            proc(11, 10),
+            proc(12, 11),
+            proc(20, 1),
+        ];
+        let pids: Vec<u32> = descendants(&table, 10).iter().map(|p| p.pid).collect();
+        assert_eq!(pids, vec![11, 12]);
+        assert!(descendants(&table, 12).is_empty());
+    }
+
+    #[test]
+    fn ps_rows_parse_on_linux_and_macos() {
+        assert_eq!(
+            parse_ps_line("  4242     1 1-02:03:04 ninja"),
+            Some(ProcInfo {
+                pid: 4242,
+                ppid: 1,
+                cpu_time: Duration::from_secs(86_400 + 2 * 3600 + 3 * 60 + 4),
+                name: "ninja".into(),
+            })
+        );
+        let mac = parse_ps_line("501 1 1:02.50 /Applications/Some App.app/cmake").unwrap();
+        assert_eq!(mac.cpu_time, Duration::from_millis(62_500));
+        assert_eq!(mac.name, "cmake");
+        assert_eq!(parse_ps_line("PID PPID TIME COMMAND"), None);
+    }
+}
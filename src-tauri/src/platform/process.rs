@@ -597,7 +597,7 @@ unsafe impl Sync for JobHandle {}
 /// `None` if the job could not be set up; the caller then just loses the
 /// backstop and keeps the graceful path.
 #[cfg(target_os = "windows")]
-fn kill_on_close_job() -> Option<::windows::Win32::Foundation::HANDLE> {
+pub(super) fn kill_on_close_job() -> Option<::windows::Win32::Foundation::HANDLE> {
     static JOB: std::sync::OnceLock<Option<JobHandle>> = std::sync::OnceLock::new();
 
     // The singleton's handle is intentionally never closed (see the doc above);
@@ -0,0 +1,20 @@
+//! Secure random bytes from the OS, for keys written into device configs.
+
+use anyhow::Result;
+
+/// Fill `buf` from the OS's secure random source.
+pub(crate) fn random_bytes(buf: &mut [u8]) -> Result<()> {
+    #[cfg(target_os = "windows")]
+    {
+        super::windows::random_bytes(buf)
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        use anyhow::Context;
+        use std::io::Read;
+
+        std::fs::File::open("/dev/urandom")
+            .and_then(|mut random| random.read_exact(buf))
+            .context("Failed to read /dev/urandom")
+    }
+}
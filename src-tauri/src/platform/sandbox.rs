@@ -0,0 +1,343 @@
+//! Reduced privileges for the native backend (`sandbox_backend`).
+//!
+//! A YAML config can pull in external components, and those run arbitrary
+//! Python at config time with whatever the backend is allowed to do. This
+//! narrows that without getting in the way of a compile:
+//!
+//! - everywhere, the backend inherits only an allowlist of environment
+//!   variables, so tokens and credentials in the user's shell don't reach it;
+//! - on Linux, Landlock limits writes to the directories a build needs (the
+//!   config, our data, the toolchain caches, temp and `/dev`). Reads are left
+//!   alone: the toolchains live all over the filesystem;
+//! - on Windows, the backend's job object gets UI restrictions, so nothing in
+//!   it can read the clipboard, reach other processes' windows or log the
+//!   user off.
+//!
+//! All of it is best-effort. A kernel without Landlock or a job that won't
+//! take the limits is logged and the backend runs unconfined, the same as
+//! with the setting off.
+
+use std::path::PathBuf;
+use tokio::process::Command;
+
+/// Variables the backend keeps, compared case-insensitively (Windows names
+/// have no fixed case, and the proxy variables are used both ways).
+const KEEP: &[&str] = &[
+    // Basics
+    "PATH",
+    "HOME",
+    "USER",
+    "LOGNAME",
+    "SHELL",
+    "TERM",
+    "LANG",
+    "LANGUAGE",
+    "TZ",
+    "TMPDIR",
+    "TMP",
+    "TEMP",
+    // Networking: proxies and CA bundles for downloads
+    "HTTP_PROXY",
+    "HTTPS_PROXY",
+    "NO_PROXY",
+    "ALL_PROXY",
+    "SSL_CERT_FILE",
+    "SSL_CERT_DIR",
+    "REQUESTS_CA_BUNDLE",
+    "CURL_CA_BUNDLE",
+    "GIT_SSL_CAINFO",
+    // Git over SSH for private external components
+    "SSH_AUTH_SOCK",
+    // Windows essentials; without these Python and the toolchains misbehave
+    "SYSTEMROOT",
+    "WINDIR",
+    "SYSTEMDRIVE",
+    "COMSPEC",
+    "PATHEXT",
+    "OS",
+    "USERPROFILE",
+    "USERNAME",
+    "USERDOMAIN",
+    "COMPUTERNAME",
+    "HOMEDRIVE",
+    "HOMEPATH",
+    "APPDATA",
+    "LOCALAPPDATA",
+    "PROGRAMDATA",
+    "PROGRAMFILES",
+    "PROGRAMFILES(X86)",
+    "PROGRAMW6432",
+    "COMMONPROGRAMFILES",
+    "COMMONPROGRAMFILES(X86)",
+    "NUMBER_OF_PROCESSORS",
+    "PROCESSOR_ARCHITECTURE",
+    "PROCESSOR_IDENTIFIER",
+    // macOS text encoding default
+    "__CF_USER_TEXT_ENCODING",
+];
+
+/// Prefixes of variable families the backend keeps.
+const KEEP_PREFIXES: &[&str] = &["LC_", "XDG_", "ESPHOME_", "PLATFORMIO_", "IDF_"];
+
+/// Whether the backend inherits the variable `name`.
+fn keeps_var(name: &str) -> bool {
+    let name = name.to_ascii_uppercase();
+    KEEP.contains(&name.as_str()) || KEEP_PREFIXES.iter().any(|p| name.starts_with(p))
+}
+
+/// Drop every inherited variable not on the allowlist. Call before setting the
+/// backend's own variables; those are set afterwards and so survive.
+pub fn scrub_env_tokio_command(cmd: &mut Command) {
+    for (name, _) in std::env::vars_os() {
+        if !name.to_str().is_some_and(keeps_var) {
+            cmd.env_remove(&name);
+        }
+    }
+}
+
+/// Directories under the home directory the toolchains write to, created up
+/// front: a confined backend can't create them itself.
+#[cfg(target_os = "linux")]
+pub fn home_write_dirs() -> Vec<PathBuf> {
+    let Some(home) = dirs::home_dir() else {
+        return Vec::new();
+    };
+    let platformio = std::env::var_os("PLATFORMIO_CORE_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| home.join(".platformio"));
+    let dirs = vec![platformio, home.join(".espressif"), home.join(".cache")];
+    for dir in &dirs {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    dirs
+}
+
+/// Confine writes of the spawned command to `writable` (plus temp and `/dev`)
+/// with Landlock. Needs Landlock ABI 2 (Linux 5.19) so renames across
+/// directories can be handled; older kernels are logged and left unconfined.
+#[cfg(target_os = "linux")]
+pub fn confine_writes_tokio_command(cmd: &mut Command, writable: &[PathBuf]) {
+    let mut paths = writable.to_vec();
+    paths.extend(["/tmp", "/var/tmp", "/dev"].map(PathBuf::from));
+    paths.extend(std::env::var_os("TMPDIR").map(PathBuf::from));
+    let ruleset = match landlock::Ruleset::new(&paths) {
+        Ok(ruleset) => ruleset,
+        Err(e) => {
+            tracing::warn!("Not confining the backend's writes: {e:#}");
+            return;
+        }
+    };
+    // SAFETY: the closure runs between fork and exec and only makes two
+    // syscalls on an fd the closure owns; it allocates nothing.
+    unsafe {
+        cmd.pre_exec(move || ruleset.restrict_self());
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod landlock {
+    use anyhow::{bail, Context, Result};
+    use nix::libc;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::PathBuf;
+
+    // Landlock syscalls share their numbers across architectures.
+    const SYS_CREATE_RULESET: libc::c_long = 444;
+    const SYS_ADD_RULE: libc::c_long = 445;
+    const SYS_RESTRICT_SELF: libc::c_long = 446;
+    const CREATE_RULESET_VERSION: libc::c_uint = 1;
+    const RULE_PATH_BENEATH: libc::c_int = 1;
+
+    const WRITE_FILE: u64 = 1 << 1;
+    const REMOVE_DIR: u64 = 1 << 4;
+    const REMOVE_FILE: u64 = 1 << 5;
+    const MAKE_CHAR: u64 = 1 << 6;
+    const MAKE_DIR: u64 = 1 << 7;
+    const MAKE_REG: u64 = 1 << 8;
+    const MAKE_SOCK: u64 = 1 << 9;
+    const MAKE_FIFO: u64 = 1 << 10;
+    const MAKE_BLOCK: u64 = 1 << 11;
+    const MAKE_SYM: u64 = 1 << 12;
+    const REFER: u64 = 1 << 13;
+    const TRUNCATE: u64 = 1 << 14;
+
+    #[repr(C)]
+    struct RulesetAttr {
+        handled_access_fs: u64,
+    }
+
+    #[repr(C, packed)]
+    struct PathBeneathAttr {
+        allowed_access: u64,
+        parent_fd: i32,
+    }
+
+    /// A Landlock ruleset allowing writes beneath a set of directories.
+    pub(super) struct Ruleset(OwnedFd);
+
+    impl Ruleset {
+        pub(super) fn new(paths: &[PathBuf]) -> Result<Self> {
+            // SAFETY: the version query passes no memory.
+            let abi = unsafe {
+                libc::syscall(
+                    SYS_CREATE_RULESET,
+                    std::ptr::null::<RulesetAttr>(),
+                    0,
+                    CREATE_RULESET_VERSION,
+                )
+            };
+            if abi < 2 {
+                bail!("Landlock ABI 2 is not available (got {abi})");
+            }
+            let mut handled = WRITE_FILE
+                | REMOVE_DIR
+                | REMOVE_FILE
+                | MAKE_CHAR
+                | MAKE_DIR
+                | MAKE_REG
+                | MAKE_SOCK
+                | MAKE_FIFO
+                | MAKE_BLOCK
+                | MAKE_SYM
+                | REFER;
+            if abi >= 3 {
+                handled |= TRUNCATE;
+            }
+            let attr = RulesetAttr {
+                handled_access_fs: handled,
+            };
+            // SAFETY: `attr` outlives the call and its size is passed with it.
+            let fd = unsafe {
+                libc::syscall(
+                    SYS_CREATE_RULESET,
+                    &attr as *const RulesetAttr,
+                    std::mem::size_of::<RulesetAttr>(),
+                    0,
+                )
+            };
+            if fd < 0 {
+                return Err(std::io::Error::last_os_error())
+                    .context("Failed to create a Landlock ruleset");
+            }
+            // SAFETY: the syscall returned a fresh fd (close-on-exec) we now own.
+            let ruleset = Self(unsafe { OwnedFd::from_raw_fd(fd as i32) });
+            for path in paths.iter().filter(|p| p.is_dir()) {
+                ruleset.allow(path, handled)?;
+            }
+            Ok(ruleset)
+        }
+
+        fn allow(&self, path: &PathBuf, access: u64) -> Result<()> {
+            let c_path = std::ffi::CString::new(path.as_os_str().as_bytes())
+                .with_context(|| format!("Invalid path {path:?}"))?;
+            // SAFETY: `c_path` is a valid C string for the duration of the call.
+            let fd = unsafe { libc::open(c_path.as_ptr(), libc::O_PATH | libc::O_CLOEXEC) };
+            if fd < 0 {
+                return Err(std::io::Error::last_os_error())
+                    .with_context(|| format!("Failed to open {path:?}"));
+            }
+            // SAFETY: `open` returned a fresh fd we now own.
+            let dir = unsafe { OwnedFd::from_raw_fd(fd) };
+            let rule = PathBeneathAttr {
+                allowed_access: access,
+                parent_fd: dir.as_raw_fd(),
+            };
+            // SAFETY: `rule` and both fds outlive the call.
+            let rc = unsafe {
+                libc::syscall(
+                    SYS_ADD_RULE,
+                    self.0.as_raw_fd(),
+                    RULE_PATH_BENEATH,
+                    &rule as *const PathBeneathAttr,
+                    0,
+                )
+            };
+            if rc != 0 {
+                return Err(std::io::Error::last_os_error())
+                    .with_context(|| format!("Failed to allow writes beneath {path:?}"));
+            }
+            Ok(())
+        }
+
+        /// Apply the ruleset to the calling process. Async-signal-safe, for
+        /// `pre_exec`.
+        pub(super) fn restrict_self(&self) -> std::io::Result<()> {
+            // SAFETY: plain syscalls; no memory is passed.
+            unsafe {
+                if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0
+                    || libc::syscall(SYS_RESTRICT_SELF, self.0.as_raw_fd(), 0) != 0
+                {
+                    return Err(std::io::Error::last_os_error());
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Put UI restrictions on the backend's kill-on-close job: no clipboard, no
+/// handles to windows outside the job, no desktop switching, no logoff or
+/// shutdown and no changes to system settings. Best-effort.
+#[cfg(target_os = "windows")]
+pub fn restrict_backend_job_ui() {
+    use ::windows::Win32::System::JobObjects::{
+        JobObjectBasicUIRestrictions, SetInformationJobObject, JOBOBJECT_BASIC_UI_RESTRICTIONS,
+        JOB_OBJECT_UILIMIT_DESKTOP, JOB_OBJECT_UILIMIT_DISPLAYSETTINGS,
+        JOB_OBJECT_UILIMIT_EXITWINDOWS, JOB_OBJECT_UILIMIT_GLOBALATOMS, JOB_OBJECT_UILIMIT_HANDLES,
+        JOB_OBJECT_UILIMIT_READCLIPBOARD, JOB_OBJECT_UILIMIT_SYSTEMPARAMETERS,
+        JOB_OBJECT_UILIMIT_WRITECLIPBOARD,
+    };
+
+    let Some(job) = super::process::kill_on_close_job() else {
+        return;
+    };
+    let info = JOBOBJECT_BASIC_UI_RESTRICTIONS {
+        UIRestrictionsClass: JOB_OBJECT_UILIMIT_DESKTOP
+            | JOB_OBJECT_UILIMIT_DISPLAYSETTINGS
+            | JOB_OBJECT_UILIMIT_EXITWINDOWS
+            | JOB_OBJECT_UILIMIT_GLOBALATOMS
+            | JOB_OBJECT_UILIMIT_HANDLES
+            | JOB_OBJECT_UILIMIT_READCLIPBOARD
+            | JOB_OBJECT_UILIMIT_SYSTEMPARAMETERS
+            | JOB_OBJECT_UILIMIT_WRITECLIPBOARD,
+    };
+    // SAFETY: `job` is the live process-wide job handle; `info` outlives the
+    // call and its size is passed with it.
+    if let Err(e) = unsafe {
+        SetInformationJobObject(
+            job,
+            JobObjectBasicUIRestrictions,
+            &info as *const _ as *const std::ffi::c_void,
+            std::mem::size_of::<JOBOBJECT_BASIC_UI_RESTRICTIONS>() as u32,
+        )
+    } {
+        tracing::warn!("Failed to set UI restrictions on the backend's job object: {e}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_only_allowlisted_variables() {
+        for name in [
+            "PATH",
+            "Path",
+            "http_proxy",
+            "LC_ALL",
+            "PLATFORMIO_CORE_DIR",
+        ] {
+            assert!(keeps_var(name), "{name} should be kept");
+        }
+        for name in [
+            "GITHUB_TOKEN",
+            "AWS_SECRET_ACCESS_KEY",
+            "PYTHONPATH",
+            "LD_PRELOAD",
+        ] {
+            assert!(!keeps_var(name), "{name} should be dropped");
+        }
+    }
+}
@@ -0,0 +1,100 @@
+//! The USB serial ports connected to this machine, for flashing a board by
+//! cable.
+//!
+//! Only USB adapters count, not a PC's built-in COM1: on Linux the entries
+//! of `/dev/serial/by-id` (named after the adapter, and stable across
+//! replugging), else `/dev/ttyUSB*` and `/dev/ttyACM*`; on macOS the
+//! `/dev/cu.*` names USB adapters get; on Windows the COM ports of USB
+//! devices, from PowerShell.
+
+use anyhow::Result;
+
+/// The ports, sorted, as ESPHome's `--device` takes them.
+pub fn usb_serial_ports() -> Result<Vec<String>> {
+    let mut ports = imp::ports()?;
+    ports.sort();
+    ports.dedup();
+    Ok(ports)
+}
+
+/// Entries of `dir` whose names start with one of `prefixes`, as paths.
+#[cfg(not(target_os = "windows"))]
+fn entries(dir: &str, prefixes: &[&str]) -> Vec<String> {
+    std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter(|e| {
+                    let name = e.file_name();
+                    let name = name.to_string_lossy();
+                    prefixes.iter().any(|p| name.starts_with(p))
+                })
+                .map(|e| e.path().to_string_lossy().into_owned())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(target_os = "linux")]
+mod imp {
+    use anyhow::Result;
+
+    pub(super) fn ports() -> Result<Vec<String>> {
+        let by_id = super::entries("/dev/serial/by-id", &[""]);
+        if !by_id.is_empty() {
+            return Ok(by_id);
+        }
+        Ok(super::entries("/dev", &["ttyUSB", "ttyACM"]))
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod imp {
+    use anyhow::Result;
+
+    pub(super) fn ports() -> Result<Vec<String>> {
+        Ok(super::entries(
+            "/dev",
+            &[
+                "cu.usbserial",
+                "cu.usbmodem",
+                "cu.SLAB_USBtoUART",
+                "cu.wchusbserial",
+            ],
+        ))
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod imp {
+    use anyhow::{bail, Context, Result};
+
+    const SCRIPT: &str = "Get-CimInstance Win32_PnPEntity -Filter \"PNPClass='Ports'\" | \
+        Where-Object { $_.PNPDeviceID -like 'USB*' -or $_.PNPDeviceID -like 'FTDIBUS*' } | \
+        ForEach-Object { if ($_.Name -match '\\((COM\\d+)\\)') { $matches[1] } }";
+
+    pub(super) fn ports() -> Result<Vec<String>> {
+        let mut cmd = std::process::Command::new("powershell");
+        cmd.args(["-NoProfile", "-NonInteractive", "-Command", SCRIPT]);
+        crate::platform::configure_no_window_command(&mut cmd);
+        let output = cmd.output().context("Failed to run PowerShell")?;
+        if !output.status.success() {
+            bail!("listing serial ports failed: {}", output.status);
+        }
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect())
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+mod imp {
+    use anyhow::Result;
+
+    pub(super) fn ports() -> Result<Vec<String>> {
+        Ok(super::entries("/dev", &["cuaU"]))
+    }
+}
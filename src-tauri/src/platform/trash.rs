@@ -0,0 +1,108 @@
+//! Moving files to the OS trash, so what the app removes can be got back
+//! the way anything else the user deletes is: the Trash through Finder
+//! (over `osascript`) on macOS, the Recycle Bin through PowerShell on
+//! Windows, and the desktop's trash through `gio trash` on Linux.
+
+use anyhow::Result;
+use std::path::Path;
+
+/// Move the file or directory at `path` to the trash.
+pub(crate) fn move_to_trash(path: &Path) -> Result<()> {
+    if path.is_absolute() {
+        imp::move_to_trash(path)
+    } else {
+        imp::move_to_trash(&std::env::current_dir()?.join(path))
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod imp {
+    use anyhow::{bail, Context, Result};
+    use std::path::Path;
+    use std::process::Command;
+
+    /// The path goes in through the environment rather than the script, so
+    /// no quoting can break it.
+    const SCRIPT: &str = "Add-Type -AssemblyName Microsoft.VisualBasic; \
+        $p = $env:ESPHOME_TRASH_PATH; \
+        if (Test-Path -LiteralPath $p -PathType Container) { \
+        [Microsoft.VisualBasic.FileIO.FileSystem]::DeleteDirectory($p, 'OnlyErrorDialogs', 'SendToRecycleBin') \
+        } else { \
+        [Microsoft.VisualBasic.FileIO.FileSystem]::DeleteFile($p, 'OnlyErrorDialogs', 'SendToRecycleBin') }";
+
+    pub(super) fn move_to_trash(path: &Path) -> Result<()> {
+        let mut cmd = Command::new("powershell");
+        cmd.args(["-NoProfile", "-NonInteractive", "-Command", SCRIPT])
+            .env("ESPHOME_TRASH_PATH", path);
+        crate::platform::configure_no_window_command(&mut cmd);
+        let output = cmd.output().context("Failed to run PowerShell")?;
+        if !output.status.success() {
+            bail!(
+                "moving {path:?} to the Recycle Bin failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod imp {
+    use anyhow::{bail, Context, Result};
+    use std::path::Path;
+    use std::process::Command;
+
+    pub(super) fn move_to_trash(path: &Path) -> Result<()> {
+        let output = Command::new("osascript")
+            .args([
+                "-e",
+                "on run argv",
+                "-e",
+                "tell application \"Finder\" to delete (POSIX file (item 1 of argv) as alias)",
+                "-e",
+                "end run",
+            ])
+            .arg(path)
+            .output()
+            .context("Failed to run osascript")?;
+        if !output.status.success() {
+            bail!(
+                "moving {path:?} to the Trash failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod imp {
+    use anyhow::{bail, Result};
+    use std::io::ErrorKind;
+    use std::path::Path;
+    use std::process::Command;
+
+    pub(super) fn move_to_trash(path: &Path) -> Result<()> {
+        let output = match Command::new("gio")
+            .arg("trash")
+            .arg("--")
+            .arg(path)
+            .output()
+        {
+            Ok(output) => output,
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                bail!(
+                    "gio is not installed; install GLib's tools (libglib2.0-bin) to use the trash"
+                )
+            }
+            Err(e) => return Err(anyhow::Error::new(e).context("Failed to run gio")),
+        };
+        if !output.status.success() {
+            bail!(
+                "moving {path:?} to the trash failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,123 @@
+//! The Wi-Fi networks this machine can see, by SSID.
+//!
+//! Each platform's own tool, none needing privileges: NetworkManager's
+//! `nmcli` on Linux, `system_profiler` on macOS (the `airport` tool is gone
+//! since macOS 14.4) and `netsh wlan` on Windows. They report the last scan
+//! rather than starting one, so asking is cheap. A machine without Wi-Fi,
+//! or a Linux box without NetworkManager, gets an error.
+
+use anyhow::{bail, Context, Result};
+
+/// The SSIDs in range, deduplicated, hidden networks left out.
+pub fn visible_ssids() -> Result<Vec<String>> {
+    let (program, args, parse): (&str, &[&str], fn(&str) -> Vec<String>) =
+        if cfg!(target_os = "macos") {
+            (
+                "system_profiler",
+                &["SPAirPortDataType"],
+                parse_system_profiler,
+            )
+        } else if cfg!(target_os = "windows") {
+            ("netsh", &["wlan", "show", "networks"], parse_netsh)
+        } else {
+            (
+                "nmcli",
+                &["-t", "-f", "SSID", "dev", "wifi", "list"],
+                parse_nmcli,
+            )
+        };
+    let mut cmd = std::process::Command::new(program);
+    cmd.args(args);
+    super::configure_no_window_command(&mut cmd);
+    let output = cmd
+        .output()
+        .with_context(|| format!("Failed to run {program}"))?;
+    if !output.status.success() {
+        bail!("{program} exited with {}", output.status);
+    }
+    let mut ssids = parse(&String::from_utf8_lossy(&output.stdout));
+    ssids.retain(|s| !s.is_empty());
+    ssids.sort();
+    ssids.dedup();
+    Ok(ssids)
+}
+
+/// One SSID per line, with `:` and `\` escaped.
+fn parse_nmcli(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .map(|line| line.replace("\\:", ":").replace("\\\\", "\\"))
+        .collect()
+}
+
+/// `SSID 1 : Name` lines; the word stays `SSID` in every language.
+fn parse_netsh(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            let (label, ssid) = line.split_once(':')?;
+            let label = label.trim();
+            label.starts_with("SSID").then(|| ssid.trim().to_string())
+        })
+        .collect()
+}
+
+/// The entries one level under `Current Network Information:` and
+/// `Other Local Wi-Fi Networks:`, each a name ending in `:`.
+fn parse_system_profiler(output: &str) -> Vec<String> {
+    let indent = |line: &str| line.len() - line.trim_start().len();
+    let mut ssids = Vec::new();
+    // The section header's indent, and its entries' once seen.
+    let mut section: Option<(usize, Option<usize>)> = None;
+    for line in output.lines().filter(|l| !l.trim().is_empty()) {
+        let depth = indent(line);
+        let trimmed = line.trim();
+        if trimmed.ends_with("Network Information:") || trimmed.ends_with("Wi-Fi Networks:") {
+            section = Some((depth, None));
+            continue;
+        }
+        let Some((header, entries)) = section.as_mut() else {
+            continue;
+        };
+        if depth <= *header {
+            section = None;
+            continue;
+        }
+        let entry_depth = *entries.get_or_insert(depth);
+        if depth == entry_depth {
+            if let Some(name) = trimmed.strip_suffix(':') {
+                ssids.push(name.to_string());
+            }
+        }
+    }
+    ssids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_each_platform_tool() {
+        assert_eq!(
+            parse_nmcli("Home\nPorch Fallback Hotspot\nweird\\:name\n"),
+            ["Home", "Porch Fallback Hotspot", "weird:name"]
+        );
+
+        let netsh = "Interface name : Wi-Fi\nThere are 2 networks currently visible.\n\n\
+                     SSID 1 : Home\n    Network type            : Infrastructure\n\
+                     SSID 2 : ESPHome-1A2B3C\n    Authentication          : WPA2-Personal\n";
+        assert_eq!(parse_netsh(netsh), ["Home", "ESPHome-1A2B3C"]);
+
+        let profiler = "Wi-Fi:\n\n      Interfaces:\n        en0:\n          \
+                        Current Network Information:\n            Home:\n              \
+                        PHY Mode: 802.11ax\n          Other Local Wi-Fi Networks:\n            \
+                        Porch Fallback Hotspot:\n              PHY Mode: 802.11n\n            \
+                        Neighbour:\n              Channel: 6\n          Status: Connected\n";
+        assert_eq!(
+            parse_system_profiler(profiler),
+            ["Home", "Porch Fallback Hotspot", "Neighbour"]
+        );
+    }
+}
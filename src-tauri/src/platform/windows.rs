@@ -117,14 +117,15 @@ fn ensure_firewall_rule(app_handle: &AppHandle) {
     });
 }
 
-/// Absolute path of a binary under `System32`. The firewall flow launches an
-/// *elevated* subprocess, and both `CreateProcessW` and `ShellExecuteEx`
-/// include the current directory in their by-name search order — a planted
-/// `netsh.exe` in a user-writable CWD would run as administrator behind the
-/// one UAC prompt the user expects. Resolved via `GetSystemDirectoryW`
-/// rather than `%SystemRoot%`, since the environment is user-writable state
-/// too; the unelevated query uses it as well for consistency.
-fn system32(tail: &str) -> std::path::PathBuf {
+/// Absolute path of a binary under `System32`. The firewall flow (and the
+/// hosts file write in [`super::hosts`]) launches an *elevated* subprocess,
+/// and both `CreateProcessW` and `ShellExecuteEx` include the current
+/// directory in their by-name search order — a planted `netsh.exe` in a
+/// user-writable CWD would run as administrator behind the one UAC prompt
+/// the user expects. Resolved via `GetSystemDirectoryW` rather than
+/// `%SystemRoot%`, since the environment is user-writable state too; the
+/// unelevated query uses it as well for consistency.
+pub(super) fn system32(tail: &str) -> std::path::PathBuf {
     use std::os::windows::ffi::OsStringExt;
 
     use ::windows::Win32::System::SystemInformation::GetSystemDirectoryW;
@@ -146,6 +147,17 @@ fn system32(tail: &str) -> std::path::PathBuf {
     dir.join(tail)
 }
 
+/// Fill `buf` from the system's preferred random number generator.
+pub(super) fn random_bytes(buf: &mut [u8]) -> Result<()> {
+    use ::windows::Win32::Security::Cryptography::{
+        BCryptGenRandom, BCRYPT_USE_SYSTEM_PREFERRED_RNG,
+    };
+
+    unsafe { BCryptGenRandom(None, buf, BCRYPT_USE_SYSTEM_PREFERRED_RNG) }
+        .ok()
+        .context("BCryptGenRandom failed")
+}
+
 /// Whether a rule named [`FIREWALL_RULE_NAME`] exists. Querying the firewall
 /// needs no elevation; `netsh` exits non-zero when no rule matches.
 fn firewall_rule_exists() -> bool {
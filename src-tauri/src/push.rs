@@ -0,0 +1,153 @@
+//! Push notifications through ntfy or Gotify (`push` in the settings), so the
+//! builder's events reach a phone from a headless or remote machine, where
+//! desktop notifications go nowhere.
+//!
+//! Every notification [`notifications::show`](crate::notifications::show)
+//! lets through is posted too, whether or not the desktop shows it; pausing
+//! notifications pauses these with them. A post that fails is logged and
+//! dropped: nothing retries or waits for one.
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use serde_json::json;
+use tracing::{info, warn};
+
+use crate::settings::PushTarget;
+
+/// How long a post may take before it is given up.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Gotify's priority for the messages: high enough to pop up on Android,
+/// short of the ones that break through Do Not Disturb.
+const GOTIFY_PRIORITY: u8 = 5;
+
+struct Push {
+    target: PushTarget,
+    client: reqwest::Client,
+}
+
+/// The configured service; set once at launch.
+static PUSH: OnceLock<Push> = OnceLock::new();
+
+/// Post notifications to `target` from now on, if there is one.
+pub(crate) fn init(target: Option<&PushTarget>) {
+    let Some(target) = target else {
+        return;
+    };
+    info!("Posting notifications to {}", target.service());
+    let client = crate::network::client()
+        .timeout(TIMEOUT)
+        .build()
+        .unwrap_or_default();
+    let _ = PUSH.set(Push {
+        target: target.clone(),
+        client,
+    });
+}
+
+/// Post a notification, in the background.
+pub(crate) fn send(title: &str, body: &str) {
+    let Some(push) = PUSH.get() else {
+        return;
+    };
+    let post = match Post::new(&push.target, title, body) {
+        Ok(post) => post,
+        Err(e) => {
+            warn!("Not posting to {}: {:#}", push.target.service(), e);
+            return;
+        }
+    };
+    let service = push.target.service();
+    let request = push.client.post(&post.url).json(&post.body);
+    let request = post
+        .headers
+        .into_iter()
+        .fold(request, |request, (name, value)| {
+            request.header(name, value)
+        });
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = request.send().await.and_then(|r| r.error_for_status()) {
+            warn!("Failed to post a notification to {}: {}", service, e);
+        }
+    });
+}
+
+/// One notification as the service takes it.
+#[derive(Debug, PartialEq)]
+struct Post {
+    url: String,
+    headers: Vec<(&'static str, String)>,
+    body: serde_json::Value,
+}
+
+impl Post {
+    fn new(target: &PushTarget, title: &str, message: &str) -> Result<Self> {
+        match target {
+            // JSON to the server's root rather than the text body to the
+            // topic's URL, where the title would have to fit in a header.
+            PushTarget::Ntfy { url, token } => {
+                let parts = url
+                    .trim_end_matches('/')
+                    .rsplit_once('/')
+                    .filter(|(server, _)| server.contains("://") && !server.ends_with('/'));
+                let Some((server, topic)) = parts else {
+                    bail!("{url} names no topic, e.g. https://ntfy.sh/my-builder");
+                };
+                Ok(Self {
+                    url: server.to_string(),
+                    headers: token
+                        .iter()
+                        .map(|token| ("Authorization", format!("Bearer {token}")))
+                        .collect(),
+                    body: json!({ "topic": topic, "title": title, "message": message }),
+                })
+            }
+            PushTarget::Gotify { url, token } => Ok(Self {
+                url: format!("{}/message", url.trim_end_matches('/')),
+                headers: vec![("X-Gotify-Key", token.clone())],
+                body: json!({ "title": title, "message": message, "priority": GOTIFY_PRIORITY }),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ntfy_posts_json_to_the_server() {
+        let target = PushTarget::Ntfy {
+            url: "https://ntfy.sh/my-builder/".into(),
+            token: Some("tk_1".into()),
+        };
+        let post = Post::new(&target, "Update", "ESPHome 2025.2.0 is out").unwrap();
+        assert_eq!(post.url, "https://ntfy.sh");
+        assert_eq!(post.headers, [("Authorization", "Bearer tk_1".to_string())]);
+        assert_eq!(post.body["topic"], "my-builder");
+        assert_eq!(post.body["title"], "Update");
+
+        for url in ["https://ntfy.sh", "https://ntfy.sh/", "ntfy.sh/topic"] {
+            let target = PushTarget::Ntfy {
+                url: url.into(),
+                token: None,
+            };
+            assert!(Post::new(&target, "t", "m").is_err(), "{url}");
+        }
+    }
+
+    #[test]
+    fn gotify_posts_to_the_message_endpoint() {
+        let target = PushTarget::Gotify {
+            url: "https://push.example.com/".into(),
+            token: "A1b2".into(),
+        };
+        let post = Post::new(&target, "Update", "body").unwrap();
+        assert_eq!(post.url, "https://push.example.com/message");
+        assert_eq!(post.headers, [("X-Gotify-Key", "A1b2".to_string())]);
+        assert_eq!(post.body["message"], "body");
+        assert_eq!(post.body["priority"], 5);
+    }
+}
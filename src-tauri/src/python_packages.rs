@@ -0,0 +1,415 @@
+//! The "Python Packages..." window: every package in the environment the
+//! backend runs on (the app's own, or the pinned venv) with its version,
+//! next to the version the app shipped with, for when an update pulled in a
+//! dependency that breaks builds and it needs pinning back. A row's version
+//! list installs any release PyPI has of that package, older ones included,
+//! with pip's progress in the update progress window.
+//!
+//! The shipped versions are read from the `*.dist-info` directories of the
+//! bundled Python tree, which nothing changes; a development build without
+//! one shows no baseline. The page is `dist/python_packages.html`.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tracing::error;
+
+use crate::audit::{self, Source};
+use crate::control::ops::{self, UpdateGuard};
+use crate::error::UserError;
+use crate::i18n::{t, t_with};
+use crate::window::app_state;
+
+/// How deep below the bundled tree's root `site-packages` may be
+/// (`lib/python3.12/site-packages`, or `Lib/site-packages` on Windows).
+const SITE_PACKAGES_DEPTH: usize = 4;
+
+/// Show the Python packages window, opening it if it isn't.
+pub(crate) fn open(app: &AppHandle) -> tauri::Result<()> {
+    let texts = serde_json::json!({
+        "intro": t("python_packages.intro"),
+        "filter": t("python_packages.filter"),
+        "changed_only": t("python_packages.changed_only"),
+        "package": t("python_packages.package"),
+        "installed": t("python_packages.installed"),
+        "bundled": t("python_packages.bundled"),
+        "change": t("python_packages.change"),
+        "actions": t("python_packages.actions"),
+        "no_baseline": t("python_packages.no_baseline"),
+        "same": t("python_packages.same"),
+        "changed": t("python_packages.changed"),
+        "added": t("python_packages.added"),
+        "missing": t("python_packages.missing"),
+        "versions": t("python_packages.versions"),
+        "latest": t("python_packages.latest"),
+        "install": t("python_packages.install"),
+        "loading": t("python_packages.loading"),
+        "versions_label": t("python_packages.versions_label"),
+        "version_label": t("python_packages.version_label"),
+        "install_label": t("python_packages.install_label"),
+        "installed_done": t("python_packages.installed_done"),
+        "shown": t("python_packages.shown"),
+    });
+    crate::window::open(
+        app,
+        "python-packages",
+        "python_packages.html",
+        t("python_packages.title"),
+        texts,
+    )
+}
+
+/// How a package differs from the one the app shipped with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Change {
+    Same,
+    /// Another version than the shipped one.
+    Changed,
+    /// Not shipped with the app.
+    Added,
+    /// Shipped, but no longer installed.
+    Missing,
+}
+
+/// A package's row in the window.
+#[derive(Debug, Serialize)]
+pub(crate) struct PackageRow {
+    name: String,
+    installed: Option<String>,
+    bundled: Option<String>,
+    change: Change,
+}
+
+/// The environment's packages.
+#[derive(Debug, Serialize)]
+pub(crate) struct Packages {
+    /// Which environment they are in, for the window's heading.
+    environment: String,
+    rows: Vec<PackageRow>,
+    /// Whether the shipped versions are known.
+    baseline: bool,
+}
+
+/// One entry of `pip list --format=json`.
+#[derive(Debug, Deserialize)]
+struct Listed {
+    name: String,
+    version: String,
+}
+
+/// A package name as PEP 503 compares them: lowercase, with every run of
+/// `-`, `_` and `.` as one `-`.
+fn normalize(name: &str) -> String {
+    let mut normalized = String::with_capacity(name.len());
+    for c in name.chars() {
+        if matches!(c, '-' | '_' | '.') {
+            if !normalized.ends_with('-') {
+                normalized.push('-');
+            }
+        } else {
+            normalized.push(c.to_ascii_lowercase());
+        }
+    }
+    normalized
+}
+
+/// The name and version in a `name-version.dist-info` directory's name. The
+/// name part never has a `-` of its own: the wheel format escapes it as `_`.
+fn parse_dist_info(dir_name: &str) -> Option<(String, String)> {
+    let (name, version) = dir_name.strip_suffix(".dist-info")?.split_once('-')?;
+    (!name.is_empty() && !version.is_empty()).then(|| (name.to_string(), version.to_string()))
+}
+
+/// Every package in a Python tree's `site-packages`, by name as in its
+/// `.dist-info`, found without running its interpreter.
+fn dist_infos(root: &Path) -> Vec<(String, String)> {
+    let mut packages = Vec::new();
+    let mut dirs: Vec<(PathBuf, usize)> = vec![(root.to_path_buf(), 0)];
+    while let Some((dir, depth)) = dirs.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        let site_packages = dir.file_name().is_some_and(|name| name == "site-packages");
+        for entry in entries.flatten() {
+            if !entry.file_type().is_ok_and(|kind| kind.is_dir()) {
+                continue;
+            }
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if site_packages {
+                packages.extend(parse_dist_info(&name));
+            } else if depth < SITE_PACKAGES_DEPTH {
+                dirs.push((entry.path(), depth + 1));
+            }
+        }
+    }
+    packages
+}
+
+/// The installed packages next to the shipped ones, by name.
+fn diff(installed: Vec<Listed>, bundled: Vec<(String, String)>, baseline: bool) -> Vec<PackageRow> {
+    let mut rows: BTreeMap<String, PackageRow> = BTreeMap::new();
+    for Listed { name, version } in installed {
+        rows.insert(
+            normalize(&name),
+            PackageRow {
+                name,
+                installed: Some(version),
+                bundled: None,
+                change: Change::Added,
+            },
+        );
+    }
+    for (name, version) in bundled {
+        let row = rows.entry(normalize(&name)).or_insert(PackageRow {
+            name,
+            installed: None,
+            bundled: None,
+            change: Change::Missing,
+        });
+        row.change = match &row.installed {
+            Some(installed) if *installed == version => Change::Same,
+            Some(_) => Change::Changed,
+            None => Change::Missing,
+        };
+        row.bundled = Some(version);
+    }
+    // Without a baseline there is nothing to compare with.
+    if !baseline {
+        for row in rows.values_mut() {
+            row.change = Change::Same;
+        }
+    }
+    rows.into_values().collect()
+}
+
+/// The interpreter the backend runs on, which the window lists and installs
+/// into.
+fn backend_python(app: &AppHandle) -> Result<PathBuf, String> {
+    let state = app_state(app)?;
+    state
+        .daemon
+        .host_python()
+        .cloned()
+        .ok_or_else(|| t("python_packages.native_only"))
+}
+
+/// Every package in the backend's environment, with the shipped versions.
+#[tauri::command]
+pub(crate) async fn python_packages(app: AppHandle) -> Result<Packages, String> {
+    let state = app_state(&app)?;
+    let python = backend_python(&app)?;
+    let environment = match state.daemon.pinned_esphome() {
+        Some(version) => t_with("python_packages.pinned", &[("version", version)]),
+        None => t("python_packages.own"),
+    };
+    let bundled_root = crate::platform::get_bundled_python_root(&app).ok();
+    let listed = tauri::async_runtime::spawn_blocking(move || {
+        let listed = crate::platform::run_python_capture_stdout(
+            &python,
+            [
+                "-m",
+                "pip",
+                "list",
+                "--format=json",
+                "--disable-pip-version-check",
+            ],
+        );
+        let bundled = bundled_root.as_deref().map(dist_infos).unwrap_or_default();
+        (listed, bundled)
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    let (listed, bundled) = listed;
+    let installed: Vec<Listed> = match listed {
+        Ok(Some(json)) => serde_json::from_str(&json).map_err(|e| e.to_string()),
+        Ok(None) => Err("pip list failed".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+    .map_err(|e| {
+        error!("Failed to list the Python packages: {}", e);
+        t_with("python_packages.failed", &[("error", &e)])
+    })?;
+    let baseline = !bundled.is_empty();
+    Ok(Packages {
+        environment,
+        rows: diff(installed, bundled, baseline),
+        baseline,
+    })
+}
+
+/// Every release of `package` on PyPI, newest first.
+#[tauri::command]
+pub(crate) async fn python_package_releases(
+    app: AppHandle,
+    package: String,
+) -> Result<Vec<String>, String> {
+    let state = app_state(&app)?;
+    state
+        .update_checker
+        .package_releases(&normalize(&package))
+        .await
+        .map_err(|e| {
+            error!("Failed to list the {} releases: {}", package, e);
+            t_with(
+                "python_packages.releases_failed",
+                &[("package", &package), ("error", &e.user_message())],
+            )
+        })
+}
+
+/// Install `package` at `version`, or its newest release with `None`, once
+/// the user agrees, with the dashboard stopped meanwhile. Returns whether it
+/// went ahead.
+#[tauri::command]
+pub(crate) async fn install_python_package(
+    app: AppHandle,
+    package: String,
+    version: Option<String>,
+) -> Result<bool, String> {
+    let state = app_state(&app)?;
+    let python = backend_python(&app)?;
+    // Only names and releases PyPI knows go to pip, so neither can be read
+    // as one of its options.
+    let releases = python_package_releases(app.clone(), package.clone()).await?;
+    if let Some(version) = &version {
+        if !releases.contains(version) {
+            return Err(t_with(
+                "python_packages.no_release",
+                &[("package", &package), ("version", version)],
+            ));
+        }
+    }
+    let message = match &version {
+        Some(version) => t_with(
+            "python_packages.confirm_install",
+            &[("package", &package), ("version", version)],
+        ),
+        None => t_with("python_packages.confirm_upgrade", &[("package", &package)]),
+    };
+    let confirmed = crate::dialog::confirm(
+        &app,
+        &t("python_packages.title"),
+        message,
+        &t("python_packages.install"),
+        &t("python_packages.cancel"),
+    )
+    .await;
+    if !confirmed {
+        return Ok(false);
+    }
+    let Some(_guard) = UpdateGuard::try_acquire(state.update_in_flight.clone()) else {
+        return Err(t("python_packages.busy"));
+    };
+    if !ops::authorize(&state, t("auth.update"), &|_, _| {}).await {
+        return Ok(false);
+    }
+    let package = normalize(&package);
+    let result = ops::stop_install_start(&state, || {
+        state
+            .update_checker
+            .install_package(&app, &python, &package, version.as_deref())
+    })
+    .await;
+    // Installing a package can move ESPHome along with it.
+    ops::refresh_version_display_blocking(&app).await;
+    let target = match &version {
+        Some(version) => format!("{package} {version}"),
+        None => format!("the latest {package}"),
+    };
+    let detail = match &result {
+        Ok(()) => format!("installed {target}"),
+        Err(e) => format!("installing {target} failed: {e}"),
+    };
+    audit::record(&app, Source::Tray, "update", detail);
+    result.map(|()| true).map_err(|e| {
+        error!("Failed to install {}: {}", target, e);
+        t_with(
+            "python_packages.install_failed",
+            &[("package", &target), ("error", &e)],
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn listed(name: &str, version: &str) -> Listed {
+        Listed {
+            name: name.into(),
+            version: version.into(),
+        }
+    }
+
+    #[test]
+    fn dist_info_names_give_the_package_and_version() {
+        assert_eq!(
+            parse_dist_info("aioesphomeapi-29.1.0.dist-info"),
+            Some(("aioesphomeapi".into(), "29.1.0".into()))
+        );
+        assert_eq!(
+            parse_dist_info("esphome_device_builder-1.2.0b1.dist-info"),
+            Some(("esphome_device_builder".into(), "1.2.0b1".into()))
+        );
+        assert_eq!(parse_dist_info("esphome"), None);
+        assert_eq!(parse_dist_info("esphome.dist-info"), None);
+        assert_eq!(
+            normalize("Esphome_Device.Builder"),
+            "esphome-device-builder"
+        );
+        assert_eq!(normalize("ruamel.yaml"), "ruamel-yaml");
+    }
+
+    #[test]
+    fn the_diff_matches_names_as_pip_does() {
+        let rows = diff(
+            vec![
+                listed("esptool", "4.8.1"),
+                listed("Pillow", "11.1.0"),
+                listed("esphome-device-builder", "1.2.0"),
+                listed("requests", "2.32.3"),
+            ],
+            vec![
+                ("esptool".into(), "4.8.1".into()),
+                ("pillow".into(), "10.4.0".into()),
+                ("esphome_device_builder".into(), "1.2.0".into()),
+                ("aioesphomeapi".into(), "29.1.0".into()),
+            ],
+            true,
+        );
+        let changes: Vec<(&str, Change)> = rows
+            .iter()
+            .map(|row| (row.name.as_str(), row.change))
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                ("aioesphomeapi", Change::Missing),
+                ("esphome-device-builder", Change::Same),
+                ("esptool", Change::Same),
+                ("Pillow", Change::Changed),
+                ("requests", Change::Added),
+            ]
+        );
+        assert_eq!(rows[3].bundled.as_deref(), Some("10.4.0"));
+
+        let rows = diff(vec![listed("requests", "2.32.3")], Vec::new(), false);
+        assert_eq!(rows[0].change, Change::Same);
+    }
+
+    #[test]
+    fn dist_infos_are_found_in_site_packages_only() {
+        let root =
+            std::env::temp_dir().join(format!("esphome-desktop-dist-infos-{}", std::process::id()));
+        let site = root.join("lib/python3.12/site-packages");
+        std::fs::create_dir_all(site.join("esptool-4.8.1.dist-info")).unwrap();
+        std::fs::create_dir_all(site.join("esptool")).unwrap();
+        std::fs::create_dir_all(root.join("lib/other-1.0.dist-info")).unwrap();
+        let packages = dist_infos(&root);
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(packages, [("esptool".to_string(), "4.8.1".to_string())]);
+    }
+}
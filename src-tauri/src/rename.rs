@@ -0,0 +1,175 @@
+//! The "Rename Device..." window: pick a device and its new name, rename
+//! everything that goes with it ([`crate::devices::rename`]), then explain
+//! that the device answers at its old address until it runs firmware with
+//! the new name, and offer that first upload there. The page is
+//! `dist/rename.html`.
+
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::AppHandle;
+use tracing::{error, info};
+
+use crate::audit::{self, Source};
+use crate::control::ops::UpdateGuard;
+use crate::devices::batch::{self, BatchAction};
+use crate::devices::registry::{ManualDevice, Registry};
+use crate::devices::sources::AllowList;
+use crate::devices::{self, rename};
+use crate::i18n::{t, t_with};
+use crate::util::ConfigStore;
+use crate::window::app_state;
+
+/// Show the rename window, opening it if it isn't.
+pub(crate) fn open(app: &AppHandle) -> tauri::Result<()> {
+    let texts = serde_json::json!({
+        "device": t("rename.device"),
+        "new_name": t("rename.new_name"),
+        "explain": t("rename.explain"),
+        "rename": t("rename.rename"),
+        "renamed": t("rename.renamed"),
+        "removed": t("rename.removed"),
+        "next": t("rename.next"),
+        "upload": t("rename.upload"),
+        "uploading": t("rename.uploading"),
+        "uploaded": t("rename.uploaded"),
+    });
+    crate::window::open(app, "rename", "rename.html", t("rename.title"), texts)
+}
+
+/// The devices that can be renamed, as the page lists them.
+#[derive(Debug, Serialize)]
+pub(crate) struct Devices {
+    /// Those with a config.
+    devices: Vec<String>,
+    /// The one picked last time, if it's still there.
+    selected: Option<String>,
+}
+
+/// The devices that can be renamed, and which to pick.
+#[tauri::command]
+pub(crate) async fn rename_devices(app: AppHandle) -> Result<Devices, String> {
+    let state = app_state(&app)?;
+    let devices = devices::list_devices(state.daemon.config_dir()).map_err(|e| format!("{e:#}"))?;
+    let selected = crate::session::selected_device().filter(|device| devices.contains(device));
+    Ok(Devices { devices, selected })
+}
+
+/// Remember `device` as the one picked, for the next time the window opens.
+#[tauri::command]
+pub(crate) fn select_rename_device(device: String) {
+    crate::session::select_device(&device);
+}
+
+/// A rename done, as the page shows it.
+#[derive(Debug, Serialize)]
+pub(crate) struct Done {
+    /// The config's new file name.
+    config: String,
+    old_address: String,
+    /// How many stale build files went.
+    removed: usize,
+}
+
+/// Rename `device` to `new_name`.
+#[tauri::command]
+pub(crate) async fn rename_device(
+    app: AppHandle,
+    device: String,
+    new_name: String,
+) -> Result<Done, String> {
+    let state = app_state(&app)?;
+    let data_dir = crate::platform::get_data_dir(&app).map_err(|e| e.to_string())?;
+    let config_dir = state.daemon.config_dir().clone();
+    let (from, to) = (device.clone(), new_name.clone());
+    let renamed = tauri::async_runtime::spawn_blocking(move || {
+        let renamed = rename::rename(&config_dir, &data_dir, &from, &to)?;
+        rename::move_key(&from, &to);
+        anyhow::Ok(renamed)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| {
+        error!("Failed to rename {} to {}: {:#}", device, new_name, e);
+        t_with("rename.failed", &[("error", &format!("{e:#}"))])
+    })?;
+    info!("Renamed {} to {}", device, new_name);
+    crate::session::select_device(&new_name);
+    audit::record(
+        &app,
+        Source::Tray,
+        "rename",
+        format!("renamed {device} to {new_name}"),
+    );
+    Ok(Done {
+        config: renamed
+            .config
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned(),
+        old_address: renamed.old_address,
+        removed: renamed.removed.len(),
+    })
+}
+
+/// Build `device` and flash it at `address`, where it answers under its
+/// old name. An `Err` carries what went wrong, translated.
+#[tauri::command]
+pub(crate) async fn upload_renamed(
+    app: AppHandle,
+    device: String,
+    address: String,
+) -> Result<(), String> {
+    let state = app_state(&app)?;
+    // Holds the guard so an update can't swap ESPHome out mid-build.
+    let Some(_guard) = UpdateGuard::try_acquire(state.update_in_flight.clone()) else {
+        return Err(t("rename.busy"));
+    };
+    let loaded = crate::platform::get_data_dir(&app)
+        .map_err(anyhow::Error::from)
+        .and_then(|dir| {
+            let allow = AllowList::load(&AllowList::path(&dir))?;
+            let mut registry = Registry::load(&Registry::path(&dir))?;
+            // Only for this upload; the saved registry is left alone.
+            let entry = ManualDevice {
+                key_secret: registry.get(&device).and_then(|d| d.key_secret.clone()),
+                address: address.clone(),
+            };
+            registry.add(&device, entry)?;
+            Ok((allow, registry, dir))
+        });
+    let (allow, registry, data_dir) =
+        loaded.map_err(|e| t_with("rename.upload_failed", &[("output", &format!("{e:#}"))]))?;
+    let lines = Mutex::new(Vec::new());
+    let progress = |_: &str, line: &str| {
+        lines
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(line.to_string());
+    };
+    let report = batch::run(
+        &state.daemon,
+        BatchAction::Upload,
+        &[device.clone()],
+        &allow,
+        &registry,
+        &data_dir,
+        &progress,
+    )
+    .await;
+    if report.failed.is_empty() {
+        audit::record(
+            &app,
+            Source::Tray,
+            "upload",
+            format!("uploaded renamed {device} to {address}"),
+        );
+        return Ok(());
+    }
+    let lines = lines.into_inner().unwrap_or_else(|e| e.into_inner());
+    Err(t_with(
+        "rename.upload_failed",
+        &[("output", &lines.join("\n"))],
+    ))
+}
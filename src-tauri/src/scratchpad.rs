@@ -0,0 +1,102 @@
+//! The "Validate Snippet..." scratchpad window, where any YAML can be pasted
+//! and checked with `esphome config` against the installed ESPHome, in a
+//! scratch config outside the config directory (see
+//! [`crate::devices::snippet::check`]). Below it, the configs' `!secret`
+//! names are checked against `secrets.yaml` ([`crate::devices::secrets`]),
+//! missing secrets being what fails validation most often. The page is
+//! `dist/scratchpad.html`.
+
+use std::sync::Arc;
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+use tracing::error;
+
+use crate::devices::{secrets, snippet};
+use crate::i18n::{t, t_with};
+use crate::AppState;
+
+/// Show the scratchpad, opening it if it isn't.
+pub(crate) fn open(app: &AppHandle) -> tauri::Result<()> {
+    let texts = serde_json::json!({
+        "placeholder": t("scratchpad.placeholder"),
+        "yaml_label": t("scratchpad.yaml_label"),
+        "output_label": t("scratchpad.output_label"),
+        "check": t("scratchpad.check"),
+        "checking": t("scratchpad.checking"),
+        "hint": t("scratchpad.hint"),
+        "secrets": t("scratchpad.secrets"),
+        "secrets_ok": t("scratchpad.secrets_ok"),
+        "secrets_none": t("scratchpad.secrets_none"),
+        "secrets_missing": t("scratchpad.secrets_missing"),
+        "secrets_unused": t("scratchpad.secrets_unused"),
+    });
+    crate::window::open(
+        app,
+        "scratchpad",
+        "scratchpad.html",
+        t("scratchpad.title"),
+        texts,
+    )
+}
+
+/// A check's outcome, as the page shows it.
+#[derive(Debug, Serialize)]
+pub(crate) struct Checked {
+    valid: bool,
+    message: String,
+    /// ESPHome's complaint, or an empty string.
+    output: String,
+}
+
+/// Check `yaml` with the installed ESPHome. An `Err` is a check that couldn't
+/// run, translated for the page.
+#[tauri::command]
+pub(crate) async fn check_snippet(app: AppHandle, yaml: String) -> Result<Checked, String> {
+    let Some(state) = app.try_state::<Arc<AppState>>() else {
+        return Err(t("scratchpad.not_ready"));
+    };
+    let preset = state.settings.read().await.workspace.new_device.clone();
+    let result = match crate::platform::get_data_dir(&app) {
+        Ok(data_dir) => snippet::check(&state.daemon, &data_dir, &preset, &yaml).await,
+        Err(e) => Err(e.into()),
+    };
+    let version = state
+        .store
+        .snapshot()
+        .esphome_version
+        .unwrap_or_else(|| t("version.unknown"));
+    match result {
+        Ok(None) => Ok(Checked {
+            valid: true,
+            message: t_with("scratchpad.valid", &[("version", &version)]),
+            output: String::new(),
+        }),
+        Ok(Some(output)) => Ok(Checked {
+            valid: false,
+            message: t_with("scratchpad.invalid", &[("version", &version)]),
+            output,
+        }),
+        Err(e) => {
+            error!("Failed to check the scratchpad: {:#}", e);
+            Err(t_with("scratchpad.failed", &[("error", &format!("{e:#}"))]))
+        }
+    }
+}
+
+/// The configs' secrets checked against `secrets.yaml`, read afresh so
+/// edits since the window opened show.
+#[tauri::command]
+pub(crate) async fn check_secrets(app: AppHandle) -> Result<secrets::Report, String> {
+    let Some(state) = app.try_state::<Arc<AppState>>() else {
+        return Err(t("scratchpad.not_ready"));
+    };
+    let config_dir = state.daemon.config_dir().clone();
+    let report = tauri::async_runtime::spawn_blocking(move || secrets::check(&config_dir))
+        .await
+        .map_err(|e| e.to_string())?;
+    report.map_err(|e| {
+        error!("Failed to check the configs' secrets: {:#}", e);
+        t_with("scratchpad.secrets_failed", &[("error", &format!("{e:#}"))])
+    })
+}
@@ -0,0 +1,65 @@
+//! The "Search Configs..." window: type a component, pin or secret name and
+//! see which devices use it, each hit opening its file at the line in the
+//! editor (see [`crate::devices::search`] and [`crate::devices::editor`]).
+//! The page is `dist/search.html`.
+
+use tauri::AppHandle;
+use tracing::error;
+
+use crate::devices::{editor, search};
+use crate::i18n::{t, t_with};
+use crate::window::app_state;
+
+/// Show the search window, opening it if it isn't.
+pub(crate) fn open(app: &AppHandle) -> tauri::Result<()> {
+    let texts = serde_json::json!({
+        "placeholder": t("search.placeholder"),
+        "query_label": t("search.query_label"),
+        "hits_label": t("search.hits_label"),
+        "whole_word": t("search.whole_word"),
+        "devices": t("search.devices"),
+        "none": t("search.none"),
+        "truncated": t("search.truncated"),
+    });
+    crate::window::open(app, "search", "search.html", t("search.title"), texts)
+}
+
+/// Search the config directory for `query`.
+#[tauri::command]
+pub(crate) async fn search_configs(
+    app: AppHandle,
+    query: String,
+    whole_word: bool,
+) -> Result<search::Results, String> {
+    let config_dir = app_state(&app)?.daemon.config_dir().clone();
+    let found = tauri::async_runtime::spawn_blocking(move || {
+        search::search(&config_dir, &query, whole_word)
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    found.map_err(|e| {
+        error!("Config search failed: {:#}", e);
+        t_with("search.failed", &[("error", &format!("{e:#}"))])
+    })
+}
+
+/// Open `file`, one a window listed under the config directory, at `line`
+/// in the editor.
+#[tauri::command]
+pub(crate) async fn open_config_at(
+    app: AppHandle,
+    file: String,
+    line: usize,
+) -> Result<(), String> {
+    let state = app_state(&app)?;
+    let Some(path) = search::resolve(state.daemon.config_dir(), &file) else {
+        return Err(t_with("search.gone", &[("file", &file)]));
+    };
+    let command = state.settings.read().await.workspace.editor_command.clone();
+    editor::choose(command.as_deref(), None)
+        .and_then(|editor| editor::open_at(editor.as_ref(), &path, line))
+        .map_err(|e| {
+            error!("Failed to open {:?} in an editor: {:#}", path, e);
+            t_with("search.open_failed", &[("error", &format!("{e:#}"))])
+        })
+}
@@ -0,0 +1,140 @@
+//! `--self-test`: check headlessly that a build actually works.
+//!
+//! For packagers and the CI of downstream distributions. In a throwaway data
+//! directory it copies the bundled Python the way a first launch does, starts
+//! the dashboard on a free loopback port, waits for it to answer, and
+//! validates a sample config through the same path as `device validate`. No
+//! Tauri app is built, so no display is needed, and nothing of the user's
+//! (settings, configs, a running instance) is touched. One line per step goes
+//! to stdout; the exit code is 0 when every step passed and 1 otherwise, in
+//! which case the directory is kept for its dashboard log.
+
+use anyhow::{Context, Result};
+use std::path::Path;
+use std::process::ExitCode;
+use std::sync::Mutex;
+
+use crate::daemon::{DaemonManager, DASHBOARD_LOG_NAME};
+use crate::devices::batch::{self, BatchAction};
+use crate::devices::registry::Registry;
+use crate::devices::sources::AllowList;
+use crate::platform;
+
+/// Name of the sample device.
+const SAMPLE_DEVICE: &str = "self-test";
+
+/// The host platform targets no chip, so validating it downloads no
+/// toolchain.
+const SAMPLE_CONFIG: &str = "esphome:\n  name: self-test\n\nhost:\n\nlogger:\n";
+
+/// How long the dashboard gets to answer; a first start compiles its Python.
+const READY_TIMEOUT_SECS: u64 = 120;
+
+pub(crate) fn run() -> ExitCode {
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| "esphome_desktop=warn".into()),
+        )
+        .init();
+
+    let dir =
+        std::env::temp_dir().join(format!("esphome-desktop-self-test-{}", std::process::id()));
+    let passed = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime.block_on(steps(&dir)),
+        Err(e) => report::<()>("start the async runtime", Err(e.into())).is_some(),
+    };
+    if passed {
+        let _ = std::fs::remove_dir_all(&dir);
+        println!("Self-test passed");
+        ExitCode::SUCCESS
+    } else {
+        println!("Self-test failed; its files are in {}", dir.display());
+        ExitCode::from(1)
+    }
+}
+
+/// Run each step, stopping at the first failure but always stopping the
+/// dashboard once started.
+async fn steps(dir: &Path) -> bool {
+    let Some(daemon) = report("provision a data directory", provision(dir)) else {
+        return false;
+    };
+    let passed = report("start the dashboard", start(&daemon).await).is_some()
+        && report("validate a config", validate(&daemon, dir).await).is_some();
+    let stopped = report("stop the dashboard", daemon.stop().await).is_some();
+    passed && stopped
+}
+
+/// Print a step's outcome, passing its value on.
+fn report<T, E: std::fmt::Display>(step: &str, result: Result<T, E>) -> Option<T> {
+    match result {
+        Ok(value) => {
+            println!("ok: {step}");
+            Some(value)
+        }
+        Err(e) => {
+            println!("FAILED: {step}: {e:#}");
+            None
+        }
+    }
+}
+
+/// The config directory with the sample in it, the logs directory and the
+/// Python tree, and a manager over them on a free port.
+fn provision(dir: &Path) -> Result<DaemonManager> {
+    let config_dir = dir.join("config");
+    let logs_dir = dir.join("logs");
+    std::fs::create_dir_all(&config_dir).context("Failed to create config directory")?;
+    std::fs::create_dir_all(&logs_dir).context("Failed to create logs directory")?;
+    std::fs::write(
+        config_dir.join(format!("{SAMPLE_DEVICE}.yaml")),
+        SAMPLE_CONFIG,
+    )
+    .context("Failed to write the sample config")?;
+    let (python, bin_dir) = platform::provision_python_no_handle(dir)?;
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .context("No free loopback port")?
+        .port();
+    Ok(DaemonManager::standalone(
+        python, bin_dir, config_dir, logs_dir, port,
+    ))
+}
+
+async fn start(daemon: &DaemonManager) -> Result<()> {
+    daemon.start().await?;
+    if !crate::wait_for_dashboard_ready(daemon.address(), READY_TIMEOUT_SECS).await {
+        anyhow::bail!(
+            "it didn't answer within {READY_TIMEOUT_SECS}s; see {}",
+            daemon.logs_dir().join(DASHBOARD_LOG_NAME).display()
+        );
+    }
+    Ok(())
+}
+
+async fn validate(daemon: &DaemonManager, dir: &Path) -> Result<()> {
+    let lines = Mutex::new(Vec::new());
+    let progress = |_: &str, line: &str| {
+        lines
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(line.to_string());
+    };
+    let outcome = batch::run(
+        daemon,
+        BatchAction::Validate,
+        &[SAMPLE_DEVICE.to_string()],
+        &AllowList::default(),
+        &Registry::default(),
+        dir,
+        &progress,
+    )
+    .await;
+    if outcome.failed.is_empty() {
+        return Ok(());
+    }
+    let lines = lines.into_inner().unwrap_or_else(|e| e.into_inner());
+    anyhow::bail!("{}", lines.join("\n"))
+}
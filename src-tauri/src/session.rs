@@ -0,0 +1,267 @@
+//! Where each window was last, so it opens there again, the device last
+//! picked in the rename window, and with `restore_windows` on, which windows
+//! were open when the app quit, so they open again at the next launch. Kept
+//! in `session.json` in the app data.
+//!
+//! Placements are in physical pixels, as the window events report them. A
+//! placement on a monitor that is gone is ignored rather than opening a
+//! window nobody can see. The command palette and the update progress come
+//! and go with what summons them, so they are never reopened.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, PhysicalPosition, PhysicalSize, WebviewWindow};
+use tracing::{info, warn};
+
+use crate::util::ConfigStore;
+
+/// The windows that reopen, by label, with what opens each.
+const REOPENED: &[(&str, fn(&AppHandle) -> tauri::Result<()>)] = &[
+    ("scratchpad", crate::scratchpad::open),
+    ("terminal", crate::terminal::open),
+    ("search", crate::search::open),
+    ("pins", crate::pins::open),
+    ("packages", crate::packages::open),
+    ("duplicates", crate::duplicates::open),
+    ("rename", crate::rename::open),
+    ("signing", crate::signing::open),
+    ("versions", crate::versions::open),
+    ("python-packages", crate::python_packages::open),
+    ("settings", crate::settings_window::open),
+];
+
+/// The session as last saved, and where; `None` until [`restore`].
+static SESSION: Mutex<Option<(PathBuf, Session)>> = Mutex::new(None);
+
+/// Set once the session was saved on the way out, as exiting asks twice.
+static SAVED_AT_EXIT: AtomicBool = AtomicBool::new(false);
+
+/// The windows open at the last exit, every window's last placement and the
+/// device last picked.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Session {
+    /// Labels of the windows open when the app quit.
+    #[serde(default)]
+    open: Vec<String>,
+    #[serde(default)]
+    placements: BTreeMap<String, Placement>,
+    /// The device last picked in the rename window.
+    #[serde(default)]
+    device: Option<String>,
+}
+
+impl ConfigStore for Session {
+    const FILE_NAME: &'static str = "session.json";
+    const WHAT: &'static str = "window session";
+}
+
+/// A window's position and inner size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Placement {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+}
+
+impl Placement {
+    /// Whether the window's top-left corner is on one of `monitors`, each
+    /// given by position and size.
+    fn on_screen(&self, monitors: &[(PhysicalPosition<i32>, PhysicalSize<u32>)]) -> bool {
+        monitors.iter().any(|(position, size)| {
+            let right = i64::from(position.x) + i64::from(size.width);
+            let bottom = i64::from(position.y) + i64::from(size.height);
+            (i64::from(position.x)..right).contains(&i64::from(self.x))
+                && (i64::from(position.y)..bottom).contains(&i64::from(self.y))
+        })
+    }
+}
+
+/// Read the session, then with `reopen` open the windows that were open
+/// when the app quit.
+pub(crate) fn restore(app: &AppHandle, reopen: bool) {
+    let path = match crate::platform::get_data_dir(app) {
+        Ok(dir) => Session::path(&dir),
+        Err(e) => {
+            warn!("Failed to locate the window session: {}", e);
+            return;
+        }
+    };
+    let session = Session::load(&path).unwrap_or_else(|e| {
+        warn!("Failed to read the window session: {:#}", e);
+        Session::default()
+    });
+    let open = if reopen {
+        session.open.clone()
+    } else {
+        Vec::new()
+    };
+    *SESSION.lock().unwrap_or_else(|e| e.into_inner()) = Some((path, session));
+    for label in open {
+        let Some((_, opener)) = REOPENED.iter().find(|(known, _)| *known == label) else {
+            continue;
+        };
+        info!("Reopening the {} window", label);
+        if let Err(e) = opener(app) {
+            warn!("Failed to reopen the {} window: {}", label, e);
+        }
+    }
+}
+
+/// Where `label` was last, if that is still on a monitor of `window`'s.
+pub(crate) fn placement(window: &WebviewWindow, label: &str) -> Option<Placement> {
+    let placement = SESSION
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()?
+        .1
+        .placements
+        .get(label)
+        .copied()?;
+    let monitors: Vec<_> = window
+        .available_monitors()
+        .ok()?
+        .iter()
+        .map(|monitor| (*monitor.position(), *monitor.size()))
+        .collect();
+    placement.on_screen(&monitors).then_some(placement)
+}
+
+/// Move and size `window` to `placement`.
+pub(crate) fn place(window: &WebviewWindow, placement: Placement) -> tauri::Result<()> {
+    window.set_position(PhysicalPosition::new(placement.x, placement.y))?;
+    window.set_size(PhysicalSize::new(placement.width, placement.height))
+}
+
+/// Note where `window` is now, for the next time it opens.
+pub(crate) fn remember(window: &WebviewWindow) {
+    let (Ok(position), Ok(size)) = (window.outer_position(), window.inner_size()) else {
+        return;
+    };
+    // A minimized window reports a size of nothing.
+    if size.width == 0 || size.height == 0 {
+        return;
+    }
+    if let Some((_, session)) = SESSION.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+        session.placements.insert(
+            window.label().to_string(),
+            Placement {
+                x: position.x,
+                y: position.y,
+                width: size.width,
+                height: size.height,
+            },
+        );
+    }
+}
+
+/// The device last picked, to pick again.
+pub(crate) fn selected_device() -> Option<String> {
+    SESSION
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()?
+        .1
+        .device
+        .clone()
+}
+
+/// Note `device` as the one picked, for the next time a window offers a
+/// choice.
+pub(crate) fn select_device(device: &str) {
+    if let Some((_, session)) = SESSION.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+        session.device = Some(device.to_string());
+    }
+}
+
+/// Save the placements and the device picked, and with `exiting` which
+/// windows are open, to reopen them. Once on the way out: the windows are
+/// gone by the second time exiting asks.
+pub(crate) fn save(app: &AppHandle, exiting: bool) {
+    if exiting && SAVED_AT_EXIT.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let mut guard = SESSION.lock().unwrap_or_else(|e| e.into_inner());
+    let Some((path, session)) = guard.as_mut() else {
+        return;
+    };
+    if exiting {
+        let windows = app.webview_windows();
+        session.open = REOPENED
+            .iter()
+            .map(|(label, _)| *label)
+            .filter(|label| windows.contains_key(*label))
+            .map(String::from)
+            .collect();
+    }
+    if let Err(e) = session.save(path) {
+        warn!("Failed to save the window session: {:#}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor(
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+    ) -> (PhysicalPosition<i32>, PhysicalSize<u32>) {
+        (
+            PhysicalPosition::new(x, y),
+            PhysicalSize::new(width, height),
+        )
+    }
+
+    #[test]
+    fn placement_counts_as_on_screen_only_on_a_monitor() {
+        let at = |x, y| Placement {
+            x,
+            y,
+            width: 760,
+            height: 600,
+        };
+        let monitors = [monitor(0, 0, 1920, 1080), monitor(-1280, 0, 1280, 1024)];
+        assert!(at(100, 100).on_screen(&monitors));
+        assert!(at(-1200, 40).on_screen(&monitors));
+        assert!(!at(2500, 100).on_screen(&monitors));
+        assert!(!at(-1200, 1050).on_screen(&monitors));
+        assert!(!at(100, 100).on_screen(&[]));
+    }
+
+    #[test]
+    fn the_device_picked_survives_a_save() {
+        let dir = crate::util::unique_temp_dir("session");
+        let path = Session::path(&dir);
+        std::fs::write(&path, r#"{"open":["rename"]}"#).unwrap();
+        let older = Session::load(&path).unwrap();
+        assert_eq!(older.device, None);
+
+        let session = Session {
+            device: Some("kitchen-light".into()),
+            ..older
+        };
+        session.save(&path).unwrap();
+        let loaded = Session::load(&path).unwrap();
+        assert_eq!(loaded.device.as_deref(), Some("kitchen-light"));
+        assert_eq!(loaded.open, ["rename"]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn every_reopened_window_has_its_own_label() {
+        for (i, (label, _)) in REOPENED.iter().enumerate() {
+            assert!(
+                REOPENED[i + 1..].iter().all(|(other, _)| other != label),
+                "{label} is listed twice"
+            );
+        }
+    }
+}
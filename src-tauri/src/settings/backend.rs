@@ -0,0 +1,146 @@
+//! Which ESPHome and device builder the app runs, and where.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use tauri::AppHandle;
+
+use super::Settings;
+
+/// ESPHome release channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ReleaseChannel {
+    /// Latest stable release from PyPI
+    #[default]
+    Stable,
+    /// Latest beta/pre-release from PyPI
+    Beta,
+    /// Latest development build from GitHub (no auto-updates)
+    Dev,
+}
+
+impl fmt::Display for ReleaseChannel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Stable => write!(f, "Stable"),
+            Self::Beta => write!(f, "Beta"),
+            Self::Dev => write!(f, "Dev"),
+        }
+    }
+}
+
+/// Which device-builder channel the daemon should run. The classic ESPHome
+/// dashboard backend was removed in line with ESPHome 2026.6.0 retiring the
+/// legacy in-tree dashboard; the daemon now always launches the device builder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Backend {
+    /// ESPHome device builder, stable release from PyPI.
+    ///
+    /// This is the default so a fresh install (and any legacy/unknown/corrupt
+    /// settings that fall back here) matches the stable-by-default
+    /// [`ReleaseChannel`]: a user on the stable channel must not be silently
+    /// offered a beta device-builder update (#241). Beta is opt-in via the tray
+    /// or the `backend beta` CLI subcommand.
+    #[default]
+    BuilderStable,
+    /// ESPHome device builder, beta/pre-release from PyPI
+    BuilderBeta,
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BuilderStable => write!(f, "ESPHome Device Builder (stable)"),
+            Self::BuilderBeta => write!(f, "ESPHome Device Builder (beta)"),
+        }
+    }
+}
+
+/// Where the device builder process runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Runtime {
+    /// The bundled Python on the host, the default everywhere.
+    #[default]
+    Native,
+    /// A venv inside a WSL2 distro (Windows only). Compiles are much faster on
+    /// the Linux filesystem; ignored with a warning on other platforms.
+    Wsl2,
+    /// A venv in a Docker container (`docker_image`).
+    Docker,
+    /// A venv on another machine, reached over SSH (`ssh_host`).
+    Ssh,
+    /// A dashboard already running elsewhere (`remote_address`); nothing is
+    /// started but a relay to it.
+    Remote,
+}
+
+impl fmt::Display for Runtime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Native => write!(f, "native"),
+            Self::Wsl2 => write!(f, "WSL2"),
+            Self::Docker => write!(f, "Docker"),
+            Self::Ssh => write!(f, "SSH"),
+            Self::Remote => write!(f, "remote"),
+        }
+    }
+}
+
+/// Deserialize the runtime, falling back to [`Runtime::Native`] for an unknown
+/// or malformed value so a hand-edit can't discard every other preference via
+/// the corrupt-file recovery path (same policy as [`deserialize_backend`]).
+pub(super) fn deserialize_runtime<'de, D>(deserializer: D) -> Result<Runtime, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let raw = serde_json::Value::deserialize(deserializer)?;
+    Ok(match raw.as_str() {
+        Some("wsl2") => Runtime::Wsl2,
+        Some("docker") => Runtime::Docker,
+        Some("ssh") => Runtime::Ssh,
+        Some("remote") => Runtime::Remote,
+        _ => Runtime::default(),
+    })
+}
+
+/// Deserialize the backend, tolerating legacy or unknown values by falling back
+/// to the default. An old settings file selecting the removed classic dashboard
+/// (`"backend": "classic"`) must migrate to the default device builder rather
+/// than failing the whole parse, which would discard every other preference via
+/// the corrupt-file recovery path.
+pub(super) fn deserialize_backend<'de, D>(deserializer: D) -> Result<Backend, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    // Deserialize into a generic value so a non-string `backend` (null, number,
+    // bool from a hand-edited or future file) falls back to the default too,
+    // rather than failing the whole parse and discarding every other preference.
+    let raw = serde_json::Value::deserialize(deserializer)?;
+    Ok(match raw.as_str() {
+        Some("builder_stable") => Backend::BuilderStable,
+        Some("builder_beta") => Backend::BuilderBeta,
+        _ => Backend::default(),
+    })
+}
+
+/// Returns true if the persisted settings file selects the removed classic
+/// dashboard backend. Used at startup to force a fresh bundled device builder
+/// for users migrating off classic. Tolerant of a missing or unreadable file.
+pub fn persisted_backend_was_classic(app_handle: &AppHandle) -> bool {
+    let Ok(path) = Settings::settings_path(app_handle) else {
+        return false;
+    };
+    let Ok(content) = std::fs::read_to_string(&path) else {
+        return false;
+    };
+    serde_json::from_str::<serde_json::Value>(&content)
+        .ok()
+        .and_then(|v| {
+            v.get("backend")
+                .and_then(|b| b.as_str())
+                .map(|b| b == "classic")
+        })
+        .unwrap_or(false)
+}
@@ -0,0 +1,82 @@
+//! Defaults for new device configs, and how devices are found.
+
+use serde::{Deserialize, Serialize};
+
+/// What `esphome-desktop device new` starts a config with, in place of
+/// ESPHome's generic defaults.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NewDevicePreset {
+    /// Chip family and the config block it gets: `esp32`, `esp8266`,
+    /// `rp2040`, `bk72xx`, `rtl87xx` or `ln882x`
+    pub platform: String,
+    /// Board id, e.g. `esp32-c3-devkitm-1`
+    pub board: String,
+    /// `arduino` or `esp-idf`, for `esp32` (None = ESPHome's default)
+    pub framework: Option<String>,
+    /// Name in `secrets.yaml` of the Wi-Fi network
+    pub wifi_ssid_secret: String,
+    /// Name in `secrets.yaml` of the Wi-Fi password
+    pub wifi_password_secret: String,
+    /// Give the API a fresh encryption key
+    pub api_encryption: bool,
+}
+
+impl Default for NewDevicePreset {
+    fn default() -> Self {
+        Self {
+            platform: "esp32".into(),
+            board: "esp32dev".into(),
+            framework: None,
+            wifi_ssid_secret: "wifi_ssid".into(),
+            wifi_password_secret: "wifi_password".into(),
+            api_encryption: true,
+        }
+    }
+}
+
+/// How the app finds devices on the network and how often it probes them
+/// (`monitor_latency`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiscoverySettings {
+    /// Seconds between probe rounds; anything under 30 counts as 30
+    pub interval_secs: u64,
+    /// IPv4 subnets to probe devices in, e.g. `192.168.1.0/24`, leaving out
+    /// devices that resolve elsewhere (a VPN, a guest network); empty = any
+    pub subnets: Vec<String>,
+    /// Addresses by device name, tried when the device's `<name>.local`
+    /// doesn't resolve over mDNS
+    pub static_addresses: std::collections::BTreeMap<String, String>,
+}
+
+impl Default for DiscoverySettings {
+    fn default() -> Self {
+        Self {
+            interval_secs: 300,
+            subnets: Vec::new(),
+            static_addresses: Default::default(),
+        }
+    }
+}
+
+/// Deserialize the discovery settings, falling back to the defaults for
+/// malformed ones (same policy as [`deserialize_new_device`]).
+pub(super) fn deserialize_discovery<'de, D>(deserializer: D) -> Result<DiscoverySettings, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let raw = serde_json::Value::deserialize(deserializer)?;
+    Ok(DiscoverySettings::deserialize(raw).unwrap_or_default())
+}
+
+/// Deserialize the new-device preset, falling back to the defaults for a
+/// malformed one (same policy as
+/// [`deserialize_backend`](super::backend::deserialize_backend)).
+pub(super) fn deserialize_new_device<'de, D>(deserializer: D) -> Result<NewDevicePreset, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let raw = serde_json::Value::deserialize(deserializer)?;
+    Ok(NewDevicePreset::deserialize(raw).unwrap_or_default())
+}
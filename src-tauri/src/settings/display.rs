@@ -0,0 +1,145 @@
+//! How the tray menu, the windows and `esphome-desktop logs` look.
+
+use serde::{Deserialize, Serialize};
+
+/// Colours for log levels, in `esphome-desktop logs` on a terminal and in
+/// the Terminal window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LogPalette {
+    /// The usual red/yellow/green terminal colours.
+    #[default]
+    Standard,
+    /// Blue/orange hues that stay distinct with red-green colour blindness;
+    /// errors are bold too, so they never rely on hue alone.
+    Colorblind,
+    /// Bold text on solid backgrounds, readable from across the room.
+    HighContrast,
+    /// Plain text.
+    None,
+}
+
+/// Severity of a log line, as far as its prefix tells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum LogLevel {
+    Debug,
+    Info,
+    Warning,
+    Error,
+}
+
+impl LogLevel {
+    /// Detect the level from Python's level names (`WARNING`, `ERROR`, ...)
+    /// among the first few words, or ESPHome's bracketed device-log tags
+    /// (`[W]`, `[E]`, ...) near the start of the line.
+    pub(crate) fn of(line: &str) -> Option<Self> {
+        for word in line.split_whitespace().take(4) {
+            match word.trim_matches(|c: char| !c.is_ascii_alphabetic()) {
+                "DEBUG" => return Some(Self::Debug),
+                "INFO" => return Some(Self::Info),
+                "WARNING" | "WARN" => return Some(Self::Warning),
+                "ERROR" | "CRITICAL" => return Some(Self::Error),
+                _ => {}
+            }
+        }
+        let head = line.get(..48).unwrap_or(line);
+        [
+            ("[E]", Self::Error),
+            ("[W]", Self::Warning),
+            ("[I]", Self::Info),
+            ("[D]", Self::Debug),
+            ("[V]", Self::Debug),
+        ]
+        .into_iter()
+        .find_map(|(tag, level)| head.contains(tag).then_some(level))
+    }
+}
+
+/// Deserialize the log palette, falling back to [`LogPalette::Standard`] for an
+/// unknown or malformed value (same policy as
+/// [`deserialize_backend`](super::backend::deserialize_backend)).
+pub(super) fn deserialize_log_palette<'de, D>(deserializer: D) -> Result<LogPalette, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let raw = serde_json::Value::deserialize(deserializer)?;
+    Ok(match raw.as_str() {
+        Some("colorblind") => LogPalette::Colorblind,
+        Some("high_contrast") => LogPalette::HighContrast,
+        Some("none") => LogPalette::None,
+        _ => LogPalette::default(),
+    })
+}
+
+/// Default window zoom, in percent.
+pub(crate) const DEFAULT_UI_SCALE: u16 = 100;
+
+/// The window zooms `ui_scale` accepts, in percent.
+pub(crate) const UI_SCALES: std::ops::RangeInclusive<u16> = 50..=300;
+
+/// Deserialize the window zoom, falling back to [`DEFAULT_UI_SCALE`] for a
+/// value out of [`UI_SCALES`] or not a whole number (same policy as
+/// [`deserialize_backend`](super::backend::deserialize_backend)).
+pub(super) fn deserialize_ui_scale<'de, D>(deserializer: D) -> Result<u16, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let raw = serde_json::Value::deserialize(deserializer)?;
+    Ok(raw
+        .as_u64()
+        .and_then(|n| u16::try_from(n).ok())
+        .filter(|n| UI_SCALES.contains(n))
+        .unwrap_or(DEFAULT_UI_SCALE))
+}
+
+/// Which tray menu items show, in what order, and which devices get a
+/// shortcut at the top. Items are named by their menu id (`check_updates`,
+/// `view_logs`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrayMenuLayout {
+    /// Items to leave out
+    pub hidden: Vec<String>,
+    /// Items to put first in their section, in this order; the rest follow
+    /// in the usual order
+    pub order: Vec<String>,
+    /// Devices listed at the top, each opening its config in the editor
+    pub shortcuts: Vec<String>,
+}
+
+/// Deserialize the tray menu layout, falling back to the default menu for a
+/// malformed one (same policy as
+/// [`deserialize_discovery`](super::devices::deserialize_discovery)).
+pub(super) fn deserialize_tray_menu<'de, D>(deserializer: D) -> Result<TrayMenuLayout, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let raw = serde_json::Value::deserialize(deserializer)?;
+    Ok(TrayMenuLayout::deserialize(raw).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_level_from_python_and_device_log_prefixes() {
+        assert_eq!(
+            LogLevel::of("2026-10-15 12:00:00,123 WARNING Something odd"),
+            Some(LogLevel::Warning)
+        );
+        assert_eq!(LogLevel::of("ERROR: compile failed"), Some(LogLevel::Error));
+        assert_eq!(
+            LogLevel::of("[12:00:01][E][wifi:123]: Connection lost"),
+            Some(LogLevel::Error)
+        );
+        assert_eq!(
+            LogLevel::of("[12:00:01][D][sensor:094]: 21.5"),
+            Some(LogLevel::Debug)
+        );
+        assert_eq!(LogLevel::of("Compiling .pioenvs/foo/src/main.cpp.o"), None);
+        // A level name deep inside the message is not the line's level.
+        assert_eq!(LogLevel::of("Linking the firmware, no ERROR here"), None);
+    }
+}
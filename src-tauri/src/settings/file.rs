@@ -0,0 +1,157 @@
+//! Reading `settings.json` and the section files next to it, and moving a
+//! corrupt one aside so a bad byte resets the preferences instead of stopping
+//! the app from starting.
+
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, warn};
+
+use super::Settings;
+use crate::util::ConfigStore;
+
+/// Read and parse the settings file at `path`, recovering gracefully from a
+/// missing or corrupt file.
+///
+/// - File absent → defaults (the first-run case).
+/// - File present and valid → the parsed settings.
+/// - File present but unreadable or unparseable (truncated by a torn write
+///   from a pre-atomic-write build, a bad hand-edit, or on-disk corruption) →
+///   the bad file is moved aside to `<name>.corrupt` and defaults are
+///   returned. This deliberately favors a working app with reset preferences
+///   over a non-starting one.
+///
+/// The workspace and notification settings come from their own files, see
+/// [`load_section`].
+///
+/// Does not populate `installed_version`; that requires an `AppHandle` and is
+/// filled in by the caller.
+pub(super) fn load_settings_file(path: &Path) -> Settings {
+    let mut settings = load_main_file(path);
+    settings.workspace = load_section(path);
+    settings.notifications = load_section(path);
+    settings
+}
+
+fn load_main_file(path: &Path) -> Settings {
+    // Branch on the read result rather than pre-checking `exists()`:
+    // `Path::exists()` returns `false` for any stat failure (e.g. a permission
+    // error), which would misclassify an unreadable file as first-run and skip
+    // the backup path. `NotFound` is the only genuine first-run case; every
+    // other read error is treated as a corrupt/unreadable file.
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            info!("No settings file found, using defaults");
+            return Settings::default();
+        }
+        Err(e) => {
+            warn!(
+                "Settings file {:?} is unreadable ({}); backing it up and using defaults",
+                path, e
+            );
+            back_up_corrupt_settings(path);
+            return Settings::default();
+        }
+    };
+
+    match serde_json::from_str::<Settings>(&content) {
+        Ok(settings) => {
+            debug!("Loaded settings from {:?}", path);
+            settings
+        }
+        Err(e) => {
+            warn!(
+                "Settings file {:?} is corrupt ({}); backing it up and using defaults",
+                path, e
+            );
+            back_up_corrupt_settings(path);
+            Settings::default()
+        }
+    }
+}
+
+/// Read-only settings peek for out-of-process readers (the CLI client's
+/// offline `status`). Unlike [`load_settings_file`] this never touches disk
+/// beyond the read: backing a corrupt file aside is the running app's recovery
+/// decision to make, not a bystander's, so a missing or unparseable file is
+/// simply `None`.
+pub(crate) fn peek_settings_file(path: &Path) -> Option<Settings> {
+    let content = std::fs::read_to_string(path).ok()?;
+    let mut settings: Settings = serde_json::from_str(&content).ok()?;
+    settings.workspace = peek_section(path);
+    settings.notifications = peek_section(path);
+    Some(settings)
+}
+
+/// Load the section kept in its own file next to the settings file at
+/// `settings_path`. A missing file is read from the fields `settings.json`
+/// held before the split; a corrupt one (or one from a newer build) is moved
+/// aside like `settings.json` and the defaults used.
+fn load_section<T: ConfigStore>(settings_path: &Path) -> T {
+    let path = section_path::<T>(settings_path);
+    if let Ok(false) = path.try_exists() {
+        return legacy_section(settings_path);
+    }
+    T::load(&path).unwrap_or_else(|e| {
+        warn!("{:#}; backing it up and using defaults", e);
+        back_up_corrupt_settings(&path);
+        T::default()
+    })
+}
+
+/// [`load_section`] for [`peek_settings_file`]: a bad file is just the
+/// defaults, left where it is.
+fn peek_section<T: ConfigStore>(settings_path: &Path) -> T {
+    let path = section_path::<T>(settings_path);
+    match path.try_exists() {
+        Ok(false) => legacy_section(settings_path),
+        _ => T::load(&path).unwrap_or_default(),
+    }
+}
+
+fn section_path<T: ConfigStore>(settings_path: &Path) -> PathBuf {
+    T::path(settings_path.parent().unwrap_or(Path::new(".")))
+}
+
+/// The section as `settings.json` held it, its fields among the others.
+fn legacy_section<T: ConfigStore>(settings_path: &Path) -> T {
+    std::fs::read_to_string(settings_path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Move a corrupt settings file aside to `<name>.corrupt` so it isn't silently
+/// overwritten by the next save, preserving it for recovery/debugging.
+///
+/// Best-effort: if the rename fails we just log and proceed with defaults, and
+/// the bad file will be overwritten on the next successful save.
+///
+/// If `<name>.corrupt` already exists from a prior recovery, a numbered suffix
+/// (`<name>.corrupt.1`, `.2`, ...) is chosen so an earlier backup is not lost
+/// (Unix `rename` overwrites) or the rename made to fail (Windows `rename`
+/// errors on an existing destination).
+fn back_up_corrupt_settings(path: &Path) {
+    let backup = unique_backup_path(path);
+    match std::fs::rename(path, &backup) {
+        Ok(()) => warn!("Moved corrupt settings file to {:?}", backup),
+        Err(e) => warn!("Failed to back up corrupt settings file {:?}: {}", path, e),
+    }
+}
+
+/// Pick a backup path that does not already exist, starting at `<name>.corrupt`
+/// and falling back to `<name>.corrupt.N` for the first free `N`.
+fn unique_backup_path(path: &Path) -> PathBuf {
+    let base = path.with_extension("json.corrupt");
+    if !base.exists() {
+        return base;
+    }
+    let mut n = 1u32;
+    loop {
+        let candidate = base.with_extension(format!("corrupt.{n}"));
+        if !candidate.exists() {
+            return candidate;
+        }
+        n += 1;
+    }
+}
@@ -0,0 +1,99 @@
+//! Deserializers for numbers and addresses that fall back rather than fail,
+//! so a bad hand-edit of one value doesn't reset every other preference.
+
+use serde::Deserialize;
+use std::net::IpAddr;
+
+use super::{default_listen_address, DEFAULT_LANGUAGE_SERVER_PORT, DEFAULT_PORT};
+
+/// Deserialize the dashboard port, falling back to the default for a zero,
+/// out-of-range, or non-numeric value.
+///
+/// Port `0` is the dangerous case: a server reads it as "pick any free
+/// ephemeral port," but this app uses the configured value verbatim for the
+/// health check (`daemon::probe_url`) and the dashboard URL it opens, never
+/// the port the backend actually bound. A persisted `{"port": 0}` (hand-edited
+/// file) would therefore leave the dashboard permanently unreachable with no
+/// visible error. A non-number (null, string, bool from a hand-edited or future
+/// file) likewise falls back here rather than failing the whole parse and
+/// discarding every other preference via the corrupt-file recovery path.
+pub(super) fn deserialize_port<'de, D>(deserializer: D) -> Result<u16, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(parse_port(serde_json::Value::deserialize(deserializer)?).unwrap_or(DEFAULT_PORT))
+}
+
+/// Deserialize `listen_address`, falling back to the IPv4 loopback for
+/// anything that isn't an IP address (same policy as [`deserialize_port`]).
+pub(super) fn deserialize_listen_address<'de, D>(deserializer: D) -> Result<IpAddr, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let raw = serde_json::Value::deserialize(deserializer)?;
+    Ok(raw
+        .as_str()
+        .and_then(|s| s.trim().parse().ok())
+        .unwrap_or_else(default_listen_address))
+}
+
+/// Deserialize `language_server_port` like the dashboard port.
+pub(super) fn deserialize_language_server_port<'de, D>(deserializer: D) -> Result<u16, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(parse_port(serde_json::Value::deserialize(deserializer)?)
+        .unwrap_or(DEFAULT_LANGUAGE_SERVER_PORT))
+}
+
+/// Deserialize `port_range`, a `[first, last]` pair of ports, treating
+/// anything else (an empty or reversed range included) as unset.
+pub(super) fn deserialize_port_range<'de, D>(
+    deserializer: D,
+) -> Result<Option<(u16, u16)>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let raw = serde_json::Value::deserialize(deserializer)?;
+    let Some([first, last]) = raw.as_array().map(Vec::as_slice) else {
+        return Ok(None);
+    };
+    Ok(parse_port(first.clone())
+        .zip(parse_port(last.clone()))
+        .filter(|(first, last)| first <= last))
+}
+
+/// A port number other than `0`.
+fn parse_port(raw: serde_json::Value) -> Option<u16> {
+    raw.as_u64()
+        .and_then(|n| u16::try_from(n).ok())
+        .filter(|&port| port != 0)
+}
+
+/// Deserialize an optional count or limit (`build_jobs`, `thermal_limit`,
+/// `stop_timeout`, `ready_timeout`, `share_minutes`), treating anything but a positive integer
+/// (`0`, negative, a string from a hand-edited file) as unset rather than
+/// failing the whole parse.
+pub(super) fn deserialize_positive<'de, D>(deserializer: D) -> Result<Option<u32>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let raw = serde_json::Value::deserialize(deserializer)?;
+    Ok(raw
+        .as_u64()
+        .and_then(|n| u32::try_from(n).ok())
+        .filter(|&n| n > 0))
+}
+
+/// Deserialize an hour of the day (`dev_refresh_hour`), treating anything
+/// but 0 to 23 as unset (same policy as [`deserialize_positive`]).
+pub(super) fn deserialize_hour<'de, D>(deserializer: D) -> Result<Option<u8>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let raw = serde_json::Value::deserialize(deserializer)?;
+    Ok(raw
+        .as_u64()
+        .and_then(|n| u8::try_from(n).ok())
+        .filter(|&n| n < 24))
+}
@@ -4,18 +4,57 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::fmt;
-use std::io::ErrorKind;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::path::{Path, PathBuf};
 use tauri::AppHandle;
-use tracing::{debug, info, warn};
+use tracing::{debug, info};
 
 use crate::platform;
+use crate::util::ConfigStore;
+
+mod backend;
+mod devices;
+mod display;
+mod file;
+mod lenient;
+mod network;
+mod notify;
+mod policy;
+mod workspace;
+
+pub use backend::{persisted_backend_was_classic, Backend, ReleaseChannel, Runtime};
+pub use devices::{DiscoverySettings, NewDevicePreset};
+pub(crate) use display::{LogLevel, DEFAULT_UI_SCALE, UI_SCALES};
+pub use display::{LogPalette, TrayMenuLayout};
+use file::load_settings_file;
+pub(crate) use file::peek_settings_file;
+pub use network::ProxySettings;
+pub use notify::{DigestInterval, EmailDigest, NotificationSettings, PushTarget};
+pub use policy::{Policy, KIOSK_REFUSAL};
+pub use workspace::WorkspaceSettings;
+
+use backend::{deserialize_backend, deserialize_runtime};
+use devices::deserialize_discovery;
+use display::{deserialize_log_palette, deserialize_tray_menu, deserialize_ui_scale};
+use lenient::{
+    deserialize_hour, deserialize_language_server_port, deserialize_listen_address,
+    deserialize_port, deserialize_port_range, deserialize_positive,
+};
+use network::{
+    deserialize_ca_bundle, deserialize_ca_certificates, deserialize_index_url, deserialize_proxy,
+};
 
 /// Default dashboard port
 const DEFAULT_PORT: u16 = 6052;
 
-/// Default config directory used when `Settings.config_dir` is unset:
+/// Default language server port
+const DEFAULT_LANGUAGE_SERVER_PORT: u16 = 6055;
+
+/// Default command palette shortcut: E for ESPHome, with every modifier so
+/// no application's own shortcut is taken
+const DEFAULT_PALETTE_SHORTCUT: &str = "CommandOrControl+Alt+Shift+E";
+
+/// Default config directory used when `workspace.config_dir` is unset:
 /// `~/esphome`, or a cwd-relative `esphome` in the degenerate case where the
 /// home directory cannot be resolved (unusual service/container setups).
 /// Shared by the daemon and the CLI's offline `status` so the two can never
@@ -26,120 +65,6 @@ pub(crate) fn default_config_dir() -> PathBuf {
         .unwrap_or_else(|| PathBuf::from("esphome"))
 }
 
-/// ESPHome release channel
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
-#[serde(rename_all = "lowercase")]
-pub enum ReleaseChannel {
-    /// Latest stable release from PyPI
-    #[default]
-    Stable,
-    /// Latest beta/pre-release from PyPI
-    Beta,
-    /// Latest development build from GitHub (no auto-updates)
-    Dev,
-}
-
-impl fmt::Display for ReleaseChannel {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Self::Stable => write!(f, "Stable"),
-            Self::Beta => write!(f, "Beta"),
-            Self::Dev => write!(f, "Dev"),
-        }
-    }
-}
-
-/// Which device-builder channel the daemon should run. The classic ESPHome
-/// dashboard backend was removed in line with ESPHome 2026.6.0 retiring the
-/// legacy in-tree dashboard; the daemon now always launches the device builder.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
-#[serde(rename_all = "snake_case")]
-pub enum Backend {
-    /// ESPHome device builder, stable release from PyPI.
-    ///
-    /// This is the default so a fresh install (and any legacy/unknown/corrupt
-    /// settings that fall back here) matches the stable-by-default
-    /// [`ReleaseChannel`]: a user on the stable channel must not be silently
-    /// offered a beta device-builder update (#241). Beta is opt-in via the tray
-    /// or the `backend beta` CLI subcommand.
-    #[default]
-    BuilderStable,
-    /// ESPHome device builder, beta/pre-release from PyPI
-    BuilderBeta,
-}
-
-impl fmt::Display for Backend {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Self::BuilderStable => write!(f, "ESPHome Device Builder (stable)"),
-            Self::BuilderBeta => write!(f, "ESPHome Device Builder (beta)"),
-        }
-    }
-}
-
-/// Deserialize the backend, tolerating legacy or unknown values by falling back
-/// to the default. An old settings file selecting the removed classic dashboard
-/// (`"backend": "classic"`) must migrate to the default device builder rather
-/// than failing the whole parse, which would discard every other preference via
-/// the corrupt-file recovery path.
-fn deserialize_backend<'de, D>(deserializer: D) -> Result<Backend, D::Error>
-where
-    D: serde::Deserializer<'de>,
-{
-    // Deserialize into a generic value so a non-string `backend` (null, number,
-    // bool from a hand-edited or future file) falls back to the default too,
-    // rather than failing the whole parse and discarding every other preference.
-    let raw = serde_json::Value::deserialize(deserializer)?;
-    Ok(match raw.as_str() {
-        Some("builder_stable") => Backend::BuilderStable,
-        Some("builder_beta") => Backend::BuilderBeta,
-        _ => Backend::default(),
-    })
-}
-
-/// Deserialize the dashboard port, falling back to the default for a zero,
-/// out-of-range, or non-numeric value.
-///
-/// Port `0` is the dangerous case: a server reads it as "pick any free
-/// ephemeral port," but this app uses the configured value verbatim for the
-/// health check (`daemon::loopback_url`) and the dashboard URL it opens, never
-/// the port the backend actually bound. A persisted `{"port": 0}` (hand-edited
-/// file) would therefore leave the dashboard permanently unreachable with no
-/// visible error. A non-number (null, string, bool from a hand-edited or future
-/// file) likewise falls back here rather than failing the whole parse and
-/// discarding every other preference via the corrupt-file recovery path.
-fn deserialize_port<'de, D>(deserializer: D) -> Result<u16, D::Error>
-where
-    D: serde::Deserializer<'de>,
-{
-    let raw = serde_json::Value::deserialize(deserializer)?;
-    let port = raw
-        .as_u64()
-        .and_then(|n| u16::try_from(n).ok())
-        .unwrap_or(0);
-    Ok(if port == 0 { DEFAULT_PORT } else { port })
-}
-
-/// Returns true if the persisted settings file selects the removed classic
-/// dashboard backend. Used at startup to force a fresh bundled device builder
-/// for users migrating off classic. Tolerant of a missing or unreadable file.
-pub fn persisted_backend_was_classic(app_handle: &AppHandle) -> bool {
-    let Ok(path) = Settings::settings_path(app_handle) else {
-        return false;
-    };
-    let Ok(content) = std::fs::read_to_string(&path) else {
-        return false;
-    };
-    serde_json::from_str::<serde_json::Value>(&content)
-        .ok()
-        .and_then(|v| {
-            v.get("backend")
-                .and_then(|b| b.as_str())
-                .map(|b| b == "classic")
-        })
-        .unwrap_or(false)
-}
-
 /// Application settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Settings {
@@ -147,9 +72,23 @@ pub struct Settings {
     #[serde(default = "default_port", deserialize_with = "deserialize_port")]
     pub port: u16,
 
-    /// Custom config directory (None = use default)
+    /// Ports to move the dashboard to, first to last inclusive, when `port`
+    /// is taken at launch (None = the ten after it)
+    #[serde(default, deserialize_with = "deserialize_port_range")]
+    pub port_range: Option<(u16, u16)>,
+
+    /// Address the dashboard listens on: the IPv4 loopback by default, `::1`
+    /// for the IPv6 one, or an address of this machine to serve the LAN
+    #[serde(
+        default = "default_listen_address",
+        deserialize_with = "deserialize_listen_address"
+    )]
+    pub listen_address: IpAddr,
+
+    /// Friendly name for the dashboard, announced over mDNS when it ends in
+    /// `.local` and added to the hosts file otherwise (see `hostname`)
     #[serde(default)]
-    pub config_dir: Option<PathBuf>,
+    pub dashboard_hostname: Option<String>,
 
     /// Open dashboard in browser when app starts
     #[serde(default = "default_true")]
@@ -165,6 +104,10 @@ pub struct Settings {
     #[serde(default = "default_true")]
     pub check_updates: bool,
 
+    /// Restart the dashboard when the tray icon is middle-clicked
+    #[serde(default = "default_true")]
+    pub middle_click_restart: bool,
+
     /// Release channel (stable, beta, or dev)
     #[serde(default)]
     pub release_channel: ReleaseChannel,
@@ -173,6 +116,204 @@ pub struct Settings {
     #[serde(default, deserialize_with = "deserialize_backend")]
     pub backend: Backend,
 
+    /// Where the device builder runs (native, a WSL2 distro, a container,
+    /// another machine, or an existing dashboard)
+    #[serde(default, deserialize_with = "deserialize_runtime")]
+    pub runtime: Runtime,
+
+    /// WSL2 distro to run the device builder in (None = the default distro).
+    /// Only read when `runtime` is `wsl2`.
+    #[serde(default)]
+    pub wsl_distro: Option<String>,
+
+    /// Image for the `docker` runtime (None = a stock `python` slim image).
+    /// It needs `python3` with `venv`.
+    #[serde(default)]
+    pub docker_image: Option<String>,
+
+    /// Host for the `ssh` runtime: `user@host`, or a `Host` from the SSH
+    /// config.
+    #[serde(default)]
+    pub ssh_host: Option<String>,
+
+    /// Config directory on the `ssh` host, relative to its home (None =
+    /// `esphome`).
+    #[serde(default)]
+    pub ssh_config_dir: Option<String>,
+
+    /// `host:port` of the dashboard the `remote` runtime uses.
+    #[serde(default)]
+    pub remote_address: Option<String>,
+
+    /// `TZ` for the device builder and its compiles, e.g. `Europe/Berlin`
+    /// (None = inherit the app's environment). Some components bake the build
+    /// machine's timezone into the firmware. Python on Windows doesn't read
+    /// an IANA name from `TZ`, so there it only reaches WSL and Docker.
+    #[serde(default)]
+    pub timezone: Option<String>,
+
+    /// Locale (`LANG`/`LC_ALL`) for the device builder and its compiles, e.g.
+    /// `de_DE.UTF-8` (None = inherit the app's environment).
+    #[serde(default)]
+    pub locale: Option<String>,
+
+    /// Log-level colours for `esphome-desktop logs`
+    #[serde(default, deserialize_with = "deserialize_log_palette")]
+    pub log_palette: LogPalette,
+
+    /// Run the device builder, and so its compiles, at background CPU and
+    /// I/O priority so builds don't make calls and video stutter
+    #[serde(default)]
+    pub low_priority_builds: bool,
+
+    /// Maximum parallel compile jobs (None = one per core), to keep cores
+    /// free while building
+    #[serde(default, deserialize_with = "deserialize_positive")]
+    pub build_jobs: Option<u32>,
+
+    /// CPU temperature in °C above which running builds are paused until the
+    /// CPU cools down (None = off). Linux only.
+    #[serde(default, deserialize_with = "deserialize_positive")]
+    pub thermal_limit: Option<u32>,
+
+    /// Seconds a stop gives the dashboard to finish in-flight work before
+    /// escalating (None = 30)
+    #[serde(default, deserialize_with = "deserialize_positive")]
+    pub stop_timeout: Option<u32>,
+
+    /// Seconds a launch waits for the dashboard before saying it is still
+    /// starting (None = adaptive, see `startup::ready_timeout`)
+    #[serde(default, deserialize_with = "deserialize_positive")]
+    pub ready_timeout: Option<u32>,
+
+    /// Keep the dashboard's address in the app and show a page saying it is
+    /// starting while the backend doesn't answer (see `daemon::proxy`)
+    #[serde(default)]
+    pub startup_page: bool,
+
+    /// Follow the dashboard's request log to show in the tray how long ago it
+    /// was last used, and from which other machines
+    #[serde(default)]
+    pub access_summary: bool,
+
+    /// Command that tunnels the dashboard for "Share Dashboard Temporarily"
+    /// and prints the public URL; `{port}` marks the dashboard port (see
+    /// `share`)
+    #[serde(default)]
+    pub share_command: Option<String>,
+
+    /// SSH host that "Share Dashboard Temporarily" forwards the dashboard
+    /// port to, when `share_command` is unset
+    #[serde(default)]
+    pub share_ssh_host: Option<String>,
+
+    /// Minutes a share stays open (None = 60)
+    #[serde(default, deserialize_with = "deserialize_positive")]
+    pub share_minutes: Option<u32>,
+
+    /// Watch the clipboard for ESPHome YAML snippets and offer them from
+    /// the tray
+    #[serde(default)]
+    pub clipboard_snippets: bool,
+
+    /// Watch shared package files and offer to check the devices using one
+    /// when it changes
+    #[serde(default)]
+    pub check_shared_packages: bool,
+
+    /// On the dev channel, reinstall the latest dev snapshot every day at
+    /// this hour, UTC (None = off)
+    #[serde(default, deserialize_with = "deserialize_hour")]
+    pub dev_refresh_hour: Option<u8>,
+
+    /// Ask for OS authentication (Windows Hello, Touch ID/password, polkit)
+    /// before updating or switching channels, on machines several people use
+    #[serde(default)]
+    pub require_auth: bool,
+
+    /// Run the device builder with reduced privileges (a scrubbed
+    /// environment, plus Landlock on Linux and job limits on Windows). Off is
+    /// the escape hatch for setups the confinement breaks
+    #[serde(default = "default_true")]
+    pub sandbox_backend: bool,
+
+    /// Serve ESPHome diagnostics to editors over the Language Server
+    /// Protocol on `language_server_port` (see `language_server`)
+    #[serde(default)]
+    pub language_server: bool,
+
+    /// Loopback port of the language server
+    #[serde(
+        default = "default_language_server_port",
+        deserialize_with = "deserialize_language_server_port"
+    )]
+    pub language_server_port: u16,
+
+    /// Check the Wi-Fi networks in range every few minutes for a device's
+    /// fallback hotspot, and notify when one appears
+    #[serde(default = "default_true")]
+    pub watch_fallback_hotspots: bool,
+
+    /// Probe every device every few minutes and record how fast and how
+    /// reliably it answers (see `device latency`)
+    #[serde(default)]
+    pub monitor_latency: bool,
+
+    /// Probe interval, subnets and fallback addresses for device discovery
+    #[serde(default, deserialize_with = "deserialize_discovery")]
+    pub discovery: DiscoverySettings,
+
+    /// Zoom of the app's windows, in percent (50 to 300)
+    #[serde(
+        default = "default_ui_scale",
+        deserialize_with = "deserialize_ui_scale"
+    )]
+    pub ui_scale: u16,
+
+    /// Reopen at launch the windows that were open when the app quit
+    #[serde(default)]
+    pub restore_windows: bool,
+
+    /// Hidden, reordered and added tray menu items
+    #[serde(default, deserialize_with = "deserialize_tray_menu")]
+    pub tray_menu: TrayMenuLayout,
+
+    /// Global shortcut opening the command palette, in the form
+    /// `CommandOrControl+Alt+Shift+E` (None = none)
+    #[serde(default = "default_palette_shortcut")]
+    pub palette_shortcut: Option<String>,
+
+    /// HTTP proxy for update checks, notifications posted to ntfy or Gotify,
+    /// and the backend's and pip's downloads (None = connect directly)
+    #[serde(default, deserialize_with = "deserialize_proxy")]
+    pub proxy: Option<ProxySettings>,
+
+    /// PEM files of certificate authorities to trust besides the usual ones,
+    /// for a network that intercepts TLS
+    #[serde(default, deserialize_with = "deserialize_ca_certificates")]
+    pub ca_certificates: Vec<PathBuf>,
+
+    /// A complete PEM bundle of the certificate authorities to trust instead
+    /// of the usual ones (None = the usual ones)
+    #[serde(default, deserialize_with = "deserialize_ca_bundle")]
+    pub ca_bundle: Option<PathBuf>,
+
+    /// pip's index on a mirror of PyPI, e.g.
+    /// `https://mirror.example.com/pypi/simple`, for networks that block
+    /// pypi.org (None = PyPI)
+    #[serde(default, deserialize_with = "deserialize_index_url")]
+    pub index_url: Option<String>,
+
+    /// The config folder, editor and new-device defaults, kept in
+    /// `workspace.json`
+    #[serde(skip)]
+    pub workspace: WorkspaceSettings,
+
+    /// The pause, digests, hooks and push target, kept in
+    /// `notifications.json`
+    #[serde(skip)]
+    pub notifications: NotificationSettings,
+
     /// Installed ESPHome version (detected from venv)
     #[serde(skip)]
     pub installed_version: Option<String>,
@@ -182,6 +323,22 @@ fn default_port() -> u16 {
     DEFAULT_PORT
 }
 
+fn default_listen_address() -> IpAddr {
+    Ipv4Addr::LOCALHOST.into()
+}
+
+fn default_language_server_port() -> u16 {
+    DEFAULT_LANGUAGE_SERVER_PORT
+}
+
+fn default_ui_scale() -> u16 {
+    DEFAULT_UI_SCALE
+}
+
+fn default_palette_shortcut() -> Option<String> {
+    Some(DEFAULT_PALETTE_SHORTCUT.to_string())
+}
+
 fn default_true() -> bool {
     true
 }
@@ -190,18 +347,70 @@ impl Default for Settings {
     fn default() -> Self {
         Self {
             port: DEFAULT_PORT,
-            config_dir: None,
+            port_range: None,
+            listen_address: default_listen_address(),
+            dashboard_hostname: None,
             open_on_start: true,
             launch_at_startup: true,
             check_updates: true,
+            middle_click_restart: true,
             release_channel: ReleaseChannel::default(),
             backend: Backend::default(),
+            runtime: Runtime::default(),
+            wsl_distro: None,
+            docker_image: None,
+            ssh_host: None,
+            ssh_config_dir: None,
+            remote_address: None,
+            timezone: None,
+            locale: None,
+            log_palette: LogPalette::default(),
+            low_priority_builds: false,
+            build_jobs: None,
+            thermal_limit: None,
+            stop_timeout: None,
+            ready_timeout: None,
+            startup_page: false,
+            access_summary: false,
+            share_command: None,
+            share_ssh_host: None,
+            share_minutes: None,
+            clipboard_snippets: false,
+            check_shared_packages: false,
+            dev_refresh_hour: None,
+            require_auth: false,
+            sandbox_backend: true,
+            language_server: false,
+            language_server_port: DEFAULT_LANGUAGE_SERVER_PORT,
+            watch_fallback_hotspots: true,
+            monitor_latency: false,
+            discovery: DiscoverySettings::default(),
+            ui_scale: DEFAULT_UI_SCALE,
+            restore_windows: false,
+            tray_menu: TrayMenuLayout::default(),
+            palette_shortcut: default_palette_shortcut(),
+            proxy: None,
+            ca_certificates: Vec::new(),
+            ca_bundle: None,
+            index_url: None,
+            workspace: WorkspaceSettings::default(),
+            notifications: NotificationSettings::default(),
             installed_version: None,
         }
     }
 }
 
 impl Settings {
+    /// Where the dashboard listens. Always the IPv4 loopback under `wsl2`,
+    /// whose port forwarding only serves that.
+    pub fn dashboard_address(&self) -> SocketAddr {
+        let ip = match self.runtime {
+            Runtime::Wsl2 if cfg!(target_os = "windows") => default_listen_address(),
+            _ => self.listen_address,
+        };
+        SocketAddr::new(ip, self.port)
+    }
+
     /// Load settings from disk, or create defaults.
     ///
     /// A settings file that exists but cannot be read or parsed must NOT abort
@@ -228,122 +437,34 @@ impl Settings {
     /// Save settings to disk
     pub fn save(&self, app_handle: &AppHandle) -> Result<()> {
         let settings_path = Self::settings_path(app_handle)?;
+        self.save_to(&settings_path)?;
+        info!("Settings saved to {:?}", settings_path);
+        Ok(())
+    }
 
-        // Ensure parent directory exists
-        if let Some(parent) = settings_path.parent() {
-            std::fs::create_dir_all(parent).context("Failed to create settings directory")?;
-        }
+    /// Write `settings.json` to `settings_path`, and the workspace and
+    /// notification settings to their files next to it.
+    pub(crate) fn save_to(&self, settings_path: &Path) -> Result<()> {
+        let dir = settings_path
+            .parent()
+            .context("Settings file has no parent directory")?;
+        std::fs::create_dir_all(dir).context("Failed to create settings directory")?;
+
+        // The sections first: if one fails, a `settings.json` from before the
+        // split still holds its fields to migrate from.
+        self.workspace.save(&WorkspaceSettings::path(dir))?;
+        self.notifications.save(&NotificationSettings::path(dir))?;
 
         let content = serde_json::to_string_pretty(self).context("Failed to serialize settings")?;
         // Atomic write: a torn `fs::write` would leave settings.json truncated,
         // failing the next parse and silently resetting every user preference.
-        crate::util::atomic_write(&settings_path, content)
-            .context("Failed to write settings file")?;
-
-        info!("Settings saved to {:?}", settings_path);
-        Ok(())
+        crate::util::atomic_write(settings_path, content).context("Failed to write settings file")
     }
 
     /// Get the path to the settings file
     fn settings_path(app_handle: &AppHandle) -> Result<PathBuf> {
-        let data_dir = platform::get_data_dir(app_handle)?;
-        Ok(data_dir.join("settings.json"))
-    }
-}
-
-/// Read and parse the settings file at `path`, recovering gracefully from a
-/// missing or corrupt file.
-///
-/// - File absent → defaults (the first-run case).
-/// - File present and valid → the parsed settings.
-/// - File present but unreadable or unparseable (truncated by a torn write
-///   from a pre-atomic-write build, a bad hand-edit, or on-disk corruption) →
-///   the bad file is moved aside to `<name>.corrupt` and defaults are
-///   returned. This deliberately favors a working app with reset preferences
-///   over a non-starting one.
-///
-/// Does not populate `installed_version`; that requires an `AppHandle` and is
-/// filled in by the caller.
-fn load_settings_file(path: &Path) -> Settings {
-    // Branch on the read result rather than pre-checking `exists()`:
-    // `Path::exists()` returns `false` for any stat failure (e.g. a permission
-    // error), which would misclassify an unreadable file as first-run and skip
-    // the backup path. `NotFound` is the only genuine first-run case; every
-    // other read error is treated as a corrupt/unreadable file.
-    let content = match std::fs::read_to_string(path) {
-        Ok(content) => content,
-        Err(e) if e.kind() == ErrorKind::NotFound => {
-            info!("No settings file found, using defaults");
-            return Settings::default();
-        }
-        Err(e) => {
-            warn!(
-                "Settings file {:?} is unreadable ({}); backing it up and using defaults",
-                path, e
-            );
-            back_up_corrupt_settings(path);
-            return Settings::default();
-        }
-    };
-
-    match serde_json::from_str::<Settings>(&content) {
-        Ok(settings) => {
-            debug!("Loaded settings from {:?}", path);
-            settings
-        }
-        Err(e) => {
-            warn!(
-                "Settings file {:?} is corrupt ({}); backing it up and using defaults",
-                path, e
-            );
-            back_up_corrupt_settings(path);
-            Settings::default()
-        }
-    }
-}
-
-/// Read-only settings peek for out-of-process readers (the CLI client's
-/// offline `status`). Unlike [`load_settings_file`] this never touches disk
-/// beyond the read: backing a corrupt file aside is the running app's recovery
-/// decision to make, not a bystander's, so a missing or unparseable file is
-/// simply `None`.
-pub(crate) fn peek_settings_file(path: &Path) -> Option<Settings> {
-    let content = std::fs::read_to_string(path).ok()?;
-    serde_json::from_str(&content).ok()
-}
-
-/// Move a corrupt settings file aside to `<name>.corrupt` so it isn't silently
-/// overwritten by the next save, preserving it for recovery/debugging.
-///
-/// Best-effort: if the rename fails we just log and proceed with defaults, and
-/// the bad file will be overwritten on the next successful save.
-///
-/// If `<name>.corrupt` already exists from a prior recovery, a numbered suffix
-/// (`<name>.corrupt.1`, `.2`, ...) is chosen so an earlier backup is not lost
-/// (Unix `rename` overwrites) or the rename made to fail (Windows `rename`
-/// errors on an existing destination).
-fn back_up_corrupt_settings(path: &Path) {
-    let backup = unique_backup_path(path);
-    match std::fs::rename(path, &backup) {
-        Ok(()) => warn!("Moved corrupt settings file to {:?}", backup),
-        Err(e) => warn!("Failed to back up corrupt settings file {:?}: {}", path, e),
-    }
-}
-
-/// Pick a backup path that does not already exist, starting at `<name>.corrupt`
-/// and falling back to `<name>.corrupt.N` for the first free `N`.
-fn unique_backup_path(path: &Path) -> PathBuf {
-    let base = path.with_extension("json.corrupt");
-    if !base.exists() {
-        return base;
-    }
-    let mut n = 1u32;
-    loop {
-        let candidate = base.with_extension(format!("corrupt.{n}"));
-        if !candidate.exists() {
-            return candidate;
-        }
-        n += 1;
+        let config_dir = platform::get_config_dir(app_handle)?;
+        Ok(config_dir.join(platform::SETTINGS_FILE_NAME))
     }
 }
 
@@ -384,20 +505,136 @@ mod tests {
 
         let original = Settings {
             port: 1234,
+            port_range: Some((1235, 1240)),
+            listen_address: "::1".parse().unwrap(),
+            dashboard_hostname: Some("esphome.localhost".into()),
             open_on_start: false,
             release_channel: ReleaseChannel::Beta,
             backend: Backend::BuilderStable,
+            runtime: Runtime::Wsl2,
+            wsl_distro: Some("Ubuntu".into()),
+            timezone: Some("Europe/Berlin".into()),
+            log_palette: LogPalette::HighContrast,
+            low_priority_builds: true,
+            build_jobs: Some(6),
+            thermal_limit: Some(85),
+            stop_timeout: Some(90),
+            ready_timeout: Some(300),
+            startup_page: true,
+            access_summary: true,
+            share_command: Some("bore local {port} --to bore.pub".into()),
+            share_ssh_host: Some("helper@jump.example.com".into()),
+            share_minutes: Some(30),
+            clipboard_snippets: true,
+            check_shared_packages: true,
+            dev_refresh_hour: Some(0),
+            require_auth: true,
+            sandbox_backend: false,
+            language_server: true,
+            language_server_port: 7000,
+            discovery: DiscoverySettings {
+                interval_secs: 60,
+                subnets: vec!["192.168.1.0/24".into()],
+                static_addresses: [("porch".to_string(), "192.168.1.40".to_string())].into(),
+            },
+            tray_menu: TrayMenuLayout {
+                hidden: vec!["check_updates".into()],
+                shortcuts: vec!["porch".into()],
+                ..Default::default()
+            },
+            palette_shortcut: None,
+            proxy: Some(ProxySettings {
+                host: "proxy.example.com".into(),
+                port: 3128,
+                username: Some("me".into()),
+                no_proxy: vec![".corp.example.com".into()],
+            }),
+            ca_certificates: vec![PathBuf::from("/etc/ssl/corp-root.pem")],
+            ca_bundle: Some(PathBuf::from("/etc/ssl/certs/ca-certificates.crt")),
+            index_url: Some("https://mirror.example.com/pypi/simple".into()),
+            workspace: WorkspaceSettings {
+                config_dir: Some(PathBuf::from("/srv/esphome")),
+                editor_command: Some("code --goto".into()),
+                new_device: NewDevicePreset {
+                    board: "esp32-c3-devkitm-1".into(),
+                    framework: Some("esp-idf".into()),
+                    api_encryption: false,
+                    ..Default::default()
+                },
+            },
+            notifications: NotificationSettings {
+                paused_until: Some(1_700_000_000),
+                weekly_summary: true,
+                hooks: [("daemon_started".to_string(), "notify-send up".to_string())].into(),
+                push: Some(PushTarget::Ntfy {
+                    url: "https://ntfy.sh/my-builder".into(),
+                    token: None,
+                }),
+                email_digest: Some(EmailDigest {
+                    every: DigestInterval::Weekly,
+                    to: "me@example.com".into(),
+                    from: None,
+                    smtp_host: "smtp.example.com".into(),
+                    smtp_port: Some(465),
+                    username: Some("me@example.com".into()),
+                }),
+            },
             ..Default::default()
         };
-        let content = serde_json::to_string_pretty(&original).expect("serialize");
-        fs::write(&path, content).expect("write settings");
+        original.save_to(&path).expect("save settings");
 
         let loaded = load_settings_file(&path);
 
         assert_eq!(loaded.port, 1234);
+        assert_eq!(loaded.listen_address, original.listen_address);
+        assert_eq!(loaded.port_range, Some((1235, 1240)));
+        assert_eq!(
+            loaded.dashboard_hostname.as_deref(),
+            Some("esphome.localhost")
+        );
         assert!(!loaded.open_on_start);
         assert_eq!(loaded.release_channel, ReleaseChannel::Beta);
         assert_eq!(loaded.backend, Backend::BuilderStable);
+        assert_eq!(loaded.runtime, Runtime::Wsl2);
+        assert_eq!(loaded.wsl_distro.as_deref(), Some("Ubuntu"));
+        assert_eq!(loaded.timezone.as_deref(), Some("Europe/Berlin"));
+        assert_eq!(loaded.locale, None);
+        assert_eq!(loaded.log_palette, LogPalette::HighContrast);
+        assert!(loaded.low_priority_builds);
+        assert_eq!(loaded.build_jobs, Some(6));
+        assert_eq!(loaded.thermal_limit, Some(85));
+        assert_eq!(loaded.stop_timeout, Some(90));
+        assert_eq!(loaded.ready_timeout, Some(300));
+        assert!(loaded.startup_page);
+        assert!(loaded.access_summary);
+        assert_eq!(
+            loaded.share_command.as_deref(),
+            Some("bore local {port} --to bore.pub")
+        );
+        assert_eq!(
+            loaded.share_ssh_host.as_deref(),
+            Some("helper@jump.example.com")
+        );
+        assert_eq!(loaded.share_minutes, Some(30));
+        assert!(loaded.clipboard_snippets);
+        assert!(loaded.check_shared_packages);
+        assert_eq!(loaded.dev_refresh_hour, Some(0));
+        assert!(loaded.require_auth);
+        assert!(!loaded.sandbox_backend);
+        assert!(loaded.language_server);
+        assert_eq!(loaded.language_server_port, 7000);
+        assert_eq!(loaded.discovery.interval_secs, 60);
+        assert_eq!(loaded.discovery.static_addresses["porch"], "192.168.1.40");
+        assert_eq!(loaded.tray_menu.hidden, ["check_updates"]);
+        assert_eq!(loaded.tray_menu.shortcuts, ["porch"]);
+        // Turned off, not reset to the default.
+        assert_eq!(loaded.palette_shortcut, None);
+        assert_eq!(loaded.proxy, original.proxy);
+        assert_eq!(loaded.ca_certificates, original.ca_certificates);
+        assert_eq!(loaded.ca_bundle, original.ca_bundle);
+        assert_eq!(loaded.index_url, original.index_url);
+        assert_eq!(loaded.workspace, original.workspace);
+        assert_eq!(loaded.notifications, original.notifications);
         // A successful parse must not move the file aside.
         assert!(path.exists());
         assert!(!path.with_extension("json.corrupt").exists());
@@ -425,6 +662,74 @@ mod tests {
         let _ = fs::remove_dir_all(&dir);
     }
 
+    #[test]
+    fn fields_from_before_the_split_move_to_their_files() {
+        // Workspace and notification settings used to sit in settings.json
+        // itself; the first load after the split must pick them up from there
+        // and the next save move them out.
+        let dir = unique_temp_dir("split");
+        let path = dir.join("settings.json");
+        fs::write(
+            &path,
+            r#"{"port":1234,"config_dir":"/srv/esphome","editor_command":"subl",
+                "notifications_paused_until":1700000000,"weekly_summary":true,
+                "hooks":{"daemon_started":"notify-send up"}}"#,
+        )
+        .expect("write settings");
+
+        let loaded = load_settings_file(&path);
+        assert_eq!(loaded.port, 1234);
+        assert_eq!(
+            loaded.workspace.config_dir,
+            Some(PathBuf::from("/srv/esphome"))
+        );
+        assert_eq!(loaded.workspace.editor_command.as_deref(), Some("subl"));
+        assert_eq!(loaded.notifications.paused_until, Some(1_700_000_000));
+        assert!(loaded.notifications.weekly_summary);
+        assert_eq!(
+            loaded.notifications.hooks["daemon_started"],
+            "notify-send up"
+        );
+
+        loaded.save_to(&path).expect("save settings");
+        let main: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert!(main.get("config_dir").is_none());
+        assert!(main.get("hooks").is_none());
+        assert!(WorkspaceSettings::path(&dir).exists());
+        assert!(NotificationSettings::path(&dir).exists());
+        let reloaded = load_settings_file(&path);
+        assert_eq!(reloaded.workspace, loaded.workspace);
+        assert_eq!(reloaded.notifications, loaded.notifications);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn corrupt_section_file_recovers_to_defaults_and_is_backed_up() {
+        // A bad notifications.json costs the notification settings, not the
+        // rest, and is kept aside like a bad settings.json.
+        let dir = unique_temp_dir("corrupt-section");
+        let path = dir.join("settings.json");
+        fs::write(&path, r#"{"port":1234}"#).expect("write settings");
+        let section = NotificationSettings::path(&dir);
+        fs::write(&section, "{ not json").expect("write section");
+
+        let loaded = load_settings_file(&path);
+
+        assert_eq!(loaded.port, 1234);
+        assert_eq!(loaded.notifications, NotificationSettings::default());
+        assert!(!section.exists());
+        assert!(section.with_extension("json.corrupt").exists());
+        // The peek leaves a bad file where it is.
+        fs::write(&section, "{ not json").expect("write section");
+        let peeked = peek_settings_file(&path).expect("peek");
+        assert_eq!(peeked.notifications, NotificationSettings::default());
+        assert!(section.exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn zero_port_falls_back_to_default() {
         // A persisted port of 0 would leave the dashboard unreachable: the app
@@ -472,6 +777,132 @@ mod tests {
         }
     }
 
+    #[test]
+    fn non_positive_or_non_numeric_build_jobs_are_unset() {
+        for body in [
+            r#"{"build_jobs":0}"#,
+            r#"{"build_jobs":-2}"#,
+            r#"{"build_jobs":"6"}"#,
+        ] {
+            let dir = unique_temp_dir("bad_build_jobs");
+            let path = dir.join("settings.json");
+            fs::write(&path, body).expect("write settings");
+
+            let settings = load_settings_file(&path);
+
+            assert_eq!(settings.build_jobs, None, "body: {body}");
+            assert!(
+                !path.with_extension("json.corrupt").exists(),
+                "body: {body}"
+            );
+
+            let _ = fs::remove_dir_all(&dir);
+        }
+    }
+
+    #[test]
+    fn malformed_ca_certificates_keep_only_the_paths() {
+        for (body, expected) in [
+            (
+                r#"{"ca_certificates":["/etc/ssl/corp.pem",3]}"#,
+                vec!["/etc/ssl/corp.pem"],
+            ),
+            (r#"{"ca_certificates":"/etc/ssl/corp.pem"}"#, vec![]),
+            (r#"{"ca_certificates":null}"#, vec![]),
+        ] {
+            let dir = unique_temp_dir("bad_ca_certificates");
+            let path = dir.join("settings.json");
+            fs::write(&path, body).expect("write settings");
+
+            let settings = load_settings_file(&path);
+
+            let expected: Vec<PathBuf> = expected.into_iter().map(PathBuf::from).collect();
+            assert_eq!(settings.ca_certificates, expected, "body: {body}");
+            assert!(
+                !path.with_extension("json.corrupt").exists(),
+                "body: {body}"
+            );
+
+            let _ = fs::remove_dir_all(&dir);
+        }
+    }
+
+    #[test]
+    fn non_string_ca_bundle_is_unset() {
+        for (body, expected) in [
+            (
+                r#"{"ca_bundle":"/etc/ssl/corp-bundle.pem"}"#,
+                Some("/etc/ssl/corp-bundle.pem"),
+            ),
+            (r#"{"ca_bundle":["/etc/ssl/corp-bundle.pem"]}"#, None),
+            (r#"{"ca_bundle":true}"#, None),
+        ] {
+            let dir = unique_temp_dir("bad_ca_bundle");
+            let path = dir.join("settings.json");
+            fs::write(&path, body).expect("write settings");
+
+            let settings = load_settings_file(&path);
+
+            assert_eq!(
+                settings.ca_bundle,
+                expected.map(PathBuf::from),
+                "body: {body}"
+            );
+            assert!(
+                !path.with_extension("json.corrupt").exists(),
+                "body: {body}"
+            );
+
+            let _ = fs::remove_dir_all(&dir);
+        }
+    }
+
+    #[test]
+    fn non_string_index_url_is_unset() {
+        for body in [
+            r#"{"index_url":42}"#,
+            r#"{"index_url":{"url":"https://mirror.example.com/simple"}}"#,
+        ] {
+            let dir = unique_temp_dir("bad_index_url");
+            let path = dir.join("settings.json");
+            fs::write(&path, body).expect("write settings");
+
+            let settings = load_settings_file(&path);
+
+            assert_eq!(settings.index_url, None, "body: {body}");
+            assert!(
+                !path.with_extension("json.corrupt").exists(),
+                "body: {body}"
+            );
+
+            let _ = fs::remove_dir_all(&dir);
+        }
+    }
+
+    #[test]
+    fn out_of_range_or_non_numeric_ui_scale_falls_back_to_default() {
+        for (body, expected) in [
+            (r#"{"ui_scale":150}"#, 150),
+            (r#"{"ui_scale":20}"#, DEFAULT_UI_SCALE),
+            (r#"{"ui_scale":1.5}"#, DEFAULT_UI_SCALE),
+            (r#"{"ui_scale":"150"}"#, DEFAULT_UI_SCALE),
+        ] {
+            let dir = unique_temp_dir("bad_ui_scale");
+            let path = dir.join("settings.json");
+            fs::write(&path, body).expect("write settings");
+
+            let settings = load_settings_file(&path);
+
+            assert_eq!(settings.ui_scale, expected, "body: {body}");
+            assert!(
+                !path.with_extension("json.corrupt").exists(),
+                "body: {body}"
+            );
+
+            let _ = fs::remove_dir_all(&dir);
+        }
+    }
+
     #[test]
     fn non_string_backend_value_falls_back_to_default() {
         // A malformed (non-string) backend value must fall back to the default
@@ -491,6 +922,42 @@ mod tests {
         let _ = fs::remove_dir_all(&dir);
     }
 
+    #[test]
+    fn unknown_runtime_value_falls_back_to_native() {
+        // A typo'd or future runtime must not discard every other preference
+        // via corrupt-file recovery; it just runs natively.
+        let dir = unique_temp_dir("bad_runtime");
+        let path = dir.join("settings.json");
+        fs::write(&path, r#"{"port":1234,"runtime":"kubernetes"}"#).expect("write settings");
+
+        let settings = load_settings_file(&path);
+
+        assert_eq!(settings.runtime, Runtime::Native);
+        assert_eq!(settings.port, 1234);
+        assert!(!path.with_extension("json.corrupt").exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn every_runtime_round_trips() {
+        for runtime in [
+            Runtime::Native,
+            Runtime::Wsl2,
+            Runtime::Docker,
+            Runtime::Ssh,
+            Runtime::Remote,
+        ] {
+            let json = serde_json::to_string(&Settings {
+                runtime,
+                ..Settings::default()
+            })
+            .expect("serialize");
+            let loaded: Settings = serde_json::from_str(&json).expect("deserialize");
+            assert_eq!(loaded.runtime, runtime);
+        }
+    }
+
     #[test]
     fn corrupt_file_recovers_to_defaults_and_is_backed_up() {
         let dir = unique_temp_dir("corrupt");
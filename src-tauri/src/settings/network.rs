@@ -0,0 +1,69 @@
+//! How the app reaches the internet (see [`crate::network`]).
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// The HTTP proxy the app, the backend and pip reach the internet through
+/// (`proxy`). The password is in the OS keychain, stored with
+/// `proxy-password`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProxySettings {
+    /// Proxy server, e.g. `proxy.example.com`
+    pub host: String,
+    /// Proxy port, e.g. `3128`
+    pub port: u16,
+    /// User to log in as (None = no login)
+    #[serde(default)]
+    pub username: Option<String>,
+    /// Hosts reached directly rather than through the proxy, as in
+    /// `NO_PROXY`, e.g. `.corp.example.com`; this machine always is
+    #[serde(default)]
+    pub no_proxy: Vec<String>,
+}
+
+/// Deserialize the proxy, leaving it off for a malformed one (same policy as
+/// [`deserialize_push`](super::notify::deserialize_push)).
+pub(super) fn deserialize_proxy<'de, D>(deserializer: D) -> Result<Option<ProxySettings>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let raw = serde_json::Value::deserialize(deserializer)?;
+    Ok(Option::<ProxySettings>::deserialize(raw).unwrap_or_default())
+}
+
+/// Deserialize the extra CA certificate files, keeping the paths among a
+/// malformed list and none for a value that isn't a list (same policy as
+/// [`deserialize_proxy`]).
+pub(super) fn deserialize_ca_certificates<'de, D>(deserializer: D) -> Result<Vec<PathBuf>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let raw = serde_json::Value::deserialize(deserializer)?;
+    let serde_json::Value::Array(entries) = raw else {
+        return Ok(Vec::new());
+    };
+    Ok(entries
+        .into_iter()
+        .filter_map(|entry| PathBuf::deserialize(entry).ok())
+        .collect())
+}
+
+/// Deserialize the CA bundle, trusting the usual roots for a malformed one
+/// (same policy as [`deserialize_proxy`]).
+pub(super) fn deserialize_ca_bundle<'de, D>(deserializer: D) -> Result<Option<PathBuf>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let raw = serde_json::Value::deserialize(deserializer)?;
+    Ok(Option::<PathBuf>::deserialize(raw).unwrap_or_default())
+}
+
+/// Deserialize the PyPI mirror, installing from PyPI for a malformed one
+/// (same policy as [`deserialize_proxy`]).
+pub(super) fn deserialize_index_url<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let raw = serde_json::Value::deserialize(deserializer)?;
+    Ok(Option::<String>::deserialize(raw).unwrap_or_default())
+}
@@ -0,0 +1,130 @@
+//! Where the app's notifications go besides the desktop: push services, the
+//! email digest and hook commands, kept with the pause and the weekly summary
+//! in `notifications.json` next to `settings.json`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::util::ConfigStore;
+
+/// The pause, the digests and where else notifications go.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NotificationSettings {
+    /// Unix time (seconds) notifications are paused until, set from the tray
+    /// (None = not paused). "Until restart" pauses are not persisted.
+    #[serde(default, alias = "notifications_paused_until")]
+    pub paused_until: Option<u64>,
+
+    /// Post a weekly digest of updates, builds, offline devices, failing
+    /// configs and cache disk use
+    #[serde(default)]
+    pub weekly_summary: bool,
+
+    /// Commands to run on the app's events, by event name, e.g.
+    /// `compile_failed` (see `hooks`)
+    #[serde(default, deserialize_with = "deserialize_hooks")]
+    pub hooks: BTreeMap<String, String>,
+
+    /// ntfy topic or Gotify server to post notifications to as well, for
+    /// machines nobody watches (None = off)
+    #[serde(default, deserialize_with = "deserialize_push")]
+    pub push: Option<PushTarget>,
+
+    /// Mail a daily or weekly digest of the dashboard's health, updates and
+    /// failing configs (None = off)
+    #[serde(default, deserialize_with = "deserialize_email_digest")]
+    pub email_digest: Option<EmailDigest>,
+}
+
+impl ConfigStore for NotificationSettings {
+    const FILE_NAME: &'static str = "notifications.json";
+    const WHAT: &'static str = "notification settings";
+}
+
+/// A push notification service the app posts its notifications to as well
+/// (`push`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "service", rename_all = "snake_case")]
+pub enum PushTarget {
+    /// An ntfy topic, by its URL, e.g. `https://ntfy.sh/my-builder`, with an
+    /// access token for a protected one
+    Ntfy {
+        url: String,
+        #[serde(default)]
+        token: Option<String>,
+    },
+    /// A Gotify server, with an application's token
+    Gotify { url: String, token: String },
+}
+
+impl PushTarget {
+    /// The service's name, for the log.
+    pub fn service(&self) -> &'static str {
+        match self {
+            Self::Ntfy { .. } => "ntfy",
+            Self::Gotify { .. } => "Gotify",
+        }
+    }
+}
+
+/// Deserialize the push target, leaving push off for a malformed one (same
+/// policy as [`deserialize_hooks`]).
+fn deserialize_push<'de, D>(deserializer: D) -> Result<Option<PushTarget>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let raw = serde_json::Value::deserialize(deserializer)?;
+    Ok(Option::<PushTarget>::deserialize(raw).unwrap_or_default())
+}
+
+/// How often the email digest goes out.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DigestInterval {
+    #[default]
+    Daily,
+    Weekly,
+}
+
+/// A digest mailed over SMTP (`email_digest`), for a builder nobody sits
+/// at. The password is in the OS keychain, stored with `email-password`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmailDigest {
+    /// `daily` or `weekly`
+    #[serde(default)]
+    pub every: DigestInterval,
+    /// Address to send it to
+    pub to: String,
+    /// Address to send it from (None = `to`)
+    #[serde(default)]
+    pub from: Option<String>,
+    /// SMTP server, e.g. `smtp.example.com`
+    pub smtp_host: String,
+    /// SMTP port: 465 speaks TLS from the start, any other upgrades with
+    /// STARTTLS (None = 587)
+    #[serde(default)]
+    pub smtp_port: Option<u16>,
+    /// User to log in as (None = send without logging in)
+    #[serde(default)]
+    pub username: Option<String>,
+}
+
+/// Deserialize the email digest, leaving it off for a malformed one (same
+/// policy as [`deserialize_push`]).
+fn deserialize_email_digest<'de, D>(deserializer: D) -> Result<Option<EmailDigest>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let raw = serde_json::Value::deserialize(deserializer)?;
+    Ok(Option::<EmailDigest>::deserialize(raw).unwrap_or_default())
+}
+
+/// Deserialize the hooks, dropping them all for a malformed map (same policy
+/// as [`deserialize_tray_menu`](super::display::deserialize_tray_menu)).
+fn deserialize_hooks<'de, D>(deserializer: D) -> Result<BTreeMap<String, String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let raw = serde_json::Value::deserialize(deserializer)?;
+    Ok(BTreeMap::deserialize(raw).unwrap_or_default())
+}
@@ -0,0 +1,115 @@
+//! Machine-wide policy set by an administrator.
+//!
+//! Unlike `settings.json`, which lives in the user's data directory and which
+//! the user (and the app) can rewrite, `policy.json` sits in a system location
+//! only an administrator can write. That makes it the place for switches the
+//! user at the keyboard must not be able to flip, such as `kiosk_mode`.
+//!
+//! The app only ever reads it; there is deliberately no tray item or CLI
+//! subcommand that writes it.
+
+use serde::Deserialize;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// File name of the policy inside [`policy_dir`].
+const POLICY_FILE_NAME: &str = "policy.json";
+
+/// What kiosk mode answers a change with, wherever one is refused: the
+/// control channel, the windows' IPC commands and the offline subcommands.
+pub const KIOSK_REFUSAL: &str =
+    "kiosk mode is on; this machine's administrator has disabled changes";
+
+/// Administrator policy. Every field defaults to the unrestricted behavior, so
+/// a missing file means no policy.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct Policy {
+    /// Read-only tray for a shared machine: only Open Dashboard and status,
+    /// and the control channel, the windows and the CLI refuse anything
+    /// that changes the install.
+    #[serde(default)]
+    pub kiosk_mode: bool,
+}
+
+/// The admin-only directory holding `policy.json`.
+fn policy_dir() -> PathBuf {
+    #[cfg(target_os = "windows")]
+    {
+        let program_data =
+            std::env::var_os("ProgramData").unwrap_or_else(|| "C:\\ProgramData".into());
+        PathBuf::from(program_data).join(crate::platform::BUNDLE_IDENTIFIER)
+    }
+    #[cfg(target_os = "macos")]
+    {
+        PathBuf::from("/Library/Application Support").join(crate::platform::BUNDLE_IDENTIFIER)
+    }
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    {
+        PathBuf::from("/etc/esphome-desktop")
+    }
+}
+
+/// Path of the policy file.
+fn policy_path() -> PathBuf {
+    policy_dir().join(POLICY_FILE_NAME)
+}
+
+impl Policy {
+    /// Load the machine policy. Called once at startup; changing the policy
+    /// takes an app restart.
+    pub fn load() -> Self {
+        let path = policy_path();
+        let policy = load_policy_file(&path);
+        if policy.kiosk_mode {
+            info!("Kiosk mode is on (set in {:?})", path);
+        }
+        policy
+    }
+}
+
+/// Read `path` as a policy. A missing file is no policy. A file that exists
+/// but can't be read or parsed fails closed: an administrator put it there to
+/// lock something down, and a typo must not quietly lift that.
+fn load_policy_file(path: &Path) -> Policy {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Policy::default(),
+        Err(e) => {
+            warn!(
+                "Policy file {:?} is unreadable ({}); assuming kiosk mode",
+                path, e
+            );
+            return Policy { kiosk_mode: true };
+        }
+    };
+    serde_json::from_str(&content).unwrap_or_else(|e| {
+        warn!(
+            "Policy file {:?} is corrupt ({}); assuming kiosk mode",
+            path, e
+        );
+        Policy { kiosk_mode: true }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::unique_temp_dir;
+    use std::fs;
+
+    #[test]
+    fn missing_policy_is_unrestricted_and_a_corrupt_one_locks_down() {
+        let dir = unique_temp_dir("policy");
+        let path = dir.join(POLICY_FILE_NAME);
+        assert_eq!(load_policy_file(&path), Policy::default());
+
+        fs::write(&path, r#"{"kiosk_mode": true, "future_field": 1}"#).unwrap();
+        assert!(load_policy_file(&path).kiosk_mode);
+
+        fs::write(&path, "{ kiosk_mode: yes").unwrap();
+        assert!(load_policy_file(&path).kiosk_mode);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,41 @@
+//! Where the user's configs are and how they're worked on, kept in
+//! `workspace.json` next to `settings.json`.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use super::devices::{deserialize_new_device, NewDevicePreset};
+use crate::util::ConfigStore;
+
+/// The config folder, the editor and the new-device defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkspaceSettings {
+    /// Custom config directory (None = use default)
+    #[serde(default)]
+    pub config_dir: Option<PathBuf>,
+
+    /// Command to open a device's config with, e.g. `subl` or
+    /// `gvim --remote-tab {file}` (None = the first editor found). `{file}`
+    /// marks where the file goes; without it the file is passed last
+    #[serde(default)]
+    pub editor_command: Option<String>,
+
+    /// Defaults for new device configs
+    #[serde(default, deserialize_with = "deserialize_new_device")]
+    pub new_device: NewDevicePreset,
+}
+
+impl WorkspaceSettings {
+    /// The config directory, [`default_config_dir`](super::default_config_dir)
+    /// when none is set.
+    pub fn resolved_config_dir(&self) -> PathBuf {
+        self.config_dir
+            .clone()
+            .unwrap_or_else(super::default_config_dir)
+    }
+}
+
+impl ConfigStore for WorkspaceSettings {
+    const FILE_NAME: &'static str = "workspace.json";
+    const WHAT: &'static str = "workspace settings";
+}
@@ -0,0 +1,274 @@
+//! The "Settings..." window: the dashboard port, the address it listens on,
+//! the config folder, whether the dashboard opens at launch, the windows' UI
+//! scale and `require_auth`, edited in a form rather than in `settings.json`.
+//! The page is `dist/settings.html`.
+//!
+//! Saving applies at once what can be: the UI scale zooms the open windows,
+//! and opening at launch is read at launch anyway. The address, port and
+//! config folder are fixed when the dashboard manager is built, so a change to
+//! any of them relaunches the app, once the user agrees, to start the
+//! dashboard on them. An address other machines can reach is a security
+//! decision, so that one asks in its own words, and with `require_auth` on any
+//! of the three takes OS authentication too. So does turning `require_auth`
+//! off, whatever else changes, or anyone could lift it here first.
+
+use std::net::{IpAddr, SocketAddr, TcpListener};
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tracing::{error, info};
+
+use crate::audit::{self, Source};
+use crate::control::ops::{self, UpdateGuard};
+use crate::i18n::{t, t_with};
+use crate::settings::{default_config_dir, UI_SCALES};
+use crate::window::app_state;
+
+/// Show the settings window, opening it if it isn't.
+pub(crate) fn open(app: &AppHandle) -> tauri::Result<()> {
+    let texts = serde_json::json!({
+        "port": t("settings_window.port"),
+        "port_hint": t("settings_window.port_hint"),
+        "listen_address": t("settings_window.listen_address"),
+        "listen_address_hint": t("settings_window.listen_address_hint"),
+        "config_dir": t("settings_window.config_dir"),
+        "browse": t("settings_window.browse"),
+        "open_on_start": t("settings_window.open_on_start"),
+        "ui_scale": t("settings_window.ui_scale"),
+        "ui_scale_hint": t("settings_window.ui_scale_hint"),
+        "require_auth": t("settings_window.require_auth"),
+        "require_auth_hint": t("settings_window.require_auth_hint"),
+        "save": t("settings_window.save"),
+        "saved": t("settings_window.saved"),
+    });
+    crate::window::open(
+        app,
+        "settings",
+        "settings.html",
+        t("settings_window.title"),
+        texts,
+    )
+}
+
+/// The settings the window edits. The port is wider than a port, and the
+/// address a string, so a bad entry reaches [`validate`] rather than failing
+/// the call.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct SettingsForm {
+    port: u32,
+    listen_address: String,
+    config_dir: String,
+    open_on_start: bool,
+    /// In percent.
+    ui_scale: u16,
+    require_auth: bool,
+}
+
+/// The settings as they are, the config folder resolved to the default
+/// when none is set.
+#[tauri::command]
+pub(crate) async fn settings_form(app: AppHandle) -> Result<SettingsForm, String> {
+    let state = app_state(&app)?;
+    let settings = state.settings.read().await;
+    let config_dir = settings.workspace.resolved_config_dir();
+    Ok(SettingsForm {
+        port: settings.port.into(),
+        listen_address: settings.listen_address.to_string(),
+        config_dir: config_dir.display().to_string(),
+        open_on_start: settings.open_on_start,
+        ui_scale: settings.ui_scale,
+        require_auth: settings.require_auth,
+    })
+}
+
+/// Pick the config folder, starting in `current`. `None` when cancelled.
+#[tauri::command]
+pub(crate) async fn pick_config_dir(app: AppHandle, current: String) -> Option<String> {
+    crate::dialog::pick_folder(&app, &t("settings_window.config_dir"), current.into())
+        .await
+        .map(|dir| dir.display().to_string())
+}
+
+/// Save `form`, relaunching the app when the address, port or config folder
+/// changed and the user agrees. Returns whether it was saved.
+#[tauri::command]
+pub(crate) async fn save_settings_form(app: AppHandle, form: SettingsForm) -> Result<bool, String> {
+    let state = app_state(&app)?;
+    let (port, address, config_dir) = validate(&form)?;
+    let (current_port, current_address, current_dir, forced, auth_required) = {
+        let settings = state.settings.read().await;
+        let dir = settings.workspace.resolved_config_dir();
+        // The runtime may listen elsewhere whatever the setting says (WSL2).
+        let effective = settings.dashboard_address().ip();
+        let forced = (effective != settings.listen_address).then_some(effective);
+        (
+            settings.port,
+            settings.listen_address,
+            dir,
+            forced,
+            settings.require_auth,
+        )
+    };
+    let listen_ip = forced.unwrap_or(address);
+    // An address that isn't this machine's can't be listened on at all.
+    if listen_ip != current_address && TcpListener::bind(SocketAddr::new(listen_ip, 0)).is_err() {
+        return Err(t_with(
+            "settings_window.address_unavailable",
+            &[("address", &address.to_string())],
+        ));
+    }
+    // Anything else on the port would keep the dashboard from starting, and
+    // the relaunched app from telling why. On the same port our own dashboard
+    // holds it, so there is nothing to learn.
+    if port != current_port && TcpListener::bind(SocketAddr::new(listen_ip, port)).is_err() {
+        return Err(t_with(
+            "settings_window.port_in_use",
+            &[("port", &port.to_string())],
+        ));
+    }
+
+    let relaunch = port != current_port || address != current_address || config_dir != current_dir;
+    let guard = if relaunch {
+        // The address asked for, not where the runtime listens on this
+        // machine: WSL2 exposes the distro's listener behind the loopback.
+        let (prompt, accept) = if address != current_address && !address.is_loopback() {
+            (
+                t_with(
+                    "settings_window.confirm_expose",
+                    &[("address", &address.to_string())],
+                ),
+                t("settings_window.expose"),
+            )
+        } else {
+            (
+                t("settings_window.confirm_relaunch"),
+                t("settings_window.relaunch"),
+            )
+        };
+        let confirmed = crate::dialog::confirm(
+            &app,
+            &t("settings_window.title"),
+            prompt,
+            &accept,
+            &t("settings_window.cancel"),
+        )
+        .await;
+        if !confirmed {
+            return Ok(false);
+        }
+        // Held to the end, like a version switch: nothing may install while
+        // the app winds down to relaunch.
+        let Some(guard) = UpdateGuard::try_acquire(state.update_in_flight.clone()) else {
+            return Err(t("settings_window.busy"));
+        };
+        if !ops::authorize(&state, t("auth.change_settings"), &|_, _| {}).await {
+            return Ok(false);
+        }
+        Some(guard)
+    } else {
+        None
+    };
+    // The relaunch above authenticated already.
+    if guard.is_none()
+        && lifts_auth(auth_required, form.require_auth)
+        && !ops::authorize(&state, t("auth.disable_auth"), &|_, _| {}).await
+    {
+        return Ok(false);
+    }
+
+    {
+        let mut settings = state.settings.write().await;
+        settings.port = port;
+        settings.listen_address = address;
+        settings.workspace.config_dir =
+            (config_dir != default_config_dir()).then(|| config_dir.clone());
+        settings.open_on_start = form.open_on_start;
+        settings.ui_scale = form.ui_scale;
+        settings.require_auth = form.require_auth;
+        if let Err(e) = settings.save(&app) {
+            error!("Failed to save settings: {:#}", e);
+            return Err(t_with(
+                "settings_window.failed",
+                &[("error", &format!("{e:#}"))],
+            ));
+        }
+    }
+    crate::window::set_ui_scale(&app, form.ui_scale);
+    if auth_required != form.require_auth {
+        let detail = format!(
+            "require_auth {}",
+            if form.require_auth { "on" } else { "off" }
+        );
+        info!("Settings window turned {}", detail);
+        audit::record(&app, Source::Tray, "settings", detail);
+    }
+    if let Some(guard) = guard {
+        let detail = format!(
+            "address {}, config folder {}",
+            SocketAddr::new(address, port),
+            config_dir.display()
+        );
+        info!("Settings window changed the {}; relaunching", detail);
+        audit::record(&app, Source::Tray, "settings", detail);
+        std::mem::forget(guard);
+        crate::platform::relaunch_for_update(&app);
+    }
+    Ok(true)
+}
+
+/// Whether a save turns `require_auth` off, which always takes
+/// authentication while it is still on.
+fn lifts_auth(required: bool, asked: bool) -> bool {
+    required && !asked
+}
+
+/// The port, address and config folder `form` asks for, or why they, or its
+/// UI scale, can't be.
+fn validate(form: &SettingsForm) -> Result<(u16, IpAddr, PathBuf), String> {
+    if !UI_SCALES.contains(&form.ui_scale) {
+        return Err(t_with(
+            "settings_window.bad_ui_scale",
+            &[("scale", &form.ui_scale.to_string())],
+        ));
+    }
+    let port = u16::try_from(form.port)
+        .ok()
+        .filter(|&port| port != 0)
+        .ok_or_else(|| {
+            t_with(
+                "settings_window.bad_port",
+                &[("port", &form.port.to_string())],
+            )
+        })?;
+    let address = form.listen_address.trim().parse().map_err(|_| {
+        t_with(
+            "settings_window.bad_address",
+            &[("address", form.listen_address.trim())],
+        )
+    })?;
+    let config_dir = PathBuf::from(form.config_dir.trim());
+    if !config_dir.is_absolute() {
+        return Err(t("settings_window.relative_config_dir"));
+    }
+    if config_dir.exists() && !config_dir.is_dir() {
+        return Err(t_with(
+            "settings_window.not_a_folder",
+            &[("path", &config_dir.display().to_string())],
+        ));
+    }
+    Ok((port, address, config_dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_turning_require_auth_off_lifts_it() {
+        assert!(lifts_auth(true, false));
+        assert!(!lifts_auth(true, true));
+        assert!(!lifts_auth(false, true));
+        assert!(!lifts_auth(false, false));
+    }
+}
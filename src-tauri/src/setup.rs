@@ -0,0 +1,243 @@
+//! The pieces of [`crate::run`]'s setup hook that start features once the
+//! app state exists: the tray icon, the login item, the background tasks the
+//! settings turn on, the update checks and the signal handlers.
+
+use std::sync::Arc;
+use tauri::{
+    async_runtime,
+    tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
+    App, AppHandle,
+};
+use tauri_plugin_autostart::ManagerExt;
+use tracing::{info, warn};
+
+use crate::{
+    app_update, command_palette, dev_refresh, devices, digest, email, open_dashboard, platform,
+    tray, AppState,
+};
+
+/// Start the background tasks the settings turn on.
+pub(crate) fn spawn_features(app: &AppHandle, state: &AppState) {
+    let settings = async_runtime::block_on(state.settings.read());
+    let config_dir = state.daemon.config_dir();
+    if settings.watch_fallback_hotspots {
+        devices::hotspots::spawn(&state.tasks, app.clone(), config_dir.clone());
+    }
+    if settings.monitor_latency {
+        devices::discovery::spawn(
+            &state.tasks,
+            app.clone(),
+            config_dir.clone(),
+            settings.discovery.clone(),
+        );
+    }
+    if settings.notifications.weekly_summary {
+        digest::spawn(app.clone(), &state.tasks);
+    }
+    if let Some(email_digest) = &settings.notifications.email_digest {
+        email::spawn(app.clone(), &state.tasks, email_digest.clone());
+    }
+    if let Some(hour) = settings.dev_refresh_hour {
+        dev_refresh::spawn(app.clone(), &state.tasks, hour);
+    }
+    if let Some(shortcut) = &settings.palette_shortcut {
+        command_palette::register(app, shortcut);
+    }
+}
+
+/// Reconcile the OS login item to the persisted preference. This applies the
+/// on-by-default on first run and re-asserts a user's choice on every launch
+/// (so an "off" sticks and drift self-heals).
+pub(crate) fn reconcile_autostart(app: &AppHandle, state: &AppState) {
+    let want = async_runtime::block_on(state.settings.read()).launch_at_startup;
+    let manager = app.autolaunch();
+    match manager.is_enabled() {
+        Ok(current) if current != want => {
+            let result = if want {
+                manager.enable()
+            } else {
+                manager.disable()
+            };
+            if let Err(e) = result {
+                warn!("Failed to set autostart to {}: {}", want, e);
+            }
+        }
+        Err(e) => warn!("Failed to query autostart state: {}", e),
+        _ => {}
+    }
+}
+
+/// Handle tray icon left-click (open dashboard)
+fn handle_tray_click(_app: &AppHandle, state: &AppState) {
+    open_dashboard(state);
+}
+
+/// Build and set up the tray menu, if tray support is available. Returns
+/// whether there is a tray.
+pub(crate) fn create_tray(app: &App, state: &Arc<AppState>) -> bool {
+    if platform::is_tray_supported() {
+        // Create the tray icon programmatically.
+        // We wrap this in catch_unwind as a safety net: on Linux the
+        // underlying libappindicator-sys crate will panic!() if the
+        // shared library fails to load (e.g. GLIBC version mismatch).
+        let tray_result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            // The macOS menu bar expects a monochrome "template" image
+            // whose alpha channel the system recolors to match the
+            // light/dark theme. Linux tray pixmaps are rendered
+            // literally with no theme recoloring, so it gets a fixed
+            // white glyph suited to the (near-universal) dark panels.
+            // Windows keeps the full-color bundled icon.
+            #[cfg(target_os = "macos")]
+            let (icon, icon_as_template) = (
+                tauri::image::Image::from_bytes(include_bytes!("../icons/tray-mac.png"))?,
+                true,
+            );
+            #[cfg(target_os = "linux")]
+            let (icon, icon_as_template) = (
+                tauri::image::Image::from_bytes(include_bytes!("../icons/tray-linux.png"))?,
+                false,
+            );
+            #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+            let (icon, icon_as_template) = (
+                app.default_window_icon()
+                    .cloned()
+                    .ok_or_else(|| anyhow::anyhow!("No default icon available for tray"))?,
+                false,
+            );
+
+            let tray = TrayIconBuilder::with_id("main")
+                .icon(icon.clone())
+                .icon_as_template(icon_as_template)
+                .tooltip(tray::icon::TOOLTIP)
+                .build(app)?;
+
+            let menu = tray::build_tray_menu(app.handle(), state)?;
+            tray.set_menu(Some(menu))?;
+            tray::icon::follow(tray.clone(), icon, &state.store);
+
+            // Set up click handler
+            let state_clone = state.clone();
+            let app_handle = app.handle().clone();
+            tray.on_tray_icon_event(move |_tray, event| {
+                if let TrayIconEvent::Click {
+                    button,
+                    button_state: MouseButtonState::Up,
+                    ..
+                } = event
+                {
+                    match button {
+                        MouseButton::Left => handle_tray_click(&app_handle, &state_clone),
+                        MouseButton::Middle => {
+                            tray::handle_tray_middle_click(&app_handle, &state_clone)
+                        }
+                        _ => {}
+                    }
+                }
+            });
+
+            Ok::<(), anyhow::Error>(())
+        }));
+
+        match tray_result {
+            Ok(Ok(())) => {
+                info!("System tray icon created successfully");
+                true
+            }
+            Ok(Err(e)) => {
+                warn!(
+                    "Failed to create system tray icon: {}. Running without tray.",
+                    e
+                );
+                false
+            }
+            Err(_) => {
+                warn!(
+                    "System tray creation panicked (appindicator library not usable?). \
+                     Running without tray."
+                );
+                false
+            }
+        }
+    } else {
+        warn!(
+            "System tray not supported (appindicator library not found). \
+             Running without tray."
+        );
+        false
+    }
+}
+
+/// Start the update checks (after 30s, then every 24 hours).
+///
+/// Order matters: check the desktop app first. A self-update ships
+/// a fresh Python bundle that overwrites the user's `python/`
+/// directory, so any pip-installed ESPHome / device-builder bump
+/// we'd do now would be wiped by the next launch. Skip the Python
+/// checks while an app update is pending.
+/// The dev channel skips automatic update checks entirely, and so
+/// does `check_updates` off. When the active backend is a builder
+/// variant, the `esphome-device-builder` package is checked on the
+/// same schedule.
+pub(crate) fn spawn_update_checks(app: &AppHandle, state: &Arc<AppState>, tray_available: bool) {
+    let update_state = state.clone();
+    let update_app = app.clone();
+    // Captured so background update notifications can adapt their
+    // "how to update" hint when there is no tray menu to point at
+    // (issue #87).
+    let update_tray_available = tray_available;
+    state.tasks.spawn("update checks", async move {
+        tokio::time::sleep(tokio::time::Duration::from_secs(30)).await;
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(86400));
+        loop {
+            interval.tick().await;
+            // Read each time, so the tray's Preferences toggle
+            // applies without a restart.
+            if !update_state.settings.read().await.check_updates {
+                continue;
+            }
+            if app_update::check_and_notify(&update_app, update_tray_available).await
+                == app_update::NextStep::Skip
+            {
+                // App update pending — leave the Python packages alone.
+                continue;
+            }
+            let (channel, backend) = {
+                let settings = update_state.settings.read().await;
+                (settings.release_channel, settings.backend)
+            };
+            update_state
+                .update_checker
+                .check_and_notify(&update_app, channel, update_tray_available)
+                .await;
+            update_state
+                .update_checker
+                .check_and_notify_device_builder(&update_app, backend, update_tray_available)
+                .await;
+        }
+    });
+}
+
+/// Set up signal handlers for graceful shutdown on Ctrl+C. The daemon-stop is
+/// handled by the `RunEvent::ExitRequested` branch in [`crate::run`]; this
+/// just trips the exit.
+#[cfg(unix)]
+pub(crate) fn handle_signals(app: &AppHandle) {
+    let signal_app = app.clone();
+    async_runtime::spawn(async move {
+        let mut sigint = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::interrupt())
+            .expect("Failed to set up SIGINT handler");
+        let mut sigterm = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to set up SIGTERM handler");
+
+        tokio::select! {
+            _ = sigint.recv() => {
+                info!("Received SIGINT, shutting down...");
+            }
+            _ = sigterm.recv() => {
+                info!("Received SIGTERM, shutting down...");
+            }
+        }
+
+        signal_app.exit(0);
+    });
+}
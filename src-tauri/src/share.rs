@@ -0,0 +1,328 @@
+//! Sharing the dashboard for a while through a tunnel, for getting remote
+//! help without exposing its port for good.
+//!
+//! Opt-in: the tray offers it only with one of two settings.
+//!
+//! * `share_command` - any command that tunnels a local port and prints the
+//!   public URL, with `{port}` where the dashboard port goes, e.g.
+//!   `cloudflared tunnel --url http://localhost:{port}`. The first URL it
+//!   prints that isn't this machine's is the one shown.
+//! * `share_ssh_host` - `ssh -R` forwards the same port on that host to the
+//!   dashboard. The URL is that host's, reachable from there (and from its
+//!   network, if its `sshd` allows `GatewayPorts`).
+//!
+//! A share ends after `share_minutes`, when stopped from the tray, or when
+//! the app exits; the tunnel process goes with it.
+
+use anyhow::{bail, Context, Result};
+use std::net::SocketAddr;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+use crate::i18n::{t, t_with};
+use crate::settings::Settings;
+use crate::tasks::Tasks;
+
+/// Where the dashboard port goes in `share_command`.
+const PORT_PLACEHOLDER: &str = "{port}";
+
+/// How long a share lasts without `share_minutes`.
+const DEFAULT_MINUTES: u32 = 60;
+
+/// How long `share_command` gets to print its URL.
+const URL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long `ssh` gets to fail (a refused login, a port taken on the host)
+/// before the forward counts as up.
+const SSH_SETTLE: Duration = Duration::from_secs(5);
+
+/// Lines of the tunnel's output kept for the error when it fails.
+const OUTPUT_TAIL: usize = 5;
+
+/// How a share tunnels, from the settings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Method {
+    Command(String),
+    Ssh(String),
+}
+
+impl Method {
+    /// `share_command` if set, else `share_ssh_host`; `None` keeps the tray
+    /// item hidden.
+    pub(crate) fn from_settings(settings: &Settings) -> Option<Self> {
+        let set = |value: &Option<String>| {
+            value
+                .as_deref()
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+        };
+        set(&settings.share_command)
+            .map(Method::Command)
+            .or_else(|| set(&settings.share_ssh_host).map(Method::Ssh))
+    }
+
+    fn command(&self, address: SocketAddr) -> Result<Command> {
+        let port = address.port();
+        let mut cmd = match self {
+            Method::Command(template) => {
+                let command = template.replace(PORT_PLACEHOLDER, &port.to_string());
+                let words = crate::devices::editor::split_command(&command);
+                let (program, args) = words.split_first().context("share_command is empty")?;
+                let mut cmd = Command::new(program);
+                cmd.args(args);
+                cmd
+            }
+            Method::Ssh(host) => {
+                let mut cmd = Command::new("ssh");
+                cmd.args(crate::daemon::SSH_OPTIONS)
+                    .args(["-N", "-o", "ExitOnForwardFailure=yes", "-R"])
+                    .arg(format!("{port}:{}", crate::daemon::reachable(address)))
+                    .arg(host);
+                cmd
+            }
+        };
+        cmd.stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        crate::platform::configure_no_window_tokio_command(&mut cmd);
+        Ok(cmd)
+    }
+}
+
+/// The minutes a share lasts.
+pub(crate) fn minutes(settings: &Settings) -> u32 {
+    settings.share_minutes.unwrap_or(DEFAULT_MINUTES)
+}
+
+/// The share, if one is open.
+#[derive(Default)]
+pub struct Share {
+    open: Arc<Mutex<Option<Open>>>,
+    /// Tells a share's expiry from a later one's.
+    next_id: AtomicU64,
+}
+
+struct Open {
+    id: u64,
+    url: String,
+    until: Instant,
+    child: Child,
+}
+
+impl Share {
+    /// The open share's URL and the whole minutes it has left. One whose
+    /// tunnel exited on its own is forgotten.
+    pub(crate) fn current(&self) -> Option<(String, u64)> {
+        let mut open = lock(&self.open);
+        if let Some(o) = open.as_mut() {
+            if !matches!(o.child.try_wait(), Ok(None)) {
+                warn!("The share tunnel exited");
+                *open = None;
+            }
+        }
+        open.as_ref().map(|o| {
+            let left = o.until.saturating_duration_since(Instant::now());
+            (o.url.clone(), left.as_secs().div_ceil(60))
+        })
+    }
+
+    /// Open a tunnel to the dashboard on `address` with `method` for `minutes`,
+    /// replacing a share already open, and return its URL. The expiry runs
+    /// in `tasks` and says so in a notification.
+    pub(crate) async fn start(
+        &self,
+        app: &AppHandle,
+        tasks: &Tasks,
+        method: &Method,
+        address: SocketAddr,
+        minutes: u32,
+    ) -> Result<String> {
+        self.stop();
+        let port = address.port();
+        let mut child = method
+            .command(address)?
+            .spawn()
+            .context("Failed to start the share tunnel")?;
+        let (tx, mut output) = mpsc::unbounded_channel();
+        forward(tasks, child.stdout.take(), tx.clone());
+        forward(tasks, child.stderr.take(), tx);
+
+        let url = match method {
+            Method::Command(_) => {
+                let mut tail = Vec::new();
+                let found = tokio::time::timeout(URL_TIMEOUT, async {
+                    while let Some(line) = output.recv().await {
+                        if let Some(url) = find_url(&line) {
+                            return Some(url);
+                        }
+                        keep(&mut tail, line);
+                    }
+                    None
+                })
+                .await;
+                match found {
+                    Ok(Some(url)) => url,
+                    _ => bail!("the share command printed no URL: {}", tail.join(" / ")),
+                }
+            }
+            Method::Ssh(host) => {
+                if let Ok(status) = tokio::time::timeout(SSH_SETTLE, child.wait()).await {
+                    let mut tail = Vec::new();
+                    while let Ok(line) = output.try_recv() {
+                        keep(&mut tail, line);
+                    }
+                    let status = status.context("Failed to wait for ssh")?;
+                    bail!("ssh {status}: {}", tail.join(" / "));
+                }
+                let name = host.rsplit('@').next().unwrap_or(host);
+                format!("http://{name}:{port}")
+            }
+        };
+
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let lasts = Duration::from_secs(u64::from(minutes) * 60);
+        info!("Sharing the dashboard at {} for {} minutes", url, minutes);
+        *lock(&self.open) = Some(Open {
+            id,
+            url: url.clone(),
+            until: Instant::now() + lasts,
+            child,
+        });
+
+        let open = self.open.clone();
+        let app = app.clone();
+        tasks.spawn("share expiry", async move {
+            tokio::time::sleep(lasts).await;
+            let mut open = lock(&open);
+            if open.as_ref().is_some_and(|o| o.id == id) {
+                *open = None;
+                drop(open);
+                info!("Dashboard share expired");
+                if let Err(e) =
+                    crate::notifications::show(&app, t("share.title"), t("share.expired"))
+                {
+                    warn!("Failed to show share-expiry notification: {}", e);
+                }
+            }
+        });
+        Ok(url)
+    }
+
+    /// Close the open share, if any. Synchronous, for the exit path.
+    pub(crate) fn stop(&self) {
+        if let Some(mut open) = lock(&self.open).take() {
+            let _ = open.child.start_kill();
+            info!("Stopped sharing the dashboard at {}", open.url);
+        }
+    }
+}
+
+fn lock(open: &Mutex<Option<Open>>) -> std::sync::MutexGuard<'_, Option<Open>> {
+    open.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Send each line of `out` to `tx` until the tunnel closes it. Keeps the
+/// pipe drained after the URL was found, so a chatty tunnel never blocks.
+fn forward(
+    tasks: &Tasks,
+    out: Option<impl AsyncRead + Unpin + Send + 'static>,
+    tx: mpsc::UnboundedSender<String>,
+) {
+    let Some(out) = out else { return };
+    tasks.spawn("share output", async move {
+        let mut lines = BufReader::new(out).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            debug!("share tunnel: {}", line);
+            let _ = tx.send(line);
+        }
+    });
+}
+
+fn keep(tail: &mut Vec<String>, line: String) {
+    if tail.len() == OUTPUT_TAIL {
+        tail.remove(0);
+    }
+    tail.push(line);
+}
+
+/// The first http(s) URL in `line` that isn't this machine's (tunnels tend to
+/// print the local address they forward too).
+fn find_url(line: &str) -> Option<String> {
+    line.split(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '<' | '>' | '|'))
+        .filter(|word| word.starts_with("https://") || word.starts_with("http://"))
+        .map(|word| word.trim_end_matches(['.', ',', ')', ']']))
+        .find(|url| {
+            let host = url.split("://").nth(1).unwrap_or_default();
+            !["localhost", "127.0.0.1", "0.0.0.0", "[::1]"]
+                .iter()
+                .any(|local| host.starts_with(local))
+        })
+        .map(str::to_string)
+}
+
+/// The confirmation text for a share with `method` lasting `minutes`.
+pub(crate) fn describe(method: &Method, minutes: u32) -> String {
+    let minutes = minutes.to_string();
+    match method {
+        Method::Command(command) => t_with(
+            "share.confirm_command",
+            &[("command", command), ("minutes", &minutes)],
+        ),
+        Method::Ssh(host) => t_with(
+            "share.confirm_ssh",
+            &[("host", host), ("minutes", &minutes)],
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_public_url_not_the_local_one() {
+        assert_eq!(
+            find_url(
+                "INF Requesting new quick Tunnel on trycloudflare.com... (http://localhost:6052)"
+            ),
+            None
+        );
+        assert_eq!(
+            find_url("|  https://tall-gray-fox.trycloudflare.com  |").as_deref(),
+            Some("https://tall-gray-fox.trycloudflare.com")
+        );
+        assert_eq!(
+            find_url("listening at bore.pub:41234, see http://bore.pub:41234.").as_deref(),
+            Some("http://bore.pub:41234")
+        );
+    }
+
+    #[test]
+    fn the_command_wins_over_the_ssh_host() {
+        let mut settings = Settings {
+            share_ssh_host: Some("helper@jump.example.com".into()),
+            ..Settings::default()
+        };
+        assert_eq!(
+            Method::from_settings(&settings),
+            Some(Method::Ssh("helper@jump.example.com".into()))
+        );
+        settings.share_command = Some("bore local {port} --to bore.pub".into());
+        assert_eq!(
+            Method::from_settings(&settings),
+            Some(Method::Command("bore local {port} --to bore.pub".into()))
+        );
+        settings.share_command = Some("  ".into());
+        settings.share_ssh_host = None;
+        assert_eq!(Method::from_settings(&settings), None);
+    }
+}
@@ -0,0 +1,121 @@
+//! The "Firmware Signing Keys..." window: each device, whether its config
+//! asks for a signing key, and whether the keychain holds one, with buttons
+//! to make or remove a device's key (see [`crate::devices::signing`]). Keys
+//! are never shown. The page is `dist/signing.html`.
+
+use serde::Serialize;
+use tauri::AppHandle;
+use tracing::{error, info};
+
+use crate::audit::{self, Source};
+use crate::devices::{self, signing};
+use crate::i18n::{t, t_with};
+use crate::window::app_state;
+
+/// Show the signing keys window, opening it if it isn't.
+pub(crate) fn open(app: &AppHandle) -> tauri::Result<()> {
+    let texts = serde_json::json!({
+        "intro": t("signing.intro"),
+        "none": t("signing.none"),
+        "uses": t("signing.uses"),
+        "unused": t("signing.unused"),
+        "stored": t("signing.stored"),
+        "missing": t("signing.missing"),
+        "generate": t("signing.generate"),
+        "remove": t("signing.remove"),
+        "generate_label": t("signing.generate_label"),
+        "remove_label": t("signing.remove_label"),
+        "generated": t("signing.generated"),
+        "removed": t("signing.removed"),
+    });
+    crate::window::open(app, "signing", "signing.html", t("signing.title"), texts)
+}
+
+/// A device's row in the window.
+#[derive(Debug, Serialize)]
+pub(crate) struct KeyStatus {
+    device: String,
+    /// Its config asks for a key.
+    uses: bool,
+    stored: bool,
+}
+
+/// Every device with a config, those asking for a key first.
+#[tauri::command]
+pub(crate) async fn signing_keys(app: AppHandle) -> Result<Vec<KeyStatus>, String> {
+    let config_dir = app_state(&app)?.daemon.config_dir().clone();
+    let statuses = tauri::async_runtime::spawn_blocking(move || {
+        let mut statuses = Vec::new();
+        for device in devices::list_devices(&config_dir)? {
+            let yaml = devices::config_path(&config_dir, &device)
+                .and_then(|path| std::fs::read_to_string(path).ok())
+                .unwrap_or_default();
+            statuses.push(KeyStatus {
+                uses: signing::uses_signing(&yaml),
+                stored: signing::load(&device)?.is_some(),
+                device,
+            });
+        }
+        statuses.sort_by_key(|s| !s.uses);
+        anyhow::Ok(statuses)
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    statuses.map_err(|e| {
+        error!("Failed to list signing keys: {:#}", e);
+        t_with("signing.failed", &[("error", &format!("{e:#}"))])
+    })
+}
+
+/// Make and store a key for `device`.
+#[tauri::command]
+pub(crate) async fn generate_signing_key(app: AppHandle, device: String) -> Result<(), String> {
+    let name = device.clone();
+    let generated = tauri::async_runtime::spawn_blocking(move || signing::generate(&name))
+        .await
+        .map_err(|e| e.to_string())?;
+    if let Err(e) = generated {
+        error!("Failed to make a signing key for {}: {:#}", device, e);
+        return Err(t_with("signing.failed", &[("error", &format!("{e:#}"))]));
+    }
+    info!("Made a signing key for {}", device);
+    audit::record(
+        &app,
+        Source::Tray,
+        "signing_key",
+        format!("made a signing key for {device}"),
+    );
+    Ok(())
+}
+
+/// Remove `device`'s key, once the user agrees. Returns whether it went.
+#[tauri::command]
+pub(crate) async fn remove_signing_key(app: AppHandle, device: String) -> Result<bool, String> {
+    let confirmed = crate::dialog::confirm(
+        &app,
+        &t("signing.title"),
+        t_with("signing.confirm_remove", &[("device", &device)]),
+        &t("signing.remove"),
+        &t("signing.cancel"),
+    )
+    .await;
+    if !confirmed {
+        return Ok(false);
+    }
+    let name = device.clone();
+    let removed = tauri::async_runtime::spawn_blocking(move || signing::delete(&name))
+        .await
+        .map_err(|e| e.to_string())?;
+    if let Err(e) = removed {
+        error!("Failed to remove {}'s signing key: {:#}", device, e);
+        return Err(t_with("signing.failed", &[("error", &format!("{e:#}"))]));
+    }
+    info!("Removed {}'s signing key", device);
+    audit::record(
+        &app,
+        Source::Tray,
+        "signing_key",
+        format!("removed {device}'s signing key"),
+    );
+    Ok(true)
+}
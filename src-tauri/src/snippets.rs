@@ -0,0 +1,74 @@
+//! The opt-in clipboard watcher (`clipboard_snippets`): YAML copied from the
+//! ESPHome docs is offered for adding to a device config or checking against
+//! the installed ESPHome.
+//!
+//! The clipboard is read every [`POLL_INTERVAL`] and nothing of it leaves the
+//! app or reaches the log. A new copy that [`is_snippet`] accepts becomes the
+//! pending [`Event::Snippet`], which enables the tray's "Use Copied
+//! Snippet..." item, and a notification points there: a notification can't
+//! carry buttons on every platform, the tray item can. What was on the
+//! clipboard when the app started is never offered.
+
+use std::time::Duration;
+
+use tauri::AppHandle;
+use tracing::{debug, warn};
+
+use crate::devices::snippet::is_snippet;
+use crate::i18n::t;
+use crate::store::{Event, Store};
+use crate::tasks::Tasks;
+
+/// Time between reads of the clipboard.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Watch the clipboard for as long as `tasks` lives.
+pub(crate) fn spawn(app: AppHandle, tasks: &Tasks, store: Store) {
+    tasks.spawn("snippet watcher", async move {
+        let mut sequence = crate::platform::clipboard_sequence();
+        let mut last = read().await.ok().flatten();
+        let mut failing = false;
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            let now = crate::platform::clipboard_sequence();
+            if now.is_some() && now == sequence {
+                continue;
+            }
+            sequence = now;
+            let text = match read().await {
+                Ok(text) => {
+                    failing = false;
+                    text
+                }
+                Err(e) => {
+                    // Once per run of failures: a missing `xclip` fails
+                    // every poll.
+                    if !failing {
+                        warn!("Failed to read the clipboard: {:#}", e);
+                    }
+                    failing = true;
+                    continue;
+                }
+            };
+            let Some(text) = text else { continue };
+            if last.as_ref() == Some(&text) {
+                continue;
+            }
+            last = Some(text.clone());
+            if !is_snippet(&text) {
+                continue;
+            }
+            debug!("An ESPHome snippet was copied");
+            store.publish(Event::Snippet(Some(text)));
+            if let Err(e) =
+                crate::notifications::show(&app, t("snippets.copied_title"), t("snippets.copied"))
+            {
+                warn!("Failed to show snippet notification: {}", e);
+            }
+        }
+    });
+}
+
+async fn read() -> anyhow::Result<Option<String>> {
+    tokio::task::spawn_blocking(crate::platform::clipboard_text).await?
+}
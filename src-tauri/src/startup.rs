@@ -0,0 +1,334 @@
+//! How long the app takes to get the dashboard up, and whether the last
+//! start was unusually slow.
+//!
+//! Each launch times three phases: checking the app's Python (the copy from
+//! the bundle and the repair of a broken tree), starting the backend
+//! process (for the Docker and SSH runtimes that includes their own
+//! preparation), and the wait from there to the dashboard's first
+//! successful HTTP response. The last [`HISTORY_LEN`] launches are kept in
+//! `startups.json` in the app data, and `status` and the status overview
+//! point out a phase that took far longer than it usually does.
+//!
+//! The same history sets how long a launch waits to open the dashboard
+//! before saying it is still starting (see [`ready_timeout`]); the browser
+//! only opens once the dashboard answers.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tauri::AppHandle;
+use tracing::{error, info, warn};
+
+use crate::i18n::{t, t_with};
+use crate::util::ConfigStore;
+use crate::AppState;
+
+/// File name of the history inside the data directory.
+const STARTUPS_FILE_NAME: &str = "startups.json";
+
+/// Launches kept.
+const HISTORY_LEN: usize = 20;
+
+/// How long the first response is waited for. A first run that sets up
+/// PlatformIO can take minutes.
+const READY_LIMIT: Duration = Duration::from_secs(600);
+
+/// The shortest wait before a launch says the dashboard is still starting,
+/// and the whole wait on a first run, which has no times to go by.
+const MIN_READY_WAIT: Duration = Duration::from_secs(60);
+
+/// Earlier launches a phase needs before its latest time is judged.
+const MIN_BASELINE: usize = 3;
+
+/// A phase is slow when it took this many times its usual time...
+const SLOW_FACTOR: u64 = 3;
+
+/// ...and at least this much longer, so a 1 s phase taking 4 s isn't.
+const SLOW_MARGIN_MS: u64 = 10_000;
+
+/// A timed part of the launch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Phase {
+    Python,
+    Spawn,
+    Ready,
+}
+
+impl Phase {
+    const ALL: [Phase; 3] = [Phase::Python, Phase::Spawn, Phase::Ready];
+
+    /// What happened in it, to follow "the app took 90 s ...".
+    fn doing(self) -> &'static str {
+        match self {
+            Phase::Python => "checking its Python",
+            Phase::Spawn => "starting the backend",
+            Phase::Ready => "waiting for the dashboard to answer",
+        }
+    }
+}
+
+/// One launch's phases, in milliseconds.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Startup {
+    /// When it finished, `YYYY-MM-DDTHH:MM:SSZ`.
+    pub time: String,
+    pub python_ms: u64,
+    pub spawn_ms: u64,
+    /// `None` when the dashboard didn't answer within [`READY_LIMIT`].
+    pub ready_ms: Option<u64>,
+}
+
+impl Startup {
+    /// The time `phase` took; a dashboard that never answered counts as
+    /// [`READY_LIMIT`].
+    fn phase(&self, phase: Phase) -> u64 {
+        match phase {
+            Phase::Python => self.python_ms,
+            Phase::Spawn => self.spawn_ms,
+            Phase::Ready => self.ready_ms.unwrap_or(millis(READY_LIMIT)),
+        }
+    }
+
+    /// `Python 1.2 s, backend 0.4 s, first response 8.1 s`.
+    pub(crate) fn describe(&self) -> String {
+        let ready = match self.ready_ms {
+            Some(ms) => secs(ms),
+            None => format!("none within {} s", READY_LIMIT.as_secs()),
+        };
+        format!(
+            "Python {}, backend {}, first response {ready}",
+            secs(self.python_ms),
+            secs(self.spawn_ms)
+        )
+    }
+}
+
+/// A phase of the latest launch that took far longer than usual.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct SlowStart {
+    pub phase: Phase,
+    pub secs: u64,
+    /// The median over the earlier launches.
+    pub usual_secs: u64,
+}
+
+impl SlowStart {
+    /// `the app took 90 s waiting for the dashboard to answer, usually 8 s`.
+    pub(crate) fn describe(&self) -> String {
+        format!(
+            "the app took {} s {}, usually {} s",
+            self.secs,
+            self.phase.doing(),
+            self.usual_secs
+        )
+    }
+}
+
+/// The recorded launches, oldest first.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct StartupHistory {
+    #[serde(default)]
+    startups: Vec<Startup>,
+}
+
+impl ConfigStore for StartupHistory {
+    const FILE_NAME: &'static str = STARTUPS_FILE_NAME;
+    const WHAT: &'static str = "startup history";
+}
+
+impl StartupHistory {
+    /// Add a launch to the history at `path`, dropping the oldest beyond
+    /// [`HISTORY_LEN`].
+    fn record(path: &Path, startup: Startup) -> Result<Self> {
+        let mut history = Self::load(path)?;
+        history.startups.push(startup);
+        let excess = history.startups.len().saturating_sub(HISTORY_LEN);
+        history.startups.drain(..excess);
+        history.save(path)?;
+        Ok(history)
+    }
+
+    /// The latest launch.
+    pub(crate) fn latest(&self) -> Option<&Startup> {
+        self.startups.last()
+    }
+
+    /// The latest launch's slowest phase against the earlier ones, if it
+    /// was far slower than usual.
+    pub(crate) fn slow_start(&self) -> Option<SlowStart> {
+        let (latest, earlier) = self.startups.split_last()?;
+        if earlier.len() < MIN_BASELINE {
+            return None;
+        }
+        Phase::ALL
+            .into_iter()
+            .filter_map(|phase| {
+                let mut times: Vec<u64> = earlier.iter().map(|s| s.phase(phase)).collect();
+                times.sort_unstable();
+                let usual = times[times.len() / 2];
+                let took = latest.phase(phase);
+                let slow = took >= usual.saturating_mul(SLOW_FACTOR)
+                    && took.saturating_sub(usual) >= SLOW_MARGIN_MS;
+                slow.then_some((took.saturating_sub(usual), phase, took, usual))
+            })
+            .max_by_key(|(excess, ..)| *excess)
+            .map(|(_, phase, took, usual)| SlowStart {
+                phase,
+                secs: took.div_ceil(1000),
+                usual_secs: usual.div_ceil(1000),
+            })
+    }
+}
+
+/// How long a launch waits quietly for the dashboard: `ready_timeout` when
+/// it is set, otherwise twice the slowest first response recorded, at least
+/// [`MIN_READY_WAIT`].
+pub(crate) fn ready_timeout(history: &StartupHistory, setting: Option<u32>) -> Duration {
+    if let Some(secs) = setting {
+        return Duration::from_secs(secs.into());
+    }
+    match history.startups.iter().filter_map(|s| s.ready_ms).max() {
+        Some(ms) => Duration::from_millis(ms.saturating_mul(2)).clamp(MIN_READY_WAIT, READY_LIMIT),
+        None => MIN_READY_WAIT,
+    }
+}
+
+/// Open the dashboard once it answers. One that takes longer than
+/// [`ready_timeout`] gets a notification saying it is still starting, and
+/// one that never answers one saying so, rather than a browser tab on a
+/// refused connection.
+pub(crate) async fn open_when_ready(app: AppHandle, state: Arc<AppState>) {
+    let setting = state.settings.read().await.ready_timeout;
+    let history = crate::platform::get_data_dir(&app)
+        .ok()
+        .and_then(|dir| StartupHistory::load(&StartupHistory::path(&dir)).ok())
+        .unwrap_or_default();
+    let quiet = ready_timeout(&history, setting);
+    let address = state.daemon.address();
+    if !crate::wait_for_dashboard_ready(address, quiet.as_secs()).await {
+        info!(
+            "Dashboard not ready after {} s; waiting on without opening it",
+            quiet.as_secs()
+        );
+        notify(
+            &app,
+            t("startup.still_starting_title"),
+            t("startup.still_starting_body"),
+        );
+        let rest = READY_LIMIT.saturating_sub(quiet).max(quiet);
+        if !crate::wait_for_dashboard_ready(address, rest.as_secs()).await {
+            let minutes = (quiet + rest).as_secs().div_ceil(60).to_string();
+            notify(
+                &app,
+                t("startup.not_ready_title"),
+                t_with("startup.not_ready_body", &[("minutes", &minutes)]),
+            );
+            return;
+        }
+    }
+    crate::open_dashboard(&state);
+}
+
+fn notify(app: &AppHandle, title: String, body: String) {
+    if let Err(e) = crate::notifications::show(app, title, body) {
+        error!("Failed to show startup notification: {}", e);
+    }
+}
+
+/// Wait for the dashboard at `address` to answer, then record the launch
+/// with the Python and backend start times measured before it.
+pub(crate) async fn finish(app: AppHandle, address: SocketAddr, python: Duration, spawn: Duration) {
+    let waiting = Instant::now();
+    let ready = crate::wait_for_dashboard_ready(address, READY_LIMIT.as_secs()).await;
+    let startup = Startup {
+        time: crate::audit::rfc3339(SystemTime::now()),
+        python_ms: millis(python),
+        spawn_ms: millis(spawn),
+        ready_ms: ready.then(|| millis(waiting.elapsed())),
+    };
+    info!("Startup timings: {}", startup.describe());
+    let recorded = crate::platform::get_data_dir(&app)
+        .map_err(anyhow::Error::from)
+        .and_then(|dir| StartupHistory::record(&StartupHistory::path(&dir), startup));
+    match recorded {
+        Ok(history) => {
+            if let Some(slow) = history.slow_start() {
+                warn!("Slow start: {}", slow.describe());
+            }
+        }
+        Err(e) => warn!("Failed to record startup timings: {:#}", e),
+    }
+}
+
+fn millis(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
+
+fn secs(ms: u64) -> String {
+    format!("{:.1} s", ms as f64 / 1000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn startup(python_ms: u64, spawn_ms: u64, ready_ms: Option<u64>) -> Startup {
+        Startup {
+            time: "2026-10-16T08:00:00Z".into(),
+            python_ms,
+            spawn_ms,
+            ready_ms,
+        }
+    }
+
+    #[test]
+    fn flags_a_phase_far_slower_than_usual() {
+        let mut history = StartupHistory {
+            startups: vec![
+                startup(900, 300, Some(8_000)),
+                startup(1_100, 400, Some(7_000)),
+                startup(1_000, 300, Some(9_000)),
+            ],
+        };
+        assert_eq!(history.slow_start(), None, "too few to judge");
+
+        history.startups.push(startup(1_200, 350, Some(8_500)));
+        assert_eq!(history.slow_start(), None);
+
+        history.startups.push(startup(4_000, 300, Some(90_000)));
+        let slow = history.slow_start().unwrap();
+        assert_eq!(
+            slow,
+            SlowStart {
+                phase: Phase::Ready,
+                secs: 90,
+                usual_secs: 9,
+            }
+        );
+        assert_eq!(
+            slow.describe(),
+            "the app took 90 s waiting for the dashboard to answer, usually 9 s"
+        );
+
+        history.startups.push(startup(1_000, 300, None));
+        assert_eq!(history.slow_start().unwrap().secs, 600);
+    }
+
+    #[test]
+    fn waits_twice_the_slowest_first_response() {
+        let mut history = StartupHistory::default();
+        assert_eq!(ready_timeout(&history, None), MIN_READY_WAIT);
+        assert_eq!(ready_timeout(&history, Some(300)), Duration::from_secs(300));
+
+        history.startups = vec![startup(1_000, 300, Some(8_000)), startup(1_000, 300, None)];
+        assert_eq!(ready_timeout(&history, None), MIN_READY_WAIT);
+        history.startups.push(startup(1_000, 300, Some(95_000)));
+        assert_eq!(ready_timeout(&history, None), Duration::from_secs(190));
+        history.startups.push(startup(1_000, 300, Some(400_000)));
+        assert_eq!(ready_timeout(&history, None), READY_LIMIT);
+    }
+}
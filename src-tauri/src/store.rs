@@ -0,0 +1,329 @@
+//! Application-wide state store and event bus.
+//!
+//! What more than one surface shows lives in one [`Store`]: whether the
+//! backend runs, the installed versions, the selected channel and backend,
+//! the login item, the language server, why the backend was last restarted
+//! and who has been using it. Whoever changes one of them
+//! publishes an [`Event`]; the tray, notifications and anything else that
+//! wants to follow along subscribe, instead of every producer calling into
+//! each of them. Readers that only need the current values (the control
+//! API's replies, a menu being built) take a [`Snapshot`].
+//!
+//! Events go out on a tokio broadcast channel. A subscriber that falls more
+//! than [`CAPACITY`] events behind skips ahead; every event carries the full
+//! new value, so it only misses the intermediate states.
+
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+
+use crate::notifications::PauseState;
+use crate::settings::{Backend, ReleaseChannel, Settings};
+
+/// Events kept for a slow subscriber before it starts skipping.
+const CAPACITY: usize = 64;
+
+/// Restarts the snapshot remembers, oldest first.
+const RESTART_HISTORY: usize = 10;
+
+/// The backend process as the supervisor sees it.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub(crate) enum DaemonState {
+    /// Not started yet this session.
+    #[default]
+    Starting,
+    Running,
+    Stopped,
+    /// Exited on its own, with this status.
+    Crashed(String),
+}
+
+impl DaemonState {
+    pub(crate) fn is_running(&self) -> bool {
+        *self == DaemonState::Running
+    }
+}
+
+/// Why the backend was started again after its first start this session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum RestartReason {
+    /// Restart from the tray or the control API.
+    User,
+    /// Around an ESPHome, device builder or app update, or a channel switch.
+    Update,
+    /// The user's restart of a backend that had crashed.
+    CrashRecovery,
+    /// A settings change that needs a new backend, such as the backend switch.
+    Settings,
+}
+
+impl RestartReason {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            RestartReason::User => "user",
+            RestartReason::Update => "update",
+            RestartReason::CrashRecovery => "crash_recovery",
+            RestartReason::Settings => "settings",
+        }
+    }
+}
+
+/// One restart of the backend.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Restart {
+    /// Which restart of the session, from 1.
+    pub number: u32,
+    /// UTC, RFC 3339.
+    pub time: String,
+    pub reason: RestartReason,
+}
+
+/// Who has been using the dashboard, from its access log (`access_summary`).
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub(crate) struct Usage {
+    /// Whole minutes since a browser last made a request; `None` before the
+    /// first.
+    pub idle_minutes: Option<u64>,
+    /// Addresses of the other machines that made requests, sorted.
+    pub remote_clients: Vec<String>,
+}
+
+/// A change to the application state. Each carries the new value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Event {
+    Daemon(DaemonState),
+    /// The installed ESPHome, as shown ("2024.7.3", or "unknown").
+    EsphomeVersion(String),
+    /// Where the installed ESPHome came from, when not from PyPI or the dev
+    /// channel's snapshot: a git URL and ref, e.g.
+    /// "github.com/esphome/esphome@dev".
+    EsphomeSource(Option<String>),
+    /// The installed device builder, as shown.
+    BuilderVersion(String),
+    Channel(ReleaseChannel),
+    Backend(Backend),
+    /// Whether the app launches at login.
+    Startup(bool),
+    /// Whether the editor language server runs.
+    LanguageServer(bool),
+    /// Whether notifications are on, rather than turned off in the tray's
+    /// Preferences (a timed pause doesn't count).
+    Notifications(bool),
+    /// Whether notifications are paused and for how much longer.
+    Pause(PauseState),
+    /// The backend was started again. Added to the history rather than
+    /// replacing a value.
+    Restarted(Restart),
+    Usage(Usage),
+    /// The ESPHome snippet copied last and not yet used
+    /// (`clipboard_snippets`).
+    Snippet(Option<String>),
+    /// The shared package file, relative to the config directory, that
+    /// changed last and whose devices haven't been checked since
+    /// (`check_shared_packages`).
+    PackageChanged(Option<String>),
+}
+
+/// The current value of everything [`Event`]s report.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub(crate) struct Snapshot {
+    pub daemon: DaemonState,
+    /// `None` until detected.
+    pub esphome_version: Option<String>,
+    pub esphome_source: Option<String>,
+    pub builder_version: Option<String>,
+    pub channel: ReleaseChannel,
+    pub backend: Backend,
+    pub startup: bool,
+    pub language_server: bool,
+    pub notifications: bool,
+    pub pause: PauseState,
+    /// The session's last [`RESTART_HISTORY`] restarts, oldest first.
+    pub restarts: Vec<Restart>,
+    /// `None` while nothing follows the access log.
+    pub usage: Option<Usage>,
+    pub snippet: Option<String>,
+    pub changed_package: Option<String>,
+}
+
+impl Snapshot {
+    fn apply(&mut self, event: &Event) {
+        match event {
+            Event::Daemon(state) => self.daemon = state.clone(),
+            Event::EsphomeVersion(v) => self.esphome_version = Some(v.clone()),
+            Event::EsphomeSource(s) => self.esphome_source = s.clone(),
+            Event::BuilderVersion(v) => self.builder_version = Some(v.clone()),
+            Event::Channel(channel) => self.channel = *channel,
+            Event::Backend(backend) => self.backend = *backend,
+            Event::Startup(enabled) => self.startup = *enabled,
+            Event::LanguageServer(running) => self.language_server = *running,
+            Event::Notifications(on) => self.notifications = *on,
+            Event::Pause(pause) => self.pause = *pause,
+            Event::Restarted(restart) => {
+                if self.restarts.len() == RESTART_HISTORY {
+                    self.restarts.remove(0);
+                }
+                self.restarts.push(restart.clone());
+            }
+            Event::Usage(usage) => self.usage = Some(usage.clone()),
+            Event::Snippet(snippet) => self.snippet = snippet.clone(),
+            Event::PackageChanged(file) => self.changed_package = file.clone(),
+        }
+    }
+}
+
+/// The state and the bus it is published on. Clones share both.
+#[derive(Debug, Clone)]
+pub(crate) struct Store {
+    snapshot: Arc<Mutex<Snapshot>>,
+    events: broadcast::Sender<Event>,
+}
+
+impl Default for Store {
+    fn default() -> Self {
+        Self::with(Snapshot::default())
+    }
+}
+
+impl Store {
+    /// A store seeded from the persisted settings, until the producers
+    /// report the live values.
+    pub(crate) fn from_settings(settings: &Settings) -> Self {
+        Self::with(Snapshot {
+            esphome_version: settings.installed_version.clone(),
+            channel: settings.release_channel,
+            backend: settings.backend,
+            startup: settings.launch_at_startup,
+            language_server: settings.language_server,
+            notifications: !crate::notifications::turned_off(settings.notifications.paused_until),
+            pause: crate::notifications::pause_state(),
+            ..Snapshot::default()
+        })
+    }
+
+    fn with(snapshot: Snapshot) -> Self {
+        Self {
+            snapshot: Arc::new(Mutex::new(snapshot)),
+            events: broadcast::channel(CAPACITY).0,
+        }
+    }
+
+    /// Record `event` and send it to every subscriber. A no-op for a value
+    /// that didn't change, so publishing the same state twice (a stop of a
+    /// stopped backend) doesn't wake anyone.
+    pub(crate) fn publish(&self, event: Event) {
+        {
+            let mut snapshot = self.snapshot.lock().unwrap_or_else(|e| e.into_inner());
+            let before = snapshot.clone();
+            snapshot.apply(&event);
+            if *snapshot == before {
+                return;
+            }
+        }
+        // Fails only when nobody is subscribed.
+        let _ = self.events.send(event);
+    }
+
+    /// Every event published from now on.
+    pub(crate) fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.events.subscribe()
+    }
+
+    /// The current values.
+    pub(crate) fn snapshot(&self) -> Snapshot {
+        self.snapshot
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
+
+/// Run `handle` on each of `events` ([`Store::subscribe`]), on the async
+/// runtime, for as long as the store lives. The shape every subscriber takes.
+pub(crate) fn listen(
+    mut events: broadcast::Receiver<Event>,
+    mut handle: impl FnMut(Event) + Send + 'static,
+) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match events.recv().await {
+                Ok(event) => handle(event),
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::debug!("Event subscriber skipped {skipped} events");
+                }
+                Err(broadcast::error::RecvError::Closed) => return,
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn subscribers_see_each_change_once() {
+        let store = Store::default();
+        let mut events = store.subscribe();
+
+        store.publish(Event::Daemon(DaemonState::Running));
+        store.publish(Event::Daemon(DaemonState::Running));
+        store.publish(Event::Channel(ReleaseChannel::Beta));
+
+        assert_eq!(
+            events.recv().await.unwrap(),
+            Event::Daemon(DaemonState::Running)
+        );
+        assert_eq!(
+            events.recv().await.unwrap(),
+            Event::Channel(ReleaseChannel::Beta)
+        );
+        assert!(events.try_recv().is_err());
+    }
+
+    #[test]
+    fn the_snapshot_follows_the_events() {
+        let store = Store::from_settings(&Settings {
+            installed_version: Some("2024.6.0".into()),
+            ..Settings::default()
+        });
+        assert_eq!(
+            store.snapshot().esphome_version.as_deref(),
+            Some("2024.6.0")
+        );
+        assert_eq!(store.snapshot().daemon, DaemonState::Starting);
+
+        store.publish(Event::EsphomeVersion("2024.7.3".into()));
+        store.publish(Event::Daemon(DaemonState::Crashed("exit status: 1".into())));
+        store.publish(Event::LanguageServer(true));
+
+        let snapshot = store.snapshot();
+        assert_eq!(snapshot.esphome_version.as_deref(), Some("2024.7.3"));
+        assert!(!snapshot.daemon.is_running());
+        assert!(snapshot.language_server);
+    }
+
+    #[test]
+    fn the_restart_history_keeps_the_latest() {
+        let store = Store::default();
+        for number in 1..=12 {
+            store.publish(Event::Restarted(Restart {
+                number,
+                time: "2026-10-16T09:00:00Z".into(),
+                reason: RestartReason::Update,
+            }));
+        }
+        let restarts = store.snapshot().restarts;
+        assert_eq!(restarts.len(), RESTART_HISTORY);
+        assert_eq!(restarts[0].number, 3);
+        assert_eq!(restarts[RESTART_HISTORY - 1].number, 12);
+    }
+
+    #[test]
+    fn publishing_without_subscribers_is_fine() {
+        let store = Store::default();
+        store.publish(Event::Startup(true));
+        assert!(store.snapshot().startup);
+    }
+}
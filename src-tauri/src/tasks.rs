@@ -0,0 +1,202 @@
+//! Background tasks with a lifetime.
+//!
+//! A long-running task (the update loop, the backend's health checks and
+//! watchers, the readiness poller) is spawned into a [`Tasks`] scope rather
+//! than fire-and-forget. Cancelling the scope drops every task in it at its
+//! next `await`, so a backend restart retires the previous run's watchers
+//! before the new run starts, and quitting stops the app's loops instead of
+//! leaving them to be torn down mid-step with the runtime. Scopes nest: the
+//! app's root scope is the parent of the daemon's, which is the parent of each
+//! run's, and cancelling a scope cancels everything under it.
+//!
+//! Tasks need no cancellation handling of their own. One that must not be cut
+//! short at an `await` (an install, a settings write) doesn't belong here.
+
+use std::future::Future;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+use tauri::async_runtime::{self, JoinHandle};
+use tokio::sync::watch;
+use tracing::{debug, warn};
+
+/// A cancellation flag shared by a scope and everything spawned in it.
+#[derive(Debug, Clone)]
+pub(crate) struct CancelToken(Arc<Node>);
+
+#[derive(Debug)]
+struct Node {
+    cancelled: watch::Sender<bool>,
+    /// Weak so a child scope that is dropped doesn't linger here.
+    children: Mutex<Vec<Weak<Node>>>,
+}
+
+impl Default for CancelToken {
+    fn default() -> Self {
+        Self(Arc::new(Node {
+            cancelled: watch::channel(false).0,
+            children: Mutex::new(Vec::new()),
+        }))
+    }
+}
+
+impl CancelToken {
+    /// A token cancelled along with this one (and on its own). Cancelled
+    /// already if this one is.
+    pub(crate) fn child(&self) -> Self {
+        let child = Self::default();
+        let mut children = self.0.children.lock().unwrap_or_else(|e| e.into_inner());
+        children.retain(|c| c.strong_count() > 0);
+        children.push(Arc::downgrade(&child.0));
+        drop(children);
+        if self.is_cancelled() {
+            child.cancel();
+        }
+        child
+    }
+
+    /// Cancel this token and its children. Idempotent.
+    pub(crate) fn cancel(&self) {
+        if self.0.cancelled.send_replace(true) {
+            return;
+        }
+        let children =
+            std::mem::take(&mut *self.0.children.lock().unwrap_or_else(|e| e.into_inner()));
+        for child in children.iter().filter_map(Weak::upgrade) {
+            CancelToken(child).cancel();
+        }
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        *self.0.cancelled.borrow()
+    }
+
+    /// Resolves once the token is cancelled.
+    pub(crate) async fn cancelled(&self) {
+        let mut rx = self.0.cancelled.subscribe();
+        // Fails only when the sender is gone, and `self` holds it.
+        let _ = rx.wait_for(|cancelled| *cancelled).await;
+    }
+}
+
+/// A scope of background tasks. Dropping it cancels them.
+#[derive(Debug, Default)]
+pub(crate) struct Tasks {
+    token: CancelToken,
+    handles: Mutex<Vec<(&'static str, JoinHandle<()>)>>,
+}
+
+impl Tasks {
+    /// A scope cancelled along with this one.
+    pub(crate) fn child(&self) -> Self {
+        Self {
+            token: self.token.child(),
+            handles: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// The scope's token, for a task that ends the scope itself.
+    pub(crate) fn token(&self) -> CancelToken {
+        self.token.clone()
+    }
+
+    /// Run `task` on the async runtime until it finishes or the scope is
+    /// cancelled. `name` is for the logs.
+    pub(crate) fn spawn<F>(&self, name: &'static str, task: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let token = self.token.clone();
+        let handle = async_runtime::spawn(async move {
+            tokio::select! {
+                () = token.cancelled() => debug!("Cancelled {name}"),
+                () = task => {}
+            }
+        });
+        let mut handles = self.handles.lock().unwrap_or_else(|e| e.into_inner());
+        handles.retain(|(_, handle)| !handle.inner().is_finished());
+        handles.push((name, handle));
+    }
+
+    /// Cancel the scope's tasks without waiting for them; for synchronous
+    /// exit paths.
+    pub(crate) fn cancel(&self) {
+        self.token.cancel();
+    }
+
+    /// Cancel the scope's tasks and wait up to `grace` for them to wind
+    /// down, aborting any that haven't.
+    pub(crate) async fn shutdown(&self, grace: Duration) {
+        self.token.cancel();
+        let handles = std::mem::take(&mut *self.handles.lock().unwrap_or_else(|e| e.into_inner()));
+        let deadline = tokio::time::Instant::now() + grace;
+        for (name, handle) in handles {
+            let abort = handle.inner().abort_handle();
+            if tokio::time::timeout_at(deadline, handle).await.is_err() {
+                warn!("{name} didn't stop within {grace:?}; aborting it");
+                abort.abort();
+            }
+        }
+    }
+}
+
+impl Drop for Tasks {
+    fn drop(&mut self) {
+        self.token.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    #[test]
+    fn cancelling_a_parent_cancels_its_children() {
+        let root = CancelToken::default();
+        let child = root.child();
+        let grandchild = child.child();
+        let sibling = root.child();
+
+        child.cancel();
+        assert!(grandchild.is_cancelled());
+        assert!(!root.is_cancelled() && !sibling.is_cancelled());
+
+        root.cancel();
+        assert!(sibling.is_cancelled());
+        // Born cancelled.
+        assert!(root.child().is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn shutdown_stops_a_task_that_never_finishes() {
+        let tasks = Tasks::default();
+        let finished = Arc::new(AtomicBool::new(false));
+        let flag = finished.clone();
+        tasks.spawn("forever", async move {
+            tokio::time::sleep(Duration::from_secs(3600)).await;
+            flag.store(true, Ordering::SeqCst);
+        });
+
+        tokio::time::timeout(
+            Duration::from_secs(5),
+            tasks.shutdown(Duration::from_secs(1)),
+        )
+        .await
+        .expect("shutdown waits for the cancelled task, not the hour");
+        assert!(!finished.load(Ordering::SeqCst));
+        assert!(tasks.handles.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn dropping_a_scope_cancels_it() {
+        let parent = Tasks::default();
+        let token = {
+            let run = parent.child();
+            run.token.clone()
+        };
+        assert!(token.is_cancelled());
+        tokio::time::timeout(Duration::from_secs(1), token.cancelled())
+            .await
+            .unwrap();
+    }
+}
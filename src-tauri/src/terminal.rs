@@ -0,0 +1,214 @@
+//! The "Terminal..." window: a command line in the environment the backend
+//! runs in, for `esphome run porch.yaml` or `pip list` without hunting for
+//! the app's interpreter. Commands start in the config folder with the
+//! interpreter's directories first on `PATH` and the backend's sandbox
+//! (`sandbox_backend`) around them, as the backend's own `esphome` commands
+//! get.
+//!
+//! One command runs at a time, without a shell (no pipes, globs or
+//! variables) and with nothing on its standard input; Stop kills it. Its
+//! output is kept here, the last [`KEPT_LINES`] lines, and the page at
+//! `dist/terminal.html` polls [`terminal_output`] for what it hasn't shown,
+//! each line with its level, which the page colours in the `log_palette`.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::AppHandle;
+use tracing::{info, warn};
+
+use crate::control::ops;
+use crate::devices::editor::split_command;
+use crate::i18n::{t, t_with};
+use crate::settings::LogLevel;
+use crate::tasks::CancelToken;
+use crate::window::app_state;
+
+/// How many lines of output the window can scroll back through.
+const KEPT_LINES: usize = 5000;
+
+#[derive(Debug, Default)]
+struct Session {
+    lines: VecDeque<String>,
+    /// How many lines were dropped from the front, so line numbers stay put.
+    dropped: usize,
+    /// The running command's, to stop it.
+    running: Option<CancelToken>,
+}
+
+impl Session {
+    fn push(&mut self, line: String) {
+        if self.lines.len() == KEPT_LINES {
+            self.lines.pop_front();
+            self.dropped += 1;
+        }
+        self.lines.push_back(line);
+    }
+}
+
+static SESSION: Mutex<Session> = Mutex::new(Session {
+    lines: VecDeque::new(),
+    dropped: 0,
+    running: None,
+});
+
+fn lock() -> std::sync::MutexGuard<'static, Session> {
+    SESSION.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Show the terminal, opening it if it isn't.
+pub(crate) fn open(app: &AppHandle) -> tauri::Result<()> {
+    let texts = serde_json::json!({
+        "placeholder": t("terminal.placeholder"),
+        "run": t("terminal.run"),
+        "stop": t("terminal.stop"),
+        "clear": t("terminal.clear"),
+        "command_label": t("terminal.command_label"),
+        "output_label": t("terminal.output_label"),
+    });
+    crate::window::open(app, "terminal", "terminal.html", t("terminal.title"), texts)
+}
+
+/// Where commands run, for the window's heading.
+#[tauri::command]
+pub(crate) fn terminal_intro(app: AppHandle) -> Result<String, String> {
+    let state = app_state(&app)?;
+    Ok(t_with(
+        "terminal.intro",
+        &[("dir", &state.daemon.config_dir().display().to_string())],
+    ))
+}
+
+/// An output line, with its level for the log palette.
+#[derive(Debug, Serialize)]
+pub(crate) struct Line {
+    text: String,
+    level: Option<LogLevel>,
+}
+
+/// Output from line `from` on.
+#[derive(Debug, Serialize)]
+pub(crate) struct Output {
+    lines: Vec<Line>,
+    /// The line to ask from next.
+    next: usize,
+    running: bool,
+}
+
+/// The output since line `from`, and whether a command still runs.
+#[tauri::command]
+pub(crate) fn terminal_output(from: usize) -> Output {
+    let session = lock();
+    let skip = from.saturating_sub(session.dropped);
+    Output {
+        lines: session
+            .lines
+            .iter()
+            .skip(skip)
+            .map(|text| Line {
+                level: LogLevel::of(text),
+                text: text.clone(),
+            })
+            .collect(),
+        next: session.dropped + session.lines.len(),
+        running: session.running.is_some(),
+    }
+}
+
+/// Start `command` unless one is running. With `require_auth` on it takes
+/// OS authentication first: a `pip install` here changes the environment as
+/// much as an update does.
+#[tauri::command]
+pub(crate) async fn terminal_run(app: AppHandle, command: String) -> Result<(), String> {
+    let words = split_command(&command);
+    if words.is_empty() {
+        return Ok(());
+    }
+    let state = app_state(&app)?;
+    if lock().running.is_some() {
+        return Err(t("terminal.busy"));
+    }
+    let authorized = ops::authorize(&state, t("auth.terminal"), &|_, _| {}).await;
+    let mut session = lock();
+    if session.running.is_some() {
+        return Err(t("terminal.busy"));
+    }
+    session.push(format!("$ {}", command.trim()));
+    if !authorized {
+        session.push(t("terminal.not_authorized"));
+        return Ok(());
+    }
+    let cmd = match state.daemon.terminal_command(&words) {
+        Ok(cmd) => cmd,
+        Err(e) => {
+            session.push(t_with("terminal.failed", &[("error", &format!("{e:#}"))]));
+            return Ok(());
+        }
+    };
+    let token = CancelToken::default();
+    session.running = Some(token.clone());
+    drop(session);
+    info!("Terminal: running {}", command.trim());
+
+    tauri::async_runtime::spawn(async move {
+        // pip's runner, for its line-by-line output, its kill on cancel and,
+        // on Windows, the job that ends the command along with the app.
+        let result = crate::platform::run_pip_watched(
+            cmd,
+            |line| lock().push(line.to_string()),
+            token.cancelled(),
+        )
+        .await;
+        let last = match result {
+            Ok(output) => match output.status.code() {
+                Some(0) => None,
+                Some(code) => Some(t_with("terminal.exited", &[("code", &code.to_string())])),
+                None => Some(t("terminal.killed")),
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => Some(t("terminal.stopped")),
+            Err(e) => {
+                warn!("Terminal command failed to run: {}", e);
+                Some(t_with("terminal.failed", &[("error", &e.to_string())]))
+            }
+        };
+        let mut session = lock();
+        if let Some(last) = last {
+            session.push(last);
+        }
+        session.running = None;
+    });
+    Ok(())
+}
+
+/// Stop the running command, if there is one.
+#[tauri::command]
+pub(crate) fn terminal_stop() {
+    if let Some(token) = &lock().running {
+        token.cancel();
+    }
+}
+
+/// Forget the output so far.
+#[tauri::command]
+pub(crate) fn terminal_clear() {
+    let mut session = lock();
+    session.dropped += session.lines.len();
+    session.lines.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_numbers_survive_dropping_old_output() {
+        let mut session = Session::default();
+        for i in 0..KEPT_LINES + 3 {
+            session.push(i.to_string());
+        }
+        assert_eq!(session.dropped, 3);
+        assert_eq!(session.lines.front().map(String::as_str), Some("3"));
+        assert_eq!(session.lines.len(), KEPT_LINES);
+    }
+}
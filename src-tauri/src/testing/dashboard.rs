@@ -0,0 +1,307 @@
+//! A stand-in for the dashboard on a loopback port.
+//!
+//! Plain HTTP requests get the status (and body) set for their path, `200`
+//! with an empty body by default; a WebSocket upgrade is accepted and sent
+//! the queued events as text frames, then closed. Every request line is
+//! recorded. One connection at a time, each closed after its response, which
+//! is all the probes and the update checker need.
+
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::devices::template::base64;
+
+/// What RFC 6455 appends to the client's key before hashing it.
+const WS_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+#[derive(Default)]
+struct State {
+    /// Status for paths without a route.
+    status: u16,
+    /// Status and body by path, query left out.
+    routes: BTreeMap<String, (u16, String)>,
+    /// Sent to each WebSocket client.
+    events: Vec<String>,
+    /// Request lines seen, e.g. `GET / HTTP/1.1`.
+    requests: Vec<String>,
+}
+
+pub(crate) struct MockDashboard {
+    port: u16,
+    state: Arc<Mutex<State>>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl MockDashboard {
+    /// Listen on a free loopback port.
+    pub(crate) fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("mock dashboard failed to bind");
+        let port = listener.local_addr().expect("bound listener").port();
+        let state = Arc::new(Mutex::new(State {
+            status: 200,
+            ..State::default()
+        }));
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let (state, stop) = (state.clone(), stop.clone());
+            std::thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if stop.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    if let Ok(stream) = stream {
+                        let _ = serve(stream, &state);
+                    }
+                }
+            })
+        };
+        Self {
+            port,
+            state,
+            stop,
+            thread: Some(thread),
+        }
+    }
+
+    pub(crate) fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Answer paths without a route with `status`.
+    pub(crate) fn set_status(&self, status: u16) {
+        self.lock().status = status;
+    }
+
+    /// Answer `path` with `status` and `body`.
+    pub(crate) fn route(&self, path: &str, status: u16, body: impl Into<String>) {
+        self.lock()
+            .routes
+            .insert(path.to_string(), (status, body.into()));
+    }
+
+    /// Queue a text frame for WebSocket clients.
+    pub(crate) fn push_event(&self, event: impl Into<String>) {
+        self.lock().events.push(event.into());
+    }
+
+    /// The request lines seen so far.
+    pub(crate) fn requests(&self) -> Vec<String> {
+        self.lock().requests.clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for MockDashboard {
+    /// Stop listening, so the port refuses connections like a dead backend.
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        // Wake the accept loop so it sees the flag.
+        let _ = TcpStream::connect(("127.0.0.1", self.port));
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn serve(stream: TcpStream, state: &Mutex<State>) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(2)))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut ws_key = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("sec-websocket-key") {
+                ws_key = Some(value.trim().to_string());
+            }
+        }
+    }
+
+    let request_line = request_line.trim().to_string();
+    let path = request_line
+        .split_whitespace()
+        .nth(1)
+        .unwrap_or("/")
+        .split('?')
+        .next()
+        .unwrap_or("/")
+        .to_string();
+    let (status, body, events) = {
+        let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+        state.requests.push(request_line);
+        let (status, body) = state
+            .routes
+            .get(&path)
+            .cloned()
+            .unwrap_or((state.status, String::new()));
+        (status, body, state.events.clone())
+    };
+
+    let mut stream = stream;
+    match ws_key {
+        Some(key) => {
+            write!(
+                stream,
+                "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\n\
+                 Connection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+                accept_key(&key)
+            )?;
+            for event in events {
+                stream.write_all(&text_frame(&event))?;
+            }
+            // A close frame without a status code.
+            stream.write_all(&[0x88, 0])?;
+        }
+        None => {
+            let reason = if status < 400 { "OK" } else { "Error" };
+            write!(
+                stream,
+                "HTTP/1.1 {status} {reason}\r\nContent-Length: {}\r\n\
+                 Connection: close\r\n\r\n{body}",
+                body.len()
+            )?;
+        }
+    }
+    stream.flush()
+}
+
+/// `Sec-WebSocket-Accept` for a client's `Sec-WebSocket-Key`.
+fn accept_key(key: &str) -> String {
+    base64(&sha1(format!("{key}{WS_GUID}").as_bytes()))
+}
+
+/// An unmasked text frame, as a server sends them.
+fn text_frame(text: &str) -> Vec<u8> {
+    let len = text.len();
+    let mut frame = vec![0x81];
+    if len < 126 {
+        frame.push(len as u8);
+    } else {
+        frame.push(126);
+        frame.extend_from_slice(&(len as u16).to_be_bytes());
+    }
+    frame.extend_from_slice(text.as_bytes());
+    frame
+}
+
+/// SHA-1, which the WebSocket handshake needs and nothing else here does.
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&(data.len() as u64 * 8).to_be_bytes());
+    for block in message.chunks(64) {
+        let mut w = [0u32; 80];
+        for (word, bytes) in w.iter_mut().zip(block.chunks(4)) {
+            *word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A827999),
+                20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+            let next = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*word);
+            (e, d, c, b, a) = (d, c, b.rotate_left(30), a, next);
+        }
+        for (state, v) in h.iter_mut().zip([a, b, c, d, e]) {
+            *state = state.wrapping_add(v);
+        }
+    }
+    let mut digest = [0u8; 20];
+    for (bytes, word) in digest.chunks_mut(4).zip(h) {
+        bytes.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn exchange(port: u16, request: &str) -> Vec<u8> {
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn accept_key_matches_the_rfc_example() {
+        assert_eq!(
+            accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+    }
+
+    #[test]
+    fn answers_routes_and_upgrades() {
+        let mock = MockDashboard::start();
+        mock.route("/version", 200, "{\"version\":\"2024.7.0\"}");
+        mock.set_status(503);
+        let event = "{\"event\":\"entry_state_changed\"}";
+        mock.push_event(event);
+
+        let response = exchange(mock.port(), "GET /version?x=1 HTTP/1.1\r\n\r\n");
+        let response = String::from_utf8(response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response}");
+        assert!(
+            response.ends_with("{\"version\":\"2024.7.0\"}"),
+            "{response}"
+        );
+        let response = exchange(mock.port(), "GET / HTTP/1.1\r\n\r\n");
+        assert!(response.starts_with(b"HTTP/1.1 503 "));
+
+        let response = exchange(
+            mock.port(),
+            "GET /events HTTP/1.1\r\nUpgrade: websocket\r\n\
+             Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n",
+        );
+        let split = response.windows(4).position(|w| w == b"\r\n\r\n").unwrap() + 4;
+        let (head, frames) = response.split_at(split);
+        let head = String::from_utf8_lossy(head);
+        assert!(head.starts_with("HTTP/1.1 101 "), "{head}");
+        assert!(head.contains("Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo="));
+        let (frame, close) = frames.split_at(2 + event.len());
+        assert_eq!(frame, text_frame(event));
+        assert_eq!(frame[1] as usize, event.len());
+        assert_eq!(close, [0x88, 0]);
+
+        assert_eq!(
+            mock.requests(),
+            [
+                "GET /version?x=1 HTTP/1.1",
+                "GET / HTTP/1.1",
+                "GET /events HTTP/1.1"
+            ]
+        );
+    }
+}
@@ -0,0 +1,16 @@
+//! Test doubles for the flows that otherwise need a real backend.
+//!
+//! [`MockDashboard`] stands in for the dashboard's HTTP and WebSocket
+//! endpoints on a loopback port, and [`FakeVenv`] is a venv layout whose
+//! `python3` is a shell script acting out the few commands this app runs
+//! (the dashboard itself, `esphome version`, `pip install`). Together they
+//! let the daemon and update flows run end to end without Python or the
+//! network.
+
+mod dashboard;
+#[cfg(unix)]
+mod venv;
+
+pub(crate) use dashboard::MockDashboard;
+#[cfg(unix)]
+pub(crate) use venv::FakeVenv;
@@ -0,0 +1,148 @@
+//! A venv layout whose interpreter is a shell script.
+//!
+//! `bin/python3` logs each invocation's arguments to `calls.log` and acts
+//! out the commands this app runs, steered by files in the venv root:
+//!
+//! - `-m esphome_device_builder …` sleeps like a running dashboard, or exits
+//!   with the code in `dashboard_exit`.
+//! - `-m esphome version` prints the version in `esphome_version`, or exits 1
+//!   when there is none, like an interpreter without ESPHome.
+//! - `-m pip install … esphome==X` records `X` as the installed version, or
+//!   fails with pip's missing-RECORD abort while `pip_failures` counts down.
+//!
+//! Anything else exits 2. Unix only: the script is POSIX sh.
+
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use crate::util::unique_temp_dir;
+
+const PYTHON: &str = r#"#!/bin/sh
+here="$(cd "$(dirname "$0")/.." && pwd)"
+echo "$*" >> "$here/calls.log"
+[ "$1" = "-I" ] && shift
+case "$1 $2" in
+"-m esphome_device_builder")
+    [ -f "$here/dashboard_exit" ] && exit "$(cat "$here/dashboard_exit")"
+    exec sleep 600 ;;
+"-m esphome")
+    if [ "$3" = version ] && [ -f "$here/esphome_version" ]; then
+        echo "Version: $(cat "$here/esphome_version")"
+        exit 0
+    fi
+    exit 1 ;;
+"-m pip")
+    failures="$(cat "$here/pip_failures" 2>/dev/null || echo 0)"
+    if [ "$failures" -gt 0 ]; then
+        echo $((failures - 1)) > "$here/pip_failures"
+        echo "error: uninstall-no-record-file" >&2
+        echo "Cannot uninstall esphome, RECORD file not found." >&2
+        exit 1
+    fi
+    for arg; do
+        case "$arg" in esphome==*) echo "${arg#esphome==}" > "$here/esphome_version" ;; esac
+    done
+    echo "Successfully installed"
+    exit 0 ;;
+esac
+exit 2
+"#;
+
+pub(crate) struct FakeVenv {
+    root: PathBuf,
+}
+
+impl FakeVenv {
+    /// A fresh venv with ESPHome not installed.
+    pub(crate) fn new() -> Self {
+        let root = unique_temp_dir("fake_venv");
+        std::fs::create_dir_all(root.join("bin")).unwrap();
+        std::fs::create_dir_all(root.join("lib/python3.12/site-packages")).unwrap();
+        std::fs::write(
+            root.join("pyvenv.cfg"),
+            "home = /usr/bin\nversion = 3.12.0\n",
+        )
+        .unwrap();
+        let python = root.join("bin/python3");
+        std::fs::write(&python, PYTHON).unwrap();
+        std::fs::set_permissions(&python, std::fs::Permissions::from_mode(0o755)).unwrap();
+        Self { root }
+    }
+
+    pub(crate) fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub(crate) fn python(&self) -> PathBuf {
+        self.root.join("bin/python3")
+    }
+
+    pub(crate) fn bin_dir(&self) -> PathBuf {
+        self.root.join("bin")
+    }
+
+    /// Install `version` of ESPHome, or uninstall it with `None`.
+    pub(crate) fn set_esphome_version(&self, version: Option<&str>) {
+        let path = self.root.join("esphome_version");
+        match version {
+            Some(version) => std::fs::write(path, version).unwrap(),
+            None => {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+    }
+
+    /// Make the dashboard exit with `code` as soon as it starts.
+    pub(crate) fn crash_dashboard(&self, code: i32) {
+        std::fs::write(self.root.join("dashboard_exit"), code.to_string()).unwrap();
+    }
+
+    /// Make the next `times` pip installs abort on a missing RECORD file.
+    pub(crate) fn fail_pip_with_missing_record(&self, times: u32) {
+        std::fs::write(self.root.join("pip_failures"), times.to_string()).unwrap();
+    }
+
+    /// The arguments of each invocation so far.
+    pub(crate) fn calls(&self) -> Vec<String> {
+        std::fs::read_to_string(self.root.join("calls.log"))
+            .unwrap_or_default()
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+impl Drop for FakeVenv {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.root);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::run_python_capture_stdout;
+
+    #[test]
+    fn acts_out_esphome_and_pip() {
+        let venv = FakeVenv::new();
+        let version = || run_python_capture_stdout(&venv.python(), ["-m", "esphome", "version"]);
+        assert_eq!(version().unwrap(), None);
+
+        venv.fail_pip_with_missing_record(1);
+        let pip = |pin: &str| {
+            std::process::Command::new(venv.python())
+                .args(["-m", "pip", "install", pin])
+                .output()
+                .unwrap()
+        };
+        let aborted = pip("esphome==2024.7.0");
+        assert!(!aborted.status.success());
+        assert!(String::from_utf8_lossy(&aborted.stderr).contains("uninstall-no-record-file"));
+        assert!(pip("esphome==2024.7.0").status.success());
+        assert_eq!(version().unwrap().as_deref(), Some("Version: 2024.7.0"));
+
+        assert_eq!(venv.calls().len(), 4);
+        assert_eq!(venv.calls()[1], "-m pip install esphome==2024.7.0");
+    }
+}
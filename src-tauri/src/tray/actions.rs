@@ -0,0 +1,105 @@
+//! The tray menu as actions for the command palette
+//! ([`crate::command_palette`]): every enabled item, by its label, plus a
+//! shortcut to every device. Choosing one dispatches it like a click on the
+//! item, so the palette offers what the menu does, with its layout and
+//! kiosk mode.
+
+use std::sync::Arc;
+
+use tauri::menu::{Menu, MenuItemKind};
+use tauri::{AppHandle, Manager, Wry};
+use tracing::warn;
+
+use super::ids;
+use crate::i18n::t_with;
+use crate::AppState;
+
+/// Runs a menu item by id, as a click on it does.
+pub(super) type Dispatch = Arc<dyn Fn(&AppHandle, &str) + Send + Sync>;
+
+/// The built menu and its dispatch, kept in the app's state.
+struct Actions {
+    menu: Menu<Wry>,
+    dispatch: Dispatch,
+}
+
+/// Keep `menu` and `dispatch` for the palette.
+pub(super) fn manage(app: &AppHandle, menu: Menu<Wry>, dispatch: Dispatch) {
+    app.manage(Actions { menu, dispatch });
+}
+
+/// Every action, as (id, label), in menu order and devices last. Empty
+/// without a tray.
+pub(crate) fn list(app: &AppHandle) -> Vec<(String, String)> {
+    let Some(actions) = app.try_state::<Actions>() else {
+        return Vec::new();
+    };
+    let mut found = Vec::new();
+    match actions.menu.items() {
+        Ok(items) => collect(&items, None, &mut found),
+        Err(e) => warn!("Failed to read the tray menu: {}", e),
+    }
+    let Some(state) = app.try_state::<Arc<AppState>>() else {
+        return found;
+    };
+    if state.kiosk {
+        return found;
+    }
+    match crate::devices::list_devices(state.daemon.config_dir()) {
+        Ok(devices) => {
+            for device in devices {
+                let id = format!("{}{device}", ids::DEVICE_SHORTCUT);
+                if !found.iter().any(|(known, _)| *known == id) {
+                    let label = t_with("tray.device_shortcut", &[("device", &device)]);
+                    found.push((id, label));
+                }
+            }
+        }
+        Err(e) => warn!("No device actions: {:#}", e),
+    }
+    found
+}
+
+/// The enabled plain items of `items`, labelled with the `submenu` they are
+/// in. Check items are left out: a click toggles them before dispatch, which
+/// the palette can't.
+fn collect(items: &[MenuItemKind<Wry>], submenu: Option<&str>, found: &mut Vec<(String, String)>) {
+    for item in items {
+        match item {
+            MenuItemKind::MenuItem(item) => {
+                let (Ok(true), Ok(text)) = (item.is_enabled(), item.text()) else {
+                    continue;
+                };
+                let label = match submenu {
+                    Some(menu) => t_with("palette.nested", &[("menu", menu), ("item", &text)]),
+                    None => text,
+                };
+                found.push((item.id().as_ref().to_string(), label));
+            }
+            MenuItemKind::Submenu(menu) => {
+                if let (Ok(text), Ok(items)) = (menu.text(), menu.items()) {
+                    collect(&items, Some(&text), found);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Run the action `id` on the main thread, as a click does. Returns whether
+/// it is one of [`list`]'s.
+pub(crate) fn run(app: &AppHandle, id: &str) -> bool {
+    if !list(app).iter().any(|(known, _)| known == id) {
+        return false;
+    }
+    let Some(actions) = app.try_state::<Actions>() else {
+        return false;
+    };
+    let dispatch = actions.dispatch.clone();
+    let (app, id) = (app.clone(), id.to_string());
+    let handle = app.clone();
+    if let Err(e) = handle.run_on_main_thread(move || dispatch(&app, &id)) {
+        warn!("Failed to run a palette action: {}", e);
+    }
+    true
+}
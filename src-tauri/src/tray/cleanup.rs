@@ -0,0 +1,230 @@
+//! The tray's "Uninstall Data..." and "Reset ESPHome Builder..." items.
+//!
+//! Uninstalling data removes what the app keeps on disk, then quits, so
+//! removing the app afterwards leaves nothing behind. PlatformIO's
+//! toolchains, which other PlatformIO use shares, and the device configs
+//! each go only when the user says so in a question of their own. A reset
+//! removes the Python environment, settings and build caches, then
+//! relaunches, and the launch sets the app up again from the bundle as on a
+//! first run: a clean slate for a broken install.
+//!
+//! The same removal as the `uninstall-data` and `reset` subcommands (see
+//! [`crate::cleanup`]); the tray adds the dialogs, and stops the dashboard
+//! first since the subcommands refuse to run next to it.
+
+use std::sync::Arc;
+
+use tauri::AppHandle;
+use tauri_plugin_dialog::MessageDialogKind;
+use tracing::{error, info};
+
+use crate::audit::{self, Source};
+use crate::cleanup::{self, Kind, Locations, Purpose, Target};
+use crate::control::ops::{self, UpdateGuard};
+use crate::error::UserError;
+use crate::i18n::{t, t_with};
+use crate::AppState;
+
+/// The dialog texts that differ between the two actions.
+struct Texts {
+    title: String,
+    prompt: String,
+    confirm: String,
+    auth: String,
+    done: String,
+}
+
+fn texts(purpose: Purpose, targets: &str) -> Texts {
+    let targets = [("targets", targets)];
+    match purpose {
+        Purpose::Uninstall => Texts {
+            title: t("cleanup.title"),
+            prompt: t_with("cleanup.prompt", &targets),
+            confirm: t("cleanup.remove"),
+            auth: t("auth.uninstall_data"),
+            done: t("cleanup.done"),
+        },
+        Purpose::Reset => Texts {
+            title: t("cleanup.reset_title"),
+            prompt: t_with("cleanup.reset_prompt", &targets),
+            confirm: t("cleanup.reset"),
+            auth: t("auth.reset"),
+            done: t("cleanup.reset_done"),
+        },
+    }
+}
+
+pub(super) async fn run(app: AppHandle, state: Arc<AppState>, purpose: Purpose) {
+    let Some(guard) = UpdateGuard::try_acquire(state.update_in_flight.clone()) else {
+        info!("Update/switch already in progress; ignoring {purpose:?}");
+        return;
+    };
+    let Some(mut locations) = Locations::resolve() else {
+        error!("Could not resolve the data directory to remove");
+        return;
+    };
+    locations.config_dir = state.daemon.config_dir().clone();
+    let scan = locations.clone();
+    let targets =
+        match tokio::task::spawn_blocking(move || cleanup::targets(&scan, purpose, false, false))
+            .await
+        {
+            Ok(targets) => targets,
+            Err(e) => {
+                error!("Failed to list the data to remove: {}", e);
+                return;
+            }
+        };
+    let texts = texts(purpose, &describe(&targets));
+    if targets.is_empty() {
+        crate::dialog::notice(
+            &app,
+            &texts.title,
+            t("cleanup.nothing"),
+            MessageDialogKind::Info,
+        )
+        .await;
+        return;
+    }
+
+    let confirmed = crate::dialog::confirm(
+        &app,
+        &texts.title,
+        texts.prompt,
+        &texts.confirm,
+        &t("common.cancel"),
+    )
+    .await;
+    if !confirmed {
+        return;
+    }
+    // Keeping the toolchains is the default: other PlatformIO use may share
+    // them, and they take long to download again.
+    let include_toolchains = purpose == Purpose::Uninstall
+        && match locations.platformio_dir.as_ref().filter(|dir| dir.exists()) {
+            Some(dir) => {
+                crate::dialog::confirm(
+                    &app,
+                    &texts.title,
+                    t_with(
+                        "cleanup.toolchains_prompt",
+                        &[("path", &dir.display().to_string())],
+                    ),
+                    &t("cleanup.remove_toolchains"),
+                    &t("cleanup.keep_toolchains"),
+                )
+                .await
+            }
+            None => false,
+        };
+    // Keeping the configs is the default: they are the one thing here that
+    // can't be downloaded or rebuilt.
+    let include_config = locations.config_dir.exists()
+        && crate::dialog::confirm(
+            &app,
+            &texts.title,
+            t_with(
+                "cleanup.configs_prompt",
+                &[("path", &locations.config_dir.display().to_string())],
+            ),
+            &t("cleanup.delete_configs"),
+            &t("cleanup.keep_configs"),
+        )
+        .await;
+    if !ops::authorize(&state, texts.auth, &|_, _| {}).await {
+        return;
+    }
+
+    info!(
+        "Removing app data for {purpose:?} (configs included: {include_config}, \
+         toolchains included: {include_toolchains})"
+    );
+    if let Err(e) = state.daemon.stop().await {
+        error!("Failed to stop the dashboard to remove app data: {:#}", e);
+        crate::dialog::notice(
+            &app,
+            &texts.title,
+            t_with(
+                "errors.stop_dashboard_failed",
+                &[("error", &e.user_message())],
+            ),
+            MessageDialogKind::Error,
+        )
+        .await;
+        return;
+    }
+    let removal = tokio::task::spawn_blocking(move || match purpose {
+        Purpose::Uninstall => cleanup::remove(&cleanup::targets(
+            &locations,
+            purpose,
+            include_config,
+            include_toolchains,
+        )),
+        Purpose::Reset => cleanup::reset(&locations, include_config),
+    })
+    .await;
+    let (message, kind) = match removal {
+        Ok(failures) if failures.is_empty() => (texts.done, MessageDialogKind::Info),
+        Ok(failures) => {
+            let paths: Vec<String> = failures
+                .iter()
+                .map(|(path, _)| path.display().to_string())
+                .collect();
+            (
+                t_with("cleanup.partial", &[("paths", &paths.join("\n"))]),
+                MessageDialogKind::Warning,
+            )
+        }
+        Err(e) => {
+            error!("App data removal task failed: {}", e);
+            (
+                t_with("cleanup.partial", &[("paths", &describe(&targets))]),
+                MessageDialogKind::Warning,
+            )
+        }
+    };
+    if purpose == Purpose::Reset {
+        let configs = if include_config { "deleted" } else { "kept" };
+        audit::record(
+            &app,
+            Source::Tray,
+            "reset",
+            format!("app data reset, configs {configs}"),
+        );
+    }
+    crate::dialog::notice(&app, &texts.title, message, kind).await;
+
+    // Like Quit: hold the guard for the rest of the process's life, so no
+    // update reinstalls what was just removed while the app winds down.
+    std::mem::forget(guard);
+    match purpose {
+        Purpose::Uninstall => app.exit(0),
+        Purpose::Reset => crate::platform::relaunch_for_update(&app),
+    }
+}
+
+/// One line per target: what it is, its size, where.
+fn describe(targets: &[Target]) -> String {
+    targets
+        .iter()
+        .map(|target| {
+            format!(
+                "• {} ({}): {}",
+                label(target.kind),
+                cleanup::format_size(target.bytes),
+                target.path.display()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn label(kind: Kind) -> String {
+    match kind {
+        Kind::PythonEnv => t("cleanup.python_env"),
+        Kind::AppData => t("cleanup.app_data"),
+        Kind::BuildCache => t("cleanup.build_cache"),
+        Kind::Toolchains => t("cleanup.toolchains"),
+        Kind::Configs => t("cleanup.configs"),
+    }
+}
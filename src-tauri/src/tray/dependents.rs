@@ -0,0 +1,146 @@
+//! The tray's "Check Devices Using Changed Package..." item (see
+//! [`crate::package_watch`]): generate the code of, or only validate, every
+//! device using the changed file, one after another in the background, and
+//! report which break. Dismissing the choice validates, the quicker one.
+
+use std::sync::{Arc, Mutex};
+
+use tauri::AppHandle;
+use tauri_plugin_dialog::MessageDialogKind;
+use tracing::{error, info, warn};
+
+use crate::control::ops::UpdateGuard;
+use crate::devices::batch::{self, BatchAction};
+use crate::devices::packages;
+use crate::devices::registry::Registry;
+use crate::devices::sources::AllowList;
+use crate::i18n::{t, t_with};
+use crate::store::Event;
+use crate::util::ConfigStore;
+use crate::AppState;
+
+pub(super) async fn run(app: AppHandle, state: Arc<AppState>) {
+    let Some(file) = state.store.snapshot().changed_package else {
+        return;
+    };
+    let config_dir = state.daemon.config_dir().clone();
+    let graph = tokio::task::spawn_blocking(move || packages::graph(&config_dir)).await;
+    let devices = match graph {
+        Ok(Ok(graph)) => graph
+            .packages
+            .into_iter()
+            .find(|p| p.name == file)
+            .map(|p| p.devices)
+            .unwrap_or_default(),
+        Ok(Err(e)) => {
+            error!("Failed to find the devices using {}: {:#}", file, e);
+            return;
+        }
+        Err(e) => {
+            error!("Shared package scan panicked: {}", e);
+            return;
+        }
+    };
+    if devices.is_empty() {
+        // No longer included anywhere since it changed.
+        state.store.publish(Event::PackageChanged(None));
+        return;
+    }
+    let generate = crate::dialog::confirm(
+        &app,
+        &t("dependents.title"),
+        t_with(
+            "dependents.choose",
+            &[
+                ("file", &file),
+                ("count", &devices.len().to_string()),
+                ("devices", &devices.join(", ")),
+            ],
+        ),
+        &t("dependents.generate"),
+        &t("dependents.validate"),
+    )
+    .await;
+    let action = if generate {
+        BatchAction::Generate
+    } else {
+        BatchAction::Validate
+    };
+    // Holds the guard so an update can't swap ESPHome out mid-check.
+    let Some(_guard) = UpdateGuard::try_acquire(state.update_in_flight.clone()) else {
+        info!("Update/switch already in progress; ignoring the package check");
+        return;
+    };
+    // A change during the check offers the next one.
+    state.store.publish(Event::PackageChanged(None));
+
+    let loaded = crate::platform::get_data_dir(&app)
+        .map_err(anyhow::Error::from)
+        .and_then(|dir| {
+            let allow = AllowList::load(&AllowList::path(&dir))?;
+            let registry = Registry::load(&Registry::path(&dir))?;
+            Ok((allow, registry, dir))
+        });
+    let (allow, registry, data_dir) = match loaded {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            error!("Failed to load the device lists: {:#}", e);
+            return;
+        }
+    };
+    let count = devices.len().to_string();
+    let body = t_with("dependents.started", &[("file", &file), ("count", &count)]);
+    if let Err(e) = crate::notifications::show(&app, t("dependents.title"), body) {
+        warn!("Failed to show package check notification: {}", e);
+    }
+    let lines = Mutex::new(Vec::new());
+    let progress = |_: &str, line: &str| {
+        // Only failures and warnings are reported, not each device's start.
+        if !line.starts_with(action.doing()) {
+            lines
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(line.to_string());
+        }
+    };
+    let report = batch::run(
+        &state.daemon,
+        action,
+        &devices,
+        &allow,
+        &registry,
+        &data_dir,
+        &progress,
+    )
+    .await;
+    if report.failed.is_empty() {
+        let body = t_with(
+            if generate {
+                "dependents.all_generated"
+            } else {
+                "dependents.all_validated"
+            },
+            &[("file", &file), ("count", &count)],
+        );
+        if let Err(e) = crate::notifications::show(&app, t("dependents.title"), body) {
+            warn!("Failed to show package check notification: {}", e);
+        }
+        return;
+    }
+    let lines = lines.into_inner().unwrap_or_else(|e| e.into_inner());
+    crate::dialog::notice(
+        &app,
+        &t("dependents.title"),
+        t_with(
+            "dependents.failed",
+            &[
+                ("file", &file),
+                ("failed", &report.failed.len().to_string()),
+                ("count", &count),
+                ("output", &lines.join("\n")),
+            ],
+        ),
+        MessageDialogKind::Warning,
+    )
+    .await;
+}
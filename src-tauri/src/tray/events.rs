@@ -9,21 +9,64 @@ use std::sync::Arc;
 
 use tauri::{async_runtime, AppHandle};
 use tauri_plugin_dialog::MessageDialogKind;
-use tauri_plugin_notification::NotificationExt;
 use tracing::{error, info, warn};
 
+use crate::audit::{self, Source};
+use crate::cleanup::Purpose;
 use crate::control::ops::{self, SwitchOutcome, UpdateGuard};
+use crate::daemon::DaemonError;
+use crate::error::UserError;
 use crate::i18n::{t, t_with};
+use crate::notifications::Pause;
 use crate::settings::{Backend, ReleaseChannel};
+use crate::store::{Event, RestartReason};
 use crate::AppState;
 
 use super::ids;
-use super::{
-    refresh_builder_version_display, refresh_version_display, update_backend_checks,
-    update_channel_checks,
-};
+use super::preferences::Preferences;
+use super::{refresh_builder_version_display, refresh_version_display};
 
-pub(super) fn handle_menu_event(app_handle: &AppHandle, id: &str, state: &Arc<AppState>) {
+/// Middle-click on the tray icon: restart the dashboard and confirm with a
+/// notification, unless `middle_click_restart` is off. A power-user shortcut
+/// for rapid config iteration; it takes the same guard as the Restart item.
+pub(crate) fn handle_tray_middle_click(app_handle: &AppHandle, state: &Arc<AppState>) {
+    let state = state.clone();
+    let app = app_handle.clone();
+    async_runtime::spawn(async move {
+        if state.kiosk || !state.settings.read().await.middle_click_restart {
+            return;
+        }
+        let Some(guard) = UpdateGuard::try_acquire(state.update_in_flight.clone()) else {
+            info!("Update/switch already in progress; ignoring middle-click restart");
+            return;
+        };
+        info!("Restarting ESPHome backend (tray middle-click)");
+        let result = ops::restart_daemon(&state, true, &guard, &|_, _| {}).await;
+        audit_restart(&app, "middle-click", &result);
+        let body = match result {
+            Ok(true) => t("daemon.restarted_body"),
+            Ok(false) => t("daemon.restarted_not_ready_body"),
+            Err(e) => {
+                error!("Failed to restart daemon: {}", e);
+                t_with(
+                    "daemon.restart_failed_body",
+                    &[("error", &e.user_message())],
+                )
+            }
+        };
+        drop(guard);
+        if let Err(e) = crate::notifications::show(&app, t("daemon.restarted_title"), body) {
+            warn!("Failed to show restart notification: {}", e);
+        }
+    });
+}
+
+pub(super) fn handle_menu_event(
+    app_handle: &AppHandle,
+    id: &str,
+    state: &Arc<AppState>,
+    preferences: &Preferences,
+) {
     /// Acquire the `UpdateGuard` or log and `return` from the spawned task.
     /// Collapses the acquire-or-bail boilerplate shared by the three multi-step
     /// menu arms while preserving the `return`-in-closure control flow.
@@ -39,213 +82,71 @@ pub(super) fn handle_menu_event(app_handle: &AppHandle, id: &str, state: &Arc<Ap
         };
     }
 
+    // The kiosk menu has nothing else, but a stale or synthesized event must
+    // not reach an action the policy hides.
+    if state.kiosk && !ids::KIOSK.contains(&id) {
+        warn!("Kiosk mode is on; ignoring menu item {}", id);
+        return;
+    }
+
+    if preferences.handle(app_handle, id, state) {
+        return;
+    }
+    if let Some(device) = id.strip_prefix(ids::DEVICE_SHORTCUT) {
+        let device = device.to_string();
+        let state = state.clone();
+        let app = app_handle.clone();
+        async_runtime::spawn(
+            async move { super::layout::open_shortcut(&app, &state, &device).await },
+        );
+        return;
+    }
+
     match id {
-        ids::OPEN_DASHBOARD => {
-            let settings = async_runtime::block_on(state.settings.read());
-            crate::open_dashboard(settings.port);
-        }
-        ids::STARTUP_ENABLE | ids::STARTUP_DISABLE => {
-            let enable = id == ids::STARTUP_ENABLE;
-            let state = state.clone();
-            let app = app_handle.clone();
-            async_runtime::spawn(async move {
-                ops::set_launch_at_startup(&app, &state, enable).await;
-            });
-        }
-        ids::CHECK_UPDATES => {
+        ids::OPEN_DASHBOARD => crate::open_dashboard(state),
+        ids::LANGUAGE_SERVER_ON | ids::LANGUAGE_SERVER_OFF => {
+            let enable = id == ids::LANGUAGE_SERVER_ON;
             let state = state.clone();
             let app = app_handle.clone();
             async_runtime::spawn(async move {
-                let _guard = guard_or_return!(state, "Check for Updates");
-                // Always check the desktop app first. Installing a self-update
-                // replaces the bundled `python/` directory, which would wipe
-                // any pip bump we do here. If the user accepts the app update
-                // (or it errors mid-install), skip the Python checks; if they
-                // decline or there's no app update, fall through.
-                if crate::app_update::check_for_user(&app, false).await
-                    == crate::app_update::NextStep::Skip
-                {
-                    return;
-                }
-
-                let (channel, backend) = {
-                    let settings = state.settings.read().await;
-                    (settings.release_channel, settings.backend)
-                };
-
-                // Check for updates and get version if user wants to update
-                if let Some(version) = state.update_checker.check_for_user(&app, channel).await {
-                    info!("User requested update to version {}", version);
-
-                    // Stop the dashboard
-                    if let Err(e) = state.daemon.stop().await {
-                        error!("Failed to stop backend for update: {}", e);
-                        crate::dialog::notice(
-                            &app,
-                            &t("update.update_failed_title"),
-                            t_with("errors.stop_dashboard_failed", &[("error", &e.to_string())]),
-                            MessageDialogKind::Error,
-                        )
-                        .await;
-                        return;
-                    }
-
-                    // Perform the update
-                    match state
-                        .update_checker
-                        .update_to(&app, &version, channel)
-                        .await
-                    {
-                        Ok(()) => {
-                            info!("Update completed successfully");
-
-                            // Update the version display in the tray menu, off
-                            // the async executor (detection spawns a Python
-                            // subprocess) — mirrors the device-builder arm below.
-                            let refresh_app = app.clone();
-                            let _ = tokio::task::spawn_blocking(move || {
-                                refresh_version_display(&refresh_app)
-                            })
-                            .await;
-
-                            // Restart the dashboard
-                            if let Err(e) = state.daemon.start().await {
-                                error!("Failed to restart backend after update: {}", e);
-                                crate::dialog::notice(
-                                    &app,
-                                    &t("update.update_partial_title"),
-                                    t_with(
-                                        "update.esphome_partial",
-                                        &[("version", version.as_str()), ("error", &e.to_string())],
-                                    ),
-                                    MessageDialogKind::Warning,
-                                )
-                                .await;
-                            } else {
-                                let msg = if channel == ReleaseChannel::Dev {
-                                    t("update.esphome_updated_dev")
-                                } else {
-                                    t_with("update.esphome_updated", &[("version", &version)])
-                                };
-                                crate::dialog::notice(
-                                    &app,
-                                    &t("update.update_complete_title"),
-                                    msg,
-                                    MessageDialogKind::Info,
-                                )
-                                .await;
-                            }
-                        }
-                        Err(e) => {
-                            error!("Update failed: {}", e);
-                            crate::dialog::notice(
-                                &app,
-                                &t("update.update_failed_title"),
-                                t_with(
-                                    "update.esphome_update_failed",
-                                    &[("error", &e.to_string())],
-                                ),
-                                MessageDialogKind::Error,
-                            )
-                            .await;
-
-                            // Try to restart dashboard anyway
-                            if let Err(restart_err) = state.daemon.start().await {
-                                error!(
-                                    "Failed to restart backend after failed update: {}",
-                                    restart_err
-                                );
-                            }
-                        }
-                    }
-                }
-
-                // Also check `esphome-device-builder`, independent of the
-                // ESPHome release channel.
-                let Some(builder_version) = state
-                    .update_checker
-                    .check_device_builder_for_user(&app, backend)
-                    .await
-                else {
-                    return;
-                };
-                info!(
-                    "User requested device-builder update to version {}",
-                    builder_version
-                );
-
-                if let Err(e) = state.daemon.stop().await {
-                    error!("Failed to stop backend for device-builder update: {}", e);
+                if let Err(e) = ops::set_language_server(&app, &state, enable, None).await {
+                    error!("Failed to start the language server: {:#}", e);
                     crate::dialog::notice(
                         &app,
-                        &t("update.update_failed_title"),
-                        t_with("errors.stop_backend_failed", &[("error", &e.to_string())]),
+                        &t("tray.language_server"),
+                        t_with(
+                            "errors.language_server_failed",
+                            &[("error", &format!("{e:#}"))],
+                        ),
                         MessageDialogKind::Error,
                     )
                     .await;
-                    return;
-                }
-
-                match state
-                    .update_checker
-                    .install_device_builder(&app, backend)
-                    .await
-                {
-                    Ok(()) => {
-                        info!("Device builder updated successfully to {}", builder_version);
-
-                        // Refresh the device-builder version display in the tray menu
-                        refresh_builder_version_display(&app).await;
-
-                        if let Err(e) = state.daemon.start().await {
-                            error!(
-                                "Failed to restart backend after device-builder update: {}",
-                                e
-                            );
-                            crate::dialog::notice(
-                                &app,
-                                &t("update.update_partial_title"),
-                                t_with(
-                                    "update.builder_partial",
-                                    &[
-                                        ("version", builder_version.as_str()),
-                                        ("error", &e.to_string()),
-                                    ],
-                                ),
-                                MessageDialogKind::Warning,
-                            )
-                            .await;
-                        } else {
-                            crate::dialog::notice(
-                                &app,
-                                &t("update.update_complete_title"),
-                                t_with("update.builder_updated", &[("version", &builder_version)]),
-                                MessageDialogKind::Info,
-                            )
-                            .await;
-                        }
-                    }
-                    Err(e) => {
-                        error!("Device-builder update failed: {}", e);
-                        crate::dialog::notice(
-                            &app,
-                            &t("update.update_failed_title"),
-                            t_with("update.builder_update_failed", &[("error", &e.to_string())]),
-                            MessageDialogKind::Error,
-                        )
-                        .await;
-
-                        // Try to restart backend anyway
-                        if let Err(restart_err) = state.daemon.start().await {
-                            error!(
-                                "Failed to restart backend after failed device-builder update: {}",
-                                restart_err
-                            );
-                        }
-                    }
                 }
             });
         }
+        ids::PAUSE_1H | ids::PAUSE_8H | ids::PAUSE_UNTIL_RESTART | ids::RESUME_NOTIFICATIONS => {
+            let pause = match id {
+                ids::PAUSE_1H => Some(Pause::OneHour),
+                ids::PAUSE_8H => Some(Pause::EightHours),
+                ids::PAUSE_UNTIL_RESTART => Some(Pause::UntilRestart),
+                _ => None,
+            };
+            let state = state.clone();
+            let app = app_handle.clone();
+            async_runtime::spawn(async move {
+                ops::set_notifications_paused(&app, &state, pause).await;
+            });
+        }
+        ids::STATUS_OVERVIEW => {
+            let state = state.clone();
+            let app = app_handle.clone();
+            async_runtime::spawn(async move {
+                super::overview::show_status_overview(&app, &state).await;
+            });
+        }
+        ids::CHECK_UPDATES => {
+            async_runtime::spawn(check_for_updates(app_handle.clone(), state.clone()));
+        }
         ids::CHANNEL_STABLE | ids::CHANNEL_BETA | ids::CHANNEL_DEV => {
             let new_channel = match id {
                 ids::CHANNEL_STABLE => ReleaseChannel::Stable,
@@ -290,18 +191,25 @@ pub(super) fn handle_menu_event(app_handle: &AppHandle, id: &str, state: &Arc<Ap
                 )
                 .await;
 
-                if !confirmed {
+                let reason = t_with(
+                    "auth.switch_channel",
+                    &[("channel", &new_channel.to_string())],
+                );
+                if !confirmed || !ops::authorize(&state, reason, &|_, _| {}).await {
                     // Revert the check marks
-                    update_channel_checks(old_channel);
+                    state.store.publish(Event::Channel(old_channel));
                     return;
                 }
 
                 // The stop→install→persist→start sequence (including label
                 // updates and their failure-path reverts) lives in ops so the
                 // CLI drives the exact same code; the tray adds the dialogs.
-                match ops::switch_release_channel(&app, &state, new_channel, &guard, &|_, _| {})
-                    .await
-                {
+                let outcome =
+                    ops::switch_release_channel(&app, &state, new_channel, &guard, &|_, _| {})
+                        .await;
+                let target = format!("to the {new_channel} channel");
+                audit_switch(&app, "set_channel", &target, &outcome);
+                match outcome {
                     SwitchOutcome::Unchanged => {}
                     SwitchOutcome::Success { .. } => {
                         let msg = t_with(
@@ -320,7 +228,10 @@ pub(super) fn handle_menu_event(app_handle: &AppHandle, id: &str, state: &Arc<Ap
                         crate::dialog::notice(
                             &app,
                             &t("switch_channel.failed_title"),
-                            t_with("errors.stop_dashboard_failed", &[("error", &e.to_string())]),
+                            t_with(
+                                "errors.stop_dashboard_failed",
+                                &[("error", &e.user_message())],
+                            ),
                             MessageDialogKind::Error,
                         )
                         .await;
@@ -329,7 +240,7 @@ pub(super) fn handle_menu_event(app_handle: &AppHandle, id: &str, state: &Arc<Ap
                         crate::dialog::notice(
                             &app,
                             &t("switch_channel.failed_title"),
-                            t_with("switch_channel.failed", &[("error", &error.to_string())]),
+                            t_with("switch_channel.failed", &[("error", &error.user_message())]),
                             MessageDialogKind::Error,
                         )
                         .await;
@@ -342,7 +253,7 @@ pub(super) fn handle_menu_event(app_handle: &AppHandle, id: &str, state: &Arc<Ap
                                 "switch_channel.partial",
                                 &[
                                     ("channel", &new_channel.to_string()),
-                                    ("error", &e.to_string()),
+                                    ("error", &e.user_message()),
                                 ],
                             ),
                             MessageDialogKind::Warning,
@@ -386,8 +297,12 @@ pub(super) fn handle_menu_event(app_handle: &AppHandle, id: &str, state: &Arc<Ap
                 )
                 .await;
 
-                if !confirmed {
-                    update_backend_checks(old_backend);
+                let reason = t_with(
+                    "auth.switch_backend",
+                    &[("backend", &new_backend.to_string())],
+                );
+                if !confirmed || !ops::authorize(&state, reason, &|_, _| {}).await {
+                    state.store.publish(Event::Backend(old_backend));
                     return;
                 }
 
@@ -395,7 +310,10 @@ pub(super) fn handle_menu_event(app_handle: &AppHandle, id: &str, state: &Arc<Ap
                 // label updates and their failure-path reverts) lives in ops
                 // so the CLI drives the exact same code; the tray adds the
                 // dialogs and the readiness notification.
-                match ops::switch_backend(&app, &state, new_backend, &guard, &|_, _| {}).await {
+                let outcome =
+                    ops::switch_backend(&app, &state, new_backend, &guard, &|_, _| {}).await;
+                audit_switch(&app, "set_backend", &format!("to {new_backend}"), &outcome);
+                match outcome {
                     SwitchOutcome::Unchanged => {}
                     SwitchOutcome::Success { ready } => {
                         let body = if ready {
@@ -409,13 +327,11 @@ pub(super) fn handle_menu_event(app_handle: &AppHandle, id: &str, state: &Arc<Ap
                                 &[("backend", &new_backend.to_string())],
                             )
                         };
-                        if let Err(e) = app
-                            .notification()
-                            .builder()
-                            .title(t("switch_backend.switched_title"))
-                            .body(body)
-                            .show()
-                        {
+                        if let Err(e) = crate::notifications::show(
+                            &app,
+                            t("switch_backend.switched_title"),
+                            body,
+                        ) {
                             warn!("Failed to show backend-switch notification: {}", e);
                         }
                     }
@@ -423,7 +339,10 @@ pub(super) fn handle_menu_event(app_handle: &AppHandle, id: &str, state: &Arc<Ap
                         crate::dialog::notice(
                             &app,
                             &t("switch_backend.failed_title"),
-                            t_with("errors.stop_backend_failed", &[("error", &e.to_string())]),
+                            t_with(
+                                "errors.stop_backend_failed",
+                                &[("error", &e.user_message())],
+                            ),
                             MessageDialogKind::Error,
                         )
                         .await;
@@ -434,7 +353,7 @@ pub(super) fn handle_menu_event(app_handle: &AppHandle, id: &str, state: &Arc<Ap
                             &t("switch_backend.failed_title"),
                             t_with(
                                 "switch_backend.install_failed",
-                                &[("error", &error.to_string())],
+                                &[("error", &error.user_message())],
                             ),
                             MessageDialogKind::Error,
                         )
@@ -444,7 +363,10 @@ pub(super) fn handle_menu_event(app_handle: &AppHandle, id: &str, state: &Arc<Ap
                         crate::dialog::notice(
                             &app,
                             &t("switch_backend.failed_title"),
-                            t_with("switch_backend.start_failed", &[("error", &e.to_string())]),
+                            t_with(
+                                "switch_backend.start_failed",
+                                &[("error", &e.user_message())],
+                            ),
                             MessageDialogKind::Error,
                         )
                         .await;
@@ -464,8 +386,87 @@ pub(super) fn handle_menu_event(app_handle: &AppHandle, id: &str, state: &Arc<Ap
                 error!("Failed to open config folder: {}", e);
             }
         }
+        ids::EDIT_CONFIG => {
+            let state = state.clone();
+            let app = app_handle.clone();
+            async_runtime::spawn(async move { edit_config(&app, &state).await });
+        }
+        ids::RENAME_DEVICE => {
+            if let Err(e) = crate::rename::open(app_handle) {
+                error!("Failed to open device rename: {}", e);
+            }
+        }
+        ids::SEARCH_CONFIGS => {
+            if let Err(e) = crate::search::open(app_handle) {
+                error!("Failed to open config search: {}", e);
+            }
+        }
+        ids::PIN_USAGE => {
+            if let Err(e) = crate::pins::open(app_handle) {
+                error!("Failed to open pin usage: {}", e);
+            }
+        }
+        ids::SHARED_PACKAGES => {
+            if let Err(e) = crate::packages::open(app_handle) {
+                error!("Failed to open shared packages: {}", e);
+            }
+        }
+        ids::DUPLICATES => {
+            if let Err(e) = crate::duplicates::open(app_handle) {
+                error!("Failed to open duplicate devices: {}", e);
+            }
+        }
+        ids::SIGNING_KEYS => {
+            if let Err(e) = crate::signing::open(app_handle) {
+                error!("Failed to open signing keys: {}", e);
+            }
+        }
+        ids::SETTINGS => {
+            if let Err(e) = crate::settings_window::open(app_handle) {
+                error!("Failed to open settings: {}", e);
+            }
+        }
+        ids::VERSIONS => {
+            if let Err(e) = crate::versions::open(app_handle) {
+                error!("Failed to open ESPHome versions: {}", e);
+            }
+        }
+        ids::PYTHON_PACKAGES => {
+            if let Err(e) = crate::python_packages::open(app_handle) {
+                error!("Failed to open Python packages: {}", e);
+            }
+        }
+        ids::UNDO_CHANGE => {
+            async_runtime::spawn(super::undo::run(app_handle.clone()));
+        }
+        ids::SETUP_VSCODE => {
+            async_runtime::spawn(super::vscode::run(app_handle.clone(), state.clone()));
+        }
+        ids::SHARE => {
+            async_runtime::spawn(super::share::run(app_handle.clone(), state.clone()));
+        }
+        ids::USE_SNIPPET => {
+            async_runtime::spawn(super::snippet::run(app_handle.clone(), state.clone()));
+        }
+        ids::CHECK_DEPENDENTS => {
+            async_runtime::spawn(super::dependents::run(app_handle.clone(), state.clone()));
+        }
+        ids::SCRATCHPAD => {
+            if let Err(e) = crate::scratchpad::open(app_handle) {
+                error!("Failed to open the scratchpad: {}", e);
+            }
+        }
+        ids::TERMINAL => {
+            if let Err(e) = crate::terminal::open(app_handle) {
+                error!("Failed to open the terminal: {}", e);
+            }
+        }
+        ids::TOGGLE_DASHBOARD => {
+            async_runtime::spawn(super::power::toggle(app_handle.clone(), state.clone()));
+        }
         ids::RESTART => {
             let state = state.clone();
+            let app = app_handle.clone();
             async_runtime::spawn(async move {
                 // restart() is a stop()->start() sequence, so it must hold the
                 // same re-entrancy guard as the channel/backend switch arms.
@@ -475,11 +476,44 @@ pub(super) fn handle_menu_event(app_handle: &AppHandle, id: &str, state: &Arc<Ap
                 // saved settings and tray radio state.
                 let guard = guard_or_return!(state, "restart");
                 info!("Restarting ESPHome backend");
-                if let Err(e) = ops::restart_daemon(&state, false, &guard, &|_, _| {}).await {
+                let result = ops::restart_daemon(&state, false, &guard, &|_, _| {}).await;
+                audit_restart(&app, "menu", &result);
+                if let Err(e) = result {
                     error!("Failed to restart daemon: {}", e);
                 }
             });
         }
+        ids::CANCEL_BUILD => {
+            let state = state.clone();
+            let app = app_handle.clone();
+            async_runtime::spawn(async move {
+                info!("Cancelling the current build");
+                let body = match state.daemon.cancel_build().await {
+                    Ok(0) => t("daemon.no_build_running"),
+                    Ok(_) => t("daemon.build_cancelled"),
+                    Err(e) => {
+                        error!("Failed to cancel the build: {:#}", e);
+                        t_with(
+                            "daemon.cancel_build_failed",
+                            &[("error", &e.user_message())],
+                        )
+                    }
+                };
+                if let Err(e) =
+                    crate::notifications::show(&app, t("daemon.cancel_build_title"), body)
+                {
+                    warn!("Failed to show build-cancel notification: {}", e);
+                }
+            });
+        }
+        ids::RESET => {
+            let run = super::cleanup::run(app_handle.clone(), state.clone(), Purpose::Reset);
+            async_runtime::spawn(run);
+        }
+        ids::UNINSTALL_DATA => {
+            let run = super::cleanup::run(app_handle.clone(), state.clone(), Purpose::Uninstall);
+            async_runtime::spawn(run);
+        }
         ids::QUIT => {
             // Refuse to tear the app down while an update/switch is mid-flight:
             // exiting now would orphan a pip install mid-write and corrupt the
@@ -499,3 +533,256 @@ pub(super) fn handle_menu_event(app_handle: &AppHandle, id: &str, state: &Arc<Ap
         _ => {}
     }
 }
+
+/// Check for Updates: the desktop app first, then ESPHome and the device
+/// builder, each installed once the user agrees. The Versions window's
+/// Update button runs it too.
+pub(crate) async fn check_for_updates(app: AppHandle, state: Arc<AppState>) {
+    let Some(_guard) = UpdateGuard::try_acquire(state.update_in_flight.clone()) else {
+        info!("Update/switch already in progress; ignoring Check for Updates");
+        return;
+    };
+    // Always check the desktop app first. Installing a self-update
+    // replaces the bundled `python/` directory, which would wipe
+    // any pip bump we do here. If the user accepts the app update
+    // (or it errors mid-install), skip the Python checks; if they
+    // decline or there's no app update, fall through.
+    if crate::app_update::check_for_user(&app, false).await == crate::app_update::NextStep::Skip {
+        return;
+    }
+
+    let (channel, backend) = {
+        let settings = state.settings.read().await;
+        (settings.release_channel, settings.backend)
+    };
+
+    // Check for updates and get version if user wants to update
+    if let Some(version) = state.update_checker.check_for_user(&app, channel).await {
+        info!("User requested update to version {}", version);
+        if !ops::authorize(&state, t("auth.update"), &|_, _| {}).await {
+            return;
+        }
+
+        // Stop the dashboard
+        if let Err(e) = state.daemon.stop().await {
+            error!("Failed to stop backend for update: {}", e);
+            crate::dialog::notice(
+                &app,
+                &t("update.update_failed_title"),
+                t_with(
+                    "errors.stop_dashboard_failed",
+                    &[("error", &e.user_message())],
+                ),
+                MessageDialogKind::Error,
+            )
+            .await;
+            return;
+        }
+
+        // Perform the update
+        match state
+            .update_checker
+            .update_to(&app, &version, channel)
+            .await
+        {
+            Ok(()) => {
+                info!("Update completed successfully");
+                let detail = format!("ESPHome updated to {version}");
+                audit::record(&app, Source::Tray, "update", detail);
+
+                // Update the version display in the tray menu, off
+                // the async executor (detection spawns a Python
+                // subprocess) — mirrors the device-builder arm below.
+                let refresh_app = app.clone();
+                let _ = tokio::task::spawn_blocking(move || refresh_version_display(&refresh_app))
+                    .await;
+
+                // Restart the dashboard
+                if let Err(e) = state.daemon.start_for(RestartReason::Update).await {
+                    error!("Failed to restart backend after update: {}", e);
+                    crate::dialog::notice(
+                        &app,
+                        &t("update.update_partial_title"),
+                        t_with(
+                            "update.esphome_partial",
+                            &[("version", version.as_str()), ("error", &e.user_message())],
+                        ),
+                        MessageDialogKind::Warning,
+                    )
+                    .await;
+                } else {
+                    let msg = if channel == ReleaseChannel::Dev {
+                        t("update.esphome_updated_dev")
+                    } else {
+                        t_with("update.esphome_updated", &[("version", &version)])
+                    };
+                    crate::dialog::notice(
+                        &app,
+                        &t("update.update_complete_title"),
+                        msg,
+                        MessageDialogKind::Info,
+                    )
+                    .await;
+                }
+            }
+            Err(e) => {
+                error!("Update failed: {}", e);
+                let detail = format!("ESPHome update to {version} failed: {e}");
+                audit::record(&app, Source::Tray, "update", detail);
+                crate::dialog::notice(
+                    &app,
+                    &t("update.update_failed_title"),
+                    t_with(
+                        "update.esphome_update_failed",
+                        &[("error", &e.user_message())],
+                    ),
+                    MessageDialogKind::Error,
+                )
+                .await;
+
+                // Try to restart dashboard anyway
+                if let Err(restart_err) = state.daemon.start_for(RestartReason::Update).await {
+                    error!(
+                        "Failed to restart backend after failed update: {}",
+                        restart_err
+                    );
+                }
+            }
+        }
+    }
+
+    // Also check `esphome-device-builder`, independent of the
+    // ESPHome release channel.
+    let Some(builder_version) = state
+        .update_checker
+        .check_device_builder_for_user(&app, backend)
+        .await
+    else {
+        return;
+    };
+    info!(
+        "User requested device-builder update to version {}",
+        builder_version
+    );
+    if !ops::authorize(&state, t("auth.update"), &|_, _| {}).await {
+        return;
+    }
+
+    if let Err(e) = state.daemon.stop().await {
+        error!("Failed to stop backend for device-builder update: {}", e);
+        crate::dialog::notice(
+            &app,
+            &t("update.update_failed_title"),
+            t_with(
+                "errors.stop_backend_failed",
+                &[("error", &e.user_message())],
+            ),
+            MessageDialogKind::Error,
+        )
+        .await;
+        return;
+    }
+
+    match state
+        .update_checker
+        .install_device_builder(&app, backend)
+        .await
+    {
+        Ok(()) => {
+            info!("Device builder updated successfully to {}", builder_version);
+            let detail = format!("device builder updated to {builder_version}");
+            audit::record(&app, Source::Tray, "update", detail);
+
+            // Refresh the device-builder version display in the tray menu
+            refresh_builder_version_display(&app).await;
+
+            if let Err(e) = state.daemon.start_for(RestartReason::Update).await {
+                error!(
+                    "Failed to restart backend after device-builder update: {}",
+                    e
+                );
+                crate::dialog::notice(
+                    &app,
+                    &t("update.update_partial_title"),
+                    t_with(
+                        "update.builder_partial",
+                        &[
+                            ("version", builder_version.as_str()),
+                            ("error", &e.user_message()),
+                        ],
+                    ),
+                    MessageDialogKind::Warning,
+                )
+                .await;
+            } else {
+                crate::dialog::notice(
+                    &app,
+                    &t("update.update_complete_title"),
+                    t_with("update.builder_updated", &[("version", &builder_version)]),
+                    MessageDialogKind::Info,
+                )
+                .await;
+            }
+        }
+        Err(e) => {
+            error!("Device-builder update failed: {}", e);
+            let detail = format!("device builder update to {builder_version} failed: {e}");
+            audit::record(&app, Source::Tray, "update", detail);
+            crate::dialog::notice(
+                &app,
+                &t("update.update_failed_title"),
+                t_with(
+                    "update.builder_update_failed",
+                    &[("error", &e.user_message())],
+                ),
+                MessageDialogKind::Error,
+            )
+            .await;
+
+            // Try to restart backend anyway
+            if let Err(restart_err) = state.daemon.start_for(RestartReason::Update).await {
+                error!(
+                    "Failed to restart backend after failed device-builder update: {}",
+                    restart_err
+                );
+            }
+        }
+    }
+}
+
+/// Ask which config to edit, starting in the config directory, and open it
+/// in the editor (see [`crate::devices::editor`]).
+async fn edit_config(app: &AppHandle, state: &Arc<AppState>) {
+    let config_dir = state.daemon.config_dir().clone();
+    let Some(path) = crate::dialog::pick_yaml(app, &t("tray.edit_config_pick"), config_dir).await
+    else {
+        return;
+    };
+    super::layout::edit(app, state, &path).await;
+}
+
+/// Record a tray restart and how it ended.
+fn audit_restart(app: &AppHandle, how: &str, result: &Result<bool, DaemonError>) {
+    let detail = match result {
+        Ok(true) => format!("dashboard restarted ({how})"),
+        Ok(false) => format!("dashboard restarted ({how}) but not responding"),
+        Err(e) => format!("dashboard restart ({how}) failed: {e}"),
+    };
+    audit::record(app, Source::Tray, "restart", detail);
+}
+
+/// Record a tray channel/backend switch and how it ended.
+fn audit_switch(app: &AppHandle, action: &str, target: &str, outcome: &SwitchOutcome) {
+    let detail = match outcome {
+        SwitchOutcome::Unchanged => return,
+        SwitchOutcome::Success { .. } => format!("switched {target}"),
+        SwitchOutcome::StopFailed(e) => {
+            format!("switch {target} failed to stop the dashboard: {e}")
+        }
+        SwitchOutcome::InstallFailed { error, .. } => format!("switch {target} failed: {error}"),
+        SwitchOutcome::StartFailed(e) => {
+            format!("switched {target}, but the dashboard failed to start: {e}")
+        }
+    };
+    audit::record(app, Source::Tray, action, detail);
+}
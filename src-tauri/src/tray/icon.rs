@@ -0,0 +1,57 @@
+//! The tray icon follows the dashboard: faded, with a tooltip that says so,
+//! while it isn't running, so a stopped dashboard shows without opening the
+//! menu.
+
+use tauri::image::Image;
+use tauri::tray::TrayIcon;
+use tracing::warn;
+
+use crate::i18n::t;
+use crate::store::{self, DaemonState, Event, Store};
+
+/// The tooltip while the dashboard runs. A product name, untranslated.
+pub(crate) const TOOLTIP: &str = "ESPHome Device Builder";
+
+/// Percent of its opacity the icon keeps while the dashboard is down.
+const FADED_OPACITY: u16 = 40;
+
+/// Keep `tray`, showing `icon`, in step with the dashboard's state in
+/// `store` from now on.
+pub(crate) fn follow(tray: TrayIcon, icon: Image<'static>, store: &Store) {
+    let faded = fade(&icon);
+    // Subscribe before reading the snapshot, so nothing published in
+    // between is lost.
+    let events = store.subscribe();
+    let show = move |state: &DaemonState| {
+        let running = matches!(state, DaemonState::Starting | DaemonState::Running);
+        let (image, tooltip) = if running {
+            (icon.clone(), TOOLTIP.to_string())
+        } else {
+            (faded.clone(), t("tray.tooltip_stopped"))
+        };
+        if let Err(e) = tray.set_icon(Some(image)) {
+            warn!("Failed to update the tray icon: {}", e);
+        }
+        if let Err(e) = tray.set_tooltip(Some(tooltip)) {
+            warn!("Failed to update the tray tooltip: {}", e);
+        }
+    };
+    show(&store.snapshot().daemon);
+    store::listen(events, move |event| {
+        if let Event::Daemon(state) = event {
+            show(&state);
+        }
+    });
+}
+
+/// `icon` with its alpha scaled down to [`FADED_OPACITY`]. Scaling alpha
+/// rather than color also works for the macOS template image, which the
+/// system recolors from its alpha.
+fn fade(icon: &Image<'_>) -> Image<'static> {
+    let mut rgba = icon.rgba().to_vec();
+    for pixel in rgba.chunks_exact_mut(4) {
+        // At most 255 * 100 / 100, so the cast can't truncate.
+        pixel[3] = (u16::from(pixel[3]) * FADED_OPACITY / 100) as u8;
+    }
+    Image::new_owned(rgba, icon.width(), icon.height())
+}
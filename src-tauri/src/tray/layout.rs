@@ -0,0 +1,102 @@
+//! The user's tray menu layout (`tray_menu` in the settings): hidden and
+//! reordered items, and device shortcuts at the top that open a config in
+//! the editor.
+//!
+//! Only the settings and tools sections can be changed. Open Dashboard, the
+//! status lines and Reset/Uninstall/Quit always show where they are, and
+//! kiosk mode ignores the layout.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use tauri::{menu::MenuItem, menu::MenuItemBuilder, AppHandle};
+use tauri_plugin_dialog::MessageDialogKind;
+use tracing::error;
+
+use super::ids;
+use crate::i18n::{t, t_with};
+use crate::settings::TrayMenuLayout;
+use crate::AppState;
+
+/// The items of a section the layout shows, in its order: those named in
+/// `order` first, the rest as they came.
+pub(super) fn arrange<T>(items: Vec<(&str, T)>, layout: &TrayMenuLayout) -> Vec<T> {
+    let mut items: Vec<(usize, T)> = items
+        .into_iter()
+        .filter(|(id, _)| !layout.hidden.iter().any(|hidden| hidden == id))
+        .map(|(id, item)| {
+            let rank = layout.order.iter().position(|o| o == id);
+            (rank.unwrap_or(usize::MAX), item)
+        })
+        .collect();
+    items.sort_by_key(|(rank, _)| *rank);
+    items.into_iter().map(|(_, item)| item).collect()
+}
+
+/// The menu item opening `device`'s config.
+pub(super) fn shortcut(app: &AppHandle, device: &str) -> tauri::Result<MenuItem<tauri::Wry>> {
+    MenuItemBuilder::with_id(
+        format!("{}{device}", ids::DEVICE_SHORTCUT),
+        t_with("tray.device_shortcut", &[("device", device)]),
+    )
+    .build(app)
+}
+
+/// Open `device`'s config from its shortcut.
+pub(super) async fn open_shortcut(app: &AppHandle, state: &Arc<AppState>, device: &str) {
+    match crate::devices::config_path(state.daemon.config_dir(), device) {
+        Some(path) => edit(app, state, &path).await,
+        None => {
+            crate::dialog::notice(
+                app,
+                &t("tray.edit_config_failed_title"),
+                t_with("tray.device_shortcut_missing", &[("device", device)]),
+                MessageDialogKind::Error,
+            )
+            .await;
+        }
+    }
+}
+
+/// Open `path` in the user's editor, saying so if that fails.
+pub(super) async fn edit(app: &AppHandle, state: &Arc<AppState>, path: &Path) {
+    let command = state.settings.read().await.workspace.editor_command.clone();
+    let result = crate::devices::editor::choose(command.as_deref(), None)
+        .and_then(|editor| crate::devices::editor::open(editor.as_ref(), path));
+    if let Err(e) = result {
+        error!("Failed to open {:?} in an editor: {:#}", path, e);
+        crate::dialog::notice(
+            app,
+            &t("tray.edit_config_failed_title"),
+            t_with("tray.edit_config_failed", &[("error", &format!("{e:#}"))]),
+            MessageDialogKind::Error,
+        )
+        .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hides_and_moves_items_keeping_the_rest_in_order() {
+        let items = vec![
+            ("view_logs", 1),
+            ("open_config", 2),
+            ("duplicates", 3),
+            ("restart", 4),
+        ];
+        let layout = TrayMenuLayout {
+            hidden: vec!["open_config".into()],
+            order: vec![
+                "restart".into(),
+                "check_updates".into(),
+                "duplicates".into(),
+            ],
+            shortcuts: Vec::new(),
+        };
+        assert_eq!(arrange(items.clone(), &layout), [4, 3, 1]);
+        assert_eq!(arrange(items, &TrayMenuLayout::default()), [1, 2, 3, 4]);
+    }
+}
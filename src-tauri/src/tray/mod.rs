@@ -6,32 +6,81 @@ use anyhow::Result;
 use std::sync::Arc;
 use tauri::{
     async_runtime,
-    menu::{Menu, MenuBuilder, MenuItem, MenuItemBuilder, SubmenuBuilder},
-    AppHandle,
+    menu::{IsMenuItem, Menu, MenuBuilder, MenuItem, MenuItemBuilder, SubmenuBuilder},
+    AppHandle, Manager,
 };
 use tauri_plugin_autostart::ManagerExt;
 use tracing::warn;
 
 use crate::i18n::{t, t_with};
+use crate::notifications::PauseState;
 use crate::settings::{Backend, ReleaseChannel};
+use crate::store::{self, DaemonState, Event, Usage};
 use crate::AppState;
 
+pub(crate) mod actions;
+mod cleanup;
+mod dependents;
 mod events;
+pub(crate) mod icon;
+mod layout;
+mod overview;
+mod power;
+mod preferences;
+mod share;
+mod snippet;
+mod undo;
+mod vscode;
+
+pub(crate) use events::{check_for_updates, handle_tray_middle_click};
 
 /// Menu item IDs
 mod ids {
     pub const OPEN_DASHBOARD: &str = "open_dashboard";
     pub const STATUS: &str = "status";
+    pub const LAST_USED: &str = "last_used";
     pub const APP_VERSION: &str = "app_version";
     pub const VERSION: &str = "version";
     pub const BUILDER_VERSION: &str = "builder_version";
     pub const PORT: &str = "port";
+    pub const STATUS_OVERVIEW: &str = "status_overview";
     pub const CHECK_UPDATES: &str = "check_updates";
+    pub const VERSIONS: &str = "versions";
+    pub const PYTHON_PACKAGES: &str = "python_packages";
+    pub const SETTINGS: &str = "settings";
     pub const VIEW_LOGS: &str = "view_logs";
     pub const OPEN_CONFIG: &str = "open_config";
+    pub const EDIT_CONFIG: &str = "edit_config";
+    pub const RENAME_DEVICE: &str = "rename_device";
+    pub const SEARCH_CONFIGS: &str = "search_configs";
+    pub const PIN_USAGE: &str = "pin_usage";
+    pub const SHARED_PACKAGES: &str = "shared_packages";
+    pub const DUPLICATES: &str = "duplicates";
+    pub const SIGNING_KEYS: &str = "signing_keys";
+    pub const UNDO_CHANGE: &str = "undo_change";
+    pub const SETUP_VSCODE: &str = "setup_vscode";
+    pub const SHARE: &str = "share";
+    pub const USE_SNIPPET: &str = "use_snippet";
+    pub const CHECK_DEPENDENTS: &str = "check_dependents";
+    pub const SCRATCHPAD: &str = "scratchpad";
+    pub const TERMINAL: &str = "terminal";
+    pub const TOGGLE_DASHBOARD: &str = "toggle_dashboard";
     pub const RESTART: &str = "restart";
+    pub const CANCEL_BUILD: &str = "cancel_build";
+    pub const RESET: &str = "reset";
+    pub const UNINSTALL_DATA: &str = "uninstall_data";
     pub const QUIT: &str = "quit";
 
+    // Submenus
+    pub const RELEASE_CHANNEL: &str = "release_channel";
+    pub const BACKEND: &str = "backend";
+    pub const PREFERENCES: &str = "preferences";
+    pub const LANGUAGE_SERVER: &str = "language_server";
+    pub const NOTIFICATIONS: &str = "notifications";
+
+    /// Prefix of a device shortcut's id, before the device name.
+    pub const DEVICE_SHORTCUT: &str = "open_device:";
+
     // Release channel submenu items
     pub const CHANNEL_STABLE: &str = "channel_stable";
     pub const CHANNEL_BETA: &str = "channel_beta";
@@ -41,25 +90,83 @@ mod ids {
     pub const BACKEND_BUILDER_STABLE: &str = "backend_builder_stable";
     pub const BACKEND_BUILDER_BETA: &str = "backend_builder_beta";
 
-    // Startup submenu items
-    pub const STARTUP_ENABLE: &str = "startup_enable";
-    pub const STARTUP_DISABLE: &str = "startup_disable";
+    // Preferences submenu items
+    pub const OPEN_ON_START: &str = "open_on_start";
+    pub const CHECK_UPDATES_AUTOMATICALLY: &str = "check_updates_automatically";
+    pub const SHOW_NOTIFICATIONS: &str = "show_notifications";
+    pub const LAUNCH_AT_LOGIN: &str = "launch_at_login";
+
+    // Language server submenu items
+    pub const LANGUAGE_SERVER_ON: &str = "language_server_on";
+    pub const LANGUAGE_SERVER_OFF: &str = "language_server_off";
+
+    // Notifications submenu items
+    pub const PAUSE_1H: &str = "pause_notifications_1h";
+    pub const PAUSE_8H: &str = "pause_notifications_8h";
+    pub const PAUSE_UNTIL_RESTART: &str = "pause_notifications_until_restart";
+    pub const RESUME_NOTIFICATIONS: &str = "resume_notifications";
+    pub const PAUSE_STATUS: &str = "pause_status";
+
+    /// The items kiosk mode keeps.
+    pub const KIOSK: &[&str] = &[OPEN_DASHBOARD, STATUS_OVERVIEW];
 }
 
-/// Build the tray menu
+/// Build the tray menu. Its status, version and radio items follow the
+/// [`Store`](store::Store) from then on.
 pub fn build_tray_menu(app_handle: &AppHandle, state: &Arc<AppState>) -> Result<Menu<tauri::Wry>> {
     let settings = async_runtime::block_on(state.settings.read());
-    let status_text = if state.daemon.is_running() {
-        t("tray.status_running")
-    } else {
-        t("tray.status_starting")
-    };
+    // Subscribe before reading the snapshot, so nothing published in
+    // between is lost.
+    let events = state.store.subscribe();
+
+    // Startup radio items from the actual OS login-item state so a failed
+    // startup reconcile doesn't show a lie; fall back to the persisted intent
+    // only if the query itself errors.
+    let launch_at_startup = app_handle
+        .autolaunch()
+        .is_enabled()
+        .unwrap_or(settings.launch_at_startup);
+    state.store.publish(Event::Startup(launch_at_startup));
+    let snapshot = state.store.snapshot();
 
-    // Create status item and store it for later updates
-    let status_item = MenuItemBuilder::with_id(ids::STATUS, status_text)
+    let status_item = MenuItemBuilder::with_id(ids::STATUS, status_text(&snapshot.daemon))
         .enabled(false)
         .build(app_handle)?;
-    let _ = STATUS_ITEM.set(status_item.clone());
+    let last_used_item = if settings.access_summary {
+        let usage = snapshot.usage.clone().unwrap_or_default();
+        Some(
+            MenuItemBuilder::with_id(ids::LAST_USED, usage_text(&usage))
+                .enabled(false)
+                .build(app_handle)?,
+        )
+    } else {
+        None
+    };
+    // Enabled while a copied snippet waits to be used.
+    let snippet_item = if settings.clipboard_snippets && !state.kiosk {
+        Some(
+            MenuItemBuilder::with_id(ids::USE_SNIPPET, t("tray.use_snippet"))
+                .enabled(snapshot.snippet.is_some())
+                .build(app_handle)?,
+        )
+    } else {
+        None
+    };
+    // Enabled while a changed shared package waits for its devices' check.
+    let dependents_item = if settings.check_shared_packages && !state.kiosk {
+        Some(
+            MenuItemBuilder::with_id(ids::CHECK_DEPENDENTS, t("tray.check_dependents"))
+                .enabled(snapshot.changed_package.is_some())
+                .build(app_handle)?,
+        )
+    } else {
+        None
+    };
+
+    // Stop Dashboard while it runs, Start Dashboard while it doesn't.
+    let toggle_item =
+        MenuItemBuilder::with_id(ids::TOGGLE_DASHBOARD, toggle_text(&snapshot.daemon))
+            .build(app_handle)?;
 
     // Create desktop app version display item (Tauri app version from
     // tauri.conf.json — fixed for the lifetime of the process, never updated).
@@ -72,20 +179,14 @@ pub fn build_tray_menu(app_handle: &AppHandle, state: &Arc<AppState>) -> Result<
         .build(app_handle)?;
 
     // Create ESPHome version display item
-    let version_text = t_with(
-        "tray.esphome_version",
-        &[(
-            "version",
-            settings
-                .installed_version
-                .as_deref()
-                .unwrap_or(&t("version.unknown")),
-        )],
-    );
+    let esphome_version = snapshot
+        .esphome_version
+        .clone()
+        .unwrap_or_else(|| t("version.unknown"));
+    let version_text = esphome_version_text(&esphome_version, snapshot.esphome_source.as_deref());
     let version_item = MenuItemBuilder::with_id(ids::VERSION, version_text)
         .enabled(false)
         .build(app_handle)?;
-    let _ = VERSION_ITEM.set(version_item.clone());
 
     // Create esphome-device-builder version display item. Always shown so the
     // menu structure is stable. Detection spawns a Python subprocess which is
@@ -96,91 +197,148 @@ pub fn build_tray_menu(app_handle: &AppHandle, state: &Arc<AppState>) -> Result<
         ids::BUILDER_VERSION,
         t_with(
             "tray.builder_version",
-            &[("version", &t("version.detecting"))],
+            &[(
+                "version",
+                snapshot
+                    .builder_version
+                    .as_deref()
+                    .unwrap_or(&t("version.detecting")),
+            )],
         ),
     )
     .enabled(false)
     .build(app_handle)?;
-    let _ = BUILDER_VERSION_ITEM.set(builder_version_item.clone());
 
     // Kick off async detection of the installed `esphome-device-builder`
-    // version. The blocking Python call runs on a dedicated thread so it
-    // can't stall tray creation or other setup work.
+    // version, and of where ESPHome came from. The blocking Python calls run
+    // on a dedicated thread so they can't stall tray creation or other setup
+    // work.
     {
         let app = app_handle.clone();
         async_runtime::spawn(async move {
             refresh_builder_version_display(&app).await;
+            let _ = tokio::task::spawn_blocking(move || refresh_source_display(&app)).await;
         });
     }
 
-    // Create release channel items
-    let current_channel = settings.release_channel;
-    let channel_stable = CHANNEL_STABLE_ITEM.build(
+    // Channel and backend names are deliberately untranslated (product
+    // terms).
+    let channel = snapshot.channel;
+    let channel_stable = RadioItem::build(
         app_handle,
         ids::CHANNEL_STABLE,
-        current_channel == ReleaseChannel::Stable,
+        || "Stable".to_string(),
+        channel == ReleaseChannel::Stable,
     )?;
-    let channel_beta = CHANNEL_BETA_ITEM.build(
+    let channel_beta = RadioItem::build(
         app_handle,
         ids::CHANNEL_BETA,
-        current_channel == ReleaseChannel::Beta,
+        || "Beta".to_string(),
+        channel == ReleaseChannel::Beta,
     )?;
-    let channel_dev = CHANNEL_DEV_ITEM.build(
+    let channel_dev = RadioItem::build(
         app_handle,
         ids::CHANNEL_DEV,
-        current_channel == ReleaseChannel::Dev,
+        || "Dev".to_string(),
+        channel == ReleaseChannel::Dev,
     )?;
 
     let channel_submenu =
-        SubmenuBuilder::with_id(app_handle, "release_channel", t("tray.release_channel"))
-            .item(&channel_stable)
-            .item(&channel_beta)
-            .item(&channel_dev)
+        SubmenuBuilder::with_id(app_handle, ids::RELEASE_CHANNEL, t("tray.release_channel"))
+            .item(&channel_stable.item)
+            .item(&channel_beta.item)
+            .item(&channel_dev.item)
             .build()?;
 
-    // Backend submenu items
-    let current_backend = settings.backend;
-    let backend_builder_stable = BACKEND_BUILDER_STABLE_ITEM.build(
+    let backend = snapshot.backend;
+    let backend_builder_stable = RadioItem::build(
         app_handle,
         ids::BACKEND_BUILDER_STABLE,
-        current_backend == Backend::BuilderStable,
+        || "ESPHome Device Builder (stable)".to_string(),
+        backend == Backend::BuilderStable,
     )?;
-    let backend_builder_beta = BACKEND_BUILDER_BETA_ITEM.build(
+    let backend_builder_beta = RadioItem::build(
         app_handle,
         ids::BACKEND_BUILDER_BETA,
-        current_backend == Backend::BuilderBeta,
+        || "ESPHome Device Builder (beta)".to_string(),
+        backend == Backend::BuilderBeta,
     )?;
 
-    let backend_submenu = SubmenuBuilder::with_id(app_handle, "backend", t("tray.backend"))
-        .item(&backend_builder_stable)
-        .item(&backend_builder_beta)
+    let backend_submenu = SubmenuBuilder::with_id(app_handle, ids::BACKEND, t("tray.backend"))
+        .item(&backend_builder_stable.item)
+        .item(&backend_builder_beta.item)
         .build()?;
 
-    // Startup submenu items (radio group, mirroring Backend). Label from the
-    // actual OS login-item state so a failed startup reconcile doesn't show a
-    // lie; fall back to the persisted intent only if the query itself errors.
-    let launch_at_startup = app_handle
-        .autolaunch()
-        .is_enabled()
-        .unwrap_or(settings.launch_at_startup);
-    let startup_enable =
-        STARTUP_ENABLE_ITEM.build(app_handle, ids::STARTUP_ENABLE, launch_at_startup)?;
-    let startup_disable =
-        STARTUP_DISABLE_ITEM.build(app_handle, ids::STARTUP_DISABLE, !launch_at_startup)?;
-
-    let startup_submenu = SubmenuBuilder::with_id(app_handle, "startup", t("tray.startup"))
-        .item(&startup_enable)
-        .item(&startup_disable)
-        .build()?;
+    let (preferences, preferences_submenu) =
+        preferences::Preferences::build(app_handle, &settings, &snapshot)?;
 
-    let menu = MenuBuilder::new(app_handle)
-        .item(
-            &MenuItemBuilder::with_id(ids::OPEN_DASHBOARD, t("tray.open_dashboard"))
-                .accelerator("CmdOrCtrl+O")
+    // Language server items: from the setting until the server, which starts
+    // in the background, reports; a failed start corrects them.
+    let language_server_on = RadioItem::build(
+        app_handle,
+        ids::LANGUAGE_SERVER_ON,
+        || t("tray.language_server_on"),
+        snapshot.language_server,
+    )?;
+    let language_server_off = RadioItem::build(
+        app_handle,
+        ids::LANGUAGE_SERVER_OFF,
+        || t("tray.language_server_off"),
+        !snapshot.language_server,
+    )?;
+    let language_server_submenu =
+        SubmenuBuilder::with_id(app_handle, ids::LANGUAGE_SERVER, t("tray.language_server"))
+            .item(&language_server_on.item)
+            .item(&language_server_off.item)
+            .build()?;
+
+    // Says whether notifications are paused, and for how much longer.
+    let pause_item = MenuItemBuilder::with_id(ids::PAUSE_STATUS, pause_text(snapshot.pause))
+        .enabled(false)
+        .build(app_handle)?;
+    let notifications_submenu =
+        SubmenuBuilder::with_id(app_handle, ids::NOTIFICATIONS, t("tray.notifications"))
+            .item(&pause_item)
+            .separator()
+            .item(&MenuItemBuilder::with_id(ids::PAUSE_1H, t("tray.pause_1h")).build(app_handle)?)
+            .item(&MenuItemBuilder::with_id(ids::PAUSE_8H, t("tray.pause_8h")).build(app_handle)?)
+            .item(
+                &MenuItemBuilder::with_id(ids::PAUSE_UNTIL_RESTART, t("tray.pause_until_restart"))
+                    .build(app_handle)?,
+            )
+            .separator()
+            .item(
+                &MenuItemBuilder::with_id(
+                    ids::RESUME_NOTIFICATIONS,
+                    t("tray.resume_notifications"),
+                )
                 .build(app_handle)?,
-        )
-        .separator()
-        .item(&status_item)
+            )
+            .build()?;
+
+    // Name the editor the config will open in, when there is one to name.
+    let edit_config_label =
+        match crate::devices::editor::choose(settings.workspace.editor_command.as_deref(), None) {
+            Ok(Some(editor)) => t_with("tray.edit_config_in", &[("editor", &editor.name)]),
+            _ => t("tray.edit_config"),
+        };
+
+    let mut menu = MenuBuilder::new(app_handle).item(
+        &MenuItemBuilder::with_id(ids::OPEN_DASHBOARD, t("tray.open_dashboard"))
+            .accelerator("CmdOrCtrl+O")
+            .build(app_handle)?,
+    );
+    // Shortcuts to the devices the user picked, right under it.
+    if !state.kiosk {
+        for device in &settings.tray_menu.shortcuts {
+            menu = menu.item(&layout::shortcut(app_handle, device)?);
+        }
+    }
+    menu = menu.separator().item(&status_item);
+    if let Some(item) = &last_used_item {
+        menu = menu.item(item);
+    }
+    menu = menu
         .item(&app_version_item)
         .item(&version_item)
         .item(&builder_version_item)
@@ -192,158 +350,340 @@ pub fn build_tray_menu(app_handle: &AppHandle, state: &Arc<AppState>) -> Result<
             .enabled(false)
             .build(app_handle)?,
         )
-        .separator()
-        .item(&backend_submenu)
-        .item(&channel_submenu)
-        .item(&startup_submenu)
         .item(
-            &MenuItemBuilder::with_id(ids::CHECK_UPDATES, t("tray.check_updates"))
+            &MenuItemBuilder::with_id(ids::STATUS_OVERVIEW, t("tray.status_overview"))
                 .build(app_handle)?,
-        )
-        .separator()
-        .item(&MenuItemBuilder::with_id(ids::VIEW_LOGS, t("tray.view_logs")).build(app_handle)?)
-        .item(&MenuItemBuilder::with_id(ids::OPEN_CONFIG, t("tray.open_config")).build(app_handle)?)
-        .item(
-            &MenuItemBuilder::with_id(ids::RESTART, t("tray.restart_dashboard"))
-                .build(app_handle)?,
-        )
-        .separator()
-        .item(&MenuItemBuilder::with_id(ids::QUIT, t("tray.quit")).build(app_handle)?)
-        .build()?;
+        );
+    // Kiosk mode stops here: nothing below is safe to leave to whoever is
+    // at a shared machine.
+    if !state.kiosk {
+        let item = |id: &str, label: String| -> Result<Box<dyn IsMenuItem<tauri::Wry>>> {
+            Ok(Box::new(
+                MenuItemBuilder::with_id(id, label).build(app_handle)?,
+            ))
+        };
+        let settings_section: Vec<(&str, Box<dyn IsMenuItem<tauri::Wry>>)> = vec![
+            (ids::BACKEND, Box::new(backend_submenu)),
+            (ids::RELEASE_CHANNEL, Box::new(channel_submenu)),
+            (ids::PREFERENCES, Box::new(preferences_submenu)),
+            (ids::SETTINGS, item(ids::SETTINGS, t("tray.settings"))?),
+            (ids::LANGUAGE_SERVER, Box::new(language_server_submenu)),
+            (ids::NOTIFICATIONS, Box::new(notifications_submenu)),
+            (
+                ids::CHECK_UPDATES,
+                item(ids::CHECK_UPDATES, t("tray.check_updates"))?,
+            ),
+            (ids::VERSIONS, item(ids::VERSIONS, t("tray.versions"))?),
+            (
+                ids::PYTHON_PACKAGES,
+                item(ids::PYTHON_PACKAGES, t("tray.python_packages"))?,
+            ),
+        ];
+        let mut tools = vec![
+            (ids::VIEW_LOGS, item(ids::VIEW_LOGS, t("tray.view_logs"))?),
+            (
+                ids::OPEN_CONFIG,
+                item(ids::OPEN_CONFIG, t("tray.open_config"))?,
+            ),
+            (ids::EDIT_CONFIG, item(ids::EDIT_CONFIG, edit_config_label)?),
+            (
+                ids::RENAME_DEVICE,
+                item(ids::RENAME_DEVICE, t("tray.rename_device"))?,
+            ),
+            (
+                ids::SEARCH_CONFIGS,
+                item(ids::SEARCH_CONFIGS, t("tray.search_configs"))?,
+            ),
+            (ids::PIN_USAGE, item(ids::PIN_USAGE, t("tray.pin_usage"))?),
+            (
+                ids::SHARED_PACKAGES,
+                item(ids::SHARED_PACKAGES, t("tray.shared_packages"))?,
+            ),
+            (
+                ids::DUPLICATES,
+                item(ids::DUPLICATES, t("tray.duplicates"))?,
+            ),
+            (
+                ids::SIGNING_KEYS,
+                item(ids::SIGNING_KEYS, t("tray.signing_keys"))?,
+            ),
+            (
+                ids::UNDO_CHANGE,
+                item(ids::UNDO_CHANGE, t("tray.undo_change"))?,
+            ),
+            (
+                ids::SETUP_VSCODE,
+                item(ids::SETUP_VSCODE, t("tray.setup_vscode"))?,
+            ),
+        ];
+        // Only once a tunnel is configured: sharing is opt-in.
+        if crate::share::Method::from_settings(&settings).is_some() {
+            tools.push((ids::SHARE, item(ids::SHARE, t("tray.share"))?));
+        }
+        if let Some(snippet) = &snippet_item {
+            tools.push((ids::USE_SNIPPET, Box::new(snippet.clone())));
+        }
+        if let Some(dependents) = &dependents_item {
+            tools.push((ids::CHECK_DEPENDENTS, Box::new(dependents.clone())));
+        }
+        tools.push((
+            ids::SCRATCHPAD,
+            item(ids::SCRATCHPAD, t("tray.scratchpad"))?,
+        ));
+        tools.push((ids::TERMINAL, item(ids::TERMINAL, t("tray.terminal"))?));
+        tools.push((ids::TOGGLE_DASHBOARD, Box::new(toggle_item.clone())));
+        tools.push((
+            ids::RESTART,
+            item(ids::RESTART, t("tray.restart_dashboard"))?,
+        ));
+        tools.push((
+            ids::CANCEL_BUILD,
+            item(ids::CANCEL_BUILD, t("tray.cancel_build"))?,
+        ));
+        for section in [settings_section, tools] {
+            let section = layout::arrange(section, &settings.tray_menu);
+            if !section.is_empty() {
+                menu = menu.separator();
+            }
+            for item in &section {
+                menu = menu.item(&**item);
+            }
+        }
+        menu = menu
+            .separator()
+            .item(&MenuItemBuilder::with_id(ids::RESET, t("tray.reset")).build(app_handle)?)
+            .item(
+                &MenuItemBuilder::with_id(ids::UNINSTALL_DATA, t("tray.uninstall_data"))
+                    .build(app_handle)?,
+            )
+            .item(&MenuItemBuilder::with_id(ids::QUIT, t("tray.quit")).build(app_handle)?);
+    }
+    let menu = menu.build()?;
+
+    let mut items = Items {
+        status: status_item,
+        toggle: toggle_item,
+        last_used: last_used_item,
+        snippet: snippet_item,
+        dependents: dependents_item,
+        version: version_item,
+        esphome_version,
+        esphome_source: snapshot.esphome_source.clone(),
+        builder_version: builder_version_item,
+        channel: [channel_stable, channel_beta, channel_dev],
+        backend: [backend_builder_stable, backend_builder_beta],
+        preferences: preferences.clone(),
+        language_server: [language_server_on, language_server_off],
+        pause: pause_item,
+    };
+    store::listen(events, move |event| items.apply(&event));
 
-    // Set up menu event handler
+    // Set up menu event handler, shared with the command palette
     let state_clone = state.clone();
-    app_handle.on_menu_event(move |app_handle, event| {
-        events::handle_menu_event(app_handle, event.id().as_ref(), &state_clone);
+    let dispatch: actions::Dispatch = Arc::new(move |app_handle: &AppHandle, id: &str| {
+        events::handle_menu_event(app_handle, id, &state_clone, &preferences);
     });
+    actions::manage(app_handle, menu.clone(), dispatch.clone());
+    app_handle.on_menu_event(move |app_handle, event| dispatch(app_handle, event.id().as_ref()));
 
     Ok(menu)
 }
 
-/// Status menu item stored globally for updates
-static STATUS_ITEM: std::sync::OnceLock<MenuItem<tauri::Wry>> = std::sync::OnceLock::new();
-
-/// Version menu item stored globally for updates
-static VERSION_ITEM: std::sync::OnceLock<MenuItem<tauri::Wry>> = std::sync::OnceLock::new();
-
-/// `esphome-device-builder` version menu item stored globally for updates
-static BUILDER_VERSION_ITEM: std::sync::OnceLock<MenuItem<tauri::Wry>> = std::sync::OnceLock::new();
-
-/// A radio-style menu entry: the base label plus the globally stored menu
-/// item. `build` creates the item and registers it; `refresh` rewrites its
-/// label to reflect the current selection.
-///
-/// The label is a function rather than a string so translated labels resolve
-/// through `i18n::t` at build/refresh time; untranslated product terms
-/// (channel and backend names) just return their literal.
-struct RadioItem {
-    label: fn() -> String,
-    item: std::sync::OnceLock<MenuItem<tauri::Wry>>,
+/// The items whose labels follow the [`Store`](store::Store), owned by the
+/// subscriber that rewrites them. Each radio group is in the order of its
+/// submenu; an on/off pair has "on" first.
+struct Items {
+    status: MenuItem<tauri::Wry>,
+    toggle: MenuItem<tauri::Wry>,
+    /// Only with `access_summary` on.
+    last_used: Option<MenuItem<tauri::Wry>>,
+    /// Only with `clipboard_snippets` on.
+    snippet: Option<MenuItem<tauri::Wry>>,
+    /// Only with `check_shared_packages` on.
+    dependents: Option<MenuItem<tauri::Wry>>,
+    version: MenuItem<tauri::Wry>,
+    /// The two halves of `version`'s label, each following its event.
+    esphome_version: String,
+    esphome_source: Option<String>,
+    builder_version: MenuItem<tauri::Wry>,
+    channel: [RadioItem; 3],
+    backend: [RadioItem; 2],
+    preferences: preferences::Preferences,
+    language_server: [RadioItem; 2],
+    pause: MenuItem<tauri::Wry>,
 }
 
-impl RadioItem {
-    const fn new(label: fn() -> String) -> Self {
-        Self {
-            label,
-            item: std::sync::OnceLock::new(),
+impl Items {
+    fn apply(&mut self, event: &Event) {
+        match event {
+            Event::Daemon(state) => {
+                set_text(&self.status, status_text(state));
+                set_text(&self.toggle, toggle_text(state));
+            }
+            Event::EsphomeVersion(version) => {
+                self.esphome_version = version.clone();
+                set_text(
+                    &self.version,
+                    esphome_version_text(version, self.esphome_source.as_deref()),
+                );
+            }
+            Event::EsphomeSource(source) => {
+                self.esphome_source = source.clone();
+                set_text(
+                    &self.version,
+                    esphome_version_text(&self.esphome_version, source.as_deref()),
+                );
+            }
+            Event::BuilderVersion(version) => set_text(
+                &self.builder_version,
+                t_with("tray.builder_version", &[("version", version)]),
+            ),
+            Event::Channel(channel) => {
+                let [stable, beta, dev] = &self.channel;
+                stable.refresh(*channel == ReleaseChannel::Stable);
+                beta.refresh(*channel == ReleaseChannel::Beta);
+                dev.refresh(*channel == ReleaseChannel::Dev);
+            }
+            Event::Backend(backend) => {
+                let [stable, beta] = &self.backend;
+                stable.refresh(*backend == Backend::BuilderStable);
+                beta.refresh(*backend == Backend::BuilderBeta);
+            }
+            Event::Startup(_) | Event::Notifications(_) => self.preferences.apply(event),
+            Event::LanguageServer(running) => refresh_pair(&self.language_server, *running),
+            Event::Pause(pause) => set_text(&self.pause, pause_text(*pause)),
+            Event::Restarted(_) => {}
+            Event::Usage(usage) => {
+                if let Some(item) = &self.last_used {
+                    set_text(item, usage_text(usage));
+                }
+            }
+            Event::Snippet(snippet) => {
+                if let Some(item) = &self.snippet {
+                    if let Err(e) = item.set_enabled(snippet.is_some()) {
+                        warn!(
+                            "Failed to update tray menu item '{}': {}",
+                            ids::USE_SNIPPET,
+                            e
+                        );
+                    }
+                }
+            }
+            Event::PackageChanged(file) => {
+                if let Some(item) = &self.dependents {
+                    if let Err(e) = item.set_enabled(file.is_some()) {
+                        warn!(
+                            "Failed to update tray menu item '{}': {}",
+                            ids::CHECK_DEPENDENTS,
+                            e
+                        );
+                    }
+                }
+            }
         }
     }
+}
 
-    /// Build the menu item with a `radio_label` and register it for later
-    /// refreshes.
-    fn build(
-        &self,
-        app_handle: &AppHandle,
-        id: &str,
-        selected: bool,
-    ) -> Result<MenuItem<tauri::Wry>> {
-        self.build_impl(app_handle, id, selected, true)
+/// The ESPHome version line, saying so when the install came from git
+/// rather than PyPI.
+fn esphome_version_text(version: &str, source: Option<&str>) -> String {
+    match source {
+        Some(source) => t_with(
+            "tray.esphome_version_git",
+            &[("version", version), ("source", source)],
+        ),
+        None => t_with("tray.esphome_version", &[("version", version)]),
     }
+}
 
-    /// Like [`RadioItem::build`], but the item is greyed out.
-    #[allow(dead_code)]
-    fn build_disabled(
-        &self,
-        app_handle: &AppHandle,
-        id: &str,
-        selected: bool,
-    ) -> Result<MenuItem<tauri::Wry>> {
-        self.build_impl(app_handle, id, selected, false)
+fn status_text(state: &DaemonState) -> String {
+    match state {
+        DaemonState::Starting => t("tray.status_starting"),
+        DaemonState::Running => t("tray.status_running"),
+        DaemonState::Stopped | DaemonState::Crashed(_) => t("tray.status_stopped"),
     }
+}
 
-    fn build_impl(
-        &self,
-        app_handle: &AppHandle,
-        id: &str,
-        selected: bool,
-        enabled: bool,
-    ) -> Result<MenuItem<tauri::Wry>> {
-        // If a previous build already registered an item, reuse it so
-        // `refresh` keeps targeting the item that is live in the menu.
-        if let Some(item) = self.item.get() {
-            item.set_text(radio_label(&(self.label)(), selected))?;
-            item.set_enabled(enabled)?;
-            return Ok(item.clone());
-        }
-        let item = MenuItemBuilder::with_id(id, radio_label(&(self.label)(), selected))
-            .enabled(enabled)
-            .build(app_handle)?;
-        let _ = self.item.set(item.clone());
-        Ok(item)
+fn toggle_text(state: &DaemonState) -> String {
+    match state {
+        DaemonState::Starting | DaemonState::Running => t("tray.stop_dashboard"),
+        DaemonState::Stopped | DaemonState::Crashed(_) => t("tray.start_dashboard"),
     }
+}
 
-    /// Update the registered item's label to reflect the current selection.
-    fn refresh(&self, selected: bool) {
-        if let Some(item) = self.item.get() {
-            let label = (self.label)();
-            if let Err(e) = item.set_text(radio_label(&label, selected)) {
-                warn!("Failed to update tray menu item '{}': {}", label, e);
-            }
-        }
+fn pause_text(pause: PauseState) -> String {
+    match pause {
+        PauseState::On => t("tray.notifications_on"),
+        PauseState::PausedFor(minutes) if minutes < 60 => t_with(
+            "tray.paused_for_minutes",
+            &[("minutes", &minutes.to_string())],
+        ),
+        PauseState::PausedFor(minutes) => t_with(
+            "tray.paused_for_hours",
+            &[
+                ("hours", &(minutes / 60).to_string()),
+                ("minutes", &(minutes % 60).to_string()),
+            ],
+        ),
+        PauseState::UntilRestart => t("tray.paused_until_restart"),
+        PauseState::TurnedOff => t("tray.notifications_off"),
     }
 }
 
-/// Release channel items stored globally for radio-button behavior.
-/// Channel names are deliberately untranslated (product terms).
-static CHANNEL_STABLE_ITEM: RadioItem = RadioItem::new(|| "Stable".to_string());
-static CHANNEL_BETA_ITEM: RadioItem = RadioItem::new(|| "Beta".to_string());
-static CHANNEL_DEV_ITEM: RadioItem = RadioItem::new(|| "Dev".to_string());
-
-/// Backend menu items stored globally for radio-button behavior.
-/// Backend names are deliberately untranslated (product terms).
-static BACKEND_BUILDER_STABLE_ITEM: RadioItem =
-    RadioItem::new(|| "ESPHome Device Builder (stable)".to_string());
-static BACKEND_BUILDER_BETA_ITEM: RadioItem =
-    RadioItem::new(|| "ESPHome Device Builder (beta)".to_string());
-
-/// Startup menu items stored globally for radio-button behavior
-static STARTUP_ENABLE_ITEM: RadioItem = RadioItem::new(|| t("tray.launch_at_login"));
-static STARTUP_DISABLE_ITEM: RadioItem = RadioItem::new(|| t("tray.dont_launch_at_login"));
-
-/// Update the tray status text
-pub fn update_status(_app_handle: &AppHandle, running: bool) {
-    let status_text = if running {
-        t("tray.status_running")
-    } else {
-        t("tray.status_stopped")
+fn usage_text(usage: &Usage) -> String {
+    let used = match usage.idle_minutes {
+        None => t("tray.last_used_never"),
+        Some(0) => t("tray.last_used_now"),
+        Some(minutes) => t_with("tray.last_used", &[("minutes", &minutes.to_string())]),
     };
-
-    if let Some(item) = STATUS_ITEM.get() {
-        let _ = item.set_text(status_text);
+    if usage.remote_clients.is_empty() {
+        return used;
     }
+    t_with(
+        "tray.last_used_remote",
+        &[
+            ("used", &used),
+            ("count", &usage.remote_clients.len().to_string()),
+        ],
+    )
 }
 
-/// Update the version display in the tray menu.
-pub fn update_version(version: &str) {
-    if let Some(item) = VERSION_ITEM.get() {
-        let _ = item.set_text(t_with("tray.esphome_version", &[("version", version)]));
+fn set_text(item: &MenuItem<tauri::Wry>, text: String) {
+    if let Err(e) = item.set_text(&text) {
+        warn!("Failed to update tray menu item '{}': {}", text, e);
     }
 }
 
-/// Update the `esphome-device-builder` version display in the tray menu.
-pub fn update_builder_version(version: &str) {
-    if let Some(item) = BUILDER_VERSION_ITEM.get() {
-        let _ = item.set_text(t_with("tray.builder_version", &[("version", version)]));
+fn refresh_pair([on, off]: &[RadioItem; 2], enabled: bool) {
+    on.refresh(enabled);
+    off.refresh(!enabled);
+}
+
+/// A radio-style menu entry: the base label plus its menu item. `refresh`
+/// rewrites the label to reflect the current selection.
+///
+/// The label is a function rather than a string so translated labels resolve
+/// through `i18n::t` at build/refresh time; untranslated product terms
+/// (channel and backend names) just return their literal.
+struct RadioItem {
+    label: fn() -> String,
+    item: MenuItem<tauri::Wry>,
+}
+
+impl RadioItem {
+    fn build(
+        app_handle: &AppHandle,
+        id: &str,
+        label: fn() -> String,
+        selected: bool,
+    ) -> Result<Self> {
+        let item =
+            MenuItemBuilder::with_id(id, radio_label(&label(), selected)).build(app_handle)?;
+        Ok(Self { label, item })
+    }
+
+    fn refresh(&self, selected: bool) {
+        set_text(&self.item, radio_label(&(self.label)(), selected));
     }
 }
 
@@ -356,26 +696,14 @@ fn radio_label(name: &str, selected: bool) -> String {
     }
 }
 
-/// Update the channel menu item labels to reflect the given channel
-pub(crate) fn update_channel_checks(channel: ReleaseChannel) {
-    CHANNEL_STABLE_ITEM.refresh(channel == ReleaseChannel::Stable);
-    CHANNEL_BETA_ITEM.refresh(channel == ReleaseChannel::Beta);
-    CHANNEL_DEV_ITEM.refresh(channel == ReleaseChannel::Dev);
-}
-
-/// Update the backend menu item labels to reflect the given backend.
-pub(crate) fn update_backend_checks(backend: Backend) {
-    BACKEND_BUILDER_STABLE_ITEM.refresh(backend == Backend::BuilderStable);
-    BACKEND_BUILDER_BETA_ITEM.refresh(backend == Backend::BuilderBeta);
-}
-
-/// Update the startup menu item labels to reflect whether autostart is enabled.
-pub(crate) fn update_startup_checks(enabled: bool) {
-    STARTUP_ENABLE_ITEM.refresh(enabled);
-    STARTUP_DISABLE_ITEM.refresh(!enabled);
+/// Publish `event` to the app's store, once there is one.
+fn publish(app_handle: &AppHandle, event: Event) {
+    if let Some(state) = app_handle.try_state::<Arc<AppState>>() {
+        state.store.publish(event);
+    }
 }
 
-/// Re-detect the installed version and update the tray version display.
+/// Re-detect the installed version, and where it came from, and publish them.
 pub(crate) fn refresh_version_display(app_handle: &AppHandle) {
     // Mirror the device-builder display: keep "not installed" distinct from a
     // real detection failure ("unknown") instead of collapsing both.
@@ -387,11 +715,21 @@ pub(crate) fn refresh_version_display(app_handle: &AppHandle) {
             t("version.unknown")
         }
     };
-    update_version(&version);
+    refresh_source_display(app_handle);
+    publish(app_handle, Event::EsphomeVersion(version));
+}
+
+/// Re-detect where the installed ESPHome came from and publish it.
+fn refresh_source_display(app_handle: &AppHandle) {
+    let source = crate::update::installed_esphome_source(app_handle).unwrap_or_else(|e| {
+        warn!("Could not detect where ESPHome was installed from: {}", e);
+        None
+    });
+    publish(app_handle, Event::EsphomeSource(source));
 }
 
 /// Re-detect the installed `esphome-device-builder` package version and
-/// update the tray display. Runs the blocking Python call off the caller's
+/// publish it. Runs the blocking Python call off the caller's
 /// thread, and distinguishes "package not installed" from "detection
 /// failed" so the latter doesn't get silently misreported.
 pub(crate) async fn refresh_builder_version_display(app_handle: &AppHandle) {
@@ -411,5 +749,5 @@ pub(crate) async fn refresh_builder_version_display(app_handle: &AppHandle) {
         warn!("Device-builder version detection task failed: {}", e);
         t("version.unknown")
     });
-    update_builder_version(&label);
+    publish(app_handle, Event::BuilderVersion(label));
 }
@@ -0,0 +1,387 @@
+//! The "Status Overview" dialog: every subsystem's state in one place, beyond
+//! what fits in the terse tray menu.
+//!
+//! A native dialog rather than a window, built from the same snapshot
+//! `esphome-desktop status` prints, so the two never disagree. It shows the
+//! state as of opening, the latest periodic health check included, and
+//! doesn't refresh. Only the tray menu opens it: a double-click on the icon
+//! reaches the app on Windows alone, not on macOS or Linux.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tauri::AppHandle;
+use tauri_plugin_dialog::MessageDialogKind;
+use tracing::warn;
+
+use crate::audit::{self, AuditLog};
+use crate::control::protocol::{ComponentUpdate, StatusReply, UpdateCheckReply};
+use crate::devices::sizes::SizeHistory;
+use crate::i18n::{t, t_with};
+use crate::startup::Phase;
+use crate::store::{Restart, RestartReason};
+use crate::util::ConfigStore;
+use crate::AppState;
+
+/// Audit entries the overview shows; `esphome-desktop audit` has the rest.
+const RECENT_AUDIT_ENTRIES: usize = 3;
+
+/// Restarts the overview lists; `esphome-desktop status` has the rest.
+const RECENT_RESTARTS: usize = 3;
+
+/// How long the overview waits on the network for pending updates before
+/// showing without them. The update check hits GitHub and PyPI; a slow network
+/// must not leave a tray click looking dead.
+const UPDATE_CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Offer to stop any build processes the watchdog flagged, then gather the
+/// status snapshot and pending updates, show them, and open the dashboard if
+/// the user asks to.
+pub(super) async fn show_status_overview(app: &AppHandle, state: &Arc<AppState>) {
+    if !state.kiosk {
+        offer_to_stop_runaways(app, state).await;
+    }
+    let (status, updates) = tokio::join!(
+        crate::control::server::build_status(app, state),
+        tokio::time::timeout(
+            UPDATE_CHECK_TIMEOUT,
+            crate::control::server::build_update_check(app, state),
+        ),
+    );
+    let open = crate::dialog::confirm(
+        app,
+        &t("overview.title"),
+        overview_text(&status, updates.ok().as_ref()) + &size_text(app) + &audit_text(app),
+        &t("tray.open_dashboard"),
+        &t("overview.close"),
+    )
+    .await;
+    if open {
+        crate::open_dashboard(state);
+    }
+}
+
+/// One prompt per flagged process. Each is asked about separately since the
+/// user may know one is a legitimately long compile.
+async fn offer_to_stop_runaways(app: &AppHandle, state: &Arc<AppState>) {
+    let runaways = state.daemon.runaways();
+    for runaway in runaways {
+        let pid = runaway.pid.to_string();
+        let stop = crate::dialog::confirm(
+            app,
+            &t("overview.runaway_title"),
+            t_with(
+                "overview.runaway_body",
+                &[("name", &runaway.name), ("pid", &pid)],
+            ),
+            &t("overview.stop_process"),
+            &t("overview.keep_running"),
+        )
+        .await;
+        if !stop {
+            continue;
+        }
+        if let Err(e) = state.daemon.kill_runaway(runaway.pid) {
+            warn!("Failed to stop {} (pid {}): {:#}", runaway.name, pid, e);
+            crate::dialog::notice(
+                app,
+                &t("overview.runaway_title"),
+                t_with(
+                    "overview.stop_failed",
+                    &[("name", &runaway.name), ("error", &e.to_string())],
+                ),
+                MessageDialogKind::Error,
+            )
+            .await;
+        }
+    }
+}
+
+/// The dialog body. `updates` is `None` when the update check timed out.
+fn overview_text(status: &StatusReply, updates: Option<&UpdateCheckReply>) -> String {
+    let unknown = t("version.unknown");
+    let mut lines = vec![
+        match (status.backend_running, status.backend_healthy) {
+            (true, true) => t("overview.dashboard_healthy"),
+            (true, false) => t("overview.dashboard_not_responding"),
+            (false, _) => t("overview.dashboard_stopped"),
+        },
+        t_with("tray.port", &[("port", &status.port.to_string())]),
+        t_with(
+            "overview.restarts",
+            &[("count", &status.restarts.to_string())],
+        ),
+        String::new(),
+        t_with("tray.desktop_version", &[("version", &status.app_version)]),
+        match &status.pinned_esphome {
+            Some(pinned) => t_with("overview.esphome_pinned", &[("version", pinned)]),
+            None => t_with(
+                "tray.esphome_version",
+                &[(
+                    "version",
+                    status.esphome_version.as_deref().unwrap_or(&unknown),
+                )],
+            ),
+        },
+        t_with(
+            "tray.builder_version",
+            &[(
+                "version",
+                status
+                    .device_builder_version
+                    .as_deref()
+                    .unwrap_or(&t("version.not_installed")),
+            )],
+        ),
+        String::new(),
+    ];
+    if let Some(check) = &status.last_health_check {
+        let ago = crate::daemon::format_uptime(Duration::from_secs(check.secs_ago));
+        let key = if check.passed {
+            "overview.health_passed"
+        } else {
+            "overview.health_failed"
+        };
+        lines.insert(1, t_with(key, &[("ago", &ago)]));
+    }
+    if let Some(secs) = status.uptime_secs {
+        let uptime = crate::daemon::format_uptime(Duration::from_secs(secs));
+        lines.insert(1, t_with("overview.uptime", &[("uptime", &uptime)]));
+    }
+    // Why the backend restarted and how it last crashed go under the restart
+    // count, before the versions.
+    let mut at = lines
+        .iter()
+        .position(String::is_empty)
+        .unwrap_or(lines.len());
+    let skip = status.restart_history.len().saturating_sub(RECENT_RESTARTS);
+    for restart in &status.restart_history[skip..] {
+        lines.insert(at, restart_line(restart));
+        at += 1;
+    }
+    if let Some(crash) = &status.last_crash {
+        lines.insert(at, t_with("overview.last_crash", &[("reason", crash)]));
+        at += 1;
+    }
+    if let Some(slow) = &status.slow_start {
+        let key = match slow.phase {
+            Phase::Python => "overview.slow_python",
+            Phase::Spawn => "overview.slow_spawn",
+            Phase::Ready => "overview.slow_ready",
+        };
+        let secs = slow.secs.to_string();
+        let usual = slow.usual_secs.to_string();
+        lines.insert(at, t_with(key, &[("secs", &secs), ("usual", &usual)]));
+    }
+    match updates {
+        None => lines.push(t("overview.update_check_failed")),
+        Some(updates) if !updates.any_available => lines.push(t("overview.up_to_date")),
+        Some(updates) => {
+            if let Some(latest) = available_version(&updates.app) {
+                lines.push(t_with("overview.update_app", &[("version", latest)]));
+            }
+            if let Some(latest) = available_version(&updates.esphome) {
+                lines.push(t_with("overview.update_esphome", &[("version", latest)]));
+            }
+            if let Some(latest) = available_version(&updates.device_builder) {
+                lines.push(t_with("overview.update_builder", &[("version", latest)]));
+            }
+        }
+    }
+    lines.join("\n")
+}
+
+fn restart_line(restart: &Restart) -> String {
+    let reason = match restart.reason {
+        RestartReason::User => t("overview.restart_user"),
+        RestartReason::Update => t("overview.restart_update"),
+        RestartReason::CrashRecovery => t("overview.restart_crash_recovery"),
+        RestartReason::Settings => t("overview.restart_settings"),
+    };
+    t_with(
+        "overview.restart",
+        &[
+            ("number", &restart.number.to_string()),
+            ("time", &restart.time),
+            ("reason", &reason),
+        ],
+    )
+}
+
+/// Devices whose last recorded build is close to a flash or RAM limit.
+/// Empty when none is, or the history can't be read.
+fn size_text(app: &AppHandle) -> String {
+    let history = match crate::platform::get_data_dir(app)
+        .map_err(anyhow::Error::from)
+        .and_then(|d| SizeHistory::load(&SizeHistory::path(&d)))
+    {
+        Ok(history) => history,
+        Err(e) => {
+            warn!("Failed to read the build size history: {e:#}");
+            return String::new();
+        }
+    };
+    near_limit_lines(&history)
+}
+
+fn near_limit_lines(history: &SizeHistory) -> String {
+    let mut lines = Vec::new();
+    for (device, sample) in history.latest() {
+        for (region, percent) in sample.near_limit() {
+            let percent = percent.to_string();
+            let params = [("device", device), ("percent", percent.as_str())];
+            lines.push(if region == "flash" {
+                t_with("overview.flash_warning", &params)
+            } else {
+                t_with("overview.ram_warning", &params)
+            });
+        }
+    }
+    if lines.is_empty() {
+        return String::new();
+    }
+    lines.insert(0, String::new());
+    lines.insert(1, t("overview.size_warnings"));
+    lines.join("\n")
+}
+
+/// The last few audited actions, and a warning if the log was edited.
+/// Empty when nothing was recorded or the log can't be read.
+fn audit_text(app: &AppHandle) -> String {
+    let log = match crate::platform::get_data_dir(app)
+        .map_err(anyhow::Error::from)
+        .and_then(|d| audit::read(&audit::path(&d)))
+    {
+        Ok(log) => log,
+        Err(e) => {
+            warn!("Failed to read the audit log: {e:#}");
+            return String::new();
+        }
+    };
+    recent_changes(&log)
+}
+
+fn recent_changes(log: &AuditLog) -> String {
+    if log.entries.is_empty() {
+        return String::new();
+    }
+    let mut lines = vec![String::new(), t("overview.recent_changes")];
+    let skip = log.entries.len().saturating_sub(RECENT_AUDIT_ENTRIES);
+    lines.extend(
+        log.entries[skip..]
+            .iter()
+            .map(|e| format!("{} ({}): {}", e.time, e.source.name(), e.detail)),
+    );
+    if let Some(line) = log.broken_at {
+        lines.push(t_with(
+            "overview.audit_modified",
+            &[("line", &line.to_string())],
+        ));
+    }
+    lines.join("\n")
+}
+
+fn available_version(component: &ComponentUpdate) -> Option<&str> {
+    component
+        .available
+        .then_some(component.latest.as_deref())
+        .flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::control::protocol::HealthCheck;
+    use crate::settings::{Backend, ReleaseChannel};
+    use crate::startup::SlowStart;
+    use std::path::PathBuf;
+
+    fn status() -> StatusReply {
+        StatusReply {
+            app_version: "0.16.1".into(),
+            backend_running: true,
+            backend_healthy: true,
+            port: 6052,
+            url: None,
+            esphome_version: Some("2026.9.0".into()),
+            pinned_esphome: None,
+            device_builder_version: None,
+            release_channel: ReleaseChannel::Stable,
+            backend: Backend::BuilderStable,
+            launch_at_startup: true,
+            config_dir: PathBuf::from("/home/x/esphome"),
+            logs_dir: PathBuf::from("/tmp/logs"),
+            timezone: None,
+            locale: None,
+            uptime_secs: Some(3725),
+            restarts: 1,
+            last_crash: None,
+            restart_history: vec![Restart {
+                number: 1,
+                time: "2026-10-16T09:00:00Z".into(),
+                reason: RestartReason::Update,
+            }],
+            last_startup: None,
+            slow_start: Some(SlowStart {
+                phase: Phase::Ready,
+                secs: 90,
+                usual_secs: 8,
+            }),
+            last_health_check: Some(HealthCheck {
+                secs_ago: 12,
+                passed: true,
+            }),
+        }
+    }
+
+    #[test]
+    fn overview_lists_state_versions_and_pending_updates() {
+        let updates = UpdateCheckReply {
+            any_available: true,
+            app: ComponentUpdate::current("0.16.1".into(), "0.16.1".into()),
+            esphome: ComponentUpdate::upgradable("2026.9.0".into(), "2026.10.0".into()),
+            device_builder: ComponentUpdate::not_installed(),
+        };
+        let text = overview_text(&status(), Some(&updates));
+        assert!(text.contains("Dashboard: running"));
+        assert!(text.contains("Last health check: passed 12s ago"));
+        assert!(text.contains("Port: 6052"));
+        assert!(text.contains("Uptime: 1h 02m"));
+        assert!(text.contains("Restarts this session: 1"));
+        assert!(text.contains("#1 at 2026-10-16T09:00:00Z: for an update"));
+        assert!(!text.contains("Last crash"));
+        assert!(text.contains("Slow start: the dashboard took 90 s to answer, usually 8 s"));
+        assert!(text.contains("ESPHome: 2026.9.0"));
+        assert!(text.contains("Device Builder: not installed"));
+        assert!(text.contains("ESPHome update available: 2026.10.0"));
+        assert!(!text.contains("Desktop app update"));
+    }
+
+    #[test]
+    fn overview_says_when_updates_could_not_be_checked() {
+        let text = overview_text(&status(), None);
+        assert!(text.contains("Couldn't check for updates."));
+    }
+
+    #[test]
+    fn recent_changes_show_the_latest_entries_and_flag_edits() {
+        let entry = |detail: &str| audit::Entry {
+            time: "2026-10-16T09:00:00Z".into(),
+            source: audit::Source::Tray,
+            action: "restart".into(),
+            detail: detail.into(),
+            prev: String::new(),
+        };
+        let mut log = AuditLog {
+            entries: ["a", "b", "c", "d"].map(entry).to_vec(),
+            broken_at: None,
+        };
+        assert_eq!(recent_changes(&AuditLog::default()), "");
+        let text = recent_changes(&log);
+        assert!(text.contains("Recent changes:"));
+        assert!(!text.contains("(tray): a"));
+        assert!(text.contains("2026-10-16T09:00:00Z (tray): d"));
+        log.broken_at = Some(2);
+        assert!(recent_changes(&log).contains("audit log was edited (line 2)"));
+    }
+}
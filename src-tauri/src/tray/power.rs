@@ -0,0 +1,48 @@
+//! The tray's "Stop Dashboard" / "Start Dashboard" item: stop the backend to
+//! free its port and memory without quitting the app, and start it again.
+//! The item's label and the tray icon follow the state from the store.
+
+use std::sync::Arc;
+
+use tauri::AppHandle;
+use tauri_plugin_dialog::MessageDialogKind;
+use tracing::{error, info};
+
+use crate::audit::{self, Source};
+use crate::control::ops::UpdateGuard;
+use crate::error::UserError;
+use crate::i18n::{t, t_with};
+use crate::AppState;
+
+pub(super) async fn toggle(app: AppHandle, state: Arc<AppState>) {
+    // Held like a restart's: a start mid-switch would run the old backend,
+    // and a stop would pull it out from under an install.
+    let Some(_guard) = UpdateGuard::try_acquire(state.update_in_flight.clone()) else {
+        info!("Update/switch already in progress; ignoring dashboard stop/start");
+        return;
+    };
+    let (action, done, failed, result) = if state.daemon.is_running() {
+        info!("Stopping ESPHome backend");
+        let result = state.daemon.stop().await;
+        ("stop", "stopped", "errors.stop_dashboard_failed", result)
+    } else {
+        info!("Starting ESPHome backend");
+        let result = state.daemon.start().await;
+        ("start", "started", "errors.start_dashboard_failed", result)
+    };
+    let detail = match &result {
+        Ok(()) => format!("dashboard {done} from the menu"),
+        Err(e) => format!("dashboard {action} from the menu failed: {e}"),
+    };
+    audit::record(&app, Source::Tray, action, detail);
+    if let Err(e) = result {
+        error!("Failed to {} daemon: {}", action, e);
+        crate::dialog::notice(
+            &app,
+            &t("daemon.toggle_failed_title"),
+            t_with(failed, &[("error", &e.user_message())]),
+            MessageDialogKind::Error,
+        )
+        .await;
+    }
+}
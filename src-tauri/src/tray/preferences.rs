@@ -0,0 +1,139 @@
+//! The tray's "Preferences" submenu: check items for the settings flipped
+//! most often, each saved as soon as it is clicked, without opening
+//! `settings.json`.
+
+use std::sync::Arc;
+
+use tauri::{
+    async_runtime,
+    menu::{CheckMenuItem, CheckMenuItemBuilder, Submenu, SubmenuBuilder},
+    AppHandle,
+};
+use tracing::warn;
+
+use super::ids;
+use crate::audit::{self, Source};
+use crate::control::ops;
+use crate::i18n::t;
+use crate::notifications::Pause;
+use crate::settings::Settings;
+use crate::store::{Event, Snapshot};
+use crate::AppState;
+
+/// The submenu's items, shared by the menu event handler and the
+/// [`Store`](crate::store::Store) subscriber that keeps them current.
+#[derive(Clone)]
+pub(super) struct Preferences {
+    open_on_start: CheckMenuItem<tauri::Wry>,
+    check_updates: CheckMenuItem<tauri::Wry>,
+    notifications: CheckMenuItem<tauri::Wry>,
+    launch_at_login: CheckMenuItem<tauri::Wry>,
+}
+
+impl Preferences {
+    pub(super) fn build(
+        app: &AppHandle,
+        settings: &Settings,
+        snapshot: &Snapshot,
+    ) -> tauri::Result<(Self, Submenu<tauri::Wry>)> {
+        let item = |id: &str, label: String, checked: bool| {
+            CheckMenuItemBuilder::with_id(id, label)
+                .checked(checked)
+                .build(app)
+        };
+        let preferences = Self {
+            open_on_start: item(
+                ids::OPEN_ON_START,
+                t("tray.open_on_start"),
+                settings.open_on_start,
+            )?,
+            check_updates: item(
+                ids::CHECK_UPDATES_AUTOMATICALLY,
+                t("tray.check_updates_automatically"),
+                settings.check_updates,
+            )?,
+            notifications: item(
+                ids::SHOW_NOTIFICATIONS,
+                t("tray.show_notifications"),
+                snapshot.notifications,
+            )?,
+            launch_at_login: item(
+                ids::LAUNCH_AT_LOGIN,
+                t("tray.launch_at_login"),
+                snapshot.startup,
+            )?,
+        };
+        let submenu = SubmenuBuilder::with_id(app, ids::PREFERENCES, t("tray.preferences"))
+            .item(&preferences.open_on_start)
+            .item(&preferences.check_updates)
+            .item(&preferences.notifications)
+            .item(&preferences.launch_at_login)
+            .build()?;
+        Ok((preferences, submenu))
+    }
+
+    /// Flip the preference `id` names, if it names one.
+    pub(super) fn handle(&self, app: &AppHandle, id: &str, state: &Arc<AppState>) -> bool {
+        let item = match id {
+            ids::OPEN_ON_START => &self.open_on_start,
+            ids::CHECK_UPDATES_AUTOMATICALLY => &self.check_updates,
+            ids::SHOW_NOTIFICATIONS => &self.notifications,
+            ids::LAUNCH_AT_LOGIN => &self.launch_at_login,
+            _ => return false,
+        };
+        let item = item.clone();
+        let id = id.to_string();
+        let state = state.clone();
+        let app = app.clone();
+        async_runtime::spawn(async move {
+            let checked = match id.as_str() {
+                ids::OPEN_ON_START => {
+                    ops::toggle_setting(&app, &state, |s| &mut s.open_on_start).await
+                }
+                ids::CHECK_UPDATES_AUTOMATICALLY => {
+                    ops::toggle_setting(&app, &state, |s| &mut s.check_updates).await
+                }
+                ids::SHOW_NOTIFICATIONS => {
+                    let on = !state.store.snapshot().notifications;
+                    let pause = (!on).then_some(Pause::UntilResumed);
+                    ops::set_notifications_paused(&app, &state, pause).await;
+                    on
+                }
+                _ => {
+                    let enable = !state.store.snapshot().startup;
+                    let actual = ops::set_launch_at_startup(&app, &state, enable).await;
+                    audit::record(
+                        &app,
+                        Source::Tray,
+                        "set_startup",
+                        format!("launch at login {}", if actual { "on" } else { "off" }),
+                    );
+                    actual
+                }
+            };
+            // The platform may already have flipped the mark on click; make
+            // it say what was saved, including a login item that failed.
+            set_checked(&item, checked);
+        });
+        true
+    }
+
+    /// Follow a change made elsewhere (the CLI, the Notifications submenu).
+    pub(super) fn apply(&self, event: &Event) {
+        match event {
+            Event::Startup(enabled) => set_checked(&self.launch_at_login, *enabled),
+            Event::Notifications(on) => set_checked(&self.notifications, *on),
+            _ => {}
+        }
+    }
+}
+
+fn set_checked(item: &CheckMenuItem<tauri::Wry>, checked: bool) {
+    if let Err(e) = item.set_checked(checked) {
+        warn!(
+            "Failed to update tray menu item '{}': {}",
+            item.id().as_ref(),
+            e
+        );
+    }
+}
@@ -0,0 +1,85 @@
+//! The tray's "Share Dashboard Temporarily..." item (see [`crate::share`]):
+//! open a share once the user agrees, or offer to end the one that is open.
+
+use std::sync::Arc;
+
+use tauri::AppHandle;
+use tauri_plugin_dialog::MessageDialogKind;
+use tracing::error;
+
+use crate::audit::{self, Source};
+use crate::i18n::{t, t_with};
+use crate::share::{self, Method};
+use crate::AppState;
+
+pub(super) async fn run(app: AppHandle, state: Arc<AppState>) {
+    if let Some((url, left)) = state.share.current() {
+        let stop = crate::dialog::confirm(
+            &app,
+            &t("share.title"),
+            t_with(
+                "share.open",
+                &[("url", &url), ("minutes", &left.to_string())],
+            ),
+            &t("share.stop"),
+            &t("share.keep"),
+        )
+        .await;
+        if stop {
+            state.share.stop();
+            audit::record(&app, Source::Tray, "share", "stopped sharing the dashboard");
+        }
+        return;
+    }
+
+    let (method, minutes) = {
+        let settings = state.settings.read().await;
+        (Method::from_settings(&settings), share::minutes(&settings))
+    };
+    // The item is only in the menu with a method; it may have been
+    // removed from the settings since.
+    let Some(method) = method else { return };
+    let agreed = crate::dialog::confirm(
+        &app,
+        &t("share.title"),
+        share::describe(&method, minutes),
+        &t("share.start"),
+        &t("common.cancel"),
+    )
+    .await;
+    if !agreed {
+        return;
+    }
+
+    let address = state.daemon.address();
+    match state
+        .share
+        .start(&app, &state.tasks, &method, address, minutes)
+        .await
+    {
+        Ok(url) => {
+            let detail = format!("shared the dashboard at {url} for {minutes} minutes");
+            audit::record(&app, Source::Tray, "share", detail);
+            crate::dialog::notice(
+                &app,
+                &t("share.title"),
+                t_with(
+                    "share.started",
+                    &[("url", &url), ("minutes", &minutes.to_string())],
+                ),
+                MessageDialogKind::Info,
+            )
+            .await;
+        }
+        Err(e) => {
+            error!("Failed to share the dashboard: {:#}", e);
+            crate::dialog::notice(
+                &app,
+                &t("share.title"),
+                t_with("share.failed", &[("error", &format!("{e:#}"))]),
+                MessageDialogKind::Error,
+            )
+            .await;
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//! The tray's "Use Copied Snippet..." item (see [`crate::snippets`]): add
+//! the snippet to a device config the user picks, or check it against the
+//! installed ESPHome. Dismissing the choice checks, which changes nothing.
+
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use tauri::AppHandle;
+use tauri_plugin_dialog::MessageDialogKind;
+use tracing::error;
+
+use crate::audit::{self, Source};
+use crate::devices::{snippet, undo};
+use crate::i18n::{t, t_with};
+use crate::store::Event;
+use crate::AppState;
+
+/// Lines of the snippet shown when asking what to do with it.
+const PREVIEW_LINES: usize = 8;
+
+pub(super) async fn run(app: AppHandle, state: Arc<AppState>) {
+    let Some(text) = state.store.snapshot().snippet else {
+        return;
+    };
+    let add = crate::dialog::confirm(
+        &app,
+        &t("snippets.title"),
+        t_with("snippets.choose", &[("snippet", &preview(&text))]),
+        &t("snippets.add"),
+        &t("snippets.check"),
+    )
+    .await;
+    if add {
+        add_to_device(&app, &state, &text).await;
+    } else {
+        check(&app, &state, &text).await;
+    }
+}
+
+async fn add_to_device(app: &AppHandle, state: &Arc<AppState>, text: &str) {
+    let config_dir = state.daemon.config_dir().clone();
+    let Some(path) = crate::dialog::pick_yaml(app, &t("snippets.pick"), config_dir).await else {
+        return;
+    };
+    let file = path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned();
+    let result = (|| -> Result<()> {
+        let config =
+            std::fs::read_to_string(&path).with_context(|| format!("Failed to read {path:?}"))?;
+        let merged = snippet::merge(&config, text)?;
+        let data_dir = crate::platform::get_data_dir(app)?;
+        let label = format!("add a snippet to {file}");
+        undo::transaction(&data_dir, &label, |change| change.write(&path, merged))
+    })();
+    match result {
+        Ok(()) => {
+            audit::record(
+                app,
+                Source::Tray,
+                "snippet",
+                format!("added a snippet to {file}"),
+            );
+            state.store.publish(Event::Snippet(None));
+            crate::dialog::notice(
+                app,
+                &t("snippets.title"),
+                t_with("snippets.added", &[("file", &file)]),
+                MessageDialogKind::Info,
+            )
+            .await;
+        }
+        Err(e) => {
+            error!("Failed to add the snippet to {:?}: {:#}", path, e);
+            crate::dialog::notice(
+                app,
+                &t("snippets.title"),
+                t_with("snippets.add_failed", &[("error", &format!("{e:#}"))]),
+                MessageDialogKind::Error,
+            )
+            .await;
+        }
+    }
+}
+
+async fn check(app: &AppHandle, state: &Arc<AppState>, text: &str) {
+    let preset = state.settings.read().await.workspace.new_device.clone();
+    let result = match crate::platform::get_data_dir(app) {
+        Ok(data_dir) => snippet::check(&state.daemon, &data_dir, &preset, text).await,
+        Err(e) => Err(e.into()),
+    };
+    let version = state
+        .store
+        .snapshot()
+        .esphome_version
+        .unwrap_or_else(|| t("version.unknown"));
+    let (message, kind) = match result {
+        Ok(None) => (
+            t_with("snippets.valid", &[("version", &version)]),
+            MessageDialogKind::Info,
+        ),
+        Ok(Some(output)) => (
+            t_with(
+                "snippets.invalid",
+                &[("version", &version), ("output", &output)],
+            ),
+            MessageDialogKind::Warning,
+        ),
+        Err(e) => {
+            error!("Failed to check the snippet: {:#}", e);
+            (
+                t_with("snippets.check_failed", &[("error", &format!("{e:#}"))]),
+                MessageDialogKind::Error,
+            )
+        }
+    };
+    crate::dialog::notice(app, &t("snippets.title"), message, kind).await;
+}
+
+/// The first [`PREVIEW_LINES`] of `text`.
+fn preview(text: &str) -> String {
+    let lines: Vec<&str> = text.trim().lines().collect();
+    let mut shown = lines[..lines.len().min(PREVIEW_LINES)].join("\n");
+    if lines.len() > PREVIEW_LINES {
+        shown.push_str("\n…");
+    }
+    shown
+}
@@ -0,0 +1,90 @@
+//! The tray's "Undo Last Config Change..." item (see
+//! [`crate::devices::undo`]): say what the app last changed in the config
+//! folder, and undo it if the user agrees.
+
+use tauri::AppHandle;
+use tauri_plugin_dialog::MessageDialogKind;
+use tracing::{error, info};
+
+use crate::audit::{self, Source};
+use crate::control::ops;
+use crate::devices::undo;
+use crate::i18n::{t, t_with};
+
+pub(super) async fn run(app: AppHandle) {
+    let data_dir = match crate::platform::get_data_dir(&app) {
+        Ok(dir) => dir,
+        Err(e) => {
+            error!("Failed to resolve the data directory: {}", e);
+            return;
+        }
+    };
+    let last = match undo::last(&data_dir) {
+        Ok(last) => last,
+        Err(e) => {
+            error!("Failed to read the undo stack: {:#}", e);
+            failed(&app, &e).await;
+            return;
+        }
+    };
+    let Some(entry) = last else {
+        crate::dialog::notice(
+            &app,
+            &t("undo.title"),
+            t("undo.nothing"),
+            MessageDialogKind::Info,
+        )
+        .await;
+        return;
+    };
+    let confirmed = crate::dialog::confirm(
+        &app,
+        &t("undo.title"),
+        t_with(
+            "undo.confirm",
+            &[("change", &entry.label), ("time", &entry.time)],
+        ),
+        &t("undo.undo"),
+        &t("undo.cancel"),
+    )
+    .await;
+    if !confirmed {
+        return;
+    }
+    // Undoing an archive or a rename restores what it replaced.
+    let Ok(state) = crate::window::app_state(&app) else {
+        return;
+    };
+    if !ops::authorize(&state, t("auth.undo"), &|_, _| {}).await {
+        return;
+    }
+    match tokio::task::spawn_blocking(move || undo::undo(&data_dir)).await {
+        Ok(Ok(Some(entry))) => {
+            info!("Undid {}", entry.label);
+            audit::record(&app, Source::Tray, "undo", entry.label.clone());
+            crate::dialog::notice(
+                &app,
+                &t("undo.title"),
+                t_with("undo.done", &[("change", &entry.label)]),
+                MessageDialogKind::Info,
+            )
+            .await;
+        }
+        Ok(Ok(None)) => {}
+        Ok(Err(e)) => {
+            error!("Failed to undo: {:#}", e);
+            failed(&app, &e).await;
+        }
+        Err(e) => error!("Undo panicked: {}", e),
+    }
+}
+
+async fn failed(app: &AppHandle, e: &anyhow::Error) {
+    crate::dialog::notice(
+        app,
+        &t("undo.title"),
+        t_with("undo.failed", &[("error", &format!("{e:#}"))]),
+        MessageDialogKind::Error,
+    )
+    .await;
+}
@@ -0,0 +1,74 @@
+//! The tray's "Set up VS Code..." item: write the workspace (see
+//! [`crate::devices::vscode`]) and open the config directory in VS Code.
+
+use std::sync::Arc;
+
+use tauri::AppHandle;
+use tauri_plugin_dialog::MessageDialogKind;
+use tracing::{error, info, warn};
+
+use crate::devices::{editor, vscode};
+use crate::i18n::{t, t_with};
+use crate::AppState;
+
+pub(super) async fn run(app: AppHandle, state: Arc<AppState>) {
+    let config_dir = state.daemon.config_dir().clone();
+    let address = state.daemon.address();
+    let python = state.daemon.host_python().cloned();
+    // No tasks unless the runtime is native: elsewhere its ESPHome isn't on
+    // this side.
+    let tasks = python.is_some();
+    let dir = config_dir.clone();
+    let result =
+        tokio::task::spawn_blocking(move || vscode::set_up(&dir, address, python.as_deref())).await;
+    let outcome = match result {
+        Ok(Ok(outcome)) => outcome,
+        Ok(Err(e)) => return failed(&app, format!("{e:#}")).await,
+        Err(e) => return failed(&app, e.to_string()).await,
+    };
+    info!(
+        "Set up VS Code in {:?}: wrote {:?}, skipped {:?}",
+        config_dir, outcome.written, outcome.skipped
+    );
+
+    let mut message = t_with(
+        "vscode.done",
+        &[("path", &config_dir.join(".vscode").display().to_string())],
+    );
+    if tasks {
+        message += " ";
+        message += &t("vscode.tasks");
+    }
+    if !outcome.skipped.is_empty() {
+        let files: Vec<String> = outcome
+            .skipped
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect();
+        message += "\n\n";
+        message += &t_with("vscode.skipped", &[("files", &files.join("\n"))]);
+    }
+    match editor::detect().into_iter().find(|e| e.id == "code") {
+        Some(code) => {
+            if let Err(e) = code.open(&config_dir) {
+                warn!("Failed to open the config folder in VS Code: {:#}", e);
+            }
+        }
+        None => {
+            message += "\n\n";
+            message += &t("vscode.not_installed");
+        }
+    }
+    crate::dialog::notice(&app, &t("vscode.title"), message, MessageDialogKind::Info).await;
+}
+
+async fn failed(app: &AppHandle, error: String) {
+    error!("Failed to set up VS Code: {}", error);
+    crate::dialog::notice(
+        app,
+        &t("vscode.title"),
+        t_with("vscode.failed", &[("error", &error)]),
+        MessageDialogKind::Error,
+    )
+    .await;
+}
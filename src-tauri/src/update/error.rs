@@ -0,0 +1,60 @@
+//! Why an update check or install failed.
+
+use crate::control::protocol::channel_name;
+use crate::error::UserError;
+use crate::i18n::{t, t_with};
+use crate::platform::PlatformError;
+use crate::settings::ReleaseChannel;
+
+/// A failure of one of [`UpdateChecker`](super::UpdateChecker)'s checks or
+/// installs.
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum UpdateError {
+    /// The package index couldn't be reached or answered nonsense.
+    #[error(transparent)]
+    Network(anyhow::Error),
+    /// The index answered but had nothing to offer on the channel.
+    #[error("Could not determine latest {} version", channel_name(*.0))]
+    NoRelease(ReleaseChannel),
+    /// pip failed, the broken-RECORD recovery included.
+    #[error(transparent)]
+    Install(anyhow::Error),
+    /// The user cancelled the install before pip changed anything.
+    #[error("The install was cancelled")]
+    Cancelled,
+    #[error(transparent)]
+    Platform(#[from] PlatformError),
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+impl UserError for UpdateError {
+    fn kind(&self) -> &'static str {
+        match self {
+            UpdateError::Network(_) => "update.network",
+            UpdateError::NoRelease(_) => "update.no_release",
+            UpdateError::Install(_) => "update.install",
+            UpdateError::Cancelled => "update.cancelled",
+            UpdateError::Platform(e) => e.kind(),
+            UpdateError::Other(_) => "update.other",
+        }
+    }
+
+    fn user_message(&self) -> String {
+        match self {
+            UpdateError::Network(e) => {
+                t_with("errors.update_network", &[("error", &e.to_string())])
+            }
+            UpdateError::NoRelease(channel) => t_with(
+                "errors.update_no_release",
+                &[("channel", channel_name(*channel))],
+            ),
+            UpdateError::Install(e) => {
+                t_with("errors.update_install", &[("error", &e.to_string())])
+            }
+            UpdateError::Cancelled => t("errors.update_cancelled"),
+            UpdateError::Platform(e) => e.user_message(),
+            UpdateError::Other(e) => e.to_string(),
+        }
+    }
+}
@@ -0,0 +1,121 @@
+//! Update flows end to end: the checker against a mock PyPI, and installs
+//! with RECORD recovery against a fake venv.
+
+use super::UpdateChecker;
+use crate::error::UserError;
+use crate::settings::ReleaseChannel;
+use crate::testing::MockDashboard;
+
+/// PyPI's answer for `esphome` while 2024.8 is in beta.
+const ESPHOME_JSON: &str = r#"{
+    "info": {"version": "2024.7.3"},
+    "releases": {
+        "2024.7.3": [{"yanked": false}],
+        "2024.8.0b1": [{"yanked": false}],
+        "2024.8.0b2": [{"yanked": true}]
+    }
+}"#;
+
+fn checker(index: &MockDashboard) -> UpdateChecker {
+    UpdateChecker::with_index(format!("http://127.0.0.1:{}", index.port()))
+}
+
+#[tokio::test]
+async fn checks_each_channel_against_the_index() {
+    let index = MockDashboard::start();
+    index.route("/esphome/json", 200, ESPHOME_JSON);
+    let checker = checker(&index);
+
+    assert_eq!(
+        checker.check(ReleaseChannel::Stable).await.unwrap(),
+        Some("2024.7.3".to_string())
+    );
+    // The yanked b2 is passed over.
+    assert_eq!(
+        checker.check(ReleaseChannel::Beta).await.unwrap(),
+        Some("2024.8.0b1".to_string())
+    );
+    // Dev doesn't ask at all.
+    assert_eq!(checker.check(ReleaseChannel::Dev).await.unwrap(), None);
+    assert_eq!(index.requests().len(), 2);
+}
+
+#[tokio::test]
+async fn an_unreachable_index_fails_the_check() {
+    let index = MockDashboard::start();
+    index.set_status(503);
+    let err = checker(&index)
+        .check(ReleaseChannel::Stable)
+        .await
+        .unwrap_err();
+    assert!(format!("{err:#}").contains("esphome"), "{err:#}");
+    assert_eq!(err.kind(), "update.network");
+}
+
+#[cfg(unix)]
+mod installs {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::super::install::{install_with_record_recovery, run_esphome_install};
+    use super::super::progress::Progress;
+    use crate::platform::run_python_capture_stdout;
+    use crate::testing::FakeVenv;
+
+    /// Install `version` into `venv` the way an update does, counting repairs.
+    async fn install(venv: &FakeVenv, version: &str, repairs: &AtomicUsize) -> anyhow::Result<()> {
+        let python = venv.python();
+        let progress = Progress::hidden(format!("ESPHome {version}"));
+        install_with_record_recovery(
+            || run_esphome_install(&python, version, &progress),
+            || async {
+                repairs.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            },
+            "ESPHome installed",
+            "ESPHome install failed",
+        )
+        .await
+    }
+
+    fn installed(venv: &FakeVenv) -> Option<String> {
+        run_python_capture_stdout(&venv.python(), ["-m", "esphome", "version"]).unwrap()
+    }
+
+    #[tokio::test]
+    async fn installs_the_pinned_version() {
+        let venv = FakeVenv::new();
+        venv.set_esphome_version(Some("2024.6.0"));
+        let repairs = AtomicUsize::new(0);
+
+        install(&venv, "2024.7.3", &repairs).await.unwrap();
+        assert_eq!(installed(&venv).as_deref(), Some("Version: 2024.7.3"));
+        assert_eq!(repairs.load(Ordering::SeqCst), 0);
+        assert!(venv
+            .calls()
+            .iter()
+            .any(|call| call.ends_with("pip install esphome==2024.7.3")));
+    }
+
+    #[tokio::test]
+    async fn repairs_and_retries_a_missing_record() {
+        let venv = FakeVenv::new();
+        venv.fail_pip_with_missing_record(1);
+        let repairs = AtomicUsize::new(0);
+
+        install(&venv, "2024.7.3", &repairs).await.unwrap();
+        assert_eq!(repairs.load(Ordering::SeqCst), 1);
+        assert_eq!(installed(&venv).as_deref(), Some("Version: 2024.7.3"));
+    }
+
+    #[tokio::test]
+    async fn gives_up_when_the_retry_aborts_too() {
+        let venv = FakeVenv::new();
+        venv.fail_pip_with_missing_record(2);
+        let repairs = AtomicUsize::new(0);
+
+        let err = install(&venv, "2024.7.3", &repairs).await.unwrap_err();
+        assert!(err.to_string().contains("ESPHome install failed"), "{err}");
+        assert_eq!(repairs.load(Ordering::SeqCst), 1);
+        assert_eq!(installed(&venv), None);
+    }
+}
@@ -5,9 +5,9 @@
 
 use anyhow::{Context, Result};
 use tauri::AppHandle;
-use tauri_plugin_notification::NotificationExt;
 use tracing::{info, warn};
 
+use super::progress::Progress;
 use crate::i18n::{t, t_with};
 use crate::platform;
 use crate::settings::Backend;
@@ -110,13 +110,12 @@ pub(super) async fn run_device_builder_install(
     python_path: &std::path::Path,
     backend: Backend,
     version: Option<&str>,
+    progress: &Progress,
 ) -> Result<std::process::Output> {
     let args = device_builder_install_args(backend, version);
     let mut cmd = platform::pip_command(python_path);
     cmd.args(&args);
-    platform::run_pip(cmd)
-        .await
-        .context("Failed to run pip install")
+    progress.run(cmd).await
 }
 
 /// URL of the ESPHome dev-branch source archive installed on the Dev channel.
@@ -127,12 +126,92 @@ const ESPHOME_DEV_ZIP_URL: &str = "https://github.com/esphome/esphome/archive/de
 /// A plain `--force-reinstall`, which uninstalls the existing copy of each
 /// affected package first. There is deliberately no `--ignore-installed`
 /// variant: see [`install_with_record_recovery`].
-pub(super) async fn run_dev_install(python_path: &std::path::Path) -> Result<std::process::Output> {
+pub(super) async fn run_dev_install(
+    python_path: &std::path::Path,
+    progress: &Progress,
+) -> Result<std::process::Output> {
     let mut cmd = platform::pip_command(python_path);
     cmd.args(["--force-reinstall", ESPHOME_DEV_ZIP_URL]);
-    platform::run_pip(cmd)
-        .await
-        .context("Failed to run pip install")
+    progress.run(cmd).await
+}
+
+/// The pip requirement installing ESPHome from `source`, a git URL with an
+/// optional ref after `@`, e.g. `git+https://github.com/esphome/esphome@dev`
+/// or a fork's branch. `None` for anything else, such as a version number.
+pub(crate) fn git_requirement(source: &str) -> Option<String> {
+    let url = source.strip_prefix("git+")?;
+    let location = ["https://", "http://", "ssh://", "file://"]
+        .iter()
+        .find_map(|scheme| url.strip_prefix(scheme))?;
+    // Passed to pip as one argument, so whitespace can't split it.
+    if location.is_empty() || source.contains(char::is_whitespace) {
+        return None;
+    }
+    Some(format!("esphome @ {source}"))
+}
+
+/// Run `pip install` for ESPHome from a git `requirement`
+/// ([`git_requirement`]).
+///
+/// `--force-reinstall` like the dev install: a branch moves on without its
+/// version number changing, and pip would otherwise keep the copy it has.
+pub(super) async fn run_git_install(
+    python_path: &std::path::Path,
+    requirement: &str,
+    progress: &Progress,
+) -> Result<std::process::Output> {
+    let mut cmd = platform::pip_command(python_path);
+    cmd.args(["--force-reinstall", requirement]);
+    progress.run(cmd).await
+}
+
+/// Where pip recorded it installed ESPHome from (PEP 610's
+/// `direct_url.json`), or nothing for an install from an index.
+const DIRECT_URL_SCRIPT: &str = "import importlib.metadata as m; \
+     print(m.distribution('esphome').read_text('direct_url.json') or '')";
+
+/// PEP 610's record of a direct install, as far as it matters here.
+#[derive(serde::Deserialize)]
+struct DirectUrl {
+    url: String,
+    vcs_info: Option<VcsInfo>,
+}
+
+#[derive(serde::Deserialize)]
+struct VcsInfo {
+    vcs: String,
+    requested_revision: Option<String>,
+    commit_id: String,
+}
+
+/// The git URL and ref ESPHome was installed from, as shown
+/// ("github.com/esphome/esphome@dev"), or `None` when it came from PyPI or
+/// the dev channel's source archive.
+pub fn installed_esphome_source(app_handle: &AppHandle) -> Result<Option<String>> {
+    let python_path = platform::get_python_path(app_handle)?;
+    let Some(direct_url) =
+        platform::run_python_capture_stdout(&python_path, ["-c", DIRECT_URL_SCRIPT])
+            .context("Failed to read where ESPHome was installed from")?
+    else {
+        return Ok(None);
+    };
+    Ok(git_source(&direct_url))
+}
+
+/// [`installed_esphome_source`] from the contents of `direct_url.json`: the
+/// requested ref, else the commit, after the URL without its scheme.
+fn git_source(direct_url: &str) -> Option<String> {
+    let direct: DirectUrl = serde_json::from_str(direct_url).ok()?;
+    let vcs = direct.vcs_info.filter(|vcs| vcs.vcs == "git")?;
+    let url = direct
+        .url
+        .split_once("://")
+        .map_or(direct.url.as_str(), |(_, rest)| rest);
+    let url = url.strip_suffix(".git").unwrap_or(url);
+    let reference = vcs
+        .requested_revision
+        .unwrap_or_else(|| vcs.commit_id.chars().take(7).collect());
+    Some(format!("{url}@{reference}"))
 }
 
 /// Run `pip install` for a pinned stable/beta ESPHome release (`esphome==X`).
@@ -144,12 +223,28 @@ pub(super) async fn run_dev_install(python_path: &std::path::Path) -> Result<std
 pub(super) async fn run_esphome_install(
     python_path: &std::path::Path,
     version: &str,
+    progress: &Progress,
 ) -> Result<std::process::Output> {
     let mut cmd = platform::pip_command(python_path);
     cmd.arg(format!("esphome=={version}"));
-    platform::run_pip(cmd)
-        .await
-        .context("Failed to run pip install")
+    progress.run(cmd).await
+}
+
+/// Run `pip install` for one package in `python_path`'s environment, at
+/// exactly `version`, or the newest release with `None`. Pinning is what lets
+/// it downgrade, as for ESPHome itself.
+pub(super) async fn run_package_install(
+    python_path: &std::path::Path,
+    package: &str,
+    version: Option<&str>,
+    progress: &Progress,
+) -> Result<std::process::Output> {
+    let mut cmd = platform::pip_command(python_path);
+    match version {
+        Some(version) => cmd.arg(format!("{package}=={version}")),
+        None => cmd.args(["--upgrade", package]),
+    };
+    progress.run(cmd).await
 }
 
 /// What the user can actually do about a tree we could not repair.
@@ -219,6 +314,65 @@ pub(super) async fn probe_esphome(python_path: &std::path::Path) -> Result<Optio
         .context("ESPHome health probe task panicked or was cancelled")?
 }
 
+/// What the startup integrity check found.
+pub(super) enum Integrity {
+    /// Intact, or nothing to check against.
+    Intact,
+    /// The bundle itself is damaged. A repair copies from it, so it can't
+    /// help; the user has been told to reinstall.
+    BundleDamaged,
+    /// The bundle is fine but the user's copy of it isn't: a repair fixes it.
+    TreeDamaged(String),
+}
+
+/// Check the install against the bundle manifest, off the async executor
+/// (it stats every bundled file and hashes the binaries). A check that can't
+/// run is logged and counts as intact: the health probe still runs after it.
+pub(super) async fn check_integrity(app_handle: &AppHandle) -> Integrity {
+    let app = app_handle.clone();
+    let report = match tokio::task::spawn_blocking(move || platform::verify_install(&app)).await {
+        Ok(Ok(Some(report))) => report,
+        Ok(Ok(None)) => {
+            tracing::debug!("No bundle manifest; skipping the integrity check");
+            return Integrity::Intact;
+        }
+        Ok(Err(e)) => {
+            warn!("Could not check the install's integrity: {e:#}");
+            return Integrity::Intact;
+        }
+        Err(e) => {
+            warn!("The install integrity check panicked or was cancelled: {e}");
+            return Integrity::Intact;
+        }
+    };
+    if let Some(first) = report.bundle.first() {
+        warn!(
+            "{} bundled file(s) are missing or damaged: {}",
+            report.bundle.len(),
+            report.bundle.join("; ")
+        );
+        notify_repair_needed(
+            app_handle,
+            t_with(
+                "update.bundle_damaged",
+                &[
+                    ("count", &report.bundle.len().to_string()),
+                    ("example", first),
+                ],
+            ),
+        );
+        return Integrity::BundleDamaged;
+    }
+    if report.user_tree.is_empty() {
+        return Integrity::Intact;
+    }
+    Integrity::TreeDamaged(format!(
+        "{} file(s) differ from the bundle: {}",
+        report.user_tree.len(),
+        report.user_tree.join("; ")
+    ))
+}
+
 /// Tell the user the tree is still broken after a repair, or that we could
 /// not confirm it is not.
 pub(super) fn notify_repair_incomplete(
@@ -246,13 +400,7 @@ pub(super) fn notify_repair_incomplete(
 /// build fails until it is dealt with, so a one-shot warning that scrolls out of
 /// the log is not enough.
 pub(super) fn notify_repair_needed(app_handle: &AppHandle, body: String) {
-    if let Err(e) = app_handle
-        .notification()
-        .builder()
-        .title(t("update.repair_failed_title"))
-        .body(body)
-        .show()
-    {
+    if let Err(e) = crate::notifications::show(app_handle, t("update.repair_failed_title"), body) {
         warn!("Failed to show the ESPHome repair notification: {e}");
     }
 }
@@ -666,4 +814,44 @@ mod tests {
         // The install is not retried after a failed repair.
         assert_eq!(counts, (1, 1));
     }
+
+    #[test]
+    fn git_requirement_takes_git_urls_only() {
+        assert_eq!(
+            git_requirement("git+https://github.com/esphome/esphome@dev").as_deref(),
+            Some("esphome @ git+https://github.com/esphome/esphome@dev")
+        );
+        assert!(git_requirement("git+ssh://git@github.com/me/esphome@fix-uart").is_some());
+        for source in [
+            "2025.4.0",
+            "dev",
+            "https://github.com/esphome/esphome",
+            "git+https://",
+            "git+https://github.com/esphome/esphome@dev --pre",
+        ] {
+            assert_eq!(git_requirement(source), None, "{source}");
+        }
+    }
+
+    #[test]
+    fn git_source_names_the_url_and_ref() {
+        let branch = r#"{"url": "https://github.com/me/esphome.git",
+            "vcs_info": {"vcs": "git", "requested_revision": "fix-uart",
+                         "commit_id": "0123456789abcdef"}}"#;
+        assert_eq!(
+            git_source(branch).as_deref(),
+            Some("github.com/me/esphome@fix-uart")
+        );
+        let default_branch = r#"{"url": "https://github.com/esphome/esphome",
+            "vcs_info": {"vcs": "git", "commit_id": "0123456789abcdef"}}"#;
+        assert_eq!(
+            git_source(default_branch).as_deref(),
+            Some("github.com/esphome/esphome@0123456")
+        );
+        // The dev channel's source archive, and an install from PyPI.
+        let archive = r#"{"url": "https://github.com/esphome/esphome/archive/dev.zip",
+            "archive_info": {}}"#;
+        assert_eq!(git_source(archive), None);
+        assert_eq!(git_source(""), None);
+    }
 }
@@ -4,84 +4,76 @@
 //! if an update is available. Supports stable, beta, and dev release channels.
 
 use anyhow::{Context, Result};
-use serde::Deserialize;
-use std::collections::HashMap;
 use tauri::AppHandle;
 use tauri_plugin_dialog::MessageDialogKind;
 use tracing::{debug, info, warn};
 
 use crate::control::protocol::channel_name;
+use crate::error::UserError;
 use crate::i18n::{t, t_with};
 use crate::platform;
 use crate::settings::{Backend, ReleaseChannel};
 
+mod error;
+#[cfg(test)]
+mod flows;
 mod install;
 mod notify;
+mod packages;
+pub(crate) mod progress;
+mod pypi;
+mod releases;
+mod reminders;
 mod version;
 
-pub use install::{get_installed_device_builder_version, installed_esphome_version};
+pub(crate) use error::UpdateError;
+pub(crate) use install::git_requirement;
+pub use install::{
+    get_installed_device_builder_version, installed_esphome_source, installed_esphome_version,
+};
 pub(crate) use notify::notify_update_available;
+pub(crate) use reminders::{
+    ask_to_update, is_skipped, should_notify, skip, DESKTOP_KEY, DEVICE_BUILDER_KEY, ESPHOME_KEY,
+};
 pub(crate) use version::is_newer_version;
 
 use install::{
-    detect_device_builder_version_with_heal_async, install_with_record_recovery,
+    check_integrity, detect_device_builder_version_with_heal_async, install_with_record_recovery,
     installed_esphome_version_async, interpreter_usable, notify_repair_incomplete,
     notify_repair_needed, probe_esphome, repair_hint, run_dev_install, run_device_builder_install,
-    run_esphome_install,
+    run_esphome_install, run_git_install, Integrity,
 };
-use notify::{notify_if_newer, prompt_if_newer};
+use notify::{notify_if_newer, prompt_if_newer, UpdateWording, DEVICE_BUILDER_WORDING};
+use progress::Progress;
+use pypi::PYPI_URL;
 use version::{find_latest_any, select_beta_target};
 
-/// PyPI package info response (used for stable channel)
-#[derive(Debug, Deserialize)]
-struct PyPIResponse {
-    info: PyPIInfo,
-    releases: HashMap<String, Vec<PyPIRelease>>,
-}
-
-#[derive(Debug, Deserialize)]
-struct PyPIInfo {
-    version: String,
-}
-
-/// A single release file entry from PyPI. We only need to know whether the
-/// file has been yanked — PyPI keeps a version's key in `releases` even after
-/// every file is yanked or removed, so a lingering entry does not mean the
-/// version is actually installable.
-#[derive(Debug, Deserialize)]
-struct PyPIRelease {
-    #[serde(default)]
-    yanked: bool,
-}
-
 /// Update checker
 pub struct UpdateChecker {
     client: reqwest::Client,
+    /// Base of the JSON API: PyPI's, or the mirror's in `index_url`.
+    index: String,
 }
 
 impl UpdateChecker {
     /// Create a new update checker
     pub fn new() -> Self {
         Self {
-            client: reqwest::Client::builder()
+            client: crate::network::client()
                 .timeout(std::time::Duration::from_secs(30))
                 .build()
                 .unwrap_or_default(),
+            index: crate::network::json_api().unwrap_or_else(|| PYPI_URL.to_string()),
         }
     }
 
-    /// Fetch and parse the PyPI JSON metadata for `package`.
-    ///
-    /// Callers pass fixed internal package names, so no URL encoding is needed.
-    async fn fetch_pypi(&self, package: &str) -> Result<PyPIResponse> {
-        self.client
-            .get(format!("https://pypi.org/pypi/{package}/json"))
-            .send()
-            .await
-            .with_context(|| format!("Failed to fetch PyPI info for {package}"))?
-            .json()
-            .await
-            .with_context(|| format!("Failed to parse PyPI response for {package}"))
+    /// A checker asking `index` instead of PyPI.
+    #[cfg(test)]
+    fn with_index(index: String) -> Self {
+        Self {
+            index,
+            ..Self::new()
+        }
     }
 
     /// Check for updates and return the latest version string for the given channel.
@@ -89,11 +81,14 @@ impl UpdateChecker {
     /// - Stable: returns the latest stable version from PyPI
     /// - Beta: returns the latest pre-release (beta) version from PyPI
     /// - Dev: always returns None (dev channel doesn't do version-based updates)
-    pub async fn check(&self, channel: ReleaseChannel) -> Result<Option<String>> {
+    pub async fn check(&self, channel: ReleaseChannel) -> Result<Option<String>, UpdateError> {
         match channel {
             ReleaseChannel::Stable => {
                 debug!("Checking for stable ESPHome updates on PyPI");
-                let response = self.fetch_pypi("esphome").await?;
+                let response = self
+                    .fetch_pypi("esphome")
+                    .await
+                    .map_err(UpdateError::Network)?;
 
                 let latest = response.info.version;
                 info!("Latest stable ESPHome version on PyPI: {}", latest);
@@ -101,7 +96,10 @@ impl UpdateChecker {
             }
             ReleaseChannel::Beta => {
                 debug!("Checking for beta ESPHome updates on PyPI");
-                let response = self.fetch_pypi("esphome").await?;
+                let response = self
+                    .fetch_pypi("esphome")
+                    .await
+                    .map_err(UpdateError::Network)?;
 
                 // Pick the version to offer on the beta channel. We want the
                 // newest beta (e.g. "2025.4.0b1"), but only when it is actually
@@ -198,7 +196,7 @@ impl UpdateChecker {
                 crate::dialog::notice(
                     app_handle,
                     &t("update.check_failed_title"),
-                    t_with("update.check_failed", &[("error", &e.to_string())]),
+                    t_with("update.check_failed", &[("error", &e.user_message())]),
                     MessageDialogKind::Error,
                 )
                 .await;
@@ -218,6 +216,7 @@ impl UpdateChecker {
         prompt_if_newer(
             app_handle,
             &UpdateWording {
+                key: reminders::ESPHOME_KEY,
                 component: "ESPHome",
                 log_prefix: "Update",
                 channel_label: Some(channel_label),
@@ -278,6 +277,7 @@ impl UpdateChecker {
         notify_if_newer(
             app_handle,
             &UpdateWording {
+                key: reminders::ESPHOME_KEY,
                 component: "ESPHome",
                 log_prefix: "Update",
                 channel_label: Some(channel_label),
@@ -289,34 +289,75 @@ impl UpdateChecker {
     }
 
     /// Perform an update to the specified version, or install from git for dev channel.
+    ///
+    /// A `version` that is a git URL ([`git_requirement`]), e.g.
+    /// `git+https://github.com/esphome/esphome@dev` or a fork's branch,
+    /// installs that instead, on any channel; the tray then says the build
+    /// came from git.
+    ///
+    /// pip's progress shows in the update progress window, where the install
+    /// can be cancelled until pip starts replacing the installed copy; see
+    /// [`progress`].
     pub async fn update_to(
         &self,
         app_handle: &AppHandle,
         version: &str,
         channel: ReleaseChannel,
-    ) -> Result<()> {
+    ) -> Result<(), UpdateError> {
         let python_path = platform::get_python_path(app_handle)?;
+        // Whatever pip does to them, the next start puts the workspace's
+        // extra packages back.
+        crate::daemon::forget_extras(&python_path);
+
+        if let Some(requirement) = git_requirement(version) {
+            info!("Installing ESPHome from {}", version);
+            let progress = Progress::start(
+                app_handle,
+                t_with("update_progress.git", &[("source", version)]),
+            );
 
-        if channel == ReleaseChannel::Dev || version == "dev" {
+            // The same broken-RECORD recovery as the other paths.
+            let pp = python_path.clone();
+            let result = install_with_record_recovery(
+                || {
+                    let pp = pp.clone();
+                    let requirement = requirement.clone();
+                    let progress = &progress;
+                    async move { run_git_install(&pp, &requirement, progress).await }
+                },
+                || self.repair_python_tree(app_handle),
+                "ESPHome installed successfully from git",
+                "pip install from git failed",
+            )
+            .await;
+            result.map_err(|e| progress.error(e))
+        } else if channel == ReleaseChannel::Dev || version == "dev" {
             info!("Installing ESPHome from GitHub (dev channel)");
+            let progress = Progress::start(app_handle, t("update_progress.dev"));
 
             // A clean --force-reinstall. If pip aborts because a dependency
             // (e.g. zeroconf) has no RECORD file, repair the tree and retry
             // against a clean copy — same broken-RECORD recovery as #155, here
             // on the dev/GitHub path (#183).
             let pp = python_path.clone();
-            install_with_record_recovery(
-                move || {
+            let result = install_with_record_recovery(
+                || {
                     let pp = pp.clone();
-                    async move { run_dev_install(&pp).await }
+                    let progress = &progress;
+                    async move { run_dev_install(&pp, progress).await }
                 },
                 || self.repair_python_tree(app_handle),
                 "ESPHome dev installed successfully from GitHub",
                 "pip install from GitHub failed",
             )
-            .await
+            .await;
+            result.map_err(|e| progress.error(e))
         } else {
             info!("Updating ESPHome to version {}", version);
+            let progress = Progress::start(
+                app_handle,
+                t_with("update_progress.esphome", &[("version", version)]),
+            );
 
             // Pin the exact version and route through the shared broken-RECORD
             // recovery. A stable/beta `pip install esphome==X` uninstalls the
@@ -327,17 +368,19 @@ impl UpdateChecker {
             // stable/beta was the one install path lacking that parity.
             let pp = python_path.clone();
             let version = version.to_string();
-            install_with_record_recovery(
-                move || {
+            let result = install_with_record_recovery(
+                || {
                     let pp = pp.clone();
                     let version = version.clone();
-                    async move { run_esphome_install(&pp, &version).await }
+                    let progress = &progress;
+                    async move { run_esphome_install(&pp, &version, progress).await }
                 },
                 || self.repair_python_tree(app_handle),
                 "ESPHome updated successfully",
                 "pip install failed",
             )
-            .await
+            .await;
+            result.map_err(|e| progress.error(e))
         }
     }
 
@@ -348,8 +391,9 @@ impl UpdateChecker {
         &self,
         app_handle: &AppHandle,
         backend: Backend,
-    ) -> Result<()> {
+    ) -> Result<(), UpdateError> {
         let python_path = platform::get_python_path(app_handle)?;
+        crate::daemon::forget_extras(&python_path);
 
         info!("Installing/upgrading esphome-device-builder ({})", backend);
 
@@ -379,18 +423,23 @@ impl UpdateChecker {
         // A clean upgrade, which uninstalls the old copy normally. Only if pip
         // aborts on a missing RECORD file (#155) do we repair the tree and
         // retry against a clean copy.
+        let progress = Progress::start(app_handle, t("update_progress.builder"));
         let pp = python_path.clone();
-        install_with_record_recovery(
-            move || {
+        let result = install_with_record_recovery(
+            || {
                 let pp = pp.clone();
                 let version = version.clone();
-                async move { run_device_builder_install(&pp, backend, version.as_deref()).await }
+                let progress = &progress;
+                async move {
+                    run_device_builder_install(&pp, backend, version.as_deref(), progress).await
+                }
             },
             || self.repair_python_tree(app_handle),
             "esphome-device-builder installed/upgraded successfully",
             "pip install esphome-device-builder failed",
         )
-        .await
+        .await;
+        result.map_err(|e| progress.error(e))
     }
 
     /// Repair a broken managed Python tree by re-copying the bundled one.
@@ -419,6 +468,11 @@ impl UpdateChecker {
     /// the stale directory sitting there breaking every compile. So look for the
     /// damage directly rather than waiting for an install to fail.
     ///
+    /// The bundle manifest is checked first ([`check_integrity`]): a copy whose
+    /// binaries no longer match the bundle is repaired without probing, and a
+    /// damaged bundle, which a repair would only copy again, is reported
+    /// instead.
+    ///
     /// Never blocks the launch: a probe that cannot run, an exhausted attempt
     /// budget, or a failed repair all continue to start the app. But a tree left
     /// broken is never silent — every compile will fail, and the user is the only
@@ -452,7 +506,14 @@ impl UpdateChecker {
             }
         };
 
-        let detail = match probe_esphome(&python_path).await {
+        // Files the manifest says are missing or changed condemn the tree
+        // without a probe; a damaged bundle can't be repaired from at all.
+        let probe = match check_integrity(app_handle).await {
+            Integrity::BundleDamaged => return,
+            Integrity::TreeDamaged(detail) => Ok(Some(detail)),
+            Integrity::Intact => probe_esphome(&python_path).await,
+        };
+        let detail = match probe {
             Ok(None) => {
                 debug!("ESPHome health probe passed");
                 platform::clear_repair_count(&python_parent_dir);
@@ -572,8 +633,11 @@ impl UpdateChecker {
     /// Query PyPI for the latest available `esphome-device-builder` version.
     /// `Backend::BuilderStable` returns the latest final release; `BuilderBeta`
     /// returns the latest version including pre-releases.
-    pub async fn check_device_builder(&self, backend: Backend) -> Result<String> {
-        let response = self.fetch_pypi("esphome-device-builder").await?;
+    pub async fn check_device_builder(&self, backend: Backend) -> Result<String, UpdateError> {
+        let response = self
+            .fetch_pypi("esphome-device-builder")
+            .await
+            .map_err(UpdateError::Network)?;
 
         let include_pre = backend == Backend::BuilderBeta;
         let latest = if include_pre {
@@ -674,14 +738,14 @@ impl UpdateChecker {
         &self,
         app_handle: &AppHandle,
         channel: ReleaseChannel,
-    ) -> Result<()> {
+    ) -> Result<(), UpdateError> {
         match channel {
             ReleaseChannel::Stable => {
                 // Install the latest stable version
                 let latest = self
                     .check(ReleaseChannel::Stable)
                     .await?
-                    .context("Could not determine latest stable version")?;
+                    .ok_or(UpdateError::NoRelease(ReleaseChannel::Stable))?;
                 self.update_to(app_handle, &latest, ReleaseChannel::Stable)
                     .await
             }
@@ -690,7 +754,7 @@ impl UpdateChecker {
                 let latest = self
                     .check(ReleaseChannel::Beta)
                     .await?
-                    .context("Could not determine latest beta version")?;
+                    .ok_or(UpdateError::NoRelease(ReleaseChannel::Beta))?;
                 self.update_to(app_handle, &latest, ReleaseChannel::Beta)
                     .await
             }
@@ -701,51 +765,3 @@ impl UpdateChecker {
         }
     }
 }
-
-/// Per-component wording shared by the user-prompt and background-notify
-/// update-check tails, so the strings cannot drift between the two flows.
-struct UpdateWording<'a> {
-    /// Component display name, e.g. "ESPHome" or "ESPHome Device Builder".
-    component: &'a str,
-    /// Leading words of the "<log_prefix> available: a -> b" info log.
-    log_prefix: &'a str,
-    /// Release-channel label appended to the offered version, when shown.
-    channel_label: Option<&'a str>,
-}
-
-/// Wording for the `esphome-device-builder` check tails (no channel label;
-/// the backend channel is implied by which backend is configured).
-const DEVICE_BUILDER_WORDING: UpdateWording<'static> = UpdateWording {
-    component: "ESPHome Device Builder",
-    log_prefix: "Device-builder update",
-    channel_label: None,
-};
-
-impl UpdateWording<'_> {
-    /// "<component> <version>" with the channel label appended when present,
-    /// e.g. "ESPHome 2025.1.0 (stable)" or "ESPHome Device Builder 1.2.3".
-    fn subject(&self, version: &str) -> String {
-        match self.channel_label {
-            Some(label) => format!("{} {} ({})", self.component, version, label),
-            None => format!("{} {}", self.component, version),
-        }
-    }
-
-    /// Full body of the "would you like to update now?" confirm dialog shown
-    /// by [`prompt_if_newer`].
-    fn prompt_message(&self, latest: &str, installed: &str) -> String {
-        t_with(
-            "update.available_prompt",
-            &[("subject", &self.subject(latest)), ("installed", installed)],
-        )
-    }
-
-    /// Title of the background "update available" notification shown by
-    /// [`notify_if_newer`].
-    fn notification_title(&self) -> String {
-        t_with(
-            "update.notification_title",
-            &[("component", self.component)],
-        )
-    }
-}
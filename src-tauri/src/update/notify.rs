@@ -5,14 +5,65 @@
 
 use tauri::AppHandle;
 use tauri_plugin_dialog::MessageDialogKind;
-use tauri_plugin_notification::NotificationExt;
 use tracing::{debug, error, info};
 
-use super::{is_newer_version, UpdateWording};
+use super::{ask_to_update, is_newer_version, should_notify};
 use crate::i18n::{t, t_with};
 
-/// `latest` is newer, log it and ask the user whether to update now. Returns
-/// `Some(latest)` only when an update is available and the user confirms.
+/// Per-component wording shared by the user-prompt and background-notify
+/// update-check tails, so the strings cannot drift between the two flows.
+pub(super) struct UpdateWording<'a> {
+    /// The component's key in the update reminders.
+    pub(super) key: &'a str,
+    /// Component display name, e.g. "ESPHome" or "ESPHome Device Builder".
+    pub(super) component: &'a str,
+    /// Leading words of the "<log_prefix> available: a -> b" info log.
+    pub(super) log_prefix: &'a str,
+    /// Release-channel label appended to the offered version, when shown.
+    pub(super) channel_label: Option<&'a str>,
+}
+
+/// Wording for the `esphome-device-builder` check tails (no channel label;
+/// the backend channel is implied by which backend is configured).
+pub(super) const DEVICE_BUILDER_WORDING: UpdateWording<'static> = UpdateWording {
+    key: super::DEVICE_BUILDER_KEY,
+    component: "ESPHome Device Builder",
+    log_prefix: "Device-builder update",
+    channel_label: None,
+};
+
+impl UpdateWording<'_> {
+    /// "<component> <version>" with the channel label appended when present,
+    /// e.g. "ESPHome 2025.1.0 (stable)" or "ESPHome Device Builder 1.2.3".
+    fn subject(&self, version: &str) -> String {
+        match self.channel_label {
+            Some(label) => format!("{} {} ({})", self.component, version, label),
+            None => format!("{} {}", self.component, version),
+        }
+    }
+
+    /// Full body of the "would you like to update now?" confirm dialog shown
+    /// by [`prompt_if_newer`].
+    fn prompt_message(&self, latest: &str, installed: &str) -> String {
+        t_with(
+            "update.available_prompt",
+            &[("subject", &self.subject(latest)), ("installed", installed)],
+        )
+    }
+
+    /// Title of the background "update available" notification shown by
+    /// [`notify_if_newer`].
+    fn notification_title(&self) -> String {
+        t_with(
+            "update.notification_title",
+            &[("component", self.component)],
+        )
+    }
+}
+
+/// `latest` is newer, log it and ask the user whether to update now, skip
+/// the version or be reminded later. Returns `Some(latest)` only when an
+/// update is available and the user picks updating now.
 /// When already up to date, logs that at info level and, if
 /// `dialog_when_up_to_date` is set, also shows the "No Updates Available"
 /// notice (the device-builder flow stays silent; its caller owns that UX).
@@ -47,15 +98,7 @@ pub(super) async fn prompt_if_newer(
     );
 
     let msg = wording.prompt_message(&latest, installed);
-    if crate::dialog::confirm(
-        app_handle,
-        title,
-        msg,
-        &t("common.update_now"),
-        &t("common.later"),
-    )
-    .await
-    {
+    if ask_to_update(app_handle, title, msg, wording.key, &latest).await {
         Some(latest)
     } else {
         None
@@ -64,8 +107,9 @@ pub(super) async fn prompt_if_newer(
 
 /// Shared tail of the background update checks: compare versions and, when
 /// `latest` is newer, log it and show the "<component> Update Available"
-/// notification pointing at the updates menu. Logs the up-to-date state at
-/// debug level otherwise.
+/// notification pointing at the updates menu, once per version and not for
+/// a skipped or snoozed one. Logs the up-to-date state at debug level
+/// otherwise.
 pub(super) fn notify_if_newer(
     app_handle: &AppHandle,
     wording: &UpdateWording<'_>,
@@ -82,6 +126,9 @@ pub(super) fn notify_if_newer(
         "{} available: {} -> {} (installed: {})",
         wording.log_prefix, installed, latest, installed
     );
+    if !should_notify(app_handle, wording.key, latest) {
+        return;
+    }
 
     if let Err(e) = notify_update_available(
         app_handle,
@@ -104,12 +151,11 @@ pub(crate) fn notify_update_available(
     installed: &str,
     tray_available: bool,
 ) -> tauri_plugin_notification::Result<()> {
-    app_handle
-        .notification()
-        .builder()
-        .title(title)
-        .body(update_notification_body(subject, installed, tray_available))
-        .show()
+    crate::notifications::show(
+        app_handle,
+        title,
+        update_notification_body(subject, installed, tray_available),
+    )
 }
 
 /// Body of the standard "update available" notification, shared by every
@@ -127,12 +173,12 @@ fn update_notification_body(subject: &str, installed: &str, tray_available: bool
 
 #[cfg(test)]
 mod tests {
-    use super::super::DEVICE_BUILDER_WORDING;
     use super::*;
 
     /// The ESPHome wording as built by the check tails (channel label present).
     fn esphome_wording() -> UpdateWording<'static> {
         UpdateWording {
+            key: "esphome",
             component: "ESPHome",
             log_prefix: "Update",
             channel_label: Some("stable"),
@@ -197,13 +243,15 @@ mod tests {
             update_notification_body(&esphome_wording().subject("2025.1.0"), "2024.12.2", false),
             "ESPHome 2025.1.0 (stable) is available (you have 2024.12.2). \
              No system tray was detected. Run `esphome-desktop update` from a \
-             terminal to update."
+             terminal to update, or `esphome-desktop skip-update <component>` to \
+             skip this version."
         );
         assert_eq!(
             update_notification_body(&DEVICE_BUILDER_WORDING.subject("1.2.3"), "1.2.2", false),
             "ESPHome Device Builder 1.2.3 is available (you have 1.2.2). \
              No system tray was detected. Run `esphome-desktop update` from a \
-             terminal to update."
+             terminal to update, or `esphome-desktop skip-update <component>` to \
+             skip this version."
         );
     }
 }
@@ -0,0 +1,71 @@
+//! The backend's other Python packages, for the Python packages window (see
+//! [`crate::python_packages`]): their releases on PyPI, and installing one at
+//! a version to pin a broken dependency or take a fix early.
+
+use std::path::Path;
+use tauri::AppHandle;
+use tracing::info;
+
+use super::install::{install_with_record_recovery, run_package_install};
+use super::progress::Progress;
+use super::version::installable_versions;
+use super::{UpdateChecker, UpdateError};
+use crate::i18n::t_with;
+use crate::platform;
+
+impl UpdateChecker {
+    /// Every installable release of `package` on PyPI, pre-releases
+    /// included, newest first.
+    pub async fn package_releases(&self, package: &str) -> Result<Vec<String>, UpdateError> {
+        let response = self
+            .fetch_pypi(package)
+            .await
+            .map_err(UpdateError::Network)?;
+        let versions = installable_versions(&response.releases);
+        info!("{} {} releases on PyPI", versions.len(), package);
+        Ok(versions)
+    }
+
+    /// Install one of the backend's Python packages at `version`, or upgrade
+    /// it with `None`, in the environment of `python_path`: the app's own or
+    /// a pinned venv. Only the app's own tree is repaired on a missing RECORD
+    /// file; a pinned venv can be deleted from the versions window instead.
+    pub async fn install_package(
+        &self,
+        app_handle: &AppHandle,
+        python_path: &Path,
+        package: &str,
+        version: Option<&str>,
+    ) -> Result<(), UpdateError> {
+        info!(
+            "Installing {} {} from the Python packages window",
+            package,
+            version.unwrap_or("(latest)")
+        );
+        let heading = match version {
+            Some(version) => t_with(
+                "update_progress.package",
+                &[("package", package), ("version", version)],
+            ),
+            None => t_with("update_progress.package_latest", &[("package", package)]),
+        };
+        let progress = Progress::start(app_handle, heading);
+        let own_tree = platform::get_python_path(app_handle).is_ok_and(|own| own == python_path);
+        let result = install_with_record_recovery(
+            || {
+                let progress = &progress;
+                async move { run_package_install(python_path, package, version, progress).await }
+            },
+            || async move {
+                if !own_tree {
+                    anyhow::bail!("a pinned ESPHome environment has a damaged package");
+                }
+                self.repair_python_tree(app_handle).await
+            },
+            "Package installed successfully",
+            "pip install failed",
+        )
+        .await;
+        result.map_err(|e| progress.error(e))
+    }
+}
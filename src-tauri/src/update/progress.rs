@@ -0,0 +1,318 @@
+//! The update progress window: while pip installs ESPHome or the device
+//! builder, which takes minutes, a small window shows what it is doing
+//! (resolving, downloading, building, installing) and its last lines of
+//! output, with a Cancel button. The page is `dist/update_progress.html`; it
+//! polls [`update_progress`] and closes itself once nothing is installing.
+//!
+//! Cancelling kills pip, and is only offered until pip starts installing
+//! ("Installing collected packages"): up to then it has only downloaded and
+//! built into temporary directories, so the installed version is left as it
+//! was. Once it removes the old copy to put the new one in, stopping it
+//! would leave neither, so the button goes away.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+use tracing::{info, warn};
+
+use super::UpdateError;
+use crate::i18n::t;
+use crate::platform;
+use crate::tasks::CancelToken;
+
+/// The window's label.
+const LABEL: &str = "update-progress";
+
+/// How many of pip's last lines the window shows.
+const LINES: usize = 8;
+
+/// What pip is doing, as far as its output tells.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Stage {
+    #[default]
+    Starting,
+    Resolving,
+    Downloading,
+    Building,
+    Installing,
+}
+
+/// The stage a line of pip's output starts, if it starts one.
+fn stage_of(line: &str) -> Option<Stage> {
+    let line = line.trim_start();
+    let starts = |prefixes: &[&str]| prefixes.iter().any(|p| line.starts_with(p));
+    if starts(&["Installing collected packages", "Attempting uninstall"]) {
+        Some(Stage::Installing)
+    } else if starts(&[
+        "Downloading ",
+        "Using cached ",
+        "Cloning ",
+        "Running command git",
+    ]) {
+        Some(Stage::Downloading)
+    } else if starts(&[
+        "Building wheel",
+        "Preparing metadata",
+        "Getting requirements to build",
+    ]) {
+        Some(Stage::Building)
+    } else if starts(&["Collecting ", "Obtaining ", "Requirement already satisfied"]) {
+        Some(Stage::Resolving)
+    } else {
+        None
+    }
+}
+
+/// What the window shows.
+#[derive(Debug, Clone, Default, Serialize)]
+pub(crate) struct View {
+    heading: String,
+    stage: Stage,
+    lines: VecDeque<String>,
+    /// Whether Cancel still leaves the installed version intact.
+    cancellable: bool,
+    cancelled: bool,
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    view: Mutex<View>,
+    cancel: CancelToken,
+}
+
+impl Shared {
+    fn view(&self) -> std::sync::MutexGuard<'_, View> {
+        self.view.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn line(&self, line: &str) {
+        if line.trim().is_empty() {
+            return;
+        }
+        let mut view = self.view();
+        // pip resolves and downloads by turns, but never goes back from
+        // installing.
+        if let Some(stage) = stage_of(line).filter(|_| view.stage != Stage::Installing) {
+            view.stage = stage;
+            view.cancellable = stage != Stage::Installing;
+        }
+        if view.lines.len() == LINES {
+            view.lines.pop_front();
+        }
+        view.lines.push_back(line.to_string());
+    }
+
+    /// Cancel, if it is not too late. Under the view's lock, so pip can't
+    /// move on to installing in between.
+    fn cancel(&self) -> bool {
+        let mut view = self.view();
+        if !view.cancellable {
+            return false;
+        }
+        view.cancelled = true;
+        view.cancellable = false;
+        self.cancel.cancel();
+        true
+    }
+}
+
+/// The install running now, for the window's commands. One at a time, as the
+/// update guard allows.
+static CURRENT: Mutex<Option<Arc<Shared>>> = Mutex::new(None);
+
+/// One install's progress, shown in the window while it lives.
+#[derive(Debug)]
+pub(crate) struct Progress {
+    shared: Arc<Shared>,
+    app: Option<AppHandle>,
+}
+
+impl Progress {
+    /// Start tracking an install described by `heading`, and open the window.
+    pub(crate) fn start(app: &AppHandle, heading: String) -> Self {
+        let shared = Self::shared(heading);
+        *CURRENT.lock().unwrap_or_else(|e| e.into_inner()) = Some(shared.clone());
+        if let Err(e) = open(app) {
+            warn!("Failed to open the update progress window: {}", e);
+        }
+        Self {
+            shared,
+            app: Some(app.clone()),
+        }
+    }
+
+    /// Progress nobody sees, nor can cancel.
+    #[cfg(test)]
+    pub(crate) fn hidden(heading: String) -> Self {
+        Self {
+            shared: Self::shared(heading),
+            app: None,
+        }
+    }
+
+    fn shared(heading: String) -> Arc<Shared> {
+        let shared = Shared::default();
+        let mut view = shared.view();
+        view.heading = heading;
+        view.cancellable = true;
+        drop(view);
+        Arc::new(shared)
+    }
+
+    /// Run a pip command from [`platform::pip_command`], through the proxy if
+    /// there is one, following its output and killing it if the install is
+    /// cancelled.
+    pub(super) async fn run(
+        &self,
+        mut cmd: tokio::process::Command,
+    ) -> Result<std::process::Output> {
+        crate::network::apply_env(&mut cmd);
+        let cancel = self.shared.cancel.clone();
+        platform::run_pip_watched(cmd, |line| self.shared.line(line), async move {
+            cancel.cancelled().await
+        })
+        .await
+        .context("Failed to run pip install")
+    }
+
+    /// The error for an install that failed with `e`: its own if it was
+    /// cancelled.
+    pub(super) fn error(&self, e: anyhow::Error) -> UpdateError {
+        if self.shared.cancel.is_cancelled() {
+            UpdateError::Cancelled
+        } else {
+            UpdateError::Install(e)
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        let Some(app) = &self.app else {
+            return;
+        };
+        let mut current = CURRENT.lock().unwrap_or_else(|e| e.into_inner());
+        if current
+            .as_ref()
+            .is_some_and(|shared| Arc::ptr_eq(shared, &self.shared))
+        {
+            *current = None;
+        }
+        drop(current);
+        if let Some(window) = app.get_webview_window(LABEL) {
+            let _ = window.close();
+        }
+    }
+}
+
+fn open(app: &AppHandle) -> tauri::Result<()> {
+    let texts = serde_json::json!({
+        "starting": t("update_progress.starting"),
+        "resolving": t("update_progress.resolving"),
+        "downloading": t("update_progress.downloading"),
+        "building": t("update_progress.building"),
+        "installing": t("update_progress.installing"),
+        "cancel": t("update_progress.cancel"),
+        "output_label": t("update_progress.output_label"),
+        "cancelling": t("update_progress.cancelling"),
+        "done": t("update_progress.done"),
+    });
+    crate::window::open_sized(
+        app,
+        LABEL,
+        "update_progress.html",
+        t("update_progress.title"),
+        texts,
+        (560.0, 300.0),
+    )
+}
+
+/// The install running now, or `None` once it is over.
+#[tauri::command]
+pub(crate) fn update_progress() -> Option<View> {
+    let current = CURRENT.lock().unwrap_or_else(|e| e.into_inner());
+    current.as_ref().map(|shared| shared.view().clone())
+}
+
+/// Cancel the install running now. Fails once pip has started installing.
+#[tauri::command]
+pub(crate) fn cancel_update() -> Result<(), String> {
+    let current = CURRENT.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(shared) = current.as_ref() else {
+        return Ok(());
+    };
+    if !shared.cancel() {
+        return Err(t("update_progress.too_late"));
+    }
+    info!("Cancelling the install at the user's request");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stages_follow_pip_output() {
+        assert_eq!(
+            stage_of("Collecting esphome==2025.2.0"),
+            Some(Stage::Resolving)
+        );
+        assert_eq!(
+            stage_of("  Downloading esphome-2025.2.0-py3-none-any.whl (4.1 MB)"),
+            Some(Stage::Downloading)
+        );
+        assert_eq!(
+            stage_of("  Cloning https://github.com/esphome/esphome (to revision dev) to /tmp/x"),
+            Some(Stage::Downloading)
+        );
+        assert_eq!(
+            stage_of("Building wheels for collected packages: esphome"),
+            Some(Stage::Building)
+        );
+        assert_eq!(
+            stage_of("Installing collected packages: esphome"),
+            Some(Stage::Installing)
+        );
+        assert_eq!(stage_of("Successfully installed esphome-2025.2.0"), None);
+    }
+
+    #[test]
+    fn cancel_is_refused_once_pip_installs() {
+        let progress = Progress::hidden("ESPHome".into());
+        progress.shared.line("Collecting esphome");
+        progress
+            .shared
+            .line("Installing collected packages: esphome");
+        // A later line that would otherwise start another stage.
+        progress.shared.line("Collecting nothing");
+        assert_eq!(progress.shared.view().stage, Stage::Installing);
+        assert!(!progress.shared.cancel());
+        assert!(!progress.shared.cancel.is_cancelled());
+
+        let progress = Progress::hidden("ESPHome".into());
+        progress.shared.line("  Downloading esphome.whl");
+        assert!(progress.shared.cancel());
+        assert!(progress.shared.cancel.is_cancelled());
+        assert!(matches!(
+            progress.error(anyhow::anyhow!("killed")),
+            UpdateError::Cancelled
+        ));
+    }
+
+    #[test]
+    fn the_window_keeps_the_last_lines() {
+        let progress = Progress::hidden("ESPHome".into());
+        for i in 0..20 {
+            progress.shared.line(&format!("line {i}"));
+        }
+        progress.shared.line("");
+        let view = progress.shared.view();
+        assert_eq!(view.lines.len(), LINES);
+        assert_eq!(view.lines.back().map(String::as_str), Some("line 19"));
+    }
+}
@@ -0,0 +1,49 @@
+//! PyPI's JSON API, or a mirror's, which the checks ask about releases.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use super::UpdateChecker;
+
+/// PyPI package info response (used for stable channel)
+#[derive(Debug, Deserialize)]
+pub(super) struct PyPIResponse {
+    pub(super) info: PyPIInfo,
+    pub(super) releases: HashMap<String, Vec<PyPIRelease>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct PyPIInfo {
+    pub(super) version: String,
+}
+
+/// A single release file entry from PyPI. We only need to know whether the
+/// file has been yanked — PyPI keeps a version's key in `releases` even after
+/// every file is yanked or removed, so a lingering entry does not mean the
+/// version is actually installable.
+#[derive(Debug, Deserialize)]
+pub(super) struct PyPIRelease {
+    #[serde(default)]
+    pub(super) yanked: bool,
+}
+
+/// PyPI's JSON API, which [`UpdateChecker`] asks about releases unless the
+/// settings name a mirror.
+pub(super) const PYPI_URL: &str = "https://pypi.org/pypi";
+
+impl UpdateChecker {
+    /// Fetch and parse the PyPI JSON metadata for `package`.
+    ///
+    /// Callers pass fixed internal package names, so no URL encoding is needed.
+    pub(super) async fn fetch_pypi(&self, package: &str) -> Result<PyPIResponse> {
+        self.client
+            .get(format!("{}/{package}/json", self.index))
+            .send()
+            .await
+            .with_context(|| format!("Failed to fetch PyPI info for {package}"))?
+            .json()
+            .await
+            .with_context(|| format!("Failed to parse PyPI response for {package}"))
+    }
+}
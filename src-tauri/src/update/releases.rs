@@ -0,0 +1,15 @@
+//! Every ESPHome release on PyPI, for installing one by hand from the
+//! versions window (see [`crate::versions`]): an older one when a new
+//! release breaks a config, or a beta off the beta channel. Other packages'
+//! releases are listed the same way for the Python packages window (see
+//! [`packages`](super::packages)).
+
+use super::{UpdateChecker, UpdateError};
+
+impl UpdateChecker {
+    /// Every installable ESPHome release on PyPI, betas included, newest
+    /// first.
+    pub async fn releases(&self) -> Result<Vec<String>, UpdateError> {
+        self.package_releases("esphome").await
+    }
+}
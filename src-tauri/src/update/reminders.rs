@@ -0,0 +1,293 @@
+//! What the user has been told about each update, so a background check
+//! notifies once per new version rather than every day, and what they chose
+//! in the update dialog: skip that version, or hear about it again in a
+//! week. Kept per component (the desktop app, ESPHome, the device builder)
+//! in `update_reminders.json` in the app data.
+//!
+//! Checking for updates from the tray always asks, skipped or not: the user
+//! asked. A version newer than the skipped one is news again. Without a tray,
+//! `esphome-desktop skip-update` skips a version from the command line
+//! ([`skip`]).
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tracing::{info, warn};
+
+use crate::i18n::t;
+use crate::util::ConfigStore;
+
+/// The ESPHome package's key in the reminders.
+pub(crate) const ESPHOME_KEY: &str = "esphome";
+
+/// The device builder's key in the reminders.
+pub(crate) const DEVICE_BUILDER_KEY: &str = "device-builder";
+
+/// The desktop app's key in the reminders.
+pub(crate) const DESKTOP_KEY: &str = "desktop";
+
+/// How long "Remind Me in a Week" holds off.
+const SNOOZE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Reminder state of every component.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Reminders {
+    #[serde(default)]
+    components: BTreeMap<String, Reminder>,
+}
+
+impl ConfigStore for Reminders {
+    const FILE_NAME: &'static str = "update_reminders.json";
+    const WHAT: &'static str = "update reminders";
+}
+
+/// Reminder state of one component.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Reminder {
+    /// The version the last notification was about.
+    #[serde(default)]
+    notified: Option<String>,
+    /// The version the user chose to skip.
+    #[serde(default)]
+    skipped: Option<String>,
+    /// The version to remind about, and when (seconds since the epoch).
+    #[serde(default)]
+    snoozed: Option<(String, u64)>,
+}
+
+/// What the user chose in the update dialog.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Choice {
+    UpdateNow,
+    Skip,
+    RemindLater,
+}
+
+impl Reminders {
+    /// Whether a background check should notify about `latest` of
+    /// `component` at `now`.
+    fn due(&self, component: &str, latest: &str, now: u64) -> bool {
+        let Some(reminder) = self.components.get(component) else {
+            return true;
+        };
+        if reminder.skipped.as_deref() == Some(latest) {
+            return false;
+        }
+        match &reminder.snoozed {
+            Some((version, until)) if version == latest => now >= *until,
+            _ => reminder.notified.as_deref() != Some(latest),
+        }
+    }
+
+    /// Record a notification about `latest`. Ends a snooze of it.
+    fn notified(&mut self, component: &str, latest: &str) {
+        let reminder = self.components.entry(component.to_string()).or_default();
+        reminder.notified = Some(latest.to_string());
+        if reminder
+            .snoozed
+            .as_ref()
+            .is_some_and(|(version, _)| version == latest)
+        {
+            reminder.snoozed = None;
+        }
+    }
+
+    /// Skip `version` of `component`, or the version the last notification
+    /// was about. Returns the version skipped, if there was one to skip.
+    fn skip(&mut self, component: &str, version: Option<&str>) -> Option<String> {
+        let version = version.map(str::to_string).or_else(|| {
+            self.components
+                .get(component)
+                .and_then(|reminder| reminder.notified.clone())
+        })?;
+        self.choose(component, &version, Choice::Skip, 0);
+        Some(version)
+    }
+
+    /// Record `choice` about `version`, made at `now`.
+    fn choose(&mut self, component: &str, version: &str, choice: Choice, now: u64) {
+        let reminder = self.components.entry(component.to_string()).or_default();
+        match choice {
+            Choice::UpdateNow => {}
+            Choice::Skip => {
+                reminder.skipped = Some(version.to_string());
+                reminder.snoozed = None;
+            }
+            Choice::RemindLater => {
+                reminder.snoozed = Some((version.to_string(), now + SNOOZE.as_secs()));
+            }
+        }
+    }
+}
+
+/// Whether a background check should notify about `latest` of `component`,
+/// recording that it did. Best-effort: without the state it notifies, as
+/// before there was any.
+pub(crate) fn should_notify(app: &AppHandle, component: &str, latest: &str) -> bool {
+    let Some(path) = path(app) else {
+        return true;
+    };
+    let mut reminders = match Reminders::load(&path) {
+        Ok(reminders) => reminders,
+        Err(e) => {
+            warn!("Failed to read the update reminders: {:#}", e);
+            return true;
+        }
+    };
+    if !reminders.due(component, latest, unix_now()) {
+        info!("Already told about {} {}; not notifying", component, latest);
+        return false;
+    }
+    reminders.notified(component, latest);
+    save(&path, &reminders);
+    true
+}
+
+/// Whether the user chose to skip `version` of `component`.
+pub(crate) fn is_skipped(app: &AppHandle, component: &str, version: &str) -> bool {
+    path(app)
+        .and_then(|path| Reminders::load(&path).ok())
+        .and_then(|reminders| reminders.components.get(component).cloned())
+        .is_some_and(|reminder| reminder.skipped.as_deref() == Some(version))
+}
+
+/// Skip `version` of `component`, or the one last notified about, in the
+/// reminders in `data_dir`; with `clear`, forget the skipped version instead.
+/// Returns the version skipped or forgotten, if any. For the CLI, which runs
+/// without the app.
+pub(crate) fn skip(
+    data_dir: &Path,
+    component: &str,
+    version: Option<&str>,
+    clear: bool,
+) -> Result<Option<String>> {
+    let path = Reminders::path(data_dir);
+    let mut reminders = Reminders::load(&path)?;
+    let changed = if clear {
+        reminders
+            .components
+            .get_mut(component)
+            .and_then(|reminder| reminder.skipped.take())
+    } else {
+        reminders.skip(component, version)
+    };
+    if changed.is_some() {
+        reminders.save(&path)?;
+    }
+    Ok(changed)
+}
+
+/// Ask whether to update `component` to `version` now, skip that version or
+/// be reminded in a week, recording the answer. Returns whether to update.
+pub(crate) async fn ask_to_update(
+    app: &AppHandle,
+    title: &str,
+    message: String,
+    component: &str,
+    version: &str,
+) -> bool {
+    // Closing the dialog reads as the last button: a reminder is harmless.
+    let choice = match crate::dialog::choose(
+        app,
+        title,
+        message,
+        [
+            &t("common.update_now"),
+            &t("update.skip_version"),
+            &t("update.remind_later"),
+        ],
+    )
+    .await
+    {
+        Some(0) => Choice::UpdateNow,
+        Some(1) => Choice::Skip,
+        _ => Choice::RemindLater,
+    };
+    info!("Update of {} to {}: {:?}", component, version, choice);
+    if choice != Choice::UpdateNow {
+        if let Some(path) = path(app) {
+            match Reminders::load(&path) {
+                Ok(mut reminders) => {
+                    reminders.choose(component, version, choice, unix_now());
+                    save(&path, &reminders);
+                }
+                Err(e) => warn!("Failed to read the update reminders: {:#}", e),
+            }
+        }
+    }
+    choice == Choice::UpdateNow
+}
+
+fn path(app: &AppHandle) -> Option<PathBuf> {
+    match crate::platform::get_data_dir(app) {
+        Ok(dir) => Some(Reminders::path(&dir)),
+        Err(e) => {
+            warn!("No update reminders without a data directory: {:#}", e);
+            None
+        }
+    }
+}
+
+fn save(path: &std::path::Path, reminders: &Reminders) {
+    if let Err(e) = reminders.save(path) {
+        warn!("Failed to record the update reminders: {:#}", e);
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notifies_once_per_version_unless_skipped_or_snoozed() {
+        let mut reminders = Reminders::default();
+        assert!(reminders.due("esphome", "2026.10.0", 0));
+        reminders.notified("esphome", "2026.10.0");
+        assert!(!reminders.due("esphome", "2026.10.0", 1));
+        // Components are told apart.
+        assert!(reminders.due("desktop", "2026.10.0", 1));
+        assert!(reminders.due("esphome", "2026.10.1", 1));
+
+        reminders.choose("esphome", "2026.10.1", Choice::Skip, 1);
+        assert!(!reminders.due("esphome", "2026.10.1", 1));
+        assert!(reminders.due("esphome", "2026.11.0", 1));
+
+        let week = SNOOZE.as_secs();
+        reminders.notified("esphome", "2026.11.0");
+        reminders.choose("esphome", "2026.11.0", Choice::RemindLater, 10);
+        assert!(!reminders.due("esphome", "2026.11.0", 10 + week - 1));
+        assert!(reminders.due("esphome", "2026.11.0", 10 + week));
+        reminders.notified("esphome", "2026.11.0");
+        assert!(!reminders.due("esphome", "2026.11.0", 20 + week));
+    }
+
+    #[test]
+    fn skip_defaults_to_the_last_notified_version() {
+        let mut reminders = Reminders::default();
+        assert_eq!(reminders.skip("esphome", None), None);
+        reminders.notified("esphome", "2026.10.0");
+        reminders.choose("esphome", "2026.10.0", Choice::RemindLater, 0);
+        assert_eq!(
+            reminders.skip("esphome", None).as_deref(),
+            Some("2026.10.0")
+        );
+        assert!(!reminders.due("esphome", "2026.10.0", SNOOZE.as_secs()));
+
+        assert_eq!(
+            reminders.skip("esphome", Some("2026.11.0")).as_deref(),
+            Some("2026.11.0")
+        );
+        assert!(reminders.due("esphome", "2026.10.0", 0));
+        assert!(!reminders.due("esphome", "2026.11.0", 0));
+    }
+}
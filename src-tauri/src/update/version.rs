@@ -5,9 +5,10 @@
 //! dependencies, so it is unit-testable without a live interpreter or PyPI.
 
 use std::borrow::Cow;
+use std::cmp::Ordering;
 use std::collections::HashMap;
 
-use super::PyPIRelease;
+use super::pypi::PyPIRelease;
 
 /// Choose the version to offer on the beta channel.
 ///
@@ -41,21 +42,7 @@ fn highest_version(
     let mut best: Option<String> = None;
 
     for (version_str, files) in releases {
-        if !predicate(version_str) {
-            continue;
-        }
-
-        // Skip if not a valid-looking version
-        if !version_str
-            .chars()
-            .next()
-            .is_some_and(|c| c.is_ascii_digit())
-        {
-            continue;
-        }
-
-        // Skip versions with no installable files (fully yanked or removed).
-        if !has_active_files(files) {
+        if !predicate(version_str) || !is_installable(version_str, files) {
             continue;
         }
 
@@ -72,6 +59,31 @@ fn highest_version(
     best
 }
 
+/// Whether a release looks like a version (it starts with a digit) and has
+/// files to install.
+fn is_installable(version: &str, files: &[PyPIRelease]) -> bool {
+    version.chars().next().is_some_and(|c| c.is_ascii_digit()) && has_active_files(files)
+}
+
+/// Every installable release, newest first.
+pub(super) fn installable_versions(releases: &HashMap<String, Vec<PyPIRelease>>) -> Vec<String> {
+    let mut versions: Vec<String> = releases
+        .iter()
+        .filter(|(version, files)| is_installable(version, files))
+        .map(|(version, _)| version.clone())
+        .collect();
+    versions.sort_by(|a, b| {
+        if is_newer_version(a, b) {
+            Ordering::Less
+        } else if is_newer_version(b, a) {
+            Ordering::Greater
+        } else {
+            Ordering::Equal
+        }
+    });
+    versions
+}
+
 /// Find the latest beta/pre-release version from PyPI releases.
 ///
 /// Beta versions on PyPI look like "2025.4.0b1", "2025.4.0b2", etc.
@@ -451,4 +463,20 @@ mod tests {
             "2025.4.0".to_string()
         );
     }
+
+    #[test]
+    fn installable_versions_newest_first() {
+        let mut releases = HashMap::new();
+        releases.insert("2025.3.0".to_string(), active());
+        releases.insert("2025.4.0b1".to_string(), active());
+        releases.insert("2025.4.0".to_string(), active());
+        releases.insert("2025.12.0".to_string(), active());
+        releases.insert("2025.5.0".to_string(), yanked());
+        releases.insert("latest".to_string(), active());
+
+        assert_eq!(
+            installable_versions(&releases),
+            ["2025.12.0", "2025.4.0", "2025.4.0b1", "2025.3.0"]
+        );
+    }
 }
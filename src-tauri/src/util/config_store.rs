@@ -0,0 +1,152 @@
+//! A feature's state in a JSON file of its own in the data directory: device
+//! notes, the registry, the allow-list, the histories, and the workspace and
+//! notification settings. One file each rather than one for everything, so a
+//! feature that writes often can't take the others down with a bad write, and
+//! each can change its format on its own.
+//!
+//! Every file carries its format `version`. A file from a newer build is
+//! refused rather than read and saved back without what that build added; an
+//! older one goes through [`ConfigStore::upgrade`] first. Files from before
+//! the field existed are version 1. The rest of the settings stay in
+//! `settings.json`; the settings loader recovers from a corrupt file, that
+//! one or a section's, instead of failing.
+
+use anyhow::{bail, Context, Result};
+use serde::{de::DeserializeOwned, Serialize};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// Key of the format version in every file.
+const VERSION_KEY: &str = "version";
+
+/// State kept in its own file, read and written whole.
+pub(crate) trait ConfigStore: Serialize + DeserializeOwned + Default {
+    /// The file's name in its directory.
+    const FILE_NAME: &'static str;
+    /// What it holds, for errors ("device notes").
+    const WHAT: &'static str;
+    /// The format this build writes. A bump comes with a step in
+    /// [`upgrade`](Self::upgrade).
+    const VERSION: u32 = 1;
+
+    /// Path of the file in `dir`, the data directory but for the settings'.
+    fn path(dir: &Path) -> PathBuf {
+        dir.join(Self::FILE_NAME)
+    }
+
+    /// Bring `value`, written in format `version`, to [`VERSION`](Self::VERSION).
+    fn upgrade(version: u32, _value: serde_json::Value) -> Result<serde_json::Value> {
+        bail!("no upgrade from format {version} of the {}", Self::WHAT)
+    }
+
+    /// Load the file; a missing one is the default. A corrupt one is an error
+    /// rather than the default, so the next save can't wipe what the user can
+    /// still recover by hand.
+    fn load(path: &Path) -> Result<Self> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {path:?}")),
+        };
+        let mut value: serde_json::Value =
+            serde_json::from_str(&content).with_context(|| format!("Failed to parse {path:?}"))?;
+        let version = match value.get(VERSION_KEY) {
+            None => 1,
+            Some(v) => v
+                .as_u64()
+                .and_then(|v| u32::try_from(v).ok())
+                .with_context(|| format!("{path:?} has no valid format {VERSION_KEY}"))?,
+        };
+        if version > Self::VERSION {
+            bail!(
+                "{path:?} is in format {version} of the {}, from a newer version of the app; \
+                 this one reads up to {}",
+                Self::WHAT,
+                Self::VERSION
+            );
+        }
+        if version < Self::VERSION {
+            value = Self::upgrade(version, value)
+                .with_context(|| format!("Failed to upgrade {path:?}"))?;
+        }
+        if let Some(map) = value.as_object_mut() {
+            map.remove(VERSION_KEY);
+        }
+        serde_json::from_value(value).with_context(|| format!("Failed to parse {path:?}"))
+    }
+
+    /// Write the file atomically, creating the directory if needed.
+    fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).context("Failed to create data directory")?;
+        }
+        let content = serde_json::to_string_pretty(&Versioned {
+            version: Self::VERSION,
+            data: self,
+        })
+        .with_context(|| format!("Failed to serialize the {}", Self::WHAT))?;
+        super::atomic_write(path, content)
+            .with_context(|| format!("Failed to write the {}", Self::WHAT))
+    }
+}
+
+/// A file's contents with its format version first.
+#[derive(Serialize)]
+struct Versioned<'a, T> {
+    version: u32,
+    #[serde(flatten)]
+    data: &'a T,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::unique_temp_dir;
+    use serde::Deserialize;
+    use std::fs;
+
+    #[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+    struct Counts {
+        #[serde(default)]
+        seen: u32,
+    }
+
+    impl ConfigStore for Counts {
+        const FILE_NAME: &'static str = "counts.json";
+        const WHAT: &'static str = "counts";
+        const VERSION: u32 = 2;
+
+        fn upgrade(version: u32, mut value: serde_json::Value) -> Result<serde_json::Value> {
+            // Format 1 called it `count`.
+            if version == 1 {
+                if let Some(count) = value.get("count").cloned() {
+                    value["seen"] = count;
+                }
+            }
+            Ok(value)
+        }
+    }
+
+    #[test]
+    fn versions_upgrades_and_refuses_newer_files() {
+        let dir = unique_temp_dir("config-store");
+        let path = Counts::path(&dir);
+        assert_eq!(Counts::load(&path).unwrap(), Counts::default());
+
+        fs::write(&path, r#"{"count": 3}"#).unwrap();
+        assert_eq!(Counts::load(&path).unwrap(), Counts { seen: 3 });
+
+        Counts { seen: 4 }.save(&path).unwrap();
+        let saved: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved, serde_json::json!({ "version": 2, "seen": 4 }));
+        assert_eq!(Counts::load(&path).unwrap(), Counts { seen: 4 });
+
+        fs::write(&path, r#"{"version": 3, "seen": 5}"#).unwrap();
+        assert!(Counts::load(&path).is_err());
+        fs::write(&path, "{").unwrap();
+        assert!(Counts::load(&path).is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -5,6 +5,9 @@ use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, Ordering};
 
+mod config_store;
+pub(crate) use config_store::ConfigStore;
+
 /// Monotonic counter to keep temp file names unique within a process even when
 /// two writes to different paths race on the same millisecond/PID.
 static TMP_COUNTER: AtomicU64 = AtomicU64::new(0);
@@ -0,0 +1,314 @@
+//! The "ESPHome Versions..." window: the app's own ESPHome, on its release
+//! channel, and each per-version venv pins and comparisons installed (see
+//! [`crate::daemon::installed_pins`]), with their disk use and last start.
+//! A row's buttons switch the config folder to it, which relaunches the app
+//! as a pin is only read at launch, run Check for Updates on the app's own
+//! ESPHome, or delete a venv nothing uses. The app's own can also be put on
+//! any release PyPI has, older ones included, to get back to a known-good
+//! build, or on a git branch or fork (`git+https://...@ref`) to try out a
+//! pull request. The page is `dist/versions.html`.
+//!
+//! There is one environment per channel only while it is selected: switching
+//! channels reinstalls the app's own ESPHome rather than keeping the last.
+
+use serde::Serialize;
+use tauri::AppHandle;
+use tracing::{error, info};
+
+use crate::audit::{self, Source};
+use crate::cleanup::{dir_size, format_size};
+use crate::control::ops::{self, UpdateGuard};
+use crate::daemon;
+use crate::error::UserError;
+use crate::i18n::{t, t_with};
+use crate::settings::{ReleaseChannel, Runtime};
+use crate::window::app_state;
+
+/// Show the versions window, opening it if it isn't.
+pub(crate) fn open(app: &AppHandle) -> tauri::Result<()> {
+    let texts = serde_json::json!({
+        "intro": t("versions.intro"),
+        "own": t("versions.own"),
+        "pinned": t("versions.pinned"),
+        "unknown": t("versions.unknown"),
+        "running": t("versions.running"),
+        "selected": t("versions.selected"),
+        "comparing": t("versions.comparing"),
+        "never": t("versions.never"),
+        "activate": t("versions.activate"),
+        "update": t("versions.update"),
+        "delete": t("versions.delete"),
+        "install": t("versions.install"),
+        "git_placeholder": t("versions.git_placeholder"),
+        "install_git": t("versions.install_git"),
+        "loading": t("versions.loading"),
+        "release_label": t("versions.release_label"),
+        "activate_label": t("versions.activate_label"),
+        "delete_label": t("versions.delete_label"),
+        "working": t("versions.working"),
+        "done": t("versions.done"),
+    });
+    crate::window::open(app, "versions", "versions.html", t("versions.title"), texts)
+}
+
+/// An environment's row in the window.
+#[derive(Debug, Serialize)]
+pub(crate) struct VersionRow {
+    /// The pinned version, or `None` for the app's own environment.
+    pin: Option<String>,
+    /// The ESPHome it has, if known.
+    esphome: Option<String>,
+    /// The app's release channel, on its own environment's row.
+    channel: Option<String>,
+    size: String,
+    /// RFC 3339.
+    last_used: Option<String>,
+    /// The backend runs on it now.
+    running: bool,
+    /// The config folder selects it, so the next start runs on it.
+    selected: bool,
+    /// A comparison dashboard runs on it.
+    comparing: bool,
+}
+
+/// The app's environment, then every pinned venv, newest first.
+#[tauri::command]
+pub(crate) async fn esphome_versions(app: AppHandle) -> Result<Vec<VersionRow>, String> {
+    let state = app_state(&app)?;
+    let config_dir = state.daemon.config_dir().clone();
+    let running_pin = state.daemon.pinned_esphome().map(str::to_string);
+    let comparing = state.comparison.current().map(|(version, _)| version);
+    let snapshot = state.store.snapshot();
+    let channel = state.settings.read().await.release_channel.to_string();
+    let python_dir = crate::platform::get_python_parent_dir(&app)
+        .map(|dir| dir.join(crate::platform::PYTHON_TREE_DIRNAME));
+    let data_dir = crate::platform::get_data_dir(&app);
+    let (python_dir, data_dir) = match (python_dir, data_dir) {
+        (Ok(python_dir), Ok(data_dir)) => (python_dir, data_dir),
+        (Err(e), _) | (_, Err(e)) => {
+            error!("Failed to list ESPHome versions: {}", e);
+            return Err(t_with("versions.failed", &[("error", &e.to_string())]));
+        }
+    };
+    tauri::async_runtime::spawn_blocking(move || {
+        let selected = daemon::current_pin(&config_dir);
+        let mut rows = vec![VersionRow {
+            pin: None,
+            esphome: snapshot.esphome_version,
+            channel: Some(channel),
+            size: format_size(dir_size(&python_dir)),
+            last_used: None,
+            running: running_pin.is_none(),
+            selected: selected.is_none(),
+            comparing: false,
+        }];
+        for installed in daemon::installed_pins(&data_dir) {
+            let version = Some(installed.version.as_str());
+            rows.push(VersionRow {
+                esphome: Some(installed.version.clone()),
+                channel: None,
+                size: format_size(dir_size(&installed.dir)),
+                running: running_pin.as_deref() == version,
+                selected: selected.as_deref() == version,
+                comparing: comparing.as_deref() == version,
+                last_used: installed.last_used,
+                pin: Some(installed.version),
+            });
+        }
+        rows
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Switch the config folder to ESPHome `pin`, or back to the app's own with
+/// `None`, and relaunch to run it, once the user agrees. Returns whether it
+/// went ahead.
+#[tauri::command]
+pub(crate) async fn activate_esphome_version(
+    app: AppHandle,
+    pin: Option<String>,
+) -> Result<bool, String> {
+    let state = app_state(&app)?;
+    if state.daemon.runtime() != Runtime::Native {
+        return Err(t("versions.native_only"));
+    }
+    let message = match &pin {
+        Some(version) => t_with("versions.confirm_activate", &[("version", version)]),
+        None => t("versions.confirm_own"),
+    };
+    let confirmed = crate::dialog::confirm(
+        &app,
+        &t("versions.title"),
+        message,
+        &t("versions.relaunch"),
+        &t("versions.cancel"),
+    )
+    .await;
+    if !confirmed {
+        return Ok(false);
+    }
+    // Held to the end, like a reset: nothing may install while the app
+    // winds down to relaunch.
+    let Some(guard) = UpdateGuard::try_acquire(state.update_in_flight.clone()) else {
+        return Err(t("versions.busy"));
+    };
+    if !ops::authorize(&state, t("auth.switch_version"), &|_, _| {}).await {
+        return Ok(false);
+    }
+    if let Err(e) = daemon::write_pin(state.daemon.config_dir(), pin.as_deref()) {
+        error!("Failed to switch the ESPHome pin: {:#}", e);
+        return Err(t_with("versions.failed", &[("error", &format!("{e:#}"))]));
+    }
+    let detail = match &pin {
+        Some(version) => format!("pinned ESPHome {version}"),
+        None => "removed the ESPHome pin".to_string(),
+    };
+    info!("Versions window {}; relaunching", detail);
+    audit::record(&app, Source::Tray, "pin", detail);
+    std::mem::forget(guard);
+    crate::platform::relaunch_for_update(&app);
+    Ok(true)
+}
+
+/// Run Check for Updates, for the app's own environment.
+#[tauri::command]
+pub(crate) async fn update_esphome_version(app: AppHandle) -> Result<(), String> {
+    let state = app_state(&app)?;
+    tauri::async_runtime::spawn(crate::tray::check_for_updates(app, state));
+    Ok(())
+}
+
+/// Delete the venv of ESPHome `pin`, once the user agrees. Returns whether
+/// it went.
+#[tauri::command]
+pub(crate) async fn delete_esphome_version(app: AppHandle, pin: String) -> Result<bool, String> {
+    let state = app_state(&app)?;
+    let data_dir = crate::platform::get_data_dir(&app).map_err(|e| e.to_string())?;
+    let Some(installed) = daemon::installed_pins(&data_dir)
+        .into_iter()
+        .find(|installed| installed.version == pin)
+    else {
+        return Ok(false);
+    };
+    let in_use = state.daemon.pinned_esphome() == Some(pin.as_str())
+        || daemon::current_pin(state.daemon.config_dir()).as_deref() == Some(pin.as_str())
+        || state
+            .comparison
+            .current()
+            .is_some_and(|(version, _)| version == pin);
+    if in_use {
+        return Err(t_with("versions.in_use", &[("version", &pin)]));
+    }
+    let dir = installed.dir;
+    let size = format_size(dir_size(&dir));
+    let confirmed = crate::dialog::confirm(
+        &app,
+        &t("versions.title"),
+        t_with(
+            "versions.confirm_delete",
+            &[("version", &pin), ("size", &size)],
+        ),
+        &t("versions.delete"),
+        &t("versions.cancel"),
+    )
+    .await;
+    if !confirmed {
+        return Ok(false);
+    }
+    let reason = t_with("auth.delete_version", &[("version", &pin)]);
+    if !ops::authorize(&state, reason, &|_, _| {}).await {
+        return Ok(false);
+    }
+    let removed = tauri::async_runtime::spawn_blocking(move || std::fs::remove_dir_all(dir))
+        .await
+        .map_err(|e| e.to_string())?;
+    if let Err(e) = removed {
+        error!("Failed to delete the ESPHome {} venv: {}", pin, e);
+        return Err(t_with("versions.failed", &[("error", &e.to_string())]));
+    }
+    info!("Deleted the ESPHome {} venv ({})", pin, size);
+    audit::record(
+        &app,
+        Source::Tray,
+        "pin",
+        format!("deleted the ESPHome {pin} environment"),
+    );
+    Ok(true)
+}
+
+/// Every ESPHome release on PyPI, newest first.
+#[tauri::command]
+pub(crate) async fn esphome_releases(app: AppHandle) -> Result<Vec<String>, String> {
+    let state = app_state(&app)?;
+    state.update_checker.releases().await.map_err(|e| {
+        error!("Failed to list ESPHome releases: {}", e);
+        t_with("versions.releases_failed", &[("error", &e.user_message())])
+    })
+}
+
+/// Install ESPHome `version`, a release or a git URL, as the app's own, once
+/// the user agrees, and restart the dashboard on it. Returns whether it went
+/// ahead.
+#[tauri::command]
+pub(crate) async fn install_esphome_version(
+    app: AppHandle,
+    version: String,
+) -> Result<bool, String> {
+    let state = app_state(&app)?;
+    if state.daemon.runtime() != Runtime::Native {
+        return Err(t("versions.native_only"));
+    }
+    let from_git = crate::update::git_requirement(&version).is_some();
+    // Otherwise only a release PyPI lists: the version goes to pip as is.
+    if !from_git && !esphome_releases(app.clone()).await?.contains(&version) {
+        return Err(t_with("versions.no_release", &[("version", &version)]));
+    }
+    let installed = state.store.snapshot().esphome_version;
+    let message = match installed.as_deref() {
+        _ if from_git => t_with("versions.confirm_git", &[("source", &version)]),
+        Some(installed) if crate::update::is_newer_version(installed, &version) => t_with(
+            "versions.confirm_downgrade",
+            &[("version", &version), ("installed", installed)],
+        ),
+        _ => t_with("versions.confirm_install", &[("version", &version)]),
+    };
+    let confirmed = crate::dialog::confirm(
+        &app,
+        &t("versions.title"),
+        message,
+        &t("versions.install"),
+        &t("versions.cancel"),
+    )
+    .await;
+    if !confirmed {
+        return Ok(false);
+    }
+    let Some(_guard) = UpdateGuard::try_acquire(state.update_in_flight.clone()) else {
+        return Err(t("versions.busy"));
+    };
+    if !ops::authorize(&state, t("auth.update"), &|_, _| {}).await {
+        return Ok(false);
+    }
+    info!("Installing ESPHome {} from the versions window", version);
+    // Any channel but dev installs exactly `version`.
+    let result = ops::stop_install_start(&state, || {
+        state
+            .update_checker
+            .update_to(&app, &version, ReleaseChannel::Stable)
+    })
+    .await;
+    ops::refresh_version_display_blocking(&app).await;
+    let detail = match &result {
+        Ok(()) => format!("installed ESPHome {version}"),
+        Err(e) => format!("installing ESPHome {version} failed: {e}"),
+    };
+    audit::record(&app, Source::Tray, "update", detail);
+    result.map(|()| true).map_err(|e| {
+        error!("Failed to install ESPHome {}: {}", version, e);
+        t_with(
+            "versions.install_failed",
+            &[("version", &version), ("error", &e)],
+        )
+    })
+}
@@ -0,0 +1,226 @@
+//! The app's few windows: the scratchpad, the terminal, config search, pin
+//! usage, shared packages, duplicate devices, device rename, firmware signing
+//! keys, ESPHome versions, Python packages, update progress, settings and the
+//! command palette, each one page in `dist/` with no framework. A page gets
+//! its texts in an initialization script, as `window.TEXTS`, and talks to the
+//! app over IPC commands.
+//!
+//! Every window opens at the `ui_scale` zoom, and Ctrl/Cmd with `+`, `-` or
+//! `0` zooms it further for the moment. A page showing log lines colours
+//! them in the `log_palette`, which it gets as `window.LOG_PALETTE`. It opens where it was last, and may
+//! open again at the next launch (see [`crate::session`]).
+//!
+//! The app lives in the tray, so closing the last window must not quit it as
+//! it would a windowed app; see [`closed_last_window`].
+//!
+//! Kiosk mode opens none of them, but a page can be driven by hand, so the
+//! IPC commands that change something are refused there too ([`kiosk_gate`]).
+
+use std::sync::atomic::{AtomicBool, AtomicU16, Ordering};
+use std::sync::{Arc, Mutex};
+
+use tauri::ipc::Invoke;
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder, WindowEvent};
+use tracing::warn;
+
+use crate::i18n::t;
+use crate::session;
+use crate::settings::{LogPalette, KIOSK_REFUSAL};
+use crate::AppState;
+
+/// The IPC commands that change the install, the configs or the settings.
+const CHANGING_COMMANDS: &[&str] = &[
+    "merge_added",
+    "rename_device",
+    "upload_renamed",
+    "generate_signing_key",
+    "remove_signing_key",
+    "activate_esphome_version",
+    "update_esphome_version",
+    "delete_esphome_version",
+    "install_esphome_version",
+    "install_python_package",
+    "terminal_run",
+    "save_settings_form",
+];
+
+/// Set when the user closes a window, until the exit request that closing
+/// the last one raises has been turned down.
+static CLOSING: AtomicBool = AtomicBool::new(false);
+
+/// The `ui_scale` setting, in percent. A process-wide atomic like the
+/// notification pause, so opening a window needn't wait on the settings lock.
+static UI_SCALE: AtomicU16 = AtomicU16::new(crate::settings::DEFAULT_UI_SCALE);
+
+/// Zoom the windows to `percent`, the open ones and those opened later.
+pub(crate) fn set_ui_scale(app: &AppHandle, percent: u16) {
+    UI_SCALE.store(percent, Ordering::SeqCst);
+    for (label, window) in app.webview_windows() {
+        if let Err(e) = window.set_zoom(zoom()) {
+            warn!("Failed to zoom window '{}': {}", label, e);
+        }
+    }
+}
+
+/// The `log_palette` setting, for the pages opened from now on.
+static LOG_PALETTE: Mutex<LogPalette> = Mutex::new(LogPalette::Standard);
+
+pub(crate) fn set_log_palette(palette: LogPalette) {
+    *LOG_PALETTE.lock().unwrap_or_else(|e| e.into_inner()) = palette;
+}
+
+/// The script giving a page its texts and the log palette.
+fn init_script(texts: &serde_json::Value) -> String {
+    let palette = *LOG_PALETTE.lock().unwrap_or_else(|e| e.into_inner());
+    let palette = serde_json::to_string(&palette).unwrap_or_default();
+    format!("window.TEXTS = {texts}; window.LOG_PALETTE = {palette};")
+}
+
+/// [`UI_SCALE`] as a zoom factor.
+fn zoom() -> f64 {
+    f64::from(UI_SCALE.load(Ordering::SeqCst)) / 100.0
+}
+
+/// The app's state, for the windows' commands. `Err` is the page's message
+/// for a command that comes in while the app is still starting.
+pub(crate) fn app_state(app: &AppHandle) -> Result<Arc<AppState>, String> {
+    app.try_state::<Arc<AppState>>()
+        .map(|state| state.inner().clone())
+        .ok_or_else(|| t("window.not_ready"))
+}
+
+/// Show the window `label`, opening it on `page` if it isn't open.
+pub(crate) fn open(
+    app: &AppHandle,
+    label: &str,
+    page: &str,
+    title: String,
+    texts: serde_json::Value,
+) -> tauri::Result<()> {
+    open_sized(app, label, page, title, texts, (760.0, 600.0))
+}
+
+/// Show the window `label` like [`open`], at `size` (width, height) before
+/// the UI scale, unless it has been placed before.
+pub(crate) fn open_sized(
+    app: &AppHandle,
+    label: &str,
+    page: &str,
+    title: String,
+    texts: serde_json::Value,
+    (width, height): (f64, f64),
+) -> tauri::Result<()> {
+    if let Some(window) = app.get_webview_window(label) {
+        window.unminimize()?;
+        return window.set_focus();
+    }
+    let zoom = zoom();
+    let window = WebviewWindowBuilder::new(app, label, WebviewUrl::App(page.into()))
+        .title(title)
+        .inner_size(width * zoom, height * zoom)
+        .zoom_hotkeys_enabled(true)
+        .initialization_script(&init_script(&texts))
+        .build()?;
+    window.set_zoom(zoom)?;
+    if let Some(placement) = session::placement(&window, label) {
+        session::place(&window, placement)?;
+    }
+    let placed = window.clone();
+    window.on_window_event(move |event| match event {
+        WindowEvent::Moved(_) | WindowEvent::Resized(_) => session::remember(&placed),
+        WindowEvent::CloseRequested { .. } => {
+            CLOSING.store(true, Ordering::SeqCst);
+            session::save(placed.app_handle(), false);
+        }
+        _ => {}
+    });
+    Ok(())
+}
+
+/// The IPC `handler`, refusing the [`CHANGING_COMMANDS`] under kiosk mode
+/// with the control channel's answer.
+pub(crate) fn kiosk_gate(
+    handler: impl Fn(Invoke) -> bool + Send + Sync + 'static,
+) -> impl Fn(Invoke) -> bool + Send + Sync + 'static {
+    move |invoke| {
+        let kiosk = invoke
+            .message
+            .webview_ref()
+            .try_state::<Arc<AppState>>()
+            .is_some_and(|state| state.kiosk);
+        if kiosk && refused_in_kiosk(invoke.message.command()) {
+            warn!("Kiosk mode is on; refusing {}", invoke.message.command());
+            invoke.resolver.reject(KIOSK_REFUSAL);
+            return true;
+        }
+        handler(invoke)
+    }
+}
+
+/// Whether kiosk mode refuses the IPC `command`.
+fn refused_in_kiosk(command: &str) -> bool {
+    CHANGING_COMMANDS.contains(&command)
+}
+
+/// Whether an exit request only follows the user closing a window, which may
+/// have left none open.
+pub(crate) fn closed_last_window() -> bool {
+    CLOSING.swap(false, Ordering::SeqCst)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kiosk_refuses_what_the_windows_change() {
+        assert!(refused_in_kiosk("merge_added"));
+        assert!(refused_in_kiosk("rename_device"));
+        assert!(refused_in_kiosk("upload_renamed"));
+        assert!(refused_in_kiosk("generate_signing_key"));
+        assert!(refused_in_kiosk("remove_signing_key"));
+        assert!(refused_in_kiosk("activate_esphome_version"));
+        assert!(refused_in_kiosk("update_esphome_version"));
+        assert!(refused_in_kiosk("delete_esphome_version"));
+        assert!(refused_in_kiosk("install_esphome_version"));
+        assert!(refused_in_kiosk("install_python_package"));
+        assert!(refused_in_kiosk("terminal_run"));
+        assert!(refused_in_kiosk("save_settings_form"));
+    }
+
+    #[test]
+    fn pages_get_their_texts_and_the_log_palette() {
+        set_log_palette(LogPalette::HighContrast);
+        let script = init_script(&serde_json::json!({ "run": "Run" }));
+        set_log_palette(LogPalette::Standard);
+        assert_eq!(
+            script,
+            r#"window.TEXTS = {"run":"Run"}; window.LOG_PALETTE = "high_contrast";"#
+        );
+    }
+
+    #[test]
+    fn kiosk_leaves_reading_alone() {
+        for command in [
+            "check_snippet",
+            "search_configs",
+            "pin_overview",
+            "esphome_versions",
+            "python_packages",
+            "terminal_output",
+            "settings_form",
+            "palette_actions",
+        ] {
+            assert!(!refused_in_kiosk(command), "{command}");
+        }
+    }
+
+    #[test]
+    fn every_refused_command_is_registered() {
+        // A typo here would leave the real command unguarded.
+        let lib = include_str!("lib.rs");
+        for command in CHANGING_COMMANDS {
+            assert!(lib.contains(&format!("::{command},")), "{command}");
+        }
+    }
+}
@@ -0,0 +1,80 @@
+#!/usr/bin/env python3
+"""Tests for build-scripts/write_bundle_manifest.py.
+
+The app condemns an install whose files don't match this manifest, so a file
+listed wrongly here is a false "reinstall the app" on every machine. What
+matters: every file is listed with its size, only native binaries outside
+site-packages are hashed, and neither symlinks nor the manifest itself are
+listed.
+"""
+
+from __future__ import annotations
+
+import hashlib
+import json
+import os
+from pathlib import Path
+
+import pytest
+from script_loader import load_script_module
+
+REPO_ROOT = Path(__file__).resolve().parent.parent
+SCRIPT_PATH = REPO_ROOT / "build-scripts" / "write_bundle_manifest.py"
+
+write_bundle_manifest = load_script_module(SCRIPT_PATH)
+
+ELF = b"\x7fELF" + b"\0" * 60
+
+
+def _write(path: Path, content: bytes) -> None:
+    path.parent.mkdir(parents=True, exist_ok=True)
+    path.write_bytes(content)
+
+
+def test_lists_every_file_and_hashes_binaries_outside_site_packages(
+    tmp_path: Path,
+) -> None:
+    _write(tmp_path / "python/bin/python3.14", ELF)
+    _write(tmp_path / "python/bin/esphome", b"#!/bin/sh\n")
+    _write(tmp_path / "python/lib/site-packages/ext.so", ELF)
+    _write(tmp_path / "git/cmd/git.exe", b"MZ" + b"\0" * 30)
+    _write(tmp_path / "python" / write_bundle_manifest.MANIFEST_NAME, b"{}")
+
+    files = write_bundle_manifest.build_manifest(tmp_path)["files"]
+
+    assert sorted(files) == [
+        "git/cmd/git.exe",
+        "python/bin/esphome",
+        "python/bin/python3.14",
+        "python/lib/site-packages/ext.so",
+    ]
+    assert files["python/bin/python3.14"] == {
+        "size": len(ELF),
+        "sha256": hashlib.sha256(ELF).hexdigest(),
+    }
+    assert "sha256" in files["git/cmd/git.exe"]
+    assert files["python/bin/esphome"] == {"size": 10}
+    assert "sha256" not in files["python/lib/site-packages/ext.so"]
+
+
+@pytest.mark.skipif(os.name == "nt", reason="symlinks need privileges on Windows")
+def test_skips_symlinks(tmp_path: Path) -> None:
+    _write(tmp_path / "python/bin/python3.14", ELF)
+    (tmp_path / "python/bin/python3").symlink_to("python3.14")
+
+    files = write_bundle_manifest.build_manifest(tmp_path)["files"]
+
+    assert list(files) == ["python/bin/python3.14"]
+
+
+def test_main_writes_the_manifest_into_the_python_resource(tmp_path: Path) -> None:
+    _write(tmp_path / "python/bin/python3.14", ELF)
+
+    assert write_bundle_manifest.main([str(tmp_path)]) == 0
+
+    out = tmp_path / "python" / write_bundle_manifest.MANIFEST_NAME
+    assert list(json.loads(out.read_text())["files"]) == ["python/bin/python3.14"]
+
+
+def test_main_fails_without_a_python_bundle(tmp_path: Path) -> None:
+    assert write_bundle_manifest.main([str(tmp_path)]) == 1